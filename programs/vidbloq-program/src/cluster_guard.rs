@@ -0,0 +1,111 @@
+//! Cluster detection for instructions that must never run on mainnet (test utilities,
+//! mock clocks, dev faucets) or that only make sense there.
+//!
+//! Programs cannot read the genesis hash at runtime, so it is fixed at build time through the
+//! `VIDBLOQ_GENESIS_HASH` environment variable and resolved once into [`CLUSTER`]. Builds
+//! without it, or with an unknown hash, are treated as mainnet so dev-only paths fail closed.
+
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+pub const MAINNET_GENESIS_HASH: &str = "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d";
+pub const DEVNET_GENESIS_HASH: &str = "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG";
+pub const TESTNET_GENESIS_HASH: &str = "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY";
+/// Local validators generate a fresh genesis hash, so localnet builds opt in by name
+pub const LOCALNET_GENESIS_MARKER: &str = "localnet";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cluster {
+    Mainnet,
+    Devnet,
+    Testnet,
+    Localnet,
+}
+
+impl Cluster {
+    pub const fn from_genesis_hash(genesis_hash: Option<&str>) -> Cluster {
+        let Some(hash) = genesis_hash else {
+            return Cluster::Mainnet;
+        };
+        if str_eq(hash, DEVNET_GENESIS_HASH) {
+            Cluster::Devnet
+        } else if str_eq(hash, TESTNET_GENESIS_HASH) {
+            Cluster::Testnet
+        } else if str_eq(hash, LOCALNET_GENESIS_MARKER) {
+            Cluster::Localnet
+        } else {
+            Cluster::Mainnet
+        }
+    }
+
+    pub fn require_not_mainnet(self) -> Result<()> {
+        require!(self != Cluster::Mainnet, StreamError::NotAllowedOnMainnet);
+        Ok(())
+    }
+
+    pub fn require_mainnet(self) -> Result<()> {
+        require!(self == Cluster::Mainnet, StreamError::MainnetOnly);
+        Ok(())
+    }
+}
+
+/// Cluster this build targets
+pub const CLUSTER: Cluster = Cluster::from_genesis_hash(option_env!("VIDBLOQ_GENESIS_HASH"));
+
+pub fn require_not_mainnet() -> Result<()> {
+    CLUSTER.require_not_mainnet()
+}
+
+pub fn require_mainnet() -> Result<()> {
+    CLUSTER.require_mainnet()
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_known_genesis_hashes() {
+        assert_eq!(Cluster::from_genesis_hash(Some(MAINNET_GENESIS_HASH)), Cluster::Mainnet);
+        assert_eq!(Cluster::from_genesis_hash(Some(DEVNET_GENESIS_HASH)), Cluster::Devnet);
+        assert_eq!(Cluster::from_genesis_hash(Some(TESTNET_GENESIS_HASH)), Cluster::Testnet);
+        assert_eq!(Cluster::from_genesis_hash(Some(LOCALNET_GENESIS_MARKER)), Cluster::Localnet);
+    }
+
+    #[test]
+    fn missing_or_unknown_hash_fails_closed() {
+        assert_eq!(Cluster::from_genesis_hash(None), Cluster::Mainnet);
+        assert_eq!(Cluster::from_genesis_hash(Some("")), Cluster::Mainnet);
+        assert_eq!(Cluster::from_genesis_hash(Some("not-a-genesis-hash")), Cluster::Mainnet);
+    }
+
+    #[test]
+    fn mainnet_rejects_dev_only_paths() {
+        assert!(Cluster::Mainnet.require_not_mainnet().is_err());
+        assert!(Cluster::Mainnet.require_mainnet().is_ok());
+    }
+
+    #[test]
+    fn non_mainnet_clusters_reject_mainnet_only_paths() {
+        for cluster in [Cluster::Devnet, Cluster::Testnet, Cluster::Localnet] {
+            assert!(cluster.require_not_mainnet().is_ok());
+            assert!(cluster.require_mainnet().is_err());
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::state::{StreamState, StreamError, AccessGate, AccessGateSet, AccessGateRemoved};
+
+pub const ACCESS_GATE_SEED: &[u8] = b"access_gate";
+
+/// Requires depositors and bettors to hold `min_balance` of `gate_mint`; replaces any existing gate
+#[derive(Accounts)]
+pub struct SetAccessGate<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        space = AccessGate::INIT_SPACE,
+        seeds = [ACCESS_GATE_SEED, stream.key().as_ref()],
+        bump
+    )]
+    pub access_gate: Account<'info, AccessGate>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveAccessGate<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        close = host,
+        seeds = [ACCESS_GATE_SEED, stream.key().as_ref()],
+        bump = access_gate.bump,
+        has_one = stream
+    )]
+    pub access_gate: Account<'info, AccessGate>,
+}
+
+impl<'info> SetAccessGate<'info> {
+    pub fn set_access_gate(&mut self, gate_mint: Pubkey, min_balance: u64, bumps: &SetAccessGateBumps) -> Result<()> {
+        require!(min_balance > 0, StreamError::InvalidAmount);
+
+        self.access_gate.set_inner(AccessGate {
+            stream: self.stream.key(),
+            gate_mint,
+            min_balance,
+            bump: bumps.access_gate,
+        });
+        self.stream.access_gated = true;
+
+        emit!(AccessGateSet {
+            stream: self.stream.key(),
+            gate_mint,
+            min_balance,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+impl<'info> RemoveAccessGate<'info> {
+    pub fn remove_access_gate(&mut self) -> Result<()> {
+        self.stream.access_gated = false;
+
+        emit!(AccessGateRemoved {
+            stream: self.stream.key(),
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+/// Checks that `holder` meets the stream's access gate through `holding`, one of their token
+/// accounts for the gate mint
+pub(crate) fn enforce_access_gate(
+    stream: &StreamState,
+    gate: Option<&Account<AccessGate>>,
+    holding: Option<&InterfaceAccount<TokenAccount>>,
+    holder: &Pubkey,
+) -> Result<()> {
+    if !stream.access_gated {
+        return Ok(());
+    }
+    let (Some(gate), Some(holding)) = (gate, holding) else {
+        return err!(StreamError::MissingAccounts);
+    };
+    require_keys_eq!(holding.owner, *holder, StreamError::Unauthorized);
+    require_keys_eq!(holding.mint, gate.gate_mint, StreamError::InvalidMint);
+    require!(holding.amount >= gate.min_balance, StreamError::AccessGateNotMet);
+    Ok(())
+}
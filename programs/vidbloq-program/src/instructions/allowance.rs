@@ -0,0 +1,240 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::instructions::{
+    burn_stream_share, enforce_distribution_policy, record_burn_rate, record_clawbackable_payout, PlatformFeeRoute,
+    CLAWBACK_AGREEMENT_SEED, DISTRIBUTION_POLICY_SEED, PAYOUT_RECIPIENT_SEED, PLATFORM_CONFIG_SEED, GLOBAL_REGISTRY_SEED
+};
+use crate::state::{
+    StreamState, StreamError, RecipientAllowance, DistributionPolicy, HostProfile, PayoutRecipient,
+    ReferralRewards, RecipientAllowanceSet, AllowanceWithdrawn, FundsDistributed, GlobalRegistry
+};
+
+pub const RECIPIENT_ALLOWANCE_SEED: &[u8] = b"recipient_allowance";
+
+/// Sets how much `recipient` may pull from the stream. Replaces any unspent allowance; zero revokes it.
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct SetRecipientAllowance<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        space = RecipientAllowance::INIT_SPACE,
+        seeds = [RECIPIENT_ALLOWANCE_SEED, stream.key().as_ref(), recipient.as_ref()],
+        bump
+    )]
+    pub allowance: Account<'info, RecipientAllowance>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Recipient-signed payout against their allowance; the host does not need to be online
+#[derive(Accounts)]
+pub struct WithdrawAllowance<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [RECIPIENT_ALLOWANCE_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump = allowance.bump
+    )]
+    pub allowance: Account<'info, RecipientAllowance>,
+
+    /// Writable so deflationary streams can burn supply
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Allowlist entry for the recipient, required while the stream is in strict payout mode
+    #[account(
+        seeds = [PAYOUT_RECIPIENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump = payout_recipient.bump
+    )]
+    pub payout_recipient: Option<Account<'info, PayoutRecipient>>,
+
+    /// Spending limits, required once the host has set a distribution policy
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_POLICY_SEED, stream.key().as_ref()],
+        bump = distribution_policy.bump
+    )]
+    pub distribution_policy: Option<Account<'info, DistributionPolicy>>,
+
+    /// CHECK: Recipient's clawback agreement PDA; may be uninitialized
+    #[account(
+        mut,
+        seeds = [CLAWBACK_AGREEMENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub clawback_agreement: UncheckedAccount<'info>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required while a platform fee is configured
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"host_profile", stream.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Option<Account<'info, HostProfile>>,
+
+    /// Credited with the referrer's share of the fee while the host's referral runs
+    #[account(mut)]
+    pub referral_rewards: Option<Account<'info, ReferralRewards>>,
+
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl<'info> SetRecipientAllowance<'info> {
+    pub fn set_recipient_allowance(&mut self, recipient: Pubkey, amount: u64, bumps: &SetRecipientAllowanceBumps) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let allowance = &mut self.allowance;
+        allowance.stream = self.stream.key();
+        allowance.recipient = recipient;
+        allowance.remaining = amount;
+        allowance.updated_at = now;
+        allowance.bump = bumps.allowance;
+
+        emit!(RecipientAllowanceSet {
+            stream: self.stream.key(),
+            recipient,
+            amount,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+impl<'info> WithdrawAllowance<'info> {
+    pub fn withdraw_allowance(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        self.stream.require_distribution_allowed()?;
+        self.stream.require_within_lifetime(&self.platform_config)?;
+        self.stream.require_recipient_allowed(self.payout_recipient.is_some())?;
+
+        let available_balance = self.stream.available_balance()?;
+        require!(available_balance >= amount, StreamError::InsufficientFunds);
+        self.allowance.spend(amount)?;
+        enforce_distribution_policy(&self.stream, self.distribution_policy.as_mut(), amount)?;
+
+        let stream_seeds = self.stream.signer_seeds();
+        let stream_seeds: Vec<&[u8]> = stream_seeds.iter().map(Vec::as_slice).collect();
+        let signer = &[&stream_seeds[..]];
+
+        let fee = PlatformFeeRoute {
+            platform_config: &self.platform_config,
+            treasury_ata: self.treasury_ata.as_ref(),
+            host_profile: self.host_profile.as_ref(),
+            referral_rewards: self.referral_rewards.as_mut(),
+            referral_vault: self.referral_vault.as_ref(),
+        }.collect(
+            self.stream.host,
+            &self.stream_ata,
+            &self.mint,
+            self.stream.to_account_info(),
+            signer,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
+        let burned = burn_stream_share(
+            &self.stream,
+            &self.stream_ata,
+            &self.mint,
+            self.stream.to_account_info(),
+            signer,
+            &self.token_program,
+            amount - fee,
+        )?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.stream_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.recipient_ata.to_account_info(),
+            authority: self.stream.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount - fee - burned, self.mint.decimals)?;
+
+        self.stream.total_distributed = self.stream.total_distributed.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_distribution(&self.global_registry, amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        record_clawbackable_payout(
+            &self.clawback_agreement,
+            self.stream.key(),
+            self.recipient.key(),
+            &self.recipient_ata.key(),
+            amount - fee - burned,
+            now,
+        )?;
+        emit!(AllowanceWithdrawn {
+            stream: self.stream.key(),
+            recipient: self.recipient.key(),
+            amount,
+            remaining: self.allowance.remaining,
+            timestamp: now
+        });
+        emit!(FundsDistributed {
+            stream: self.stream.key(),
+            recipient: self.recipient.key(),
+            amount,
+            burned,
+            timestamp: now
+        });
+
+        record_burn_rate(&mut self.stream, amount)?;
+        Ok(())
+    }
+}
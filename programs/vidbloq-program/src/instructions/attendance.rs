@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{StreamState, StreamStatus, StreamError, Blocklist, ViewerAttendance, ViewerCheckedIn};
+
+pub const VIEWER_ATTENDANCE_SEED: &[u8] = b"viewer_attendance";
+
+/// Viewer-signed check-in on a live stream; creates the viewer's attendance record on first use
+#[derive(Accounts)]
+pub struct CheckIn<'info> {
+    #[account(mut)]
+    pub viewer: Signer<'info>,
+
+    #[account(
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        init_if_needed,
+        payer = viewer,
+        space = ViewerAttendance::INIT_SPACE,
+        seeds = [VIEWER_ATTENDANCE_SEED, stream.key().as_ref(), viewer.key().as_ref()],
+        bump
+    )]
+    pub attendance: Account<'info, ViewerAttendance>,
+
+    /// CHECK: Blocklist page for the viewer's bucket, required while the stream has blocked wallets
+    #[account(
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&viewer.key())]],
+        bump
+    )]
+    pub blocklist: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CheckIn<'info> {
+    pub fn check_in(&mut self, bumps: &CheckInBumps) -> Result<()> {
+        // Only a started, active stream has an audience to be part of
+        require!(
+            self.stream.status == StreamStatus::Active && self.stream.start_time.is_some(),
+            StreamError::StreamNotActive
+        );
+        Blocklist::require_not_blocked(
+            self.stream.blocked_count,
+            self.blocklist.as_ref().map(|b| b.as_ref()),
+            &self.viewer.key(),
+        )?;
+
+        let now = Clock::get()?.unix_timestamp;
+        self.attendance.check_in(self.stream.key(), self.viewer.key(), now, bumps.attendance)?;
+
+        emit!(ViewerCheckedIn {
+            stream: self.stream.key(),
+            viewer: self.viewer.key(),
+            check_in_count: self.attendance.check_in_count,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
@@ -1,29 +1,39 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{transfer as token_transfer, Transfer},
-    token_interface::{Mint, TokenAccount, TokenInterface},
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 use ephemeral_vrf_sdk::anchor::vrf;
 use ephemeral_vrf_sdk::instructions::{create_request_randomness_ix, RequestRandomnessParams};
 use ephemeral_vrf_sdk::types::SerializableAccountMeta;
+use anchor_lang::solana_program::instruction::Instruction;
 
+use crate::instructions::{amount_received, enforce_access_gate, pay_bet_referral, transfer_from_session, MIRROR_CONFIG_SEED, VALIDATOR_REGISTRATION_SEED, VALIDATOR_REGISTRY_SEED, SESSION_SEED, VALIDATOR_ESCROW_SEED, PlatformFeeRoute, ACCESS_GATE_SEED, MARKET_ACCESS_SEED, FEATURE_GATES_SEED, ORACLE_SEED, PLATFORM_CONFIG_SEED};
+use crate::math::lmsr_buy_shares;
 use crate::state::{
-    BetPlaced, BettingMarket, BettorPosition, EligibleValidator, MarketCreated, MarketOutcome,
-    MarketResolution, MarketType, OutcomePosition, RandomnessUseCase, ResolutionStatus,
-    StreamError, StreamState, ValidationVote, ValidatorVote, WinningsClaimed,
+    AccessGate, AmmCurve, BetPlaced, BetReferralShareSet, ReferralEarnings, MAX_BET_REFERRAL_SHARE_BPS, SessionKey, MarketStats, MirrorConfig, BetGifted, BetQuote, OddsUpdated, MarketOutcomesAdded, MarketOutcomeRemoved, BettingMarket, BettorPosition, BettorProfile, OutcomePositionAccount, ConsensusParams, ValidatorRegistration, ValidatorRegistry, ValidatorStake, ValidatorsSelected, MAX_ELIGIBLE_VALIDATORS, MAX_VALIDATOR_EXPOSURE_BPS, BPS_DENOMINATOR, FeatureGates, HostProfile, ReferralRewards, MarketCreated,
+    MarketAccessEntry, MarketAccessMode, MarketBook, MarketConfig, MarketOracle, MarketResolution, MarketResolved, MarketResolvedRanked, ResolutionPayoutPlan, MAX_PAYOUT_PLACES, BetTaper,
+    BettingClosedEarly, ResolutionTimeUpdated, MarketTaperSet, MarketLimitsSet, MarketDustSwept, MarketFeesCollected, MetricSourceSet, net_of_fee, notify, NotificationCode, BETTING_CLOSING_SOON, PlatformConfig, DEFAULT_CONSENSUS_BPS, DEFAULT_QUORUM_BPS, MIN_QUORUM_BPS, DEFAULT_SUPERMAJORITY_BPS, MAX_SUPERMAJORITY_BPS, Blocklist, MarketRules, MarketRulesSet, MAX_RULES_URI_LEN, MAX_MARKET_OUTCOMES, MAX_OUTCOME_DESCRIPTION_LEN, NO_PROPOSED_OUTCOME, MARKET_VERSION, POSITION_VERSION, MarketType, RandomnessUseCase, ResolutionStatus,
+    StreamError, StreamState, ValidationVote, ValidatorEscrow, ResolutionSource, MarketArbiterChanged, MarketCancelled, ResolutionForced, ResolutionTimeoutSet, RANDOMNESS_TIMEOUT, RANDOMNESS_REQUEST_TIMEOUT, RandomnessRequestExpired, RandomnessRequestStatus, RandomnessRequested, VoteRecord, WinningsClaimed,
 };
 
 // ============= CONSTANTS =============
 pub const MARKET_SEED: &[u8] = b"betting_market";
+pub const MARKET_BOOK_SEED: &[u8] = b"market_book";
+pub const MARKET_CONFIG_SEED: &[u8] = b"market_config";
 pub const RESOLUTION_SEED: &[u8] = b"market_resolution";
 pub const POSITION_SEED: &[u8] = b"bettor_position";
+pub const OUTCOME_POSITION_SEED: &[u8] = b"outcome_position";
 pub const MARKET_VAULT_SEED: &[u8] = b"market_vault";
+pub const VOTE_RECORD_SEED: &[u8] = b"vote_record";
+pub const BETTOR_PROFILE_SEED: &[u8] = b"bettor_profile";
+pub const MARKET_STATS_SEED: &[u8] = b"market_stats";
 pub const MIN_VALIDATORS: u8 = 3;
 pub const MAX_VALIDATORS: u8 = 7;
 pub const VALIDATOR_STAKE_REQUIREMENT: u64 = 10_000_000; // 10 USDC minimum
 pub const DISPUTE_WINDOW: i64 = 3600; // 1 hour
 pub const VALIDATOR_REWARD_BPS: u16 = 50; // 0.5% of pool
+pub const DEFAULT_LIQUIDITY_PER_OUTCOME: u64 = 1000_000_000; // 1000 USDC when the host seeds none
 
 // ============= INSTRUCTIONS CONTEXTS =============
 
@@ -36,7 +46,7 @@ pub struct InitializeBettingMarket<'info> {
 
     #[account(
         mut,
-        seeds = [b"stream", stream.stream_name.as_str().as_bytes(), stream.host.key().as_ref()],
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
         bump = stream.bump,
         constraint = stream.host == host.key() @ StreamError::Unauthorized,
     )]
@@ -48,46 +58,135 @@ pub struct InitializeBettingMarket<'info> {
     #[account(
         init,
         payer = host,
-        space = 8 + 32 + 32 + 32 + 100 + (100 * 10) + 8 + 8 + 8 + 1 + 2 + 1 + 2 + 8 + 1,
+        space = BettingMarket::INIT_SPACE,
         seeds = [MARKET_SEED, stream.key().as_ref()],
         bump
     )]
     pub betting_market: Account<'info, BettingMarket>,
 
+    #[account(
+        init,
+        payer = host,
+        space = MarketConfig::INIT_SPACE,
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        init,
+        payer = host,
+        space = MarketBook::SPACE,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        init,
+        payer = host,
+        space = MarketStats::INIT_SPACE,
+        seeds = [MARKET_STATS_SEED, betting_market.key().as_ref()],
+        bump
+    )]
+    pub market_stats: Account<'info, MarketStats>,
+
+    /// CHECK: Platform config PDA; may be uninitialized. Bounds the market's consensus threshold
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
 }
 
-/// Place a bet
+/// Place a bet, for the signer or as a gift to `beneficiary`, who then owns the position
 #[derive(Accounts)]
+#[instruction(
+    outcome_id: u8,
+    usdc_amount: u64,
+    min_shares: u64,
+    deadline: Option<i64>,
+    max_price_per_share: Option<u64>,
+    beneficiary: Option<Pubkey>,
+)]
 pub struct PlaceBet<'info> {
     #[account(mut)]
     pub bettor: Signer<'info>,
 
     #[account(
-        mut,
         seeds = [MARKET_SEED, betting_market.stream.as_ref()],
         bump = betting_market.bump,
+        has_one = stream,
     )]
     pub betting_market: Account<'info, BettingMarket>,
 
+    #[account(
+        mut,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    pub stream: Account<'info, StreamState>,
+
+    /// CHECK: Blocklist page for the position owner's bucket, required while the stream has blocked wallets
+    #[account(
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&beneficiary.unwrap_or(bettor.key()))]],
+        bump
+    )]
+    pub blocklist: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Position owner's access listing, required while the market is not open to everyone
+    #[account(
+        seeds = [MARKET_ACCESS_SEED, betting_market.key().as_ref(), beneficiary.unwrap_or(bettor.key()).as_ref()],
+        bump
+    )]
+    pub market_access: Option<UncheckedAccount<'info>>,
+
+    /// Required while the stream is access gated
+    #[account(
+        seeds = [ACCESS_GATE_SEED, stream.key().as_ref()],
+        bump = access_gate.bump
+    )]
+    pub access_gate: Option<Account<'info, AccessGate>>,
+
+    /// Position owner's token account for the gate mint, required while the stream is access gated
+    pub gate_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
     #[account(
         init_if_needed,
         payer = bettor,
-        space = 8 + 32 + 32 + (50 * 10) + 8 + 8 + 1 + 1 + 8 + 1,
-        seeds = [POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        space = BettorPosition::INIT_SPACE,
+        seeds = [POSITION_SEED, betting_market.key().as_ref(), beneficiary.unwrap_or(bettor.key()).as_ref()],
         bump
     )]
     pub bettor_position: Account<'info, BettorPosition>,
 
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = OutcomePositionAccount::INIT_SPACE,
+        seeds = [OUTCOME_POSITION_SEED, betting_market.key().as_ref(), beneficiary.unwrap_or(bettor.key()).as_ref(), outcome_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub outcome_position: Account<'info, OutcomePositionAccount>,
+
     /// The mint for the token (USDC) - must match market's mint
     #[account(
         constraint = mint.key() == betting_market.mint @ StreamError::InvalidMint
     )]
     pub mint: InterfaceAccount<'info, Mint>,
 
+    /// Session the signer is betting under; the stake then comes from the session owner's tokens
+    #[account(
+        mut,
+        seeds = [SESSION_SEED, session.owner.as_ref(), bettor.key().as_ref()],
+        bump = session.bump,
+    )]
+    pub session: Option<Account<'info, SessionKey>>,
+
     #[account(
         mut,
-        constraint = bettor_token.owner == bettor.key(),
+        constraint = bettor_token.owner == session.as_ref().map_or(bettor.key(), |s| s.owner),
         constraint = bettor_token.mint == mint.key(),
     )]
     pub bettor_token: InterfaceAccount<'info, TokenAccount>,
@@ -102,11 +201,62 @@ pub struct PlaceBet<'info> {
     )]
     pub market_vault: InterfaceAccount<'info, TokenAccount>,
 
+    /// CHECK: Market stats PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [MARKET_STATS_SEED, betting_market.key().as_ref()], bump)]
+    pub market_stats: UncheckedAccount<'info>,
+
+    /// CHECK: Market's mirror config PDA; the tipster's latest bet is only kept once mirroring is enabled
+    #[account(mut, seeds = [MIRROR_CONFIG_SEED, betting_market.key().as_ref()], bump)]
+    pub mirror_config: UncheckedAccount<'info>,
+
+    /// CHECK: Wallet that referred the bettor, recorded on a position that has no referrer yet
+    pub referrer: Option<UncheckedAccount<'info>>,
+
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
+/// Starts stats for a market created before markets kept them; anyone may pay for it
+#[derive(Accounts)]
+pub struct InitializeMarketStats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MarketStats::INIT_SPACE,
+        seeds = [MARKET_STATS_SEED, betting_market.key().as_ref()],
+        bump
+    )]
+    pub market_stats: Account<'info, MarketStats>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Read-only quote of a bet at the current odds
+#[derive(Accounts)]
+pub struct GetQuote<'info> {
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+}
+
 /// Request randomness for market operations
 #[vrf]
 #[derive(Accounts)]
@@ -121,14 +271,34 @@ pub struct RequestMarketRandomness<'info> {
     )]
     pub market: Account<'info, BettingMarket>,
 
+    #[account(
+        seeds = [MARKET_BOOK_SEED, market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
     #[account(
         init_if_needed,
         payer = requestor,
-        space = 8 + 32 + 2 + (32 * 20) + (100 * 10) + 8 + 50 + 32 + 50 + 8 + (50 * 100) + 1,
+        space = MarketResolution::SPACE,
         seeds = [RESOLUTION_SEED, market.key().as_ref()],
         bump
     )]
-    pub resolution: Account<'info, MarketResolution>,
+    pub resolution: AccountLoader<'info, MarketResolution>,
+
+    /// Validators staked for the market's mint. Without it validator selection lists the
+    /// bettors whose positions are passed as remaining accounts.
+    #[account(
+        seeds = [VALIDATOR_REGISTRY_SEED, market.mint.as_ref()],
+        bump = validator_registry.load()?.bump,
+    )]
+    pub validator_registry: Option<AccountLoader<'info, ValidatorRegistry>>,
 
     /// CHECK: The oracle queue from Ephemeral VRF
     #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
@@ -147,8 +317,80 @@ pub struct CallbackProcessRandomness<'info> {
     #[account(mut)]
     pub market: Account<'info, BettingMarket>,
 
+    #[account(
+        seeds = [MARKET_BOOK_SEED, market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(mut)]
+    pub resolution: AccountLoader<'info, MarketResolution>,
+}
+
+/// Anyone falls back to a VRF tie-break when validators miss the market's resolution timeout
+#[vrf]
+#[derive(Accounts)]
+pub struct ForceResolveTimeout<'info> {
     #[account(mut)]
-    pub resolution: Account<'info, MarketResolution>,
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, market.stream.as_ref()],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        seeds = [RESOLUTION_SEED, market.key().as_ref()],
+        bump = resolution.load()?.bump,
+    )]
+    pub resolution: AccountLoader<'info, MarketResolution>,
+
+    /// CHECK: The oracle queue from Ephemeral VRF
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Anyone clears a VRF request whose callback never arrived, so a new one can be made
+#[derive(Accounts)]
+pub struct ExpireRandomnessRequest<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, market.stream.as_ref()],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [RESOLUTION_SEED, market.key().as_ref()],
+        bump = resolution.load()?.bump,
+    )]
+    pub resolution: AccountLoader<'info, MarketResolution>,
 }
 
 /// Validator votes on proposed outcome
@@ -163,24 +405,152 @@ pub struct ValidatorVoteOnOutcome<'info> {
     )]
     pub market: Account<'info, BettingMarket>,
 
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
     #[account(
         mut,
         seeds = [RESOLUTION_SEED, market.key().as_ref()],
-        bump = resolution.bump,
+        bump = resolution.load()?.bump,
     )]
-    pub resolution: Account<'info, MarketResolution>,
+    pub resolution: AccountLoader<'info, MarketResolution>,
 
+    /// Validator's stake in the registry for the market's mint, required when the validators
+    /// were listed from the registry
     #[account(
-        seeds = [POSITION_SEED, market.key().as_ref(), validator.key().as_ref()],
-        bump,
+        seeds = [VALIDATOR_REGISTRATION_SEED, market.mint.as_ref(), validator.key().as_ref()],
+        bump = registration.bump,
+    )]
+    pub registration: Option<Account<'info, ValidatorRegistration>>,
+
+    /// Stake the validator locked; it is slashed if the vote goes against the final outcome
+    #[account(
+        seeds = [VALIDATOR_ESCROW_SEED, market.key().as_ref(), validator.key().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, ValidatorEscrow>,
+
+    /// One record per (resolution, validator); creation fails if the validator already voted
+    #[account(
+        init,
+        payer = validator,
+        space = VoteRecord::INIT_SPACE,
+        seeds = [VOTE_RECORD_SEED, resolution.key().as_ref(), validator.key().as_ref()],
+        bump
     )]
-    pub position: Account<'info, BettorPosition>,
+    pub vote_record: Account<'info, VoteRecord>,
+
+    pub system_program: Program<'info, System>,
 }
 
 /// Resolve the market with a winner
 #[derive(Accounts)]
 pub struct ResolveMarket<'info> {
+    /// The host, or the arbiter on arbiter markets
     #[account(mut)]
+    pub resolver: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    /// CHECK: Oracle PDA; while initialized, only its agreed value may resolve the market
+    #[account(seeds = [ORACLE_SEED, betting_market.key().as_ref()], bump)]
+    pub market_oracle: UncheckedAccount<'info>,
+
+    /// CHECK: Resolution PDA; once validator selection has started, only `finalize_resolution`
+    /// may resolve the market
+    #[account(seeds = [RESOLUTION_SEED, betting_market.key().as_ref()], bump)]
+    pub market_resolution: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"host_profile", betting_market.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Account<'info, HostProfile>,
+}
+
+/// The market's arbiter hands the role to another key, or the host replaces the arbiter before
+/// any bet is placed
+#[derive(Accounts)]
+pub struct SetMarketArbiter<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+/// Anyone resolves the market to the validators' consensus once the dispute window has passed
+#[derive(Accounts)]
+pub struct FinalizeResolution<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        seeds = [RESOLUTION_SEED, betting_market.key().as_ref()],
+        bump = resolution.load()?.bump,
+    )]
+    pub resolution: AccountLoader<'info, MarketResolution>,
+}
+
+/// Host-only market configuration, allowed before any bet is placed
+#[derive(Accounts)]
+pub struct ConfigureMarket<'info> {
     pub host: Signer<'info>,
 
     #[account(
@@ -190,26 +560,71 @@ pub struct ResolveMarket<'info> {
         constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
     )]
     pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
 }
 
-/// Claim winnings after market resolution
+/// Host adds or removes outcomes of a multi-outcome market before any bet or liquidity is in it
 #[derive(Accounts)]
-pub struct ClaimWinnings<'info> {
+pub struct ExpandMarket<'info> {
     #[account(mut)]
-    pub bettor: Signer<'info>,
+    pub host: Signer<'info>,
 
     #[account(
+        mut,
         seeds = [MARKET_SEED, betting_market.stream.as_ref()],
         bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
     )]
     pub betting_market: Account<'info, BettingMarket>,
 
     #[account(
         mut,
-        seeds = [POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
-        bump = bettor_position.bump,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
     )]
-    pub bettor_position: Account<'info, BettorPosition>,
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    // Grown to the full layout in case the config predates room for every outcome
+    #[account(
+        mut,
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+        realloc = MarketConfig::INIT_SPACE,
+        realloc::payer = host,
+        realloc::zero = false,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Host sweeps the market's rounding dust once every paying share has been settled
+#[derive(Accounts)]
+pub struct SweepMarketDust<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
         mut,
@@ -218,15 +633,248 @@ pub struct ClaimWinnings<'info> {
     )]
     pub market_vault: InterfaceAccount<'info, TokenAccount>,
 
-    #[account(mut)]
-    pub bettor_token: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        constraint = host_token.owner == host.key(),
+        constraint = host_token.mint == mint.key(),
+    )]
+    pub host_token: InterfaceAccount<'info, TokenAccount>,
 
     pub token_program: Interface<'info, TokenInterface>,
 }
 
-// ============= IMPLEMENTATION =============
+/// Host moves the market fees accrued from settled payouts out of the vault, less the
+/// platform's cut while a platform fee is configured
+#[derive(Accounts)]
+pub struct CollectMarketFees<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = host_token.owner == host.key(),
+        constraint = host_token.mint == mint.key(),
+    )]
+    pub host_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required while a platform fee is configured
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"host_profile", betting_market.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Option<Account<'info, HostProfile>>,
+
+    /// Credited with the referrer's share of the platform fee while the host's referral runs
+    #[account(mut)]
+    pub referral_rewards: Option<Account<'info, ReferralRewards>>,
+
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Claim winnings after market resolution
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump = bettor_position.bump,
+    )]
+    pub bettor_position: Account<'info, BettorPosition>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub bettor_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required while a platform fee is configured
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"host_profile", betting_market.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Option<Account<'info, HostProfile>>,
+
+    /// Credited with the referrer's share of the fee while the host's referral runs
+    #[account(mut)]
+    pub referral_rewards: Option<Account<'info, ReferralRewards>>,
+
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Earnings of the bettor's referrer, credited with the market's referral share of the fee
+    #[account(mut)]
+    pub referral_earnings: Option<Account<'info, ReferralEarnings>>,
+
+    #[account(mut)]
+    pub referral_earnings_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Feature gates PDA; may be uninitialized
+    #[account(seeds = [FEATURE_GATES_SEED], bump)]
+    pub feature_gates: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = BettorProfile::INIT_SPACE,
+        seeds = [BETTOR_PROFILE_SEED, bettor.key().as_ref()],
+        bump
+    )]
+    pub bettor_profile: Account<'info, BettorProfile>,
+
+    /// CHECK: Market stats PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [MARKET_STATS_SEED, betting_market.key().as_ref()], bump)]
+    pub market_stats: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Settles a resolved market into the bettor's profile and returns the position rent to the
+/// bettor. Anyone may close a position with nothing left to claim, so losses are recorded too.
+#[derive(Accounts)]
+pub struct CloseBettorPosition<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    /// CHECK: Owner of the position, receives its rent
+    #[account(mut)]
+    pub bettor: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        close = bettor,
+        seeds = [POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump = bettor_position.bump,
+    )]
+    pub bettor_position: Account<'info, BettorPosition>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = BettorProfile::INIT_SPACE,
+        seeds = [BETTOR_PROFILE_SEED, bettor.key().as_ref()],
+        bump
+    )]
+    pub bettor_profile: Account<'info, BettorProfile>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Returns an outcome position's rent once the bettor position it belongs to has been settled
+/// and closed, or the market cancelled and refunded
+#[derive(Accounts)]
+pub struct CloseOutcomePosition<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    /// CHECK: Bettor position PDA; must no longer exist
+    #[account(
+        seeds = [POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub bettor_position: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = bettor,
+        seeds = [OUTCOME_POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref(), outcome_position.outcome_id.to_le_bytes().as_ref()],
+        bump = outcome_position.bump,
+    )]
+    pub outcome_position: Account<'info, OutcomePositionAccount>,
+}
+
+// ============= IMPLEMENTATION =============
 
 impl<'info> InitializeBettingMarket<'info> {
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_market(
         &mut self,
         market_type: MarketType,
@@ -234,8 +882,21 @@ impl<'info> InitializeBettingMarket<'info> {
         resolution_time: i64,
         initial_liquidity: u64,
         fee_percentage: u16,
+        payout_plan: ResolutionPayoutPlan,
+        rules: Option<MarketRules>,
+        consensus: Option<ConsensusParams>,
+        amm_curve: Option<AmmCurve>,
+        resolution_source: Option<ResolutionSource>,
         bumps: &InitializeBettingMarketBumps,
     ) -> Result<()> {
+        // Over/under outcomes are fixed by the line: Over settles to 0, Under to 1
+        let outcomes = match market_type {
+            MarketType::OverUnder { .. } if outcomes.is_empty() => {
+                vec!["Over".to_string(), "Under".to_string()]
+            }
+            _ => outcomes,
+        };
+
         // Validate inputs
         match &market_type {
             MarketType::Binary => {
@@ -247,8 +908,15 @@ impl<'info> InitializeBettingMarket<'info> {
                     StreamError::InvalidMarketSetup
                 );
             }
-            _ => {}
+            MarketType::OverUnder { line } => {
+                require!(*line > 0 && outcomes.len() == 2, StreamError::InvalidMarketSetup);
+            }
         }
+        require!(
+            outcomes.len() <= MAX_MARKET_OUTCOMES
+                && outcomes.iter().all(|desc| desc.len() <= MAX_OUTCOME_DESCRIPTION_LEN),
+            StreamError::InvalidMarketSetup
+        );
 
         require!(
             resolution_time > Clock::get()?.unix_timestamp,
@@ -256,41 +924,139 @@ impl<'info> InitializeBettingMarket<'info> {
         );
         require!(fee_percentage <= 1000, StreamError::InvalidFeePercentage); // Max 10%
 
-        // Initialize market outcomes
-        let mut market_outcomes = Vec::new();
-        let liquidity_per_outcome = if initial_liquidity > 0 {
-            initial_liquidity / outcomes.len() as u64
-        } else {
-            1000_000_000 // 1000 USDC default liquidity per outcome
-        };
+        if let ResolutionPayoutPlan::Weighted { weights_bps } = &payout_plan {
+            require!(
+                !weights_bps.is_empty()
+                    && weights_bps.len() <= MAX_PAYOUT_PLACES
+                    && weights_bps.len() <= outcomes.len(),
+                StreamError::InvalidPayoutPlan
+            );
+            require!(
+                weights_bps.iter().all(|w| *w > 0)
+                    && weights_bps.iter().map(|w| *w as u32).sum::<u32>() == 10_000,
+                StreamError::InvalidPayoutPlan
+            );
+        }
 
-        for (i, desc) in outcomes.iter().enumerate() {
-            market_outcomes.push(MarketOutcome {
-                id: i as u8,
-                description: desc.clone(),
-                total_shares: 0,
-                liquidity_reserve: liquidity_per_outcome,
-                total_backing: 0,
-            });
+        let amm_curve = amm_curve.unwrap_or_default();
+        match amm_curve {
+            AmmCurve::Lmsr { b } => require!(b > 0, StreamError::InvalidMarketSetup),
+            // Pool betting is funded by the bets alone
+            AmmCurve::Parimutuel => require!(initial_liquidity == 0, StreamError::InvalidMarketSetup),
+            AmmCurve::ConstantProduct => {}
+        }
+
+        // Price markets are a yes/no question about the feed: outcome 0 when the comparison holds
+        let resolution_source = resolution_source.unwrap_or_default();
+        match resolution_source {
+            ResolutionSource::PriceOracle { .. } => require!(
+                market_type == MarketType::Binary && payout_plan == ResolutionPayoutPlan::WinnerTakesAll,
+                StreamError::InvalidMarketSetup
+            ),
+            // An arbiter stands in for the host, so it must be someone else
+            ResolutionSource::Arbiter(arbiter) => require!(
+                arbiter != Pubkey::default() && arbiter != self.host.key(),
+                StreamError::InvalidMarketSetup
+            ),
+            ResolutionSource::Manual | ResolutionSource::Consensus => {}
+        }
+
+        let rules = rules.unwrap_or(MarketRules { uri: String::new(), hash: [0; 32] });
+        require!(rules.uri.len() <= MAX_RULES_URI_LEN, StreamError::InvalidMarketRules);
+
+        let consensus = consensus.unwrap_or(ConsensusParams {
+            consensus_bps: DEFAULT_CONSENSUS_BPS,
+            quorum_validators: 0,
+            quorum_bps: DEFAULT_QUORUM_BPS,
+            supermajority_bps: DEFAULT_SUPERMAJORITY_BPS,
+        });
+        let config = PlatformConfig::load(&self.platform_config)?;
+        let (min_consensus_bps, max_consensus_bps) = PlatformConfig::consensus_bounds(config.as_ref());
+        require!(
+            consensus.consensus_bps >= min_consensus_bps && consensus.consensus_bps <= max_consensus_bps,
+            StreamError::InvalidConsensusParams
+        );
+        require!(
+            consensus.quorum_validators <= MAX_VALIDATORS,
+            StreamError::InvalidConsensusParams
+        );
+        require!(
+            consensus.quorum_bps >= MIN_QUORUM_BPS && consensus.quorum_bps as u64 <= BPS_DENOMINATOR,
+            StreamError::InvalidConsensusParams
+        );
+        require!(
+            consensus.supermajority_bps >= DEFAULT_SUPERMAJORITY_BPS
+                && consensus.supermajority_bps <= MAX_SUPERMAJORITY_BPS,
+            StreamError::InvalidConsensusParams
+        );
+
+        // Initialize market outcomes
+        let liquidity_per_outcome = liquidity_per_outcome(&amm_curve, initial_liquidity, outcomes.len());
+
+        let market_key = self.betting_market.key();
+        let mut book = self.market_book.load_init()?;
+        book.market = market_key;
+        book.outcome_count = outcomes.len() as u8;
+        book.bump = bumps.market_book;
+        book.counts_eligible = 1;
+        for outcome in book.outcomes.iter_mut().take(outcomes.len()) {
+            outcome.liquidity_reserve = liquidity_per_outcome;
         }
+        drop(book);
 
         // Set the market data
         self.betting_market.set_inner(BettingMarket {
             stream: self.stream.key(),
             host: self.host.key(),
             mint: self.mint.key(),
-            market_type,
-            outcomes: market_outcomes,
-            total_pool: 0,
-            total_liquidity: initial_liquidity,
+            outcome_count: outcomes.len() as u8,
             resolution_time,
             resolved: false,
             winning_outcome: None,
             randomness_requested: false,
+            bump: bumps.betting_market,
+            ranked_outcomes: Vec::new(),
+            taper: None,
+            settlement_hash: [0; 32],
+            free_bet_withheld: 0,
+            dust_accumulator: 0,
+            unsettled_shares: 0,
+            settled_gross: 0,
+            amm_curve,
+            version: MARKET_VERSION,
+            cancelled: false,
+            fees_collected: 0,
+            lp_total_shares: 0,
+            lp_pool: 0,
+            max_bet_per_user: None,
+            max_total_pool: None,
+            access_mode: MarketAccessMode::Open,
+            access_root: [0; 32],
+            betting_closed_at: None,
+            claim_deadline: 0,
+        });
+        self.market_config.set_inner(MarketConfig {
+            market: market_key,
+            market_type,
+            outcome_descriptions: outcomes.clone(),
             fee_percentage,
+            payout_plan,
+            total_liquidity: initial_liquidity,
+            rules_uri: rules.uri,
+            rules_hash: rules.hash,
+            consensus_bps: consensus.consensus_bps,
+            quorum_validators: consensus.quorum_validators,
             created_at: Clock::get()?.unix_timestamp,
-            bump: bumps.betting_market,
+            metric_source: self.host.key(),
+            reported_metric: None,
+            resolution_timeout: 0,
+            resolution_source,
+            bump: bumps.market_config,
+            referral_share_bps: 0,
+            quorum_bps: consensus.quorum_bps,
+            supermajority_bps: consensus.supermajority_bps,
         });
+        self.market_stats.set_inner(MarketStats::new(market_key, bumps.market_stats)?);
 
         msg!(
             "Betting market initialized with {} outcomes",
@@ -300,8 +1066,14 @@ impl<'info> InitializeBettingMarket<'info> {
         emit!(MarketCreated {
             market: self.betting_market.key(),
             stream: self.stream.key(),
-            market_type: self.betting_market.market_type.clone(),
+            market_type: self.market_config.market_type.clone(),
             outcomes,
+            rules_uri: self.market_config.rules_uri.clone(),
+            rules_hash: self.market_config.rules_hash,
+            consensus_bps: consensus.consensus_bps,
+            quorum_validators: consensus.quorum_validators,
+            quorum_bps: consensus.quorum_bps,
+            supermajority_bps: consensus.supermajority_bps,
             timestamp: Clock::get()?.unix_timestamp,
         });
 
@@ -310,240 +1082,796 @@ impl<'info> InitializeBettingMarket<'info> {
 }
 
 impl<'info> PlaceBet<'info> {
+    #[allow(clippy::too_many_arguments)]
     pub fn place_bet(
         &mut self,
         outcome_id: u8,
         usdc_amount: u64,
         min_shares: u64,
+        deadline: Option<i64>,
+        max_price_per_share: Option<u64>,
+        beneficiary: Option<Pubkey>,
         bumps: &PlaceBetBumps,
+        remaining_accounts: &[AccountInfo<'info>],
     ) -> Result<()> {
-        // Validate market state
-        require!(!self.betting_market.resolved, StreamError::MarketResolved);
-        require!(
-            Clock::get()?.unix_timestamp < self.betting_market.resolution_time,
-            StreamError::BettingClosed
-        );
-        require!(
-            (outcome_id as usize) < self.betting_market.outcomes.len(),
-            StreamError::InvalidOutcome
-        );
-        require!(usdc_amount > 0, StreamError::InvalidAmount);
-
-        // Calculate shares using AMM
-        let shares_out = self.calculate_shares_for_purchase(outcome_id, usdc_amount)?;
-        require!(shares_out >= min_shares, StreamError::SlippageExceeded);
-
-        msg!("Purchasing {} shares for {} USDC", shares_out, usdc_amount);
-
-        // Transfer USDC from bettor to market vault
-        let cpi_accounts = Transfer {
-            from: self.bettor_token.to_account_info(),
-            to: self.market_vault.to_account_info(),
-            authority: self.bettor.to_account_info(),
+        require_bet_open(&self.betting_market, outcome_id, usdc_amount)?;
+        // Limits and gates apply to whoever ends up holding the position
+        let owner = beneficiary.unwrap_or(self.bettor.key());
+        require_keys_neq!(owner, Pubkey::default(), StreamError::Unauthorized);
+        // A session key bets for its owner with the owner's tokens
+        let payer = match self.session.as_mut() {
+            Some(session) => {
+                require_keys_eq!(owner, session.owner, StreamError::Unauthorized);
+                session.spend(usdc_amount, Clock::get()?.unix_timestamp)?;
+                session.owner
+            }
+            None => self.bettor.key(),
         };
-        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
-        token_transfer(cpi_ctx, usdc_amount)?;
-
-        // Update market state
-        let outcome = &mut self.betting_market.outcomes[outcome_id as usize];
-        outcome.total_shares = outcome
-            .total_shares
-            .checked_add(shares_out)
-            .ok_or(StreamError::MathOverflow)?;
-        outcome.total_backing = outcome
-            .total_backing
-            .checked_add(usdc_amount)
-            .ok_or(StreamError::MathOverflow)?;
-        // Half goes to liquidity for AMM stability
-        outcome.liquidity_reserve = outcome
-            .liquidity_reserve
-            .checked_add(usdc_amount / 2)
-            .ok_or(StreamError::MathOverflow)?;
-
-        self.betting_market.total_pool = self
-            .betting_market
-            .total_pool
-            .checked_add(usdc_amount)
-            .ok_or(StreamError::MathOverflow)?;
+        // A bet signed against an earlier quote must not land long after it
+        if let Some(deadline) = deadline {
+            require!(Clock::get()?.unix_timestamp <= deadline, StreamError::BetDeadlinePassed);
+        }
+        Blocklist::require_not_blocked(
+            self.stream.blocked_count,
+            self.blocklist.as_ref().map(|b| b.as_ref()),
+            &owner,
+        )?;
+        MarketAccessEntry::require_access(
+            self.betting_market.access_mode,
+            self.market_access.as_ref().map(|e| e.as_ref()),
+            &owner,
+        )?;
+        enforce_access_gate(
+            &self.stream,
+            self.access_gate.as_ref(),
+            self.gate_token_account.as_ref(),
+            &owner,
+        )?;
+
+        // Transfer USDC from bettor to market vault, crediting only what arrives after any transfer fee
+        let vault_before = self.market_vault.amount;
+        match &self.session {
+            Some(session) => transfer_from_session(
+                session,
+                &self.bettor_token,
+                &self.mint,
+                self.market_vault.to_account_info(),
+                &self.token_program,
+                usdc_amount,
+                remaining_accounts,
+            )?,
+            None => {
+                let cpi_accounts = TransferChecked {
+                    from: self.bettor_token.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.market_vault.to_account_info(),
+                    authority: self.bettor.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+                    .with_remaining_accounts(remaining_accounts.to_vec());
+                transfer_checked(cpi_ctx, usdc_amount, self.mint.decimals)?;
+            }
+        }
+        let usdc_amount = amount_received(&mut self.market_vault, vault_before)?;
+
+        let shares_out = record_bet(
+            &self.betting_market,
+            &self.market_book,
+            &mut self.bettor_position,
+            &mut self.outcome_position,
+            &self.market_stats,
+            owner,
+            outcome_id,
+            usdc_amount,
+            min_shares,
+            (bumps.bettor_position, bumps.outcome_position),
+        )?;
 
-        // Initialize bettor position if needed
-        if self.bettor_position.bettor == Pubkey::default() {
-            self.bettor_position.set_inner(BettorPosition {
-                bettor: self.bettor.key(),
+        if owner != payer {
+            emit!(BetGifted {
                 market: self.betting_market.key(),
-                positions: Vec::new(),
-                total_invested: 0,
-                total_returned: 0,
-                has_claimed: false,
-                is_eligible_validator: false,
-                created_at: Clock::get()?.unix_timestamp,
-                bump: bumps.bettor_position,
+                payer,
+                beneficiary: owner,
+                outcome_id,
+                amount: usdc_amount,
+                timestamp: Clock::get()?.unix_timestamp,
             });
         }
 
-        // Update or add outcome position
-        let position_idx = self
-            .bettor_position
-            .positions
-            .iter()
-            .position(|p| p.outcome_id == outcome_id);
-
-        if let Some(idx) = position_idx {
-            // Update existing position
-            let pos = &mut self.bettor_position.positions[idx];
-            let new_total_invested = pos
-                .invested
-                .checked_add(usdc_amount)
-                .ok_or(StreamError::MathOverflow)?;
-            let new_total_shares = pos
-                .shares
-                .checked_add(shares_out)
-                .ok_or(StreamError::MathOverflow)?;
-
-            // Calculate new average price
-            pos.avg_entry_price = new_total_invested
+        MirrorConfig::record_bet(&self.mirror_config, owner, outcome_id, usdc_amount)?;
+
+        // The first referral sticks
+        if let Some(referrer) = &self.referrer {
+            require_keys_neq!(referrer.key(), owner, StreamError::Unauthorized);
+            self.bettor_position.referrer.get_or_insert(referrer.key());
+        }
+
+        // Same scale as avg_entry_price: stake per share times 1_000_000
+        if let Some(max_price) = max_price_per_share {
+            let price = (usdc_amount as u128)
                 .checked_mul(1_000_000)
                 .ok_or(StreamError::MathOverflow)?
-                .checked_div(new_total_shares)
-                .ok_or(StreamError::MathOverflow)?;
-
-            pos.shares = new_total_shares;
-            pos.invested = new_total_invested;
-        } else {
-            // Create new position
-            self.bettor_position.positions.push(OutcomePosition {
-                outcome_id,
-                shares: shares_out,
-                avg_entry_price: usdc_amount
-                    .checked_mul(1_000_000)
-                    .ok_or(StreamError::MathOverflow)?
-                    .checked_div(shares_out)
-                    .ok_or(StreamError::MathOverflow)?,
-                invested: usdc_amount,
-            });
+                / shares_out.max(1) as u128;
+            require!(price <= max_price as u128, StreamError::PriceLimitExceeded);
         }
 
-        // Update total invested
-        self.bettor_position.total_invested = self
-            .bettor_position
-            .total_invested
-            .checked_add(usdc_amount)
-            .ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+}
 
-        // Check if eligible for validation
-        if self.bettor_position.total_invested >= VALIDATOR_STAKE_REQUIREMENT {
-            self.bettor_position.is_eligible_validator = true;
-        }
+/// Fails unless `market` still takes a bet of `amount` on `outcome_id`
+pub(crate) fn require_bet_open(market: &BettingMarket, outcome_id: u8, amount: u64) -> Result<()> {
+    require!(!market.resolved, StreamError::MarketResolved);
+    require!(market.betting_open(Clock::get()?.unix_timestamp), StreamError::BettingClosed);
+    require!(outcome_id < market.outcome_count, StreamError::InvalidOutcome);
+    require!(amount > 0, StreamError::InvalidAmount);
+    Ok(())
+}
 
-        emit!(BetPlaced {
-            market: self.betting_market.key(),
-            bettor: self.bettor.key(),
-            outcome_id,
-            shares: shares_out,
-            price: usdc_amount,
-            timestamp: Clock::get()?.unix_timestamp,
+/// Buys shares of `outcome_id` with `usdc_amount` already in the market vault and records them
+/// on `position` and the bettor's `outcome_position` for that outcome, counting the bet in the
+/// market's `stats`. `bumps` are the two accounts' PDA bumps, used when either is new. Returns
+/// the shares bought.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn record_bet(
+    market: &Account<BettingMarket>,
+    book: &AccountLoader<MarketBook>,
+    position: &mut Account<BettorPosition>,
+    outcome_position: &mut Account<OutcomePositionAccount>,
+    stats: &AccountInfo,
+    bettor: Pubkey,
+    outcome_id: u8,
+    usdc_amount: u64,
+    min_shares: u64,
+    bumps: (u8, u8),
+) -> Result<u64> {
+    let (stake_for_shares, _) = stake_after_taper(market, usdc_amount)?;
+
+    if let Some(max_bet) = market.max_bet_per_user {
+        let staked = position.total_invested.checked_add(usdc_amount).ok_or(StreamError::MathOverflow)?;
+        require!(staked <= max_bet, StreamError::BetLimitExceeded);
+    }
+
+    // Calculate shares using AMM
+    let mut book = book.load_mut()?;
+    if let Some(max_pool) = market.max_total_pool {
+        let pool = book.total_pool.checked_add(usdc_amount).ok_or(StreamError::MathOverflow)?;
+        require!(pool <= max_pool, StreamError::PoolLimitReached);
+    }
+    let shares_out = calculate_shares_for_purchase(&market.amm_curve, &book, outcome_id, stake_for_shares)?;
+    require!(shares_out >= min_shares, StreamError::SlippageExceeded);
+
+    msg!("Purchasing {} shares for {} USDC", shares_out, usdc_amount);
+
+    // Update market state
+    book.record_purchase(outcome_id, shares_out, usdc_amount)?;
+    emit_odds(market.key(), &market.amm_curve, &book)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    if market.resolution_time - now <= BETTING_CLOSING_SOON {
+        notify(
+            NotificationCode::BettingClosingSoon,
+            market.key(),
+            [market.resolution_time as u64, book.total_pool],
+            now,
+        );
+    }
+
+    // Initialize bettor position if needed
+    let new_bettor = position.bettor == Pubkey::default();
+    if new_bettor {
+        position.set_inner(BettorPosition {
+            bettor,
+            market: market.key(),
+            open_outcomes: 0,
+            total_invested: 0,
+            total_returned: 0,
+            has_claimed: false,
+            is_eligible_validator: false,
+            created_at: Clock::get()?.unix_timestamp,
+            bump: bumps.0,
+            credit_staked: 0,
+            version: POSITION_VERSION,
+            referrer: None,
         });
+    }
 
-        Ok(())
+    // Update or open the outcome position
+    if outcome_position.market == Pubkey::default() {
+        outcome_position.set_inner(OutcomePositionAccount {
+            market: market.key(),
+            bettor,
+            outcome_id,
+            shares: 0,
+            avg_entry_price: 0,
+            invested: 0,
+            claimed: false,
+            bump: bumps.1,
+            credit_staked: 0,
+        });
+        position.open_outcomes = position.open_outcomes.checked_add(1).ok_or(StreamError::MathOverflow)?;
     }
+    let new_total_invested = outcome_position
+        .invested
+        .checked_add(usdc_amount)
+        .ok_or(StreamError::MathOverflow)?;
+    let new_total_shares = outcome_position
+        .shares
+        .checked_add(shares_out)
+        .ok_or(StreamError::MathOverflow)?;
+
+    // Calculate new average price
+    outcome_position.avg_entry_price = new_total_invested
+        .checked_mul(1_000_000)
+        .ok_or(StreamError::MathOverflow)?
+        .checked_div(new_total_shares)
+        .ok_or(StreamError::MathOverflow)?;
+    outcome_position.shares = new_total_shares;
+    outcome_position.invested = new_total_invested;
+
+    // Update total invested
+    let was_eligible = position.total_invested >= VALIDATOR_STAKE_REQUIREMENT;
+    position.total_invested = position
+        .total_invested
+        .checked_add(usdc_amount)
+        .ok_or(StreamError::MathOverflow)?;
+
+    // Check if eligible for validation
+    if position.total_invested >= VALIDATOR_STAKE_REQUIREMENT {
+        position.is_eligible_validator = true;
+    }
+    book.track_eligibility(was_eligible, position.is_eligible_validator)?;
 
-    fn calculate_shares_for_purchase(&self, outcome_id: u8, usdc_amount: u64) -> Result<u64> {
-        let outcome = &self.betting_market.outcomes[outcome_id as usize];
+    MarketStats::record_bet(stats, outcome_id, usdc_amount, new_bettor)?;
 
-        // Constant product AMM formula: shares_out = reserve * amount_in / (reserve + amount_in)
-        // This ensures price increases as more people bet on the same outcome
-        let shares = (outcome.liquidity_reserve as u128)
-            .checked_mul(usdc_amount as u128)
-            .ok_or(StreamError::MathOverflow)?
-            .checked_div(
-                (outcome.liquidity_reserve as u128)
-                    .checked_add(usdc_amount as u128)
-                    .ok_or(StreamError::MathOverflow)?,
-            )
-            .ok_or(StreamError::MathOverflow)? as u64;
+    emit!(BetPlaced {
+        market: market.key(),
+        bettor,
+        outcome_id,
+        shares: shares_out,
+        price: usdc_amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
 
-        // Ensure we don't give 0 shares
-        require!(shares > 0, StreamError::InvalidAmount);
+    Ok(shares_out)
+}
 
-        Ok(shares)
+/// Late bets are capped and pay an extra fee that stays in the pool. Returns the part of
+/// `amount` that buys shares and the fee taken from it.
+fn stake_after_taper(market: &BettingMarket, amount: u64) -> Result<(u64, u16)> {
+    if let Some(taper) = market.taper {
+        let seconds_left = market.resolution_time - Clock::get()?.unix_timestamp;
+        if let Some((max_bet, extra_fee_bps)) = taper.limits_at(seconds_left) {
+            require!(amount <= max_bet, StreamError::BetTooLarge);
+            return Ok((net_of_fee(amount, extra_fee_bps), extra_fee_bps));
+        }
     }
+    Ok((amount, 0))
 }
 
-impl<'info> RequestMarketRandomness<'info> {
-    pub fn request_randomness(
-        &mut self,
-        use_case: RandomnessUseCase,
-        client_seed: [u8; 32],
-        eligible_validators: Vec<EligibleValidator>,
-        bumps: &RequestMarketRandomnessBumps,
-    ) -> Result<()> {
-        msg!("Requesting randomness for {:?}", use_case);
+/// Publishes the market's odds after a trade moved them
+pub(crate) fn emit_odds(market: Pubkey, curve: &AmmCurve, book: &MarketBook) -> Result<()> {
+    emit!(OddsUpdated {
+        market,
+        probabilities_bps: book.implied_probabilities_bps(curve)?,
+        total_pool: book.total_pool,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
 
-        // Validate based on use case
-        match &use_case {
-            RandomnessUseCase::ValidatorSelection => {
-                require!(
-                    Clock::get()?.unix_timestamp >= self.market.resolution_time,
-                    StreamError::MarketNotReady
-                );
-                require!(
-                    !eligible_validators.is_empty(),
-                    StreamError::InsufficientValidators
+/// A bettor's outcome positions in the paying outcomes of a market, with the accounts they
+/// were read from so a claim can write them back
+pub(crate) struct PayingPositions<'a, 'info> {
+    pub held: Vec<OutcomePositionAccount>,
+    accounts: Vec<&'a AccountInfo<'info>>,
+}
+
+impl PayingPositions<'_, '_> {
+    /// Writes the positions back, recording which ones a claim paid
+    pub fn save(&self) -> Result<()> {
+        for (position, info) in self.held.iter().zip(&self.accounts) {
+            require!(info.is_writable, StreamError::InvalidRemainingAccounts);
+            position.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the bettor's outcome positions in each paying outcome of `market` from the leading
+/// remaining accounts, one PDA per entry of `payout_weights` in order, and returns them with
+/// the accounts left over for the token transfer. A PDA with no account behind it holds no
+/// shares, so every paying outcome is accounted for whether the bettor backed it or not.
+pub(crate) fn paying_positions<'a, 'info>(
+    market: &Account<BettingMarket>,
+    config: &MarketConfig,
+    bettor: &Pubkey,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> Result<(PayingPositions<'a, 'info>, &'a [AccountInfo<'info>])> {
+    let paying = market.payout_weights(config);
+    require!(remaining_accounts.len() >= paying.len(), StreamError::InvalidRemainingAccounts);
+    let (position_accounts, hook_accounts) = remaining_accounts.split_at(paying.len());
+
+    let mut positions = PayingPositions {
+        held: Vec::with_capacity(paying.len()),
+        accounts: Vec::with_capacity(paying.len()),
+    };
+    for ((outcome_id, _), info) in paying.iter().zip(position_accounts) {
+        let (expected, _) = Pubkey::find_program_address(
+            &[OUTCOME_POSITION_SEED, market.key().as_ref(), bettor.as_ref(), outcome_id.to_le_bytes().as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(info.key(), expected, StreamError::InvalidRemainingAccounts);
+        if info.data_is_empty() {
+            continue;
+        }
+        require_keys_eq!(*info.owner, crate::ID, StreamError::Unauthorized);
+        positions.held.push(OutcomePositionAccount::try_deserialize(&mut &info.data.borrow()[..])?);
+        positions.accounts.push(info);
+    }
+    Ok((positions, hook_accounts))
+}
+
+/// Accounts every winnings claim path settles a position through
+pub(crate) struct WinningsClaim<'a, 'info> {
+    pub betting_market: &'a mut Account<'info, BettingMarket>,
+    pub market_book: &'a AccountLoader<'info, MarketBook>,
+    pub market_config: &'a MarketConfig,
+    pub bettor_position: &'a mut Account<'info, BettorPosition>,
+    pub bettor_profile: &'a mut Account<'info, BettorProfile>,
+    pub referral_earnings: Option<&'a mut Account<'info, ReferralEarnings>>,
+    pub referral_earnings_vault: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    pub market_stats: &'a AccountInfo<'info>,
+    pub market_vault: &'a InterfaceAccount<'info, TokenAccount>,
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    pub token_program: &'a Interface<'info, TokenInterface>,
+}
+
+impl<'a, 'info> WinningsClaim<'a, 'info> {
+    /// Takes the bettor's payout off the market, pays their referrer's share of the host fee and
+    /// records the claim on the bettor's profile and the market stats. Returns the payout and the
+    /// remaining accounts left after the bettor's outcome positions, see `paying_positions`.
+    pub fn settle<'r>(
+        self,
+        bettor: Pubkey,
+        profile_bump: u8,
+        remaining_accounts: &'r [AccountInfo<'info>],
+    ) -> Result<(u64, &'r [AccountInfo<'info>])> {
+        let (mut positions, remaining_accounts) =
+            paying_positions(self.betting_market, self.market_config, &bettor, remaining_accounts)?;
+        let first_claim = !self.bettor_position.has_claimed;
+        let fees_before = self.betting_market.fees_collected;
+        let payout = self.betting_market.take_claim(
+            &*self.market_book.load()?,
+            self.market_config,
+            self.bettor_position,
+            &mut positions.held,
+        )?;
+        positions.save()?;
+
+        let host_fee = self.betting_market.fees_collected - fees_before;
+        pay_bet_referral(
+            self.betting_market,
+            self.market_config,
+            bettor,
+            self.bettor_position.referrer,
+            host_fee,
+            self.referral_earnings,
+            self.referral_earnings_vault,
+            self.market_vault,
+            self.mint,
+            self.token_program,
+            remaining_accounts,
+        )?;
+
+        self.bettor_profile.record_claim(
+            bettor,
+            self.betting_market.key(),
+            self.bettor_position.total_invested,
+            payout,
+            first_claim,
+            profile_bump,
+        )?;
+        MarketStats::record_claim(self.market_stats, payout)?;
+        Ok((payout, remaining_accounts))
+    }
+}
+
+fn calculate_shares_for_purchase(curve: &AmmCurve, book: &MarketBook, outcome_id: u8, usdc_amount: u64) -> Result<u64> {
+    match curve {
+        AmmCurve::Lmsr { b } => {
+            let shares = lmsr_buy_shares(&book.share_quantities(), *b, outcome_id as usize, usdc_amount)?;
+            require!(shares > 0, StreamError::InvalidAmount);
+            return Ok(shares);
+        }
+        // The stake is the claim on the pool
+        AmmCurve::Parimutuel => {
+            require!(usdc_amount > 0, StreamError::InvalidAmount);
+            return Ok(usdc_amount);
+        }
+        AmmCurve::ConstantProduct => {}
+    }
+
+    let outcome = book.outcome(outcome_id);
+
+    // Constant product AMM formula: shares_out = reserve * amount_in / (reserve + amount_in)
+    // This ensures price increases as more people bet on the same outcome
+    let shares = (outcome.liquidity_reserve as u128)
+        .checked_mul(usdc_amount as u128)
+        .ok_or(StreamError::MathOverflow)?
+        .checked_div(
+            (outcome.liquidity_reserve as u128)
+                .checked_add(usdc_amount as u128)
+                .ok_or(StreamError::MathOverflow)?,
+        )
+        .ok_or(StreamError::MathOverflow)? as u64;
+
+    // Ensure we don't give 0 shares
+    require!(shares > 0, StreamError::InvalidAmount);
+
+    Ok(shares)
+}
+
+impl<'info> InitializeMarketStats<'info> {
+    pub fn initialize_market_stats(&mut self, bumps: &InitializeMarketStatsBumps) -> Result<()> {
+        self.market_stats.set_inner(MarketStats::new(self.betting_market.key(), bumps.market_stats)?);
+        Ok(())
+    }
+}
+
+impl<'info> GetQuote<'info> {
+    /// Simulates `record_bet` on a copy of the book, so the quote matches what `place_bet`
+    /// would buy in the same slot
+    pub fn get_quote(&self, outcome_id: u8, amount: u64) -> Result<BetQuote> {
+        let market = &self.betting_market;
+        require_bet_open(market, outcome_id, amount)?;
+        let (stake_for_shares, extra_fee_bps) = stake_after_taper(market, amount)?;
+
+        let mut book = *self.market_book.load()?;
+        let probability_bps = book.implied_probabilities_bps(&market.amm_curve)?[outcome_id as usize];
+        let shares = calculate_shares_for_purchase(&market.amm_curve, &book, outcome_id, stake_for_shares)?;
+        book.record_purchase(outcome_id, shares, amount)?;
+        let probability_after_bps = book.implied_probabilities_bps(&market.amm_curve)?[outcome_id as usize];
+
+        Ok(BetQuote {
+            outcome_id,
+            amount,
+            shares,
+            average_price: (amount as u128 * 1_000_000 / shares as u128) as u64,
+            extra_fee_bps,
+            probability_bps,
+            probability_after_bps,
+        })
+    }
+}
+
+impl<'info> RequestMarketRandomness<'info> {
+    pub fn request_randomness(
+        &mut self,
+        use_case: RandomnessUseCase,
+        client_seed: [u8; 32],
+        bumps: &RequestMarketRandomnessBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        msg!("Requesting randomness for {:?}", use_case);
+
+        // Validate based on use case
+        match &use_case {
+            RandomnessUseCase::ValidatorSelection => {
+                require_validator_selection_due(&self.market, &self.market_config, Clock::get()?.unix_timestamp)?;
+            }
+            // A tie-break is only for a vote every selected validator has cast and that ended
+            // level, and only the host or one of those validators may ask for it
+            RandomnessUseCase::TieBreaker => {
+                let resolution = self.resolution.load()?;
+                let requestor = self.requestor.key();
+                require!(
+                    requestor == self.market.host || resolution.validators().contains(&requestor),
+                    StreamError::Unauthorized
                 );
+                require!(
+                    resolution.status_is(ResolutionStatus::UnderValidation)
+                        && resolution.votes_cast as usize == resolution.validators().len(),
+                    StreamError::InvalidResolutionState
+                );
+                require!(resolution.top_stake_outcomes().count_ones() >= 2, StreamError::NoTieToBreak);
+            }
+            // The callback only acts on the two use cases above
+            RandomnessUseCase::DisputeResolution | RandomnessUseCase::FairDistribution => {
+                return err!(StreamError::UnsupportedRandomnessUseCase);
             }
-            _ => {}
         }
 
-        // Initialize or update resolution account
-        if self.resolution.market == Pubkey::default() {
-            // First time initialization
-            self.resolution.set_inner(MarketResolution {
-                market: self.market.key(),
-                proposed_outcome: None,
-                validators: Vec::new(),
-                validator_votes: Vec::new(),
-                dispute_end_time: Clock::get()?.unix_timestamp + DISPUTE_WINDOW,
-                resolution_status: ResolutionStatus::AwaitingRandomness,
-                randomness_seed: [0u8; 32],
-                randomness_use_case: use_case.clone(),
-                total_stake_validating: 0,
-                eligible_validators, // Store the eligible validators
-                bump: bumps.resolution,
-            });
-        } else {
-            // Update existing resolution
-            self.resolution.randomness_use_case = use_case.clone();
-            self.resolution.eligible_validators = eligible_validators;
-            self.resolution.resolution_status = ResolutionStatus::AwaitingRandomness;
-        }
+        let nonce = {
+            let mut resolution = open_resolution(&self.resolution, &self.market, bumps.resolution)?;
+            if use_case == RandomnessUseCase::ValidatorSelection {
+                resolution.require_validator_selection_open()?;
+            }
+            let nonce = resolution.begin_randomness_request(use_case.clone(), Clock::get()?.unix_timestamp)?;
+            if use_case == RandomnessUseCase::TieBreaker {
+                resolution.tied_outcomes = resolution.top_stake_outcomes();
+            } else {
+                let (eligible, exposure) = list_eligible_validators(
+                    &self.market.key(),
+                    &*self.market_book.load()?,
+                    self.validator_registry.as_ref(),
+                    remaining_accounts,
+                )?;
+                resolution.set_eligible_validators(&eligible, &exposure, self.validator_registry.is_some())?;
+            }
+            resolution.set_status(ResolutionStatus::AwaitingRandomness);
+            nonce
+        };
+        self.market.randomness_requested = true;
 
         // Create the randomness request instruction
-        let ix = create_request_randomness_ix(RequestRandomnessParams {
-            payer: self.requestor.key(),
-            oracle_queue: self.oracle_queue.key(),
-            callback_program_id: crate::ID,
-            // Use the instruction discriminator that Anchor generates
-            callback_discriminator: crate::instruction::CallbackProcessRandomness::DISCRIMINATOR
-                .to_vec(),
-            caller_seed: client_seed,
-            accounts_metas: Some(vec![
-                SerializableAccountMeta {
-                    pubkey: self.market.key(),
-                    is_signer: false,
-                    is_writable: true,
-                },
-                SerializableAccountMeta {
-                    pubkey: self.resolution.key(),
-                    is_signer: false,
-                    is_writable: true,
-                },
-            ]),
-            ..Default::default()
-        });
+        let ix = randomness_request_ix(
+            self.requestor.key(),
+            self.oracle_queue.key(),
+            client_seed,
+            [self.market.key(), self.market_book.key(), self.market_config.key(), self.resolution.key()],
+        );
 
         // Invoke the VRF instruction
         self.invoke_signed_vrf(&self.requestor.to_account_info(), &ix)?;
 
+        emit!(RandomnessRequested {
+            market: self.market.key(),
+            nonce,
+            use_case,
+            requestor: self.requestor.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+/// Validators are only selected once the market's resolution time has passed, and for an
+/// arbiter market only once the arbiter has let the resolution timeout pass too
+pub(crate) fn require_validator_selection_due(market: &BettingMarket, config: &MarketConfig, now: i64) -> Result<()> {
+    require!(now >= market.resolution_time, StreamError::MarketNotReady);
+    if let ResolutionSource::Arbiter(_) = config.resolution_source {
+        require!(
+            now >= config.resolution_deadline(market.resolution_time),
+            StreamError::ArbiterResolutionRequired
+        );
+    }
+    Ok(())
+}
+
+/// Loads the market's resolution for writing, initializing it first if it is a fresh account
+/// with no discriminator yet
+pub(crate) fn open_resolution<'a>(
+    resolution: &'a AccountLoader<'_, MarketResolution>,
+    market: &Account<BettingMarket>,
+    bump: u8,
+) -> Result<std::cell::RefMut<'a, MarketResolution>> {
+    let is_new = resolution.as_ref().try_borrow_data()?[..8] == [0u8; 8];
+    if !is_new {
+        return resolution.load_mut();
+    }
+    let mut state = resolution.load_init()?;
+    state.market = market.key();
+    state.outcome_count = market.outcome_count;
+    state.proposed_outcome = NO_PROPOSED_OUTCOME;
+    state.dispute_end_time = Clock::get()?.unix_timestamp + DISPUTE_WINDOW;
+    state.bump = bump;
+    Ok(state)
+}
+
+/// Validators eligible to resolve `market` with each one's stake in it: the mint's registry
+/// when one is passed, otherwise every qualifying bettor, whose positions are `remaining_accounts`
+pub(crate) fn list_eligible_validators(
+    market: &Pubkey,
+    book: &MarketBook,
+    registry: Option<&AccountLoader<ValidatorRegistry>>,
+    remaining_accounts: &[AccountInfo],
+) -> Result<(Vec<ValidatorStake>, Vec<u64>)> {
+    let (eligible, exposure) = match registry {
+        Some(registry) => {
+            let eligible = registry.load()?.validators().to_vec();
+            let exposure = validator_exposure(market, &eligible, remaining_accounts)?;
+            (eligible, exposure)
+        }
+        // A bettor's whole stake is exposure to the market
+        None => {
+            let eligible = eligible_bettors(market, book, remaining_accounts)?;
+            let exposure = eligible.iter().map(|validator| validator.stake).collect();
+            (eligible, exposure)
+        }
+    };
+    require!(!eligible.is_empty(), StreamError::InsufficientValidators);
+    Ok((eligible, exposure))
+}
+
+/// Lists the bettors behind `position_accounts` who have invested at least
+/// `VALIDATOR_STAKE_REQUIREMENT`, each weighted by what they invested. Every account must be a
+/// position PDA of `market`, and the qualifying ones must add up to the count the market's book
+/// keeps, so the requestor cannot leave any eligible bettor out. Books split from markets that
+/// predate the count have to select from the validator registry.
+fn eligible_bettors(market: &Pubkey, book: &MarketBook, position_accounts: &[AccountInfo]) -> Result<Vec<ValidatorStake>> {
+    require!(book.counts_eligible == 1, StreamError::ValidatorRegistryRequired);
+    require!(position_accounts.len() <= MAX_ELIGIBLE_VALIDATORS, StreamError::TooManyValidators);
+    let mut eligible: Vec<ValidatorStake> = Vec::with_capacity(position_accounts.len());
+    for info in position_accounts {
+        require_keys_eq!(*info.owner, crate::ID, StreamError::InvalidRemainingAccounts);
+        let position = BettorPosition::try_deserialize(&mut &info.data.borrow()[..])?;
+        let expected = Pubkey::create_program_address(
+            &[POSITION_SEED, market.as_ref(), position.bettor.as_ref(), &[position.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| StreamError::InvalidRemainingAccounts)?;
+        require_keys_eq!(info.key(), expected, StreamError::InvalidRemainingAccounts);
+        require!(
+            eligible.iter().all(|listed| listed.pubkey != position.bettor),
+            StreamError::InvalidRemainingAccounts
+        );
+        if position.total_invested >= VALIDATOR_STAKE_REQUIREMENT {
+            eligible.push(ValidatorStake { pubkey: position.bettor, stake: position.total_invested });
+        }
+    }
+    require!(eligible.len() == book.eligible_bettors as usize, StreamError::InvalidRemainingAccounts);
+    Ok(eligible)
+}
+
+/// Stake each registered validator holds in `market`, read from `position_accounts`: one
+/// position PDA per validator in registry order. A PDA with no account behind it holds nothing,
+/// so no validator's stake can be left out.
+fn validator_exposure(
+    market: &Pubkey,
+    validators: &[ValidatorStake],
+    position_accounts: &[AccountInfo],
+) -> Result<Vec<u64>> {
+    require!(position_accounts.len() == validators.len(), StreamError::InvalidRemainingAccounts);
+    validators
+        .iter()
+        .zip(position_accounts)
+        .map(|(validator, info)| {
+            let (expected, _) = Pubkey::find_program_address(
+                &[POSITION_SEED, market.as_ref(), validator.pubkey.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(info.key(), expected, StreamError::InvalidRemainingAccounts);
+            if info.data_is_empty() {
+                return Ok(0);
+            }
+            require_keys_eq!(*info.owner, crate::ID, StreamError::InvalidRemainingAccounts);
+            Ok(BettorPosition::try_deserialize(&mut &info.data.borrow()[..])?.total_invested)
+        })
+        .collect()
+}
+
+/// VRF request whose callback is `callback_process_randomness` with `[market, market_book,
+/// market_config, resolution]`
+pub(crate) fn randomness_request_ix(
+    payer: Pubkey,
+    oracle_queue: Pubkey,
+    client_seed: [u8; 32],
+    [market, market_book, market_config, resolution]: [Pubkey; 4],
+) -> Instruction {
+    let meta = |pubkey, is_writable| SerializableAccountMeta {
+        pubkey,
+        is_signer: false,
+        is_writable,
+    };
+    create_request_randomness_ix(RequestRandomnessParams {
+        payer,
+        oracle_queue,
+        callback_program_id: crate::ID,
+        // Use the instruction discriminator that Anchor generates
+        callback_discriminator: crate::instruction::CallbackProcessRandomness::DISCRIMINATOR.to_vec(),
+        caller_seed: client_seed,
+        accounts_metas: Some(vec![
+            meta(market, true),
+            meta(market_book, false),
+            meta(market_config, false),
+            meta(resolution, true),
+        ]),
+        ..Default::default()
+    })
+}
+
+impl<'info> ForceResolveTimeout<'info> {
+    /// First call, once the market's resolution timeout has passed without consensus: hands the
+    /// outcome to a VRF tie-break. Second call, once `RANDOMNESS_TIMEOUT` has also passed without
+    /// the callback resolving the market: cancels it so bettors can reclaim their stakes.
+    pub fn force_resolve_timeout(&mut self, client_seed: [u8; 32]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        if !force_timeout(&mut self.market, &self.market_config, &self.resolution, self.caller.key(), now)? {
+            return Ok(());
+        }
+
+        let ix = randomness_request_ix(
+            self.caller.key(),
+            self.oracle_queue.key(),
+            client_seed,
+            [self.market.key(), self.market_book.key(), self.market_config.key(), self.resolution.key()],
+        );
+        self.invoke_signed_vrf(&self.caller.to_account_info(), &ix)
+    }
+}
+
+/// The state change behind `force_resolve_timeout`. Returns whether a VRF tie-break request has
+/// to be sent; false when the market was cancelled instead.
+pub(crate) fn force_timeout(
+    market: &mut Account<BettingMarket>,
+    config: &MarketConfig,
+    resolution: &AccountLoader<MarketResolution>,
+    caller: Pubkey,
+    now: i64,
+) -> Result<bool> {
+    require!(!market.resolved, StreamError::MarketResolved);
+
+    {
+        let mut resolution = resolution.load_mut()?;
+        if resolution.status_is(ResolutionStatus::ForcedByRandomness) {
+            require!(
+                now >= resolution.forced_at.saturating_add(RANDOMNESS_TIMEOUT),
+                StreamError::ResolutionTimeoutPending
+            );
+            drop(resolution);
+            market.cancelled = true;
+            market.resolved = true;
+            market.winning_outcome = None;
+            market.open_claim_window()?;
+
+            emit!(MarketCancelled {
+                market: market.key(),
+                cancelled_by: caller,
+                timestamp: now,
+            });
+            return Ok(false);
+        }
+
+        require!(
+            !resolution.status_is(ResolutionStatus::Finalized)
+                && !resolution.status_is(ResolutionStatus::Disputed),
+            StreamError::InvalidResolutionState
+        );
+        require!(
+            now >= config.resolution_deadline(market.resolution_time),
+            StreamError::ResolutionTimeoutPending
+        );
+        resolution.begin_randomness_request(RandomnessUseCase::TieBreaker, now)?;
+        // Nobody reached a result in time, so every outcome is in the draw
+        resolution.tied_outcomes = (1u16 << resolution.outcome_count) - 1;
+        resolution.set_status(ResolutionStatus::ForcedByRandomness);
+        resolution.forced_at = now;
+    }
+    market.randomness_requested = true;
+
+    emit!(ResolutionForced {
+        market: market.key(),
+        caller,
+        randomness_deadline: now.saturating_add(RANDOMNESS_TIMEOUT),
+        timestamp: now,
+    });
+    Ok(true)
+}
+
+impl<'info> ExpireRandomnessRequest<'info> {
+    /// The resolution keeps its status, so a forced tie-break still cancels the market once
+    /// `RANDOMNESS_TIMEOUT` passes
+    pub fn expire_randomness_request(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let mut resolution = self.resolution.load_mut()?;
+        require!(
+            resolution.randomness_status_is(RandomnessRequestStatus::Pending),
+            StreamError::NoPendingRandomnessRequest
+        );
+        require!(
+            now >= resolution.randomness_requested_at.saturating_add(RANDOMNESS_REQUEST_TIMEOUT),
+            StreamError::RandomnessRequestNotExpired
+        );
+        resolution.randomness_status = RandomnessRequestStatus::Expired as u8;
+        self.market.randomness_requested = false;
+
+        emit!(RandomnessRequestExpired {
+            market: self.market.key(),
+            nonce: resolution.randomness_nonce,
+            requested_at: resolution.randomness_requested_at,
+            timestamp: now,
+        });
         Ok(())
     }
 }
@@ -552,24 +1880,50 @@ impl<'info> CallbackProcessRandomness<'info> {
     pub fn process_randomness(&mut self, randomness: [u8; 32]) -> Result<()> {
         msg!("Processing randomness callback");
 
+        // Only the request in flight may be answered; a late callback for an expired one is refused
+        let (use_case, tied_count) = {
+            let mut resolution = self.resolution.load_mut()?;
+            require!(
+                resolution.randomness_status_is(RandomnessRequestStatus::Pending),
+                StreamError::NoPendingRandomnessRequest
+            );
+            resolution.randomness_status = RandomnessRequestStatus::Fulfilled as u8;
+            (resolution.use_case(), resolution.tied_outcomes.count_ones() as u8)
+        };
+        self.market.randomness_requested = false;
+
         // Use Ephemeral VRF's random utilities
-        match self.resolution.randomness_use_case {
-            RandomnessUseCase::ValidatorSelection => {
-                // Select validators using randomness
-                let num_validators = MIN_VALIDATORS;
-                let random_value =
-                    ephemeral_vrf_sdk::rnd::random_u8_with_range(&randomness, 0, num_validators);
-                msg!("Selected {} validators", random_value);
+        match use_case {
+            // With nobody left to draw the resolution awaits another request or the timeout
+            Some(RandomnessUseCase::ValidatorSelection) if !self.market.resolved => {
+                let total_pool = self.market_book.load()?.total_pool;
+                let max_exposure = (total_pool as u128 * MAX_VALIDATOR_EXPOSURE_BPS as u128 / BPS_DENOMINATOR as u128) as u64;
+                let count = (MIN_VALIDATORS as usize).max(self.market_config.quorum_validators as usize);
+                let mut resolution = self.resolution.load_mut()?;
+                let selected = resolution.select_validators(&randomness, count, max_exposure);
+                msg!("Selected {} validators", selected);
+                if selected > 0 {
+                    resolution.set_status(ResolutionStatus::UnderValidation);
+                    emit!(ValidatorsSelected {
+                        market: self.market.key(),
+                        validators: resolution.validators().to_vec(),
+                        total_validators: resolution.eligible_count,
+                        max_exposure,
+                        timestamp: Clock::get()?.unix_timestamp,
+                    });
+                }
             }
-            RandomnessUseCase::TieBreaker => {
-                // Resolve tie with randomness
-                let winner = ephemeral_vrf_sdk::rnd::random_u8_with_range(
-                    &randomness,
-                    0,
-                    self.market.outcomes.len() as u8,
-                );
+            // A market resolved or cancelled while the request was in flight keeps its state
+            Some(RandomnessUseCase::TieBreaker) if !self.market.resolved && tied_count > 0 => {
+                // Resolve tie with randomness, picking only between the tied outcomes (range is inclusive)
+                let index = ephemeral_vrf_sdk::rnd::random_u8_with_range(&randomness, 0, tied_count - 1);
+                let winner = self
+                    .resolution
+                    .load()?
+                    .tied_outcome(index)
+                    .ok_or(StreamError::InvalidOutcome)?;
                 self.market.winning_outcome = Some(winner);
-                self.market.resolved = true;
+                self.market.record_resolution(&*self.market_book.load()?, &self.market_config)?;
             }
             _ => {}
         }
@@ -579,33 +1933,32 @@ impl<'info> CallbackProcessRandomness<'info> {
 }
 
 impl<'info> ValidatorVoteOnOutcome<'info> {
-    pub fn vote(&mut self, outcome_id: u8) -> Result<()> {
+    pub fn vote(&mut self, outcome_id: u8, bumps: &ValidatorVoteOnOutcomeBumps) -> Result<()> {
+        let mut resolution = self.resolution.load_mut()?;
+
         // Validate voting conditions
         require!(
-            self.resolution.resolution_status == ResolutionStatus::UnderValidation,
+            resolution.status_is(ResolutionStatus::UnderValidation),
             StreamError::InvalidResolutionState
         );
         require!(
-            self.resolution.validators.contains(&self.validator.key()),
+            resolution.validators().contains(&self.validator.key()),
             StreamError::NotValidator
         );
+        if resolution.eligible_from_registry == 1 {
+            let registration = self.registration.as_ref().ok_or(StreamError::MissingAccounts)?;
+            require!(registration.is_active(), StreamError::InsufficientStakeForValidation);
+        }
+        // Weighed by the stake listed at selection, so topping up afterwards buys no weight
+        let stake = resolution
+            .eligible_stake(&self.validator.key())
+            .ok_or(StreamError::NotValidator)?;
         require!(
-            self.position.total_invested >= VALIDATOR_STAKE_REQUIREMENT,
-            StreamError::InsufficientStakeForValidation
-        );
-        require!(
-            (outcome_id as usize) < self.market.outcomes.len(),
+            outcome_id < self.market.outcome_count
+                && (outcome_id as usize) < resolution.outcome_stakes().len(),
             StreamError::InvalidOutcome
         );
 
-        // Check if already voted
-        let already_voted = self
-            .resolution
-            .validator_votes
-            .iter()
-            .any(|v| v.validator == self.validator.key());
-        require!(!already_voted, StreamError::AlreadyVoted);
-
         msg!(
             "Validator {} voting for outcome {}",
             self.validator.key(),
@@ -613,79 +1966,79 @@ impl<'info> ValidatorVoteOnOutcome<'info> {
         );
 
         // Record the vote
-        self.resolution.validator_votes.push(ValidatorVote {
+        self.vote_record.set_inner(VoteRecord {
+            resolution: self.resolution.key(),
             validator: self.validator.key(),
             voted_outcome: outcome_id,
             vote_timestamp: Clock::get()?.unix_timestamp,
-            stake_amount: self.position.total_invested,
+            stake_amount: stake,
+            bump: bumps.vote_record,
         });
 
+        // Update running tallies
+        let tally = &mut resolution.outcome_stakes[outcome_id as usize];
+        *tally = tally
+            .checked_add(stake)
+            .ok_or(StreamError::MathOverflow)?;
+        resolution.votes_cast = resolution
+            .votes_cast
+            .checked_add(1)
+            .ok_or(StreamError::MathOverflow)?;
+
         // Update total stake validating
-        self.resolution.total_stake_validating = self
-            .resolution
+        resolution.total_stake_validating = resolution
             .total_stake_validating
-            .checked_add(self.position.total_invested)
+            .checked_add(stake)
             .ok_or(StreamError::MathOverflow)?;
 
-        // Check if we have enough votes for consensus (2/3 of validators)
-        let required_votes = (self.resolution.validators.len() * 2) / 3;
-        if self.resolution.validator_votes.len() >= required_votes {
-            self.check_consensus()?;
+        // Check if we have enough votes for the market's quorum
+        let required_votes = self.market_config.required_votes(resolution.validators().len());
+        if resolution.votes_cast as usize >= required_votes {
+            self.check_consensus(&mut resolution)?;
         }
 
         emit!(ValidationVote {
             market: self.market.key(),
             validator: self.validator.key(),
             voted_outcome: outcome_id,
-            stake_weight: self.position.total_invested,
+            stake_weight: stake,
             timestamp: Clock::get()?.unix_timestamp,
         });
 
         Ok(())
     }
 
-    fn check_consensus(&mut self) -> Result<()> {
-        // Count votes weighted by stake
-        let mut outcome_stakes: Vec<(u8, u64)> = Vec::new();
-
-        for vote in &self.resolution.validator_votes {
-            if let Some(pos) = outcome_stakes
-                .iter_mut()
-                .find(|(id, _)| *id == vote.voted_outcome)
-            {
-                pos.1 = pos
-                    .1
-                    .checked_add(vote.stake_amount)
-                    .ok_or(StreamError::MathOverflow)?;
-            } else {
-                outcome_stakes.push((vote.voted_outcome, vote.stake_amount));
-            }
-        }
-
-        // Find outcome with most stake
+    fn check_consensus(&self, resolution: &mut MarketResolution) -> Result<()> {
+        // Find outcome with most stake from the running tallies
         let mut winning_outcome = 0u8;
         let mut max_stake = 0u64;
 
-        for (outcome, stake) in outcome_stakes.iter() {
+        for (outcome, stake) in resolution.outcome_stakes().iter().enumerate() {
             if *stake > max_stake {
                 max_stake = *stake;
-                winning_outcome = *outcome;
+                winning_outcome = outcome as u8;
             }
         }
 
-        // Check if we have super-majority (66%+ of total stake)
-        let required_stake = (self.resolution.total_stake_validating * 2) / 3;
+        // Check if the leading outcome holds the market's consensus share of total stake
+        let required_stake = self.market_config.required_stake(resolution.total_stake_validating);
         if max_stake >= required_stake {
             msg!(
                 "Consensus reached: outcome {} with {} stake",
                 winning_outcome,
                 max_stake
             );
-            self.resolution.proposed_outcome = Some(winning_outcome);
-            self.resolution.resolution_status = ResolutionStatus::Finalized;
+            resolution.proposed_outcome = winning_outcome;
+            resolution.set_status(ResolutionStatus::Finalized);
+            notify(
+                NotificationCode::ConsensusReached,
+                self.market.key(),
+                [winning_outcome as u64, max_stake],
+                Clock::get()?.unix_timestamp,
+            );
 
-            // Note: Actual market resolution should be done in a separate instruction
-            // to maintain separation of concerns
+            // The market itself resolves through finalize_resolution once the dispute
+            // window has passed
         } else {
             msg!(
                 "No consensus yet. Max stake: {}, required: {}",
@@ -699,69 +2052,512 @@ impl<'info> ValidatorVoteOnOutcome<'info> {
 }
 
 impl<'info> ResolveMarket<'info> {
-    pub fn resolve_market(&mut self, winning_outcome: u8) -> Result<()> {
+    /// Returns whether the host is the one resolving. Manual markets answer to the host and
+    /// arbiter markets to their arbiter alone.
+    fn authorize_resolver(&self) -> Result<bool> {
+        let resolver = self.resolver.key();
+        match self.market_config.resolution_source {
+            ResolutionSource::Manual => {
+                require_keys_eq!(resolver, self.betting_market.host, StreamError::Unauthorized);
+                Ok(true)
+            }
+            ResolutionSource::Arbiter(arbiter) => {
+                require_keys_eq!(resolver, arbiter, StreamError::Unauthorized);
+                Ok(false)
+            }
+            ResolutionSource::PriceOracle { .. } => err!(StreamError::PriceOracleResolutionRequired),
+            ResolutionSource::Consensus => err!(StreamError::ConsensusResolutionRequired),
+        }
+    }
+
+    /// `settlement_hash` commits the host to a human-readable statement of what happened and
+    /// why the outcome won, so frontends can show it and detect later edits
+    pub fn resolve_market(&mut self, winning_outcome: u8, settlement_hash: [u8; 32]) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(settlement_hash != [0; 32], StreamError::MissingSettlementStatement);
+        require!(
+            MarketOracle::load(&self.market_oracle)?.is_none(),
+            StreamError::OracleResolutionRequired
+        );
+        require!(
+            self.market_config.market_type.outcome_for_value(0).is_none(),
+            StreamError::MetricResolutionRequired
+        );
+        require!(
+            self.market_resolution.data_is_empty(),
+            StreamError::ConsensusResolutionRequired
+        );
+        let by_host = self.authorize_resolver()?;
+        require!(
+            self.market_config.payout_plan == ResolutionPayoutPlan::WinnerTakesAll,
+            StreamError::RankedResolutionRequired
+        );
+        require!(winning_outcome < self.betting_market.outcome_count, StreamError::InvalidOutcome);
         msg!("Resolving market with outcome {}", winning_outcome);
+        let book = self.market_book.load()?;
         self.betting_market.winning_outcome = Some(winning_outcome);
-        self.betting_market.resolved = true;
+        self.betting_market.settlement_hash = settlement_hash;
+        self.betting_market.record_resolution(&book, &self.market_config)?;
+        if by_host {
+            self.host_profile.record_resolution(Clock::get()?.unix_timestamp)?;
+        }
+
+        emit!(MarketResolved {
+            market: self.betting_market.key(),
+            winning_outcome,
+            total_pool: book.total_pool,
+            used_randomness: false,
+            settlement_hash,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn resolve_market_ranked(&mut self, ranked_outcomes: Vec<u8>, settlement_hash: [u8; 32]) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(settlement_hash != [0; 32], StreamError::MissingSettlementStatement);
+        require!(
+            MarketOracle::load(&self.market_oracle)?.is_none(),
+            StreamError::OracleResolutionRequired
+        );
+        require!(
+            self.market_config.market_type.outcome_for_value(0).is_none(),
+            StreamError::MetricResolutionRequired
+        );
+        require!(
+            self.market_resolution.data_is_empty(),
+            StreamError::ConsensusResolutionRequired
+        );
+        let by_host = self.authorize_resolver()?;
+        let ResolutionPayoutPlan::Weighted { weights_bps } = self.market_config.payout_plan.clone() else {
+            return err!(StreamError::InvalidPayoutPlan);
+        };
+        require!(
+            ranked_outcomes.len() == weights_bps.len(),
+            StreamError::InvalidRanking
+        );
+        for (i, outcome) in ranked_outcomes.iter().enumerate() {
+            require!(
+                *outcome < self.betting_market.outcome_count,
+                StreamError::InvalidOutcome
+            );
+            require!(
+                !ranked_outcomes[..i].contains(outcome),
+                StreamError::InvalidRanking
+            );
+        }
+
+        msg!("Resolving market with ranking {:?}", ranked_outcomes);
+        let book = self.market_book.load()?;
+        self.betting_market.winning_outcome = Some(ranked_outcomes[0]);
+        self.betting_market.ranked_outcomes = ranked_outcomes.clone();
+        self.betting_market.settlement_hash = settlement_hash;
+        self.betting_market.record_resolution(&book, &self.market_config)?;
+        if by_host {
+            self.host_profile.record_resolution(Clock::get()?.unix_timestamp)?;
+        }
+
+        emit!(MarketResolvedRanked {
+            market: self.betting_market.key(),
+            ranked_outcomes,
+            weights_bps,
+            total_pool: book.total_pool,
+            settlement_hash,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
         Ok(())
     }
 }
 
-impl<'info> ClaimWinnings<'info> {
-    pub fn claim_winnings(&mut self) -> Result<()> {
-        // Validate market is resolved
-        require!(self.betting_market.resolved, StreamError::MarketNotResolved);
-        let winning_outcome = self
-            .betting_market
-            .winning_outcome
-            .ok_or(StreamError::MarketNotResolved)?;
-
-        // Check if already claimed
-        require!(
-            !self.bettor_position.has_claimed,
-            StreamError::AlreadyClaimed
-        );
-
-        // Calculate winnings
-        let mut payout = 0u64;
-        let mut has_winning_position = false;
-
-        for position in &self.bettor_position.positions {
-            if position.outcome_id == winning_outcome {
-                has_winning_position = true;
-
-                // Calculate share of the total pool
-                let winning_outcome_data = &self.betting_market.outcomes[winning_outcome as usize];
-
-                if winning_outcome_data.total_shares > 0 {
-                    // Calculate proportional share of the entire pool
-                    let share_value = (self.betting_market.total_pool as u128)
-                        .checked_mul(position.shares as u128)
-                        .ok_or(StreamError::MathOverflow)?
-                        .checked_div(winning_outcome_data.total_shares as u128)
-                        .ok_or(StreamError::MathOverflow)?
-                        as u64;
-
-                    // Apply platform fee
-                    let fee = (share_value as u128)
-                        .checked_mul(self.betting_market.fee_percentage as u128)
-                        .ok_or(StreamError::MathOverflow)?
-                        .checked_div(10000)
-                        .ok_or(StreamError::MathOverflow)? as u64;
-
-                    let net_payout = share_value
-                        .checked_sub(fee)
-                        .ok_or(StreamError::MathOverflow)?;
-
-                    payout = payout
-                        .checked_add(net_payout)
-                        .ok_or(StreamError::MathOverflow)?;
-                }
-            }
+impl<'info> FinalizeResolution<'info> {
+    /// Writes the outcome validators reached consensus on into the market. The host has no say
+    /// once validator selection has started, so this is the only way such a market resolves.
+    /// `VALIDATOR_REWARD_BPS` of the pool is set aside for `distribute_validator_rewards` first,
+    /// so winners are paid from what remains.
+    pub fn finalize_resolution(&mut self) -> Result<()> {
+        finalize_consensus(
+            &mut self.betting_market,
+            &self.market_book,
+            &self.market_config,
+            &self.resolution,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+}
+
+/// The state change behind `finalize_resolution`
+pub(crate) fn finalize_consensus(
+    market: &mut Account<BettingMarket>,
+    market_book: &AccountLoader<MarketBook>,
+    config: &MarketConfig,
+    resolution: &AccountLoader<MarketResolution>,
+    now: i64,
+) -> Result<()> {
+    require!(!market.resolved, StreamError::MarketResolved);
+    require!(
+        config.payout_plan == ResolutionPayoutPlan::WinnerTakesAll,
+        StreamError::RankedResolutionRequired
+    );
+    let winning_outcome = {
+        let resolution = resolution.load()?;
+        require!(
+            resolution.status_is(ResolutionStatus::Finalized),
+            StreamError::InvalidResolutionState
+        );
+        require!(now >= resolution.dispute_end_time, StreamError::DisputeWindowOpen);
+        resolution.proposed_outcome().ok_or(StreamError::InvalidResolutionState)?
+    };
+    require!(winning_outcome < market.outcome_count, StreamError::InvalidOutcome);
+
+    let mut book = market_book.load_mut()?;
+    {
+        let mut resolution = resolution.load_mut()?;
+        // Nobody to reward when no selected validator voted for the winning outcome
+        if resolution.outcome_stakes().get(winning_outcome as usize).is_some_and(|stake| *stake > 0) {
+            let reward_pool = (book.total_pool as u128 * VALIDATOR_REWARD_BPS as u128 / BPS_DENOMINATOR as u128) as u64;
+            resolution.validator_reward_pool = reward_pool;
+            book.total_pool -= reward_pool;
+        }
+    }
+    market.winning_outcome = Some(winning_outcome);
+    market.record_resolution(&book, config)?;
+
+    emit!(MarketResolved {
+        market: market.key(),
+        winning_outcome,
+        total_pool: book.total_pool,
+        used_randomness: false,
+        settlement_hash: market.settlement_hash,
+        timestamp: now,
+    });
+    Ok(())
+}
+
+impl<'info> SetMarketArbiter<'info> {
+    /// The arbiter may pass the role on at any time; the host may only swap it out while no
+    /// bettor has relied on the current choice
+    pub fn set_market_arbiter(&mut self, new_arbiter: Pubkey) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        let ResolutionSource::Arbiter(previous_arbiter) = self.market_config.resolution_source else {
+            return err!(StreamError::InvalidMarketSetup);
+        };
+        let authority = self.authority.key();
+        if authority != previous_arbiter {
+            require_keys_eq!(authority, self.betting_market.host, StreamError::Unauthorized);
+            require!(self.market_book.load()?.total_pool == 0, StreamError::MarketConfigLocked);
+        }
+        require!(
+            new_arbiter != Pubkey::default() && new_arbiter != self.betting_market.host,
+            StreamError::InvalidMarketSetup
+        );
+
+        self.market_config.resolution_source = ResolutionSource::Arbiter(new_arbiter);
+
+        emit!(MarketArbiterChanged {
+            market: self.betting_market.key(),
+            previous_arbiter,
+            new_arbiter,
+            changed_by: authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> ConfigureMarket<'info> {
+    pub fn set_market_taper(&mut self, taper: Option<BetTaper>) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.market_book.load()?.total_pool == 0,
+            StreamError::MarketConfigLocked
+        );
+        if let Some(taper) = &taper {
+            require!(
+                taper.window_seconds > 0
+                    && taper.max_bet_at_close > 0
+                    && taper.max_bet_at_close <= taper.max_bet_at_window_start
+                    && taper.max_extra_fee_bps <= 1000,
+                StreamError::InvalidTaper
+            );
         }
 
-        require!(has_winning_position, StreamError::NoWinnings);
-        require!(payout > 0, StreamError::NoWinnings);
+        self.betting_market.taper = taper;
+
+        emit!(MarketTaperSet {
+            market: self.betting_market.key(),
+            taper,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Moves the resolution time for events that run long. Once anyone has bet it can only move
+    /// later, so nobody's bet ends up outside the window they bet into. A time that has already
+    /// passed stays put, since betting closed there and resolution may have started.
+    pub fn update_resolution_time(&mut self, new_time: i64) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < self.betting_market.resolution_time, StreamError::BettingClosed);
+        let previous_time = self.betting_market.resolution_time;
+        require!(new_time > now, StreamError::InvalidTime);
+        require!(
+            self.market_book.load()?.total_pool == 0 || new_time > previous_time,
+            StreamError::ResolutionTimeShortened
+        );
+
+        self.betting_market.resolution_time = new_time;
+
+        emit!(ResolutionTimeUpdated {
+            market: self.betting_market.key(),
+            previous_time,
+            new_time,
+            timestamp: now,
+        });
+        notify(
+            NotificationCode::ResolutionRescheduled,
+            self.betting_market.key(),
+            [previous_time as u64, new_time as u64],
+            now,
+        );
+        Ok(())
+    }
+
+    /// Stops new bets and sells ahead of the resolution time, e.g. once the match kicks off.
+    /// Resolution still waits for the resolution time.
+    pub fn close_betting(&mut self) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.betting_market.betting_open(now), StreamError::BettingClosed);
+
+        self.betting_market.betting_closed_at = Some(now);
+
+        emit!(BettingClosedEarly {
+            market: self.betting_market.key(),
+            closed_at: now,
+            resolution_time: self.betting_market.resolution_time,
+        });
+        Ok(())
+    }
+
+    /// Caps what one bettor may have staked and how large the pool may grow; `None` lifts a cap
+    pub fn set_market_limits(&mut self, max_bet_per_user: Option<u64>, max_total_pool: Option<u64>) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.market_book.load()?.total_pool == 0,
+            StreamError::MarketConfigLocked
+        );
+        require!(
+            max_bet_per_user != Some(0) && max_total_pool != Some(0),
+            StreamError::InvalidAmount
+        );
+
+        self.betting_market.max_bet_per_user = max_bet_per_user;
+        self.betting_market.max_total_pool = max_total_pool;
+
+        emit!(MarketLimitsSet {
+            market: self.betting_market.key(),
+            max_bet_per_user,
+            max_total_pool,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn set_market_rules(&mut self, rules: MarketRules) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.market_book.load()?.total_pool == 0,
+            StreamError::MarketConfigLocked
+        );
+        require!(
+            !rules.uri.is_empty() && rules.uri.len() <= MAX_RULES_URI_LEN,
+            StreamError::InvalidMarketRules
+        );
+
+        self.market_config.rules_uri = rules.uri;
+        self.market_config.rules_hash = rules.hash;
+
+        emit!(MarketRulesSet {
+            market: self.betting_market.key(),
+            rules_uri: self.market_config.rules_uri.clone(),
+            rules_hash: self.market_config.rules_hash,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Hands over/under reporting to a data feed instead of the host
+    pub fn set_metric_source(&mut self, source: Pubkey) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.market_book.load()?.total_pool == 0,
+            StreamError::MarketConfigLocked
+        );
+        require!(
+            self.market_config.market_type.outcome_for_value(0).is_some(),
+            StreamError::InvalidMarketSetup
+        );
+
+        self.market_config.metric_source = source;
+
+        emit!(MetricSourceSet {
+            market: self.betting_market.key(),
+            source,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Share of the host's fee on each claim paid to the claiming bettor's referrer
+    pub fn set_bet_referral_share(&mut self, referral_share_bps: u16) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.market_book.load()?.total_pool == 0,
+            StreamError::MarketConfigLocked
+        );
+        require!(
+            referral_share_bps <= MAX_BET_REFERRAL_SHARE_BPS,
+            StreamError::InvalidFeePercentage
+        );
+
+        self.market_config.referral_share_bps = referral_share_bps;
+
+        emit!(BetReferralShareSet {
+            market: self.betting_market.key(),
+            referral_share_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// How long after the resolution time validators get before `force_resolve_timeout` may
+    /// hand the market to randomness; zero restores the default
+    pub fn set_resolution_timeout(&mut self, resolution_timeout: i64) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.market_book.load()?.total_pool == 0,
+            StreamError::MarketConfigLocked
+        );
+        require!(resolution_timeout >= 0, StreamError::InvalidDuration);
+
+        self.market_config.resolution_timeout = resolution_timeout;
+
+        emit!(ResolutionTimeoutSet {
+            market: self.betting_market.key(),
+            resolution_timeout,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> ExpandMarket<'info> {
+    /// Appends `new_outcomes` to a multi-outcome market
+    pub fn expand_market(&mut self, new_outcomes: Vec<String>) -> Result<()> {
+        require!(!new_outcomes.is_empty(), StreamError::InvalidMarketSetup);
+        let mut outcomes = self.market_config.outcome_descriptions.clone();
+        outcomes.extend(new_outcomes.iter().cloned());
+        self.set_outcomes(outcomes)?;
+
+        emit!(MarketOutcomesAdded {
+            market: self.betting_market.key(),
+            outcomes: new_outcomes,
+            outcome_count: self.betting_market.outcome_count,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Appends one late-confirmed outcome
+    pub fn add_market_outcome(&mut self, description: String) -> Result<()> {
+        self.expand_market(vec![description])
+    }
+
+    /// Drops `outcome_id`; the outcomes after it move down one id
+    pub fn remove_market_outcome(&mut self, outcome_id: u8) -> Result<()> {
+        require!(
+            outcome_id < self.betting_market.outcome_count,
+            StreamError::InvalidOutcome
+        );
+        let mut outcomes = self.market_config.outcome_descriptions.clone();
+        let description = outcomes.remove(outcome_id as usize);
+        self.set_outcomes(outcomes)?;
+
+        emit!(MarketOutcomeRemoved {
+            market: self.betting_market.key(),
+            outcome_id,
+            description,
+            outcome_count: self.betting_market.outcome_count,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Replaces the outcome list and splits the market's liquidity evenly across it again.
+    /// Only allowed while nothing is staked, so no position refers to an outcome id yet.
+    fn set_outcomes(&mut self, outcomes: Vec<String>) -> Result<()> {
+        let market = &mut self.betting_market;
+        require!(!market.resolved && !market.cancelled, StreamError::MarketResolved);
+        require!(market.lp_total_shares == 0, StreamError::MarketConfigLocked);
+        let MarketType::MultiOutcome { max } = self.market_config.market_type else {
+            return err!(StreamError::InvalidMarketSetup);
+        };
+
+        let mut book = self.market_book.load_mut()?;
+        require!(book.total_pool == 0, StreamError::MarketConfigLocked);
+        require!(
+            outcomes.len() >= 2
+                && outcomes.len() <= max as usize
+                && outcomes.len() <= MAX_MARKET_OUTCOMES
+                && outcomes.iter().all(|desc| desc.len() <= MAX_OUTCOME_DESCRIPTION_LEN),
+            StreamError::InvalidMarketSetup
+        );
+        if let ResolutionPayoutPlan::Weighted { weights_bps } = &self.market_config.payout_plan {
+            require!(weights_bps.len() <= outcomes.len(), StreamError::InvalidPayoutPlan);
+        }
+
+        let liquidity_per_outcome =
+            liquidity_per_outcome(&market.amm_curve, self.market_config.total_liquidity, outcomes.len());
+        for (id, outcome) in book.outcomes.iter_mut().enumerate() {
+            outcome.liquidity_reserve = if id < outcomes.len() { liquidity_per_outcome } else { 0 };
+        }
+        book.outcome_count = outcomes.len() as u8;
+        market.outcome_count = outcomes.len() as u8;
+        self.market_config.outcome_descriptions = outcomes;
+        Ok(())
+    }
+}
+
+/// Starting reserve of each of `outcome_count` outcomes when the host seeds `total_liquidity`.
+/// Parimutuel markets price nothing off their reserves, so they start empty.
+fn liquidity_per_outcome(curve: &AmmCurve, total_liquidity: u64, outcome_count: usize) -> u64 {
+    if *curve == AmmCurve::Parimutuel {
+        0
+    } else if total_liquidity > 0 {
+        total_liquidity / outcome_count as u64
+    } else {
+        DEFAULT_LIQUIDITY_PER_OUTCOME
+    }
+}
+
+impl<'info> ClaimWinnings<'info> {
+    /// The leading remaining accounts are the bettor's outcome positions in the paying outcomes,
+    /// see `paying_positions`
+    pub fn claim_winnings(&mut self, bumps: &ClaimWinningsBumps, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let (payout, remaining_accounts) = WinningsClaim {
+            betting_market: &mut self.betting_market,
+            market_book: &self.market_book,
+            market_config: &self.market_config,
+            bettor_position: &mut self.bettor_position,
+            bettor_profile: &mut self.bettor_profile,
+            referral_earnings: self.referral_earnings.as_mut(),
+            referral_earnings_vault: self.referral_earnings_vault.as_ref(),
+            market_stats: &self.market_stats,
+            market_vault: &self.market_vault,
+            mint: &self.mint,
+            token_program: &self.token_program,
+        }.settle(self.bettor.key(), bumps.bettor_profile, remaining_accounts)?;
+        FeatureGates::require_not_queued(&self.feature_gates, payout)?;
 
         msg!("Claiming {} USDC in winnings", payout);
 
@@ -773,18 +2569,33 @@ impl<'info> ClaimWinnings<'info> {
         ];
         let signer = &[&market_seeds[..]];
 
-        let cpi_accounts = Transfer {
+        let fee = PlatformFeeRoute {
+            platform_config: &self.platform_config,
+            treasury_ata: self.treasury_ata.as_ref(),
+            host_profile: self.host_profile.as_ref(),
+            referral_rewards: self.referral_rewards.as_mut(),
+            referral_vault: self.referral_vault.as_ref(),
+        }.collect(
+            self.betting_market.host,
+            &self.market_vault,
+            &self.mint,
+            self.betting_market.to_account_info(),
+            signer,
+            &self.token_program,
+            remaining_accounts,
+            payout,
+        )?;
+
+        let cpi_accounts = TransferChecked {
             from: self.market_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
             to: self.bettor_token.to_account_info(),
             authority: self.betting_market.to_account_info(),
         };
         let cpi_ctx =
-            CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer);
-        token_transfer(cpi_ctx, payout)?;
-
-        // Update bettor position
-        self.bettor_position.has_claimed = true;
-        self.bettor_position.total_returned = payout;
+            CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, payout - fee, self.mint.decimals)?;
 
         emit!(WinningsClaimed {
             market: self.betting_market.key(),
@@ -796,3 +2607,141 @@ impl<'info> ClaimWinnings<'info> {
         Ok(())
     }
 }
+
+impl<'info> CloseBettorPosition<'info> {
+    /// The leading remaining accounts are the bettor's outcome positions in the paying
+    /// outcomes, see `paying_positions`
+    pub fn close_bettor_position(
+        &mut self,
+        bumps: &CloseBettorPositionBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(self.betting_market.resolved, StreamError::MarketNotResolved);
+        // Positions on a cancelled market close through claim_refund so the stake goes back
+        require!(!self.betting_market.cancelled, StreamError::MarketCancelled);
+
+        let (positions, _) =
+            paying_positions(&self.betting_market, &self.market_config, &self.bettor.key(), remaining_accounts)?;
+        let book = self.market_book.load()?;
+        // So do positions on a market nobody won
+        require!(
+            !self.betting_market.no_winning_shares(&book, &self.market_config),
+            StreamError::StakeRefundRequired
+        );
+        require!(
+            self.betting_market.winnings_for(&book, &self.market_config, &positions.held)? == 0,
+            StreamError::WinningsUnclaimed
+        );
+        // Shares that round to nothing still count toward settling the market's dust
+        self.betting_market.settle_position(&book, &self.market_config, &positions.held)?;
+
+        // Claimed positions were settled into the profile at claim time
+        if !self.bettor_position.has_claimed {
+            self.bettor_profile.record_market(
+                self.bettor.key(),
+                self.betting_market.key(),
+                self.bettor_position.total_invested,
+                0,
+                bumps.bettor_profile,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl<'info> CloseOutcomePosition<'info> {
+    pub fn close_outcome_position(&mut self) -> Result<()> {
+        require!(self.betting_market.resolved, StreamError::MarketNotResolved);
+        // Settlement reads the outcome positions, so they outlive the position they belong to
+        require!(self.bettor_position.data_is_empty(), StreamError::PositionNotClosed);
+        Ok(())
+    }
+}
+
+impl<'info> CollectMarketFees<'info> {
+    pub fn collect_market_fees(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(self.betting_market.resolved, StreamError::MarketNotResolved);
+        let amount = self.betting_market.fees_collected;
+        require!(amount > 0, StreamError::NothingToSweep);
+        self.betting_market.fees_collected = 0;
+
+        let market_seeds = &[
+            MARKET_SEED,
+            self.betting_market.stream.as_ref(),
+            &[self.betting_market.bump],
+        ];
+        let signer = &[&market_seeds[..]];
+
+        let platform_fee = PlatformFeeRoute {
+            platform_config: &self.platform_config,
+            treasury_ata: self.treasury_ata.as_ref(),
+            host_profile: self.host_profile.as_ref(),
+            referral_rewards: self.referral_rewards.as_mut(),
+            referral_vault: self.referral_vault.as_ref(),
+        }.collect(
+            self.betting_market.host,
+            &self.market_vault,
+            &self.mint,
+            self.betting_market.to_account_info(),
+            signer,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
+        let host_amount = amount - platform_fee;
+
+        if host_amount > 0 {
+            let cpi_accounts = TransferChecked {
+                from: self.market_vault.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: self.host_token.to_account_info(),
+                authority: self.betting_market.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+            transfer_checked(cpi_ctx, host_amount, self.mint.decimals)?;
+        }
+
+        emit!(MarketFeesCollected {
+            market: self.betting_market.key(),
+            host_amount,
+            platform_fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> SweepMarketDust<'info> {
+    pub fn sweep_market_dust(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let amount = self.betting_market.dust_accumulator;
+        require!(amount > 0, StreamError::NothingToSweep);
+        self.betting_market.dust_accumulator = 0;
+
+        let market_seeds = &[
+            MARKET_SEED,
+            self.betting_market.stream.as_ref(),
+            &[self.betting_market.bump],
+        ];
+        let signer = &[&market_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.market_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.host_token.to_account_info(),
+            authority: self.betting_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        emit!(MarketDustSwept {
+            market: self.betting_market.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
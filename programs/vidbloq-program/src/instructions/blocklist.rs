@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{
+    StreamState, StreamError, Blocklist, WalletBlocked, WalletUnblocked, BLOCKLIST_PAGE_CAPACITY
+};
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct BlockWallet<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = host,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        space = Blocklist::INIT_SPACE,
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&wallet)]],
+        bump
+    )]
+    pub blocklist: Account<'info, Blocklist>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> BlockWallet<'info> {
+    pub fn block_wallet(&mut self, wallet: Pubkey, bumps: &BlockWalletBumps) -> Result<()> {
+        let page = Blocklist::page_for(&wallet);
+        if self.blocklist.stream == Pubkey::default() {
+            self.blocklist.set_inner(Blocklist {
+                stream: self.stream.key(),
+                page,
+                wallets: Vec::new(),
+                bump: bumps.blocklist,
+            });
+        }
+
+        require!(
+            !self.blocklist.wallets.contains(&wallet),
+            StreamError::WalletAlreadyBlocked
+        );
+        require!(
+            self.blocklist.wallets.len() < BLOCKLIST_PAGE_CAPACITY,
+            StreamError::BlocklistFull
+        );
+
+        self.blocklist.wallets.push(wallet);
+        self.stream.blocked_count = self.stream.blocked_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+        emit!(WalletBlocked {
+            stream: self.stream.key(),
+            wallet,
+            page,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct UnblockWallet<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = host,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&wallet)]],
+        bump = blocklist.bump
+    )]
+    pub blocklist: Account<'info, Blocklist>,
+}
+
+impl<'info> UnblockWallet<'info> {
+    pub fn unblock_wallet(&mut self, wallet: Pubkey) -> Result<()> {
+        let index = self
+            .blocklist
+            .wallets
+            .iter()
+            .position(|w| *w == wallet)
+            .ok_or(StreamError::WalletNotBlocked)?;
+
+        self.blocklist.wallets.swap_remove(index);
+        self.stream.blocked_count = self.stream.blocked_count.checked_sub(1).ok_or(StreamError::MathOverflow)?;
+
+        emit!(WalletUnblocked {
+            stream: self.stream.key(),
+            wallet,
+            page: self.blocklist.page,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,458 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+use ephemeral_vrf_sdk::anchor::vrf;
+use ephemeral_vrf_sdk::instructions::{create_request_randomness_ix, RequestRandomnessParams};
+use ephemeral_vrf_sdk::types::SerializableAccountMeta;
+
+use crate::instructions::amount_received;
+use crate::state::{
+    BonusClaimed, BonusPool, BonusPoolCreated, BonusPoolDrawn, BonusPoolEntered, BonusPoolEntry,
+    BonusPoolReclaimed, BonusPoolStatus, DonorAccount, StreamError, StreamState, BONUS_CLAIM_WINDOW,
+    MAX_BONUS_WINNERS, RANDOMNESS_REQUEST_TIMEOUT,
+};
+
+// ============= CONSTANTS =============
+pub const BONUS_POOL_SEED: &[u8] = b"bonus_pool";
+pub const BONUS_POOL_VAULT_SEED: &[u8] = b"bonus_pool_vault";
+pub const BONUS_POOL_ENTRY_SEED: &[u8] = b"bonus_pool_entry";
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Host funds a bonus pot for the stream's donors
+#[derive(Accounts)]
+#[instruction(pool_id: u64)]
+pub struct CreateBonusPool<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = host_token.owner == host.key(),
+        constraint = host_token.mint == mint.key(),
+    )]
+    pub host_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = host,
+        space = BonusPool::INIT_SPACE,
+        seeds = [BONUS_POOL_SEED, stream.key().as_ref(), pool_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bonus_pool: Account<'info, BonusPool>,
+
+    #[account(
+        init,
+        payer = host,
+        seeds = [BONUS_POOL_VAULT_SEED, bonus_pool.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = bonus_pool,
+        token::token_program = token_program,
+    )]
+    pub pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Donor enters with their contribution to the stream as weight
+#[derive(Accounts)]
+pub struct EnterBonusPool<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONUS_POOL_SEED, bonus_pool.stream.as_ref(), bonus_pool.pool_id.to_le_bytes().as_ref()],
+        bump = bonus_pool.bump,
+    )]
+    pub bonus_pool: Account<'info, BonusPool>,
+
+    #[account(
+        seeds = [b"donor", bonus_pool.stream.as_ref(), donor.key().as_ref()],
+        bump = donor_account.bump,
+    )]
+    pub donor_account: Account<'info, DonorAccount>,
+
+    #[account(
+        init,
+        payer = donor,
+        space = BonusPoolEntry::INIT_SPACE,
+        seeds = [BONUS_POOL_ENTRY_SEED, bonus_pool.key().as_ref(), donor.key().as_ref()],
+        bump
+    )]
+    pub entry: Account<'info, BonusPoolEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Anyone asks Ephemeral VRF for the winning points once entries have closed
+#[vrf]
+#[derive(Accounts)]
+pub struct DrawBonusPool<'info> {
+    #[account(mut)]
+    pub requestor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONUS_POOL_SEED, bonus_pool.stream.as_ref(), bonus_pool.pool_id.to_le_bytes().as_ref()],
+        bump = bonus_pool.bump,
+    )]
+    pub bonus_pool: Account<'info, BonusPool>,
+
+    /// CHECK: The oracle queue from Ephemeral VRF
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Callback from Ephemeral VRF with the randomness for a bonus pool draw
+#[derive(Accounts)]
+pub struct CallbackDrawBonusPool<'info> {
+    /// CHECK: Must be Ephemeral VRF program identity
+    #[account(address = ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY)]
+    pub vrf_program_identity: Signer<'info>,
+
+    #[account(mut)]
+    pub bonus_pool: Account<'info, BonusPool>,
+}
+
+/// Donor collects the picks that landed in their range
+#[derive(Accounts)]
+pub struct ClaimBonus<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONUS_POOL_SEED, bonus_pool.stream.as_ref(), bonus_pool.pool_id.to_le_bytes().as_ref()],
+        bump = bonus_pool.bump,
+        has_one = mint,
+    )]
+    pub bonus_pool: Account<'info, BonusPool>,
+
+    #[account(
+        mut,
+        seeds = [BONUS_POOL_ENTRY_SEED, bonus_pool.key().as_ref(), donor.key().as_ref()],
+        bump = entry.bump,
+    )]
+    pub entry: Account<'info, BonusPoolEntry>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [BONUS_POOL_VAULT_SEED, bonus_pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = donor,
+        associated_token::mint = mint,
+        associated_token::authority = donor,
+        associated_token::token_program = token_program
+    )]
+    pub donor_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Host takes back a pot nobody entered, or whatever winners left unclaimed
+#[derive(Accounts)]
+pub struct ReclaimBonusPool<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [BONUS_POOL_SEED, bonus_pool.stream.as_ref(), bonus_pool.pool_id.to_le_bytes().as_ref()],
+        bump = bonus_pool.bump,
+        has_one = host @ StreamError::Unauthorized,
+        has_one = mint,
+    )]
+    pub bonus_pool: Account<'info, BonusPool>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [BONUS_POOL_VAULT_SEED, bonus_pool.key().as_ref()],
+        bump,
+    )]
+    pub pool_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = host_token.owner == host.key(),
+        constraint = host_token.mint == mint.key(),
+    )]
+    pub host_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> CreateBonusPool<'info> {
+    pub fn create_bonus_pool(
+        &mut self,
+        pool_id: u64,
+        amount: u64,
+        winner_count: u8,
+        entry_deadline: i64,
+        bumps: &CreateBonusPoolBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            winner_count > 0 && winner_count <= MAX_BONUS_WINNERS,
+            StreamError::InvalidBonusWinnerCount
+        );
+        require!(amount >= winner_count as u64, StreamError::InvalidAmount);
+        require!(entry_deadline > now, StreamError::InvalidTime);
+
+        let cpi_accounts = TransferChecked {
+            from: self.host_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.pool_vault.to_account_info(),
+            authority: self.host.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let amount = amount_received(&mut self.pool_vault, 0)?;
+
+        self.bonus_pool.set_inner(BonusPool {
+            stream: self.stream.key(),
+            host: self.host.key(),
+            mint: self.mint.key(),
+            pool_id,
+            amount,
+            winner_count,
+            entry_deadline,
+            total_weight: 0,
+            entrant_count: 0,
+            status: BonusPoolStatus::Open,
+            draw_requested_at: 0,
+            randomness: [0; 32],
+            drawn_at: 0,
+            paid: 0,
+            created_at: now,
+            bump: bumps.bonus_pool,
+        });
+
+        emit!(BonusPoolCreated {
+            pool: self.bonus_pool.key(),
+            stream: self.stream.key(),
+            amount,
+            winner_count,
+            entry_deadline,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> EnterBonusPool<'info> {
+    /// The weight is the donor's contribution at entry; later deposits do not add to it
+    pub fn enter_bonus_pool(&mut self, bumps: &EnterBonusPoolBumps) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut self.bonus_pool;
+        require!(
+            pool.status == BonusPoolStatus::Open && now < pool.entry_deadline,
+            StreamError::BonusPoolClosed
+        );
+        let donor_account = &self.donor_account;
+        require!(
+            !donor_account.refunded && donor_account.amount > 0,
+            StreamError::InvalidAmount
+        );
+
+        let weight = donor_account.amount;
+        let weight_start = pool.total_weight;
+        pool.total_weight = weight_start.checked_add(weight).ok_or(StreamError::MathOverflow)?;
+        pool.entrant_count = pool.entrant_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+        self.entry.set_inner(BonusPoolEntry {
+            pool: pool.key(),
+            donor: self.donor.key(),
+            weight_start,
+            weight,
+            claimed: false,
+            bump: bumps.entry,
+        });
+
+        emit!(BonusPoolEntered {
+            pool: pool.key(),
+            donor: self.donor.key(),
+            weight,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> DrawBonusPool<'info> {
+    /// This is the `RandomnessUseCase::FairDistribution` path. A draw whose callback has not
+    /// arrived within `RANDOMNESS_REQUEST_TIMEOUT` may be requested again.
+    pub fn draw_bonus_pool(&mut self, client_seed: [u8; 32]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &mut self.bonus_pool;
+        require!(now >= pool.entry_deadline, StreamError::BonusPoolStillOpen);
+        require!(pool.total_weight > 0, StreamError::NoBonusPoolEntries);
+        match pool.status {
+            BonusPoolStatus::Open => {}
+            BonusPoolStatus::Drawing => require!(
+                now >= pool.draw_requested_at.saturating_add(RANDOMNESS_REQUEST_TIMEOUT),
+                StreamError::RandomnessRequestPending
+            ),
+            _ => return err!(StreamError::BonusPoolClosed),
+        }
+        pool.status = BonusPoolStatus::Drawing;
+        pool.draw_requested_at = now;
+
+        let ix = create_request_randomness_ix(RequestRandomnessParams {
+            payer: self.requestor.key(),
+            oracle_queue: self.oracle_queue.key(),
+            callback_program_id: crate::ID,
+            callback_discriminator: crate::instruction::CallbackDrawBonusPool::DISCRIMINATOR.to_vec(),
+            caller_seed: client_seed,
+            accounts_metas: Some(vec![SerializableAccountMeta {
+                pubkey: self.bonus_pool.key(),
+                is_signer: false,
+                is_writable: true,
+            }]),
+        });
+        self.invoke_signed_vrf(&self.requestor.to_account_info(), &ix)?;
+        Ok(())
+    }
+}
+
+impl<'info> CallbackDrawBonusPool<'info> {
+    pub fn draw_bonus_pool(&mut self, randomness: [u8; 32]) -> Result<()> {
+        let pool = &mut self.bonus_pool;
+        require!(pool.status == BonusPoolStatus::Drawing, StreamError::NoPendingRandomnessRequest);
+
+        let now = Clock::get()?.unix_timestamp;
+        pool.randomness = randomness;
+        pool.drawn_at = now;
+        pool.status = BonusPoolStatus::Drawn;
+
+        emit!(BonusPoolDrawn {
+            pool: pool.key(),
+            total_weight: pool.total_weight,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> ClaimBonus<'info> {
+    pub fn claim_bonus(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(self.bonus_pool.status == BonusPoolStatus::Drawn, StreamError::BonusPoolNotDrawn);
+        require!(!self.entry.claimed, StreamError::AlreadyClaimed);
+
+        let picks = self.bonus_pool.picks_won(self.entry.weight_start, self.entry.weight);
+        require!(picks > 0, StreamError::NotBonusWinner);
+        let amount = self
+            .bonus_pool
+            .share_per_pick()
+            .checked_mul(picks as u64)
+            .ok_or(StreamError::MathOverflow)?;
+
+        pay_from_pool_vault(
+            &self.bonus_pool,
+            &self.pool_vault,
+            &self.mint,
+            &self.donor_ata,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
+        self.entry.claimed = true;
+        self.bonus_pool.paid = self.bonus_pool.paid.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+
+        emit!(BonusClaimed {
+            pool: self.bonus_pool.key(),
+            donor: self.donor.key(),
+            picks,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> ReclaimBonusPool<'info> {
+    /// Open once entries close with nobody entered, or `BONUS_CLAIM_WINDOW` after the draw
+    pub fn reclaim_bonus_pool(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let pool = &self.bonus_pool;
+        let reclaimable = match pool.status {
+            BonusPoolStatus::Open => now >= pool.entry_deadline && pool.total_weight == 0,
+            BonusPoolStatus::Drawn => now >= pool.drawn_at.saturating_add(BONUS_CLAIM_WINDOW),
+            _ => false,
+        };
+        require!(reclaimable, StreamError::BonusPoolStillOpen);
+
+        let amount = self.pool_vault.amount;
+        pay_from_pool_vault(
+            &self.bonus_pool,
+            &self.pool_vault,
+            &self.mint,
+            &self.host_token,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
+        self.bonus_pool.status = BonusPoolStatus::Reclaimed;
+
+        emit!(BonusPoolReclaimed {
+            pool: self.bonus_pool.key(),
+            amount,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+fn pay_from_pool_vault<'info>(
+    pool: &Account<'info, BonusPool>,
+    pool_vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    let id_bytes = pool.pool_id.to_le_bytes();
+    let pool_seeds = &[BONUS_POOL_SEED, pool.stream.as_ref(), id_bytes.as_ref(), &[pool.bump]];
+    let signer = &[&pool_seeds[..]];
+    let cpi_accounts = TransferChecked {
+        from: pool_vault.to_account_info(),
+        mint: mint.to_account_info(),
+        to: to.to_account_info(),
+        authority: pool.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+        .with_remaining_accounts(remaining_accounts.to_vec());
+    transfer_checked(cpi_ctx, amount, mint.decimals)
+}
@@ -0,0 +1,254 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        approve_checked, revoke, transfer_checked, ApproveChecked, Mint, Revoke, TokenAccount,
+        TokenInterface, TransferChecked,
+    },
+};
+
+use crate::instructions::{amount_received, PAYOUT_RECIPIENT_SEED};
+use crate::state::{
+    StreamState, StreamError, PayoutRecipient, ClawbackAgreement, ClawbackConsentGiven,
+    ClawbackExecuted, ClawbackConsentRevoked, ClawbackWindowOpened, MAX_CLAWBACK_WINDOW
+};
+
+pub const CLAWBACK_AGREEMENT_SEED: &[u8] = b"clawback_agreement";
+
+/// A registered payout recipient agrees that payouts may be pulled back for `window` seconds,
+/// approving the agreement PDA as delegate on their token account for up to `max_amount`
+#[derive(Accounts)]
+pub struct ConsentToClawback<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    #[account(
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        seeds = [PAYOUT_RECIPIENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump = payout_recipient.bump
+    )]
+    pub payout_recipient: Account<'info, PayoutRecipient>,
+
+    #[account(
+        init,
+        payer = recipient,
+        space = ClawbackAgreement::INIT_SPACE,
+        seeds = [CLAWBACK_AGREEMENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub clawback_agreement: Account<'info, ClawbackAgreement>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Host pulls a mistaken payout back into the stream vault while its window is open
+#[derive(Accounts)]
+pub struct InitiateClawback<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [CLAWBACK_AGREEMENT_SEED, stream.key().as_ref(), clawback_agreement.recipient.as_ref()],
+        bump = clawback_agreement.bump,
+        has_one = stream,
+        has_one = recipient_ata
+    )]
+    pub clawback_agreement: Account<'info, ClawbackAgreement>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub recipient_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Recipient withdraws consent once no payout is inside the window, closing the agreement
+/// and revoking the delegate
+#[derive(Accounts)]
+pub struct RevokeClawbackConsent<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    #[account(
+        mut,
+        close = recipient,
+        seeds = [CLAWBACK_AGREEMENT_SEED, clawback_agreement.stream.as_ref(), recipient.key().as_ref()],
+        bump = clawback_agreement.bump,
+        has_one = recipient,
+        has_one = recipient_ata
+    )]
+    pub clawback_agreement: Account<'info, ClawbackAgreement>,
+
+    #[account(mut)]
+    pub recipient_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ConsentToClawback<'info> {
+    pub fn consent_to_clawback(&mut self, window: i64, max_amount: u64, bumps: &ConsentToClawbackBumps) -> Result<()> {
+        require!(window > 0 && window <= MAX_CLAWBACK_WINDOW, StreamError::InvalidClawbackWindow);
+        require!(max_amount > 0, StreamError::InvalidAmount);
+
+        self.clawback_agreement.set_inner(ClawbackAgreement {
+            stream: self.stream.key(),
+            recipient: self.recipient.key(),
+            recipient_ata: self.recipient_ata.key(),
+            window,
+            clawbackable: 0,
+            window_ends_at: 0,
+            clawed_back: 0,
+            bump: bumps.clawback_agreement,
+        });
+
+        let cpi_accounts = ApproveChecked {
+            to: self.recipient_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            delegate: self.clawback_agreement.to_account_info(),
+            authority: self.recipient.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        approve_checked(cpi_ctx, max_amount, self.mint.decimals)?;
+
+        emit!(ClawbackConsentGiven {
+            stream: self.stream.key(),
+            recipient: self.recipient.key(),
+            window,
+            max_amount,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+impl<'info> InitiateClawback<'info> {
+    /// Fails if the recipient has since moved the funds or replaced the delegate approval
+    pub fn initiate_clawback(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        self.clawback_agreement.claw_back(amount, now)?;
+
+        let stream_key = self.stream.key();
+        let recipient_key = self.clawback_agreement.recipient;
+        let agreement_seeds = &[
+            CLAWBACK_AGREEMENT_SEED,
+            stream_key.as_ref(),
+            recipient_key.as_ref(),
+            &[self.clawback_agreement.bump],
+        ];
+        let signer = &[&agreement_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.recipient_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.stream_ata.to_account_info(),
+            authority: self.clawback_agreement.to_account_info(),
+        };
+
+        // Remaining accounts carry any transfer-hook extra accounts for the mint
+        let vault_before = self.stream_ata.amount;
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let received = amount_received(&mut self.stream_ata, vault_before)?;
+
+        // The returned tokens are available to the stream again
+        self.stream.total_distributed = self.stream.total_distributed
+            .checked_sub(received)
+            .ok_or(StreamError::MathOverflow)?;
+
+        emit!(ClawbackExecuted {
+            stream: stream_key,
+            recipient: recipient_key,
+            amount,
+            received,
+            clawbackable: self.clawback_agreement.clawbackable,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+impl<'info> RevokeClawbackConsent<'info> {
+    pub fn revoke_clawback_consent(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let agreement = &self.clawback_agreement;
+        require!(
+            agreement.clawbackable == 0 || now > agreement.window_ends_at,
+            StreamError::ClawbackWindowOpen
+        );
+
+        let cpi_accounts = Revoke {
+            source: self.recipient_ata.to_account_info(),
+            authority: self.recipient.to_account_info(),
+        };
+        revoke(CpiContext::new(self.token_program.to_account_info(), cpi_accounts))?;
+
+        emit!(ClawbackConsentRevoked {
+            stream: agreement.stream,
+            recipient: agreement.recipient,
+            clawed_back: agreement.clawed_back,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+/// Makes `paid` clawbackable when the recipient has consented, for payouts that take the
+/// agreement PDA as an account that may be uninitialized rather than leaving it to the caller
+pub(crate) fn record_clawbackable_payout(
+    agreement: &AccountInfo,
+    stream: Pubkey,
+    recipient: Pubkey,
+    recipient_ata: &Pubkey,
+    paid: u64,
+    now: i64,
+) -> Result<()> {
+    if let Some(agreement) = ClawbackAgreement::record_consented_payout(agreement, recipient_ata, paid, now)? {
+        emit!(ClawbackWindowOpened {
+            stream,
+            recipient,
+            amount: paid,
+            clawbackable: agreement.clawbackable,
+            window_ends_at: agreement.window_ends_at,
+            timestamp: now
+        });
+    }
+    Ok(())
+}
@@ -1,11 +1,11 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken, 
-    token::{Transfer, transfer as token_transfer},
-    token_interface::{TokenAccount, TokenInterface}
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
 };
 
-use crate::state::{StreamState, StreamError, DonorAccount, StreamType, StreamStatus, DepositMade};
+use crate::instructions::{amount_received, enforce_access_gate, ACCESS_GATE_SEED, GLOBAL_REGISTRY_SEED};
+use crate::state::{StreamState, StreamError, DonorAccount, DonationReceipt, MatchingPledge, Leaderboard, Blocklist, AccessGate, DepositMade, AnonymousDepositMade, MilestoneReached, StreamStatus, NotificationCode, notify, MAX_DONATION_MESSAGE_LEN, REFUND_WINDOW_CLOSING_SOON, GlobalRegistry};
 
 #[derive(Accounts)]
 pub struct Deposit <'info> {
@@ -14,11 +14,14 @@ pub struct Deposit <'info> {
 
      #[account(
         mut, 
-        seeds=[b"stream", stream.stream_name.as_str().as_bytes(), stream.host.key().as_ref()],
-        bump=stream.bump
+        seeds=[b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump=stream.bump,
+        has_one = mint
      )]
     pub stream: Account<'info, StreamState>,
 
+    pub mint: InterfaceAccount<'info, Mint>,
+
     #[account(
         init_if_needed,
         payer = donor,
@@ -28,6 +31,15 @@ pub struct Deposit <'info> {
     )]
     pub donor_account: Account<'info, DonorAccount>,
 
+    #[account(
+        init,
+        payer = donor,
+        space = DonationReceipt::INIT_SPACE,
+        seeds = [b"receipt", stream.key().as_ref(), stream.receipt_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, DonationReceipt>,
+
     #[account(
         mut,
         constraint = donor_ata.owner == donor.key(),
@@ -42,65 +54,379 @@ pub struct Deposit <'info> {
     )]
     pub stream_ata: InterfaceAccount<'info, TokenAccount>,
 
+    /// Required while the stream has an active matching pledge
+    #[account(
+        mut,
+        seeds = [b"matching_pledge", stream.key().as_ref()],
+        bump = matching_pledge.bump
+    )]
+    pub matching_pledge: Option<Account<'info, MatchingPledge>>,
+
+    #[account(
+        mut,
+        seeds = [b"pledge_vault", stream.key().as_ref()],
+        bump
+    )]
+    pub pledge_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Required while the stream maintains a leaderboard
+    #[account(
+        mut,
+        seeds = [b"leaderboard", stream.key().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+
+    /// CHECK: Blocklist page for the donor's bucket, required while the stream has blocked wallets
+    #[account(
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&donor.key())]],
+        bump
+    )]
+    pub blocklist: Option<UncheckedAccount<'info>>,
+
+    /// Required while the stream is access gated
+    #[account(
+        seeds = [ACCESS_GATE_SEED, stream.key().as_ref()],
+        bump = access_gate.bump
+    )]
+    pub access_gate: Option<Account<'info, AccessGate>>,
+
+    /// Donor's token account for the gate mint, required while the stream is access gated
+    pub gate_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub token_program: Interface<'info, TokenInterface>
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
 }
 
 impl <'info> Deposit <'info> {
-    pub fn deposit(&mut self, amount: u64, bumps: &DepositBumps) -> Result<()> {
+    pub fn deposit(&mut self, amount: u64, message: Option<String>, bumps: &DepositBumps, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
         require!(amount > 0, StreamError::InvalidAmount);
-
-        match self.stream.stream_type {
-            StreamType::Prepaid { .. } => {
-                // For prepaid, deposits allowed anytime before start
-                require!(
-                    self.stream.start_time.is_none(),
-                    StreamError::StreamAlreadyStarted
-                );
-            },
-            StreamType::Live => {
-                // For live streams, must be active and started
-                require!(
-                    self.stream.status == StreamStatus::Active && 
-                    self.stream.start_time.is_some(),
-                    StreamError::DepositNotAllowed
-                );
-            },
-            StreamType::Conditional { .. } => {
-                // For conditional, check if stream is active
-                require!(
-                    self.stream.status == StreamStatus::Active,
-                    StreamError::StreamNotActive
-                );
-            }
+        if let Some(text) = &message {
+            require!(text.len() <= MAX_DONATION_MESSAGE_LEN, StreamError::MessageTooLong);
         }
 
+        self.stream.require_deposits_open()?;
+        Blocklist::require_not_blocked(
+            self.stream.blocked_count,
+            self.blocklist.as_ref().map(|b| b.as_ref()),
+            &self.donor.key(),
+        )?;
+        enforce_access_gate(
+            &self.stream,
+            self.access_gate.as_ref(),
+            self.gate_token_account.as_ref(),
+            &self.donor.key(),
+        )?;
+
         let cpi_program = self.token_program.to_account_info();
 
-        let cpi_accounts = Transfer {
+        let cpi_accounts = TransferChecked {
             from: self.donor_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
             to: self.stream_ata.to_account_info(),
             authority: self.donor.to_account_info(),
         };
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
-        token_transfer(cpi_ctx, amount)?;
+        // Remaining accounts carry any transfer-hook extra accounts for the mint
+        let vault_before = self.stream_ata.amount;
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts).with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let amount = amount_received(&mut self.stream_ata, vault_before)?;
 
-        self.donor_account.set_inner(DonorAccount {
+        let now = Clock::get()?.unix_timestamp;
+        self.record_deposit(amount, now, bumps)?;
+
+        self.receipt.set_inner(DonationReceipt {
             stream: self.stream.key(),
             donor: self.donor.key(),
-            amount: self.donor_account.amount.checked_add(amount).ok_or(StreamError::MathOverflow)?,
-            refunded: false,
-            bump: bumps.donor_account,
+            commitment: None,
+            index: self.stream.receipt_count,
+            amount,
+            message,
+            timestamp: now,
+            bump: bumps.receipt,
         });
-        self.stream.total_deposited += self.stream.total_deposited.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        self.stream.receipt_count = self.stream.receipt_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+        let (matched_amount, matching_sponsor) = self.apply_matching(amount, remaining_accounts)?;
+
+        if self.stream.leaderboard_active {
+            let leaderboard = self.leaderboard.as_mut().ok_or(StreamError::MissingAccounts)?;
+            leaderboard.record(self.donor.key(), self.donor_account.amount);
+        }
+
         emit!(DepositMade {
             stream: self.stream.key(),
             donor: self.donor.key(),
             amount,
+            matched_amount,
+            matching_sponsor,
+            timestamp: now
+        });
+
+        emit_milestones(&mut self.stream)?;
+        notify_vault_state(&self.stream, self.stream_ata.amount, now)?;
+        Ok(())
+    }
+}
+
+impl <'info> Deposit <'info> {
+    /// Credits `amount` to the donor record and the stream total exactly once
+    fn record_deposit(&mut self, amount: u64, now: i64, bumps: &DepositBumps) -> Result<()> {
+        self.donor_account.record_deposit(
+            self.stream.key(),
+            self.donor.key(),
+            amount,
+            now,
+            bumps.donor_account,
+        )?;
+        self.stream.total_deposited = self.stream.total_deposited.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_deposit(&self.global_registry, amount)?;
+        Ok(())
+    }
+
+    /// Pulls the sponsor's match for `amount` from the pledge vault into the stream vault
+    fn apply_matching(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<(u64, Option<Pubkey>)> {
+        apply_matching(
+            &mut self.stream,
+            &mut self.donor_account,
+            self.matching_pledge.as_mut(),
+            self.pledge_vault.as_ref(),
+            &self.mint,
+            &mut self.stream_ata,
+            &self.token_program,
+            &self.global_registry,
+            amount,
+            remaining_accounts,
+        )
+    }
+}
+
+/// Pulls the sponsor's match for a deposit of `amount` from the pledge vault into the stream
+/// vault, marking the deposit as matched on the donor's record. Returns what was credited and
+/// the sponsor, if the stream has a matching pledge.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_matching<'info>(
+    stream: &mut Account<'info, StreamState>,
+    donor_account: &mut Account<'info, DonorAccount>,
+    matching_pledge: Option<&mut Account<'info, MatchingPledge>>,
+    pledge_vault: Option<&InterfaceAccount<'info, TokenAccount>>,
+    mint: &InterfaceAccount<'info, Mint>,
+    stream_ata: &mut InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    global_registry: &UncheckedAccount<'info>,
+    amount: u64,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<(u64, Option<Pubkey>)> {
+    if !stream.matching_active {
+        return Ok((0, None));
+    }
+    let (Some(pledge), Some(pledge_vault)) = (matching_pledge, pledge_vault) else {
+        return err!(StreamError::MissingAccounts);
+    };
+
+    let matched = std::cmp::min(pledge.match_for(amount)?, pledge_vault.amount);
+    if matched == 0 {
+        return Ok((0, Some(pledge.sponsor)));
+    }
+
+    let stream_key = stream.key();
+    let pledge_seeds = &[
+        b"matching_pledge".as_ref(),
+        stream_key.as_ref(),
+        &[pledge.bump],
+    ];
+    let signer = &[&pledge_seeds[..]];
+
+    let cpi_accounts = TransferChecked {
+        from: pledge_vault.to_account_info(),
+        mint: mint.to_account_info(),
+        to: stream_ata.to_account_info(),
+        authority: pledge.to_account_info(),
+    };
+    let vault_before = stream_ata.amount;
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+        .with_remaining_accounts(remaining_accounts.to_vec());
+    transfer_checked(cpi_ctx, matched, mint.decimals)?;
+
+    pledge.matched = pledge.matched.checked_add(matched).ok_or(StreamError::MathOverflow)?;
+    let sponsor = pledge.sponsor;
+    let credited = amount_received(stream_ata, vault_before)?;
+    stream.total_deposited = stream.total_deposited.checked_add(credited).ok_or(StreamError::MathOverflow)?;
+    GlobalRegistry::record_deposit(global_registry, credited)?;
+    donor_account.matched_deposits = donor_account
+        .matched_deposits
+        .checked_add(amount)
+        .ok_or(StreamError::MathOverflow)?;
+
+    Ok((credited, Some(sponsor)))
+}
+
+/// Deposit without a `DonorAccount`; the donor forfeits refund rights in exchange for
+/// only a commitment hash being recorded in the receipt
+#[derive(Accounts)]
+pub struct DepositAnonymous <'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+     #[account(
+        mut, 
+        seeds=[b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump=stream.bump,
+        has_one = mint
+     )]
+    pub stream: Account<'info, StreamState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = donor,
+        space = DonationReceipt::INIT_SPACE,
+        seeds = [b"receipt", stream.key().as_ref(), stream.receipt_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, DonationReceipt>,
+
+    #[account(
+        mut,
+        constraint = donor_ata.owner == donor.key(),
+        constraint = donor_ata.mint == stream.mint
+    )]
+    pub donor_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Blocklist page for the donor's bucket, required while the stream has blocked wallets
+    #[account(
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&donor.key())]],
+        bump
+    )]
+    pub blocklist: Option<UncheckedAccount<'info>>,
+
+    /// Required while the stream is access gated
+    #[account(
+        seeds = [ACCESS_GATE_SEED, stream.key().as_ref()],
+        bump = access_gate.bump
+    )]
+    pub access_gate: Option<Account<'info, AccessGate>>,
+
+    /// Donor's token account for the gate mint, required while the stream is access gated
+    pub gate_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl <'info> DepositAnonymous <'info> {
+    pub fn deposit_anonymous(&mut self, amount: u64, commitment: [u8; 32], message: Option<String>, bumps: &DepositAnonymousBumps, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        if let Some(text) = &message {
+            require!(text.len() <= MAX_DONATION_MESSAGE_LEN, StreamError::MessageTooLong);
+        }
+
+        self.stream.require_deposits_open()?;
+        Blocklist::require_not_blocked(
+            self.stream.blocked_count,
+            self.blocklist.as_ref().map(|b| b.as_ref()),
+            &self.donor.key(),
+        )?;
+        enforce_access_gate(
+            &self.stream,
+            self.access_gate.as_ref(),
+            self.gate_token_account.as_ref(),
+            &self.donor.key(),
+        )?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.donor_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.stream_ata.to_account_info(),
+            authority: self.donor.to_account_info(),
+        };
+        let vault_before = self.stream_ata.amount;
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let amount = amount_received(&mut self.stream_ata, vault_before)?;
+
+        self.stream.total_deposited = self.stream.total_deposited.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_deposit(&self.global_registry, amount)?;
+
+        self.receipt.set_inner(DonationReceipt {
+            stream: self.stream.key(),
+            donor: Pubkey::default(),
+            commitment: Some(commitment),
+            index: self.stream.receipt_count,
+            amount,
+            message,
+            timestamp: Clock::get()?.unix_timestamp,
+            bump: bumps.receipt,
+        });
+        self.stream.receipt_count = self.stream.receipt_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+        emit!(AnonymousDepositMade {
+            stream: self.stream.key(),
+            commitment,
+            receipt: self.receipt.key(),
+            amount,
             timestamp: Clock::get()?.unix_timestamp
         });
+
+        emit_milestones(&mut self.stream)?;
+        notify_vault_state(&self.stream, self.stream_ata.amount, Clock::get()?.unix_timestamp)?;
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Emits a `MilestoneReached` event for every goal milestone the latest deposit crossed
+pub(crate) fn emit_milestones(stream: &mut Account<StreamState>) -> Result<()> {
+    if stream.goal_amount == 0 {
+        return Ok(());
+    }
+
+    while let Some(&percentage) = stream.milestone_percentages.get(stream.milestones_reached as usize) {
+        if !stream.milestone_met(percentage)? {
+            break;
+        }
+        stream.milestones_reached += 1;
+
+        emit!(MilestoneReached {
+            stream: stream.key(),
+            goal_amount: stream.goal_amount,
+            percentage,
+            total_deposited: stream.total_deposited,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+    }
+    Ok(())
+}
+/// Alerts when the vault holds less than the stream believes it does, and when the
+/// refund window of an active stream is about to close
+pub(crate) fn notify_vault_state(stream: &Account<StreamState>, vault_balance: u64, now: i64) -> Result<()> {
+    let tracked = stream.available_balance()?;
+    if vault_balance < tracked {
+        notify(NotificationCode::VaultShortfall, stream.key(), [vault_balance, tracked], now);
+    }
+
+    if let (StreamStatus::Active, Some(deadline)) = (&stream.status, stream.refund_deadline) {
+        if now < deadline && deadline - now <= REFUND_WINDOW_CLOSING_SOON {
+            notify(NotificationCode::RefundWindowClosing, stream.key(), [deadline as u64, tracked], now);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,367 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::instructions::{
+    amount_received, DISPUTE_WINDOW, MARKET_BOOK_SEED, MARKET_CONFIG_SEED, MARKET_SEED, MARKET_VAULT_SEED, RESOLUTION_SEED,
+    VOTE_RECORD_SEED,
+};
+use crate::state::{
+    notify, BettingMarket, DisputeFiled, DisputeSettled, DisputeStatus, DisputeVoteCast, MarketBook, MarketConfig, MarketDispute,
+    MarketResolution, NotificationCode, ResolutionStatus, StreamError, VoteRecord,
+};
+
+// ============= CONSTANTS =============
+pub const DISPUTE_SEED: &[u8] = b"market_dispute";
+pub const DISPUTE_BOND_VAULT_SEED: &[u8] = b"dispute_bond_vault";
+pub const MIN_DISPUTE_BOND: u64 = 10_000_000; // 10 USDC minimum
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Dispute the validators' consensus during the dispute window, backed by a bond
+#[derive(Accounts)]
+pub struct FileDispute<'info> {
+    #[account(mut)]
+    pub disputer: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [RESOLUTION_SEED, betting_market.key().as_ref()],
+        bump = resolution.load()?.bump,
+    )]
+    pub resolution: AccountLoader<'info, MarketResolution>,
+
+    #[account(
+        init,
+        payer = disputer,
+        space = MarketDispute::INIT_SPACE,
+        seeds = [DISPUTE_SEED, betting_market.key().as_ref()],
+        bump
+    )]
+    pub dispute: Account<'info, MarketDispute>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = disputer_token.owner == disputer.key(),
+        constraint = disputer_token.mint == mint.key(),
+    )]
+    pub disputer_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = disputer,
+        seeds = [DISPUTE_BOND_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = dispute,
+    )]
+    pub bond_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Any eligible validator, not only the selected ones, votes on an open dispute
+#[derive(Accounts)]
+pub struct VoteOnDispute<'info> {
+    #[account(mut)]
+    pub validator: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [RESOLUTION_SEED, betting_market.key().as_ref()],
+        bump = resolution.load()?.bump,
+    )]
+    pub resolution: AccountLoader<'info, MarketResolution>,
+
+    #[account(
+        mut,
+        seeds = [DISPUTE_SEED, betting_market.key().as_ref()],
+        bump = dispute.bump,
+    )]
+    pub dispute: Account<'info, MarketDispute>,
+
+    /// One record per (dispute, validator); creation fails if the validator already voted
+    #[account(
+        init,
+        payer = validator,
+        space = VoteRecord::INIT_SPACE,
+        seeds = [VOTE_RECORD_SEED, dispute.key().as_ref(), validator.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Anyone settles a dispute once voting has closed
+#[derive(Accounts)]
+pub struct SettleDispute<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        seeds = [RESOLUTION_SEED, betting_market.key().as_ref()],
+        bump = resolution.load()?.bump,
+    )]
+    pub resolution: AccountLoader<'info, MarketResolution>,
+
+    #[account(
+        mut,
+        seeds = [DISPUTE_SEED, betting_market.key().as_ref()],
+        bump = dispute.bump,
+    )]
+    pub dispute: Account<'info, MarketDispute>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [DISPUTE_BOND_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub bond_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Takes the bond when the dispute fails
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Takes the bond back when the dispute succeeds
+    #[account(
+        mut,
+        constraint = disputer_token.owner == dispute.disputer @ StreamError::Unauthorized,
+        constraint = disputer_token.mint == mint.key(),
+    )]
+    pub disputer_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> FileDispute<'info> {
+    /// Holds the consensus back from `finalize_resolution` until the dispute is settled
+    pub fn file_dispute(
+        &mut self,
+        claimed_outcome: u8,
+        bond_amount: u64,
+        bumps: &FileDisputeBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(bond_amount >= MIN_DISPUTE_BOND, StreamError::BondTooLow);
+        let now = Clock::get()?.unix_timestamp;
+
+        let mut resolution = self.resolution.load_mut()?;
+        require!(
+            resolution.status_is(ResolutionStatus::Finalized),
+            StreamError::InvalidResolutionState
+        );
+        require!(now < resolution.dispute_end_time, StreamError::DisputeWindowClosed);
+        let disputed_outcome = resolution.proposed_outcome().ok_or(StreamError::InvalidResolutionState)?;
+        require!(
+            claimed_outcome < self.betting_market.outcome_count && claimed_outcome != disputed_outcome,
+            StreamError::InvalidOutcome
+        );
+
+        let cpi_accounts = TransferChecked {
+            from: self.disputer_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.bond_vault.to_account_info(),
+            authority: self.disputer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, bond_amount, self.mint.decimals)?;
+        let bond_amount = amount_received(&mut self.bond_vault, 0)?;
+        require!(bond_amount >= MIN_DISPUTE_BOND, StreamError::BondTooLow);
+
+        let voting_deadline = now.checked_add(DISPUTE_WINDOW).ok_or(StreamError::MathOverflow)?;
+        resolution.set_status(ResolutionStatus::Disputed);
+
+        self.dispute.set_inner(MarketDispute {
+            market: self.betting_market.key(),
+            disputer: self.disputer.key(),
+            disputed_outcome,
+            claimed_outcome,
+            bond_amount,
+            uphold_stake: 0,
+            overturn_stake: 0,
+            votes_cast: 0,
+            voting_deadline,
+            status: DisputeStatus::Open,
+            bump: bumps.dispute,
+        });
+
+        emit!(DisputeFiled {
+            market: self.betting_market.key(),
+            disputer: self.disputer.key(),
+            disputed_outcome,
+            claimed_outcome,
+            bond_amount,
+            voting_deadline,
+            timestamp: now,
+        });
+        notify(
+            NotificationCode::DisputeOpened,
+            self.betting_market.key(),
+            [bond_amount, disputed_outcome as u64],
+            now,
+        );
+
+        Ok(())
+    }
+}
+
+impl<'info> VoteOnDispute<'info> {
+    /// Votes weigh the stake the validator was listed with when validators were selected
+    pub fn vote_on_dispute(&mut self, outcome_id: u8, bumps: &VoteOnDisputeBumps) -> Result<()> {
+        let dispute = &mut self.dispute;
+        require!(dispute.status == DisputeStatus::Open, StreamError::InvalidDisputeState);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < dispute.voting_deadline, StreamError::DisputeVotingClosed);
+
+        let stake = self
+            .resolution
+            .load()?
+            .eligible_validators()
+            .iter()
+            .find(|validator| validator.pubkey == self.validator.key())
+            .map(|validator| validator.stake)
+            .ok_or(StreamError::NotValidator)?;
+        require!(stake > 0, StreamError::InsufficientStakeForValidation);
+
+        let tally = if outcome_id == dispute.disputed_outcome {
+            &mut dispute.uphold_stake
+        } else if outcome_id == dispute.claimed_outcome {
+            &mut dispute.overturn_stake
+        } else {
+            return err!(StreamError::InvalidOutcome);
+        };
+        *tally = tally.checked_add(stake).ok_or(StreamError::MathOverflow)?;
+        dispute.votes_cast = dispute.votes_cast.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+        self.vote_record.set_inner(VoteRecord {
+            resolution: dispute.key(),
+            validator: self.validator.key(),
+            voted_outcome: outcome_id,
+            vote_timestamp: now,
+            stake_amount: stake,
+            bump: bumps.vote_record,
+        });
+
+        emit!(DisputeVoteCast {
+            market: self.betting_market.key(),
+            validator: self.validator.key(),
+            voted_outcome: outcome_id,
+            stake_weight: stake,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> SettleDispute<'info> {
+    /// A successful dispute replaces the consensus outcome and returns the bond to the disputer.
+    /// A failed one adds the bond to the market pool, paying it to the bettors the consensus
+    /// already favoured. Either way the resolution can be finalized straight away.
+    pub fn settle_dispute(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(self.dispute.status == DisputeStatus::Open, StreamError::InvalidDisputeState);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= self.dispute.voting_deadline, StreamError::DisputeVotingOpen);
+
+        let overturned = self.dispute.overturned(self.market_config.supermajority_bps);
+        let (status, winning_outcome, destination) = if overturned {
+            (
+                DisputeStatus::Overturned,
+                self.dispute.claimed_outcome,
+                self.disputer_token.to_account_info(),
+            )
+        } else {
+            (
+                DisputeStatus::Upheld,
+                self.dispute.disputed_outcome,
+                self.market_vault.to_account_info(),
+            )
+        };
+
+        let bond_amount = self.bond_vault.amount;
+        let market_key = self.betting_market.key();
+        let dispute_seeds = &[DISPUTE_SEED, market_key.as_ref(), &[self.dispute.bump]];
+        let signer = &[&dispute_seeds[..]];
+
+        let vault_before = self.market_vault.amount;
+        let cpi_accounts = TransferChecked {
+            from: self.bond_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: destination,
+            authority: self.dispute.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, bond_amount, self.mint.decimals)?;
+
+        if !overturned {
+            let received = amount_received(&mut self.market_vault, vault_before)?;
+            let mut book = self.market_book.load_mut()?;
+            book.total_pool = book.total_pool.checked_add(received).ok_or(StreamError::MathOverflow)?;
+        }
+
+        {
+            let mut resolution = self.resolution.load_mut()?;
+            resolution.proposed_outcome = winning_outcome;
+            resolution.dispute_end_time = now;
+            resolution.set_status(ResolutionStatus::Finalized);
+        }
+        self.dispute.status = status;
+
+        emit!(DisputeSettled {
+            market: market_key,
+            disputer: self.dispute.disputer,
+            status,
+            winning_outcome,
+            bond_amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
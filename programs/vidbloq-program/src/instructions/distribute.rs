@@ -2,12 +2,12 @@ use anchor_lang::prelude::*;
 
 use anchor_spl::{
     associated_token::AssociatedToken, 
-    token::{Transfer, transfer as token_transfer},
-    token_interface::{TokenAccount, TokenInterface, Mint}
+    token_interface::{TokenAccount, TokenInterface, Mint, TransferChecked, transfer_checked, Burn, burn}
     // token::{Transfer, transfer as token_transfer, TokenAccount, Token},
 };
 
-use crate::state::{StreamState, StreamStatus, StreamError, StreamType, FundsDistributed};
+use crate::instructions::{enforce_distribution_policy, PlatformFeeRoute, CLAWBACK_AGREEMENT_SEED, DISTRIBUTION_POLICY_SEED, PLATFORM_CONFIG_SEED, PAYOUT_RECIPIENT_SEED, GLOBAL_REGISTRY_SEED};
+use crate::state::{StreamState, StreamError, HostProfile, PayoutRecipient, DistributionPolicy, ClawbackAgreement, ReferralRewards, FundsDistributed, ClawbackWindowOpened, BurnRate, SECONDS_PER_HOUR, BURN_RATE_SMOOTHING, GlobalRegistry};
 
 #[derive(Accounts)]
 pub struct Distribute <'info> {
@@ -17,8 +17,9 @@ pub struct Distribute <'info> {
     /// CHECK: This is the recipient public key
     pub recipient: AccountInfo<'info>,
 
-    /// VERIFIED MINT: Must match stream.mint
+    /// VERIFIED MINT: Must match stream.mint; writable so deflationary streams can burn supply
     #[account(
+        mut,
         address = stream.mint,
     )]
     pub mint: InterfaceAccount<'info, Mint>,
@@ -26,7 +27,7 @@ pub struct Distribute <'info> {
     #[account(
         mut, 
         constraint = stream.host == host.key(),
-        seeds=[b"stream", stream.stream_name.as_str().as_bytes(), stream.host.key().as_ref()],
+        seeds=[b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
         bump=stream.bump
      )]
     pub stream: Account<'info, StreamState>,
@@ -46,13 +47,62 @@ pub struct Distribute <'info> {
     )]
     pub recipient_ata: InterfaceAccount<'info, TokenAccount>,
 
+    /// Allowlist entry for the recipient, required while the stream is in strict payout mode
+    #[account(
+        seeds = [PAYOUT_RECIPIENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump = payout_recipient.bump
+    )]
+    pub payout_recipient: Option<Account<'info, PayoutRecipient>>,
+
+    /// Spending limits, required once the host has set a distribution policy
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_POLICY_SEED, stream.key().as_ref()],
+        bump = distribution_policy.bump
+    )]
+    pub distribution_policy: Option<Account<'info, DistributionPolicy>>,
+
+    /// Passed to make the payout clawbackable when the recipient has consented to clawbacks
+    #[account(
+        mut,
+        seeds = [CLAWBACK_AGREEMENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump = clawback_agreement.bump,
+        constraint = clawback_agreement.recipient_ata == recipient_ata.key() @ StreamError::Unauthorized
+    )]
+    pub clawback_agreement: Option<Account<'info, ClawbackAgreement>>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required while a platform fee is configured
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"host_profile", stream.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Option<Account<'info, HostProfile>>,
+
+    /// Credited with the referrer's share of the fee while the host's referral runs
+    #[account(mut)]
+    pub referral_rewards: Option<Account<'info, ReferralRewards>>,
+
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub token_program: Interface<'info, TokenInterface>
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
 }
 
 impl <'info> Distribute <'info> {
-    pub fn distribute(&mut self, amount: u64) -> Result<()> {
+    pub fn distribute(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
         require!(amount > 0, StreamError::InvalidAmount);
 
         require!(
@@ -60,65 +110,16 @@ impl <'info> Distribute <'info> {
             StreamError::Unauthorized
         );
 
-        // Check if stream is still active
-        require!(self.stream.status == StreamStatus::Active, StreamError::StreamNotActive);
-
-        // StreamType-specific checks
-        match self.stream.stream_type {
-            StreamType::Prepaid { min_duration } => {
-                // For prepaid, must meet minimum duration
-                let elapsed = Clock::get()?.unix_timestamp 
-                    - self.stream.start_time.ok_or(StreamError::StreamNotStarted)?;
-                require!(
-                    elapsed >= min_duration as i64,
-                    StreamError::DurationNotMet
-                );
-            },
-            StreamType::Live => {
-                // No additional restrictions for live streams
-            },
-            StreamType::Conditional { min_amount, unlock_time } => {
-                // Check minimum amount if specified
-                if let Some(min) = min_amount {
-                    require!(
-                        self.stream.total_deposited >= min,
-                        StreamError::AmountNotMet
-                    );
-                }
-                // Check unlock time if specified
-                if let Some(time) = unlock_time {
-                    require!(
-                        Clock::get()?.unix_timestamp >= time,
-                        StreamError::TimeLocked
-                    );
-                }
-            }
-        }
-        
-
-        if let Some(end_time) = self.stream.end_time {
-            let current_time = Clock::get()?.unix_timestamp;
-            require!(
-                current_time >= end_time,
-                StreamError::StreamStillLocked
-            );
-        }
+        self.stream.require_distribution_allowed()?;
+        self.stream.require_within_lifetime(&self.platform_config)?;
+        self.stream.require_recipient_allowed(self.payout_recipient.is_some())?;
 
         // Calculate available balance
-        let available_balance = self.stream.total_deposited
-            .checked_sub(self.stream.total_distributed)
-            .ok_or(StreamError::MathOverflow)?;
+        let available_balance = self.stream.available_balance()?;
             
         // Ensure sufficient funds
         require!(available_balance >= amount, StreamError::InsufficientFunds);
-
-        let cpi_program = self.token_program.to_account_info();
-
-        let cpi_accounts = Transfer {
-            from: self.stream_ata.to_account_info(),
-            to: self.recipient_ata.to_account_info(),
-            authority: self.stream.to_account_info(),
-        };
+        enforce_distribution_policy(&self.stream, self.distribution_policy.as_mut(), amount)?;
 
         // let stream_seeds = &[
         //     b"stream".as_ref(),
@@ -126,26 +127,157 @@ impl <'info> Distribute <'info> {
         //     self.stream.stream_name.as_bytes(),
         //     &[self.stream.bump],
         // ];
-        let stream_seeds = &[
-            b"stream".as_ref(),
-            self.stream.stream_name.as_str().as_bytes(),
-            self.stream.host.as_ref(),
-            &[self.stream.bump],
-        ];
+        let stream_seeds = self.stream.signer_seeds();
+        let stream_seeds: Vec<&[u8]> = stream_seeds.iter().map(Vec::as_slice).collect();
         let signer = &[&stream_seeds[..]];
 
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        let fee = PlatformFeeRoute {
+            platform_config: &self.platform_config,
+            treasury_ata: self.treasury_ata.as_ref(),
+            host_profile: self.host_profile.as_ref(),
+            referral_rewards: self.referral_rewards.as_mut(),
+            referral_vault: self.referral_vault.as_ref(),
+        }.collect(
+            self.stream.host,
+            &self.stream_ata,
+            &self.mint,
+            self.stream.to_account_info(),
+            signer,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
 
-        token_transfer(cpi_ctx, amount)?;
+        let burned = burn_stream_share(
+            &self.stream,
+            &self.stream_ata,
+            &self.mint,
+            self.stream.to_account_info(),
+            signer,
+            &self.token_program,
+            amount - fee,
+        )?;
+        let paid = amount - fee - burned;
+
+        let cpi_accounts = TransferChecked {
+            from: self.stream_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.recipient_ata.to_account_info(),
+            authority: self.stream.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked(cpi_ctx, paid, self.mint.decimals)?;
 
         self.stream.total_distributed = self.stream.total_distributed.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_distribution(&self.global_registry, amount)?;
+        let now = Clock::get()?.unix_timestamp;
+
+        if let Some(agreement) = self.clawback_agreement.as_mut() {
+            agreement.record_payout(paid, now)?;
+            emit!(ClawbackWindowOpened {
+                stream: self.stream.key(),
+                recipient: self.recipient.key(),
+                amount: paid,
+                clawbackable: agreement.clawbackable,
+                window_ends_at: agreement.window_ends_at,
+                timestamp: now
+            });
+        }
 
         emit!(FundsDistributed {
             stream: self.stream.key(),
             recipient: self.recipient.key(),
             amount,
-            timestamp: Clock::get()?.unix_timestamp
+            burned,
+            timestamp: now
         });
+
+        record_burn_rate(&mut self.stream, amount)?;
         Ok(())
     }
+}
+
+/// Burns the stream's deflationary share of `amount`, what is left of a payout after the platform
+/// fee, from `vault` and returns it; nothing is burned while deflationary mode is off
+pub(crate) fn burn_stream_share<'info>(
+    stream: &StreamState,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+) -> Result<u64> {
+    let burned = stream.burn_share(amount);
+    if burned > 0 {
+        let cpi_accounts = Burn {
+            mint: mint.to_account_info(),
+            from: vault.to_account_info(),
+            authority,
+        };
+        burn(CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer), burned)?;
+    }
+    Ok(burned)
+}
+
+/// Updates the stream's rolling burn rate after a payout of `amount` and emits a `BurnRate` snapshot
+pub(crate) fn record_burn_rate(stream: &mut Account<StreamState>, amount: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let active_since = stream.start_time.unwrap_or(stream.created_at);
+    let active_seconds = (now - active_since).max(1) as u64;
+
+    let lifetime_rate_per_hour = per_hour(stream.total_distributed, active_seconds)?;
+
+    // Rate of this payout over the gap since the previous one, folded into the rolling average
+    let previous = if stream.last_distribution_at > 0 {
+        stream.last_distribution_at
+    } else {
+        active_since
+    };
+    let instant_rate = per_hour(amount, (now - previous).max(1) as u64)?;
+    stream.burn_rate_per_hour = if stream.last_distribution_at > 0 {
+        let weighted = (stream.burn_rate_per_hour as u128)
+            .checked_mul((BURN_RATE_SMOOTHING - 1) as u128)
+            .ok_or(StreamError::MathOverflow)?
+            .checked_add(instant_rate as u128)
+            .ok_or(StreamError::MathOverflow)?
+            / BURN_RATE_SMOOTHING as u128;
+        u64::try_from(weighted).map_err(|_| StreamError::MathOverflow)?
+    } else {
+        instant_rate
+    };
+    stream.last_distribution_at = now;
+
+    let remaining_balance = stream.available_balance()?;
+    let projected_depletion_at = if stream.burn_rate_per_hour > 0 {
+        let seconds_left = (remaining_balance as u128)
+            .checked_mul(SECONDS_PER_HOUR as u128)
+            .ok_or(StreamError::MathOverflow)?
+            / stream.burn_rate_per_hour as u128;
+        i64::try_from(seconds_left).ok().and_then(|s| now.checked_add(s))
+    } else {
+        None
+    };
+
+    emit!(BurnRate {
+        stream: stream.key(),
+        total_distributed: stream.total_distributed,
+        active_seconds,
+        lifetime_rate_per_hour,
+        rolling_rate_per_hour: stream.burn_rate_per_hour,
+        remaining_balance,
+        projected_depletion_at,
+        timestamp: now
+    });
+    Ok(())
+}
+
+fn per_hour(amount: u64, seconds: u64) -> Result<u64> {
+    let rate = (amount as u128)
+        .checked_mul(SECONDS_PER_HOUR as u128)
+        .ok_or(StreamError::MathOverflow)?
+        / seconds as u128;
+    Ok(u64::try_from(rate).map_err(|_| StreamError::MathOverflow)?)
 }
\ No newline at end of file
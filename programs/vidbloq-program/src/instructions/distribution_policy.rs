@@ -0,0 +1,403 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::instructions::{burn_stream_share, record_burn_rate, record_clawbackable_payout, PlatformFeeRoute, CLAWBACK_AGREEMENT_SEED, PAYOUT_RECIPIENT_SEED, PLATFORM_CONFIG_SEED, GLOBAL_REGISTRY_SEED};
+use crate::state::{
+    StreamState, StreamError, DistributionPolicy, DistributionProposal, HostProfile, PayoutRecipient,
+    ReferralRewards, DistributionPolicySet, DistributionApproversSet, LargeDistributionProposed,
+    LargeDistributionApproved, LargeDistributionExecuted, FundsDistributed, GlobalRegistry
+};
+
+pub const DISTRIBUTION_POLICY_SEED: &[u8] = b"distribution_policy";
+pub const DISTRIBUTION_PROPOSAL_SEED: &[u8] = b"distribution_proposal";
+
+/// Creates or updates the stream's spending limits. Once set, a stream cannot drop its policy.
+#[derive(Accounts)]
+pub struct SetDistributionPolicy<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        space = DistributionPolicy::INIT_SPACE,
+        seeds = [DISTRIBUTION_POLICY_SEED, stream.key().as_ref()],
+        bump
+    )]
+    pub distribution_policy: Account<'info, DistributionPolicy>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Records a payout above the approval threshold for the approvers to sign off. Funds stay in
+/// the vault until the proposal is executed.
+#[derive(Accounts)]
+pub struct ProposeLargeDistribution<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    /// CHECK: This is the recipient public key
+    pub recipient: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_POLICY_SEED, stream.key().as_ref()],
+        bump = distribution_policy.bump
+    )]
+    pub distribution_policy: Account<'info, DistributionPolicy>,
+
+    #[account(
+        init,
+        payer = host,
+        space = DistributionProposal::INIT_SPACE,
+        seeds = [
+            DISTRIBUTION_PROPOSAL_SEED,
+            stream.key().as_ref(),
+            distribution_policy.next_proposal_id.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, DistributionProposal>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveDistribution<'info> {
+    pub approver: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_PROPOSAL_SEED, proposal.stream.as_ref(), proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, DistributionProposal>,
+}
+
+/// Pays out a proposal once it has enough approvals and closes it
+#[derive(Accounts)]
+pub struct ExecuteDistribution<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    /// CHECK: Must be the proposal's recipient
+    #[account(address = proposal.recipient @ StreamError::Unauthorized)]
+    pub recipient: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_POLICY_SEED, stream.key().as_ref()],
+        bump = distribution_policy.bump
+    )]
+    pub distribution_policy: Account<'info, DistributionPolicy>,
+
+    #[account(
+        mut,
+        close = host,
+        seeds = [DISTRIBUTION_PROPOSAL_SEED, stream.key().as_ref(), proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        has_one = stream
+    )]
+    pub proposal: Account<'info, DistributionProposal>,
+
+    /// Writable so deflationary streams can burn supply
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Allowlist entry for the recipient, required while the stream is in strict payout mode
+    #[account(
+        seeds = [PAYOUT_RECIPIENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump = payout_recipient.bump
+    )]
+    pub payout_recipient: Option<Account<'info, PayoutRecipient>>,
+
+    /// CHECK: Recipient's clawback agreement PDA; may be uninitialized
+    #[account(
+        mut,
+        seeds = [CLAWBACK_AGREEMENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub clawback_agreement: UncheckedAccount<'info>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required while a platform fee is configured
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"host_profile", stream.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Option<Account<'info, HostProfile>>,
+
+    /// Credited with the referrer's share of the fee while the host's referral runs
+    #[account(mut)]
+    pub referral_rewards: Option<Account<'info, ReferralRewards>>,
+
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl<'info> SetDistributionPolicy<'info> {
+    pub fn set_distribution_policy(
+        &mut self,
+        max_per_distribution: u64,
+        max_per_window: u64,
+        bumps: &SetDistributionPolicyBumps,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.open_policy(bumps);
+        let effective_at = self.distribution_policy.set_limits(max_per_distribution, max_per_window, now)?;
+
+        emit!(DistributionPolicySet {
+            stream: self.stream.key(),
+            max_per_distribution,
+            max_per_window,
+            effective_at,
+            timestamp: now
+        });
+        Ok(())
+    }
+
+    pub fn set_distribution_approvers(
+        &mut self,
+        approvers: Vec<Pubkey>,
+        approvals_required: u8,
+        approval_threshold: u64,
+        bumps: &SetDistributionPolicyBumps,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.open_policy(bumps);
+        let effective_at = self.distribution_policy.set_approvers(
+            approvers.clone(),
+            approvals_required,
+            approval_threshold,
+            now,
+        )?;
+
+        emit!(DistributionApproversSet {
+            stream: self.stream.key(),
+            approvers,
+            approvals_required,
+            approval_threshold,
+            effective_at,
+            timestamp: now
+        });
+        Ok(())
+    }
+
+    fn open_policy(&mut self, bumps: &SetDistributionPolicyBumps) {
+        if !self.stream.has_distribution_policy {
+            self.distribution_policy.stream = self.stream.key();
+            self.distribution_policy.bump = bumps.distribution_policy;
+            self.stream.has_distribution_policy = true;
+        }
+    }
+}
+
+impl<'info> ProposeLargeDistribution<'info> {
+    pub fn propose_large_distribution(&mut self, amount: u64, bumps: &ProposeLargeDistributionBumps) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let policy = &mut self.distribution_policy;
+        policy.apply_pending(now);
+        require!(policy.requires_approval(amount), StreamError::InvalidAmount);
+
+        let available_balance = self.stream.available_balance()?;
+        require!(available_balance >= amount, StreamError::InsufficientFunds);
+
+        let id = policy.next_proposal_id;
+        policy.next_proposal_id = id.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+        self.proposal.set_inner(DistributionProposal {
+            stream: self.stream.key(),
+            id,
+            recipient: self.recipient.key(),
+            amount,
+            approvers: policy.approvers.clone(),
+            approvals_required: policy.approvals_required,
+            approvals: 0,
+            proposed_at: now,
+            bump: bumps.proposal,
+        });
+
+        emit!(LargeDistributionProposed {
+            stream: self.stream.key(),
+            proposal: self.proposal.key(),
+            recipient: self.recipient.key(),
+            amount,
+            approvals_required: policy.approvals_required,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+impl<'info> ApproveDistribution<'info> {
+    pub fn approve_distribution(&mut self) -> Result<()> {
+        self.proposal.approve(&self.approver.key())?;
+
+        emit!(LargeDistributionApproved {
+            stream: self.proposal.stream,
+            proposal: self.proposal.key(),
+            approver: self.approver.key(),
+            approvals: self.proposal.approval_count(),
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+impl<'info> ExecuteDistribution<'info> {
+    pub fn execute_distribution(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            self.proposal.approval_count() >= self.proposal.approvals_required,
+            StreamError::InsufficientApprovals
+        );
+        self.stream.require_distribution_allowed()?;
+        self.stream.require_within_lifetime(&self.platform_config)?;
+        self.stream.require_recipient_allowed(self.payout_recipient.is_some())?;
+
+        let amount = self.proposal.amount;
+        let available_balance = self.stream.available_balance()?;
+        require!(available_balance >= amount, StreamError::InsufficientFunds);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.distribution_policy.record_approved_distribution(amount, now)?;
+
+        let stream_seeds = self.stream.signer_seeds();
+        let stream_seeds: Vec<&[u8]> = stream_seeds.iter().map(Vec::as_slice).collect();
+        let signer = &[&stream_seeds[..]];
+
+        let fee = PlatformFeeRoute {
+            platform_config: &self.platform_config,
+            treasury_ata: self.treasury_ata.as_ref(),
+            host_profile: self.host_profile.as_ref(),
+            referral_rewards: self.referral_rewards.as_mut(),
+            referral_vault: self.referral_vault.as_ref(),
+        }.collect(
+            self.stream.host,
+            &self.stream_ata,
+            &self.mint,
+            self.stream.to_account_info(),
+            signer,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
+        let burned = burn_stream_share(
+            &self.stream,
+            &self.stream_ata,
+            &self.mint,
+            self.stream.to_account_info(),
+            signer,
+            &self.token_program,
+            amount - fee,
+        )?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.stream_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.recipient_ata.to_account_info(),
+            authority: self.stream.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount - fee - burned, self.mint.decimals)?;
+
+        self.stream.total_distributed = self.stream.total_distributed.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_distribution(&self.global_registry, amount)?;
+        record_clawbackable_payout(
+            &self.clawback_agreement,
+            self.stream.key(),
+            self.recipient.key(),
+            &self.recipient_ata.key(),
+            amount - fee - burned,
+            now,
+        )?;
+
+        emit!(LargeDistributionExecuted {
+            stream: self.stream.key(),
+            proposal: self.proposal.key(),
+            recipient: self.recipient.key(),
+            amount,
+            platform_fee: fee,
+            timestamp: now
+        });
+        emit!(FundsDistributed {
+            stream: self.stream.key(),
+            recipient: self.recipient.key(),
+            amount,
+            burned,
+            timestamp: now
+        });
+
+        record_burn_rate(&mut self.stream, amount)
+    }
+}
+
+/// Counts a payout of `amount` against the stream's spending limits when it has a policy
+pub(crate) fn enforce_distribution_policy(
+    stream: &StreamState,
+    policy: Option<&mut Account<DistributionPolicy>>,
+    amount: u64,
+) -> Result<()> {
+    if !stream.has_distribution_policy {
+        return Ok(());
+    }
+    let policy = policy.ok_or(StreamError::MissingAccounts)?;
+    policy.record_distribution(amount, Clock::get()?.unix_timestamp)
+}
@@ -0,0 +1,136 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+
+use crate::instructions::{amount_received, GLOBAL_REGISTRY_SEED};
+use crate::state::{StreamState, StreamStatus, StreamError, Blocklist, ExtensionRateSet, StreamExtended, GlobalRegistry};
+
+const SECONDS_PER_MINUTE: i64 = 60;
+
+/// Prices extensions of the stream's end time; zero stops selling them
+#[derive(Accounts)]
+pub struct SetExtensionRate<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = host,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+}
+
+impl<'info> SetExtensionRate<'info> {
+    pub fn set_extension_rate(&mut self, rate_per_minute: u64) -> Result<()> {
+        // Only a stream with an end time has something to extend
+        require!(
+            rate_per_minute == 0 || self.stream.end_time.is_some(),
+            StreamError::InvalidTime
+        );
+        self.stream.extension_rate_per_minute = rate_per_minute;
+
+        emit!(ExtensionRateSet {
+            stream: self.stream.key(),
+            rate_per_minute,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+/// Anyone pays the host's per-minute rate into the vault to push the stream's end time back.
+/// Payments count toward the stream's deposits but are not refundable donations.
+#[derive(Accounts)]
+pub struct ExtendStream<'info> {
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = payer_ata.owner == payer.key(),
+        constraint = payer_ata.mint == stream.mint
+    )]
+    pub payer_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Blocklist page for the payer's bucket, required while the stream has blocked wallets
+    #[account(
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&payer.key())]],
+        bump
+    )]
+    pub blocklist: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl<'info> ExtendStream<'info> {
+    pub fn extend_stream(&mut self, minutes: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(minutes > 0, StreamError::InvalidAmount);
+        require!(self.stream.status == StreamStatus::Active, StreamError::StreamNotActive);
+        let rate = self.stream.extension_rate_per_minute;
+        require!(rate > 0, StreamError::ExtensionsDisabled);
+
+        let now = Clock::get()?.unix_timestamp;
+        let end_time = self.stream.end_time.ok_or(StreamError::ExtensionsDisabled)?;
+        require!(now < end_time, StreamError::StreamAlreadyEnded);
+        Blocklist::require_not_blocked(
+            self.stream.blocked_count,
+            self.blocklist.as_ref().map(|b| b.as_ref()),
+            &self.payer.key(),
+        )?;
+
+        let cost = rate.checked_mul(minutes).ok_or(StreamError::MathOverflow)?;
+        let new_end_time = i64::try_from(minutes)
+            .ok()
+            .and_then(|minutes| minutes.checked_mul(SECONDS_PER_MINUTE))
+            .and_then(|seconds| end_time.checked_add(seconds))
+            .ok_or(StreamError::MathOverflow)?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.payer_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.stream_ata.to_account_info(),
+            authority: self.payer.to_account_info(),
+        };
+
+        // Remaining accounts carry any transfer-hook extra accounts for the mint
+        let vault_before = self.stream_ata.amount;
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, cost, self.mint.decimals)?;
+        let amount = amount_received(&mut self.stream_ata, vault_before)?;
+
+        self.stream.total_deposited = self.stream.total_deposited.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_deposit(&self.global_registry, amount)?;
+        self.stream.end_time = Some(new_end_time);
+
+        emit!(StreamExtended {
+            stream: self.stream.key(),
+            payer: self.payer.key(),
+            minutes,
+            amount,
+            new_end_time,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
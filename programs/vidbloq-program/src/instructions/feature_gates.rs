@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+
+use crate::program::VidbloqProgram;
+use crate::state::{FeatureGates, StreamError, WithdrawalQueueConfigured};
+
+pub const FEATURE_GATES_SEED: &[u8] = b"feature_gates";
+
+/// Creates the feature gates singleton; only the program's upgrade authority may do so
+#[derive(Accounts)]
+pub struct InitializeFeatureGates<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = FeatureGates::INIT_SPACE,
+        seeds = [FEATURE_GATES_SEED],
+        bump
+    )]
+    pub feature_gates: Account<'info, FeatureGates>,
+
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ StreamError::Unauthorized
+    )]
+    pub program: Program<'info, VidbloqProgram>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ StreamError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetWithdrawalQueue<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [FEATURE_GATES_SEED],
+        bump = feature_gates.bump,
+        has_one = admin @ StreamError::Unauthorized
+    )]
+    pub feature_gates: Account<'info, FeatureGates>,
+}
+
+impl<'info> InitializeFeatureGates<'info> {
+    pub fn initialize_feature_gates(&mut self, bumps: &InitializeFeatureGatesBumps) -> Result<()> {
+        self.feature_gates.set_inner(FeatureGates {
+            admin: self.admin.key(),
+            withdrawal_queue_enabled: false,
+            queue_threshold: 0,
+            max_releases_per_slot: 0,
+            current_slot: 0,
+            released_in_slot: 0,
+            next_ticket_id: 0,
+            pending_tickets: 0,
+            bump: bumps.feature_gates,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> SetWithdrawalQueue<'info> {
+    /// Turns the withdrawal queue on or off. While on, refunds and claims above
+    /// `queue_threshold` must be queued and at most `max_releases_per_slot` tickets
+    /// are released per slot.
+    pub fn set_withdrawal_queue(&mut self, enabled: bool, queue_threshold: u64, max_releases_per_slot: u16) -> Result<()> {
+        require!(!enabled || max_releases_per_slot > 0, StreamError::InvalidAmount);
+
+        let gates = &mut self.feature_gates;
+        gates.withdrawal_queue_enabled = enabled;
+        gates.queue_threshold = queue_threshold;
+        gates.max_releases_per_slot = max_releases_per_slot;
+
+        emit!(WithdrawalQueueConfigured {
+            admin: self.admin.key(),
+            enabled,
+            queue_threshold,
+            max_releases_per_slot,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,451 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::instructions::{
+    amount_received, record_bet, require_bet_open, MARKET_ACCESS_SEED, MARKET_BOOK_SEED, MARKET_SEED, MARKET_STATS_SEED,
+    MARKET_VAULT_SEED,
+    OUTCOME_POSITION_SEED, POSITION_SEED,
+};
+use crate::state::{
+    BettingMarket, BettorPosition, Blocklist, FreeBetCredit, MarketAccessEntry, MarketBook, OutcomePositionAccount, FreeBetCreditClawedBack,
+    FreeBetCreditGranted, FreeBetPlaced, FreeBetStakesReclaimed, StreamError, StreamState,
+};
+
+// ============= CONSTANTS =============
+pub const FREE_BET_CREDIT_SEED: &[u8] = b"free_bet_credit";
+pub const FREE_BET_VAULT_SEED: &[u8] = b"free_bet_vault";
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Fund a bettor's free bet credit on one market from the host's tokens
+#[derive(Accounts)]
+#[instruction(bettor: Pubkey)]
+pub struct GrantFreeBetCredit<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        init,
+        payer = host,
+        space = FreeBetCredit::INIT_SPACE,
+        seeds = [FREE_BET_CREDIT_SEED, betting_market.key().as_ref(), bettor.as_ref()],
+        bump
+    )]
+    pub credit: Account<'info, FreeBetCredit>,
+
+    #[account(
+        init,
+        payer = host,
+        seeds = [FREE_BET_VAULT_SEED, credit.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = credit,
+        token::token_program = token_program,
+    )]
+    pub credit_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = host_token.owner == host.key(),
+        constraint = host_token.mint == mint.key(),
+    )]
+    pub host_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Place a bet staked from free bet credit instead of the bettor's own tokens
+#[derive(Accounts)]
+#[instruction(outcome_id: u8)]
+pub struct PlaceFreeBet<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = stream,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    pub stream: Account<'info, StreamState>,
+
+    /// CHECK: Blocklist page for the bettor's bucket, required while the stream has blocked wallets
+    #[account(
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&bettor.key())]],
+        bump
+    )]
+    pub blocklist: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Bettor's access listing, required while the market is not open to everyone
+    #[account(
+        seeds = [MARKET_ACCESS_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub market_access: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = BettorPosition::INIT_SPACE,
+        seeds = [POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub bettor_position: Account<'info, BettorPosition>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = OutcomePositionAccount::INIT_SPACE,
+        seeds = [OUTCOME_POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref(), outcome_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub outcome_position: Account<'info, OutcomePositionAccount>,
+
+    #[account(
+        mut,
+        seeds = [FREE_BET_CREDIT_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump = credit.bump,
+    )]
+    pub credit: Account<'info, FreeBetCredit>,
+
+    #[account(
+        mut,
+        seeds = [FREE_BET_VAULT_SEED, credit.key().as_ref()],
+        bump,
+    )]
+    pub credit_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = mint.key() == betting_market.mint @ StreamError::InvalidMint
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = betting_market,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Market stats PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [MARKET_STATS_SEED, betting_market.key().as_ref()], bump)]
+    pub market_stats: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Return unused credit to the host once it has expired or the market has resolved
+#[derive(Accounts)]
+pub struct ClawbackFreeBetCredit<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        close = host,
+        seeds = [FREE_BET_CREDIT_SEED, betting_market.key().as_ref(), credit.bettor.as_ref()],
+        bump = credit.bump,
+    )]
+    pub credit: Account<'info, FreeBetCredit>,
+
+    #[account(
+        mut,
+        seeds = [FREE_BET_VAULT_SEED, credit.key().as_ref()],
+        bump,
+    )]
+    pub credit_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = host_token.owner == host.key(),
+        constraint = host_token.mint == mint.key(),
+    )]
+    pub host_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Pay the host the free bet stakes held back from winning claims
+#[derive(Accounts)]
+pub struct ReclaimFreeBetStakes<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = host_token.owner == host.key(),
+        constraint = host_token.mint == mint.key(),
+    )]
+    pub host_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= IMPLEMENTATION =============
+
+impl<'info> GrantFreeBetCredit<'info> {
+    pub fn grant_free_bet_credit(
+        &mut self,
+        bettor: Pubkey,
+        amount: u64,
+        expires_at: i64,
+        bumps: &GrantFreeBetCreditBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(amount > 0, StreamError::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        require!(expires_at > now, StreamError::FreeBetCreditExpired);
+
+        let cpi_accounts = TransferChecked {
+            from: self.host_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.credit_vault.to_account_info(),
+            authority: self.host.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let granted = amount_received(&mut self.credit_vault, 0)?;
+
+        self.credit.set_inner(FreeBetCredit {
+            market: self.betting_market.key(),
+            host: self.host.key(),
+            bettor,
+            granted,
+            remaining: granted,
+            expires_at,
+            bump: bumps.credit,
+        });
+
+        emit!(FreeBetCreditGranted {
+            market: self.betting_market.key(),
+            bettor,
+            amount: granted,
+            expires_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> PlaceFreeBet<'info> {
+    pub fn place_free_bet(
+        &mut self,
+        outcome_id: u8,
+        amount: u64,
+        min_shares: u64,
+        bumps: &PlaceFreeBetBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require_bet_open(&self.betting_market, outcome_id, amount)?;
+        Blocklist::require_not_blocked(
+            self.stream.blocked_count,
+            self.blocklist.as_ref().map(|b| b.as_ref()),
+            &self.bettor.key(),
+        )?;
+        MarketAccessEntry::require_access(
+            self.betting_market.access_mode,
+            self.market_access.as_ref().map(|e| e.as_ref()),
+            &self.bettor.key(),
+        )?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < self.credit.expires_at, StreamError::FreeBetCreditExpired);
+        require!(amount <= self.credit.remaining, StreamError::InsufficientFunds);
+
+        let market_key = self.betting_market.key();
+        let bettor_key = self.bettor.key();
+        let credit_seeds = &[
+            FREE_BET_CREDIT_SEED,
+            market_key.as_ref(),
+            bettor_key.as_ref(),
+            &[self.credit.bump],
+        ];
+        let signer = &[&credit_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.credit_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.market_vault.to_account_info(),
+            authority: self.credit.to_account_info(),
+        };
+        let vault_before = self.market_vault.amount;
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let staked = amount_received(&mut self.market_vault, vault_before)?;
+        self.credit.remaining -= amount;
+
+        record_bet(
+            &self.betting_market,
+            &self.market_book,
+            &mut self.bettor_position,
+            &mut self.outcome_position,
+            &self.market_stats,
+            bettor_key,
+            outcome_id,
+            staked,
+            min_shares,
+            (bumps.bettor_position, bumps.outcome_position),
+        )?;
+        self.bettor_position.credit_staked = self
+            .bettor_position
+            .credit_staked
+            .checked_add(staked)
+            .ok_or(StreamError::MathOverflow)?;
+        self.outcome_position.credit_staked = self
+            .outcome_position
+            .credit_staked
+            .checked_add(staked)
+            .ok_or(StreamError::MathOverflow)?;
+
+        emit!(FreeBetPlaced {
+            market: market_key,
+            bettor: bettor_key,
+            outcome_id,
+            amount: staked,
+            remaining_credit: self.credit.remaining,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ClawbackFreeBetCredit<'info> {
+    pub fn clawback_free_bet_credit(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            self.betting_market.resolved || Clock::get()?.unix_timestamp >= self.credit.expires_at,
+            StreamError::FreeBetCreditActive
+        );
+
+        let market_key = self.betting_market.key();
+        let credit_seeds = &[
+            FREE_BET_CREDIT_SEED,
+            market_key.as_ref(),
+            self.credit.bettor.as_ref(),
+            &[self.credit.bump],
+        ];
+        let signer = &[&credit_seeds[..]];
+
+        let amount = self.credit_vault.amount;
+        if amount > 0 {
+            let cpi_accounts = TransferChecked {
+                from: self.credit_vault.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: self.host_token.to_account_info(),
+                authority: self.credit.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+            transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        }
+
+        let close_accounts = CloseAccount {
+            account: self.credit_vault.to_account_info(),
+            destination: self.host.to_account_info(),
+            authority: self.credit.to_account_info(),
+        };
+        close_account(CpiContext::new_with_signer(self.token_program.to_account_info(), close_accounts, signer))?;
+
+        emit!(FreeBetCreditClawedBack {
+            market: market_key,
+            bettor: self.credit.bettor,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ReclaimFreeBetStakes<'info> {
+    pub fn reclaim_free_bet_stakes(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let amount = self.betting_market.free_bet_withheld;
+        require!(amount > 0, StreamError::NothingToSweep);
+        self.betting_market.free_bet_withheld = 0;
+
+        let market_seeds = &[
+            MARKET_SEED,
+            self.betting_market.stream.as_ref(),
+            &[self.betting_market.bump],
+        ];
+        let signer = &[&market_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.market_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.host_token.to_account_info(),
+            authority: self.betting_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        emit!(FreeBetStakesReclaimed {
+            market: self.betting_market.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
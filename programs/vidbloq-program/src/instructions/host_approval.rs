@@ -0,0 +1,182 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    ed25519_program,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::instructions::{process_refund, FEATURE_GATES_SEED};
+use crate::state::{
+    StreamState, StreamError, DonorAccount, FeatureGates, HostApproval, HostAction,
+    HostApprovalExecuted, Leaderboard
+};
+
+// Layout of the ed25519 program instruction data
+const ED25519_HEADER_LEN: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+const ED25519_PUBKEY_LEN: usize = 32;
+const ED25519_SIGNATURE_LEN: usize = 64;
+
+#[derive(Accounts)]
+pub struct RelayHostApproval<'info> {
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    /// CHECK: Instructions sysvar, used to introspect the ed25519 verification instruction
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    // Accounts below are only needed for `HostAction::ApproveRefund`
+    #[account(
+        mut,
+        seeds = [b"donor", stream.key().as_ref(), donor_account.donor.as_ref()],
+        bump = donor_account.bump,
+        constraint = donor_account.stream == stream.key()
+    )]
+    pub donor_account: Option<Account<'info, DonorAccount>>,
+
+    #[account(mut)]
+    pub donor_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(address = stream.mint)]
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Required while the stream maintains a leaderboard
+    #[account(
+        mut,
+        seeds = [b"leaderboard", stream.key().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+
+    /// CHECK: Feature gates PDA; may be uninitialized
+    #[account(seeds = [FEATURE_GATES_SEED], bump)]
+    pub feature_gates: UncheckedAccount<'info>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+}
+
+impl<'info> RelayHostApproval<'info> {
+    pub fn relay_host_approval(&mut self, approval: HostApproval, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(approval.stream == self.stream.key(), StreamError::InvalidApproval);
+        require!(approval.nonce == self.stream.approval_nonce, StreamError::InvalidApproval);
+        require!(now <= approval.expires_at, StreamError::ApprovalExpired);
+
+        let message = approval.try_to_vec()?;
+        verify_ed25519_instruction(&self.instructions, &self.stream.host, &message)?;
+
+        // Consume the nonce before acting so the approval can never be replayed
+        self.stream.approval_nonce = self.stream.approval_nonce.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+        match &approval.action {
+            HostAction::ApproveRefund { donor, amount } => {
+                self.stream.require_refunds_open()?;
+                let (Some(donor_account), Some(donor_ata), Some(mint), Some(stream_ata), Some(token_program)) = (
+                    self.donor_account.as_mut(),
+                    self.donor_ata.as_ref(),
+                    self.mint.as_ref(),
+                    self.stream_ata.as_ref(),
+                    self.token_program.as_ref(),
+                ) else {
+                    return err!(StreamError::MissingAccounts);
+                };
+                require!(donor_account.donor == *donor, StreamError::InvalidApproval);
+                FeatureGates::require_not_queued(&self.feature_gates, *amount)?;
+
+                process_refund(
+                    &mut self.stream,
+                    donor_account,
+                    self.leaderboard.as_mut(),
+                    mint,
+                    stream_ata,
+                    donor_ata,
+                    token_program,
+                    remaining_accounts,
+                    *amount,
+                )?;
+            }
+            HostAction::SetMetadataHash { hash } => {
+                self.stream.metadata_hash = *hash;
+            }
+        }
+
+        emit!(HostApprovalExecuted {
+            stream: self.stream.key(),
+            relayer: self.relayer.key(),
+            nonce: approval.nonce,
+            action: approval.action,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+/// Requires the instruction right before the current one to be an ed25519 program
+/// verification of `message` signed by `signer`
+pub(crate) fn verify_ed25519_instruction(
+    instructions: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<()> {
+    let current_index = load_current_index_checked(instructions)?;
+    require!(current_index > 0, StreamError::MissingSignatureVerification);
+    let ix = load_instruction_at_checked((current_index - 1) as usize, instructions)?;
+
+    require!(
+        ix.program_id == ed25519_program::ID && ix.accounts.is_empty(),
+        StreamError::MissingSignatureVerification
+    );
+
+    let data = &ix.data;
+    require!(
+        data.len() >= ED25519_HEADER_LEN + ED25519_OFFSETS_LEN && data[0] == 1,
+        StreamError::InvalidSignatureVerification
+    );
+
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let offsets = ED25519_HEADER_LEN;
+    let signature_offset = read_u16(offsets) as usize;
+    let signature_ix_index = read_u16(offsets + 2);
+    let pubkey_offset = read_u16(offsets + 4) as usize;
+    let pubkey_ix_index = read_u16(offsets + 6);
+    let message_offset = read_u16(offsets + 8) as usize;
+    let message_size = read_u16(offsets + 10) as usize;
+    let message_ix_index = read_u16(offsets + 12);
+
+    // All data must live inside the ed25519 instruction itself
+    require!(
+        signature_ix_index == u16::MAX && pubkey_ix_index == u16::MAX && message_ix_index == u16::MAX,
+        StreamError::InvalidSignatureVerification
+    );
+    require!(
+        data.len() >= signature_offset + ED25519_SIGNATURE_LEN
+            && data.len() >= pubkey_offset + ED25519_PUBKEY_LEN
+            && data.len() >= message_offset + message_size,
+        StreamError::InvalidSignatureVerification
+    );
+
+    require!(
+        &data[pubkey_offset..pubkey_offset + ED25519_PUBKEY_LEN] == signer.as_ref(),
+        StreamError::InvalidSignatureVerification
+    );
+    require!(
+        &data[message_offset..message_offset + message_size] == message,
+        StreamError::InvalidSignatureVerification
+    );
+    Ok(())
+}
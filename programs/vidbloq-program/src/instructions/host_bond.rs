@@ -0,0 +1,181 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer as system_transfer, Transfer as SystemTransfer};
+
+use crate::instructions::{MARKET_CONFIG_SEED, MARKET_SEED, PLATFORM_CONFIG_SEED};
+use crate::state::{
+    BettingMarket, HostProfile, HostSlash, MarketConfig, PlatformConfig, ResolutionSource, StreamError,
+    HostBondPosted, HostBondWithdrawn, HostBondSlashed, CLAIM_WINDOW, HOST_BOND_LOCK
+};
+
+pub const HOST_SLASH_SEED: &[u8] = b"host_slash";
+
+/// Host locks lamports on their profile as a bond against malicious market resolutions
+#[derive(Accounts)]
+pub struct PostHostBond<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"host_profile", host.key().as_ref()],
+        bump = host_profile.bump,
+        has_one = host
+    )]
+    pub host_profile: Account<'info, HostProfile>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Host takes bond back once no recent resolution can still be disputed
+#[derive(Accounts)]
+pub struct WithdrawHostBond<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"host_profile", host.key().as_ref()],
+        bump = host_profile.bump,
+        has_one = host
+    )]
+    pub host_profile: Account<'info, HostProfile>,
+}
+
+/// Platform admin rules a host's resolution of `betting_market` malicious and moves bond to the
+/// treasury. Only markets the host resolved by hand qualify, once each, while the bond is still
+/// locked for that resolution.
+#[derive(Accounts)]
+pub struct SlashHostBond<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ StreamError::Unauthorized,
+        has_one = treasury
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+
+    #[account(
+        mut,
+        seeds = [b"host_profile", host_profile.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Account<'info, HostProfile>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host_profile.host @ StreamError::Unauthorized,
+        constraint = betting_market.resolved @ StreamError::MarketNotResolved,
+        constraint = !betting_market.cancelled @ StreamError::MarketCancelled,
+        constraint = betting_market.settlement_hash != [0; 32] @ StreamError::NotHostResolved
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+        constraint = market_config.resolution_source == ResolutionSource::Manual @ StreamError::NotHostResolved
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = HostSlash::INIT_SPACE,
+        seeds = [HOST_SLASH_SEED, betting_market.key().as_ref()],
+        bump
+    )]
+    pub host_slash: Account<'info, HostSlash>,
+
+    /// CHECK: Treasury wallet named in the platform config
+    #[account(mut)]
+    pub treasury: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PostHostBond<'info> {
+    pub fn post_host_bond(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+
+        let cpi_accounts = SystemTransfer {
+            from: self.host.to_account_info(),
+            to: self.host_profile.to_account_info(),
+        };
+        system_transfer(CpiContext::new(self.system_program.to_account_info(), cpi_accounts), amount)?;
+
+        let profile = &mut self.host_profile;
+        profile.bond = profile.bond.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+
+        emit!(HostBondPosted {
+            host: profile.host,
+            amount,
+            bond: profile.bond,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+impl<'info> WithdrawHostBond<'info> {
+    pub fn withdraw_host_bond(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        let now = Clock::get()?.unix_timestamp;
+        let profile = &mut self.host_profile;
+        require!(now >= profile.bond_locked_until, StreamError::HostBondLocked);
+        profile.bond = profile.bond.checked_sub(amount).ok_or(StreamError::InsufficientFunds)?;
+
+        // Bond lamports sit above the profile's rent-exempt minimum, so moving them never closes it
+        **profile.to_account_info().try_borrow_mut_lamports()? -= amount;
+        **self.host.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(HostBondWithdrawn {
+            host: profile.host,
+            amount,
+            bond: profile.bond,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+impl<'info> SlashHostBond<'info> {
+    /// Slashes up to `amount`, capped at the bond, and counts the resolution as a lost dispute
+    pub fn slash_host_bond(&mut self, amount: u64, bumps: &SlashHostBondBumps) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        // Hand resolution locks the bond for HOST_BOND_LOCK and opens the claim window at the
+        // same moment, so the claim deadline dates the resolution
+        let now = Clock::get()?.unix_timestamp;
+        let resolved_at = self.betting_market.claim_deadline.saturating_sub(CLAIM_WINDOW);
+        require!(now < resolved_at.saturating_add(HOST_BOND_LOCK), StreamError::SlashWindowClosed);
+
+        let profile = &mut self.host_profile;
+        let slashed = amount.min(profile.bond);
+        profile.bond -= slashed;
+        profile.disputes_lost = profile.disputes_lost.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+        **profile.to_account_info().try_borrow_mut_lamports()? -= slashed;
+        **self.treasury.to_account_info().try_borrow_mut_lamports()? += slashed;
+
+        self.host_slash.set_inner(HostSlash {
+            market: self.betting_market.key(),
+            host: profile.host,
+            amount: slashed,
+            slashed_at: now,
+            bump: bumps.host_slash,
+        });
+
+        emit!(HostBondSlashed {
+            host: profile.host,
+            market: self.betting_market.key(),
+            amount: slashed,
+            treasury: self.treasury.key(),
+            disputes_lost: profile.disputes_lost,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
@@ -1,29 +1,43 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken, 
-    token_interface::{Mint, TokenAccount, TokenInterface}
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
 };
 
-use crate::state::{StreamState, StreamStatus, StreamError, StreamType};
+use crate::instructions::{amount_received, index_stream, GLOBAL_REGISTRY_SEED, HOST_STREAM_INDEX_SEED};
+use crate::state::{StreamState, StreamStatus, StreamError, StreamType, HostProfile, DonorAccount, DonationReceipt, DepositMade, GlobalRegistry, StreamSeedKind, STREAM_VERSION};
 
 #[derive(Accounts)]
-#[instruction(stream_name: String, stream_type: StreamType, end_date: Option<i64>)]
 pub struct Initialize <'info> {
     #[account(mut)]
     pub host: Signer<'info>,
 
     #[account(
-        init, 
+        init_if_needed,
+        payer = host,
+        space = HostProfile::INIT_SPACE,
+        seeds = [b"host_profile", host.key().as_ref()],
+        bump
+    )]
+    pub host_profile: Account<'info, HostProfile>,
+
+    /// Seeded by the host's stream count, so names can repeat and change
+    #[account(
+        init,
         payer=host,
         space=StreamState::INIT_SPACE,
-        seeds=[b"stream", 
-        stream_name.as_str().as_bytes(),
-        // &stream_name.as_bytes()[..std::cmp::min(stream_name.len(), 32)],
-        host.key().as_ref()],
+        seeds=[b"stream", host.key().as_ref(), host_profile.streams_created.to_le_bytes().as_ref()],
         bump
     )]
     pub stream: Account<'info, StreamState>,
 
+    /// CHECK: Host's stream index PDA, created with the host's first indexed stream
+    #[account(mut, seeds = [HOST_STREAM_INDEX_SEED, host.key().as_ref()], bump)]
+    pub host_stream_index: UncheckedAccount<'info>,
+
+    /// CHECK: Growth partner credited with referring the host; only recorded on the host's first stream
+    pub referrer: Option<UncheckedAccount<'info>>,
+
     pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(init, 
@@ -34,46 +48,250 @@ pub struct Initialize <'info> {
 
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub token_program: Interface<'info, TokenInterface>
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
 }
 
 impl <'info> Initialize <'info> {
-    pub fn initialize(&mut self, name: String, stream_type: StreamType, end_time: Option<i64>, bumps: &InitializeBumps) -> Result<()> {
-
-        require!(
-            name.len() >= 4 && name.len() <= 32,
-            StreamError::NameLengthInvalid
-        );
-
-        match &stream_type {
-            StreamType::Prepaid { min_duration } => {
-                require!(*min_duration > 0, StreamError::InvalidDuration);
-            },
-            StreamType::Conditional { min_amount, unlock_time } => {
-                if let Some(amount) = min_amount {
-                    require!(*amount > 0, StreamError::InvalidAmount);
-                }
-                if let Some(time) = unlock_time {
-                    require!(*time > Clock::get()?.unix_timestamp, StreamError::InvalidTime);
-                }
-            }
-            StreamType::Live => {
-                // No additional validation needed
-            }
-        }
-        self.stream.set_inner(StreamState {
-            host: self.host.key(),
-            stream_name: name,
-            bump: bumps.stream,
-            total_distributed: 0,
-            total_deposited: 0,
-            status: StreamStatus::Active,
-            mint: self.mint.key(),
+    pub fn initialize(&mut self, name: String, stream_type: StreamType, end_time: Option<i64>, refund_deadline: Option<i64>, bumps: &InitializeBumps) -> Result<()> {
+        self.stream.set_inner(new_stream_state(
+            self.host.key(),
+            self.host_profile.streams_created,
+            name,
+            bumps.stream,
+            self.mint.key(),
+            stream_type,
             end_time,
+            refund_deadline,
+        )?);
+        self.host_profile.register_stream(
+            self.host.key(),
+            self.referrer.as_ref().map(|r| r.key()),
+            bumps.host_profile,
+        )?;
+        index_stream(
+            &self.host_stream_index,
+            &self.host,
+            self.stream.key(),
+            bumps.host_stream_index,
+            &self.system_program,
+        )?;
+        GlobalRegistry::record_stream(&self.global_registry)?;
+        Ok(())
+    }
+}
+
+/// Creates a stream and makes its first deposit in one transaction. The donor may be the host
+/// itself; the host pays rent for every account created.
+#[derive(Accounts)]
+pub struct InitializeAndDeposit <'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    pub donor: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        space = HostProfile::INIT_SPACE,
+        seeds = [b"host_profile", host.key().as_ref()],
+        bump
+    )]
+    pub host_profile: Account<'info, HostProfile>,
+
+    /// Seeded by the host's stream count, so names can repeat and change
+    #[account(
+        init,
+        payer=host,
+        space=StreamState::INIT_SPACE,
+        seeds=[b"stream", host.key().as_ref(), host_profile.streams_created.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    /// CHECK: Host's stream index PDA, created with the host's first indexed stream
+    #[account(mut, seeds = [HOST_STREAM_INDEX_SEED, host.key().as_ref()], bump)]
+    pub host_stream_index: UncheckedAccount<'info>,
+
+    /// CHECK: Growth partner credited with referring the host; only recorded on the host's first stream
+    pub referrer: Option<UncheckedAccount<'info>>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(init,
+        associated_token::mint=mint,
+        associated_token::authority=stream,
+        payer=host)]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = host,
+        space = DonorAccount::INIT_SPACE,
+        seeds = [b"donor", stream.key().as_ref(), donor.key().as_ref()],
+        bump
+    )]
+    pub donor_account: Account<'info, DonorAccount>,
+
+    #[account(
+        init,
+        payer = host,
+        space = DonationReceipt::INIT_SPACE,
+        seeds = [b"receipt", stream.key().as_ref(), 0u64.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, DonationReceipt>,
+
+    #[account(
+        mut,
+        constraint = donor_ata.owner == donor.key(),
+        constraint = donor_ata.mint == mint.key()
+    )]
+    pub donor_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl <'info> InitializeAndDeposit <'info> {
+    /// The stream is brand new, so the opening deposit is taken for every stream type,
+    /// including live streams that have not started yet
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_and_deposit(&mut self, name: String, stream_type: StreamType, end_time: Option<i64>, refund_deadline: Option<i64>, amount: u64, bumps: &InitializeAndDepositBumps, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        self.stream.set_inner(new_stream_state(
+            self.host.key(),
+            self.host_profile.streams_created,
+            name,
+            bumps.stream,
+            self.mint.key(),
             stream_type,
-            created_at: Clock::get()?.unix_timestamp,
-            start_time: None,
+            end_time,
+            refund_deadline,
+        )?);
+        self.host_profile.register_stream(
+            self.host.key(),
+            self.referrer.as_ref().map(|r| r.key()),
+            bumps.host_profile,
+        )?;
+        index_stream(
+            &self.host_stream_index,
+            &self.host,
+            self.stream.key(),
+            bumps.host_stream_index,
+            &self.system_program,
+        )?;
+        GlobalRegistry::record_stream(&self.global_registry)?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.donor_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.stream_ata.to_account_info(),
+            authority: self.donor.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let amount = amount_received(&mut self.stream_ata, 0)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        self.donor_account.record_deposit(self.stream.key(), self.donor.key(), amount, now, bumps.donor_account)?;
+        self.stream.total_deposited = amount;
+        GlobalRegistry::record_deposit(&self.global_registry, amount)?;
+
+        self.receipt.set_inner(DonationReceipt {
+            stream: self.stream.key(),
+            donor: self.donor.key(),
+            commitment: None,
+            index: 0,
+            amount,
+            message: None,
+            timestamp: now,
+            bump: bumps.receipt,
+        });
+        self.stream.receipt_count = 1;
+
+        emit!(DepositMade {
+            stream: self.stream.key(),
+            donor: self.donor.key(),
+            amount,
+            matched_amount: 0,
+            matching_sponsor: None,
+            timestamp: now
         });
         Ok(())
     }
+}
+
+/// Validates the stream parameters and builds a fresh `StreamState`; shared by the token and native SOL initializers
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn new_stream_state(host: Pubkey, stream_id: u64, name: String, bump: u8, mint: Pubkey, stream_type: StreamType, end_time: Option<i64>, refund_deadline: Option<i64>) -> Result<StreamState> {
+    StreamState::require_valid_name(&name)?;
+
+    match &stream_type {
+        StreamType::Prepaid { min_duration } => {
+            require!(*min_duration > 0, StreamError::InvalidDuration);
+        },
+        StreamType::Conditional { min_amount, unlock_time } => {
+            if let Some(amount) = min_amount {
+                require!(*amount > 0, StreamError::InvalidAmount);
+            }
+            if let Some(time) = unlock_time {
+                require!(*time > Clock::get()?.unix_timestamp, StreamError::InvalidTime);
+            }
+        }
+        StreamType::Live => {
+            // No additional validation needed
+        }
+    }
+    if let Some(deadline) = refund_deadline {
+        require!(deadline > Clock::get()?.unix_timestamp, StreamError::InvalidTime);
+    }
+
+    Ok(StreamState {
+        host,
+        stream_name: name,
+        bump,
+        total_distributed: 0,
+        total_deposited: 0,
+        status: StreamStatus::Active,
+        mint,
+        end_time,
+        stream_type,
+        created_at: Clock::get()?.unix_timestamp,
+        start_time: None,
+        goal_amount: 0,
+        milestone_percentages: Vec::new(),
+        milestones_reached: 0,
+        receipt_count: 0,
+        burn_rate_per_hour: 0,
+        last_distribution_at: 0,
+        metadata_hash: [0u8; 32],
+        approval_nonce: 0,
+        matching_active: false,
+        leaderboard_active: false,
+        refund_deadline,
+        blocked_count: 0,
+        payout_nonce: 0,
+        payout_allowlist_strict: false,
+        dust_refund_threshold: 0,
+        has_distribution_policy: false,
+        extension_rate_per_minute: 0,
+        access_gated: false,
+        stream_id,
+        burn_bps: 0,
+        extra_mints: Vec::new(),
+        version: STREAM_VERSION,
+        seed_kind: StreamSeedKind::Nonce,
+        ticket_revenue: 0,
+        refunds_written_off: 0,
+    })
 }
\ No newline at end of file
@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{StreamState, StreamError, Leaderboard, LeaderboardResized, MAX_LEADERBOARD_CAPACITY};
+
+#[derive(Accounts)]
+#[instruction(capacity: u16)]
+pub struct InitializeLeaderboard<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = host,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        init,
+        payer = host,
+        space = Leaderboard::space(capacity),
+        seeds = [b"leaderboard", stream.key().as_ref()],
+        bump
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeLeaderboard<'info> {
+    pub fn initialize_leaderboard(&mut self, capacity: u16, bumps: &InitializeLeaderboardBumps) -> Result<()> {
+        require!(
+            capacity > 0 && capacity <= MAX_LEADERBOARD_CAPACITY,
+            StreamError::InvalidLeaderboardCapacity
+        );
+
+        self.leaderboard.set_inner(Leaderboard {
+            stream: self.stream.key(),
+            capacity,
+            entries: Vec::new(),
+            bump: bumps.leaderboard,
+        });
+        self.stream.leaderboard_active = true;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(new_capacity: u16)]
+pub struct ResizeLeaderboard<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        has_one = host,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [b"leaderboard", stream.key().as_ref()],
+        bump = leaderboard.bump,
+        realloc = Leaderboard::space(new_capacity),
+        realloc::payer = host,
+        realloc::zero = false
+    )]
+    pub leaderboard: Account<'info, Leaderboard>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ResizeLeaderboard<'info> {
+    pub fn resize_leaderboard(&mut self, new_capacity: u16) -> Result<()> {
+        require!(
+            new_capacity > 0 && new_capacity <= MAX_LEADERBOARD_CAPACITY,
+            StreamError::InvalidLeaderboardCapacity
+        );
+
+        let old_capacity = self.leaderboard.capacity;
+        self.leaderboard.capacity = new_capacity;
+        self.leaderboard.entries.truncate(new_capacity as usize);
+
+        emit!(LeaderboardResized {
+            stream: self.stream.key(),
+            old_capacity,
+            new_capacity,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
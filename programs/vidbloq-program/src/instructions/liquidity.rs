@@ -0,0 +1,287 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::instructions::{
+    amount_received, pay_from_vault, FEATURE_GATES_SEED, MARKET_BOOK_SEED, MARKET_CONFIG_SEED, MARKET_SEED,
+    MARKET_VAULT_SEED,
+};
+use crate::state::{
+    AmmCurve, BettingMarket, FeatureGates, LiquidityAdded, LiquidityRemoved, LpPosition, MarketBook, MarketConfig,
+    StreamError,
+};
+
+// ============= CONSTANTS =============
+pub const LP_POSITION_SEED: &[u8] = b"lp_position";
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Deposit tokens behind a market's outcome reserves in exchange for LP shares
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = provider,
+        space = LpPosition::INIT_SPACE,
+        seeds = [LP_POSITION_SEED, betting_market.key().as_ref(), provider.key().as_ref()],
+        bump
+    )]
+    pub lp_position: Account<'info, LpPosition>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = provider_token.owner == provider.key(),
+        constraint = provider_token.mint == mint.key(),
+    )]
+    pub provider_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = provider,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = betting_market,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Redeem LP shares for their slice of the provider pool once the market has settled, closing
+/// the position once empty
+#[derive(Accounts)]
+pub struct RemoveLiquidity<'info> {
+    #[account(mut)]
+    pub provider: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        seeds = [LP_POSITION_SEED, betting_market.key().as_ref(), provider.key().as_ref()],
+        bump = lp_position.bump,
+        has_one = provider,
+    )]
+    pub lp_position: Account<'info, LpPosition>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = provider_token.owner == provider.key(),
+        constraint = provider_token.mint == mint.key(),
+    )]
+    pub provider_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Feature gates PDA; may be uninitialized
+    #[account(seeds = [FEATURE_GATES_SEED], bump)]
+    pub feature_gates: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> AddLiquidity<'info> {
+    /// The deposit is split evenly across the outcome reserves, deepening the constant-product
+    /// curve. Provider tokens sit in the vault beside the betting pool, never in it, and earn
+    /// `LP_FEE_SHARE_BPS` of the market fees settled while they are staked.
+    pub fn add_liquidity(
+        &mut self,
+        amount: u64,
+        bumps: &AddLiquidityBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.betting_market.betting_open(Clock::get()?.unix_timestamp),
+            StreamError::BettingClosed
+        );
+        require!(amount > 0, StreamError::InvalidAmount);
+        // Pool betting has no curve for providers to deepen
+        require!(
+            self.betting_market.amm_curve != AmmCurve::Parimutuel,
+            StreamError::InvalidMarketSetup
+        );
+
+        let cpi_accounts = TransferChecked {
+            from: self.provider_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.market_vault.to_account_info(),
+            authority: self.provider.to_account_info(),
+        };
+        let vault_before = self.market_vault.amount;
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let amount = amount_received(&mut self.market_vault, vault_before)?;
+
+        let market = &mut self.betting_market;
+        let lp_shares = market.lp_shares_for_deposit(amount)?;
+        require!(lp_shares > 0, StreamError::InvalidAmount);
+        market.lp_total_shares = market
+            .lp_total_shares
+            .checked_add(lp_shares)
+            .ok_or(StreamError::MathOverflow)?;
+        market.lp_pool = market.lp_pool.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+
+        {
+            let mut book = self.market_book.load_mut()?;
+            let per_outcome = amount / book.outcome_count as u64;
+            let outcome_count = book.outcome_count as usize;
+            for outcome in book.outcomes[..outcome_count].iter_mut() {
+                outcome.liquidity_reserve = outcome
+                    .liquidity_reserve
+                    .checked_add(per_outcome)
+                    .ok_or(StreamError::MathOverflow)?;
+            }
+        }
+        self.market_config.total_liquidity = self
+            .market_config
+            .total_liquidity
+            .checked_add(amount)
+            .ok_or(StreamError::MathOverflow)?;
+
+        let position = &mut self.lp_position;
+        if position.market == Pubkey::default() {
+            position.market = market.key();
+            position.provider = self.provider.key();
+            position.bump = bumps.lp_position;
+        }
+        position.lp_shares = position.lp_shares.checked_add(lp_shares).ok_or(StreamError::MathOverflow)?;
+        position.deposited = position.deposited.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+
+        emit!(LiquidityAdded {
+            market: market.key(),
+            provider: self.provider.key(),
+            amount,
+            lp_shares,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> RemoveLiquidity<'info> {
+    /// Pays out the shares' slice of the provider pool, fee cut included, and draws the
+    /// reserves down by the deposit those shares made. Locked until the market resolves or is
+    /// cancelled: depth that could leave mid-market would let a provider deepen the curve, buy
+    /// shares on it cheaply and pull the depth out again at earlier bettors' expense.
+    pub fn remove_liquidity(&mut self, lp_shares: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            lp_shares > 0 && lp_shares <= self.lp_position.lp_shares,
+            StreamError::InsufficientLpShares
+        );
+        require!(
+            self.betting_market.resolved || self.betting_market.cancelled,
+            StreamError::LiquidityLocked
+        );
+        let amount = self.betting_market.lp_redemption(lp_shares)?;
+        FeatureGates::require_not_queued(&self.feature_gates, amount)?;
+
+        let position = &mut self.lp_position;
+        let principal = (position.deposited as u128 * lp_shares as u128 / position.lp_shares as u128) as u64;
+        position.lp_shares -= lp_shares;
+        position.deposited -= principal;
+
+        let market = &mut self.betting_market;
+        market.lp_total_shares -= lp_shares;
+        market.lp_pool = market.lp_pool.checked_sub(amount).ok_or(StreamError::InsufficientFunds)?;
+
+        {
+            let mut book = self.market_book.load_mut()?;
+            let per_outcome = principal / book.outcome_count as u64;
+            let outcome_count = book.outcome_count as usize;
+            for outcome in book.outcomes[..outcome_count].iter_mut() {
+                outcome.liquidity_reserve = outcome.liquidity_reserve.saturating_sub(per_outcome);
+            }
+        }
+        self.market_config.total_liquidity = self.market_config.total_liquidity.saturating_sub(principal);
+
+        if amount > 0 {
+            pay_from_vault(
+                &self.betting_market,
+                &self.market_vault,
+                &self.mint,
+                &self.provider_token,
+                &self.token_program,
+                remaining_accounts,
+                amount,
+            )?;
+        }
+
+        emit!(LiquidityRemoved {
+            market: self.betting_market.key(),
+            provider: self.provider.key(),
+            lp_shares,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        if self.lp_position.lp_shares == 0 {
+            self.lp_position.close(self.provider.to_account_info())?;
+        }
+
+        Ok(())
+    }
+}
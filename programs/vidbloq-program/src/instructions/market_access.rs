@@ -0,0 +1,185 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::MARKET_SEED;
+use crate::state::{
+    verify_access_proof, BettingMarket, MarketAccessEntry, MarketAccessMode, MarketAccessSet, MarketBettorListed,
+    MarketBettorUnlisted, StreamError,
+};
+
+// ============= CONSTANTS =============
+pub const MARKET_ACCESS_SEED: &[u8] = b"market_access";
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Host chooses who may bet on the market
+#[derive(Accounts)]
+pub struct SetMarketAccess<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+}
+
+/// Host lists a bettor under the market's current access mode
+#[derive(Accounts)]
+#[instruction(bettor: Pubkey)]
+pub struct ListMarketBettor<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        init,
+        payer = host,
+        space = MarketAccessEntry::INIT_SPACE,
+        seeds = [MARKET_ACCESS_SEED, betting_market.key().as_ref(), bettor.as_ref()],
+        bump
+    )]
+    pub access_entry: Account<'info, MarketAccessEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Host removes a bettor's listing and takes back its rent
+#[derive(Accounts)]
+pub struct UnlistMarketBettor<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        close = host,
+        seeds = [MARKET_ACCESS_SEED, betting_market.key().as_ref(), access_entry.bettor.as_ref()],
+        bump = access_entry.bump,
+    )]
+    pub access_entry: Account<'info, MarketAccessEntry>,
+}
+
+/// Bettor adds themselves to the allowlist with a proof against the market's merkle root
+#[derive(Accounts)]
+pub struct JoinMarketAllowlist<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        init,
+        payer = bettor,
+        space = MarketAccessEntry::INIT_SPACE,
+        seeds = [MARKET_ACCESS_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump
+    )]
+    pub access_entry: Account<'info, MarketAccessEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> SetMarketAccess<'info> {
+    /// `list_root` lets bettors list themselves with `join_market_allowlist`, so large
+    /// allowlists approved off-chain need no transaction from the host per bettor
+    pub fn set_market_access(&mut self, mode: MarketAccessMode, list_root: [u8; 32]) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            list_root == [0; 32] || mode == MarketAccessMode::Allowlist,
+            StreamError::InvalidMarketSetup
+        );
+
+        self.betting_market.access_mode = mode;
+        self.betting_market.access_root = list_root;
+
+        emit!(MarketAccessSet {
+            market: self.betting_market.key(),
+            mode,
+            list_root,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> ListMarketBettor<'info> {
+    pub fn list_market_bettor(&mut self, bettor: Pubkey, bumps: &ListMarketBettorBumps) -> Result<()> {
+        let mode = self.betting_market.access_mode;
+        require!(mode != MarketAccessMode::Open, StreamError::InvalidMarketSetup);
+
+        self.access_entry.set_inner(MarketAccessEntry {
+            market: self.betting_market.key(),
+            bettor,
+            mode,
+            bump: bumps.access_entry,
+        });
+
+        emit!(MarketBettorListed {
+            market: self.betting_market.key(),
+            bettor,
+            mode,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> UnlistMarketBettor<'info> {
+    pub fn unlist_market_bettor(&mut self) -> Result<()> {
+        emit!(MarketBettorUnlisted {
+            market: self.betting_market.key(),
+            bettor: self.access_entry.bettor,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> JoinMarketAllowlist<'info> {
+    pub fn join_market_allowlist(&mut self, proof: Vec<[u8; 32]>, bumps: &JoinMarketAllowlistBumps) -> Result<()> {
+        let market = &self.betting_market;
+        require!(
+            market.access_mode == MarketAccessMode::Allowlist && market.access_root != [0; 32],
+            StreamError::MarketAccessDenied
+        );
+        require!(
+            verify_access_proof(market.access_root, &self.bettor.key(), &proof),
+            StreamError::InvalidAccessProof
+        );
+
+        self.access_entry.set_inner(MarketAccessEntry {
+            market: market.key(),
+            bettor: self.bettor.key(),
+            mode: MarketAccessMode::Allowlist,
+            bump: bumps.access_entry,
+        });
+
+        emit!(MarketBettorListed {
+            market: market.key(),
+            bettor: self.bettor.key(),
+            mode: MarketAccessMode::Allowlist,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::instructions::{
+    pay_from_vault, FEATURE_GATES_SEED, MARKET_BOOK_SEED, MARKET_CONFIG_SEED, MARKET_SEED, MARKET_VAULT_SEED, PLATFORM_CONFIG_SEED, POSITION_SEED,
+};
+use crate::state::{
+    BettingMarket, BettorPosition, FeatureGates, MarketBook, MarketCancelled, MarketConfig, PlatformConfig, PoolRolledOver, StakeRefunded,
+    StreamError,
+};
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Call off an unresolved market so every bettor can take back their stake
+#[derive(Accounts)]
+pub struct CancelMarket<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+}
+
+/// Return a bettor's stake from a cancelled market, or one nobody won, and close their position
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        close = bettor,
+        seeds = [POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump = bettor_position.bump,
+    )]
+    pub bettor_position: Account<'info, BettorPosition>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = bettor_token.owner == bettor.key(),
+        constraint = bettor_token.mint == mint.key(),
+    )]
+    pub bettor_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Feature gates PDA; may be uninitialized
+    #[account(seeds = [FEATURE_GATES_SEED], bump)]
+    pub feature_gates: UncheckedAccount<'info>,
+}
+
+/// Host carries what a void round left in its vault into the liquidity of the next round, for
+/// tournaments played as a chain of markets
+#[derive(Accounts)]
+pub struct RolloverPool<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Successor round of the same host and mint
+    #[account(
+        seeds = [MARKET_SEED, target_market.stream.as_ref()],
+        bump = target_market.bump,
+        constraint = target_market.key() != betting_market.key() @ StreamError::InvalidMarketSetup,
+        constraint = target_market.host == host.key() @ StreamError::Unauthorized,
+        constraint = target_market.mint == mint.key() @ StreamError::InvalidMint,
+    )]
+    pub target_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_BOOK_SEED, target_market.key().as_ref()],
+        bump = target_book.load()?.bump,
+    )]
+    pub target_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_CONFIG_SEED, target_market.key().as_ref()],
+        bump = target_config.bump,
+    )]
+    pub target_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, target_market.key().as_ref()],
+        bump,
+    )]
+    pub target_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> CancelMarket<'info> {
+    /// The host or the platform admin may cancel. A cancelled market also counts as resolved,
+    /// with no winner, so betting, selling and every resolution path stay closed.
+    pub fn cancel_market(&mut self) -> Result<()> {
+        let authority = self.authority.key();
+        let is_admin = PlatformConfig::load(&self.platform_config)?
+            .is_some_and(|config| config.admin == authority);
+        require!(authority == self.betting_market.host || is_admin, StreamError::Unauthorized);
+        require!(!self.betting_market.cancelled, StreamError::MarketCancelled);
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+
+        let market = &mut self.betting_market;
+        market.cancelled = true;
+        market.resolved = true;
+        market.winning_outcome = None;
+        market.open_claim_window()?;
+
+        emit!(MarketCancelled {
+            market: market.key(),
+            cancelled_by: authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> ClaimRefund<'info> {
+    /// Pays back everything the position still has invested. Stake placed with free bet
+    /// credit goes to the host's withheld balance instead, the same as on a winning claim.
+    /// A market that resolved to outcomes nobody holds shares in refunds the same way.
+    /// Outcome positions are left open; the market counts as resolved, so once this closes
+    /// the bettor position each one can be reclaimed with `close_outcome_position`.
+    pub fn claim_refund(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            self.betting_market.cancelled
+                || self.betting_market.no_winning_shares(&*self.market_book.load()?, &self.market_config),
+            StreamError::MarketNotCancelled
+        );
+        require!(!self.bettor_position.has_claimed, StreamError::AlreadyClaimed);
+        self.betting_market.require_claim_window_open()?;
+
+        let invested = self.bettor_position.total_invested;
+        let credit_withheld = invested.min(self.bettor_position.credit_staked);
+        let amount = invested - credit_withheld;
+
+        FeatureGates::require_not_queued(&self.feature_gates, amount)?;
+
+        msg!("Refunding {} USDC", amount);
+
+        self.betting_market.free_bet_withheld = self
+            .betting_market
+            .free_bet_withheld
+            .checked_add(credit_withheld)
+            .ok_or(StreamError::MathOverflow)?;
+        if amount > 0 {
+            pay_from_vault(
+                &self.betting_market,
+                &self.market_vault,
+                &self.mint,
+                &self.bettor_token,
+                &self.token_program,
+                remaining_accounts,
+                amount,
+            )?;
+        }
+
+        emit!(StakeRefunded {
+            market: self.betting_market.key(),
+            bettor: self.bettor.key(),
+            amount,
+            credit_withheld,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> RolloverPool<'info> {
+    /// Only a cancelled market or one nobody won can roll over, and only once its claim window
+    /// has closed, so every bettor has had the chance to take their refund. Everything in the
+    /// vault but the liquidity providers' pool moves, fees, dust and withheld free bet stakes
+    /// included, the same as `sweep_unclaimed`.
+    pub fn rollover_pool(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            self.betting_market.cancelled
+                || self.betting_market.no_winning_shares(&*self.market_book.load()?, &self.market_config),
+            StreamError::MarketNotCancelled
+        );
+        require!(now > self.betting_market.claim_deadline, StreamError::ClaimWindowOpen);
+        require!(!self.target_market.resolved, StreamError::MarketResolved);
+        let amount = self.market_vault.amount.saturating_sub(self.betting_market.lp_pool);
+        require!(amount > 0, StreamError::NothingToSweep);
+
+        self.target_book.load_mut()?.credit_liquidity(&self.target_market.amm_curve, amount)?;
+        self.target_config.total_liquidity = self
+            .target_config
+            .total_liquidity
+            .checked_add(amount)
+            .ok_or(StreamError::MathOverflow)?;
+
+        let market = &mut self.betting_market;
+        market.fees_collected = 0;
+        market.dust_accumulator = 0;
+        market.free_bet_withheld = 0;
+
+        let market_seeds = &[MARKET_SEED, market.stream.as_ref(), &[market.bump]];
+        let signer = &[&market_seeds[..]];
+        let cpi_accounts = TransferChecked {
+            from: self.market_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.target_vault.to_account_info(),
+            authority: market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        emit!(PoolRolledOver {
+            market: market.key(),
+            target_market: self.target_market.key(),
+            amount,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
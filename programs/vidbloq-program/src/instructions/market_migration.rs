@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use crate::instructions::{MARKET_BOOK_SEED, MARKET_CONFIG_SEED, MARKET_SEED};
+use crate::state::{
+    AmmCurve, BettingMarket, LegacyBettingMarket, MarketAccessMode, MarketBook, MarketConfig, MarketMigrated, ResolutionSource, StreamError,
+    DEFAULT_QUORUM_BPS, DEFAULT_SUPERMAJORITY_BPS, MARKET_VERSION, MAX_MARKET_OUTCOMES, MAX_OUTCOME_DESCRIPTION_LEN,
+};
+
+/// Splits a market created before `MarketBook` and `MarketConfig` existed into the header and
+/// its two sidecars. Anyone may migrate a market; the payer funds the sidecars and gets back the
+/// rent the smaller header no longer needs. Fails once the market has a config.
+#[derive(Accounts)]
+pub struct MigrateBettingMarket<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Market in the legacy layout; owner, discriminator and address are checked on read
+    #[account(mut, owner = crate::ID)]
+    pub betting_market: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MarketConfig::INIT_SPACE,
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = MarketBook::SPACE,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateBettingMarket<'info> {
+    pub fn migrate_betting_market(&mut self, bumps: &MigrateBettingMarketBumps) -> Result<()> {
+        let info = self.betting_market.to_account_info();
+        let legacy = {
+            let data = info.try_borrow_data()?;
+            require!(
+                data.len() > 8 && data[..8] == *BettingMarket::DISCRIMINATOR,
+                StreamError::InvalidMarketSetup
+            );
+            LegacyBettingMarket::deserialize(&mut &data[8..])?
+        };
+        let expected = Pubkey::create_program_address(
+            &[MARKET_SEED, legacy.stream.as_ref(), &[legacy.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| StreamError::InvalidMarketSetup)?;
+        require_keys_eq!(expected, info.key(), StreamError::InvalidMarketSetup);
+        require!(
+            legacy.outcomes.len() <= MAX_MARKET_OUTCOMES
+                && legacy.outcomes.iter().all(|o| o.description.len() <= MAX_OUTCOME_DESCRIPTION_LEN),
+            StreamError::InvalidMarketSetup
+        );
+
+        let market_key = info.key();
+        let mut book = self.market_book.load_init()?;
+        book.market = market_key;
+        book.total_pool = legacy.total_pool;
+        book.outcome_count = legacy.outcomes.len() as u8;
+        book.bump = bumps.market_book;
+        for (slot, outcome) in book.outcomes.iter_mut().zip(legacy.outcomes.iter()) {
+            slot.total_shares = outcome.total_shares;
+            slot.liquidity_reserve = outcome.liquidity_reserve;
+            slot.total_backing = outcome.total_backing;
+        }
+        drop(book);
+
+        self.market_config.set_inner(MarketConfig {
+            market: market_key,
+            market_type: legacy.market_type,
+            outcome_descriptions: legacy.outcomes.iter().map(|o| o.description.clone()).collect(),
+            fee_percentage: legacy.fee_percentage,
+            payout_plan: legacy.payout_plan,
+            total_liquidity: legacy.total_liquidity,
+            rules_uri: legacy.rules_uri,
+            rules_hash: legacy.rules_hash,
+            consensus_bps: legacy.consensus_bps,
+            quorum_validators: legacy.quorum_validators,
+            created_at: legacy.created_at,
+            metric_source: legacy.host,
+            reported_metric: None,
+            resolution_timeout: 0,
+            resolution_source: ResolutionSource::Manual,
+            bump: bumps.market_config,
+            referral_share_bps: 0,
+            quorum_bps: DEFAULT_QUORUM_BPS,
+            supermajority_bps: DEFAULT_SUPERMAJORITY_BPS,
+        });
+
+        let mut header = BettingMarket {
+            stream: legacy.stream,
+            host: legacy.host,
+            mint: legacy.mint,
+            outcome_count: legacy.outcomes.len() as u8,
+            resolution_time: legacy.resolution_time,
+            resolved: legacy.resolved,
+            winning_outcome: legacy.winning_outcome,
+            randomness_requested: legacy.randomness_requested,
+            bump: legacy.bump,
+            ranked_outcomes: legacy.ranked_outcomes,
+            taper: legacy.taper,
+            settlement_hash: legacy.settlement_hash,
+            free_bet_withheld: legacy.free_bet_withheld,
+            dust_accumulator: legacy.dust_accumulator,
+            unsettled_shares: legacy.unsettled_shares,
+            settled_gross: legacy.settled_gross,
+            amm_curve: AmmCurve::ConstantProduct,
+            version: MARKET_VERSION,
+            cancelled: false,
+            fees_collected: 0,
+            lp_total_shares: 0,
+            lp_pool: 0,
+            max_bet_per_user: None,
+            max_total_pool: None,
+            access_mode: MarketAccessMode::Open,
+            access_root: [0; 32],
+            betting_closed_at: None,
+            claim_deadline: 0,
+        };
+        if header.resolved {
+            header.open_claim_window()?;
+        }
+
+        info.resize(BettingMarket::INIT_SPACE)?;
+        header.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        let excess = info
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(BettingMarket::INIT_SPACE));
+        **info.try_borrow_mut_lamports()? -= excess;
+        **self.payer.to_account_info().try_borrow_mut_lamports()? += excess;
+
+        emit!(MarketMigrated {
+            market: market_key,
+            market_book: self.market_book.key(),
+            market_config: self.market_config.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{Mint, TokenAccount, TokenInterface, CloseAccount, close_account, TransferChecked, transfer_checked}
+};
+
+use crate::instructions::amount_received;
+use crate::state::{
+    StreamState, StreamStatus, StreamError, MatchingPledge, MatchingPledgeCreated,
+    MatchingPledgeClosed, MAX_MATCH_RATIO_BPS
+};
+
+#[derive(Accounts)]
+pub struct CreateMatchingPledge<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(address = stream.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = sponsor,
+        space = MatchingPledge::INIT_SPACE,
+        seeds = [b"matching_pledge", stream.key().as_ref()],
+        bump
+    )]
+    pub matching_pledge: Account<'info, MatchingPledge>,
+
+    #[account(
+        init,
+        payer = sponsor,
+        seeds = [b"pledge_vault", stream.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = matching_pledge,
+    )]
+    pub pledge_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = sponsor_ata.owner == sponsor.key(),
+        constraint = sponsor_ata.mint == stream.mint
+    )]
+    pub sponsor_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>
+}
+
+impl<'info> CreateMatchingPledge<'info> {
+    pub fn create_matching_pledge(&mut self, ratio_bps: u16, cap: u64, bumps: &CreateMatchingPledgeBumps, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(cap > 0, StreamError::InvalidAmount);
+        require!(
+            ratio_bps > 0 && ratio_bps <= MAX_MATCH_RATIO_BPS,
+            StreamError::InvalidMatchRatio
+        );
+        require!(
+            self.stream.status == StreamStatus::Active,
+            StreamError::StreamNotActive
+        );
+
+        let cpi_accounts = TransferChecked {
+            from: self.sponsor_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.pledge_vault.to_account_info(),
+            authority: self.sponsor.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, cap, self.mint.decimals)?;
+        // The cap is what the vault actually holds after any transfer fee
+        let cap = amount_received(&mut self.pledge_vault, 0)?;
+
+        self.matching_pledge.set_inner(MatchingPledge {
+            stream: self.stream.key(),
+            sponsor: self.sponsor.key(),
+            ratio_bps,
+            cap,
+            matched: 0,
+            bump: bumps.matching_pledge,
+        });
+        self.stream.matching_active = true;
+
+        emit!(MatchingPledgeCreated {
+            stream: self.stream.key(),
+            sponsor: self.sponsor.key(),
+            ratio_bps,
+            cap,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseMatchingPledge<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(address = stream.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        close = sponsor,
+        has_one = sponsor,
+        seeds = [b"matching_pledge", stream.key().as_ref()],
+        bump = matching_pledge.bump
+    )]
+    pub matching_pledge: Account<'info, MatchingPledge>,
+
+    #[account(
+        mut,
+        seeds = [b"pledge_vault", stream.key().as_ref()],
+        bump
+    )]
+    pub pledge_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = sponsor_ata.owner == sponsor.key(),
+        constraint = sponsor_ata.mint == stream.mint
+    )]
+    pub sponsor_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>
+}
+
+impl<'info> CloseMatchingPledge<'info> {
+    pub fn close_matching_pledge(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Unmatched funds stay committed for as long as the campaign is running
+        require!(
+            self.stream.status != StreamStatus::Active,
+            StreamError::StreamStillActive
+        );
+
+        let returned = self.pledge_vault.amount;
+        let stream_key = self.stream.key();
+        let pledge_seeds = &[
+            b"matching_pledge".as_ref(),
+            stream_key.as_ref(),
+            &[self.matching_pledge.bump],
+        ];
+        let signer = &[&pledge_seeds[..]];
+
+        if returned > 0 {
+            let cpi_accounts = TransferChecked {
+                from: self.pledge_vault.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: self.sponsor_ata.to_account_info(),
+                authority: self.matching_pledge.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+            transfer_checked(cpi_ctx, returned, self.mint.decimals)?;
+        }
+
+        // Return the vault's rent to the sponsor as well
+        let cpi_accounts = CloseAccount {
+            account: self.pledge_vault.to_account_info(),
+            destination: self.sponsor.to_account_info(),
+            authority: self.matching_pledge.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer);
+        close_account(cpi_ctx)?;
+        self.stream.matching_active = false;
+
+        emit!(MatchingPledgeClosed {
+            stream: self.stream.key(),
+            sponsor: self.sponsor.key(),
+            matched: self.matching_pledge.matched,
+            returned,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
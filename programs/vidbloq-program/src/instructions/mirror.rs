@@ -0,0 +1,302 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::instructions::{
+    amount_received, enforce_access_gate, record_bet, require_bet_open, ACCESS_GATE_SEED, MARKET_ACCESS_SEED,
+    MARKET_BOOK_SEED, MARKET_SEED, MARKET_STATS_SEED, MARKET_VAULT_SEED, OUTCOME_POSITION_SEED, POSITION_SEED,
+};
+use crate::state::{
+    AccessGate, BetMirrored, BettingMarket, BettorPosition, Blocklist, MarketAccessEntry, MarketBook, MirrorConfig,
+    MirrorPosition, MirroringEnabled, OutcomePositionAccount, StreamError, StreamState, MAX_MIRROR_REVENUE_SHARE_BPS,
+};
+
+// ============= CONSTANTS =============
+pub const MIRROR_CONFIG_SEED: &[u8] = b"mirror_config";
+pub const MIRROR_POSITION_SEED: &[u8] = b"mirror_position";
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Host lets viewers mirror their bets on a market, or those of a tipster they designate
+#[derive(Accounts)]
+pub struct EnableHostPositionMirroring<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        space = MirrorConfig::INIT_SPACE,
+        seeds = [MIRROR_CONFIG_SEED, betting_market.key().as_ref()],
+        bump
+    )]
+    pub mirror_config: Account<'info, MirrorConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Copy the tipster's latest bet with a stake of the follower's choosing
+#[derive(Accounts)]
+pub struct MirrorBet<'info> {
+    #[account(mut)]
+    pub follower: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = stream,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [MIRROR_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = mirror_config.bump,
+        constraint = mirror_config.latest_outcome.is_some() @ StreamError::NoBetToMirror,
+    )]
+    pub mirror_config: Account<'info, MirrorConfig>,
+
+    /// Tipster's position in the outcome of their latest bet
+    #[account(
+        seeds = [
+            OUTCOME_POSITION_SEED,
+            betting_market.key().as_ref(),
+            mirror_config.tipster.as_ref(),
+            mirror_config.latest_outcome.unwrap_or_default().to_le_bytes().as_ref(),
+        ],
+        bump = source_position.bump,
+    )]
+    pub source_position: Account<'info, OutcomePositionAccount>,
+
+    /// CHECK: Blocklist page for the follower's bucket, required while the stream has blocked wallets
+    #[account(
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&follower.key())]],
+        bump
+    )]
+    pub blocklist: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Follower's access listing, required while the market is not open to everyone
+    #[account(
+        seeds = [MARKET_ACCESS_SEED, betting_market.key().as_ref(), follower.key().as_ref()],
+        bump
+    )]
+    pub market_access: Option<UncheckedAccount<'info>>,
+
+    /// Required while the stream is access gated
+    #[account(
+        seeds = [ACCESS_GATE_SEED, stream.key().as_ref()],
+        bump = access_gate.bump
+    )]
+    pub access_gate: Option<Account<'info, AccessGate>>,
+
+    /// Follower's token account for the gate mint, required while the stream is access gated
+    pub gate_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = follower,
+        space = BettorPosition::INIT_SPACE,
+        seeds = [POSITION_SEED, betting_market.key().as_ref(), follower.key().as_ref()],
+        bump
+    )]
+    pub bettor_position: Account<'info, BettorPosition>,
+
+    #[account(
+        init_if_needed,
+        payer = follower,
+        space = OutcomePositionAccount::INIT_SPACE,
+        seeds = [
+            OUTCOME_POSITION_SEED,
+            betting_market.key().as_ref(),
+            follower.key().as_ref(),
+            source_position.outcome_id.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub outcome_position: Account<'info, OutcomePositionAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = follower,
+        space = MirrorPosition::INIT_SPACE,
+        seeds = [
+            MIRROR_POSITION_SEED,
+            betting_market.key().as_ref(),
+            follower.key().as_ref(),
+            source_position.outcome_id.to_le_bytes().as_ref(),
+        ],
+        bump
+    )]
+    pub mirror_position: Account<'info, MirrorPosition>,
+
+    #[account(
+        constraint = mint.key() == betting_market.mint @ StreamError::InvalidMint
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = follower_token.owner == follower.key(),
+        constraint = follower_token.mint == mint.key(),
+    )]
+    pub follower_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = follower,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = betting_market,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Market stats PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [MARKET_STATS_SEED, betting_market.key().as_ref()], bump)]
+    pub market_stats: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+// ============= INSTRUCTIONS IMPLEMENTATION =============
+
+impl<'info> EnableHostPositionMirroring<'info> {
+    pub fn enable_host_position_mirroring(
+        &mut self,
+        tipster: Option<Pubkey>,
+        revenue_share_bps: u16,
+        bumps: &EnableHostPositionMirroringBumps,
+    ) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(revenue_share_bps <= MAX_MIRROR_REVENUE_SHARE_BPS, StreamError::InvalidFeePercentage);
+        let tipster = tipster.unwrap_or(self.host.key());
+        require_keys_neq!(tipster, Pubkey::default(), StreamError::Unauthorized);
+
+        let config = &mut self.mirror_config;
+        // A new tipster starts with no bet to copy
+        if config.market == Pubkey::default() || config.tipster != tipster {
+            config.latest_outcome = None;
+            config.latest_stake = 0;
+            config.latest_at = 0;
+        }
+        config.market = self.betting_market.key();
+        config.tipster = tipster;
+        config.revenue_share_bps = revenue_share_bps;
+        config.bump = bumps.mirror_config;
+
+        emit!(MirroringEnabled {
+            market: self.betting_market.key(),
+            tipster,
+            revenue_share_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> MirrorBet<'info> {
+    pub fn mirror_bet(
+        &mut self,
+        amount: u64,
+        min_shares: u64,
+        bumps: &MirrorBetBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let follower = self.follower.key();
+        let outcome_id = self.source_position.outcome_id;
+        require_keys_neq!(follower, self.mirror_config.tipster, StreamError::Unauthorized);
+        require_bet_open(&self.betting_market, outcome_id, amount)?;
+        Blocklist::require_not_blocked(
+            self.stream.blocked_count,
+            self.blocklist.as_ref().map(|b| b.as_ref()),
+            &follower,
+        )?;
+        MarketAccessEntry::require_access(
+            self.betting_market.access_mode,
+            self.market_access.as_ref().map(|e| e.as_ref()),
+            &follower,
+        )?;
+        enforce_access_gate(
+            &self.stream,
+            self.access_gate.as_ref(),
+            self.gate_token_account.as_ref(),
+            &follower,
+        )?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.follower_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.market_vault.to_account_info(),
+            authority: self.follower.to_account_info(),
+        };
+        let vault_before = self.market_vault.amount;
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let amount = amount_received(&mut self.market_vault, vault_before)?;
+
+        let shares = record_bet(
+            &self.betting_market,
+            &self.market_book,
+            &mut self.bettor_position,
+            &mut self.outcome_position,
+            &self.market_stats,
+            follower,
+            outcome_id,
+            amount,
+            min_shares,
+            (bumps.bettor_position, bumps.outcome_position),
+        )?;
+
+        let mirror = &mut self.mirror_position;
+        if mirror.market == Pubkey::default() {
+            mirror.market = self.betting_market.key();
+            mirror.follower = follower;
+            mirror.outcome_id = outcome_id;
+            mirror.bump = bumps.mirror_position;
+        }
+        // The latest tipster and terms apply to the whole mirrored stake on the outcome
+        mirror.tipster = self.mirror_config.tipster;
+        mirror.source_position = self.source_position.key();
+        mirror.revenue_share_bps = self.mirror_config.revenue_share_bps;
+        mirror.staked = mirror.staked.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        mirror.shares = mirror.shares.checked_add(shares).ok_or(StreamError::MathOverflow)?;
+        self.mirror_config.mirrored_volume = self
+            .mirror_config
+            .mirrored_volume
+            .checked_add(amount)
+            .ok_or(StreamError::MathOverflow)?;
+
+        emit!(BetMirrored {
+            market: self.betting_market.key(),
+            follower,
+            tipster: self.mirror_config.tipster,
+            source_position: self.source_position.key(),
+            outcome_id,
+            amount,
+            shares,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
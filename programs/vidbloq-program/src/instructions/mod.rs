@@ -9,4 +9,94 @@ pub use refund::*;
 pub mod stream_controls;
 pub use stream_controls::*;
 pub mod betting;
-pub use betting::*;
\ No newline at end of file
+pub use betting::*;
+pub mod sweep;
+pub use sweep::*;
+pub mod optimistic;
+pub use optimistic::*;
+pub mod tiers;
+pub use tiers::*;
+pub mod host_approval;
+pub use host_approval::*;
+pub mod matching;
+pub use matching::*;
+pub mod leaderboard;
+pub use leaderboard::*;
+pub mod blocklist;
+pub use blocklist::*;
+pub mod voucher;
+pub use voucher::*;
+pub mod sol_stream;
+pub use sol_stream::*;
+pub mod referral;
+pub use referral::*;
+pub mod stream_mints;
+pub use stream_mints::*;
+pub mod feature_gates;
+pub use feature_gates::*;
+pub mod withdrawal_queue;
+pub use withdrawal_queue::*;
+pub mod tax_receipt;
+pub use tax_receipt::*;
+pub mod platform;
+pub use platform::*;
+pub mod payout;
+pub use payout::*;
+pub mod oracle;
+pub use oracle::*;
+pub mod distribution_policy;
+pub use distribution_policy::*;
+pub mod split;
+pub use split::*;
+pub mod allowance;
+pub use allowance::*;
+pub mod free_bet;
+pub use free_bet::*;
+pub mod extension;
+pub use extension::*;
+pub mod attendance;
+pub use attendance::*;
+pub mod clawback;
+pub use clawback::*;
+pub mod access_gate;
+pub use access_gate::*;
+pub mod host_bond;
+pub use host_bond::*;
+pub mod registry;
+pub use registry::*;
+pub mod market_migration;
+pub use market_migration::*;
+pub mod stream_migration;
+pub use stream_migration::*;
+pub mod sell_shares;
+pub use sell_shares::*;
+pub mod market_cancel;
+pub use market_cancel::*;
+pub mod liquidity;
+pub use liquidity::*;
+pub mod market_access;
+pub use market_access::*;
+pub mod parlay;
+pub use parlay::*;
+pub mod unclaimed;
+pub use unclaimed::*;
+pub mod dispute;
+pub use dispute::*;
+pub mod validator_rewards;
+pub use validator_rewards::*;
+pub mod validator_escrow;
+pub use validator_escrow::*;
+pub mod raffle;
+pub use raffle::*;
+pub mod bonus_pool;
+pub use bonus_pool::*;
+pub mod session;
+pub use session::*;
+pub mod mirror;
+pub use mirror::*;
+pub mod validator_registry;
+pub use validator_registry::*;
+pub mod resolution_crank;
+pub use resolution_crank::*;
+pub mod token_utils;
+pub use token_utils::*;
@@ -0,0 +1,445 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::instructions::{amount_received, DISPUTE_WINDOW, MARKET_BOOK_SEED, MARKET_CONFIG_SEED, MARKET_SEED, ORACLE_SEED, RESOLUTION_SEED};
+use crate::state::{
+    BettingMarket, ChallengeSettled, MarketBook, MarketConfig, MarketOracle, MarketResolution, OptimisticProposal, OutcomeProposed,
+    ProposalChallenged, ProposalFinalized, ProposalStatus, ResolutionPayoutPlan, ResolutionStatus,
+    StreamError, notify, NotificationCode,
+};
+
+// ============= CONSTANTS =============
+pub const PROPOSAL_SEED: &[u8] = b"optimistic_proposal";
+pub const PROPOSAL_BOND_VAULT_SEED: &[u8] = b"proposal_bond_vault";
+pub const MIN_PROPOSAL_BOND: u64 = 5_000_000; // 5 USDC minimum
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Propose an outcome backed by a bond
+#[derive(Accounts)]
+pub struct ProposeOutcome<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = OptimisticProposal::INIT_SPACE,
+        seeds = [PROPOSAL_SEED, betting_market.key().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, OptimisticProposal>,
+
+    #[account(
+        constraint = mint.key() == betting_market.mint @ StreamError::InvalidMint
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = proposer_token.owner == proposer.key(),
+        constraint = proposer_token.mint == mint.key(),
+    )]
+    pub proposer_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = proposer,
+        seeds = [PROPOSAL_BOND_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = proposal,
+    )]
+    pub bond_vault: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: Oracle PDA; while initialized, only its agreed value may resolve the market
+    #[account(seeds = [ORACLE_SEED, betting_market.key().as_ref()], bump)]
+    pub market_oracle: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Challenge a pending proposal with a matching bond
+#[derive(Accounts)]
+pub struct ChallengeProposal<'info> {
+    #[account(mut)]
+    pub challenger: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [PROPOSAL_SEED, betting_market.key().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, OptimisticProposal>,
+
+    #[account(
+        mut,
+        constraint = challenger_token.owner == challenger.key(),
+        constraint = challenger_token.mint == betting_market.mint @ StreamError::InvalidMint,
+    )]
+    pub challenger_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROPOSAL_BOND_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub bond_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Finalize an unchallenged proposal once the challenge window has passed
+#[derive(Accounts)]
+pub struct FinalizeProposal<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [PROPOSAL_SEED, betting_market.key().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, OptimisticProposal>,
+
+    #[account(
+        mut,
+        constraint = proposer_token.owner == proposal.proposer @ StreamError::Unauthorized,
+        constraint = proposer_token.mint == betting_market.mint @ StreamError::InvalidMint,
+    )]
+    pub proposer_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROPOSAL_BOND_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub bond_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Pay both bonds to the winner once validators settled a challenged proposal
+#[derive(Accounts)]
+pub struct SettleChallenge<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [PROPOSAL_SEED, betting_market.key().as_ref()],
+        bump = proposal.bump,
+    )]
+    pub proposal: Account<'info, OptimisticProposal>,
+
+    #[account(
+        seeds = [RESOLUTION_SEED, betting_market.key().as_ref()],
+        bump = resolution.load()?.bump,
+    )]
+    pub resolution: AccountLoader<'info, MarketResolution>,
+
+    /// Token account of whichever party the validators sided with
+    #[account(
+        mut,
+        constraint = winner_token.mint == betting_market.mint @ StreamError::InvalidMint,
+    )]
+    pub winner_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PROPOSAL_BOND_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub bond_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= IMPLEMENTATION =============
+
+impl<'info> ProposeOutcome<'info> {
+    pub fn propose_outcome(
+        &mut self,
+        outcome_id: u8,
+        bond_amount: u64,
+        bumps: &ProposeOutcomeBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= self.betting_market.resolution_time,
+            StreamError::MarketNotReady
+        );
+        require!(
+            outcome_id < self.betting_market.outcome_count,
+            StreamError::InvalidOutcome
+        );
+        require!(bond_amount >= MIN_PROPOSAL_BOND, StreamError::BondTooLow);
+        require!(
+            MarketOracle::load(&self.market_oracle)?.is_none(),
+            StreamError::OracleResolutionRequired
+        );
+        require!(
+            self.market_config.payout_plan == ResolutionPayoutPlan::WinnerTakesAll,
+            StreamError::RankedResolutionRequired
+        );
+        self.market_config.resolution_source.require_manual()?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.proposer_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.bond_vault.to_account_info(),
+            authority: self.proposer.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, bond_amount, self.mint.decimals)?;
+        // The recorded bond is what the vault actually received after any transfer fee
+        let bond_amount = amount_received(&mut self.bond_vault, 0)?;
+        require!(bond_amount >= MIN_PROPOSAL_BOND, StreamError::BondTooLow);
+
+        let challenge_deadline = now
+            .checked_add(DISPUTE_WINDOW)
+            .ok_or(StreamError::MathOverflow)?;
+
+        self.proposal.set_inner(OptimisticProposal {
+            market: self.betting_market.key(),
+            proposer: self.proposer.key(),
+            proposed_outcome: outcome_id,
+            bond_amount,
+            proposed_at: now,
+            challenge_deadline,
+            challenger: None,
+            status: ProposalStatus::Proposed,
+            bump: bumps.proposal,
+        });
+
+        emit!(OutcomeProposed {
+            market: self.betting_market.key(),
+            proposer: self.proposer.key(),
+            proposed_outcome: outcome_id,
+            bond_amount,
+            challenge_deadline,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ChallengeProposal<'info> {
+    pub fn challenge_proposal(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.proposal.status == ProposalStatus::Proposed,
+            StreamError::InvalidProposalState
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now < self.proposal.challenge_deadline,
+            StreamError::ChallengeWindowClosed
+        );
+        require!(
+            self.challenger.key() != self.proposal.proposer,
+            StreamError::Unauthorized
+        );
+
+        // The challenger must match the proposer's bond
+        let cpi_accounts = TransferChecked {
+            from: self.challenger_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.bond_vault.to_account_info(),
+            authority: self.challenger.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, self.proposal.bond_amount, self.mint.decimals)?;
+
+        self.proposal.challenger = Some(self.challenger.key());
+        self.proposal.status = ProposalStatus::Challenged;
+
+        msg!("Proposal challenged, escalating to validator resolution");
+
+        emit!(ProposalChallenged {
+            market: self.betting_market.key(),
+            challenger: self.challenger.key(),
+            proposed_outcome: self.proposal.proposed_outcome,
+            bond_amount: self.proposal.bond_amount,
+            timestamp: now,
+        });
+        notify(
+            NotificationCode::DisputeOpened,
+            self.betting_market.key(),
+            [self.proposal.bond_amount, self.proposal.proposed_outcome as u64],
+            now,
+        );
+
+        Ok(())
+    }
+}
+
+impl<'info> FinalizeProposal<'info> {
+    pub fn finalize_proposal(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.proposal.status == ProposalStatus::Proposed,
+            StreamError::InvalidProposalState
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= self.proposal.challenge_deadline,
+            StreamError::ChallengeWindowOpen
+        );
+
+        let winning_outcome = self.proposal.proposed_outcome;
+        self.betting_market.winning_outcome = Some(winning_outcome);
+        self.betting_market.record_resolution(&*self.market_book.load()?, &self.market_config)?;
+        self.proposal.status = ProposalStatus::Finalized;
+
+        // Return the unchallenged bond to the proposer
+        let market_key = self.betting_market.key();
+        let proposal_seeds = &[
+            PROPOSAL_SEED,
+            market_key.as_ref(),
+            &[self.proposal.bump],
+        ];
+        let signer = &[&proposal_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.bond_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.proposer_token.to_account_info(),
+            authority: self.proposal.to_account_info(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, self.proposal.bond_amount, self.mint.decimals)?;
+
+        emit!(ProposalFinalized {
+            market: market_key,
+            proposer: self.proposal.proposer,
+            winning_outcome,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> SettleChallenge<'info> {
+    pub fn settle_challenge(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            self.proposal.status == ProposalStatus::Challenged,
+            StreamError::InvalidProposalState
+        );
+        let resolution = self.resolution.load()?;
+        require!(
+            resolution.status_is(ResolutionStatus::Finalized),
+            StreamError::InvalidResolutionState
+        );
+        let validated_outcome = resolution
+            .proposed_outcome()
+            .ok_or(StreamError::InvalidResolutionState)?;
+        drop(resolution);
+
+        let proposal_upheld = validated_outcome == self.proposal.proposed_outcome;
+        let winner = if proposal_upheld {
+            self.proposal.proposer
+        } else {
+            self.proposal.challenger.ok_or(StreamError::InvalidProposalState)?
+        };
+        require!(
+            self.winner_token.owner == winner,
+            StreamError::Unauthorized
+        );
+
+        // The loser's bond goes to the winner alongside their own. Paying out the vault balance
+        // rather than twice the bond keeps settlement exact when the mint charges transfer fees.
+        let payout = self.bond_vault.amount;
+
+        let market_key = self.betting_market.key();
+        let proposal_seeds = &[
+            PROPOSAL_SEED,
+            market_key.as_ref(),
+            &[self.proposal.bump],
+        ];
+        let signer = &[&proposal_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.bond_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.winner_token.to_account_info(),
+            authority: self.proposal.to_account_info(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, payout, self.mint.decimals)?;
+
+        self.proposal.status = ProposalStatus::Settled;
+
+        emit!(ChallengeSettled {
+            market: market_key,
+            winner,
+            proposal_upheld,
+            payout,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
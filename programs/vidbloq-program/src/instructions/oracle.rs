@@ -0,0 +1,458 @@
+use anchor_lang::prelude::*;
+
+use crate::instructions::{MARKET_BOOK_SEED, MARKET_CONFIG_SEED, MARKET_SEED};
+use crate::state::{
+    BettingMarket, MarketBook, MarketConfig, MarketOracle, MarketResolved, MarketType, MetricReported, OracleAgreed, OracleConfigured, OracleDisputed,
+    OraclePrice, OracleResultSubmitted, OracleRoundOpened, OracleStatus, OracleSubmission, PriceOracleResolved, ResolutionPayoutPlan, ResolutionSource,
+    StreamError, MAX_ORACLE_REPORTERS, notify, NotificationCode,
+};
+
+// ============= CONSTANTS =============
+pub const ORACLE_SEED: &[u8] = b"market_oracle";
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Attach a reporter set to a numeric market before it closes. Once configured, the market
+/// can only be resolved from the reporters' agreed value.
+#[derive(Accounts)]
+pub struct ConfigureMarketOracle<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        init,
+        payer = host,
+        space = MarketOracle::INIT_SPACE,
+        seeds = [ORACLE_SEED, betting_market.key().as_ref()],
+        bump
+    )]
+    pub market_oracle: Account<'info, MarketOracle>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Record one reporter's observed value for the current round
+#[derive(Accounts)]
+pub struct SubmitOracleResult<'info> {
+    pub reporter: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [ORACLE_SEED, betting_market.key().as_ref()],
+        bump = market_oracle.bump,
+    )]
+    pub market_oracle: Account<'info, MarketOracle>,
+}
+
+/// Discard a disputed round so reporters can submit again
+#[derive(Accounts)]
+pub struct OpenOracleRound<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [ORACLE_SEED, betting_market.key().as_ref()],
+        bump = market_oracle.bump,
+    )]
+    pub market_oracle: Account<'info, MarketOracle>,
+}
+
+/// Resolve a numeric market from the agreed oracle value; callable by anyone
+#[derive(Accounts)]
+pub struct ResolveWithOracle<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        seeds = [ORACLE_SEED, betting_market.key().as_ref()],
+        bump = market_oracle.bump,
+    )]
+    pub market_oracle: Account<'info, MarketOracle>,
+}
+
+/// Resolve a price market from its Pyth or Switchboard feed; callable by anyone
+#[derive(Accounts)]
+pub struct ResolveFromOracle<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    /// CHECK: Must be the feed the market was created with; owner and layout checked on read
+    pub price_feed: UncheckedAccount<'info>,
+}
+
+/// Report the observed metric of an over/under market and settle it against the line. Only the
+/// market's metric source may report, and only while no reporter set is attached.
+#[derive(Accounts)]
+pub struct ReportMetric<'info> {
+    pub source: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+        constraint = market_config.metric_source == source.key() @ StreamError::Unauthorized,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    /// CHECK: Oracle PDA; while initialized, only its agreed value may resolve the market
+    #[account(seeds = [ORACLE_SEED, betting_market.key().as_ref()], bump)]
+    pub market_oracle: UncheckedAccount<'info>,
+}
+
+// ============= IMPLEMENTATION =============
+
+impl<'info> ConfigureMarketOracle<'info> {
+    pub fn configure_market_oracle(
+        &mut self,
+        reporters: Vec<Pubkey>,
+        tolerance: u64,
+        bumps: &ConfigureMarketOracleBumps,
+    ) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < self.betting_market.resolution_time, StreamError::BettingClosed);
+        require!(
+            self.market_config.resolution_source != ResolutionSource::Consensus,
+            StreamError::ConsensusResolutionRequired
+        );
+        require!(
+            self.market_config.market_type.outcome_for_value(0).is_some()
+                && self.betting_market.outcome_count == 2,
+            StreamError::InvalidMarketSetup
+        );
+        require!(
+            !reporters.is_empty() && reporters.len() <= MAX_ORACLE_REPORTERS,
+            StreamError::InvalidOracleReporters
+        );
+        for (i, reporter) in reporters.iter().enumerate() {
+            require!(!reporters[..i].contains(reporter), StreamError::InvalidOracleReporters);
+        }
+
+        self.market_oracle.set_inner(MarketOracle {
+            market: self.betting_market.key(),
+            reporters: reporters.clone(),
+            tolerance,
+            round: 0,
+            submissions: Vec::new(),
+            status: OracleStatus::Collecting,
+            agreed_value: None,
+            bump: bumps.market_oracle,
+        });
+
+        emit!(OracleConfigured {
+            market: self.betting_market.key(),
+            reporters,
+            tolerance,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> SubmitOracleResult<'info> {
+    /// `round` must match the oracle's current round, so a report signed for a discarded
+    /// round cannot be replayed into the next one
+    pub fn submit_oracle_result(&mut self, round: u32, value: u64) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= self.betting_market.resolution_time,
+            StreamError::MarketNotReady
+        );
+
+        let oracle = &mut self.market_oracle;
+        let reporter = self.reporter.key();
+        require!(oracle.reporters.contains(&reporter), StreamError::Unauthorized);
+        require!(round == oracle.round, StreamError::StaleOracleRound);
+        require!(
+            oracle.status == OracleStatus::Collecting,
+            StreamError::InvalidResolutionState
+        );
+        require!(
+            !oracle.submissions.iter().any(|s| s.reporter == reporter),
+            StreamError::AlreadyReported
+        );
+
+        oracle.submissions.push(OracleSubmission {
+            reporter,
+            value,
+            submitted_at: now,
+        });
+
+        emit!(OracleResultSubmitted {
+            market: oracle.market,
+            reporter,
+            round,
+            value,
+            timestamp: now,
+        });
+
+        match oracle.evaluate() {
+            OracleStatus::Agreed => {
+                let agreed = oracle.agreed_value.ok_or(StreamError::InvalidResolutionState)?;
+                msg!("Oracle agreed on {} in round {}", agreed, round);
+                emit!(OracleAgreed {
+                    market: oracle.market,
+                    round,
+                    value: agreed,
+                    timestamp: now,
+                });
+            }
+            OracleStatus::Disputed => {
+                msg!("Oracle reporters disagree beyond tolerance in round {}", round);
+                emit!(OracleDisputed {
+                    market: oracle.market,
+                    round,
+                    values: oracle.submissions.iter().map(|s| s.value).collect(),
+                    timestamp: now,
+                });
+                notify(NotificationCode::DisputeOpened, oracle.market, [round as u64, 0], now);
+            }
+            OracleStatus::Collecting => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl<'info> OpenOracleRound<'info> {
+    pub fn open_oracle_round(&mut self) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        let oracle = &mut self.market_oracle;
+        require!(
+            oracle.status == OracleStatus::Disputed,
+            StreamError::InvalidResolutionState
+        );
+
+        oracle.round = oracle.round.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        oracle.submissions.clear();
+        oracle.status = OracleStatus::Collecting;
+
+        emit!(OracleRoundOpened {
+            market: oracle.market,
+            round: oracle.round,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ReportMetric<'info> {
+    pub fn report_metric(&mut self, value: u64) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= self.betting_market.resolution_time,
+            StreamError::MarketNotReady
+        );
+        require!(
+            MarketOracle::load(&self.market_oracle)?.is_none(),
+            StreamError::OracleResolutionRequired
+        );
+        require!(
+            self.market_config.resolution_source != ResolutionSource::Consensus,
+            StreamError::ConsensusResolutionRequired
+        );
+        require!(
+            self.market_config.payout_plan == ResolutionPayoutPlan::WinnerTakesAll,
+            StreamError::RankedResolutionRequired
+        );
+        let MarketType::OverUnder { line } = self.market_config.market_type else {
+            return err!(StreamError::InvalidMarketSetup);
+        };
+        let winning_outcome = self
+            .market_config
+            .market_type
+            .outcome_for_value(value)
+            .ok_or(StreamError::InvalidMarketSetup)?;
+
+        msg!("Metric {} against line {} settles to outcome {}", value, line, winning_outcome);
+        let book = self.market_book.load()?;
+        self.market_config.reported_metric = Some(value);
+        self.betting_market.winning_outcome = Some(winning_outcome);
+        self.betting_market.record_resolution(&book, &self.market_config)?;
+
+        emit!(MetricReported {
+            market: self.betting_market.key(),
+            source: self.source.key(),
+            value,
+            line,
+            winning_outcome,
+            timestamp: now,
+        });
+        emit!(MarketResolved {
+            market: self.betting_market.key(),
+            winning_outcome,
+            total_pool: book.total_pool,
+            used_randomness: false,
+            settlement_hash: self.betting_market.settlement_hash,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ResolveWithOracle<'info> {
+    pub fn resolve_with_oracle(&mut self) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.market_config.payout_plan == ResolutionPayoutPlan::WinnerTakesAll,
+            StreamError::RankedResolutionRequired
+        );
+        require!(
+            self.market_oracle.status == OracleStatus::Agreed,
+            StreamError::InvalidResolutionState
+        );
+        let value = self
+            .market_oracle
+            .agreed_value
+            .ok_or(StreamError::InvalidResolutionState)?;
+        let winning_outcome = self
+            .market_config
+            .market_type
+            .outcome_for_value(value)
+            .ok_or(StreamError::InvalidMarketSetup)?;
+
+        msg!("Resolving market from oracle value {} to outcome {}", value, winning_outcome);
+        let book = self.market_book.load()?;
+        self.betting_market.winning_outcome = Some(winning_outcome);
+        self.betting_market.record_resolution(&book, &self.market_config)?;
+
+        emit!(MarketResolved {
+            market: self.betting_market.key(),
+            winning_outcome,
+            total_pool: book.total_pool,
+            used_randomness: false,
+            settlement_hash: self.betting_market.settlement_hash,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ResolveFromOracle<'info> {
+    /// Settles from a price published at or after the resolution time, so "above $200 by the
+    /// end of the stream" reads the price once the stream has ended
+    pub fn resolve_from_oracle(&mut self) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= self.betting_market.resolution_time,
+            StreamError::MarketNotReady
+        );
+        let ResolutionSource::PriceOracle { provider, feed, comparison, target, target_expo } =
+            self.market_config.resolution_source
+        else {
+            return err!(StreamError::InvalidMarketSetup);
+        };
+        require_keys_eq!(self.price_feed.key(), feed, StreamError::InvalidPriceFeed);
+
+        let price = OraclePrice::read(provider, &self.price_feed)?;
+        price.require_usable(self.betting_market.resolution_time, now)?;
+        let winning_outcome = if price.satisfies(comparison, target, target_expo)? { 0 } else { 1 };
+
+        msg!("Price {}e{} settles to outcome {}", price.price, price.expo, winning_outcome);
+        let book = self.market_book.load()?;
+        self.betting_market.winning_outcome = Some(winning_outcome);
+        self.betting_market.record_resolution(&book, &self.market_config)?;
+
+        emit!(PriceOracleResolved {
+            market: self.betting_market.key(),
+            feed,
+            price: price.price,
+            expo: price.expo,
+            publish_time: price.publish_time,
+            winning_outcome,
+            timestamp: now,
+        });
+        emit!(MarketResolved {
+            market: self.betting_market.key(),
+            winning_outcome,
+            total_pool: book.total_pool,
+            used_randomness: false,
+            settlement_hash: self.betting_market.settlement_hash,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,516 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::instructions::{amount_received, enforce_access_gate, FEATURE_GATES_SEED, PLATFORM_CONFIG_SEED};
+use crate::math::FIXED_ONE;
+use crate::state::{
+    AccessGate, BettingMarket, Blocklist, FeatureGates, MarketAccessMode, MarketBook, Parlay, ParlayLeg, ParlayPlaced,
+    ParlayReserve, ParlayReserveFunded, ParlayReserveWithdrawn, ParlayResult, ParlaySettled, PlatformConfig,
+    StreamError, StreamState, BPS_DENOMINATOR, MAX_PARLAY_LEGS, MIN_LEG_PROBABILITY_BPS, MIN_PARLAY_LEGS,
+};
+
+// ============= CONSTANTS =============
+pub const PARLAY_RESERVE_SEED: &[u8] = b"parlay_reserve";
+pub const PARLAY_VAULT_SEED: &[u8] = b"parlay_vault";
+pub const PARLAY_SEED: &[u8] = b"parlay";
+const LEG_ACCOUNTS: usize = 6; // Market, book, stream, blocklist page, access gate, gate token account
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Platform admin opens the parlay reserve for a mint
+#[derive(Accounts)]
+pub struct InitializeParlayReserve<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ StreamError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = ParlayReserve::INIT_SPACE,
+        seeds = [PARLAY_RESERVE_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub reserve: Account<'info, ParlayReserve>,
+
+    #[account(
+        init,
+        payer = admin,
+        seeds = [PARLAY_VAULT_SEED, reserve.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = reserve,
+    )]
+    pub reserve_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Anyone may add to the reserve's bankroll
+#[derive(Accounts)]
+pub struct FundParlayReserve<'info> {
+    pub funder: Signer<'info>,
+
+    #[account(
+        seeds = [PARLAY_RESERVE_SEED, mint.key().as_ref()],
+        bump = reserve.bump,
+        has_one = mint,
+    )]
+    pub reserve: Account<'info, ParlayReserve>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = funder_token.owner == funder.key(),
+        constraint = funder_token.mint == mint.key(),
+    )]
+    pub funder_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PARLAY_VAULT_SEED, reserve.key().as_ref()],
+        bump,
+    )]
+    pub reserve_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Platform admin takes out bankroll that no open parlay has locked
+#[derive(Accounts)]
+pub struct WithdrawParlayReserve<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ StreamError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+
+    #[account(
+        seeds = [PARLAY_RESERVE_SEED, mint.key().as_ref()],
+        bump = reserve.bump,
+        has_one = mint,
+    )]
+    pub reserve: Account<'info, ParlayReserve>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [PARLAY_VAULT_SEED, reserve.key().as_ref()],
+        bump,
+    )]
+    pub reserve_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == mint.key() @ StreamError::InvalidMint,
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Stake on several markets at once. Remaining accounts are, per leg, its market, book and
+/// stream, the bettor's blocklist page, the stream's access gate and the bettor's gate token
+/// account, with the program ID in place of any the leg's stream does not need; transfer hook
+/// accounts follow the legs.
+#[derive(Accounts)]
+#[instruction(parlay_id: u64)]
+pub struct PlaceParlay<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PARLAY_RESERVE_SEED, mint.key().as_ref()],
+        bump = reserve.bump,
+        has_one = mint,
+    )]
+    pub reserve: Account<'info, ParlayReserve>,
+
+    #[account(
+        init,
+        payer = bettor,
+        space = Parlay::INIT_SPACE,
+        seeds = [PARLAY_SEED, bettor.key().as_ref(), parlay_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub parlay: Account<'info, Parlay>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = bettor_token.owner == bettor.key(),
+        constraint = bettor_token.mint == mint.key(),
+    )]
+    pub bettor_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PARLAY_VAULT_SEED, reserve.key().as_ref()],
+        bump,
+    )]
+    pub reserve_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Pay out or retire a parlay; remaining accounts are the leg markets in order, then any
+/// transfer hook accounts
+#[derive(Accounts)]
+pub struct SettleParlay<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PARLAY_RESERVE_SEED, mint.key().as_ref()],
+        bump = reserve.bump,
+        has_one = mint,
+    )]
+    pub reserve: Account<'info, ParlayReserve>,
+
+    #[account(
+        mut,
+        close = bettor,
+        seeds = [PARLAY_SEED, bettor.key().as_ref(), parlay.parlay_id.to_le_bytes().as_ref()],
+        bump = parlay.bump,
+        has_one = bettor,
+        has_one = reserve,
+    )]
+    pub parlay: Account<'info, Parlay>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = bettor_token.owner == bettor.key(),
+        constraint = bettor_token.mint == mint.key(),
+    )]
+    pub bettor_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [PARLAY_VAULT_SEED, reserve.key().as_ref()],
+        bump,
+    )]
+    pub reserve_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Feature gates PDA; may be uninitialized
+    #[account(seeds = [FEATURE_GATES_SEED], bump)]
+    pub feature_gates: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> InitializeParlayReserve<'info> {
+    pub fn initialize_parlay_reserve(&mut self, bumps: &InitializeParlayReserveBumps) -> Result<()> {
+        self.reserve.set_inner(ParlayReserve {
+            mint: self.mint.key(),
+            total_locked: 0,
+            open_parlays: 0,
+            bump: bumps.reserve,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> FundParlayReserve<'info> {
+    pub fn fund_parlay_reserve(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+
+        let cpi_accounts = TransferChecked {
+            from: self.funder_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.reserve_vault.to_account_info(),
+            authority: self.funder.to_account_info(),
+        };
+        let vault_before = self.reserve_vault.amount;
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let amount = amount_received(&mut self.reserve_vault, vault_before)?;
+
+        emit!(ParlayReserveFunded {
+            reserve: self.reserve.key(),
+            funder: self.funder.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> WithdrawParlayReserve<'info> {
+    pub fn withdraw_parlay_reserve(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        let available = self.reserve_vault.amount.saturating_sub(self.reserve.total_locked);
+        require!(amount <= available, StreamError::InsufficientFunds);
+
+        pay_from_reserve(
+            &self.reserve,
+            &self.reserve_vault,
+            &self.mint,
+            &self.destination,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
+
+        emit!(ParlayReserveWithdrawn {
+            reserve: self.reserve.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> PlaceParlay<'info> {
+    /// Each leg locks the odds its market implies right now; the payout is the stake times the
+    /// product of those odds, and the reserve must be able to cover it in full. Each leg's stream
+    /// blocklist and access gate apply as they do to `place_bet`; leg markets must be open to
+    /// every bettor, since the parlay has no per-market access listing.
+    pub fn place_parlay(
+        &mut self,
+        parlay_id: u64,
+        legs: Vec<ParlayLeg>,
+        stake: u64,
+        min_payout: u64,
+        bumps: &PlaceParlayBumps,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(stake > 0, StreamError::InvalidAmount);
+        require!(
+            (MIN_PARLAY_LEGS..=MAX_PARLAY_LEGS).contains(&legs.len())
+                && remaining_accounts.len() >= legs.len() * LEG_ACCOUNTS,
+            StreamError::InvalidParlayLegs
+        );
+        let (leg_accounts, hook_accounts) = remaining_accounts.split_at(legs.len() * LEG_ACCOUNTS);
+
+        // Odds apply to what actually lands in the vault, net of any transfer fee
+        let cpi_accounts = TransferChecked {
+            from: self.bettor_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.reserve_vault.to_account_info(),
+            authority: self.bettor.to_account_info(),
+        };
+        let vault_before = self.reserve_vault.amount;
+        transfer_checked(
+            CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+                .with_remaining_accounts(hook_accounts.to_vec()),
+            stake,
+            self.mint.decimals,
+        )?;
+        let stake = amount_received(&mut self.reserve_vault, vault_before)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let min_probability = FIXED_ONE * MIN_LEG_PROBABILITY_BPS as u128 / BPS_DENOMINATOR as u128;
+        let mut payout = stake as u128;
+        for (i, (leg, accounts)) in legs.iter().zip(leg_accounts.chunks_exact(LEG_ACCOUNTS)).enumerate() {
+            require!(
+                legs[..i].iter().all(|other| other.market != leg.market),
+                StreamError::InvalidParlayLegs
+            );
+            let market = load_leg_market(&accounts[0], leg)?;
+            require!(!market.resolved, StreamError::MarketResolved);
+            require!(market.betting_open(now), StreamError::BettingClosed);
+            require!(leg.outcome_id < market.outcome_count, StreamError::InvalidOutcome);
+            require!(market.access_mode == MarketAccessMode::Open, StreamError::InvalidParlayLegs);
+            self.require_leg_access(&market, &accounts[2..])?;
+
+            let book_loader = AccountLoader::<MarketBook>::try_from(&accounts[1])?;
+            let book = book_loader.load()?;
+            require_keys_eq!(book.market, leg.market, StreamError::InvalidParlayLegs);
+            let probability = book.implied_probability(&market.amm_curve, leg.outcome_id)?;
+            require!(probability >= min_probability, StreamError::InvalidParlayLegs);
+
+            payout = payout
+                .checked_mul(FIXED_ONE)
+                .ok_or(StreamError::MathOverflow)?
+                / probability;
+        }
+        let payout = u64::try_from(payout).map_err(|_| StreamError::MathOverflow)?;
+        require!(payout >= min_payout, StreamError::SlippageExceeded);
+
+        let reserve = &mut self.reserve;
+        reserve.total_locked = reserve
+            .total_locked
+            .checked_add(payout)
+            .ok_or(StreamError::MathOverflow)?;
+        require!(reserve.total_locked <= self.reserve_vault.amount, StreamError::InsufficientFunds);
+        reserve.open_parlays = reserve.open_parlays.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+        self.parlay.set_inner(Parlay {
+            bettor: self.bettor.key(),
+            reserve: reserve.key(),
+            parlay_id,
+            stake,
+            payout,
+            legs: legs.clone(),
+            created_at: now,
+            bump: bumps.parlay,
+        });
+
+        emit!(ParlayPlaced {
+            parlay: self.parlay.key(),
+            bettor: self.bettor.key(),
+            legs,
+            stake,
+            payout,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Runs the leg stream's blocklist and access gate against the bettor, from the stream,
+    /// blocklist page, access gate and gate token accounts in `accounts`
+    fn require_leg_access(&self, market: &BettingMarket, accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let bettor = self.bettor.key();
+        require_keys_eq!(accounts[0].key(), market.stream, StreamError::InvalidParlayLegs);
+        require_keys_eq!(*accounts[0].owner, crate::ID, StreamError::InvalidParlayLegs);
+        let stream = StreamState::try_deserialize(&mut &accounts[0].data.borrow()[..])?;
+
+        let blocklist = optional_leg_account(&accounts[1]);
+        if stream.blocked_count > 0 {
+            if let Some(page) = blocklist {
+                let (expected, _) = Pubkey::find_program_address(
+                    &[b"blocklist", market.stream.as_ref(), &[Blocklist::page_for(&bettor)]],
+                    &crate::ID,
+                );
+                require_keys_eq!(page.key(), expected, StreamError::InvalidParlayLegs);
+            }
+        }
+        Blocklist::require_not_blocked(stream.blocked_count, blocklist, &bettor)?;
+
+        let gate = optional_leg_account(&accounts[2])
+            .map(Account::<AccessGate>::try_from)
+            .transpose()?;
+        if let Some(gate) = &gate {
+            require_keys_eq!(gate.stream, market.stream, StreamError::InvalidParlayLegs);
+        }
+        let holding = optional_leg_account(&accounts[3])
+            .map(InterfaceAccount::<TokenAccount>::try_from)
+            .transpose()?;
+        enforce_access_gate(&stream, gate.as_ref(), holding.as_ref(), &bettor)
+    }
+}
+
+impl<'info> SettleParlay<'info> {
+    /// A parlay loses as soon as any leg resolves the other way, so it can be retired before
+    /// every market has resolved. Otherwise it waits for all legs: a cancelled leg voids the
+    /// parlay and returns the stake, and a clean sweep pays the locked payout.
+    pub fn settle_parlay(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let legs = &self.parlay.legs;
+        require!(remaining_accounts.len() >= legs.len(), StreamError::InvalidParlayLegs);
+        let (leg_accounts, hook_accounts) = remaining_accounts.split_at(legs.len());
+
+        let mut lost = false;
+        let mut void = false;
+        let mut pending = false;
+        for (info, leg) in leg_accounts.iter().zip(legs.iter()) {
+            let market = load_leg_market(info, leg)?;
+            if market.cancelled {
+                void = true;
+            } else if !market.resolved {
+                pending = true;
+            } else if market.winning_outcome != Some(leg.outcome_id) {
+                lost = true;
+            }
+        }
+        let result = if lost {
+            ParlayResult::Lost
+        } else {
+            require!(!pending, StreamError::MarketNotResolved);
+            if void { ParlayResult::Void } else { ParlayResult::Won }
+        };
+
+        let paid = match result {
+            ParlayResult::Won => self.parlay.payout,
+            ParlayResult::Void => self.parlay.stake,
+            ParlayResult::Lost => 0,
+        };
+
+        let reserve = &mut self.reserve;
+        reserve.total_locked = reserve
+            .total_locked
+            .checked_sub(self.parlay.payout)
+            .ok_or(StreamError::MathOverflow)?;
+        reserve.open_parlays = reserve.open_parlays.saturating_sub(1);
+
+        if paid > 0 {
+            FeatureGates::require_not_queued(&self.feature_gates, paid)?;
+            pay_from_reserve(
+                &self.reserve,
+                &self.reserve_vault,
+                &self.mint,
+                &self.bettor_token,
+                &self.token_program,
+                hook_accounts,
+                paid,
+            )?;
+        }
+
+        emit!(ParlaySettled {
+            parlay: self.parlay.key(),
+            bettor: self.bettor.key(),
+            result,
+            paid,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+/// Reads a leg's market from a remaining account. Only this program can write a
+/// `BettingMarket`, so the owner and discriminator checks prove it is a real market.
+fn load_leg_market(info: &AccountInfo, leg: &ParlayLeg) -> Result<BettingMarket> {
+    require_keys_eq!(info.key(), leg.market, StreamError::InvalidParlayLegs);
+    require_keys_eq!(*info.owner, crate::ID, StreamError::InvalidParlayLegs);
+    BettingMarket::try_deserialize(&mut &info.data.borrow()[..])
+}
+
+/// A leg's optional account, where the program ID stands for one that was left out
+fn optional_leg_account<'a, 'info>(info: &'a AccountInfo<'info>) -> Option<&'a AccountInfo<'info>> {
+    (info.key() != crate::ID).then_some(info)
+}
+
+/// Transfers `amount` out of the reserve vault, signed by the reserve
+fn pay_from_reserve<'info>(
+    reserve: &Account<'info, ParlayReserve>,
+    reserve_vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    let reserve_seeds = &[PARLAY_RESERVE_SEED, reserve.mint.as_ref(), &[reserve.bump]];
+    let signer = &[&reserve_seeds[..]];
+    let cpi_accounts = TransferChecked {
+        from: reserve_vault.to_account_info(),
+        mint: mint.to_account_info(),
+        to: destination.to_account_info(),
+        authority: reserve.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+        .with_remaining_accounts(remaining_accounts.to_vec());
+    transfer_checked(cpi_ctx, amount, mint.decimals)
+}
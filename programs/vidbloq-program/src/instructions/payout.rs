@@ -0,0 +1,518 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::instructions::{
+    amount_received, burn_stream_share, enforce_distribution_policy, record_burn_rate, record_clawbackable_payout,
+    PlatformFeeRoute, CLAWBACK_AGREEMENT_SEED, DISTRIBUTION_POLICY_SEED, PLATFORM_CONFIG_SEED, GLOBAL_REGISTRY_SEED
+};
+use crate::state::{
+    StreamState, StreamError, PendingPayout, PayoutRecipient, DistributionPolicy, HostProfile, ReferralRewards,
+    DistributionProposed, DistributionAccepted, DistributionCancelled, PayoutRecipientAdded,
+    PayoutRecipientRemoved, PayoutPolicySet, MAX_PAYOUT_TIMEOUT, GlobalRegistry
+};
+
+pub const PENDING_PAYOUT_SEED: &[u8] = b"pending_payout";
+pub const PAYOUT_VAULT_SEED: &[u8] = b"payout_vault";
+pub const PAYOUT_RECIPIENT_SEED: &[u8] = b"payout_recipient";
+
+/// Moves `amount` out of the stream vault into an escrow the recipient must accept
+#[derive(Accounts)]
+pub struct ProposeDistribution<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    /// CHECK: This is the recipient public key
+    pub recipient: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = host,
+        space = PendingPayout::INIT_SPACE,
+        seeds = [PENDING_PAYOUT_SEED, stream.key().as_ref(), stream.payout_nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub pending_payout: Account<'info, PendingPayout>,
+
+    #[account(
+        init,
+        payer = host,
+        seeds = [PAYOUT_VAULT_SEED, pending_payout.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = pending_payout,
+        token::token_program = token_program,
+    )]
+    pub payout_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Allowlist entry for the recipient, required while the stream is in strict payout mode
+    #[account(
+        seeds = [PAYOUT_RECIPIENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump = payout_recipient.bump
+    )]
+    pub payout_recipient: Option<Account<'info, PayoutRecipient>>,
+
+    /// Spending limits, required once the host has set a distribution policy
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_POLICY_SEED, stream.key().as_ref()],
+        bump = distribution_policy.bump
+    )]
+    pub distribution_policy: Option<Account<'info, DistributionPolicy>>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptDistribution<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    #[account(
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        close = host,
+        seeds = [PENDING_PAYOUT_SEED, stream.key().as_ref(), pending_payout.nonce.to_le_bytes().as_ref()],
+        bump = pending_payout.bump,
+        has_one = recipient @ StreamError::Unauthorized,
+        has_one = host,
+        has_one = mint
+    )]
+    pub pending_payout: Account<'info, PendingPayout>,
+
+    /// CHECK: Stream host, refunded the escrow rent
+    #[account(mut)]
+    pub host: UncheckedAccount<'info>,
+
+    /// Writable so deflationary streams can burn supply
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [PAYOUT_VAULT_SEED, pending_payout.key().as_ref()],
+        bump,
+    )]
+    pub payout_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Recipient's clawback agreement PDA; may be uninitialized
+    #[account(
+        mut,
+        seeds = [CLAWBACK_AGREEMENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump
+    )]
+    pub clawback_agreement: UncheckedAccount<'info>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required while a platform fee is configured
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"host_profile", stream.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Option<Account<'info, HostProfile>>,
+
+    /// Credited with the referrer's share of the fee while the host's referral runs
+    #[account(mut)]
+    pub referral_rewards: Option<Account<'info, ReferralRewards>>,
+
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+/// Returns an unaccepted payout to the stream vault once it has expired
+#[derive(Accounts)]
+pub struct CancelDistribution<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        close = host,
+        seeds = [PENDING_PAYOUT_SEED, stream.key().as_ref(), pending_payout.nonce.to_le_bytes().as_ref()],
+        bump = pending_payout.bump,
+        has_one = host,
+        has_one = stream
+    )]
+    pub pending_payout: Account<'info, PendingPayout>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [PAYOUT_VAULT_SEED, pending_payout.key().as_ref()],
+        bump,
+    )]
+    pub payout_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey)]
+pub struct AddPayoutRecipient<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        init,
+        payer = host,
+        space = PayoutRecipient::INIT_SPACE,
+        seeds = [PAYOUT_RECIPIENT_SEED, stream.key().as_ref(), recipient.as_ref()],
+        bump
+    )]
+    pub payout_recipient: Account<'info, PayoutRecipient>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemovePayoutRecipient<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        close = host,
+        seeds = [PAYOUT_RECIPIENT_SEED, stream.key().as_ref(), payout_recipient.recipient.as_ref()],
+        bump = payout_recipient.bump,
+        has_one = stream
+    )]
+    pub payout_recipient: Account<'info, PayoutRecipient>,
+}
+
+/// Turns strict payout mode on or off; while on, every payout needs a `PayoutRecipient` entry
+#[derive(Accounts)]
+pub struct SetPayoutPolicy<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized
+    )]
+    pub stream: Account<'info, StreamState>,
+}
+
+impl<'info> ProposeDistribution<'info> {
+    pub fn propose_distribution(
+        &mut self,
+        amount: u64,
+        timeout: i64,
+        bumps: &ProposeDistributionBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        require!(timeout > 0 && timeout <= MAX_PAYOUT_TIMEOUT, StreamError::InvalidTime);
+        self.stream.require_distribution_allowed()?;
+        self.stream.require_within_lifetime(&self.platform_config)?;
+        self.stream.require_recipient_allowed(self.payout_recipient.is_some())?;
+
+        let available_balance = self.stream.available_balance()?;
+        require!(available_balance >= amount, StreamError::InsufficientFunds);
+        enforce_distribution_policy(&self.stream, self.distribution_policy.as_mut(), amount)?;
+
+        let stream_seeds = self.stream.signer_seeds();
+        let stream_seeds: Vec<&[u8]> = stream_seeds.iter().map(Vec::as_slice).collect();
+        let signer = &[&stream_seeds[..]];
+        let cpi_accounts = TransferChecked {
+            from: self.stream_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.payout_vault.to_account_info(),
+            authority: self.stream.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let escrowed = amount_received(&mut self.payout_vault, 0)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let nonce = self.stream.payout_nonce;
+        self.stream.payout_nonce = nonce.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        self.stream.total_distributed = self.stream.total_distributed.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+
+        self.pending_payout.set_inner(PendingPayout {
+            stream: self.stream.key(),
+            host: self.host.key(),
+            recipient: self.recipient.key(),
+            mint: self.mint.key(),
+            nonce,
+            amount: escrowed,
+            proposed_at: now,
+            expires_at: now + timeout,
+            bump: bumps.pending_payout,
+        });
+
+        emit!(DistributionProposed {
+            stream: self.stream.key(),
+            payout: self.pending_payout.key(),
+            recipient: self.recipient.key(),
+            amount: escrowed,
+            expires_at: now + timeout,
+            timestamp: now
+        });
+
+        record_burn_rate(&mut self.stream, amount)
+    }
+}
+
+impl<'info> AcceptDistribution<'info> {
+    pub fn accept_distribution(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let stream_key = self.stream.key();
+        let nonce_bytes = self.pending_payout.nonce.to_le_bytes();
+        let payout_seeds = &[
+            PENDING_PAYOUT_SEED,
+            stream_key.as_ref(),
+            nonce_bytes.as_ref(),
+            &[self.pending_payout.bump],
+        ];
+        let signer = &[&payout_seeds[..]];
+
+        let amount = self.payout_vault.amount;
+        let fee = PlatformFeeRoute {
+            platform_config: &self.platform_config,
+            treasury_ata: self.treasury_ata.as_ref(),
+            host_profile: self.host_profile.as_ref(),
+            referral_rewards: self.referral_rewards.as_mut(),
+            referral_vault: self.referral_vault.as_ref(),
+        }.collect(
+            self.stream.host,
+            &self.payout_vault,
+            &self.mint,
+            self.pending_payout.to_account_info(),
+            signer,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
+        let burned = burn_stream_share(
+            &self.stream,
+            &self.payout_vault,
+            &self.mint,
+            self.pending_payout.to_account_info(),
+            signer,
+            &self.token_program,
+            amount - fee,
+        )?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.payout_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.recipient_ata.to_account_info(),
+            authority: self.pending_payout.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount - fee - burned, self.mint.decimals)?;
+
+        close_payout_vault(&self.payout_vault, &self.host, &self.pending_payout, &self.token_program, signer)?;
+        GlobalRegistry::record_distribution(&self.global_registry, amount)?;
+        let now = Clock::get()?.unix_timestamp;
+        record_clawbackable_payout(
+            &self.clawback_agreement,
+            stream_key,
+            self.recipient.key(),
+            &self.recipient_ata.key(),
+            amount - fee - burned,
+            now,
+        )?;
+
+        emit!(DistributionAccepted {
+            stream: stream_key,
+            payout: self.pending_payout.key(),
+            recipient: self.recipient.key(),
+            amount,
+            platform_fee: fee,
+            burned,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+impl<'info> CancelDistribution<'info> {
+    pub fn cancel_distribution(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= self.pending_payout.expires_at, StreamError::PayoutNotExpired);
+
+        let stream_key = self.stream.key();
+        let nonce_bytes = self.pending_payout.nonce.to_le_bytes();
+        let payout_seeds = &[
+            PENDING_PAYOUT_SEED,
+            stream_key.as_ref(),
+            nonce_bytes.as_ref(),
+            &[self.pending_payout.bump],
+        ];
+        let signer = &[&payout_seeds[..]];
+
+        let vault_before = self.stream_ata.amount;
+        let cpi_accounts = TransferChecked {
+            from: self.payout_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.stream_ata.to_account_info(),
+            authority: self.pending_payout.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, self.payout_vault.amount, self.mint.decimals)?;
+        let returned = amount_received(&mut self.stream_ata, vault_before)?;
+
+        // Only what made it back is available again; transfer fees stay counted as distributed
+        self.stream.total_distributed = self.stream.total_distributed.checked_sub(returned).ok_or(StreamError::MathOverflow)?;
+
+        close_payout_vault(&self.payout_vault, &self.host.to_account_info(), &self.pending_payout, &self.token_program, signer)?;
+
+        emit!(DistributionCancelled {
+            stream: stream_key,
+            payout: self.pending_payout.key(),
+            recipient: self.pending_payout.recipient,
+            amount: returned,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+impl<'info> AddPayoutRecipient<'info> {
+    pub fn add_payout_recipient(&mut self, recipient: Pubkey, bumps: &AddPayoutRecipientBumps) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.payout_recipient.set_inner(PayoutRecipient {
+            stream: self.stream.key(),
+            recipient,
+            added_at: now,
+            bump: bumps.payout_recipient,
+        });
+
+        emit!(PayoutRecipientAdded {
+            stream: self.stream.key(),
+            recipient,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+impl<'info> RemovePayoutRecipient<'info> {
+    pub fn remove_payout_recipient(&mut self) -> Result<()> {
+        emit!(PayoutRecipientRemoved {
+            stream: self.stream.key(),
+            recipient: self.payout_recipient.recipient,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+impl<'info> SetPayoutPolicy<'info> {
+    pub fn set_payout_policy(&mut self, strict: bool) -> Result<()> {
+        self.stream.payout_allowlist_strict = strict;
+
+        emit!(PayoutPolicySet {
+            stream: self.stream.key(),
+            strict,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+fn close_payout_vault<'info>(
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    destination: &AccountInfo<'info>,
+    pending_payout: &Account<'info, PendingPayout>,
+    token_program: &Interface<'info, TokenInterface>,
+    signer: &[&[&[u8]]],
+) -> Result<()> {
+    let close_accounts = CloseAccount {
+        account: vault.to_account_info(),
+        destination: destination.clone(),
+        authority: pending_payout.to_account_info(),
+    };
+    close_account(CpiContext::new_with_signer(token_program.to_account_info(), close_accounts, signer))
+}
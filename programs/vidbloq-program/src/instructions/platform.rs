@@ -0,0 +1,260 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+
+use crate::instructions::{REFERRAL_REWARDS_SEED, REFERRAL_VAULT_SEED};
+use crate::program::VidbloqProgram;
+use crate::state::{
+    PlatformConfig, HostProfile, ReferralRewards, StreamError, PlatformConfigUpdated,
+    PlatformFeeCollected, ConsensusBoundsUpdated, MaxStreamLifetimeUpdated, ValidatorSlashUpdated, MAX_PLATFORM_FEE_BPS,
+    DEFAULT_MIN_CONSENSUS_BPS, DEFAULT_MAX_CONSENSUS_BPS, DEFAULT_VALIDATOR_SLASH_BPS, MIN_STREAM_LIFETIME, BPS_DENOMINATOR
+};
+
+pub const PLATFORM_CONFIG_SEED: &[u8] = b"platform_config";
+
+/// Creates the platform config singleton; only the program's upgrade authority may do so
+#[derive(Accounts)]
+pub struct InitializePlatformConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = PlatformConfig::INIT_SPACE,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ StreamError::Unauthorized
+    )]
+    pub program: Program<'info, VidbloqProgram>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ StreamError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePlatformConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ StreamError::Unauthorized
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+}
+
+impl<'info> InitializePlatformConfig<'info> {
+    pub fn initialize_platform_config(&mut self, treasury: Pubkey, fee_bps: u16, bumps: &InitializePlatformConfigBumps) -> Result<()> {
+        require!(fee_bps <= MAX_PLATFORM_FEE_BPS, StreamError::InvalidFeePercentage);
+
+        self.platform_config.set_inner(PlatformConfig {
+            admin: self.admin.key(),
+            treasury,
+            fee_bps,
+            min_consensus_bps: DEFAULT_MIN_CONSENSUS_BPS,
+            max_consensus_bps: DEFAULT_MAX_CONSENSUS_BPS,
+            max_stream_lifetime: 0,
+            validator_slash_bps: DEFAULT_VALIDATOR_SLASH_BPS,
+            bump: bumps.platform_config,
+        });
+
+        emit!(PlatformConfigUpdated {
+            admin: self.admin.key(),
+            treasury,
+            fee_bps,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+impl<'info> UpdatePlatformConfig<'info> {
+    pub fn update_platform_config(&mut self, new_admin: Option<Pubkey>, treasury: Option<Pubkey>, fee_bps: Option<u16>) -> Result<()> {
+        let config = &mut self.platform_config;
+        if let Some(admin) = new_admin {
+            config.admin = admin;
+        }
+        if let Some(treasury) = treasury {
+            config.treasury = treasury;
+        }
+        if let Some(fee_bps) = fee_bps {
+            require!(fee_bps <= MAX_PLATFORM_FEE_BPS, StreamError::InvalidFeePercentage);
+            config.fee_bps = fee_bps;
+        }
+
+        emit!(PlatformConfigUpdated {
+            admin: config.admin,
+            treasury: config.treasury,
+            fee_bps: config.fee_bps,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+impl<'info> UpdatePlatformConfig<'info> {
+    /// Bounds the validator consensus threshold markets may choose; both must be strict majorities
+    pub fn set_consensus_bounds(&mut self, min_consensus_bps: u16, max_consensus_bps: u16) -> Result<()> {
+        require!(
+            min_consensus_bps >= DEFAULT_MIN_CONSENSUS_BPS
+                && min_consensus_bps <= max_consensus_bps
+                && max_consensus_bps as u64 <= BPS_DENOMINATOR,
+            StreamError::InvalidConsensusParams
+        );
+        self.platform_config.min_consensus_bps = min_consensus_bps;
+        self.platform_config.max_consensus_bps = max_consensus_bps;
+
+        emit!(ConsensusBoundsUpdated {
+            min_consensus_bps,
+            max_consensus_bps,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+impl<'info> UpdatePlatformConfig<'info> {
+    /// Caps how long any stream may run before donors get a guaranteed exit; zero removes the cap
+    pub fn set_max_stream_lifetime(&mut self, max_stream_lifetime: i64) -> Result<()> {
+        require!(
+            max_stream_lifetime == 0 || max_stream_lifetime >= MIN_STREAM_LIFETIME,
+            StreamError::InvalidDuration
+        );
+        self.platform_config.max_stream_lifetime = max_stream_lifetime;
+
+        emit!(MaxStreamLifetimeUpdated {
+            max_stream_lifetime,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+impl<'info> UpdatePlatformConfig<'info> {
+    /// Sets how much of a validator's locked stake is slashed for voting against the finalized outcome
+    pub fn set_validator_slash_bps(&mut self, validator_slash_bps: u16) -> Result<()> {
+        require!(
+            validator_slash_bps as u64 <= BPS_DENOMINATOR,
+            StreamError::InvalidFeePercentage
+        );
+        self.platform_config.validator_slash_bps = validator_slash_bps;
+
+        emit!(ValidatorSlashUpdated {
+            validator_slash_bps,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+/// Accounts a payout instruction passes so the platform fee can be routed
+pub(crate) struct PlatformFeeRoute<'a, 'info> {
+    pub platform_config: &'a AccountInfo<'info>,
+    pub treasury_ata: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+    pub host_profile: Option<&'a Account<'info, HostProfile>>,
+    pub referral_rewards: Option<&'a mut Account<'info, ReferralRewards>>,
+    pub referral_vault: Option<&'a InterfaceAccount<'info, TokenAccount>>,
+}
+
+impl<'a, 'info> PlatformFeeRoute<'a, 'info> {
+    /// Moves the platform fee on `amount` out of `vault`, signed by the vault's owning PDA, and
+    /// returns it. While the host's referral is active the referrer's rewards account and vault
+    /// for the mint are required and credited their share; the rest goes to the treasury. The
+    /// rewards account must be the recorded referrer's PDA, so a caller cannot credit the share
+    /// to anyone else.
+    #[allow(clippy::too_many_arguments)]
+    pub fn collect(
+        self,
+        host: Pubkey,
+        vault: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        authority: AccountInfo<'info>,
+        signer: &[&[&[u8]]],
+        token_program: &Interface<'info, TokenInterface>,
+        remaining_accounts: &[AccountInfo<'info>],
+        amount: u64,
+    ) -> Result<u64> {
+        let Some(config) = PlatformConfig::load(self.platform_config)? else {
+            return Ok(0);
+        };
+        let fee = config.fee_on(amount);
+        if fee == 0 {
+            return Ok(0);
+        }
+
+        let treasury_ata = self.treasury_ata.ok_or(StreamError::MissingAccounts)?;
+        require_keys_eq!(treasury_ata.owner, config.treasury, StreamError::Unauthorized);
+        require_keys_eq!(treasury_ata.mint, mint.key(), StreamError::InvalidMint);
+        let host_profile = self.host_profile.ok_or(StreamError::MissingAccounts)?;
+        require_keys_eq!(host_profile.host, host, StreamError::Unauthorized);
+
+        let clock = Clock::get()?;
+        let mut referral_amount = 0;
+        if let Some((referrer, cut)) = host_profile.referral_cut(fee, clock.unix_timestamp) {
+            let rewards = self.referral_rewards.ok_or(StreamError::MissingAccounts)?;
+            let referral_vault = self.referral_vault.ok_or(StreamError::MissingAccounts)?;
+            require_keys_eq!(rewards.referrer, referrer, StreamError::Unauthorized);
+            require_keys_eq!(rewards.mint, mint.key(), StreamError::InvalidMint);
+            let (expected_rewards, _) = Pubkey::find_program_address(
+                &[REFERRAL_REWARDS_SEED, referrer.as_ref(), mint.key().as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(rewards.key(), expected_rewards, StreamError::Unauthorized);
+            let (expected_vault, _) = Pubkey::find_program_address(
+                &[REFERRAL_VAULT_SEED, rewards.key().as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(referral_vault.key(), expected_vault, StreamError::Unauthorized);
+
+            transfer_fee(vault, mint, referral_vault, authority.clone(), signer, token_program, remaining_accounts, cut)?;
+            rewards.accrue(host, cut, clock.epoch)?;
+            referral_amount = cut;
+        }
+
+        transfer_fee(vault, mint, treasury_ata, authority.clone(), signer, token_program, remaining_accounts, fee - referral_amount)?;
+
+        emit!(PlatformFeeCollected {
+            source: authority.key(),
+            mint: mint.key(),
+            gross_amount: amount,
+            fee,
+            referral_amount,
+            timestamp: clock.unix_timestamp
+        });
+        Ok(fee)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn transfer_fee<'info>(
+    from: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    signer: &[&[&[u8]]],
+    token_program: &Interface<'info, TokenInterface>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    let cpi_accounts = TransferChecked {
+        from: from.to_account_info(),
+        mint: mint.to_account_info(),
+        to: to.to_account_info(),
+        authority,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+        .with_remaining_accounts(remaining_accounts.to_vec());
+    transfer_checked(cpi_ctx, amount, mint.decimals)
+}
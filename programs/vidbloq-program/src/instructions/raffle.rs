@@ -0,0 +1,599 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+use ephemeral_vrf_sdk::anchor::vrf;
+use ephemeral_vrf_sdk::instructions::{create_request_randomness_ix, RequestRandomnessParams};
+use ephemeral_vrf_sdk::types::SerializableAccountMeta;
+
+use crate::instructions::{
+    amount_received, burn_stream_share, enforce_distribution_policy, PlatformFeeRoute, DISTRIBUTION_POLICY_SEED,
+    PLATFORM_CONFIG_SEED,
+};
+use crate::state::{
+    DistributionPolicy, HostProfile, Raffle, RaffleCancelled, RaffleCreated, RaffleDrawRequested, RaffleDrawn,
+    RaffleEntered, RaffleEntry, RafflePrizeClaimed, RaffleStatus, ReferralRewards, StreamError, StreamState,
+    StreamStatus, RANDOMNESS_REQUEST_TIMEOUT,
+};
+
+// ============= CONSTANTS =============
+pub const RAFFLE_SEED: &[u8] = b"raffle";
+pub const RAFFLE_VAULT_SEED: &[u8] = b"raffle_vault";
+pub const RAFFLE_ENTRY_SEED: &[u8] = b"raffle_entry";
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Host opens a raffle and escrows its prize, from the stream vault or from a sponsor's wallet
+#[derive(Accounts)]
+#[instruction(raffle_id: u64)]
+pub struct CreateRaffle<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    /// Writable so a prize from a deflationary stream's vault can burn its share
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pays the prize instead of the stream vault when present
+    pub sponsor: Option<Signer<'info>>,
+
+    #[account(mut)]
+    pub sponsor_token: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Spending limits, required for a prize from the vault once the host has set a distribution policy
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_POLICY_SEED, stream.key().as_ref()],
+        bump = distribution_policy.bump
+    )]
+    pub distribution_policy: Option<Account<'info, DistributionPolicy>>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required for a prize from the vault while a platform fee is configured
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"host_profile", stream.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Option<Account<'info, HostProfile>>,
+
+    /// Credited with the referrer's share of the fee while the host's referral runs
+    #[account(mut)]
+    pub referral_rewards: Option<Account<'info, ReferralRewards>>,
+
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = host,
+        space = Raffle::INIT_SPACE,
+        seeds = [RAFFLE_SEED, stream.key().as_ref(), raffle_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        init,
+        payer = host,
+        seeds = [RAFFLE_VAULT_SEED, raffle.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = raffle,
+        token::token_program = token_program,
+    )]
+    pub raffle_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Viewer enters a raffle, paying the ticket price into the stream vault if it has one
+#[derive(Accounts)]
+pub struct EnterRaffle<'info> {
+    #[account(mut)]
+    pub entrant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [RAFFLE_SEED, stream.key().as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump,
+        has_one = stream,
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        init,
+        payer = entrant,
+        space = RaffleEntry::INIT_SPACE,
+        seeds = [RAFFLE_ENTRY_SEED, raffle.key().as_ref(), entrant.key().as_ref()],
+        bump
+    )]
+    pub entry: Account<'info, RaffleEntry>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = entrant_token.owner == entrant.key(),
+        constraint = entrant_token.mint == mint.key(),
+    )]
+    pub entrant_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Anyone asks Ephemeral VRF for the winning entry once entries have closed
+#[vrf]
+#[derive(Accounts)]
+pub struct DrawRaffleWinner<'info> {
+    #[account(mut)]
+    pub requestor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [RAFFLE_SEED, raffle.stream.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump,
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    /// CHECK: The oracle queue from Ephemeral VRF
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Callback from Ephemeral VRF with the randomness for a raffle draw
+#[derive(Accounts)]
+pub struct CallbackDrawRaffle<'info> {
+    /// CHECK: Must be Ephemeral VRF program identity
+    #[account(address = ephemeral_vrf_sdk::consts::VRF_PROGRAM_IDENTITY)]
+    pub vrf_program_identity: Signer<'info>,
+
+    #[account(mut)]
+    pub raffle: Account<'info, Raffle>,
+}
+
+/// The holder of the winning entry collects the prize
+#[derive(Accounts)]
+pub struct ClaimRafflePrize<'info> {
+    #[account(mut)]
+    pub winner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [RAFFLE_SEED, raffle.stream.as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump,
+        has_one = mint,
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    #[account(
+        seeds = [RAFFLE_ENTRY_SEED, raffle.key().as_ref(), winner.key().as_ref()],
+        bump = entry.bump,
+    )]
+    pub entry: Account<'info, RaffleEntry>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [RAFFLE_VAULT_SEED, raffle.key().as_ref()],
+        bump,
+    )]
+    pub raffle_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = winner,
+        associated_token::mint = mint,
+        associated_token::authority = winner,
+        associated_token::token_program = token_program
+    )]
+    pub winner_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Host returns the prize of a raffle nobody entered to whoever funded it
+#[derive(Accounts)]
+pub struct CancelRaffle<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized,
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [RAFFLE_SEED, stream.key().as_ref(), raffle.raffle_id.to_le_bytes().as_ref()],
+        bump = raffle.bump,
+        has_one = stream,
+        has_one = mint,
+    )]
+    pub raffle: Account<'info, Raffle>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [RAFFLE_VAULT_SEED, raffle.key().as_ref()],
+        bump,
+    )]
+    pub raffle_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut, address = raffle.funder_token)]
+    pub funder_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> CreateRaffle<'info> {
+    /// `max_entries` of 0 leaves the raffle uncapped. A prize from the stream vault is a payout:
+    /// it passes the checks `distribute` applies, pays the platform fee and burn share, and counts
+    /// as distributed. Cancelling the raffle returns only what was escrowed.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_raffle(
+        &mut self,
+        raffle_id: u64,
+        prize: u64,
+        ticket_price: u64,
+        entry_deadline: i64,
+        max_entries: u32,
+        bumps: &CreateRaffleBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(prize > 0, StreamError::InvalidAmount);
+        require!(entry_deadline > now, StreamError::InvalidTime);
+
+        let vault_before = self.raffle_vault.amount;
+        let funder_token = match (&self.sponsor, &self.sponsor_token) {
+            (Some(sponsor), Some(sponsor_token)) => {
+                require_keys_eq!(sponsor_token.owner, sponsor.key(), StreamError::Unauthorized);
+                require_keys_eq!(sponsor_token.mint, self.mint.key(), StreamError::InvalidMint);
+                let cpi_accounts = TransferChecked {
+                    from: sponsor_token.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.raffle_vault.to_account_info(),
+                    authority: sponsor.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+                    .with_remaining_accounts(remaining_accounts.to_vec());
+                transfer_checked(cpi_ctx, prize, self.mint.decimals)?;
+                sponsor_token.key()
+            }
+            (None, None) => {
+                // The winner is not known yet, so strict payout mode rules out prizes from the vault
+                require!(self.stream.status == StreamStatus::Active, StreamError::StreamNotActive);
+                self.stream.require_within_lifetime(&self.platform_config)?;
+                self.stream.require_recipient_allowed(false)?;
+                require!(self.stream.available_balance()? >= prize, StreamError::InsufficientFunds);
+                enforce_distribution_policy(&self.stream, self.distribution_policy.as_mut(), prize)?;
+
+                let stream_seeds = self.stream.signer_seeds();
+                let stream_seeds: Vec<&[u8]> = stream_seeds.iter().map(Vec::as_slice).collect();
+                let signer = &[&stream_seeds[..]];
+                let fee = PlatformFeeRoute {
+                    platform_config: &self.platform_config,
+                    treasury_ata: self.treasury_ata.as_ref(),
+                    host_profile: self.host_profile.as_ref(),
+                    referral_rewards: self.referral_rewards.as_mut(),
+                    referral_vault: self.referral_vault.as_ref(),
+                }.collect(
+                    self.stream.host,
+                    &self.stream_ata,
+                    &self.mint,
+                    self.stream.to_account_info(),
+                    signer,
+                    &self.token_program,
+                    remaining_accounts,
+                    prize,
+                )?;
+                let burned = burn_stream_share(
+                    &self.stream,
+                    &self.stream_ata,
+                    &self.mint,
+                    self.stream.to_account_info(),
+                    signer,
+                    &self.token_program,
+                    prize - fee,
+                )?;
+
+                let cpi_accounts = TransferChecked {
+                    from: self.stream_ata.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: self.raffle_vault.to_account_info(),
+                    authority: self.stream.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+                    .with_remaining_accounts(remaining_accounts.to_vec());
+                transfer_checked(cpi_ctx, prize - fee - burned, self.mint.decimals)?;
+                self.stream.total_distributed = self.stream.total_distributed
+                    .checked_add(prize)
+                    .ok_or(StreamError::MathOverflow)?;
+                self.stream_ata.key()
+            }
+            _ => return err!(StreamError::MissingAccounts),
+        };
+        let prize = amount_received(&mut self.raffle_vault, vault_before)?;
+
+        self.raffle.set_inner(Raffle {
+            stream: self.stream.key(),
+            host: self.host.key(),
+            mint: self.mint.key(),
+            raffle_id,
+            funder_token,
+            prize,
+            ticket_price,
+            entry_deadline,
+            max_entries,
+            entry_count: 0,
+            status: RaffleStatus::Open,
+            draw_requested_at: 0,
+            winning_entry: 0,
+            winner: Pubkey::default(),
+            created_at: now,
+            bump: bumps.raffle,
+        });
+
+        emit!(RaffleCreated {
+            raffle: self.raffle.key(),
+            stream: self.stream.key(),
+            prize,
+            ticket_price,
+            entry_deadline,
+            sponsored: self.sponsor.is_some(),
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> EnterRaffle<'info> {
+    pub fn enter_raffle(&mut self, bumps: &EnterRaffleBumps, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let raffle = &self.raffle;
+        require!(
+            raffle.status == RaffleStatus::Open && now < raffle.entry_deadline,
+            StreamError::RaffleClosed
+        );
+        require!(
+            raffle.max_entries == 0 || raffle.entry_count < raffle.max_entries,
+            StreamError::RaffleFull
+        );
+
+        let ticket_price = raffle.ticket_price;
+        if ticket_price > 0 {
+            self.stream.require_deposits_open()?;
+            let cpi_accounts = TransferChecked {
+                from: self.entrant_token.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: self.stream_ata.to_account_info(),
+                authority: self.entrant.to_account_info(),
+            };
+            let vault_before = self.stream_ata.amount;
+            let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+            transfer_checked(cpi_ctx, ticket_price, self.mint.decimals)?;
+            let received = amount_received(&mut self.stream_ata, vault_before)?;
+            // Ticket buyers hold no donor record, so their payments stay out of refund math
+            self.stream.ticket_revenue = self.stream.ticket_revenue
+                .checked_add(received)
+                .ok_or(StreamError::MathOverflow)?;
+        }
+
+        let index = self.raffle.entry_count;
+        self.raffle.entry_count = index.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        self.entry.set_inner(RaffleEntry {
+            raffle: self.raffle.key(),
+            entrant: self.entrant.key(),
+            index,
+            entered_at: now,
+            bump: bumps.entry,
+        });
+
+        emit!(RaffleEntered {
+            raffle: self.raffle.key(),
+            entrant: self.entrant.key(),
+            index,
+            ticket_price,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> DrawRaffleWinner<'info> {
+    /// A draw whose callback has not arrived within `RANDOMNESS_REQUEST_TIMEOUT` may be requested again
+    pub fn draw_raffle_winner(&mut self, client_seed: [u8; 32]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let raffle = &mut self.raffle;
+        require!(now >= raffle.entry_deadline, StreamError::RaffleStillOpen);
+        require!(raffle.entry_count > 0, StreamError::NoRaffleEntries);
+        match raffle.status {
+            RaffleStatus::Open => {}
+            RaffleStatus::Drawing => require!(
+                now >= raffle.draw_requested_at.saturating_add(RANDOMNESS_REQUEST_TIMEOUT),
+                StreamError::RandomnessRequestPending
+            ),
+            _ => return err!(StreamError::RaffleClosed),
+        }
+        raffle.status = RaffleStatus::Drawing;
+        raffle.draw_requested_at = now;
+
+        let ix = create_request_randomness_ix(RequestRandomnessParams {
+            payer: self.requestor.key(),
+            oracle_queue: self.oracle_queue.key(),
+            callback_program_id: crate::ID,
+            callback_discriminator: crate::instruction::CallbackDrawRaffle::DISCRIMINATOR.to_vec(),
+            caller_seed: client_seed,
+            accounts_metas: Some(vec![SerializableAccountMeta {
+                pubkey: self.raffle.key(),
+                is_signer: false,
+                is_writable: true,
+            }]),
+        });
+        self.invoke_signed_vrf(&self.requestor.to_account_info(), &ix)?;
+
+        emit!(RaffleDrawRequested {
+            raffle: self.raffle.key(),
+            requestor: self.requestor.key(),
+            entry_count: self.raffle.entry_count,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> CallbackDrawRaffle<'info> {
+    pub fn draw_raffle(&mut self, randomness: [u8; 32]) -> Result<()> {
+        let raffle = &mut self.raffle;
+        require!(raffle.status == RaffleStatus::Drawing, StreamError::NoPendingRandomnessRequest);
+
+        let winning_entry = ephemeral_vrf_sdk::rnd::random_u64(&randomness) % raffle.entry_count as u64;
+        raffle.winning_entry = winning_entry as u32;
+        raffle.status = RaffleStatus::Drawn;
+
+        emit!(RaffleDrawn {
+            raffle: raffle.key(),
+            winning_entry: raffle.winning_entry,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> ClaimRafflePrize<'info> {
+    pub fn claim_raffle_prize(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(self.raffle.status == RaffleStatus::Drawn, StreamError::RaffleNotDrawn);
+        require!(self.entry.index == self.raffle.winning_entry, StreamError::NotRaffleWinner);
+
+        let amount = self.raffle_vault.amount;
+        pay_from_raffle_vault(
+            &self.raffle,
+            &self.raffle_vault,
+            &self.mint,
+            &self.winner_ata,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
+
+        self.raffle.status = RaffleStatus::Claimed;
+        self.raffle.winner = self.winner.key();
+
+        emit!(RafflePrizeClaimed {
+            raffle: self.raffle.key(),
+            winner: self.winner.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> CancelRaffle<'info> {
+    pub fn cancel_raffle(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.raffle.status == RaffleStatus::Open, StreamError::RaffleClosed);
+        require!(now >= self.raffle.entry_deadline, StreamError::RaffleStillOpen);
+        require!(self.raffle.entry_count == 0, StreamError::RaffleHasEntries);
+
+        let amount = self.raffle_vault.amount;
+        let funder_before = self.funder_token.amount;
+        pay_from_raffle_vault(
+            &self.raffle,
+            &self.raffle_vault,
+            &self.mint,
+            &self.funder_token,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
+        let refunded = amount_received(&mut self.funder_token, funder_before)?;
+
+        // The escrowed prize is available to the host again; its fee and burn share stay spent
+        if self.funder_token.owner == self.stream.key() {
+            self.stream.total_distributed = self.stream.total_distributed.saturating_sub(refunded);
+        }
+        self.raffle.status = RaffleStatus::Cancelled;
+
+        emit!(RaffleCancelled {
+            raffle: self.raffle.key(),
+            refunded,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+fn pay_from_raffle_vault<'info>(
+    raffle: &Account<'info, Raffle>,
+    raffle_vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    let id_bytes = raffle.raffle_id.to_le_bytes();
+    let raffle_seeds = &[RAFFLE_SEED, raffle.stream.as_ref(), id_bytes.as_ref(), &[raffle.bump]];
+    let signer = &[&raffle_seeds[..]];
+    let cpi_accounts = TransferChecked {
+        from: raffle_vault.to_account_info(),
+        mint: mint.to_account_info(),
+        to: to.to_account_info(),
+        authority: raffle.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+        .with_remaining_accounts(remaining_accounts.to_vec());
+    transfer_checked(cpi_ctx, amount, mint.decimals)
+}
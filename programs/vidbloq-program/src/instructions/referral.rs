@@ -0,0 +1,302 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
+};
+
+use crate::instructions::pay_from_vault;
+use crate::state::{
+    BetReferralEarned, BettingMarket, HostReferralRewardsClaimed, MarketConfig, ReferralEarnings,
+    ReferralEarningsClaimed, ReferralRewards, StreamError, BPS_DENOMINATOR,
+};
+
+pub const REFERRAL_REWARDS_SEED: &[u8] = b"referral_rewards";
+pub const REFERRAL_VAULT_SEED: &[u8] = b"referral_vault";
+pub const REFERRAL_EARNINGS_SEED: &[u8] = b"referral_earnings";
+pub const REFERRAL_EARNINGS_VAULT_SEED: &[u8] = b"referral_earnings_vault";
+
+/// Opens a referrer's rewards account and vault for one mint. Payouts in that mint by hosts the
+/// referrer referred need it while the referral is active, so anyone may pay to open it.
+#[derive(Accounts)]
+pub struct OpenReferralRewards<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Only used as a seed and recorded as the rewards owner
+    pub referrer: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ReferralRewards::INIT_SPACE,
+        seeds = [REFERRAL_REWARDS_SEED, referrer.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub referral_rewards: Account<'info, ReferralRewards>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [REFERRAL_VAULT_SEED, referral_rewards.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = referral_rewards,
+        token::token_program = token_program,
+    )]
+    pub referral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> OpenReferralRewards<'info> {
+    pub fn open_referral_rewards(&mut self, bumps: &OpenReferralRewardsBumps) -> Result<()> {
+        self.referral_rewards.set_inner(ReferralRewards {
+            referrer: self.referrer.key(),
+            mint: self.mint.key(),
+            epoch: Clock::get()?.epoch,
+            epoch_accrued: 0,
+            total_accrued: 0,
+            total_claimed: 0,
+            bump: bumps.referral_rewards,
+        });
+        Ok(())
+    }
+}
+
+/// Pays out a referrer's accrued host-referral rewards. Rewards are escrowed in a vault owned by
+/// the referrer's `ReferralRewards` PDA as platform fees are routed.
+#[derive(Accounts)]
+pub struct ClaimHostReferralRewards<'info> {
+    pub referrer: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [REFERRAL_REWARDS_SEED, referrer.key().as_ref(), mint.key().as_ref()],
+        bump = referral_rewards.bump,
+        has_one = referrer,
+        has_one = mint,
+    )]
+    pub referral_rewards: Account<'info, ReferralRewards>,
+
+    #[account(
+        mut,
+        seeds = [REFERRAL_VAULT_SEED, referral_rewards.key().as_ref()],
+        bump,
+    )]
+    pub referral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = referrer_ata.owner == referrer.key(),
+        constraint = referrer_ata.mint == mint.key()
+    )]
+    pub referrer_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>
+}
+
+impl<'info> ClaimHostReferralRewards<'info> {
+    pub fn claim_host_referral_rewards(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let amount = std::cmp::min(self.referral_rewards.claimable(), self.referral_vault.amount);
+        require!(amount > 0, StreamError::NothingToClaim);
+
+        let referrer_key = self.referrer.key();
+        let mint_key = self.mint.key();
+        let rewards_seeds = &[
+            REFERRAL_REWARDS_SEED,
+            referrer_key.as_ref(),
+            mint_key.as_ref(),
+            &[self.referral_rewards.bump],
+        ];
+        let signer = &[&rewards_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.referral_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.referrer_ata.to_account_info(),
+            authority: self.referral_rewards.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        self.referral_rewards.total_claimed = self.referral_rewards.total_claimed.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+
+        emit!(HostReferralRewardsClaimed {
+            referrer: referrer_key,
+            mint: mint_key,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+/// Opens an affiliate's bet-referral earnings account and vault for one mint. Until it exists,
+/// the affiliate's share of the fee on their referred bettors' claims stays with the host.
+#[derive(Accounts)]
+pub struct OpenReferralEarnings<'info> {
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = referrer,
+        space = ReferralEarnings::INIT_SPACE,
+        seeds = [REFERRAL_EARNINGS_SEED, referrer.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub referral_earnings: Account<'info, ReferralEarnings>,
+
+    #[account(
+        init,
+        payer = referrer,
+        seeds = [REFERRAL_EARNINGS_VAULT_SEED, referral_earnings.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = referral_earnings,
+        token::token_program = token_program,
+    )]
+    pub referral_earnings_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> OpenReferralEarnings<'info> {
+    pub fn open_referral_earnings(&mut self, bumps: &OpenReferralEarningsBumps) -> Result<()> {
+        self.referral_earnings.set_inner(ReferralEarnings {
+            referrer: self.referrer.key(),
+            mint: self.mint.key(),
+            referred_claims: 0,
+            total_earned: 0,
+            total_claimed: 0,
+            bump: bumps.referral_earnings,
+        });
+        Ok(())
+    }
+}
+
+/// Pays out an affiliate's bet-referral earnings
+#[derive(Accounts)]
+pub struct ClaimReferralEarnings<'info> {
+    pub referrer: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [REFERRAL_EARNINGS_SEED, referrer.key().as_ref(), mint.key().as_ref()],
+        bump = referral_earnings.bump,
+        has_one = referrer,
+        has_one = mint,
+    )]
+    pub referral_earnings: Account<'info, ReferralEarnings>,
+
+    #[account(
+        mut,
+        seeds = [REFERRAL_EARNINGS_VAULT_SEED, referral_earnings.key().as_ref()],
+        bump,
+    )]
+    pub referral_earnings_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = referrer_ata.owner == referrer.key(),
+        constraint = referrer_ata.mint == mint.key()
+    )]
+    pub referrer_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>
+}
+
+impl<'info> ClaimReferralEarnings<'info> {
+    pub fn claim_referral_earnings(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let amount = std::cmp::min(self.referral_earnings.claimable(), self.referral_earnings_vault.amount);
+        require!(amount > 0, StreamError::NothingToClaim);
+
+        let referrer_key = self.referrer.key();
+        let mint_key = self.mint.key();
+        let earnings_seeds = &[
+            REFERRAL_EARNINGS_SEED,
+            referrer_key.as_ref(),
+            mint_key.as_ref(),
+            &[self.referral_earnings.bump],
+        ];
+        let signer = &[&earnings_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.referral_earnings_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.referrer_ata.to_account_info(),
+            authority: self.referral_earnings.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        self.referral_earnings.total_claimed = self.referral_earnings.total_claimed.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+
+        emit!(ReferralEarningsClaimed {
+            referrer: referrer_key,
+            mint: mint_key,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+/// Moves the market's referral share of `host_fee` out of the market vault into the bettor's
+/// referrer's earnings vault, taking it out of the host's collected fees, and returns it.
+/// Nothing is paid unless the bettor was referred and the referrer has opened earnings for
+/// the mint.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn pay_bet_referral<'info>(
+    market: &mut Account<'info, BettingMarket>,
+    config: &MarketConfig,
+    bettor: Pubkey,
+    referrer: Option<Pubkey>,
+    host_fee: u64,
+    earnings: Option<&mut Account<'info, ReferralEarnings>>,
+    earnings_vault: Option<&InterfaceAccount<'info, TokenAccount>>,
+    market_vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<u64> {
+    let (Some(referrer), Some(earnings), Some(earnings_vault)) = (referrer, earnings, earnings_vault) else {
+        return Ok(0);
+    };
+    let amount = (host_fee as u128 * config.referral_share_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+    if amount == 0 {
+        return Ok(0);
+    }
+    require_keys_eq!(earnings.referrer, referrer, StreamError::Unauthorized);
+    require_keys_eq!(earnings.mint, mint.key(), StreamError::InvalidMint);
+    let (expected_earnings, _) =
+        Pubkey::find_program_address(&[REFERRAL_EARNINGS_SEED, referrer.as_ref(), mint.key().as_ref()], &crate::ID);
+    require_keys_eq!(earnings.key(), expected_earnings, StreamError::Unauthorized);
+    let (expected_vault, _) =
+        Pubkey::find_program_address(&[REFERRAL_EARNINGS_VAULT_SEED, earnings.key().as_ref()], &crate::ID);
+    require_keys_eq!(earnings_vault.key(), expected_vault, StreamError::Unauthorized);
+
+    pay_from_vault(market, market_vault, mint, earnings_vault, token_program, remaining_accounts, amount)?;
+    market.fees_collected = market.fees_collected.checked_sub(amount).ok_or(StreamError::MathOverflow)?;
+    earnings.accrue(amount)?;
+
+    emit!(BetReferralEarned {
+        referrer,
+        market: market.key(),
+        bettor,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(amount)
+}
@@ -1,10 +1,10 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken, 
-    token::{Transfer, transfer as token_transfer},
-    token_interface::{TokenAccount, TokenInterface}
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
 };
-use crate::state::{StreamState, StreamError, DonorAccount, StreamStatus, RefundProcessed};
+use crate::instructions::{FEATURE_GATES_SEED, PLATFORM_CONFIG_SEED};
+use crate::state::{StreamState, StreamStatus, StreamError, DonorAccount, FeatureGates, Leaderboard, PlatformConfig, RefundProcessed, BatchRefundProcessed};
 
 #[derive(Accounts)]
 pub struct Refund <'info> {
@@ -20,11 +20,14 @@ pub struct Refund <'info> {
 
     #[account(
         mut, 
-        seeds=[b"stream", stream.stream_name.as_str().as_bytes(), stream.host.key().as_ref()],
-        bump=stream.bump
+        seeds=[b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump=stream.bump,
+        has_one = mint
      )]
     pub stream: Account<'info, StreamState>,
 
+    pub mint: InterfaceAccount<'info, Mint>,
+
     #[account(
         mut,
         seeds = [b"donor", stream.key().as_ref(), donor.key().as_ref()],
@@ -49,70 +52,336 @@ pub struct Refund <'info> {
     )]
     pub stream_ata: InterfaceAccount<'info, TokenAccount>,
 
+    /// Required while the stream maintains a leaderboard
+    #[account(
+        mut,
+        seeds = [b"leaderboard", stream.key().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+
+    /// CHECK: Feature gates PDA; may be uninitialized
+    #[account(seeds = [FEATURE_GATES_SEED], bump)]
+    pub feature_gates: UncheckedAccount<'info>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>
 }
 
 impl <'info> Refund <'info> {
-    pub fn refund(&mut self, amount: u64) -> Result<()> {
-        require!(amount > 0, StreamError::InvalidAmount);
-        require!(self.donor_account.refunded == false, StreamError::AlreadyRefunded);
-        require!(amount <= self.donor_account.amount, StreamError::InsufficientFunds);
+    pub fn refund(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Past the platform's maximum lifetime donors may always exit, whatever the host did
+        let config = PlatformConfig::load(&self.platform_config)?;
+        if !PlatformConfig::stream_expired(config.as_ref(), self.stream.created_at, Clock::get()?.unix_timestamp) {
+            self.stream.require_refunds_open()?;
+        }
+        FeatureGates::require_not_queued(&self.feature_gates, amount)?;
+
+        process_refund(
+            &mut self.stream,
+            &mut self.donor_account,
+            self.leaderboard.as_mut(),
+            &self.mint,
+            &self.stream_ata,
+            &self.donor_ata,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )
+    }
+}
+
+/// Permissionless push of a donor's whole balance back to their associated token account once a
+/// stream is cancelled, so small donors need not transact themselves. Balances above the stream's
+/// dust threshold must go through `refund`.
+#[derive(Accounts)]
+pub struct PushDustRefund <'info> {
+    pub caller: Signer<'info>,
+
+    /// CHECK: This is the donor public key
+    pub donor: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds=[b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump=stream.bump,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"donor", stream.key().as_ref(), donor.key().as_ref()],
+        bump = donor_account.bump,
+        constraint = donor_account.donor == donor.key(),
+        constraint = donor_account.stream == stream.key()
+    )]
+    pub donor_account: Account<'info, DonorAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = donor,
+        associated_token::token_program = token_program
+    )]
+    pub donor_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Required while the stream maintains a leaderboard
+    #[account(
+        mut,
+        seeds = [b"leaderboard", stream.key().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+
+    /// CHECK: Feature gates PDA; may be uninitialized
+    #[account(seeds = [FEATURE_GATES_SEED], bump)]
+    pub feature_gates: UncheckedAccount<'info>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>
+}
+
+impl <'info> PushDustRefund <'info> {
+    pub fn push_dust_refund(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(self.stream.status == StreamStatus::Cancelled, StreamError::StreamNotCancelled);
+        let amount = self.donor_account.amount;
+        require!(amount <= self.stream.dust_refund_threshold, StreamError::AboveDustThreshold);
+        FeatureGates::require_not_queued(&self.feature_gates, amount)?;
 
+        process_refund(
+            &mut self.stream,
+            &mut self.donor_account,
+            self.leaderboard.as_mut(),
+            &self.mint,
+            &self.stream_ata,
+            &self.donor_ata,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )
+    }
+}
+
+/// Moves `amount` from the stream vault back to the donor and updates donor and stream accounting.
+/// Callers are responsible for the stream-level refund policy.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn process_refund<'info>(
+    stream: &mut Account<'info, StreamState>,
+    donor_account: &mut Account<'info, DonorAccount>,
+    leaderboard: Option<&mut Account<'info, Leaderboard>>,
+    mint: &InterfaceAccount<'info, Mint>,
+    stream_ata: &InterfaceAccount<'info, TokenAccount>,
+    donor_ata: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    require!(donor_ata.owner == donor_account.donor, StreamError::Unauthorized);
+
+    let payout = record_refund(stream, donor_account, leaderboard, amount)?;
+
+    let cpi_program = token_program.to_account_info();
+
+    let cpi_accounts = TransferChecked {
+        from: stream_ata.to_account_info(),
+        mint: mint.to_account_info(),
+        to: donor_ata.to_account_info(),
+        authority: stream.to_account_info(),
+    };
+
+    let stream_seeds = stream.signer_seeds();
+    let stream_seeds: Vec<&[u8]> = stream_seeds.iter().map(Vec::as_slice).collect();
+    let signer = &[&stream_seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer)
+        .with_remaining_accounts(remaining_accounts.to_vec());
+    transfer_checked(cpi_ctx, payout, mint.decimals)
+}
+
+/// Validates a refund of `amount` against the donor's balance and the stream's available funds,
+/// then debits both and emits `RefundProcessed`. Returns what the donor is owed, less than
+/// `amount` when a cancelled stream cannot cover every donor; the caller moves the funds.
+pub(crate) fn record_refund(
+    stream: &mut Account<StreamState>,
+    donor_account: &mut Account<DonorAccount>,
+    leaderboard: Option<&mut Account<Leaderboard>>,
+    amount: u64,
+) -> Result<u64> {
+    require!(amount > 0, StreamError::InvalidAmount);
+    require!(!donor_account.refunded, StreamError::AlreadyRefunded);
+    require!(amount <= donor_account.amount, StreamError::InsufficientFunds);
+    require!(
+        amount <= donor_account.refundable(stream.status == StreamStatus::Active),
+        StreamError::MatchedDepositNotRefundable
+    );
+
+    // Calculate available stream balance
+    let available_balance = stream.refundable_balance()?;
+    let payout = stream.refund_payout(amount)?;
+
+    // Ensure sufficient funds in the stream
+    require!(available_balance >= payout, StreamError::InsufficientFunds);
+
+    // Update donor account
+    donor_account.amount = donor_account.amount.checked_sub(amount).ok_or(StreamError::MathOverflow)?;
+    donor_account.matched_deposits = donor_account.matched_deposits.min(donor_account.amount);
+    
+    // Mark as fully refunded if all funds returned
+    if donor_account.amount == 0 {
+        donor_account.refunded = true;
+    }
+    
+    // Update stream state
+    stream.retire_refund(amount, payout)?;
+
+    if stream.leaderboard_active {
+        let leaderboard = leaderboard.ok_or(StreamError::MissingAccounts)?;
+        leaderboard.record(donor_account.donor, donor_account.amount);
+    }
+
+    emit!(RefundProcessed {
+        stream: stream.key(),
+        donor: donor_account.donor,
+        amount: payout,
+        remaining_balance: donor_account.amount,
+        timestamp: Clock::get()?.unix_timestamp
+    });
+    Ok(payout)
+}
+
+/// Permissionless refund of every donor passed in remaining accounts as
+/// `[donor_account, donor_ata]` pairs. Only available once a stream is cancelled.
+/// Remaining accounts are taken by the donor pairs, so mints with a transfer hook must use `refund`.
+#[derive(Accounts)]
+pub struct RefundBatch <'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds=[b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump=stream.bump,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Required while the stream maintains a leaderboard
+    #[account(
+        mut,
+        seeds = [b"leaderboard", stream.key().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+
+    /// CHECK: Feature gates PDA; may be uninitialized
+    #[account(seeds = [FEATURE_GATES_SEED], bump)]
+    pub feature_gates: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>
+}
+
+impl <'info> RefundBatch <'info> {
+    pub fn refund_batch(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(self.stream.status == StreamStatus::Cancelled, StreamError::StreamNotCancelled);
         require!(
-            self.stream.status != StreamStatus::Ended,
-            StreamError::StreamAlreadyEnded
+            !remaining_accounts.is_empty() && remaining_accounts.chunks_exact(2).remainder().is_empty(),
+            StreamError::InvalidRemainingAccounts
         );
 
-        // Calculate available stream balance
-        let available_balance = self.stream.total_deposited
-            .checked_sub(self.stream.total_distributed)
-            .ok_or(StreamError::MathOverflow)?;
-
-        // Ensure sufficient funds in the stream
-        require!(available_balance >= amount, StreamError::InsufficientFunds);
-
-        let cpi_program = self.token_program.to_account_info();
-
-        let cpi_accounts = Transfer {
-            from: self.stream_ata.to_account_info(),
-            to: self.donor_ata.to_account_info(),
-            authority: self.stream.to_account_info(),
-        };
-
-        // let stream_seeds = &[
-        //     b"stream".as_ref(),
-        //     self.stream.host.as_ref(),
-        //     self.stream.stream_name.as_bytes(),
-        //     &[self.stream.bump],
-        // ];
-        let stream_seeds = &[
-            b"stream".as_ref(),
-            self.stream.stream_name.as_str().as_bytes(),
-            self.stream.host.as_ref(),
-            &[self.stream.bump],
-        ];
-        let signer = &[&stream_seeds[..]];
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        token_transfer(cpi_ctx, amount)?;
-        // Update donor account
-        self.donor_account.amount = self.donor_account.amount.checked_sub(amount).ok_or(StreamError::MathOverflow)?;
-        
-        // Mark as fully refunded if all funds returned
-        if self.donor_account.amount == 0 {
-            self.donor_account.refunded = true;
+        let stream_key = self.stream.key();
+        let gates = FeatureGates::load(&self.feature_gates)?;
+        let mut donors_refunded: u32 = 0;
+        let mut total_refunded: u64 = 0;
+
+        for pair in remaining_accounts.chunks_exact(2) {
+            let mut donor_account = Account::<DonorAccount>::try_from(&pair[0])?;
+            let donor_ata = InterfaceAccount::<TokenAccount>::try_from(&pair[1])?;
+
+            require!(pair[0].is_writable && pair[1].is_writable, StreamError::InvalidRemainingAccounts);
+            require_keys_eq!(donor_account.stream, stream_key, StreamError::Unauthorized);
+            let expected = Pubkey::create_program_address(
+                &[b"donor", stream_key.as_ref(), donor_account.donor.as_ref(), &[donor_account.bump]],
+                &crate::ID,
+            ).map_err(|_| StreamError::Unauthorized)?;
+            require_keys_eq!(expected, donor_account.key(), StreamError::Unauthorized);
+            require_keys_eq!(donor_ata.owner, donor_account.donor, StreamError::Unauthorized);
+            require_keys_eq!(donor_ata.mint, self.stream.mint, StreamError::Unauthorized);
+
+            // Already-settled donors are skipped so overlapping batches don't fail
+            if donor_account.refunded || donor_account.amount == 0 {
+                continue;
+            }
+
+            let payout = self.stream.refund_payout(donor_account.amount)?;
+            // Payouts above the queue threshold are left for `queue_refund`
+            if gates.as_ref().is_some_and(|gates| gates.queues(payout)) {
+                continue;
+            }
+            if payout > 0 {
+                let stream_seeds = self.stream.signer_seeds();
+                let stream_seeds: Vec<&[u8]> = stream_seeds.iter().map(Vec::as_slice).collect();
+                let signer = &[&stream_seeds[..]];
+                let cpi_accounts = TransferChecked {
+                    from: self.stream_ata.to_account_info(),
+                    mint: self.mint.to_account_info(),
+                    to: donor_ata.to_account_info(),
+                    authority: self.stream.to_account_info(),
+                };
+                let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer);
+                transfer_checked(cpi_ctx, payout, self.mint.decimals)?;
+            }
+
+            // Retire the donor's whole contribution so later batches see the same pro-rata ratio
+            self.stream.retire_refund(donor_account.amount, payout)?;
+
+            donor_account.amount = 0;
+            donor_account.refunded = true;
+            donor_account.exit(&crate::ID)?;
+            if self.stream.leaderboard_active {
+                let leaderboard = self.leaderboard.as_mut().ok_or(StreamError::MissingAccounts)?;
+                leaderboard.record(donor_account.donor, 0);
+            }
+
+            emit!(RefundProcessed {
+                stream: stream_key,
+                donor: donor_account.donor,
+                amount: payout,
+                remaining_balance: 0,
+                timestamp: Clock::get()?.unix_timestamp
+            });
+
+            donors_refunded += 1;
+            total_refunded = total_refunded.checked_add(payout).ok_or(StreamError::MathOverflow)?;
         }
-        
-        // Update stream state
-        self.stream.total_deposited = self.stream.total_deposited.checked_sub(amount).ok_or(StreamError::MathOverflow)?;
 
-        emit!(RefundProcessed {
-            stream: self.stream.key(),
-            donor: self.donor.key(),
-            amount,
-            remaining_balance: self.donor_account.amount,
+        emit!(BatchRefundProcessed {
+            stream: stream_key,
+            caller: self.caller.key(),
+            donors_refunded,
+            total_refunded,
             timestamp: Clock::get()?.unix_timestamp
         });
         Ok(())
     }
-}
\ No newline at end of file
+}
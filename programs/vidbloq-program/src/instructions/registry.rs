@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{
+    allocate, assign, transfer as system_transfer, Allocate, Assign, Transfer as SystemTransfer,
+};
+
+use crate::state::{GlobalRegistry, HostStreamIndex, GlobalRegistryInitialized, StreamError};
+
+pub const GLOBAL_REGISTRY_SEED: &[u8] = b"global_registry";
+pub const HOST_STREAM_INDEX_SEED: &[u8] = b"host_stream_index";
+
+/// Creates the global registry singleton; anyone may pay for it
+#[derive(Accounts)]
+pub struct InitializeGlobalRegistry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = GlobalRegistry::INIT_SPACE,
+        seeds = [GLOBAL_REGISTRY_SEED],
+        bump
+    )]
+    pub global_registry: Account<'info, GlobalRegistry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeGlobalRegistry<'info> {
+    pub fn initialize_global_registry(&mut self, bumps: &InitializeGlobalRegistryBumps) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.global_registry.set_inner(GlobalRegistry {
+            stream_count: 0,
+            total_deposited: 0,
+            total_distributed: 0,
+            created_at: now,
+            bump: bumps.global_registry,
+        });
+
+        emit!(GlobalRegistryInitialized {
+            payer: self.payer.key(),
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+/// Appends a new stream to its host's index, creating the index on first use and growing it
+/// by one entry at the host's expense
+pub(crate) fn index_stream<'info>(
+    index: &UncheckedAccount<'info>,
+    host: &Signer<'info>,
+    stream: Pubkey,
+    bump: u8,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let info = index.to_account_info();
+    let is_new = info.data_is_empty();
+    let mut state = if is_new {
+        HostStreamIndex { host: host.key(), streams: Vec::new(), bump }
+    } else {
+        require_keys_eq!(*info.owner, crate::ID, StreamError::Unauthorized);
+        HostStreamIndex::try_deserialize(&mut &info.data.borrow()[..])?
+    };
+    state.streams.push(stream);
+
+    let space = HostStreamIndex::space(state.streams.len());
+    let shortfall = Rent::get()?.minimum_balance(space).saturating_sub(info.lamports());
+    if shortfall > 0 {
+        let cpi_accounts = SystemTransfer {
+            from: host.to_account_info(),
+            to: info.clone(),
+        };
+        system_transfer(CpiContext::new(system_program.to_account_info(), cpi_accounts), shortfall)?;
+    }
+
+    if is_new {
+        // Allocate and assign rather than create, so lamports sent to the address beforehand
+        // cannot block the host's first stream
+        let host_key = host.key();
+        let seeds = &[HOST_STREAM_INDEX_SEED, host_key.as_ref(), &[bump]];
+        let signer = &[&seeds[..]];
+        allocate(
+            CpiContext::new_with_signer(system_program.to_account_info(), Allocate { account_to_allocate: info.clone() }, signer),
+            space as u64,
+        )?;
+        assign(
+            CpiContext::new_with_signer(system_program.to_account_info(), Assign { account_to_assign: info.clone() }, signer),
+            &crate::ID,
+        )?;
+    } else {
+        info.resize(space)?;
+    }
+
+    state.try_serialize(&mut &mut info.data.borrow_mut()[..])?;
+    Ok(())
+}
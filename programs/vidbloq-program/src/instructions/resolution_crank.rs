@@ -0,0 +1,173 @@
+use anchor_lang::prelude::*;
+use ephemeral_vrf_sdk::anchor::vrf;
+
+use crate::instructions::{
+    finalize_consensus, force_timeout, list_eligible_validators, open_resolution, randomness_request_ix,
+    require_validator_selection_due, MARKET_BOOK_SEED, MARKET_CONFIG_SEED, MARKET_SEED, RESOLUTION_SEED,
+    VALIDATOR_REGISTRY_SEED,
+};
+use crate::state::{
+    BettingMarket, MarketBook, MarketConfig, MarketResolution, RandomnessRequestStatus, RandomnessRequested,
+    RandomnessUseCase, ResolutionStatus, StreamError, ValidatorRegistry,
+};
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Anyone, typically a keeper bot, advances a market's resolution by whichever step is due
+#[vrf]
+#[derive(Accounts)]
+pub struct CrankResolution<'info> {
+    #[account(mut)]
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, market.stream.as_ref()],
+        bump = market.bump,
+    )]
+    pub market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_BOOK_SEED, market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = caller,
+        space = MarketResolution::SPACE,
+        seeds = [RESOLUTION_SEED, market.key().as_ref()],
+        bump
+    )]
+    pub resolution: AccountLoader<'info, MarketResolution>,
+
+    /// Validators staked for the market's mint, read when the crank starts validator selection
+    #[account(
+        seeds = [VALIDATOR_REGISTRY_SEED, market.mint.as_ref()],
+        bump = validator_registry.load()?.bump,
+    )]
+    pub validator_registry: Option<AccountLoader<'info, ValidatorRegistry>>,
+
+    /// CHECK: The oracle queue from Ephemeral VRF
+    #[account(mut, address = ephemeral_vrf_sdk::consts::DEFAULT_QUEUE)]
+    pub oracle_queue: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+enum CrankStep {
+    SelectValidators,
+    Finalize,
+    ForceTimeout,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> CrankResolution<'info> {
+    /// Finalizes a consensus once its dispute window has passed, forces a resolution that has
+    /// outrun the market's timeout, and otherwise requests validator selection once the
+    /// resolution time has come, so markets resolve even if the host never returns. Each step
+    /// runs the same checks as its own instruction; `remaining_accounts` are read only for
+    /// validator selection, as in `request_market_randomness`.
+    pub fn crank_resolution(
+        &mut self,
+        client_seed: [u8; 32],
+        bumps: &CrankResolutionBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.market.resolved, StreamError::MarketResolved);
+        let now = Clock::get()?.unix_timestamp;
+
+        // A fresh account has no discriminator yet, so validator selection is the only step
+        let is_new = self.resolution.as_ref().try_borrow_data()?[..8] == [0u8; 8];
+        let step = if is_new {
+            CrankStep::SelectValidators
+        } else {
+            let resolution = self.resolution.load()?;
+            let deadline = self.market_config.resolution_deadline(self.market.resolution_time);
+            if resolution.status_is(ResolutionStatus::Finalized) {
+                CrankStep::Finalize
+            } else if resolution.status_is(ResolutionStatus::ForcedByRandomness)
+                || (!resolution.status_is(ResolutionStatus::Disputed) && now >= deadline)
+            {
+                CrankStep::ForceTimeout
+            } else if resolution.require_validator_selection_open().is_ok()
+                && !resolution.randomness_status_is(RandomnessRequestStatus::Pending)
+            {
+                CrankStep::SelectValidators
+            } else {
+                return err!(StreamError::NothingToCrank);
+            }
+        };
+
+        match step {
+            CrankStep::Finalize => finalize_consensus(
+                &mut self.market,
+                &self.market_book,
+                &self.market_config,
+                &self.resolution,
+                now,
+            ),
+            CrankStep::ForceTimeout => {
+                if force_timeout(&mut self.market, &self.market_config, &self.resolution, self.caller.key(), now)? {
+                    self.request_randomness(client_seed)?;
+                }
+                Ok(())
+            }
+            CrankStep::SelectValidators => self.select_validators(client_seed, now, bumps, remaining_accounts),
+        }
+    }
+
+    fn select_validators(
+        &mut self,
+        client_seed: [u8; 32],
+        now: i64,
+        bumps: &CrankResolutionBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require_validator_selection_due(&self.market, &self.market_config, now)?;
+        let nonce = {
+            let mut resolution = open_resolution(&self.resolution, &self.market, bumps.resolution)?;
+            resolution.require_validator_selection_open()?;
+            let nonce = resolution.begin_randomness_request(RandomnessUseCase::ValidatorSelection, now)?;
+            let (eligible, exposure) = list_eligible_validators(
+                &self.market.key(),
+                &*self.market_book.load()?,
+                self.validator_registry.as_ref(),
+                remaining_accounts,
+            )?;
+            resolution.set_eligible_validators(&eligible, &exposure, self.validator_registry.is_some())?;
+            resolution.set_status(ResolutionStatus::AwaitingRandomness);
+            nonce
+        };
+        self.market.randomness_requested = true;
+        self.request_randomness(client_seed)?;
+
+        emit!(RandomnessRequested {
+            market: self.market.key(),
+            nonce,
+            use_case: RandomnessUseCase::ValidatorSelection,
+            requestor: self.caller.key(),
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    fn request_randomness(&self, client_seed: [u8; 32]) -> Result<()> {
+        let ix = randomness_request_ix(
+            self.caller.key(),
+            self.oracle_queue.key(),
+            client_seed,
+            [self.market.key(), self.market_book.key(), self.market_config.key(), self.resolution.key()],
+        );
+        self.invoke_signed_vrf(&self.caller.to_account_info(), &ix)
+    }
+}
@@ -0,0 +1,361 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::instructions::{
+    emit_odds, FEATURE_GATES_SEED, MARKET_BOOK_SEED, MARKET_CONFIG_SEED, MARKET_SEED, MARKET_STATS_SEED, MARKET_VAULT_SEED,
+    OUTCOME_POSITION_SEED, POSITION_SEED, VALIDATOR_STAKE_REQUIREMENT,
+};
+use crate::math::lmsr_sell_proceeds;
+use crate::state::{
+    net_of_fee, AmmCurve, BettingMarket, BettorPosition, FeatureGates, MarketBook, MarketConfig, MarketStats, OutcomePositionAccount, SharesSold,
+    StreamError,
+};
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Sell shares back into the market's AMM before resolution
+#[derive(Accounts)]
+#[instruction(outcome_id: u8)]
+pub struct SellShares<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump = bettor_position.bump,
+    )]
+    pub bettor_position: Account<'info, BettorPosition>,
+
+    #[account(
+        mut,
+        seeds = [OUTCOME_POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref(), outcome_id.to_le_bytes().as_ref()],
+        bump = outcome_position.bump,
+    )]
+    pub outcome_position: Account<'info, OutcomePositionAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = bettor_token.owner == bettor.key(),
+        constraint = bettor_token.mint == mint.key(),
+    )]
+    pub bettor_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Market stats PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [MARKET_STATS_SEED, betting_market.key().as_ref()], bump)]
+    pub market_stats: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Feature gates PDA; may be uninitialized
+    #[account(seeds = [FEATURE_GATES_SEED], bump)]
+    pub feature_gates: UncheckedAccount<'info>,
+}
+
+/// Sell every outcome a position holds and close it and its outcome positions, returning their
+/// rent. The leading remaining accounts are the bettor's open outcome positions, in any order.
+#[derive(Accounts)]
+pub struct CashOutPosition<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        close = bettor,
+        seeds = [POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump = bettor_position.bump,
+    )]
+    pub bettor_position: Account<'info, BettorPosition>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = bettor_token.owner == bettor.key(),
+        constraint = bettor_token.mint == mint.key(),
+    )]
+    pub bettor_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Market stats PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [MARKET_STATS_SEED, betting_market.key().as_ref()], bump)]
+    pub market_stats: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Feature gates PDA; may be uninitialized
+    #[account(seeds = [FEATURE_GATES_SEED], bump)]
+    pub feature_gates: UncheckedAccount<'info>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> SellShares<'info> {
+    pub fn sell_shares(
+        &mut self,
+        outcome_id: u8,
+        shares: u64,
+        min_usdc_out: u64,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.betting_market.betting_open(Clock::get()?.unix_timestamp),
+            StreamError::BettingClosed
+        );
+        require!(outcome_id < self.betting_market.outcome_count, StreamError::InvalidOutcome);
+        require!(shares > 0, StreamError::InvalidAmount);
+        // Free bet credit only pays out as winnings, never as cash on the way out
+        require!(self.bettor_position.credit_staked == 0, StreamError::FreeBetSharesNotSellable);
+
+        require!(self.outcome_position.shares >= shares, StreamError::InsufficientShares);
+
+        let usdc_out = {
+            let mut book = self.market_book.load_mut()?;
+            let usdc_out = sell_into_book(
+                &self.betting_market.amm_curve,
+                &mut book,
+                self.market_config.fee_percentage,
+                outcome_id,
+                shares,
+            )?;
+            emit_odds(self.betting_market.key(), &self.betting_market.amm_curve, &book)?;
+            usdc_out
+        };
+        require!(usdc_out > 0, StreamError::InvalidAmount);
+        require!(usdc_out >= min_usdc_out, StreamError::SlippageExceeded);
+        FeatureGates::require_not_queued(&self.feature_gates, usdc_out)?;
+
+        msg!("Selling {} shares for {} USDC", shares, usdc_out);
+
+        // Reduce the position's cost basis in proportion to the shares sold
+        let held = &mut self.outcome_position;
+        let invested_sold = (held.invested as u128 * shares as u128 / held.shares as u128) as u64;
+        held.shares -= shares;
+        held.invested -= invested_sold;
+        let position = &mut self.bettor_position;
+        if held.shares == 0 {
+            position.open_outcomes -= 1;
+            held.close(self.bettor.to_account_info())?;
+        }
+        let was_eligible = position.total_invested >= VALIDATOR_STAKE_REQUIREMENT;
+        position.total_invested = position.total_invested.saturating_sub(invested_sold);
+        position.is_eligible_validator = position.total_invested >= VALIDATOR_STAKE_REQUIREMENT;
+        self.market_book.load_mut()?.track_eligibility(was_eligible, position.is_eligible_validator)?;
+
+        pay_from_vault(
+            &self.betting_market,
+            &self.market_vault,
+            &self.mint,
+            &self.bettor_token,
+            &self.token_program,
+            remaining_accounts,
+            usdc_out,
+        )?;
+        MarketStats::record_sell(&self.market_stats, usdc_out)?;
+
+        emit!(SharesSold {
+            market: self.betting_market.key(),
+            bettor: self.bettor.key(),
+            outcome_id,
+            shares,
+            usdc_out,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> CashOutPosition<'info> {
+    /// Sells every outcome the bettor holds at current odds and closes the position
+    pub fn cash_out_position(&mut self, min_usdc_out: u64, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(!self.betting_market.resolved, StreamError::MarketResolved);
+        require!(
+            self.betting_market.betting_open(Clock::get()?.unix_timestamp),
+            StreamError::BettingClosed
+        );
+        let open_outcomes = self.bettor_position.open_outcomes as usize;
+        require!(open_outcomes > 0, StreamError::InsufficientShares);
+        // Free bet credit only pays out as winnings, never as cash on the way out
+        require!(self.bettor_position.credit_staked == 0, StreamError::FreeBetSharesNotSellable);
+        require!(remaining_accounts.len() >= open_outcomes, StreamError::InvalidRemainingAccounts);
+        let (position_accounts, remaining_accounts) = remaining_accounts.split_at(open_outcomes);
+
+        let mut held = Vec::with_capacity(open_outcomes);
+        for info in position_accounts {
+            let position = Account::<OutcomePositionAccount>::try_from(info)?;
+            require!(
+                position.market == self.betting_market.key()
+                    && position.bettor == self.bettor.key()
+                    && held.iter().all(|h: &Account<OutcomePositionAccount>| h.outcome_id != position.outcome_id),
+                StreamError::InvalidRemainingAccounts
+            );
+            held.push(position);
+        }
+
+        let mut usdc_out = 0u64;
+        {
+            let mut book = self.market_book.load_mut()?;
+            for position in &held {
+                let out = sell_into_book(&self.betting_market.amm_curve, &mut book, self.market_config.fee_percentage, position.outcome_id, position.shares)?;
+                usdc_out = usdc_out.checked_add(out).ok_or(StreamError::MathOverflow)?;
+
+                emit!(SharesSold {
+                    market: self.betting_market.key(),
+                    bettor: self.bettor.key(),
+                    outcome_id: position.outcome_id,
+                    shares: position.shares,
+                    usdc_out: out,
+                    timestamp: Clock::get()?.unix_timestamp,
+                });
+            }
+            emit_odds(self.betting_market.key(), &self.betting_market.amm_curve, &book)?;
+        }
+        MarketStats::record_sell(&self.market_stats, usdc_out)?;
+        require!(usdc_out > 0, StreamError::InvalidAmount);
+        require!(usdc_out >= min_usdc_out, StreamError::SlippageExceeded);
+        FeatureGates::require_not_queued(&self.feature_gates, usdc_out)?;
+
+        msg!("Cashing out {} outcomes for {} USDC", open_outcomes, usdc_out);
+
+        for position in held {
+            position.close(self.bettor.to_account_info())?;
+        }
+
+        pay_from_vault(
+            &self.betting_market,
+            &self.market_vault,
+            &self.mint,
+            &self.bettor_token,
+            &self.token_program,
+            remaining_accounts,
+            usdc_out,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Burns `shares` of `outcome_id` back into the book and returns what they pay after the
+/// market fee, which stays in the pool for the bettors who remain
+fn sell_into_book(curve: &AmmCurve, book: &mut MarketBook, fee_bps: u16, outcome_id: u8, shares: u64) -> Result<u64> {
+    let gross = calculate_usdc_for_sale(curve, book, outcome_id, shares)?;
+    let usdc_out = net_of_fee(gross, fee_bps);
+
+    let outcome = &mut book.outcomes[outcome_id as usize];
+    require!(
+        usdc_out <= outcome.total_backing && shares <= outcome.total_shares,
+        StreamError::InsufficientFunds
+    );
+    outcome.total_shares -= shares;
+    outcome.total_backing -= usdc_out;
+    // Mirror of the half of each bet that place_bet adds to liquidity
+    outcome.liquidity_reserve = outcome.liquidity_reserve.saturating_sub(usdc_out / 2);
+    book.total_pool = book
+        .total_pool
+        .checked_sub(usdc_out)
+        .ok_or(StreamError::InsufficientFunds)?;
+    Ok(usdc_out)
+}
+
+/// Transfers `amount` from the market vault to the bettor, signed by the market
+pub(crate) fn pay_from_vault<'info>(
+    market: &Account<'info, BettingMarket>,
+    market_vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    bettor_token: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    let market_seeds = &[MARKET_SEED, market.stream.as_ref(), &[market.bump]];
+    let signer = &[&market_seeds[..]];
+    let cpi_accounts = TransferChecked {
+        from: market_vault.to_account_info(),
+        mint: mint.to_account_info(),
+        to: bettor_token.to_account_info(),
+        authority: market.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+        .with_remaining_accounts(remaining_accounts.to_vec());
+    transfer_checked(cpi_ctx, amount, mint.decimals)
+}
+
+/// Inverse of the purchase curve: selling `shares` returns reserve * shares / (reserve + shares)
+/// on the constant-product curve and the drop in the cost function under LMSR, so a round trip
+/// never returns more than it cost
+fn calculate_usdc_for_sale(curve: &AmmCurve, book: &MarketBook, outcome_id: u8, shares: u64) -> Result<u64> {
+    match curve {
+        AmmCurve::Lmsr { b } => return lmsr_sell_proceeds(&book.share_quantities(), *b, outcome_id as usize, shares),
+        AmmCurve::Parimutuel => return err!(StreamError::SharesNotSellable),
+        AmmCurve::ConstantProduct => {}
+    }
+
+    let outcome = book.outcome(outcome_id);
+    let usdc_out = (outcome.liquidity_reserve as u128)
+        .checked_mul(shares as u128)
+        .ok_or(StreamError::MathOverflow)?
+        .checked_div(
+            (outcome.liquidity_reserve as u128)
+                .checked_add(shares as u128)
+                .ok_or(StreamError::MathOverflow)?,
+        )
+        .ok_or(StreamError::MathOverflow)? as u64;
+    Ok(usdc_out)
+}
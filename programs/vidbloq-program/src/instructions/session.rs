@@ -0,0 +1,355 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    approve_checked, revoke, transfer_checked, ApproveChecked, Mint, Revoke, TokenAccount, TokenInterface,
+    TransferChecked,
+};
+
+use crate::instructions::{
+    amount_received, apply_matching, emit_milestones, enforce_access_gate, notify_vault_state, ACCESS_GATE_SEED,
+    GLOBAL_REGISTRY_SEED,
+};
+use crate::state::{
+    AccessGate, Blocklist, DepositMade, DonationReceipt, DonorAccount, GlobalRegistry, Leaderboard, MatchingPledge,
+    SessionCreated, SessionKey, SessionRevoked, StreamError, StreamState, MAX_DONATION_MESSAGE_LEN,
+    MAX_SESSION_DURATION,
+};
+
+pub const SESSION_SEED: &[u8] = b"session_key";
+
+/// Owner approves `session_key` to spend from their token account for a while. The session PDA
+/// becomes the account's SPL delegate, replacing any delegate approved before.
+#[derive(Accounts)]
+#[instruction(session_key: Pubkey)]
+pub struct CreateSession<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = SessionKey::INIT_SPACE,
+        seeds = [SESSION_SEED, owner.key().as_ref(), session_key.as_ref()],
+        bump
+    )]
+    pub session: Account<'info, SessionKey>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = owner,
+    )]
+    pub owner_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Owner ends a session early, withdrawing its delegation and reclaiming the rent
+#[derive(Accounts)]
+pub struct RevokeSession<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        close = owner,
+        seeds = [SESSION_SEED, owner.key().as_ref(), session.session_key.as_ref()],
+        bump = session.bump,
+    )]
+    pub session: Account<'info, SessionKey>,
+
+    #[account(mut, address = session.owner_token)]
+    pub owner_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Deposit signed by a session key; the owner is credited as the donor
+#[derive(Accounts)]
+pub struct DepositWithSession<'info> {
+    /// Session key, which also pays the fees and rent
+    #[account(mut)]
+    pub session_signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SESSION_SEED, session.owner.as_ref(), session_signer.key().as_ref()],
+        bump = session.bump,
+    )]
+    pub session: Account<'info, SessionKey>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = session_signer,
+        space = DonorAccount::INIT_SPACE,
+        seeds = [b"donor", stream.key().as_ref(), session.owner.as_ref()],
+        bump
+    )]
+    pub donor_account: Account<'info, DonorAccount>,
+
+    #[account(
+        init,
+        payer = session_signer,
+        space = DonationReceipt::INIT_SPACE,
+        seeds = [b"receipt", stream.key().as_ref(), stream.receipt_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, DonationReceipt>,
+
+    #[account(
+        mut,
+        address = session.owner_token,
+        constraint = owner_token.mint == stream.mint
+    )]
+    pub owner_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Required while the stream has an active matching pledge
+    #[account(
+        mut,
+        seeds = [b"matching_pledge", stream.key().as_ref()],
+        bump = matching_pledge.bump
+    )]
+    pub matching_pledge: Option<Account<'info, MatchingPledge>>,
+
+    #[account(
+        mut,
+        seeds = [b"pledge_vault", stream.key().as_ref()],
+        bump
+    )]
+    pub pledge_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Required while the stream maintains a leaderboard
+    #[account(
+        mut,
+        seeds = [b"leaderboard", stream.key().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+
+    /// CHECK: Blocklist page for the owner's bucket, required while the stream has blocked wallets
+    #[account(
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&session.owner)]],
+        bump
+    )]
+    pub blocklist: Option<UncheckedAccount<'info>>,
+
+    /// Required while the stream is access gated
+    #[account(
+        seeds = [ACCESS_GATE_SEED, stream.key().as_ref()],
+        bump = access_gate.bump
+    )]
+    pub access_gate: Option<Account<'info, AccessGate>>,
+
+    /// Owner's token account for the gate mint, required while the stream is access gated
+    pub gate_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl<'info> CreateSession<'info> {
+    pub fn create_session(
+        &mut self,
+        session_key: Pubkey,
+        spend_limit: u64,
+        duration: i64,
+        bumps: &CreateSessionBumps,
+    ) -> Result<()> {
+        require!(spend_limit > 0, StreamError::InvalidAmount);
+        require!(duration > 0 && duration <= MAX_SESSION_DURATION, StreamError::InvalidDuration);
+        require_keys_neq!(session_key, self.owner.key(), StreamError::Unauthorized);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.session.set_inner(SessionKey {
+            owner: self.owner.key(),
+            session_key,
+            owner_token: self.owner_token.key(),
+            spend_limit,
+            spent: 0,
+            expires_at: now + duration,
+            created_at: now,
+            bump: bumps.session,
+        });
+
+        let cpi_accounts = ApproveChecked {
+            to: self.owner_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            delegate: self.session.to_account_info(),
+            authority: self.owner.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts);
+        approve_checked(cpi_ctx, spend_limit, self.mint.decimals)?;
+
+        emit!(SessionCreated {
+            owner: self.owner.key(),
+            session_key,
+            spend_limit,
+            expires_at: now + duration,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> RevokeSession<'info> {
+    pub fn revoke_session(&mut self) -> Result<()> {
+        // Leave a delegate approved for something else in place
+        if self.owner_token.delegate == Some(self.session.key()).into() {
+            let cpi_accounts = Revoke {
+                source: self.owner_token.to_account_info(),
+                authority: self.owner.to_account_info(),
+            };
+            revoke(CpiContext::new(self.token_program.to_account_info(), cpi_accounts))?;
+        }
+
+        emit!(SessionRevoked {
+            owner: self.owner.key(),
+            session_key: self.session.session_key,
+            spent: self.session.spent,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> DepositWithSession<'info> {
+    pub fn deposit_with_session(
+        &mut self,
+        amount: u64,
+        message: Option<String>,
+        bumps: &DepositWithSessionBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        if let Some(text) = &message {
+            require!(text.len() <= MAX_DONATION_MESSAGE_LEN, StreamError::MessageTooLong);
+        }
+
+        let owner = self.session.owner;
+        let now = Clock::get()?.unix_timestamp;
+        self.session.spend(amount, now)?;
+        self.stream.require_deposits_open()?;
+        Blocklist::require_not_blocked(
+            self.stream.blocked_count,
+            self.blocklist.as_ref().map(|b| b.as_ref()),
+            &owner,
+        )?;
+        enforce_access_gate(
+            &self.stream,
+            self.access_gate.as_ref(),
+            self.gate_token_account.as_ref(),
+            &owner,
+        )?;
+
+        let vault_before = self.stream_ata.amount;
+        transfer_from_session(
+            &self.session,
+            &self.owner_token,
+            &self.mint,
+            self.stream_ata.to_account_info(),
+            &self.token_program,
+            amount,
+            remaining_accounts,
+        )?;
+        let amount = amount_received(&mut self.stream_ata, vault_before)?;
+
+        self.donor_account.record_deposit(self.stream.key(), owner, amount, now, bumps.donor_account)?;
+        self.stream.total_deposited = self.stream.total_deposited.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_deposit(&self.global_registry, amount)?;
+
+        self.receipt.set_inner(DonationReceipt {
+            stream: self.stream.key(),
+            donor: owner,
+            commitment: None,
+            index: self.stream.receipt_count,
+            amount,
+            message,
+            timestamp: now,
+            bump: bumps.receipt,
+        });
+        self.stream.receipt_count = self.stream.receipt_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+        let (matched_amount, matching_sponsor) = apply_matching(
+            &mut self.stream,
+            &mut self.donor_account,
+            self.matching_pledge.as_mut(),
+            self.pledge_vault.as_ref(),
+            &self.mint,
+            &mut self.stream_ata,
+            &self.token_program,
+            &self.global_registry,
+            amount,
+            remaining_accounts,
+        )?;
+
+        if self.stream.leaderboard_active {
+            let leaderboard = self.leaderboard.as_mut().ok_or(StreamError::MissingAccounts)?;
+            leaderboard.record(owner, self.donor_account.amount);
+        }
+
+        emit!(DepositMade {
+            stream: self.stream.key(),
+            donor: owner,
+            amount,
+            matched_amount,
+            matching_sponsor,
+            timestamp: now
+        });
+
+        emit_milestones(&mut self.stream)?;
+        notify_vault_state(&self.stream, self.stream_ata.amount, now)?;
+        Ok(())
+    }
+}
+
+/// Moves `amount` from the owner's token account to `to`, signed by the session PDA as delegate
+pub(crate) fn transfer_from_session<'info>(
+    session: &Account<'info, SessionKey>,
+    owner_token: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let seeds = &[
+        SESSION_SEED,
+        session.owner.as_ref(),
+        session.session_key.as_ref(),
+        &[session.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    let cpi_accounts = TransferChecked {
+        from: owner_token.to_account_info(),
+        mint: mint.to_account_info(),
+        to,
+        authority: session.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+        .with_remaining_accounts(remaining_accounts.to_vec());
+    transfer_checked(cpi_ctx, amount, mint.decimals)
+}
@@ -0,0 +1,424 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer as system_transfer, Transfer as SystemTransfer};
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::instructions::{
+    emit_milestones, enforce_access_gate, enforce_distribution_policy, index_stream, new_stream_state, record_burn_rate, record_refund,
+    ACCESS_GATE_SEED, DISTRIBUTION_POLICY_SEED, PAYOUT_RECIPIENT_SEED, GLOBAL_REGISTRY_SEED, HOST_STREAM_INDEX_SEED, PLATFORM_CONFIG_SEED,
+};
+use crate::state::{
+    AccessGate, Blocklist, DepositMade, HostProfile, DonationReceipt, DonorAccount, FundsDistributed, Leaderboard, PayoutRecipient,
+    DistributionPolicy, StreamError, StreamState, StreamType, MAX_DONATION_MESSAGE_LEN, NATIVE_SOL_MINT, GlobalRegistry,
+    PlatformConfig,
+};
+
+pub const SOL_VAULT_SEED: &[u8] = b"sol_vault";
+
+/// Native SOL streams keep lamports in a system-owned PDA vault instead of a token ATA.
+/// The vault is seeded with its rent-exempt minimum, which stays outside stream accounting.
+#[derive(Accounts)]
+pub struct InitializeSolStream <'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        space = HostProfile::INIT_SPACE,
+        seeds = [b"host_profile", host.key().as_ref()],
+        bump
+    )]
+    pub host_profile: Account<'info, HostProfile>,
+
+    /// Seeded by the host's stream count, so names can repeat and change
+    #[account(
+        init,
+        payer=host,
+        space=StreamState::INIT_SPACE,
+        seeds=[b"stream", host.key().as_ref(), host_profile.streams_created.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    /// CHECK: Host's stream index PDA, created with the host's first indexed stream
+    #[account(mut, seeds = [HOST_STREAM_INDEX_SEED, host.key().as_ref()], bump)]
+    pub host_stream_index: UncheckedAccount<'info>,
+
+    /// CHECK: Growth partner credited with referring the host; only recorded on the host's first stream
+    pub referrer: Option<UncheckedAccount<'info>>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, stream.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl <'info> InitializeSolStream <'info> {
+    pub fn initialize_sol_stream(&mut self, name: String, stream_type: StreamType, end_time: Option<i64>, refund_deadline: Option<i64>, bumps: &InitializeSolStreamBumps) -> Result<()> {
+        self.stream.set_inner(new_stream_state(
+            self.host.key(),
+            self.host_profile.streams_created,
+            name,
+            bumps.stream,
+            NATIVE_SOL_MINT,
+            stream_type,
+            end_time,
+            refund_deadline,
+        )?);
+        self.host_profile.register_stream(
+            self.host.key(),
+            self.referrer.as_ref().map(|r| r.key()),
+            bumps.host_profile,
+        )?;
+        index_stream(
+            &self.host_stream_index,
+            &self.host,
+            self.stream.key(),
+            bumps.host_stream_index,
+            &self.system_program,
+        )?;
+        GlobalRegistry::record_stream(&self.global_registry)?;
+
+        let rent_exempt = Rent::get()?.minimum_balance(0);
+        let shortfall = rent_exempt.saturating_sub(self.sol_vault.lamports());
+        if shortfall > 0 {
+            let cpi_accounts = SystemTransfer {
+                from: self.host.to_account_info(),
+                to: self.sol_vault.to_account_info(),
+            };
+            system_transfer(CpiContext::new(self.system_program.to_account_info(), cpi_accounts), shortfall)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct DepositSol <'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds=[b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump=stream.bump,
+        constraint = stream.is_native_sol() @ StreamError::NotNativeSolStream
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, stream.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = donor,
+        space = DonorAccount::INIT_SPACE,
+        seeds = [b"donor", stream.key().as_ref(), donor.key().as_ref()],
+        bump
+    )]
+    pub donor_account: Account<'info, DonorAccount>,
+
+    #[account(
+        init,
+        payer = donor,
+        space = DonationReceipt::INIT_SPACE,
+        seeds = [b"receipt", stream.key().as_ref(), stream.receipt_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub receipt: Account<'info, DonationReceipt>,
+
+    /// Required while the stream maintains a leaderboard
+    #[account(
+        mut,
+        seeds = [b"leaderboard", stream.key().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+
+    /// CHECK: Blocklist page for the donor's bucket, required while the stream has blocked wallets
+    #[account(
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&donor.key())]],
+        bump
+    )]
+    pub blocklist: Option<UncheckedAccount<'info>>,
+
+    /// Required while the stream is access gated
+    #[account(
+        seeds = [ACCESS_GATE_SEED, stream.key().as_ref()],
+        bump = access_gate.bump
+    )]
+    pub access_gate: Option<Account<'info, AccessGate>>,
+
+    /// Donor's token account for the gate mint, required while the stream is access gated
+    pub gate_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl <'info> DepositSol <'info> {
+    pub fn deposit_sol(&mut self, amount: u64, message: Option<String>, bumps: &DepositSolBumps) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        if let Some(text) = &message {
+            require!(text.len() <= MAX_DONATION_MESSAGE_LEN, StreamError::MessageTooLong);
+        }
+
+        self.stream.require_deposits_open()?;
+        Blocklist::require_not_blocked(
+            self.stream.blocked_count,
+            self.blocklist.as_ref().map(|b| b.as_ref()),
+            &self.donor.key(),
+        )?;
+        enforce_access_gate(
+            &self.stream,
+            self.access_gate.as_ref(),
+            self.gate_token_account.as_ref(),
+            &self.donor.key(),
+        )?;
+
+        let cpi_accounts = SystemTransfer {
+            from: self.donor.to_account_info(),
+            to: self.sol_vault.to_account_info(),
+        };
+        system_transfer(CpiContext::new(self.system_program.to_account_info(), cpi_accounts), amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        self.donor_account.record_deposit(
+            self.stream.key(),
+            self.donor.key(),
+            amount,
+            now,
+            bumps.donor_account,
+        )?;
+        self.stream.total_deposited = self.stream.total_deposited.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_deposit(&self.global_registry, amount)?;
+
+        self.receipt.set_inner(DonationReceipt {
+            stream: self.stream.key(),
+            donor: self.donor.key(),
+            commitment: None,
+            index: self.stream.receipt_count,
+            amount,
+            message,
+            timestamp: now,
+            bump: bumps.receipt,
+        });
+        self.stream.receipt_count = self.stream.receipt_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+        if self.stream.leaderboard_active {
+            let leaderboard = self.leaderboard.as_mut().ok_or(StreamError::MissingAccounts)?;
+            leaderboard.record(self.donor.key(), self.donor_account.amount);
+        }
+
+        emit!(DepositMade {
+            stream: self.stream.key(),
+            donor: self.donor.key(),
+            amount,
+            matched_amount: 0,
+            matching_sponsor: None,
+            timestamp: now
+        });
+
+        emit_milestones(&mut self.stream)?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct DistributeSol <'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    /// CHECK: This is the recipient public key
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        has_one = host,
+        seeds=[b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump=stream.bump,
+        constraint = stream.is_native_sol() @ StreamError::NotNativeSolStream
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, stream.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    /// Allowlist entry for the recipient, required while the stream is in strict payout mode
+    #[account(
+        seeds = [PAYOUT_RECIPIENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump = payout_recipient.bump
+    )]
+    pub payout_recipient: Option<Account<'info, PayoutRecipient>>,
+
+    /// Spending limits, required once the host has set a distribution policy
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_POLICY_SEED, stream.key().as_ref()],
+        bump = distribution_policy.bump
+    )]
+    pub distribution_policy: Option<Account<'info, DistributionPolicy>>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl <'info> DistributeSol <'info> {
+    pub fn distribute_sol(&mut self, amount: u64, bumps: &DistributeSolBumps) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        self.stream.require_distribution_allowed()?;
+        self.stream.require_within_lifetime(&self.platform_config)?;
+        self.stream.require_recipient_allowed(self.payout_recipient.is_some())?;
+
+        let available_balance = self.stream.available_balance()?;
+        require!(available_balance >= amount, StreamError::InsufficientFunds);
+        enforce_distribution_policy(&self.stream, self.distribution_policy.as_mut(), amount)?;
+
+        transfer_from_vault(
+            &self.stream,
+            &self.sol_vault,
+            bumps.sol_vault,
+            &self.recipient,
+            &self.system_program,
+            amount,
+        )?;
+
+        self.stream.total_distributed = self.stream.total_distributed.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_distribution(&self.global_registry, amount)?;
+
+        emit!(FundsDistributed {
+            stream: self.stream.key(),
+            recipient: self.recipient.key(),
+            amount,
+            burned: 0,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+
+        record_burn_rate(&mut self.stream, amount)?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RefundSol <'info> {
+    /// CHECK: This is the donor public key
+    #[account(mut)]
+    pub donor: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = (initiator.key() == stream.host || initiator.key() == donor.key())
+    )]
+    pub initiator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds=[b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump=stream.bump,
+        constraint = stream.is_native_sol() @ StreamError::NotNativeSolStream
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [b"donor", stream.key().as_ref(), donor.key().as_ref()],
+        bump = donor_account.bump,
+        constraint = donor_account.donor == donor.key(),
+        constraint = donor_account.stream == stream.key()
+    )]
+    pub donor_account: Account<'info, DonorAccount>,
+
+    #[account(
+        mut,
+        seeds = [SOL_VAULT_SEED, stream.key().as_ref()],
+        bump
+    )]
+    pub sol_vault: SystemAccount<'info>,
+
+    /// Required while the stream maintains a leaderboard
+    #[account(
+        mut,
+        seeds = [b"leaderboard", stream.key().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl <'info> RefundSol <'info> {
+    pub fn refund_sol(&mut self, amount: u64, bumps: &RefundSolBumps) -> Result<()> {
+        // Past the platform's maximum lifetime donors may always exit, as with token refunds
+        let config = PlatformConfig::load(&self.platform_config)?;
+        if !PlatformConfig::stream_expired(config.as_ref(), self.stream.created_at, Clock::get()?.unix_timestamp) {
+            self.stream.require_refunds_open()?;
+        }
+        let payout = record_refund(&mut self.stream, &mut self.donor_account, self.leaderboard.as_mut(), amount)?;
+
+        transfer_from_vault(
+            &self.stream,
+            &self.sol_vault,
+            bumps.sol_vault,
+            &self.donor,
+            &self.system_program,
+            payout,
+        )
+    }
+}
+
+/// Pays `amount` lamports out of the stream's vault, signing with the vault PDA
+fn transfer_from_vault<'info>(
+    stream: &Account<'info, StreamState>,
+    sol_vault: &SystemAccount<'info>,
+    vault_bump: u8,
+    to: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    let stream_key = stream.key();
+    let vault_seeds = &[
+        SOL_VAULT_SEED,
+        stream_key.as_ref(),
+        &[vault_bump],
+    ];
+    let signer = &[&vault_seeds[..]];
+
+    let cpi_accounts = SystemTransfer {
+        from: sol_vault.to_account_info(),
+        to: to.clone(),
+    };
+    system_transfer(
+        CpiContext::new_with_signer(system_program.to_account_info(), cpi_accounts, signer),
+        amount,
+    )
+}
@@ -0,0 +1,240 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::instructions::{
+    burn_stream_share, enforce_distribution_policy, record_burn_rate, record_clawbackable_payout, PlatformFeeRoute,
+    CLAWBACK_AGREEMENT_SEED, DISTRIBUTION_POLICY_SEED, PAYOUT_RECIPIENT_SEED, PLATFORM_CONFIG_SEED, GLOBAL_REGISTRY_SEED
+};
+use crate::state::{
+    StreamState, StreamError, SplitConfig, SplitShare, DistributionPolicy, HostProfile, PayoutRecipient,
+    ReferralRewards, SplitConfigSet, SplitDistributed, GlobalRegistry
+};
+
+pub const SPLIT_CONFIG_SEED: &[u8] = b"split_config";
+
+#[derive(Accounts)]
+pub struct SetSplitConfig<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        space = SplitConfig::INIT_SPACE,
+        seeds = [SPLIT_CONFIG_SEED, stream.key().as_ref()],
+        bump
+    )]
+    pub split_config: Account<'info, SplitConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Pays `amount` out of the stream vault across the split's recipients. Remaining accounts are
+/// each recipient's token account in split order, then each recipient's clawback agreement PDA,
+/// writable and uninitialized unless they consented, then, while the stream is in strict payout
+/// mode, each recipient's `PayoutRecipient` entry, both in the same order, then any transfer
+/// hook accounts.
+#[derive(Accounts)]
+pub struct DistributeSplit<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        has_one = host @ StreamError::Unauthorized,
+        has_one = mint
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        seeds = [SPLIT_CONFIG_SEED, stream.key().as_ref()],
+        bump = split_config.bump
+    )]
+    pub split_config: Account<'info, SplitConfig>,
+
+    /// Writable so deflationary streams can burn supply
+    #[account(mut)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Spending limits, required once the host has set a distribution policy
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_POLICY_SEED, stream.key().as_ref()],
+        bump = distribution_policy.bump
+    )]
+    pub distribution_policy: Option<Account<'info, DistributionPolicy>>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required while a platform fee is configured
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"host_profile", stream.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Option<Account<'info, HostProfile>>,
+
+    /// Credited with the referrer's share of the fee while the host's referral runs
+    #[account(mut)]
+    pub referral_rewards: Option<Account<'info, ReferralRewards>>,
+
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl<'info> SetSplitConfig<'info> {
+    pub fn set_split_config(&mut self, shares: Vec<SplitShare>, bumps: &SetSplitConfigBumps) -> Result<()> {
+        SplitConfig::validate(&shares)?;
+        let now = Clock::get()?.unix_timestamp;
+
+        self.split_config.set_inner(SplitConfig {
+            stream: self.stream.key(),
+            shares: shares.clone(),
+            updated_at: now,
+            bump: bumps.split_config,
+        });
+
+        emit!(SplitConfigSet {
+            stream: self.stream.key(),
+            shares,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+impl<'info> DistributeSplit<'info> {
+    pub fn distribute_split(&mut self, amount: u64, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        self.stream.require_distribution_allowed()?;
+        self.stream.require_within_lifetime(&self.platform_config)?;
+
+        let shares = &self.split_config.shares;
+        let strict = self.stream.payout_allowlist_strict;
+        let needed = if strict { shares.len() * 3 } else { shares.len() * 2 };
+        require!(remaining_accounts.len() >= needed, StreamError::InvalidRemainingAccounts);
+        let (recipient_accounts, hook_accounts) = remaining_accounts.split_at(needed);
+        let (recipient_atas, recipient_accounts) = recipient_accounts.split_at(shares.len());
+        let (agreements, allowlist) = recipient_accounts.split_at(shares.len());
+
+        let mut recipient_tokens = Vec::with_capacity(shares.len());
+        for (i, (share, info)) in shares.iter().zip(recipient_atas).enumerate() {
+            let token = InterfaceAccount::<TokenAccount>::try_from(info)?;
+            require_keys_eq!(token.owner, share.recipient, StreamError::Unauthorized);
+            require_keys_eq!(token.mint, self.stream.mint, StreamError::InvalidMint);
+            let (agreement, _) = Pubkey::find_program_address(
+                &[CLAWBACK_AGREEMENT_SEED, self.stream.key().as_ref(), share.recipient.as_ref()],
+                &crate::ID,
+            );
+            require_keys_eq!(agreements[i].key(), agreement, StreamError::InvalidRemainingAccounts);
+            if strict {
+                let entry = Account::<PayoutRecipient>::try_from(&allowlist[i])?;
+                let (expected, _) = Pubkey::find_program_address(
+                    &[PAYOUT_RECIPIENT_SEED, self.stream.key().as_ref(), share.recipient.as_ref()],
+                    &crate::ID,
+                );
+                require_keys_eq!(entry.key(), expected, StreamError::RecipientNotAllowed);
+            }
+            recipient_tokens.push(token);
+        }
+
+        let available_balance = self.stream.available_balance()?;
+        require!(available_balance >= amount, StreamError::InsufficientFunds);
+        enforce_distribution_policy(&self.stream, self.distribution_policy.as_mut(), amount)?;
+
+        let stream_seeds = self.stream.signer_seeds();
+        let stream_seeds: Vec<&[u8]> = stream_seeds.iter().map(Vec::as_slice).collect();
+        let signer = &[&stream_seeds[..]];
+
+        let fee = PlatformFeeRoute {
+            platform_config: &self.platform_config,
+            treasury_ata: self.treasury_ata.as_ref(),
+            host_profile: self.host_profile.as_ref(),
+            referral_rewards: self.referral_rewards.as_mut(),
+            referral_vault: self.referral_vault.as_ref(),
+        }.collect(
+            self.stream.host,
+            &self.stream_ata,
+            &self.mint,
+            self.stream.to_account_info(),
+            signer,
+            &self.token_program,
+            hook_accounts,
+            amount,
+        )?;
+        let burned = burn_stream_share(
+            &self.stream,
+            &self.stream_ata,
+            &self.mint,
+            self.stream.to_account_info(),
+            signer,
+            &self.token_program,
+            amount - fee,
+        )?;
+
+        // The fee and the burn come off the top; recipients split what is left
+        let amounts = self.split_config.amounts(amount - fee - burned)?;
+        for (token, share_amount) in recipient_tokens.iter().zip(&amounts) {
+            if *share_amount == 0 {
+                continue;
+            }
+            let cpi_accounts = TransferChecked {
+                from: self.stream_ata.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: token.to_account_info(),
+                authority: self.stream.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+                .with_remaining_accounts(hook_accounts.to_vec());
+            transfer_checked(cpi_ctx, *share_amount, self.mint.decimals)?;
+        }
+
+        self.stream.total_distributed = self.stream.total_distributed.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_distribution(&self.global_registry, amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        for (((share, token), agreement), share_amount) in shares.iter().zip(&recipient_tokens).zip(agreements).zip(&amounts) {
+            if *share_amount > 0 {
+                record_clawbackable_payout(agreement, self.stream.key(), share.recipient, &token.key(), *share_amount, now)?;
+            }
+        }
+
+        emit!(SplitDistributed {
+            stream: self.stream.key(),
+            amount,
+            platform_fee: fee,
+            burned,
+            recipients: self.split_config.shares.iter().map(|share| share.recipient).collect(),
+            amounts,
+            timestamp: now
+        });
+
+        record_burn_rate(&mut self.stream, amount)?;
+        Ok(())
+    }
+}
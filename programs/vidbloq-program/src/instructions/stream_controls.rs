@@ -1,6 +1,8 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
 
-use crate::state::{StreamState, StreamStatus, StreamError};
+use crate::instructions::PLATFORM_CONFIG_SEED;
+use crate::state::{StreamState, StreamStatus, StreamError, StreamUpdated, StreamRenamed, StreamArchived, FundraisingGoalSet, DustRefundThresholdSet, BurnBpsSet, PlatformConfig, HostProfile, MAX_MILESTONES, MAX_MILESTONE_PERCENTAGE, MAX_BURN_BPS};
 
 #[derive(Accounts)]
 pub struct StartStream<'info> {
@@ -12,8 +14,8 @@ pub struct StartStream<'info> {
         has_one = host,
         seeds = [
             b"stream",
-            stream.stream_name.as_bytes(),
-            stream.host.as_ref()
+            stream.first_seed().as_ref(),
+            stream.second_seed().as_ref()
         ],
         bump = stream.bump
     )]
@@ -46,12 +48,19 @@ pub struct CompleteStream<'info> {
         has_one = host,
         seeds = [
             b"stream",
-            stream.stream_name.as_bytes(),
-            stream.host.as_ref()
+            stream.first_seed().as_ref(),
+            stream.second_seed().as_ref()
         ],
         bump = stream.bump
     )]
     pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [b"host_profile", host.key().as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Account<'info, HostProfile>,
 }
 
 impl<'info> CompleteStream<'info> {
@@ -67,6 +76,7 @@ impl<'info> CompleteStream<'info> {
         
         self.stream.status = StreamStatus::Ended;
         self.stream.end_time = Some(Clock::get()?.unix_timestamp);
+        self.host_profile.record_completion(self.stream.total_deposited)?;
         Ok(())
     }
 }
@@ -79,7 +89,7 @@ pub struct UpdateStream<'info> {
     #[account(
         mut,
         has_one = host,
-        seeds = [b"stream", stream.stream_name.as_bytes(), host.key().as_ref()],
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
         bump = stream.bump
     )]
     pub stream: Account<'info, StreamState>,
@@ -91,12 +101,219 @@ impl<'info> UpdateStream<'info> {
         new_end_time: Option<i64>,
         new_status: Option<StreamStatus>
     ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let old_status = self.stream.status;
+        let old_end_time = self.stream.end_time;
+
         if let Some(end_time) = new_end_time {
+            require!(old_status == StreamStatus::Active, StreamError::StreamNotActive);
+            require!(end_time >= now, StreamError::InvalidTime);
             self.stream.end_time = Some(end_time);
         }
         if let Some(status) = new_status {
+            require!(old_status.can_transition_to(status), StreamError::InvalidStatusTransition);
             self.stream.status = status;
         }
+
+        emit!(StreamUpdated {
+            stream: self.stream.key(),
+            old_status,
+            new_status: self.stream.status,
+            old_end_time,
+            new_end_time: self.stream.end_time,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+/// The name is display data only; the stream keeps its address
+#[derive(Accounts)]
+pub struct RenameStream<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = host,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+}
+
+impl<'info> RenameStream<'info> {
+    pub fn rename_stream(&mut self, new_name: String) -> Result<()> {
+        StreamState::require_valid_name(&new_name)?;
+        let old_name = std::mem::replace(&mut self.stream.stream_name, new_name.clone());
+
+        emit!(StreamRenamed {
+            stream: self.stream.key(),
+            old_name,
+            new_name,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetFundraisingGoal<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = host,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+}
+
+impl<'info> SetFundraisingGoal<'info> {
+    pub fn set_fundraising_goal(
+        &mut self,
+        goal_amount: u64,
+        milestone_percentages: Vec<u16>
+    ) -> Result<()> {
+        require!(
+            self.stream.status == StreamStatus::Active,
+            StreamError::StreamNotActive
+        );
+        require!(goal_amount > 0, StreamError::InvalidAmount);
+        require!(
+            milestone_percentages.len() <= MAX_MILESTONES,
+            StreamError::InvalidMilestones
+        );
+        require!(
+            milestone_percentages.iter().all(|p| *p > 0 && *p <= MAX_MILESTONE_PERCENTAGE)
+                && milestone_percentages.windows(2).all(|w| w[0] < w[1]),
+            StreamError::InvalidMilestones
+        );
+
+        self.stream.goal_amount = goal_amount;
+        self.stream.milestone_percentages = milestone_percentages;
+
+        // Milestones already covered by past deposits are not celebrated again
+        let mut reached = 0u8;
+        for percentage in self.stream.milestone_percentages.iter() {
+            if !self.stream.milestone_met(*percentage)? {
+                break;
+            }
+            reached += 1;
+        }
+        self.stream.milestones_reached = reached;
+
+        emit!(FundraisingGoalSet {
+            stream: self.stream.key(),
+            goal_amount,
+            milestone_percentages: self.stream.milestone_percentages.clone(),
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetDustRefundThreshold<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = host,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+}
+
+impl<'info> SetDustRefundThreshold<'info> {
+    /// Zero turns off pushed dust refunds for the stream
+    pub fn set_dust_refund_threshold(&mut self, threshold: u64) -> Result<()> {
+        self.stream.dust_refund_threshold = threshold;
+
+        emit!(DustRefundThresholdSet {
+            stream: self.stream.key(),
+            threshold,
+            timestamp: Clock::get()?.unix_timestamp
+        });
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Turns on deflationary mode, burning `burn_bps` of every payout; zero turns it off
+#[derive(Accounts)]
+pub struct SetBurnBps<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = host,
+        has_one = mint,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+}
+
+impl<'info> SetBurnBps<'info> {
+    pub fn set_burn_bps(&mut self, burn_bps: u16) -> Result<()> {
+        // A freeze authority could lock the vault and strand the burn, so only fixed-control mints qualify
+        require!(
+            burn_bps == 0 || (burn_bps <= MAX_BURN_BPS && self.mint.freeze_authority.is_none()),
+            StreamError::BurnNotPermitted
+        );
+        self.stream.burn_bps = burn_bps;
+
+        emit!(BurnBpsSet {
+            stream: self.stream.key(),
+            burn_bps,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+/// Permissionless cancellation of a stream that has outlived the platform's maximum lifetime,
+/// so donors are never left waiting on an absent host
+#[derive(Accounts)]
+pub struct ArchiveStream<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+}
+
+impl<'info> ArchiveStream<'info> {
+    /// Ended streams are archived too: whatever the host left in the vault goes back to donors
+    pub fn archive_stream(&mut self) -> Result<()> {
+        require!(self.stream.status != StreamStatus::Cancelled, StreamError::InvalidStatusTransition);
+        let config = PlatformConfig::load(&self.platform_config)?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            PlatformConfig::stream_expired(config.as_ref(), self.stream.created_at, now),
+            StreamError::StreamLifetimeNotReached
+        );
+
+        let previous_status = self.stream.status;
+        self.stream.status = StreamStatus::Cancelled;
+
+        emit!(StreamArchived {
+            stream: self.stream.key(),
+            previous_status,
+            archived_by: self.caller.key(),
+            timestamp: now
+        });
+        Ok(())
+    }
+}
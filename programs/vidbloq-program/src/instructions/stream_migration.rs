@@ -0,0 +1,128 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer as system_transfer, Transfer as SystemTransfer};
+use anchor_lang::Discriminator;
+
+use crate::state::{
+    DonorAccount, DonorAccountMigrated, StreamError, StreamMigrated, StreamSeedKind, StreamState, STREAM_VERSION,
+};
+
+/// Upgrades a stream written by an older program to the current layout in place. Anyone may
+/// migrate a stream; the payer covers the rent of any space the new layout adds.
+#[derive(Accounts)]
+pub struct MigrateStream<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Stream in an older layout; owner, discriminator and address are checked on read
+    #[account(mut, owner = crate::ID)]
+    pub stream: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Grows a donor record written before `DonorAccount` gained its appended fields to the
+/// current layout. Anyone may migrate a record; the payer covers the added rent.
+#[derive(Accounts)]
+pub struct MigrateDonorAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Donor record in an older layout; owner, discriminator and address are checked on read
+    #[account(mut, owner = crate::ID)]
+    pub donor_account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateStream<'info> {
+    pub fn migrate_stream(&mut self) -> Result<()> {
+        let info = self.stream.to_account_info();
+        require!(has_discriminator(&info, StreamState::DISCRIMINATOR)?, StreamError::InvalidStreamAccount);
+        grow_account(&info, &self.payer, &self.system_program, StreamState::INIT_SPACE)?;
+
+        let mut stream = StreamState::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        let from_version = stream.version;
+        require!(from_version < STREAM_VERSION, StreamError::StreamAlreadyMigrated);
+        // Version 0 covers every layout written before versioning, back to the original
+        // `StreamState`. Each field appended since reads as zero after the resize, which is its
+        // empty default: no goal, milestones, refund deadline, policy, gate or extra mints.
+        // Version 2 records the seed kind. Streams still at the `[b"stream", name, host]` address
+        // they were created at before per-host nonces keep that address under their current name,
+        // which cannot have changed since renames need the nonce seeds. Versions 3 and 4 add
+        // raffle ticket revenue and refund write-offs, which start at zero like the fields before.
+        if from_version < 2 && stream.address().ok() != Some(info.key()) {
+            stream.seed_kind = StreamSeedKind::Name { seed_name: stream.stream_name.clone() };
+        }
+        require_keys_eq!(stream.address()?, info.key(), StreamError::InvalidStreamAccount);
+
+        stream.version = STREAM_VERSION;
+        stream.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+        emit!(StreamMigrated {
+            stream: info.key(),
+            from_version,
+            to_version: STREAM_VERSION,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> MigrateDonorAccount<'info> {
+    /// Current records are always allocated at `INIT_SPACE`, so a shorter account is an old
+    /// layout. The deposit count and time, extra mint balances and matched deposits were never
+    /// recorded for it and start at zero.
+    pub fn migrate_donor_account(&mut self) -> Result<()> {
+        let info = self.donor_account.to_account_info();
+        require!(has_discriminator(&info, DonorAccount::DISCRIMINATOR)?, StreamError::InvalidDonorAccount);
+        require!(info.data_len() < DonorAccount::INIT_SPACE, StreamError::DonorAccountAlreadyMigrated);
+        grow_account(&info, &self.payer, &self.system_program, DonorAccount::INIT_SPACE)?;
+
+        let donor_account = DonorAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        let expected = Pubkey::create_program_address(
+            &[
+                b"donor",
+                donor_account.stream.as_ref(),
+                donor_account.donor.as_ref(),
+                &[donor_account.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| StreamError::InvalidDonorAccount)?;
+        require_keys_eq!(expected, info.key(), StreamError::InvalidDonorAccount);
+
+        emit!(DonorAccountMigrated {
+            donor_account: info.key(),
+            stream: donor_account.stream,
+            donor: donor_account.donor,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+fn has_discriminator(info: &AccountInfo, discriminator: &[u8]) -> Result<bool> {
+    let data = info.try_borrow_data()?;
+    Ok(data.len() > 8 && data[..8] == *discriminator)
+}
+
+/// Tops the account up to rent exemption at `space` and resizes it. The added bytes are zeroed,
+/// so fields appended since the account was written read as defaults.
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    let space = space.max(info.data_len());
+    let shortfall = Rent::get()?.minimum_balance(space).saturating_sub(info.lamports());
+    if shortfall > 0 {
+        let cpi_accounts = SystemTransfer {
+            from: payer.to_account_info(),
+            to: info.clone(),
+        };
+        system_transfer(CpiContext::new(system_program.to_account_info(), cpi_accounts), shortfall)?;
+    }
+    info.resize(space)?;
+    Ok(())
+}
@@ -0,0 +1,400 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
+};
+
+use crate::instructions::{amount_received, enforce_access_gate, enforce_distribution_policy, ACCESS_GATE_SEED, DISTRIBUTION_POLICY_SEED, PAYOUT_RECIPIENT_SEED, GLOBAL_REGISTRY_SEED, PLATFORM_CONFIG_SEED};
+use crate::state::{
+    StreamState, StreamError, StreamMintBalance, DonorAccount, Blocklist, AccessGate, PayoutRecipient, DistributionPolicy,
+    StreamMintAdded, MintDepositMade, MintFundsDistributed, MintRefundProcessed, MAX_EXTRA_MINTS, GlobalRegistry,
+    PlatformConfig
+};
+
+/// Registers an additional mint the stream accepts, with its own vault ATA owned by the stream
+#[derive(Accounts)]
+pub struct AddStreamMint<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        constraint = stream.host == host.key() @ StreamError::Unauthorized
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        associated_token::mint = mint,
+        associated_token::authority = stream,
+        associated_token::token_program = token_program
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>
+}
+
+impl<'info> AddStreamMint<'info> {
+    pub fn add_stream_mint(&mut self) -> Result<()> {
+        let mint = self.mint.key();
+        require!(!self.stream.is_native_sol(), StreamError::InvalidMint);
+        require!(
+            mint != self.stream.mint && !self.stream.extra_mints.iter().any(|balance| balance.mint == mint),
+            StreamError::MintAlreadyAccepted
+        );
+        require!(self.stream.extra_mints.len() < MAX_EXTRA_MINTS, StreamError::TooManyMints);
+
+        self.stream.extra_mints.push(StreamMintBalance {
+            mint,
+            total_deposited: 0,
+            total_distributed: 0,
+        });
+
+        emit!(StreamMintAdded {
+            stream: self.stream.key(),
+            mint,
+            vault: self.stream_ata.key(),
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct DepositStreamMint<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = donor,
+        space = DonorAccount::INIT_SPACE,
+        seeds = [b"donor", stream.key().as_ref(), donor.key().as_ref()],
+        bump
+    )]
+    pub donor_account: Account<'info, DonorAccount>,
+
+    #[account(
+        mut,
+        constraint = donor_ata.owner == donor.key(),
+        constraint = donor_ata.mint == mint.key()
+    )]
+    pub donor_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = stream,
+        associated_token::token_program = token_program
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Blocklist page for the donor's bucket, required while the stream has blocked wallets
+    #[account(
+        seeds = [b"blocklist", stream.key().as_ref(), &[Blocklist::page_for(&donor.key())]],
+        bump
+    )]
+    pub blocklist: Option<UncheckedAccount<'info>>,
+
+    /// Required while the stream is access gated
+    #[account(
+        seeds = [ACCESS_GATE_SEED, stream.key().as_ref()],
+        bump = access_gate.bump
+    )]
+    pub access_gate: Option<Account<'info, AccessGate>>,
+
+    /// Donor's token account for the gate mint, required while the stream is access gated
+    pub gate_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl<'info> DepositStreamMint<'info> {
+    pub fn deposit_stream_mint(&mut self, amount: u64, bumps: &DepositStreamMintBumps, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+
+        self.stream.require_deposits_open()?;
+        self.stream.extra_mint_mut(&self.mint.key())?;
+        Blocklist::require_not_blocked(
+            self.stream.blocked_count,
+            self.blocklist.as_ref().map(|b| b.as_ref()),
+            &self.donor.key(),
+        )?;
+        enforce_access_gate(
+            &self.stream,
+            self.access_gate.as_ref(),
+            self.gate_token_account.as_ref(),
+            &self.donor.key(),
+        )?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.donor_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.stream_ata.to_account_info(),
+            authority: self.donor.to_account_info(),
+        };
+        let vault_before = self.stream_ata.amount;
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let amount = amount_received(&mut self.stream_ata, vault_before)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let stream_key = self.stream.key();
+        let mint = self.mint.key();
+        self.donor_account.record_mint_deposit(stream_key, self.donor.key(), mint, amount, now, bumps.donor_account)?;
+
+        let balance = self.stream.extra_mint_mut(&mint)?;
+        balance.total_deposited = balance.total_deposited.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_deposit(&self.global_registry, amount)?;
+
+        emit!(MintDepositMade {
+            stream: stream_key,
+            donor: self.donor.key(),
+            mint,
+            amount,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct DistributeStreamMint<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    /// CHECK: This is the recipient public key
+    pub recipient: AccountInfo<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump,
+        constraint = stream.host == host.key() @ StreamError::Unauthorized
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = stream,
+        associated_token::token_program = token_program
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program
+    )]
+    pub recipient_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Allowlist entry for the recipient, required while the stream is in strict payout mode
+    #[account(
+        seeds = [PAYOUT_RECIPIENT_SEED, stream.key().as_ref(), recipient.key().as_ref()],
+        bump = payout_recipient.bump
+    )]
+    pub payout_recipient: Option<Account<'info, PayoutRecipient>>,
+
+    /// Spending limits, required once the host has set a distribution policy
+    #[account(
+        mut,
+        seeds = [DISTRIBUTION_POLICY_SEED, stream.key().as_ref()],
+        bump = distribution_policy.bump
+    )]
+    pub distribution_policy: Option<Account<'info, DistributionPolicy>>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Global registry PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [GLOBAL_REGISTRY_SEED], bump)]
+    pub global_registry: UncheckedAccount<'info>,
+}
+
+impl<'info> DistributeStreamMint<'info> {
+    /// Payouts in an extra mint count against the same spending limits as the stream's mint
+    pub fn distribute_stream_mint(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        self.stream.require_distribution_allowed()?;
+        self.stream.require_within_lifetime(&self.platform_config)?;
+        self.stream.require_recipient_allowed(self.payout_recipient.is_some())?;
+        enforce_distribution_policy(&self.stream, self.distribution_policy.as_mut(), amount)?;
+
+        let mint = self.mint.key();
+        let balance = self.stream.extra_mint_mut(&mint)?;
+        require!(balance.available()? >= amount, StreamError::InsufficientFunds);
+        balance.total_distributed = balance.total_distributed.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        GlobalRegistry::record_distribution(&self.global_registry, amount)?;
+
+        transfer_from_stream(
+            &self.stream,
+            &self.mint,
+            &self.stream_ata,
+            &self.recipient_ata,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
+
+        emit!(MintFundsDistributed {
+            stream: self.stream.key(),
+            mint,
+            recipient: self.recipient.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RefundStreamMint<'info> {
+    /// CHECK: This is the donor public key
+    pub donor: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = (initiator.key() == stream.host || initiator.key() == donor.key())
+    )]
+    pub initiator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"donor", stream.key().as_ref(), donor.key().as_ref()],
+        bump = donor_account.bump,
+        constraint = donor_account.donor == donor.key(),
+        constraint = donor_account.stream == stream.key()
+    )]
+    pub donor_account: Account<'info, DonorAccount>,
+
+    #[account(
+        mut,
+        constraint = donor_ata.owner == donor.key(),
+        constraint = donor_ata.mint == mint.key()
+    )]
+    pub donor_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = stream,
+        associated_token::token_program = token_program
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>
+}
+
+impl<'info> RefundStreamMint<'info> {
+    pub fn refund_stream_mint(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        // Past the platform's maximum lifetime donors may always exit, as with token refunds
+        let config = PlatformConfig::load(&self.platform_config)?;
+        if !PlatformConfig::stream_expired(config.as_ref(), self.stream.created_at, Clock::get()?.unix_timestamp) {
+            self.stream.require_refunds_open()?;
+        }
+
+        let mint = self.mint.key();
+        let donor_balance = self.donor_account.extra_balances
+            .iter_mut()
+            .find(|balance| balance.mint == mint)
+            .ok_or(StreamError::MintNotAccepted)?;
+        require!(amount <= donor_balance.amount, StreamError::InsufficientFunds);
+        donor_balance.amount -= amount;
+        let remaining_balance = donor_balance.amount;
+
+        let balance = self.stream.extra_mint_mut(&mint)?;
+        require!(balance.available()? >= amount, StreamError::InsufficientFunds);
+        balance.total_deposited = balance.total_deposited.checked_sub(amount).ok_or(StreamError::MathOverflow)?;
+
+        transfer_from_stream(
+            &self.stream,
+            &self.mint,
+            &self.stream_ata,
+            &self.donor_ata,
+            &self.token_program,
+            remaining_accounts,
+            amount,
+        )?;
+
+        emit!(MintRefundProcessed {
+            stream: self.stream.key(),
+            donor: self.donor.key(),
+            mint,
+            amount,
+            remaining_balance,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+/// Moves `amount` of `mint` out of the stream's vault for that mint, signed by the stream PDA
+fn transfer_from_stream<'info>(
+    stream: &Account<'info, StreamState>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    remaining_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    let cpi_accounts = TransferChecked {
+        from: from.to_account_info(),
+        mint: mint.to_account_info(),
+        to: to.to_account_info(),
+        authority: stream.to_account_info(),
+    };
+    let stream_seeds = stream.signer_seeds();
+    let stream_seeds: Vec<&[u8]> = stream_seeds.iter().map(Vec::as_slice).collect();
+    let signer = &[&stream_seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer)
+        .with_remaining_accounts(remaining_accounts.to_vec());
+    transfer_checked(cpi_ctx, amount, mint.decimals)
+}
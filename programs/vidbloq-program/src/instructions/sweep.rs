@@ -0,0 +1,117 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
+};
+
+use crate::state::{StreamState, StreamError, ForeignTokensSwept, ExcessLamportsSwept};
+
+#[derive(Accounts)]
+pub struct SweepForeignTokens<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        has_one = host,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    /// Tokens of the stream's own mint and of its extra mints are tracked by stream accounting
+    /// and can never be swept
+    #[account(
+        constraint = foreign_mint.key() != stream.mint @ StreamError::CannotSweepStreamMint,
+        constraint = !stream.extra_mints.iter().any(|m| m.mint == foreign_mint.key()) @ StreamError::CannotSweepStreamMint
+    )]
+    pub foreign_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = stream_foreign_ata.mint == foreign_mint.key(),
+        constraint = stream_foreign_ata.owner == stream.key()
+    )]
+    pub stream_foreign_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        associated_token::mint = foreign_mint,
+        associated_token::authority = host
+    )]
+    pub host_ata: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>
+}
+
+impl<'info> SweepForeignTokens<'info> {
+    pub fn sweep_foreign_tokens(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let amount = self.stream_foreign_ata.amount;
+        require!(amount > 0, StreamError::NothingToSweep);
+
+        let cpi_accounts = TransferChecked {
+            from: self.stream_foreign_ata.to_account_info(),
+            mint: self.foreign_mint.to_account_info(),
+            to: self.host_ata.to_account_info(),
+            authority: self.stream.to_account_info(),
+        };
+
+        let stream_seeds = self.stream.signer_seeds();
+        let stream_seeds: Vec<&[u8]> = stream_seeds.iter().map(Vec::as_slice).collect();
+        let signer = &[&stream_seeds[..]];
+
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.foreign_mint.decimals)?;
+
+        emit!(ForeignTokensSwept {
+            stream: self.stream.key(),
+            mint: self.foreign_mint.key(),
+            recipient: self.host.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SweepExcessLamports<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = host,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+}
+
+impl<'info> SweepExcessLamports<'info> {
+    pub fn sweep_excess_lamports(&mut self) -> Result<()> {
+        let stream_info = self.stream.to_account_info();
+
+        // Only lamports above the rent-exempt minimum are untracked; the rest keeps the stream alive
+        let rent_exempt_minimum = Rent::get()?.minimum_balance(stream_info.data_len());
+        let excess = stream_info
+            .lamports()
+            .checked_sub(rent_exempt_minimum)
+            .ok_or(StreamError::MathOverflow)?;
+        require!(excess > 0, StreamError::NothingToSweep);
+
+        **stream_info.try_borrow_mut_lamports()? -= excess;
+        **self.host.to_account_info().try_borrow_mut_lamports()? += excess;
+
+        emit!(ExcessLamportsSwept {
+            stream: self.stream.key(),
+            recipient: self.host.key(),
+            amount: excess,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::state::{
+    StreamState, StreamError, DonorAccount, DonationReceipt, HostProfile, TaxReceipt, TaxReceiptLine,
+    TaxReceiptIssued, HostJurisdictionSet, MAX_TAX_RECEIPT_STREAMS
+};
+
+pub const TAX_RECEIPT_SEED: &[u8] = b"tax_receipt";
+
+#[derive(Accounts)]
+pub struct SetHostJurisdiction<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"host_profile", host.key().as_ref()],
+        bump = host_profile.bump,
+        has_one = host
+    )]
+    pub host_profile: Account<'info, HostProfile>,
+}
+
+impl<'info> SetHostJurisdiction<'info> {
+    pub fn set_host_jurisdiction(&mut self, jurisdiction: [u8; 2]) -> Result<()> {
+        require!(
+            jurisdiction.iter().all(|c| c.is_ascii_uppercase()),
+            StreamError::InvalidJurisdiction
+        );
+        self.host_profile.jurisdiction = jurisdiction;
+
+        emit!(HostJurisdictionSet {
+            host: self.host.key(),
+            jurisdiction,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+/// Sums a donor's deposits to one host in one mint over a finished calendar year into a
+/// `TaxReceipt`. Remaining accounts are `stream_count` `[stream, donor_account]` pairs
+/// followed by the donor's `DonationReceipt`s for those streams.
+#[derive(Accounts)]
+#[instruction(year: u16)]
+pub struct AggregateDonorReceipt<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    #[account(
+        seeds = [b"host_profile", host_profile.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Account<'info, HostProfile>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = donor,
+        space = TaxReceipt::INIT_SPACE,
+        seeds = [
+            TAX_RECEIPT_SEED,
+            host_profile.host.as_ref(),
+            donor.key().as_ref(),
+            mint.key().as_ref(),
+            year.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub tax_receipt: Account<'info, TaxReceipt>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AggregateDonorReceipt<'info> {
+    pub fn aggregate_donor_receipt(
+        &mut self,
+        year: u16,
+        stream_count: u8,
+        bumps: &AggregateDonorReceiptBumps,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let (year_start, year_end) = TaxReceipt::year_bounds(year)?;
+        require!(now >= year_end, StreamError::TaxYearNotOver);
+
+        let stream_count = stream_count as usize;
+        require!(
+            stream_count > 0 && stream_count <= MAX_TAX_RECEIPT_STREAMS,
+            StreamError::InvalidRemainingAccounts
+        );
+        require!(remaining_accounts.len() >= stream_count * 2, StreamError::InvalidRemainingAccounts);
+        let (pairs, receipts) = remaining_accounts.split_at(stream_count * 2);
+
+        let host = self.host_profile.host;
+        let donor = self.donor.key();
+        let mint = self.mint.key();
+
+        // Each line is capped at what the donor still has in that stream, so refunded
+        // deposits are never counted
+        let mut lines: Vec<TaxReceiptLine> = Vec::with_capacity(stream_count);
+        let mut caps: Vec<u64> = Vec::with_capacity(stream_count);
+        for pair in pairs.chunks_exact(2) {
+            let stream = Account::<StreamState>::try_from(&pair[0])?;
+            let donor_account = Account::<DonorAccount>::try_from(&pair[1])?;
+            require_keys_eq!(stream.host, host, StreamError::Unauthorized);
+            require_keys_eq!(stream.mint, mint, StreamError::InvalidMint);
+            require_keys_eq!(donor_account.stream, stream.key(), StreamError::Unauthorized);
+            require_keys_eq!(donor_account.donor, donor, StreamError::Unauthorized);
+            require!(
+                !lines.iter().any(|line| line.stream == stream.key()),
+                StreamError::InvalidRemainingAccounts
+            );
+
+            lines.push(TaxReceiptLine { stream: stream.key(), amount: 0, donation_count: 0 });
+            caps.push(donor_account.amount);
+        }
+
+        let mut seen: Vec<Pubkey> = Vec::with_capacity(receipts.len());
+        for info in receipts {
+            require!(!seen.contains(info.key), StreamError::InvalidRemainingAccounts);
+            seen.push(info.key());
+
+            let receipt = Account::<DonationReceipt>::try_from(info)?;
+            require_keys_eq!(receipt.donor, donor, StreamError::Unauthorized);
+            if receipt.timestamp < year_start || receipt.timestamp >= year_end {
+                continue;
+            }
+            let line = lines
+                .iter_mut()
+                .find(|line| line.stream == receipt.stream)
+                .ok_or(StreamError::InvalidRemainingAccounts)?;
+            line.amount = line.amount.checked_add(receipt.amount).ok_or(StreamError::MathOverflow)?;
+            line.donation_count += 1;
+        }
+
+        let mut total_amount: u64 = 0;
+        let mut donation_count: u32 = 0;
+        for (line, cap) in lines.iter_mut().zip(caps) {
+            line.amount = line.amount.min(cap);
+            total_amount = total_amount.checked_add(line.amount).ok_or(StreamError::MathOverflow)?;
+            donation_count += line.donation_count;
+        }
+        lines.retain(|line| line.amount > 0);
+        require!(total_amount > 0, StreamError::NothingToClaim);
+
+        let jurisdiction = self.host_profile.jurisdiction;
+        self.tax_receipt.set_inner(TaxReceipt {
+            host,
+            donor,
+            mint,
+            year,
+            jurisdiction,
+            total_amount,
+            donation_count,
+            streams: lines,
+            issued_at: now,
+            bump: bumps.tax_receipt,
+        });
+
+        emit!(TaxReceiptIssued {
+            receipt: self.tax_receipt.key(),
+            host,
+            donor,
+            mint,
+            year,
+            jurisdiction,
+            total_amount,
+            donation_count,
+            timestamp: now
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{
+    StreamState, StreamError, DonorAccount, TierConfig, TierMembership, TiersConfigured,
+    TierMembershipUpdated, MAX_TIERS
+};
+
+#[derive(Accounts)]
+pub struct ConfigureTiers<'info> {
+    #[account(mut)]
+    pub host: Signer<'info>,
+
+    #[account(
+        has_one = host,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        init_if_needed,
+        payer = host,
+        space = TierConfig::INIT_SPACE,
+        seeds = [b"tier_config", stream.key().as_ref()],
+        bump
+    )]
+    pub tier_config: Account<'info, TierConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ConfigureTiers<'info> {
+    pub fn configure_tiers(&mut self, thresholds: Vec<u64>, bumps: &ConfigureTiersBumps) -> Result<()> {
+        require!(
+            !thresholds.is_empty() && thresholds.len() <= MAX_TIERS,
+            StreamError::InvalidTiers
+        );
+        require!(
+            thresholds[0] > 0 && thresholds.windows(2).all(|w| w[0] < w[1]),
+            StreamError::InvalidTiers
+        );
+
+        self.tier_config.set_inner(TierConfig {
+            stream: self.stream.key(),
+            thresholds: thresholds.clone(),
+            bump: bumps.tier_config,
+        });
+
+        emit!(TiersConfigured {
+            stream: self.stream.key(),
+            thresholds,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SyncTierMembership<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: The donor whose tier is being synced
+    pub donor: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        seeds = [b"tier_config", stream.key().as_ref()],
+        bump = tier_config.bump
+    )]
+    pub tier_config: Account<'info, TierConfig>,
+
+    #[account(
+        seeds = [b"donor", stream.key().as_ref(), donor.key().as_ref()],
+        bump = donor_account.bump,
+        constraint = donor_account.donor == donor.key(),
+        constraint = donor_account.stream == stream.key()
+    )]
+    pub donor_account: Account<'info, DonorAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = TierMembership::INIT_SPACE,
+        seeds = [b"tier_membership", stream.key().as_ref(), donor.key().as_ref()],
+        bump
+    )]
+    pub tier_membership: Account<'info, TierMembership>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SyncTierMembership<'info> {
+    pub fn sync_tier_membership(&mut self, bumps: &SyncTierMembershipBumps) -> Result<()> {
+        let is_new = self.tier_membership.donor == Pubkey::default();
+        let tier = self.tier_config.tier_for(self.donor_account.amount);
+
+        // Memberships are only minted once the first threshold is crossed
+        if is_new {
+            require!(tier > 0, StreamError::TierNotReached);
+        }
+        let previous_tier = self.tier_membership.tier;
+
+        self.tier_membership.set_inner(TierMembership {
+            stream: self.stream.key(),
+            donor: self.donor.key(),
+            tier,
+            updated_at: Clock::get()?.unix_timestamp,
+            bump: bumps.tier_membership,
+        });
+
+        emit!(TierMembershipUpdated {
+            stream: self.stream.key(),
+            donor: self.donor.key(),
+            previous_tier,
+            tier,
+            donated_amount: self.donor_account.amount,
+            timestamp: Clock::get()?.unix_timestamp
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::state::StreamError;
+
+/// Amount that actually landed in `vault` since it held `balance_before`. Token-2022 mints with a
+/// transfer fee withhold part of every transfer, so inbound accounting must use this rather than
+/// the amount requested.
+pub fn amount_received(vault: &mut InterfaceAccount<'_, TokenAccount>, balance_before: u64) -> Result<u64> {
+    vault.reload()?;
+    let received = vault.amount.checked_sub(balance_before).ok_or(StreamError::MathOverflow)?;
+    require!(received > 0, StreamError::InvalidAmount);
+    Ok(received)
+}
@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::instructions::{PlatformFeeRoute, MARKET_SEED, MARKET_VAULT_SEED, PLATFORM_CONFIG_SEED};
+use crate::state::{BettingMarket, HostProfile, MarketBook, ReferralRewards, StreamError, UnclaimedSwept};
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Host empties what bettors left in the vault once the claim deadline has passed. The funds go
+/// to the host, less the platform's cut, or into the pool of the host's next market when its
+/// accounts are passed.
+#[derive(Accounts)]
+pub struct SweepUnclaimed<'info> {
+    pub host: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        constraint = betting_market.host == host.key() @ StreamError::Unauthorized,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = host_token.owner == host.key(),
+        constraint = host_token.mint == mint.key(),
+    )]
+    pub host_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required while a platform fee is configured
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"host_profile", betting_market.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Option<Account<'info, HostProfile>>,
+
+    /// Credited with the referrer's share of the platform fee while the host's referral runs
+    #[account(mut)]
+    pub referral_rewards: Option<Account<'info, ReferralRewards>>,
+
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Open market of the same host and mint whose pool takes the funds instead
+    #[account(
+        mut,
+        constraint = next_market.host == host.key() @ StreamError::Unauthorized,
+        constraint = next_market.mint == mint.key() @ StreamError::InvalidMint,
+    )]
+    pub next_market: Option<Account<'info, BettingMarket>>,
+
+    #[account(mut)]
+    pub next_market_book: Option<AccountLoader<'info, MarketBook>>,
+
+    #[account(mut)]
+    pub next_market_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> SweepUnclaimed<'info> {
+    /// Sweeps everything in the vault except the liquidity providers' pool, who can still
+    /// withdraw. Fees, dust and withheld free bet stakes not yet taken out go with it.
+    pub fn sweep_unclaimed(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.betting_market.resolved, StreamError::MarketNotResolved);
+        require!(now > self.betting_market.claim_deadline, StreamError::ClaimWindowOpen);
+        let amount = self.market_vault.amount.saturating_sub(self.betting_market.lp_pool);
+        require!(amount > 0, StreamError::NothingToSweep);
+
+        let market = &mut self.betting_market;
+        market.fees_collected = 0;
+        market.dust_accumulator = 0;
+        market.free_bet_withheld = 0;
+
+        let market_seeds = &[MARKET_SEED, market.stream.as_ref(), &[market.bump]];
+        let signer = &[&market_seeds[..]];
+
+        let (destination, platform_fee, rolled_into) = match (
+            self.next_market.as_ref(),
+            self.next_market_book.as_ref(),
+            self.next_market_vault.as_ref(),
+        ) {
+            (Some(next_market), Some(next_book), Some(next_vault)) => {
+                require!(!next_market.resolved, StreamError::MarketResolved);
+                let (expected_vault, _) =
+                    Pubkey::find_program_address(&[MARKET_VAULT_SEED, next_market.key().as_ref()], &crate::ID);
+                require_keys_eq!(next_vault.key(), expected_vault, StreamError::InvalidMarketSetup);
+
+                let mut book = next_book.load_mut()?;
+                require_keys_eq!(book.market, next_market.key(), StreamError::InvalidMarketSetup);
+                book.total_pool = book.total_pool.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+                (next_vault.to_account_info(), 0, Some(next_market.key()))
+            }
+            (None, None, None) => {
+                let platform_fee = PlatformFeeRoute {
+                    platform_config: &self.platform_config,
+                    treasury_ata: self.treasury_ata.as_ref(),
+                    host_profile: self.host_profile.as_ref(),
+                    referral_rewards: self.referral_rewards.as_mut(),
+                    referral_vault: self.referral_vault.as_ref(),
+                }
+                .collect(
+                    market.host,
+                    &self.market_vault,
+                    &self.mint,
+                    market.to_account_info(),
+                    signer,
+                    &self.token_program,
+                    remaining_accounts,
+                    amount,
+                )?;
+                (self.host_token.to_account_info(), platform_fee, None)
+            }
+            _ => return err!(StreamError::MissingAccounts),
+        };
+
+        let transfer_amount = amount - platform_fee;
+        if transfer_amount > 0 {
+            let cpi_accounts = TransferChecked {
+                from: self.market_vault.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: destination,
+                authority: market.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+            transfer_checked(cpi_ctx, transfer_amount, self.mint.decimals)?;
+        }
+
+        emit!(UnclaimedSwept {
+            market: market.key(),
+            amount,
+            platform_fee,
+            rolled_into,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,257 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::instructions::{
+    amount_received, MARKET_SEED, PLATFORM_CONFIG_SEED, RESOLUTION_SEED, VALIDATOR_STAKE_REQUIREMENT, VOTE_RECORD_SEED,
+};
+use crate::state::{
+    BettingMarket, MarketResolution, PlatformConfig, ResolutionStatus, StreamError, ValidatorEscrow,
+    ValidatorStakeLocked, ValidatorStakeReleased, VoteRecord,
+};
+
+// ============= CONSTANTS =============
+pub const VALIDATOR_ESCROW_SEED: &[u8] = b"validator_escrow";
+pub const VALIDATOR_ESCROW_VAULT_SEED: &[u8] = b"validator_escrow_vault";
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// A selected validator locks stake before voting on the market's outcome
+#[derive(Accounts)]
+pub struct LockValidatorStake<'info> {
+    #[account(mut)]
+    pub validator: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [RESOLUTION_SEED, betting_market.key().as_ref()],
+        bump = resolution.load()?.bump,
+    )]
+    pub resolution: AccountLoader<'info, MarketResolution>,
+
+    #[account(
+        init,
+        payer = validator,
+        space = ValidatorEscrow::INIT_SPACE,
+        seeds = [VALIDATOR_ESCROW_SEED, betting_market.key().as_ref(), validator.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, ValidatorEscrow>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = validator_token.owner == validator.key(),
+        constraint = validator_token.mint == mint.key(),
+    )]
+    pub validator_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = validator,
+        seeds = [VALIDATOR_ESCROW_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = betting_market,
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Anyone releases a validator's locked stake once the market is settled, slashing a vote
+/// against the finalized outcome
+#[derive(Accounts)]
+pub struct ReleaseValidatorStake<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [RESOLUTION_SEED, betting_market.key().as_ref()],
+        bump = resolution.load()?.bump,
+    )]
+    pub resolution: AccountLoader<'info, MarketResolution>,
+
+    /// CHECK: Validator wallet; receives the escrow account's rent
+    #[account(mut)]
+    pub validator: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = validator,
+        seeds = [VALIDATOR_ESCROW_SEED, betting_market.key().as_ref(), validator.key().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, ValidatorEscrow>,
+
+    /// CHECK: Vote record PDA; empty when the validator never voted or was already rewarded
+    /// for voting with the finalized outcome
+    #[account(seeds = [VOTE_RECORD_SEED, resolution.key().as_ref(), validator.key().as_ref()], bump)]
+    pub vote_record: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [VALIDATOR_ESCROW_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub escrow_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = validator_token.owner == validator.key() @ StreamError::Unauthorized,
+        constraint = validator_token.mint == mint.key(),
+    )]
+    pub validator_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required when the stake is slashed
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> LockValidatorStake<'info> {
+    pub fn lock_validator_stake(
+        &mut self,
+        amount: u64,
+        bumps: &LockValidatorStakeBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        {
+            let resolution = self.resolution.load()?;
+            require!(
+                resolution.status_is(ResolutionStatus::UnderValidation),
+                StreamError::InvalidResolutionState
+            );
+            require!(
+                resolution.validators().contains(&self.validator.key()),
+                StreamError::NotValidator
+            );
+        }
+        require!(amount >= VALIDATOR_STAKE_REQUIREMENT, StreamError::BondTooLow);
+
+        let vault_before = self.escrow_vault.amount;
+        let cpi_accounts = TransferChecked {
+            from: self.validator_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.escrow_vault.to_account_info(),
+            authority: self.validator.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let amount = amount_received(&mut self.escrow_vault, vault_before)?;
+        require!(amount >= VALIDATOR_STAKE_REQUIREMENT, StreamError::BondTooLow);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.escrow.set_inner(ValidatorEscrow {
+            market: self.betting_market.key(),
+            validator: self.validator.key(),
+            amount,
+            locked_at: now,
+            bump: bumps.escrow,
+        });
+
+        emit!(ValidatorStakeLocked {
+            market: self.betting_market.key(),
+            validator: self.validator.key(),
+            amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ReleaseValidatorStake<'info> {
+    /// A cancelled market releases every stake whole. Otherwise a vote for any outcome but the
+    /// finalized one loses the platform's `validator_slash_bps` to the treasury; with no platform
+    /// config there is no treasury and nothing is slashed.
+    pub fn release_validator_stake(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let market = &self.betting_market;
+        require!(market.resolved || market.cancelled, StreamError::MarketNotResolved);
+
+        let voted_against = if market.cancelled || self.vote_record.data_is_empty() {
+            false
+        } else {
+            let resolution = self.resolution.load()?;
+            require!(
+                resolution.status_is(ResolutionStatus::Finalized),
+                StreamError::InvalidResolutionState
+            );
+            let vote = VoteRecord::try_deserialize(&mut &self.vote_record.data.borrow()[..])?;
+            market.winning_outcome != Some(vote.voted_outcome)
+        };
+
+        let amount = self.escrow.amount;
+        let platform_config = PlatformConfig::load(&self.platform_config)?;
+        let slashed = match (&platform_config, voted_against) {
+            (Some(config), true) => config.validator_slash_on(amount),
+            _ => 0,
+        };
+
+        let market_seeds = &[MARKET_SEED, market.stream.as_ref(), &[market.bump]];
+        let signer = &[&market_seeds[..]];
+
+        if slashed > 0 {
+            let config = platform_config.as_ref().ok_or(StreamError::MissingAccounts)?;
+            let treasury_ata = self.treasury_ata.as_ref().ok_or(StreamError::MissingAccounts)?;
+            require_keys_eq!(treasury_ata.owner, config.treasury, StreamError::Unauthorized);
+            require_keys_eq!(treasury_ata.mint, self.mint.key(), StreamError::InvalidMint);
+            self.transfer_from_escrow(treasury_ata.to_account_info(), slashed, signer, remaining_accounts)?;
+        }
+        let released = amount - slashed;
+        if released > 0 {
+            self.transfer_from_escrow(self.validator_token.to_account_info(), released, signer, remaining_accounts)?;
+        }
+
+        emit!(ValidatorStakeReleased {
+            market: self.betting_market.key(),
+            validator: self.validator.key(),
+            released,
+            slashed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    fn transfer_from_escrow(
+        &self,
+        to: AccountInfo<'info>,
+        amount: u64,
+        signer: &[&[&[u8]]],
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let cpi_accounts = TransferChecked {
+            from: self.escrow_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to,
+            authority: self.betting_market.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)
+    }
+}
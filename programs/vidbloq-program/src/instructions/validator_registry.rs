@@ -0,0 +1,263 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::instructions::{amount_received, VALIDATOR_STAKE_REQUIREMENT};
+use crate::state::{
+    StreamError, ValidatorDeregistered, ValidatorRegistered, ValidatorRegistration, ValidatorRegistry,
+    ValidatorStakeWithdrawn, VALIDATOR_COOLDOWN,
+};
+
+// ============= CONSTANTS =============
+pub const VALIDATOR_REGISTRY_SEED: &[u8] = b"validator_registry";
+pub const VALIDATOR_REGISTRY_VAULT_SEED: &[u8] = b"validator_registry_vault";
+pub const VALIDATOR_REGISTRATION_SEED: &[u8] = b"validator_registration";
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Opens the validator registry and stake vault for a mint; anyone may pay for it
+#[derive(Accounts)]
+pub struct InitializeValidatorRegistry<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = ValidatorRegistry::SPACE,
+        seeds = [VALIDATOR_REGISTRY_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub validator_registry: AccountLoader<'info, ValidatorRegistry>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [VALIDATOR_REGISTRY_VAULT_SEED, validator_registry.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = validator_registry,
+        token::token_program = token_program,
+    )]
+    pub registry_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Validator deposits stake to join a mint's registry, or tops up their stake
+#[derive(Accounts)]
+pub struct RegisterValidator<'info> {
+    #[account(mut)]
+    pub validator: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [VALIDATOR_REGISTRY_SEED, mint.key().as_ref()],
+        bump = validator_registry.load()?.bump,
+    )]
+    pub validator_registry: AccountLoader<'info, ValidatorRegistry>,
+
+    #[account(
+        init_if_needed,
+        payer = validator,
+        space = ValidatorRegistration::INIT_SPACE,
+        seeds = [VALIDATOR_REGISTRATION_SEED, mint.key().as_ref(), validator.key().as_ref()],
+        bump
+    )]
+    pub registration: Account<'info, ValidatorRegistration>,
+
+    #[account(
+        mut,
+        seeds = [VALIDATOR_REGISTRY_VAULT_SEED, validator_registry.key().as_ref()],
+        bump,
+    )]
+    pub registry_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = validator_token.owner == validator.key(),
+        constraint = validator_token.mint == mint.key(),
+    )]
+    pub validator_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Validator leaves the registry; their stake unlocks after `VALIDATOR_COOLDOWN`
+#[derive(Accounts)]
+pub struct DeregisterValidator<'info> {
+    pub validator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [VALIDATOR_REGISTRY_SEED, registration.mint.as_ref()],
+        bump = validator_registry.load()?.bump,
+    )]
+    pub validator_registry: AccountLoader<'info, ValidatorRegistry>,
+
+    #[account(
+        mut,
+        seeds = [VALIDATOR_REGISTRATION_SEED, registration.mint.as_ref(), validator.key().as_ref()],
+        bump = registration.bump,
+    )]
+    pub registration: Account<'info, ValidatorRegistration>,
+}
+
+/// Validator takes back their stake once the cooldown has ended, closing the registration
+#[derive(Accounts)]
+pub struct WithdrawValidatorStake<'info> {
+    #[account(mut)]
+    pub validator: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [VALIDATOR_REGISTRY_SEED, mint.key().as_ref()],
+        bump = validator_registry.load()?.bump,
+    )]
+    pub validator_registry: AccountLoader<'info, ValidatorRegistry>,
+
+    #[account(
+        mut,
+        close = validator,
+        seeds = [VALIDATOR_REGISTRATION_SEED, mint.key().as_ref(), validator.key().as_ref()],
+        bump = registration.bump,
+    )]
+    pub registration: Account<'info, ValidatorRegistration>,
+
+    #[account(
+        mut,
+        seeds = [VALIDATOR_REGISTRY_VAULT_SEED, validator_registry.key().as_ref()],
+        bump,
+    )]
+    pub registry_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = validator_token.owner == validator.key(),
+        constraint = validator_token.mint == mint.key(),
+    )]
+    pub validator_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> InitializeValidatorRegistry<'info> {
+    pub fn initialize_validator_registry(&mut self, bumps: &InitializeValidatorRegistryBumps) -> Result<()> {
+        let mut registry = self.validator_registry.load_init()?;
+        registry.mint = self.mint.key();
+        registry.bump = bumps.validator_registry;
+        Ok(())
+    }
+}
+
+impl<'info> RegisterValidator<'info> {
+    /// A new registration must bring at least `VALIDATOR_STAKE_REQUIREMENT`; a validator who is
+    /// cooling down has to withdraw before registering again
+    pub fn register_validator(
+        &mut self,
+        amount: u64,
+        bumps: &RegisterValidatorBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(amount > 0, StreamError::InvalidAmount);
+        require!(self.registration.unlocks_at == 0, StreamError::ValidatorCoolingDown);
+
+        let vault_before = self.registry_vault.amount;
+        let cpi_accounts = TransferChecked {
+            from: self.validator_token.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.registry_vault.to_account_info(),
+            authority: self.validator.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        let amount = amount_received(&mut self.registry_vault, vault_before)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let registration = &mut self.registration;
+        if registration.validator == Pubkey::default() {
+            registration.validator = self.validator.key();
+            registration.mint = self.mint.key();
+            registration.registered_at = now;
+            registration.bump = bumps.registration;
+        }
+        registration.stake = registration.stake.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        require!(registration.stake >= VALIDATOR_STAKE_REQUIREMENT, StreamError::BondTooLow);
+
+        self.validator_registry.load_mut()?.add(self.validator.key(), amount)?;
+
+        emit!(ValidatorRegistered {
+            mint: self.mint.key(),
+            validator: self.validator.key(),
+            amount,
+            stake: registration.stake,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> DeregisterValidator<'info> {
+    /// Markets that already selected the validator keep them; only new selections skip them
+    pub fn deregister_validator(&mut self) -> Result<()> {
+        require!(self.registration.is_active(), StreamError::NotValidator);
+
+        self.validator_registry.load_mut()?.remove(self.validator.key())?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let unlocks_at = now.checked_add(VALIDATOR_COOLDOWN).ok_or(StreamError::MathOverflow)?;
+        self.registration.unlocks_at = unlocks_at;
+
+        emit!(ValidatorDeregistered {
+            mint: self.registration.mint,
+            validator: self.validator.key(),
+            stake: self.registration.stake,
+            unlocks_at,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
+
+impl<'info> WithdrawValidatorStake<'info> {
+    pub fn withdraw_validator_stake(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let registration = &self.registration;
+        require!(registration.unlocks_at != 0 && now >= registration.unlocks_at, StreamError::ValidatorCoolingDown);
+
+        let mint_key = self.mint.key();
+        let registry_seeds = &[
+            VALIDATOR_REGISTRY_SEED,
+            mint_key.as_ref(),
+            &[self.validator_registry.load()?.bump],
+        ];
+        let signer = &[&registry_seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.registry_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.validator_token.to_account_info(),
+            authority: self.validator_registry.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, registration.stake, self.mint.decimals)?;
+
+        emit!(ValidatorStakeWithdrawn {
+            mint: mint_key,
+            validator: self.validator.key(),
+            amount: registration.stake,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
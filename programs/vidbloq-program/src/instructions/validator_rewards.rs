@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::instructions::{pay_from_vault, MARKET_SEED, MARKET_VAULT_SEED, RESOLUTION_SEED, VOTE_RECORD_SEED};
+use crate::state::{BettingMarket, MarketResolution, ResolutionStatus, StreamError, ValidatorRewardPaid, VoteRecord};
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Anyone pays a validator who voted for the winning outcome their slice of the validator reward
+#[derive(Accounts)]
+pub struct DistributeValidatorRewards<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+        has_one = mint,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        mut,
+        seeds = [RESOLUTION_SEED, betting_market.key().as_ref()],
+        bump = resolution.load()?.bump,
+    )]
+    pub resolution: AccountLoader<'info, MarketResolution>,
+
+    /// CHECK: Validator wallet; receives the vote record's rent
+    #[account(mut)]
+    pub validator: UncheckedAccount<'info>,
+
+    /// Closed once paid, so each vote is rewarded once
+    #[account(
+        mut,
+        close = validator,
+        seeds = [VOTE_RECORD_SEED, resolution.key().as_ref(), validator.key().as_ref()],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = validator_token.owner == validator.key() @ StreamError::Unauthorized,
+        constraint = validator_token.mint == mint.key(),
+    )]
+    pub validator_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= INSTRUCTION IMPLEMENTATIONS =============
+
+impl<'info> DistributeValidatorRewards<'info> {
+    /// Pays the validator's vote stake pro rata of the reward pool `finalize_resolution` set aside
+    pub fn distribute_validator_rewards(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(self.betting_market.resolved, StreamError::MarketNotResolved);
+        let reward = {
+            let mut resolution = self.resolution.load_mut()?;
+            require!(
+                resolution.status_is(ResolutionStatus::Finalized),
+                StreamError::InvalidResolutionState
+            );
+            let winning_outcome = resolution.proposed_outcome().ok_or(StreamError::InvalidResolutionState)?;
+            require!(
+                self.betting_market.winning_outcome == Some(winning_outcome),
+                StreamError::InvalidResolutionState
+            );
+            require!(self.vote_record.voted_outcome == winning_outcome, StreamError::NoWinnings);
+
+            let reward = resolution
+                .validator_reward(winning_outcome, self.vote_record.stake_amount)?
+                .min(resolution.validator_reward_pool.saturating_sub(resolution.validator_rewards_paid));
+            require!(reward > 0, StreamError::NoWinnings);
+            resolution.validator_rewards_paid = resolution
+                .validator_rewards_paid
+                .checked_add(reward)
+                .ok_or(StreamError::MathOverflow)?;
+            reward
+        };
+
+        pay_from_vault(
+            &self.betting_market,
+            &self.market_vault,
+            &self.mint,
+            &self.validator_token,
+            &self.token_program,
+            remaining_accounts,
+            reward,
+        )?;
+
+        emit!(ValidatorRewardPaid {
+            market: self.betting_market.key(),
+            validator: self.validator.key(),
+            amount: reward,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
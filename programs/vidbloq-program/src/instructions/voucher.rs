@@ -0,0 +1,409 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::{
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::instructions::{amount_received, PlatformFeeRoute, WinningsClaim, BETTOR_PROFILE_SEED, FEATURE_GATES_SEED, PLATFORM_CONFIG_SEED, MARKET_BOOK_SEED, MARKET_STATS_SEED, MARKET_CONFIG_SEED, MARKET_SEED, MARKET_VAULT_SEED, POSITION_SEED};
+use crate::state::{
+    BettingMarket, BettorPosition, BettorProfile, ClaimVoucher, MarketBook, MarketConfig, FeatureGates, HostProfile, ReferralEarnings, ReferralRewards, ClaimVoucherIssued, ClaimVoucherReclaimed,
+    ClaimVoucherRedeemed, StreamError, WinningsClaimed, VOUCHER_LIFETIME,
+};
+
+// ============= CONSTANTS =============
+pub const VOUCHER_SEED: &[u8] = b"claim_voucher";
+pub const VOUCHER_VAULT_SEED: &[u8] = b"voucher_vault";
+
+// ============= INSTRUCTIONS CONTEXTS =============
+
+/// Move a winning position's payout into a voucher escrow instead of a wallet. `secret_hash` is
+/// the sha256 of the secret followed by the wallet the winnings may be redeemed to.
+#[derive(Accounts)]
+#[instruction(secret_hash: [u8; 32])]
+pub struct IssueClaimVoucher<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump = bettor_position.bump,
+    )]
+    pub bettor_position: Account<'info, BettorPosition>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = mint.key() == betting_market.mint @ StreamError::InvalidMint
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = bettor,
+        space = ClaimVoucher::INIT_SPACE,
+        seeds = [VOUCHER_SEED, betting_market.key().as_ref(), secret_hash.as_ref()],
+        bump
+    )]
+    pub voucher: Account<'info, ClaimVoucher>,
+
+    #[account(
+        init,
+        payer = bettor,
+        seeds = [VOUCHER_VAULT_SEED, voucher.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = voucher,
+        token::token_program = token_program,
+    )]
+    pub voucher_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required while a platform fee is configured
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"host_profile", betting_market.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Option<Account<'info, HostProfile>>,
+
+    /// Credited with the referrer's share of the fee while the host's referral runs
+    #[account(mut)]
+    pub referral_rewards: Option<Account<'info, ReferralRewards>>,
+
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Earnings of the bettor's referrer, credited with the market's referral share of the fee
+    #[account(mut)]
+    pub referral_earnings: Option<Account<'info, ReferralEarnings>>,
+
+    #[account(mut)]
+    pub referral_earnings_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = BettorProfile::INIT_SPACE,
+        seeds = [BETTOR_PROFILE_SEED, bettor.key().as_ref()],
+        bump
+    )]
+    pub bettor_profile: Account<'info, BettorProfile>,
+
+    /// CHECK: Feature gates PDA; may be uninitialized
+    #[account(seeds = [FEATURE_GATES_SEED], bump)]
+    pub feature_gates: UncheckedAccount<'info>,
+
+    /// CHECK: Market stats PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [MARKET_STATS_SEED, betting_market.key().as_ref()], bump)]
+    pub market_stats: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Redeem a voucher by revealing the secret. The secret hash commits to the owner of the
+/// destination account as well, so a copied secret cannot redirect the winnings elsewhere.
+#[derive(Accounts)]
+pub struct RedeemClaimVoucher<'info> {
+    pub redeemer: Signer<'info>,
+
+    #[account(
+        mut,
+        close = issuer,
+        seeds = [VOUCHER_SEED, voucher.market.as_ref(), voucher.secret_hash.as_ref()],
+        bump = voucher.bump,
+        has_one = issuer,
+    )]
+    pub voucher: Account<'info, ClaimVoucher>,
+
+    #[account(
+        mut,
+        seeds = [VOUCHER_VAULT_SEED, voucher.key().as_ref()],
+        bump,
+    )]
+    pub voucher_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = destination.mint == voucher.mint @ StreamError::InvalidMint
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = voucher.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Original issuer, refunded the voucher rent
+    #[account(mut)]
+    pub issuer: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Return an expired, unredeemed voucher's winnings to the bettor who issued it
+#[derive(Accounts)]
+pub struct ReclaimClaimVoucher<'info> {
+    #[account(mut)]
+    pub issuer: Signer<'info>,
+
+    #[account(
+        mut,
+        close = issuer,
+        seeds = [VOUCHER_SEED, voucher.market.as_ref(), voucher.secret_hash.as_ref()],
+        bump = voucher.bump,
+        has_one = issuer,
+    )]
+    pub voucher: Account<'info, ClaimVoucher>,
+
+    #[account(
+        mut,
+        seeds = [VOUCHER_VAULT_SEED, voucher.key().as_ref()],
+        bump,
+    )]
+    pub voucher_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = issuer_ata.mint == voucher.mint @ StreamError::InvalidMint,
+        constraint = issuer_ata.owner == issuer.key() @ StreamError::Unauthorized
+    )]
+    pub issuer_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = voucher.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+// ============= IMPLEMENTATION =============
+
+impl<'info> IssueClaimVoucher<'info> {
+    pub fn issue_claim_voucher(
+        &mut self,
+        secret_hash: [u8; 32],
+        bumps: &IssueClaimVoucherBumps,
+        remaining_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let (payout, remaining_accounts) = WinningsClaim {
+            betting_market: &mut self.betting_market,
+            market_book: &self.market_book,
+            market_config: &self.market_config,
+            bettor_position: &mut self.bettor_position,
+            bettor_profile: &mut self.bettor_profile,
+            referral_earnings: self.referral_earnings.as_mut(),
+            referral_earnings_vault: self.referral_earnings_vault.as_ref(),
+            market_stats: &self.market_stats,
+            market_vault: &self.market_vault,
+            mint: &self.mint,
+            token_program: &self.token_program,
+        }.settle(self.bettor.key(), bumps.bettor_profile, remaining_accounts)?;
+        FeatureGates::require_not_queued(&self.feature_gates, payout)?;
+
+        let market_seeds = &[
+            MARKET_SEED,
+            self.betting_market.stream.as_ref(),
+            &[self.betting_market.bump],
+        ];
+        let signer = &[&market_seeds[..]];
+
+        let fee = PlatformFeeRoute {
+            platform_config: &self.platform_config,
+            treasury_ata: self.treasury_ata.as_ref(),
+            host_profile: self.host_profile.as_ref(),
+            referral_rewards: self.referral_rewards.as_mut(),
+            referral_vault: self.referral_vault.as_ref(),
+        }.collect(
+            self.betting_market.host,
+            &self.market_vault,
+            &self.mint,
+            self.betting_market.to_account_info(),
+            signer,
+            &self.token_program,
+            remaining_accounts,
+            payout,
+        )?;
+
+        let cpi_accounts = TransferChecked {
+            from: self.market_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.voucher_vault.to_account_info(),
+            authority: self.betting_market.to_account_info(),
+        };
+        let cpi_ctx =
+            CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+                .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, payout - fee, self.mint.decimals)?;
+        let escrowed = amount_received(&mut self.voucher_vault, 0)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        self.voucher.set_inner(ClaimVoucher {
+            market: self.betting_market.key(),
+            issuer: self.bettor.key(),
+            mint: self.mint.key(),
+            secret_hash,
+            amount: escrowed,
+            created_at: now,
+            bump: bumps.voucher,
+            expires_at: now + VOUCHER_LIFETIME,
+        });
+
+        emit!(WinningsClaimed {
+            market: self.betting_market.key(),
+            bettor: self.bettor.key(),
+            payout,
+            timestamp: now,
+        });
+
+        emit!(ClaimVoucherIssued {
+            market: self.betting_market.key(),
+            voucher: self.voucher.key(),
+            issuer: self.bettor.key(),
+            secret_hash,
+            amount: escrowed,
+            expires_at: now + VOUCHER_LIFETIME,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> RedeemClaimVoucher<'info> {
+    pub fn redeem_claim_voucher(&mut self, secret: Vec<u8>, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            hashv(&[&secret, self.destination.owner.as_ref()]).to_bytes() == self.voucher.secret_hash,
+            StreamError::InvalidVoucherSecret
+        );
+        require!(
+            Clock::get()?.unix_timestamp < self.voucher.expires_at,
+            StreamError::VoucherExpired
+        );
+
+        let amount = release_voucher(
+            &self.voucher,
+            &self.voucher_vault,
+            &self.destination,
+            &self.mint,
+            self.issuer.to_account_info(),
+            &self.token_program,
+            remaining_accounts,
+        )?;
+
+        emit!(ClaimVoucherRedeemed {
+            market: self.voucher.market,
+            voucher: self.voucher.key(),
+            destination: self.destination.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> ReclaimClaimVoucher<'info> {
+    pub fn reclaim_claim_voucher(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= self.voucher.expires_at, StreamError::VoucherNotExpired);
+
+        let amount = release_voucher(
+            &self.voucher,
+            &self.voucher_vault,
+            &self.issuer_ata,
+            &self.mint,
+            self.issuer.to_account_info(),
+            &self.token_program,
+            remaining_accounts,
+        )?;
+
+        emit!(ClaimVoucherReclaimed {
+            market: self.voucher.market,
+            voucher: self.voucher.key(),
+            issuer: self.issuer.key(),
+            amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+/// Empties the voucher vault into `destination` and closes it, refunding its rent to the
+/// issuer. Returns the amount paid out.
+fn release_voucher<'info>(
+    voucher: &Account<'info, ClaimVoucher>,
+    voucher_vault: &InterfaceAccount<'info, TokenAccount>,
+    destination: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    issuer: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<u64> {
+    let voucher_seeds = &[
+        VOUCHER_SEED,
+        voucher.market.as_ref(),
+        voucher.secret_hash.as_ref(),
+        &[voucher.bump],
+    ];
+    let signer = &[&voucher_seeds[..]];
+
+    let amount = voucher_vault.amount;
+    let cpi_accounts = TransferChecked {
+        from: voucher_vault.to_account_info(),
+        mint: mint.to_account_info(),
+        to: destination.to_account_info(),
+        authority: voucher.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        signer,
+    )
+    .with_remaining_accounts(remaining_accounts.to_vec());
+    transfer_checked(cpi_ctx, amount, mint.decimals)?;
+
+    let close_accounts = CloseAccount {
+        account: voucher_vault.to_account_info(),
+        destination: issuer,
+        authority: voucher.to_account_info(),
+    };
+    close_account(CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        close_accounts,
+        signer,
+    ))?;
+
+    Ok(amount)
+}
@@ -0,0 +1,391 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked};
+
+use crate::instructions::{record_refund, PlatformFeeRoute, WinningsClaim, BETTOR_PROFILE_SEED, FEATURE_GATES_SEED, PLATFORM_CONFIG_SEED, MARKET_BOOK_SEED, MARKET_STATS_SEED, MARKET_CONFIG_SEED, MARKET_SEED, MARKET_VAULT_SEED, POSITION_SEED};
+use crate::state::{
+    StreamState, StreamError, DonorAccount, BettingMarket, BettorPosition, MarketBook, MarketConfig, BettorProfile, FeatureGates, HostProfile, Leaderboard, ReferralEarnings, ReferralRewards,
+    WithdrawalTicket, WithdrawalSource, WithdrawalQueued, WithdrawalReleased
+};
+
+pub const WITHDRAWAL_TICKET_SEED: &[u8] = b"withdrawal_ticket";
+
+/// Settles a refund's accounting now and queues the token transfer for the crank
+#[derive(Accounts)]
+pub struct QueueRefund<'info> {
+    /// CHECK: This is the donor public key
+    pub donor: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = (initiator.key() == stream.host || initiator.key() == donor.key())
+    )]
+    pub initiator: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream", stream.first_seed().as_ref(), stream.second_seed().as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, StreamState>,
+
+    #[account(
+        mut,
+        seeds = [b"donor", stream.key().as_ref(), donor.key().as_ref()],
+        bump = donor_account.bump,
+        constraint = donor_account.donor == donor.key(),
+        constraint = donor_account.stream == stream.key()
+    )]
+    pub donor_account: Account<'info, DonorAccount>,
+
+    #[account(
+        constraint = donor_ata.owner == donor.key(),
+        constraint = donor_ata.mint == stream.mint
+    )]
+    pub donor_ata: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        constraint = stream_ata.mint == stream.mint,
+        constraint = stream_ata.owner == stream.key()
+    )]
+    pub stream_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// Required while the stream maintains a leaderboard
+    #[account(
+        mut,
+        seeds = [b"leaderboard", stream.key().as_ref()],
+        bump = leaderboard.bump
+    )]
+    pub leaderboard: Option<Account<'info, Leaderboard>>,
+
+    #[account(
+        mut,
+        seeds = [FEATURE_GATES_SEED],
+        bump = feature_gates.bump
+    )]
+    pub feature_gates: Account<'info, FeatureGates>,
+
+    #[account(
+        init,
+        payer = initiator,
+        space = WithdrawalTicket::INIT_SPACE,
+        seeds = [WITHDRAWAL_TICKET_SEED, feature_gates.next_ticket_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub ticket: Account<'info, WithdrawalTicket>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Marks a winning position claimed now and queues the payout for the crank
+#[derive(Accounts)]
+pub struct QueueClaimWinnings<'info> {
+    #[account(mut)]
+    pub bettor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_SEED, betting_market.stream.as_ref()],
+        bump = betting_market.bump,
+    )]
+    pub betting_market: Account<'info, BettingMarket>,
+
+    #[account(
+        seeds = [MARKET_BOOK_SEED, betting_market.key().as_ref()],
+        bump = market_book.load()?.bump,
+    )]
+    pub market_book: AccountLoader<'info, MarketBook>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, betting_market.key().as_ref()],
+        bump = market_config.bump,
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        mut,
+        seeds = [POSITION_SEED, betting_market.key().as_ref(), bettor.key().as_ref()],
+        bump = bettor_position.bump,
+    )]
+    pub bettor_position: Account<'info, BettorPosition>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_VAULT_SEED, betting_market.key().as_ref()],
+        bump,
+    )]
+    pub market_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = betting_market.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        constraint = bettor_token.mint == betting_market.mint @ StreamError::InvalidMint
+    )]
+    pub bettor_token: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Platform config PDA; may be uninitialized
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump)]
+    pub platform_config: UncheckedAccount<'info>,
+
+    /// Treasury token account, required while a platform fee is configured
+    #[account(mut)]
+    pub treasury_ata: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        seeds = [b"host_profile", betting_market.host.as_ref()],
+        bump = host_profile.bump
+    )]
+    pub host_profile: Option<Account<'info, HostProfile>>,
+
+    /// Credited with the referrer's share of the fee while the host's referral runs
+    #[account(mut)]
+    pub referral_rewards: Option<Account<'info, ReferralRewards>>,
+
+    #[account(mut)]
+    pub referral_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Earnings of the bettor's referrer, credited with the market's referral share of the fee
+    #[account(mut)]
+    pub referral_earnings: Option<Account<'info, ReferralEarnings>>,
+
+    #[account(mut)]
+    pub referral_earnings_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [FEATURE_GATES_SEED],
+        bump = feature_gates.bump
+    )]
+    pub feature_gates: Account<'info, FeatureGates>,
+
+    #[account(
+        init_if_needed,
+        payer = bettor,
+        space = BettorProfile::INIT_SPACE,
+        seeds = [BETTOR_PROFILE_SEED, bettor.key().as_ref()],
+        bump
+    )]
+    pub bettor_profile: Account<'info, BettorProfile>,
+
+    #[account(
+        init,
+        payer = bettor,
+        space = WithdrawalTicket::INIT_SPACE,
+        seeds = [WITHDRAWAL_TICKET_SEED, feature_gates.next_ticket_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub ticket: Account<'info, WithdrawalTicket>,
+
+    /// CHECK: Market stats PDA; counts are only kept once it has been initialized
+    #[account(mut, seeds = [MARKET_STATS_SEED, betting_market.key().as_ref()], bump)]
+    pub market_stats: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Permissionless crank that releases one queued payout, within the per-slot budget
+#[derive(Accounts)]
+pub struct ProcessWithdrawalTicket<'info> {
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [FEATURE_GATES_SEED],
+        bump = feature_gates.bump
+    )]
+    pub feature_gates: Account<'info, FeatureGates>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [WITHDRAWAL_TICKET_SEED, ticket.id.to_le_bytes().as_ref()],
+        bump = ticket.bump,
+        has_one = payer,
+        has_one = vault,
+        has_one = destination,
+        has_one = mint
+    )]
+    pub ticket: Account<'info, WithdrawalTicket>,
+
+    /// CHECK: Rent payer of the ticket, checked by `has_one`
+    #[account(mut)]
+    pub payer: UncheckedAccount<'info>,
+
+    /// Vault owner for `WithdrawalSource::Refund`
+    #[account(constraint = stream.key() == ticket.authority @ StreamError::Unauthorized)]
+    pub stream: Option<Account<'info, StreamState>>,
+
+    /// Vault owner for `WithdrawalSource::Winnings`
+    #[account(constraint = betting_market.key() == ticket.authority @ StreamError::Unauthorized)]
+    pub betting_market: Option<Account<'info, BettingMarket>>,
+
+    #[account(mut)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> QueueRefund<'info> {
+    pub fn queue_refund(&mut self, amount: u64, bumps: &QueueRefundBumps) -> Result<()> {
+        require!(self.feature_gates.withdrawal_queue_enabled, StreamError::WithdrawalQueueDisabled);
+        self.stream.require_refunds_open()?;
+
+        let payout = record_refund(&mut self.stream, &mut self.donor_account, self.leaderboard.as_mut(), amount)?;
+
+        open_ticket(
+            &mut self.feature_gates,
+            &mut self.ticket,
+            WithdrawalTicket {
+                id: 0,
+                source: WithdrawalSource::Refund,
+                authority: self.stream.key(),
+                vault: self.stream_ata.key(),
+                mint: self.stream.mint,
+                recipient: self.donor.key(),
+                destination: self.donor_ata.key(),
+                payer: self.initiator.key(),
+                amount: payout,
+                queued_at: 0,
+                bump: bumps.ticket,
+            },
+        )
+    }
+}
+
+impl<'info> QueueClaimWinnings<'info> {
+    pub fn queue_claim_winnings(&mut self, bumps: &QueueClaimWinningsBumps, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(self.feature_gates.withdrawal_queue_enabled, StreamError::WithdrawalQueueDisabled);
+
+        let (payout, remaining_accounts) = WinningsClaim {
+            betting_market: &mut self.betting_market,
+            market_book: &self.market_book,
+            market_config: &self.market_config,
+            bettor_position: &mut self.bettor_position,
+            bettor_profile: &mut self.bettor_profile,
+            referral_earnings: self.referral_earnings.as_mut(),
+            referral_earnings_vault: self.referral_earnings_vault.as_ref(),
+            market_stats: &self.market_stats,
+            market_vault: &self.market_vault,
+            mint: &self.mint,
+            token_program: &self.token_program,
+        }.settle(self.bettor.key(), bumps.bettor_profile, remaining_accounts)?;
+
+        // The platform fee is taken now; only the bettor's share waits in the queue
+        let market_seeds = &[
+            MARKET_SEED,
+            self.betting_market.stream.as_ref(),
+            &[self.betting_market.bump],
+        ];
+        let fee = PlatformFeeRoute {
+            platform_config: &self.platform_config,
+            treasury_ata: self.treasury_ata.as_ref(),
+            host_profile: self.host_profile.as_ref(),
+            referral_rewards: self.referral_rewards.as_mut(),
+            referral_vault: self.referral_vault.as_ref(),
+        }.collect(
+            self.betting_market.host,
+            &self.market_vault,
+            &self.mint,
+            self.betting_market.to_account_info(),
+            &[&market_seeds[..]],
+            &self.token_program,
+            remaining_accounts,
+            payout,
+        )?;
+
+        open_ticket(
+            &mut self.feature_gates,
+            &mut self.ticket,
+            WithdrawalTicket {
+                id: 0,
+                source: WithdrawalSource::Winnings,
+                authority: self.betting_market.key(),
+                vault: self.market_vault.key(),
+                mint: self.betting_market.mint,
+                recipient: self.bettor.key(),
+                destination: self.bettor_token.key(),
+                payer: self.bettor.key(),
+                amount: payout - fee,
+                queued_at: 0,
+                bump: bumps.ticket,
+            },
+        )
+    }
+}
+
+/// Stores `ticket` under the next queue id and emits `WithdrawalQueued`
+fn open_ticket(
+    gates: &mut Account<FeatureGates>,
+    account: &mut Account<WithdrawalTicket>,
+    mut ticket: WithdrawalTicket,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    ticket.id = gates.next_ticket_id;
+    ticket.queued_at = now;
+
+    gates.next_ticket_id = gates.next_ticket_id.checked_add(1).ok_or(StreamError::MathOverflow)?;
+    gates.pending_tickets = gates.pending_tickets.checked_add(1).ok_or(StreamError::MathOverflow)?;
+
+    emit!(WithdrawalQueued {
+        ticket: account.key(),
+        id: ticket.id,
+        source: ticket.source,
+        authority: ticket.authority,
+        recipient: ticket.recipient,
+        amount: ticket.amount,
+        timestamp: now
+    });
+    account.set_inner(ticket);
+    Ok(())
+}
+
+impl<'info> ProcessWithdrawalTicket<'info> {
+    pub fn process_withdrawal_ticket(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let clock = Clock::get()?;
+        self.feature_gates.record_release(clock.slot)?;
+
+        // The vault owner signs: the stream for refunds, the market for winnings
+        let (authority, seeds): (AccountInfo<'info>, Vec<Vec<u8>>) = match self.ticket.source {
+            WithdrawalSource::Refund => {
+                let stream = self.stream.as_ref().ok_or(StreamError::MissingAccounts)?;
+                (stream.to_account_info(), stream.signer_seeds())
+            }
+            WithdrawalSource::Winnings => {
+                let market = self.betting_market.as_ref().ok_or(StreamError::MissingAccounts)?;
+                (market.to_account_info(), vec![
+                    MARKET_SEED.to_vec(),
+                    market.stream.to_bytes().to_vec(),
+                    vec![market.bump],
+                ])
+            }
+        };
+        let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.destination.to_account_info(),
+            authority,
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+        transfer_checked(cpi_ctx, self.ticket.amount, self.mint.decimals)?;
+
+        emit!(WithdrawalReleased {
+            ticket: self.ticket.key(),
+            id: self.ticket.id,
+            recipient: self.ticket.recipient,
+            amount: self.ticket.amount,
+            slot: clock.slot,
+            timestamp: clock.unix_timestamp
+        });
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
 #![allow(unexpected_cfgs)]
 use anchor_lang::prelude::*;
 
+pub mod cluster_guard;
 pub mod instructions;
+pub mod math;
 pub mod state;
 
 use crate::instructions::*;
@@ -13,26 +15,286 @@ declare_id!("14SYsuFUHifkTHbgcvrZ4xKMsqeFGCD3rV7qNoZLdoND");
 pub mod vidbloq_program {
     use super::*;
 
-    pub fn initialize(ctx: Context<Initialize>, name: String, stream_type: StreamType, end_time: Option<i64>) -> Result<()> {
-        ctx.accounts.initialize(name, stream_type, end_time, &ctx.bumps)?;
+    pub fn initialize(ctx: Context<Initialize>, name: String, stream_type: StreamType, end_time: Option<i64>, refund_deadline: Option<i64>) -> Result<()> {
+        ctx.accounts.initialize(name, stream_type, end_time, refund_deadline, &ctx.bumps)?;
         Ok(())
     }
 
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
-        ctx.accounts.deposit(amount, &ctx.bumps)?;
+    pub fn initialize_and_deposit<'info>(ctx: Context<'_, '_, '_, 'info, InitializeAndDeposit<'info>>, name: String, stream_type: StreamType, end_time: Option<i64>, refund_deadline: Option<i64>, amount: u64) -> Result<()> {
+        ctx.accounts.initialize_and_deposit(name, stream_type, end_time, refund_deadline, amount, &ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn deposit<'info>(ctx: Context<'_, '_, '_, 'info, Deposit<'info>>, amount: u64, message: Option<String>) -> Result<()> {
+        ctx.accounts.deposit(amount, message, &ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn deposit_anonymous<'info>(ctx: Context<'_, '_, '_, 'info, DepositAnonymous<'info>>, amount: u64, commitment: [u8; 32], message: Option<String>) -> Result<()> {
+        ctx.accounts.deposit_anonymous(amount, commitment, message, &ctx.bumps, ctx.remaining_accounts)?;
         Ok(())
     }
     
-    pub fn refund(ctx: Context<Refund>, amount: u64) -> Result<()> {
-        ctx.accounts.refund(amount)?;
+    pub fn refund<'info>(ctx: Context<'_, '_, '_, 'info, Refund<'info>>, amount: u64) -> Result<()> {
+        ctx.accounts.refund(amount, ctx.remaining_accounts)?;
         Ok(())
     }
     
-    pub fn distribute(ctx: Context<Distribute>, amount: u64) -> Result<()> {
-        ctx.accounts.distribute(amount)?;
+    pub fn refund_batch<'info>(ctx: Context<'_, '_, 'info, 'info, RefundBatch<'info>>) -> Result<()> {
+        ctx.accounts.refund_batch(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn push_dust_refund<'info>(ctx: Context<'_, '_, '_, 'info, PushDustRefund<'info>>) -> Result<()> {
+        ctx.accounts.push_dust_refund(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn set_dust_refund_threshold(ctx: Context<SetDustRefundThreshold>, threshold: u64) -> Result<()> {
+        ctx.accounts.set_dust_refund_threshold(threshold)?;
+        Ok(())
+    }
+
+    pub fn set_extension_rate(ctx: Context<SetExtensionRate>, rate_per_minute: u64) -> Result<()> {
+        ctx.accounts.set_extension_rate(rate_per_minute)?;
+        Ok(())
+    }
+
+    pub fn extend_stream<'info>(ctx: Context<'_, '_, '_, 'info, ExtendStream<'info>>, minutes: u64) -> Result<()> {
+        ctx.accounts.extend_stream(minutes, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn check_in(ctx: Context<CheckIn>) -> Result<()> {
+        ctx.accounts.check_in(&ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn distribute<'info>(ctx: Context<'_, '_, '_, 'info, Distribute<'info>>, amount: u64) -> Result<()> {
+        ctx.accounts.distribute(amount, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn set_host_jurisdiction(ctx: Context<SetHostJurisdiction>, jurisdiction: [u8; 2]) -> Result<()> {
+        ctx.accounts.set_host_jurisdiction(jurisdiction)?;
+        Ok(())
+    }
+
+    pub fn post_host_bond(ctx: Context<PostHostBond>, amount: u64) -> Result<()> {
+        ctx.accounts.post_host_bond(amount)?;
+        Ok(())
+    }
+
+    pub fn withdraw_host_bond(ctx: Context<WithdrawHostBond>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_host_bond(amount)?;
+        Ok(())
+    }
+
+    pub fn slash_host_bond(ctx: Context<SlashHostBond>, amount: u64) -> Result<()> {
+        ctx.accounts.slash_host_bond(amount, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn aggregate_donor_receipt<'info>(ctx: Context<'_, '_, 'info, 'info, AggregateDonorReceipt<'info>>, year: u16, stream_count: u8) -> Result<()> {
+        ctx.accounts.aggregate_donor_receipt(year, stream_count, &ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn initialize_platform_config(ctx: Context<InitializePlatformConfig>, treasury: Pubkey, fee_bps: u16) -> Result<()> {
+        ctx.accounts.initialize_platform_config(treasury, fee_bps, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn initialize_global_registry(ctx: Context<InitializeGlobalRegistry>) -> Result<()> {
+        ctx.accounts.initialize_global_registry(&ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn update_platform_config(ctx: Context<UpdatePlatformConfig>, new_admin: Option<Pubkey>, treasury: Option<Pubkey>, fee_bps: Option<u16>) -> Result<()> {
+        ctx.accounts.update_platform_config(new_admin, treasury, fee_bps)?;
+        Ok(())
+    }
+
+    pub fn set_consensus_bounds(ctx: Context<UpdatePlatformConfig>, min_consensus_bps: u16, max_consensus_bps: u16) -> Result<()> {
+        ctx.accounts.set_consensus_bounds(min_consensus_bps, max_consensus_bps)?;
+        Ok(())
+    }
+
+    pub fn set_max_stream_lifetime(ctx: Context<UpdatePlatformConfig>, max_stream_lifetime: i64) -> Result<()> {
+        ctx.accounts.set_max_stream_lifetime(max_stream_lifetime)?;
+        Ok(())
+    }
+
+    pub fn set_validator_slash_bps(ctx: Context<UpdatePlatformConfig>, validator_slash_bps: u16) -> Result<()> {
+        ctx.accounts.set_validator_slash_bps(validator_slash_bps)?;
+        Ok(())
+    }
+
+    pub fn initialize_feature_gates(ctx: Context<InitializeFeatureGates>) -> Result<()> {
+        ctx.accounts.initialize_feature_gates(&ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn set_withdrawal_queue(ctx: Context<SetWithdrawalQueue>, enabled: bool, queue_threshold: u64, max_releases_per_slot: u16) -> Result<()> {
+        ctx.accounts.set_withdrawal_queue(enabled, queue_threshold, max_releases_per_slot)?;
+        Ok(())
+    }
+
+    pub fn queue_refund(ctx: Context<QueueRefund>, amount: u64) -> Result<()> {
+        ctx.accounts.queue_refund(amount, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn queue_claim_winnings<'info>(ctx: Context<'_, '_, '_, 'info, QueueClaimWinnings<'info>>) -> Result<()> {
+        ctx.accounts.queue_claim_winnings(&ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn process_withdrawal_ticket<'info>(ctx: Context<'_, '_, '_, 'info, ProcessWithdrawalTicket<'info>>) -> Result<()> {
+        ctx.accounts.process_withdrawal_ticket(ctx.remaining_accounts)?;
         Ok(())
     }
     
+    pub fn propose_distribution<'info>(ctx: Context<'_, '_, '_, 'info, ProposeDistribution<'info>>, amount: u64, timeout: i64) -> Result<()> {
+        ctx.accounts.propose_distribution(amount, timeout, &ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn accept_distribution<'info>(ctx: Context<'_, '_, '_, 'info, AcceptDistribution<'info>>) -> Result<()> {
+        ctx.accounts.accept_distribution(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn cancel_distribution<'info>(ctx: Context<'_, '_, '_, 'info, CancelDistribution<'info>>) -> Result<()> {
+        ctx.accounts.cancel_distribution(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn set_distribution_policy(ctx: Context<SetDistributionPolicy>, max_per_distribution: u64, max_per_window: u64) -> Result<()> {
+        ctx.accounts.set_distribution_policy(max_per_distribution, max_per_window, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn set_distribution_approvers(ctx: Context<SetDistributionPolicy>, approvers: Vec<Pubkey>, approvals_required: u8, approval_threshold: u64) -> Result<()> {
+        ctx.accounts.set_distribution_approvers(approvers, approvals_required, approval_threshold, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn propose_large_distribution(ctx: Context<ProposeLargeDistribution>, amount: u64) -> Result<()> {
+        ctx.accounts.propose_large_distribution(amount, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn approve_distribution(ctx: Context<ApproveDistribution>) -> Result<()> {
+        ctx.accounts.approve_distribution()?;
+        Ok(())
+    }
+
+    pub fn execute_distribution<'info>(ctx: Context<'_, '_, '_, 'info, ExecuteDistribution<'info>>) -> Result<()> {
+        ctx.accounts.execute_distribution(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn set_split_config(ctx: Context<SetSplitConfig>, shares: Vec<SplitShare>) -> Result<()> {
+        ctx.accounts.set_split_config(shares, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn distribute_split<'info>(ctx: Context<'_, '_, 'info, 'info, DistributeSplit<'info>>, amount: u64) -> Result<()> {
+        ctx.accounts.distribute_split(amount, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn set_recipient_allowance(ctx: Context<SetRecipientAllowance>, recipient: Pubkey, amount: u64) -> Result<()> {
+        ctx.accounts.set_recipient_allowance(recipient, amount, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn withdraw_allowance<'info>(ctx: Context<'_, '_, '_, 'info, WithdrawAllowance<'info>>, amount: u64) -> Result<()> {
+        ctx.accounts.withdraw_allowance(amount, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn add_payout_recipient(ctx: Context<AddPayoutRecipient>, recipient: Pubkey) -> Result<()> {
+        ctx.accounts.add_payout_recipient(recipient, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn remove_payout_recipient(ctx: Context<RemovePayoutRecipient>) -> Result<()> {
+        ctx.accounts.remove_payout_recipient()?;
+        Ok(())
+    }
+
+    pub fn set_payout_policy(ctx: Context<SetPayoutPolicy>, strict: bool) -> Result<()> {
+        ctx.accounts.set_payout_policy(strict)?;
+        Ok(())
+    }
+
+    pub fn consent_to_clawback(ctx: Context<ConsentToClawback>, window: i64, max_amount: u64) -> Result<()> {
+        ctx.accounts.consent_to_clawback(window, max_amount, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn initiate_clawback<'info>(ctx: Context<'_, '_, '_, 'info, InitiateClawback<'info>>, amount: u64) -> Result<()> {
+        ctx.accounts.initiate_clawback(amount, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn revoke_clawback_consent(ctx: Context<RevokeClawbackConsent>) -> Result<()> {
+        ctx.accounts.revoke_clawback_consent()?;
+        Ok(())
+    }
+
+    pub fn set_access_gate(ctx: Context<SetAccessGate>, gate_mint: Pubkey, min_balance: u64) -> Result<()> {
+        ctx.accounts.set_access_gate(gate_mint, min_balance, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn remove_access_gate(ctx: Context<RemoveAccessGate>) -> Result<()> {
+        ctx.accounts.remove_access_gate()?;
+        Ok(())
+    }
+
+    pub fn initialize_sol_stream(ctx: Context<InitializeSolStream>, name: String, stream_type: StreamType, end_time: Option<i64>, refund_deadline: Option<i64>) -> Result<()> {
+        ctx.accounts.initialize_sol_stream(name, stream_type, end_time, refund_deadline, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn deposit_sol(ctx: Context<DepositSol>, amount: u64, message: Option<String>) -> Result<()> {
+        ctx.accounts.deposit_sol(amount, message, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn distribute_sol(ctx: Context<DistributeSol>, amount: u64) -> Result<()> {
+        ctx.accounts.distribute_sol(amount, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn refund_sol(ctx: Context<RefundSol>, amount: u64) -> Result<()> {
+        ctx.accounts.refund_sol(amount, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn add_stream_mint(ctx: Context<AddStreamMint>) -> Result<()> {
+        ctx.accounts.add_stream_mint()?;
+        Ok(())
+    }
+
+    pub fn deposit_stream_mint<'info>(ctx: Context<'_, '_, '_, 'info, DepositStreamMint<'info>>, amount: u64) -> Result<()> {
+        ctx.accounts.deposit_stream_mint(amount, &ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn distribute_stream_mint<'info>(ctx: Context<'_, '_, '_, 'info, DistributeStreamMint<'info>>, amount: u64) -> Result<()> {
+        ctx.accounts.distribute_stream_mint(amount, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn refund_stream_mint<'info>(ctx: Context<'_, '_, '_, 'info, RefundStreamMint<'info>>, amount: u64) -> Result<()> {
+        ctx.accounts.refund_stream_mint(amount, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
     pub fn start_stream(ctx: Context<StartStream>) -> Result<()> {
         ctx.accounts.start_stream()?;
         Ok(())
@@ -47,9 +309,115 @@ pub mod vidbloq_program {
         ctx.accounts.update_stream(new_end_time, new_status)?;
         Ok(())
     }
+
+    pub fn rename_stream(ctx: Context<RenameStream>, new_name: String) -> Result<()> {
+        ctx.accounts.rename_stream(new_name)?;
+        Ok(())
+    }
+
+    pub fn migrate_stream(ctx: Context<MigrateStream>) -> Result<()> {
+        ctx.accounts.migrate_stream()?;
+        Ok(())
+    }
+
+    pub fn migrate_donor_account(ctx: Context<MigrateDonorAccount>) -> Result<()> {
+        ctx.accounts.migrate_donor_account()?;
+        Ok(())
+    }
+
+    pub fn set_burn_bps(ctx: Context<SetBurnBps>, burn_bps: u16) -> Result<()> {
+        ctx.accounts.set_burn_bps(burn_bps)?;
+        Ok(())
+    }
+
+    pub fn archive_stream(ctx: Context<ArchiveStream>) -> Result<()> {
+        ctx.accounts.archive_stream()?;
+        Ok(())
+    }
+    
+    pub fn set_fundraising_goal(ctx: Context<SetFundraisingGoal>, goal_amount: u64, milestone_percentages: Vec<u16>) -> Result<()> {
+        ctx.accounts.set_fundraising_goal(goal_amount, milestone_percentages)?;
+        Ok(())
+    }
+
+    pub fn configure_tiers(ctx: Context<ConfigureTiers>, thresholds: Vec<u64>) -> Result<()> {
+        ctx.accounts.configure_tiers(thresholds, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn sync_tier_membership(ctx: Context<SyncTierMembership>) -> Result<()> {
+        ctx.accounts.sync_tier_membership(&ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn relay_host_approval<'info>(ctx: Context<'_, '_, '_, 'info, RelayHostApproval<'info>>, approval: HostApproval) -> Result<()> {
+        ctx.accounts.relay_host_approval(approval, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn create_matching_pledge<'info>(ctx: Context<'_, '_, '_, 'info, CreateMatchingPledge<'info>>, ratio_bps: u16, cap: u64) -> Result<()> {
+        ctx.accounts.create_matching_pledge(ratio_bps, cap, &ctx.bumps, ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn close_matching_pledge<'info>(ctx: Context<'_, '_, '_, 'info, CloseMatchingPledge<'info>>) -> Result<()> {
+        ctx.accounts.close_matching_pledge(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn initialize_leaderboard(ctx: Context<InitializeLeaderboard>, capacity: u16) -> Result<()> {
+        ctx.accounts.initialize_leaderboard(capacity, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn resize_leaderboard(ctx: Context<ResizeLeaderboard>, new_capacity: u16) -> Result<()> {
+        ctx.accounts.resize_leaderboard(new_capacity)?;
+        Ok(())
+    }
+
+    pub fn block_wallet(ctx: Context<BlockWallet>, wallet: Pubkey) -> Result<()> {
+        ctx.accounts.block_wallet(wallet, &ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn unblock_wallet(ctx: Context<UnblockWallet>, wallet: Pubkey) -> Result<()> {
+        ctx.accounts.unblock_wallet(wallet)?;
+        Ok(())
+    }
+
+    pub fn open_referral_rewards(ctx: Context<OpenReferralRewards>) -> Result<()> {
+        ctx.accounts.open_referral_rewards(&ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn claim_host_referral_rewards<'info>(ctx: Context<'_, '_, '_, 'info, ClaimHostReferralRewards<'info>>) -> Result<()> {
+        ctx.accounts.claim_host_referral_rewards(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn open_referral_earnings(ctx: Context<OpenReferralEarnings>) -> Result<()> {
+        ctx.accounts.open_referral_earnings(&ctx.bumps)?;
+        Ok(())
+    }
+
+    pub fn claim_referral_earnings<'info>(ctx: Context<'_, '_, '_, 'info, ClaimReferralEarnings<'info>>) -> Result<()> {
+        ctx.accounts.claim_referral_earnings(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn sweep_foreign_tokens<'info>(ctx: Context<'_, '_, '_, 'info, SweepForeignTokens<'info>>) -> Result<()> {
+        ctx.accounts.sweep_foreign_tokens(ctx.remaining_accounts)?;
+        Ok(())
+    }
+
+    pub fn sweep_excess_lamports(ctx: Context<SweepExcessLamports>) -> Result<()> {
+        ctx.accounts.sweep_excess_lamports()?;
+        Ok(())
+    }
     
     // ============= BETTING INSTRUCTIONS =============
     
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_betting_market(
         ctx: Context<InitializeBettingMarket>,
         market_type: MarketType,
@@ -57,26 +425,196 @@ pub mod vidbloq_program {
         resolution_time: i64,
         initial_liquidity: u64,
         fee_percentage: u16,
+        payout_plan: ResolutionPayoutPlan,
+        rules: Option<MarketRules>,
+        consensus: Option<ConsensusParams>,
+        amm_curve: Option<AmmCurve>,
+        resolution_source: Option<ResolutionSource>,
     ) -> Result<()> {
-        ctx.accounts.initialize_market(market_type, outcomes, resolution_time, initial_liquidity, fee_percentage, &ctx.bumps)
+        ctx.accounts.initialize_market(market_type, outcomes, resolution_time, initial_liquidity, fee_percentage, payout_plan, rules, consensus, amm_curve, resolution_source, &ctx.bumps)
+    }
+
+    pub fn migrate_betting_market(ctx: Context<MigrateBettingMarket>) -> Result<()> {
+        ctx.accounts.migrate_betting_market(&ctx.bumps)
     }
     
-    pub fn place_bet(
-        ctx: Context<PlaceBet>,
+    pub fn place_bet<'info>(
+        ctx: Context<'_, '_, '_, 'info, PlaceBet<'info>>,
         outcome_id: u8,
         usdc_amount: u64,
         min_shares: u64,
+        deadline: Option<i64>,
+        max_price_per_share: Option<u64>,
+        beneficiary: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.place_bet(
+            outcome_id,
+            usdc_amount,
+            min_shares,
+            deadline,
+            max_price_per_share,
+            beneficiary,
+            &ctx.bumps,
+            ctx.remaining_accounts,
+        )
+    }
+
+    pub fn get_quote(ctx: Context<GetQuote>, outcome_id: u8, amount: u64) -> Result<BetQuote> {
+        ctx.accounts.get_quote(outcome_id, amount)
+    }
+
+    pub fn initialize_market_stats(ctx: Context<InitializeMarketStats>) -> Result<()> {
+        ctx.accounts.initialize_market_stats(&ctx.bumps)
+    }
+
+    pub fn add_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, AddLiquidity<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.add_liquidity(amount, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    pub fn remove_liquidity<'info>(
+        ctx: Context<'_, '_, '_, 'info, RemoveLiquidity<'info>>,
+        lp_shares: u64,
     ) -> Result<()> {
-        ctx.accounts.place_bet(outcome_id, usdc_amount, min_shares, &ctx.bumps)
+        ctx.accounts.remove_liquidity(lp_shares, ctx.remaining_accounts)
+    }
+
+    pub fn initialize_parlay_reserve(ctx: Context<InitializeParlayReserve>) -> Result<()> {
+        ctx.accounts.initialize_parlay_reserve(&ctx.bumps)
+    }
+
+    pub fn fund_parlay_reserve<'info>(
+        ctx: Context<'_, '_, '_, 'info, FundParlayReserve<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.fund_parlay_reserve(amount, ctx.remaining_accounts)
+    }
+
+    pub fn withdraw_parlay_reserve<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawParlayReserve<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.withdraw_parlay_reserve(amount, ctx.remaining_accounts)
+    }
+
+    pub fn place_parlay<'info>(
+        ctx: Context<'_, '_, 'info, 'info, PlaceParlay<'info>>,
+        parlay_id: u64,
+        legs: Vec<ParlayLeg>,
+        stake: u64,
+        min_payout: u64,
+    ) -> Result<()> {
+        ctx.accounts.place_parlay(parlay_id, legs, stake, min_payout, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    pub fn settle_parlay<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleParlay<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.settle_parlay(ctx.remaining_accounts)
+    }
+
+    pub fn sell_shares<'info>(
+        ctx: Context<'_, '_, '_, 'info, SellShares<'info>>,
+        outcome_id: u8,
+        shares: u64,
+        min_usdc_out: u64,
+    ) -> Result<()> {
+        ctx.accounts.sell_shares(outcome_id, shares, min_usdc_out, ctx.remaining_accounts)
+    }
+
+    pub fn cash_out_position<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CashOutPosition<'info>>,
+        min_usdc_out: u64,
+    ) -> Result<()> {
+        ctx.accounts.cash_out_position(min_usdc_out, ctx.remaining_accounts)
+    }
+    
+    pub fn set_market_taper(
+        ctx: Context<ConfigureMarket>,
+        taper: Option<BetTaper>,
+    ) -> Result<()> {
+        ctx.accounts.set_market_taper(taper)
+    }
+
+    pub fn update_resolution_time(ctx: Context<ConfigureMarket>, new_time: i64) -> Result<()> {
+        ctx.accounts.update_resolution_time(new_time)
+    }
+
+    pub fn close_betting(ctx: Context<ConfigureMarket>) -> Result<()> {
+        ctx.accounts.close_betting()
+    }
+
+    pub fn set_market_limits(
+        ctx: Context<ConfigureMarket>,
+        max_bet_per_user: Option<u64>,
+        max_total_pool: Option<u64>,
+    ) -> Result<()> {
+        ctx.accounts.set_market_limits(max_bet_per_user, max_total_pool)
+    }
+
+    pub fn set_market_access(
+        ctx: Context<SetMarketAccess>,
+        mode: MarketAccessMode,
+        list_root: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.set_market_access(mode, list_root)
+    }
+
+    pub fn list_market_bettor(ctx: Context<ListMarketBettor>, bettor: Pubkey) -> Result<()> {
+        ctx.accounts.list_market_bettor(bettor, &ctx.bumps)
+    }
+
+    pub fn unlist_market_bettor(ctx: Context<UnlistMarketBettor>) -> Result<()> {
+        ctx.accounts.unlist_market_bettor()
+    }
+
+    pub fn join_market_allowlist(ctx: Context<JoinMarketAllowlist>, proof: Vec<[u8; 32]>) -> Result<()> {
+        ctx.accounts.join_market_allowlist(proof, &ctx.bumps)
+    }
+
+    pub fn set_market_rules(
+        ctx: Context<ConfigureMarket>,
+        rules: MarketRules,
+    ) -> Result<()> {
+        ctx.accounts.set_market_rules(rules)
+    }
+
+    pub fn set_metric_source(ctx: Context<ConfigureMarket>, source: Pubkey) -> Result<()> {
+        ctx.accounts.set_metric_source(source)
+    }
+
+    pub fn expand_market(ctx: Context<ExpandMarket>, new_outcomes: Vec<String>) -> Result<()> {
+        ctx.accounts.expand_market(new_outcomes)
+    }
+
+    pub fn add_market_outcome(ctx: Context<ExpandMarket>, description: String) -> Result<()> {
+        ctx.accounts.add_market_outcome(description)
+    }
+
+    pub fn remove_market_outcome(ctx: Context<ExpandMarket>, outcome_id: u8) -> Result<()> {
+        ctx.accounts.remove_market_outcome(outcome_id)
     }
     
-    pub fn request_market_randomness(
-        ctx: Context<RequestMarketRandomness>,
+    pub fn set_resolution_timeout(ctx: Context<ConfigureMarket>, resolution_timeout: i64) -> Result<()> {
+        ctx.accounts.set_resolution_timeout(resolution_timeout)
+    }
+
+    pub fn set_bet_referral_share(ctx: Context<ConfigureMarket>, referral_share_bps: u16) -> Result<()> {
+        ctx.accounts.set_bet_referral_share(referral_share_bps)
+    }
+    
+    pub fn request_market_randomness<'info>(
+        ctx: Context<'_, '_, '_, 'info, RequestMarketRandomness<'info>>,
         use_case: RandomnessUseCase,
         client_seed: [u8; 32],
-        eligible_validators: Vec<EligibleValidator>,
     ) -> Result<()> {
-        ctx.accounts.request_randomness(use_case, client_seed, eligible_validators, &ctx.bumps)
+        ctx.accounts.request_randomness(use_case, client_seed, &ctx.bumps, ctx.remaining_accounts)
+    }
+    
+    pub fn force_resolve_timeout(ctx: Context<ForceResolveTimeout>, client_seed: [u8; 32]) -> Result<()> {
+        ctx.accounts.force_resolve_timeout(client_seed)
     }
     
     // VRF Callback - This MUST be in the main program module for Anchor to generate the discriminator
@@ -87,23 +625,377 @@ pub mod vidbloq_program {
         ctx.accounts.process_randomness(randomness)
     }
     
+    pub fn expire_randomness_request(ctx: Context<ExpireRandomnessRequest>) -> Result<()> {
+        ctx.accounts.expire_randomness_request()
+    }
+    
     pub fn validator_vote_on_outcome(
         ctx: Context<ValidatorVoteOnOutcome>,
         outcome_id: u8,
     ) -> Result<()> {
-        ctx.accounts.vote(outcome_id)
+        ctx.accounts.vote(outcome_id, &ctx.bumps)
     }
     
     pub fn resolve_market(
         ctx: Context<ResolveMarket>,
         winning_outcome: u8,
+        settlement_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.resolve_market(winning_outcome, settlement_hash)
+    }
+    
+    pub fn resolve_market_ranked(
+        ctx: Context<ResolveMarket>,
+        ranked_outcomes: Vec<u8>,
+        settlement_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.resolve_market_ranked(ranked_outcomes, settlement_hash)
+    }
+    
+    pub fn set_market_arbiter(ctx: Context<SetMarketArbiter>, new_arbiter: Pubkey) -> Result<()> {
+        ctx.accounts.set_market_arbiter(new_arbiter)
+    }
+    
+    pub fn finalize_resolution(ctx: Context<FinalizeResolution>) -> Result<()> {
+        ctx.accounts.finalize_resolution()
+    }
+    
+    pub fn distribute_validator_rewards<'info>(
+        ctx: Context<'_, '_, '_, 'info, DistributeValidatorRewards<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.distribute_validator_rewards(ctx.remaining_accounts)
+    }
+    
+    pub fn lock_validator_stake<'info>(
+        ctx: Context<'_, '_, '_, 'info, LockValidatorStake<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.lock_validator_stake(amount, &ctx.bumps, ctx.remaining_accounts)
+    }
+    
+    pub fn release_validator_stake<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReleaseValidatorStake<'info>>,
     ) -> Result<()> {
-        ctx.accounts.resolve_market(winning_outcome)
+        ctx.accounts.release_validator_stake(ctx.remaining_accounts)
     }
     
-    pub fn claim_winnings(
-        ctx: Context<ClaimWinnings>,
+    pub fn claim_winnings<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimWinnings<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.claim_winnings(&ctx.bumps, ctx.remaining_accounts)
+    }
+
+    pub fn cancel_market(ctx: Context<CancelMarket>) -> Result<()> {
+        ctx.accounts.cancel_market()
+    }
+
+    pub fn claim_refund<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimRefund<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.claim_refund(ctx.remaining_accounts)
+    }
+
+    pub fn rollover_pool<'info>(ctx: Context<'_, '_, '_, 'info, RolloverPool<'info>>) -> Result<()> {
+        ctx.accounts.rollover_pool(ctx.remaining_accounts)
+    }
+
+    pub fn close_bettor_position<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseBettorPosition<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.close_bettor_position(&ctx.bumps, ctx.remaining_accounts)
+    }
+
+    pub fn close_outcome_position(ctx: Context<CloseOutcomePosition>) -> Result<()> {
+        ctx.accounts.close_outcome_position()
+    }
+
+    pub fn collect_market_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, CollectMarketFees<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.collect_market_fees(ctx.remaining_accounts)
+    }
+
+    pub fn sweep_market_dust<'info>(
+        ctx: Context<'_, '_, '_, 'info, SweepMarketDust<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.sweep_market_dust(ctx.remaining_accounts)
+    }
+
+    pub fn sweep_unclaimed<'info>(ctx: Context<'_, '_, '_, 'info, SweepUnclaimed<'info>>) -> Result<()> {
+        ctx.accounts.sweep_unclaimed(ctx.remaining_accounts)
+    }
+
+    pub fn issue_claim_voucher<'info>(
+        ctx: Context<'_, '_, '_, 'info, IssueClaimVoucher<'info>>,
+        secret_hash: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.issue_claim_voucher(secret_hash, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    pub fn redeem_claim_voucher<'info>(
+        ctx: Context<'_, '_, '_, 'info, RedeemClaimVoucher<'info>>,
+        secret: Vec<u8>,
+    ) -> Result<()> {
+        ctx.accounts.redeem_claim_voucher(secret, ctx.remaining_accounts)
+    }
+
+    pub fn reclaim_claim_voucher<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReclaimClaimVoucher<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.reclaim_claim_voucher(ctx.remaining_accounts)
+    }
+    
+    // ============= OPTIMISTIC RESOLUTION =============
+    
+    pub fn propose_outcome<'info>(
+        ctx: Context<'_, '_, '_, 'info, ProposeOutcome<'info>>,
+        outcome_id: u8,
+        bond_amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.propose_outcome(outcome_id, bond_amount, &ctx.bumps, ctx.remaining_accounts)
+    }
+    
+    pub fn challenge_proposal<'info>(
+        ctx: Context<'_, '_, '_, 'info, ChallengeProposal<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.challenge_proposal(ctx.remaining_accounts)
+    }
+    
+    pub fn finalize_proposal<'info>(
+        ctx: Context<'_, '_, '_, 'info, FinalizeProposal<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.finalize_proposal(ctx.remaining_accounts)
+    }
+    
+    pub fn settle_challenge<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleChallenge<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.settle_challenge(ctx.remaining_accounts)
+    }
+
+    // ============= DISPUTES =============
+
+    pub fn file_dispute<'info>(
+        ctx: Context<'_, '_, '_, 'info, FileDispute<'info>>,
+        claimed_outcome: u8,
+        bond_amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.file_dispute(claimed_outcome, bond_amount, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    pub fn vote_on_dispute(ctx: Context<VoteOnDispute>, outcome_id: u8) -> Result<()> {
+        ctx.accounts.vote_on_dispute(outcome_id, &ctx.bumps)
+    }
+
+    pub fn settle_dispute<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleDispute<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.settle_dispute(ctx.remaining_accounts)
+    }
+
+    // ============= ORACLE RESOLUTION =============
+
+    pub fn configure_market_oracle(
+        ctx: Context<ConfigureMarketOracle>,
+        reporters: Vec<Pubkey>,
+        tolerance: u64,
+    ) -> Result<()> {
+        ctx.accounts.configure_market_oracle(reporters, tolerance, &ctx.bumps)
+    }
+
+    pub fn submit_oracle_result(
+        ctx: Context<SubmitOracleResult>,
+        round: u32,
+        value: u64,
+    ) -> Result<()> {
+        ctx.accounts.submit_oracle_result(round, value)
+    }
+
+    pub fn open_oracle_round(ctx: Context<OpenOracleRound>) -> Result<()> {
+        ctx.accounts.open_oracle_round()
+    }
+
+    pub fn resolve_with_oracle(ctx: Context<ResolveWithOracle>) -> Result<()> {
+        ctx.accounts.resolve_with_oracle()
+    }
+
+    pub fn resolve_from_oracle(ctx: Context<ResolveFromOracle>) -> Result<()> {
+        ctx.accounts.resolve_from_oracle()
+    }
+
+    pub fn report_metric(ctx: Context<ReportMetric>, value: u64) -> Result<()> {
+        ctx.accounts.report_metric(value)
+    }
+    
+    // ============= FREE BETS =============
+    
+    pub fn grant_free_bet_credit<'info>(
+        ctx: Context<'_, '_, '_, 'info, GrantFreeBetCredit<'info>>,
+        bettor: Pubkey,
+        amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        ctx.accounts.grant_free_bet_credit(bettor, amount, expires_at, &ctx.bumps, ctx.remaining_accounts)
+    }
+    
+    pub fn place_free_bet<'info>(
+        ctx: Context<'_, '_, '_, 'info, PlaceFreeBet<'info>>,
+        outcome_id: u8,
+        amount: u64,
+        min_shares: u64,
+    ) -> Result<()> {
+        ctx.accounts.place_free_bet(outcome_id, amount, min_shares, &ctx.bumps, ctx.remaining_accounts)
+    }
+    
+    pub fn clawback_free_bet_credit<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClawbackFreeBetCredit<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.clawback_free_bet_credit(ctx.remaining_accounts)
+    }
+    
+    pub fn reclaim_free_bet_stakes<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReclaimFreeBetStakes<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.reclaim_free_bet_stakes(ctx.remaining_accounts)
+    }
+    
+    // ============= RAFFLES =============
+    
+    pub fn create_raffle<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateRaffle<'info>>,
+        raffle_id: u64,
+        prize: u64,
+        ticket_price: u64,
+        entry_deadline: i64,
+        max_entries: u32,
+    ) -> Result<()> {
+        ctx.accounts.create_raffle(raffle_id, prize, ticket_price, entry_deadline, max_entries, &ctx.bumps, ctx.remaining_accounts)
+    }
+    
+    pub fn enter_raffle<'info>(ctx: Context<'_, '_, '_, 'info, EnterRaffle<'info>>) -> Result<()> {
+        ctx.accounts.enter_raffle(&ctx.bumps, ctx.remaining_accounts)
+    }
+    
+    pub fn draw_raffle_winner(ctx: Context<DrawRaffleWinner>, client_seed: [u8; 32]) -> Result<()> {
+        ctx.accounts.draw_raffle_winner(client_seed)
+    }
+    
+    // VRF Callback for raffle draws
+    pub fn callback_draw_raffle(ctx: Context<CallbackDrawRaffle>, randomness: [u8; 32]) -> Result<()> {
+        ctx.accounts.draw_raffle(randomness)
+    }
+    
+    pub fn claim_raffle_prize<'info>(ctx: Context<'_, '_, '_, 'info, ClaimRafflePrize<'info>>) -> Result<()> {
+        ctx.accounts.claim_raffle_prize(ctx.remaining_accounts)
+    }
+    
+    pub fn cancel_raffle<'info>(ctx: Context<'_, '_, '_, 'info, CancelRaffle<'info>>) -> Result<()> {
+        ctx.accounts.cancel_raffle(ctx.remaining_accounts)
+    }
+    
+    // ============= DONOR BONUS POOLS =============
+    
+    pub fn create_bonus_pool<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateBonusPool<'info>>,
+        pool_id: u64,
+        amount: u64,
+        winner_count: u8,
+        entry_deadline: i64,
+    ) -> Result<()> {
+        ctx.accounts.create_bonus_pool(pool_id, amount, winner_count, entry_deadline, &ctx.bumps, ctx.remaining_accounts)
+    }
+    
+    pub fn enter_bonus_pool(ctx: Context<EnterBonusPool>) -> Result<()> {
+        ctx.accounts.enter_bonus_pool(&ctx.bumps)
+    }
+    
+    pub fn draw_bonus_pool(ctx: Context<DrawBonusPool>, client_seed: [u8; 32]) -> Result<()> {
+        ctx.accounts.draw_bonus_pool(client_seed)
+    }
+    
+    // VRF Callback for bonus pool draws
+    pub fn callback_draw_bonus_pool(ctx: Context<CallbackDrawBonusPool>, randomness: [u8; 32]) -> Result<()> {
+        ctx.accounts.draw_bonus_pool(randomness)
+    }
+    
+    pub fn claim_bonus<'info>(ctx: Context<'_, '_, '_, 'info, ClaimBonus<'info>>) -> Result<()> {
+        ctx.accounts.claim_bonus(ctx.remaining_accounts)
+    }
+    
+    pub fn reclaim_bonus_pool<'info>(ctx: Context<'_, '_, '_, 'info, ReclaimBonusPool<'info>>) -> Result<()> {
+        ctx.accounts.reclaim_bonus_pool(ctx.remaining_accounts)
+    }
+
+    // ============= SESSION KEYS =============
+
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        session_key: Pubkey,
+        spend_limit: u64,
+        duration: i64,
+    ) -> Result<()> {
+        ctx.accounts.create_session(session_key, spend_limit, duration, &ctx.bumps)
+    }
+
+    pub fn revoke_session(ctx: Context<RevokeSession>) -> Result<()> {
+        ctx.accounts.revoke_session()
+    }
+
+    pub fn deposit_with_session<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositWithSession<'info>>,
+        amount: u64,
+        message: Option<String>,
+    ) -> Result<()> {
+        ctx.accounts.deposit_with_session(amount, message, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    // ============= BET MIRRORING =============
+
+    pub fn enable_host_position_mirroring(
+        ctx: Context<EnableHostPositionMirroring>,
+        tipster: Option<Pubkey>,
+        revenue_share_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.enable_host_position_mirroring(tipster, revenue_share_bps, &ctx.bumps)
+    }
+
+    pub fn mirror_bet<'info>(
+        ctx: Context<'_, '_, '_, 'info, MirrorBet<'info>>,
+        amount: u64,
+        min_shares: u64,
+    ) -> Result<()> {
+        ctx.accounts.mirror_bet(amount, min_shares, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    // ============= VALIDATOR REGISTRY =============
+
+    pub fn initialize_validator_registry(ctx: Context<InitializeValidatorRegistry>) -> Result<()> {
+        ctx.accounts.initialize_validator_registry(&ctx.bumps)
+    }
+
+    pub fn register_validator<'info>(
+        ctx: Context<'_, '_, '_, 'info, RegisterValidator<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.register_validator(amount, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    pub fn deregister_validator(ctx: Context<DeregisterValidator>) -> Result<()> {
+        ctx.accounts.deregister_validator()
+    }
+
+    pub fn withdraw_validator_stake<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawValidatorStake<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.withdraw_validator_stake(ctx.remaining_accounts)
+    }
+
+    // ============= RESOLUTION CRANK =============
+
+    pub fn crank_resolution<'info>(
+        ctx: Context<'_, '_, '_, 'info, CrankResolution<'info>>,
+        client_seed: [u8; 32],
     ) -> Result<()> {
-        ctx.accounts.claim_winnings()
+        ctx.accounts.crank_resolution(client_seed, &ctx.bumps, ctx.remaining_accounts)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,125 @@
+//! Fixed-point helpers for market-maker curves.
+//!
+//! Values are `u128`/`i128` scaled by [`FIXED_ONE`] (twelve decimal places). That precision is
+//! well below a base unit for realistic liquidity, and it leaves enough headroom that products of
+//! two scaled values stay inside 128 bits without a wide multiply. Every result that becomes an
+//! amount or share count rounds down.
+
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+pub const FIXED_ONE: u128 = 1_000_000_000_000;
+pub const LN_2: u128 = 693_147_180_560; // ln(2) scaled by FIXED_ONE
+/// Largest exponent `exp_fixed` accepts; e^20 scaled still fits comfortably in a u128 product
+pub const MAX_EXP_INPUT: i128 = 20 * FIXED_ONE as i128;
+/// Below this exponent e^x is under one scaled unit
+const MIN_EXP_INPUT: i128 = -29 * FIXED_ONE as i128;
+
+/// e^x for a scaled `x` no larger than [`MAX_EXP_INPUT`]. Large negative inputs underflow to 0.
+pub fn exp_fixed(x: i128) -> Result<u128> {
+    require!(x <= MAX_EXP_INPUT, StreamError::MathOverflow);
+    if x < MIN_EXP_INPUT {
+        return Ok(0);
+    }
+
+    // x = k * ln 2 + r with |r| <= ln 2 / 2, so e^x = 2^k * e^r and the series for e^r converges fast
+    let ln2 = LN_2 as i128;
+    let k = if x >= 0 { (x + ln2 / 2) / ln2 } else { (x - ln2 / 2) / ln2 };
+    let r = x - k * ln2;
+
+    let one = FIXED_ONE as i128;
+    let mut term = one;
+    let mut sum = one;
+    for n in 1..=16 {
+        term = term * r / (n * one);
+        if term == 0 {
+            break;
+        }
+        sum += term;
+    }
+
+    let sum = sum as u128;
+    Ok(if k >= 0 { sum << k } else { sum >> (-k) })
+}
+
+/// Natural log of a positive scaled `x`
+pub fn ln_fixed(x: u128) -> Result<i128> {
+    require!(x > 0, StreamError::MathOverflow);
+
+    // Normalise to m in [1, 2) so ln x = k * ln 2 + ln m
+    let mut m = x;
+    let mut k: i128 = 0;
+    while m >= 2 * FIXED_ONE {
+        m >>= 1;
+        k += 1;
+    }
+    while m < FIXED_ONE {
+        m <<= 1;
+        k -= 1;
+    }
+
+    // ln m = 2 * atanh(z) with z = (m - 1) / (m + 1) <= 1/3
+    let z = (m - FIXED_ONE) * FIXED_ONE / (m + FIXED_ONE);
+    let z2 = z * z / FIXED_ONE;
+    let mut term = z;
+    let mut sum = z;
+    for n in 1..=20u128 {
+        term = term * z2 / FIXED_ONE;
+        if term == 0 {
+            break;
+        }
+        sum += term / (2 * n + 1);
+    }
+
+    Ok(k * LN_2 as i128 + 2 * sum as i128)
+}
+
+/// e^((q_i - max q) / b) for each outcome, plus their sum. Shifting by the largest quantity
+/// keeps every term at or below one without changing any ratio between them.
+fn lmsr_terms(quantities: &[u64], liquidity: u64) -> Result<(Vec<u128>, u128)> {
+    require!(liquidity > 0 && !quantities.is_empty(), StreamError::InvalidMarketSetup);
+    let max = *quantities.iter().max().unwrap_or(&0);
+    let terms = quantities
+        .iter()
+        .map(|q| exp_fixed(-(((max - q) as u128 * FIXED_ONE / liquidity as u128) as i128)))
+        .collect::<Result<Vec<_>>>()?;
+    let sum = terms.iter().sum();
+    Ok((terms, sum))
+}
+
+/// Shares of `outcome` that `amount` buys under a logarithmic market scoring rule with
+/// liquidity `b`: the Δ solving C(q + Δ·e_k) - C(q) = amount for C(q) = b·ln Σ e^(q_i/b)
+pub fn lmsr_buy_shares(quantities: &[u64], liquidity: u64, outcome: usize, amount: u64) -> Result<u64> {
+    let (terms, sum) = lmsr_terms(quantities, liquidity)?;
+    let term = *terms.get(outcome).ok_or(StreamError::InvalidOutcome)?;
+    require!(term > 0, StreamError::MathOverflow);
+
+    let exponent = amount as u128 * FIXED_ONE / liquidity as u128;
+    require!(exponent <= MAX_EXP_INPUT as u128, StreamError::BetTooLarge);
+    let growth = exp_fixed(exponent as i128)?;
+    // Δ / b = ln(t_k + S·(e^(amount/b) - 1)) - ln(t_k)
+    let raised = term + sum * (growth - FIXED_ONE) / FIXED_ONE;
+    let delta = (ln_fixed(raised)? - ln_fixed(term)?).max(0) as u128;
+    Ok((delta * liquidity as u128 / FIXED_ONE) as u64)
+}
+
+/// Amount returned for selling `shares` of `outcome` back to the curve: C(q) - C(q - Δ·e_k)
+pub fn lmsr_sell_proceeds(quantities: &[u64], liquidity: u64, outcome: usize, shares: u64) -> Result<u64> {
+    let (terms, sum) = lmsr_terms(quantities, liquidity)?;
+    let term = *terms.get(outcome).ok_or(StreamError::InvalidOutcome)?;
+
+    let exponent = shares as u128 * FIXED_ONE / liquidity as u128;
+    let shrink = exp_fixed(-(exponent as i128))?;
+    let lowered = sum - term + term * shrink / FIXED_ONE;
+    require!(lowered > 0, StreamError::MathOverflow);
+    let value = (ln_fixed(sum)? - ln_fixed(lowered)?).max(0) as u128;
+    Ok((value * liquidity as u128 / FIXED_ONE) as u64)
+}
+
+/// Implied probability of `outcome`, scaled by [`FIXED_ONE`]; probabilities sum to one
+pub fn lmsr_price(quantities: &[u64], liquidity: u64, outcome: usize) -> Result<u128> {
+    let (terms, sum) = lmsr_terms(quantities, liquidity)?;
+    let term = *terms.get(outcome).ok_or(StreamError::InvalidOutcome)?;
+    Ok(term * FIXED_ONE / sum)
+}
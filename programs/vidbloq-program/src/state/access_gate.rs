@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+/// Holding requirement for depositing into a stream and betting on its market. Gating on an
+/// NFT uses the NFT's own mint with `min_balance` of one.
+#[account]
+pub struct AccessGate {
+    pub stream: Pubkey,         // Gated stream
+    pub gate_mint: Pubkey,      // Token the signer must hold
+    pub min_balance: u64,       // Smallest qualifying balance, in base units
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for AccessGate {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 32    // gate_mint: Pubkey
+        + 8     // min_balance: u64
+        + 1;    // bump: u8
+}
+
+#[event]
+pub struct AccessGateSet {
+    pub stream: Pubkey,
+    pub gate_mint: Pubkey,
+    pub min_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AccessGateRemoved {
+    pub stream: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+/// Amount a recipient may pull from a stream's vault on their own, without the host signing
+#[account]
+pub struct RecipientAllowance {
+    pub stream: Pubkey,         // Paying stream
+    pub recipient: Pubkey,      // Wallet allowed to withdraw
+    pub remaining: u64,         // Still withdrawable
+    pub withdrawn: u64,         // Withdrawn over the allowance's lifetime
+    pub updated_at: i64,        // Last time the host set the allowance
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for RecipientAllowance {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 32    // recipient: Pubkey
+        + 8     // remaining: u64
+        + 8     // withdrawn: u64
+        + 8     // updated_at: i64
+        + 1;    // bump: u8
+}
+
+impl RecipientAllowance {
+    pub fn spend(&mut self, amount: u64) -> Result<()> {
+        require!(amount <= self.remaining, StreamError::AllowanceExceeded);
+        self.remaining -= amount;
+        self.withdrawn = self.withdrawn.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[event]
+pub struct RecipientAllowanceSet {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowanceWithdrawn {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+/// Message the host signs off-chain; relayers submit it alongside an ed25519 verify instruction
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct HostApproval {
+    pub stream: Pubkey,
+    pub nonce: u64,        // Must equal the stream's next approval nonce
+    pub expires_at: i64,
+    pub action: HostAction,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum HostAction {
+    ApproveRefund {
+        donor: Pubkey,
+        amount: u64,
+    },
+    SetMetadataHash {
+        hash: [u8; 32],
+    },
+}
+
+#[event]
+pub struct HostApprovalExecuted {
+    pub stream: Pubkey,
+    pub relayer: Pubkey,
+    pub nonce: u64,
+    pub action: HostAction,
+    pub timestamp: i64,
+}
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+/// Minimum gap between counted check-ins, so the count tracks time present rather than spam
+pub const CHECK_IN_INTERVAL: i64 = 5 * 60;
+
+/// Proof that a viewer was present while a stream was live. Hosts can reward attendees from
+/// these records and other instructions can gate on them.
+#[account]
+pub struct ViewerAttendance {
+    pub stream: Pubkey,         // Attended stream
+    pub viewer: Pubkey,         // Checked-in wallet
+    pub first_seen: i64,        // First check-in
+    pub last_seen: i64,         // Latest counted check-in
+    pub check_in_count: u32,    // Counted check-ins, at most one per CHECK_IN_INTERVAL
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for ViewerAttendance {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 32    // viewer: Pubkey
+        + 8     // first_seen: i64
+        + 8     // last_seen: i64
+        + 4     // check_in_count: u32
+        + 1;    // bump: u8
+}
+
+impl ViewerAttendance {
+    /// Counts a check-in at `now`; the first one also fills in the record
+    pub fn check_in(&mut self, stream: Pubkey, viewer: Pubkey, now: i64, bump: u8) -> Result<()> {
+        if self.check_in_count == 0 {
+            self.stream = stream;
+            self.viewer = viewer;
+            self.first_seen = now;
+            self.bump = bump;
+        } else {
+            require!(
+                now >= self.last_seen.saturating_add(CHECK_IN_INTERVAL),
+                StreamError::CheckInTooSoon
+            );
+        }
+        self.last_seen = now;
+        self.check_in_count = self.check_in_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ViewerCheckedIn {
+    pub stream: Pubkey,
+    pub viewer: Pubkey,
+    pub check_in_count: u32,
+    pub timestamp: i64,
+}
@@ -1,10 +1,646 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
 
+use crate::math::{lmsr_price, FIXED_ONE};
+use crate::state::{MarketAccessMode, ResolutionSource, StreamError, BPS_DENOMINATOR};
+
+/// Market header: identity, resolution state and settlement accounting. Trading state lives in
+/// the market's `MarketBook` and rarely-read settings in its `MarketConfig`.
 #[account]
 pub struct BettingMarket {
     pub stream: Pubkey,
     pub host: Pubkey,
     pub mint: Pubkey,  // Store the mint (USDC) for this market
+    pub outcome_count: u8,
+    pub resolution_time: i64,
+    pub resolved: bool,
+    pub winning_outcome: Option<u8>,
+    pub randomness_requested: bool,
+    pub bump: u8,
+    pub ranked_outcomes: Vec<u8>, // Finishing order for weighted payout plans
+    pub taper: Option<BetTaper>,
+    pub settlement_hash: [u8; 32], // sha256 of the host's settlement statement, zero unless the host resolved
+    pub free_bet_withheld: u64, // Free bet stakes held back from winning claims, reclaimable by the host
+    pub dust_accumulator: u64, // Pool left over once every paying share is settled, sweepable by the host
+    pub unsettled_shares: u64, // Paying shares not yet claimed or closed
+    pub settled_gross: u64,    // Pre-fee value of the paying shares settled so far
+    pub amm_curve: AmmCurve,   // Pricing curve for buying and selling shares
+    pub version: u8,           // Layout version, see MARKET_VERSION
+    pub cancelled: bool,       // Called off before resolution; bettors reclaim their stakes
+    pub fees_collected: u64,   // Market fees deducted from settled payouts, awaiting collect_market_fees
+    pub lp_total_shares: u64,  // Outstanding liquidity provider shares
+    pub lp_pool: u64,          // Provider deposits plus their fee cut, less withdrawals
+    pub max_bet_per_user: Option<u64>, // Most one bettor may have staked at once
+    pub max_total_pool: Option<u64>,   // Largest the betting pool may grow
+    pub access_mode: MarketAccessMode, // Who may bet, see MarketAccessEntry
+    pub access_root: [u8; 32],         // Merkle root bettors prove allowlist membership against, zero for none
+    pub betting_closed_at: Option<i64>, // Host stopped betting early at this time
+    pub claim_deadline: i64,   // Last time winnings or refunds can be claimed; 0 until resolved
+}
+
+impl Space for BettingMarket {
+    const INIT_SPACE: usize = 8 // Discriminator
+        + 32 // stream
+        + 32 // host
+        + 32 // mint
+        + 1  // outcome_count
+        + 8  // resolution_time
+        + 1  // resolved
+        + 1 + 1 // winning_outcome
+        + 1  // randomness_requested
+        + 1  // bump
+        + 4 + MAX_PAYOUT_PLACES // ranked_outcomes
+        + 1 + BetTaper::SPACE // taper
+        + 32 // settlement_hash
+        + 8  // free_bet_withheld
+        + 8  // dust_accumulator
+        + 8  // unsettled_shares
+        + 8  // settled_gross
+        + AmmCurve::SPACE // amm_curve
+        + 1  // version
+        + 1  // cancelled
+        + 8  // fees_collected
+        + 8  // lp_total_shares
+        + 8  // lp_pool
+        + 1 + 8 // max_bet_per_user
+        + 1 + 8 // max_total_pool
+        + MarketAccessMode::SPACE // access_mode
+        + 32 // access_root
+        + 1 + 8 // betting_closed_at
+        + 8; // claim_deadline
+}
+
+/// Settings fixed at creation or changed only by the host before betting opens. Loaded by
+/// admin and resolution instructions, never by `place_bet`.
+#[account]
+pub struct MarketConfig {
+    pub market: Pubkey,
+    pub market_type: MarketType,
+    pub outcome_descriptions: Vec<String>,
+    pub fee_percentage: u16,
+    pub payout_plan: ResolutionPayoutPlan,
+    pub total_liquidity: u64,
+    pub rules_uri: String,    // Off-chain settlement rules, locked once betting opens
+    pub rules_hash: [u8; 32], // sha256 of the rules document
+    pub consensus_bps: u16,   // Share of validating stake an outcome needs to win
+    pub quorum_validators: u8, // Votes needed before consensus is checked, 0 to use quorum_bps
+    pub created_at: i64,
+    pub metric_source: Pubkey,         // Signer allowed to report an over/under metric, the host by default
+    pub reported_metric: Option<u64>,  // Value an over/under market was settled from
+    pub resolution_timeout: i64,       // Seconds after the resolution time before a stalled resolution can be forced, 0 for the default
+    pub resolution_source: ResolutionSource, // Who or what settles the market
+    pub bump: u8,
+    pub referral_share_bps: u16,       // Share of the host's fee on a claim paid to the bettor's referrer
+    pub quorum_bps: u16,               // Share of the selected validators who must vote before consensus is checked
+    pub supermajority_bps: u16,        // Share of dispute vote stake needed to overturn the consensus outcome
+}
+
+impl Space for MarketConfig {
+    const INIT_SPACE: usize = 8 // Discriminator
+        + 32    // market
+        + 1 + 8 // market_type
+        + 4 + MAX_MARKET_OUTCOMES * (4 + MAX_OUTCOME_DESCRIPTION_LEN) // outcome_descriptions
+        + 2     // fee_percentage
+        + 1 + 4 + 2 * MAX_PAYOUT_PLACES // payout_plan
+        + 8     // total_liquidity
+        + 4 + MAX_RULES_URI_LEN // rules_uri
+        + 32    // rules_hash
+        + 2     // consensus_bps
+        + 1     // quorum_validators
+        + 8     // created_at
+        + 32    // metric_source
+        + 1 + 8 // reported_metric
+        + 8     // resolution_timeout
+        + ResolutionSource::SPACE // resolution_source
+        + 1     // bump
+        + 2     // referral_share_bps
+        + 2     // quorum_bps
+        + 2;    // supermajority_bps
+}
+
+impl MarketConfig {
+    /// Votes needed from `validators` selected validators before consensus is checked. A fixed
+    /// `quorum_validators` count takes precedence over `quorum_bps`, capped at the validators
+    /// actually selected, since exposure limits or a thin registry can leave fewer than asked for.
+    pub fn required_votes(&self, validators: usize) -> usize {
+        match self.quorum_validators {
+            0 => (validators * self.quorum_bps as usize / BPS_DENOMINATOR as usize).max(1),
+            quorum => (quorum as usize).min(validators),
+        }
+    }
+
+    /// Stake the leading outcome needs out of `total_stake` to reach consensus
+    pub fn required_stake(&self, total_stake: u64) -> u64 {
+        (total_stake as u128 * self.consensus_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    /// When a resolution still short of consensus for a market resolving at `resolution_time`
+    /// may be forced
+    pub fn resolution_deadline(&self, resolution_time: i64) -> i64 {
+        let timeout = match self.resolution_timeout {
+            0 => DEFAULT_RESOLUTION_TIMEOUT,
+            timeout => timeout,
+        };
+        resolution_time.saturating_add(timeout)
+    }
+}
+
+/// Trading state written by every bet, kept zero-copy so the hot path skips Borsh entirely
+#[account(zero_copy)]
+pub struct MarketBook {
+    pub market: Pubkey,
+    pub total_pool: u64,
+    pub outcomes: [BookOutcome; MAX_MARKET_OUTCOMES],
+    pub outcome_count: u8,
+    pub bump: u8,
+    pub eligible_bettors: u16, // Positions staking at least VALIDATOR_STAKE_REQUIREMENT
+    pub counts_eligible: u8,   // 1 when eligible_bettors has been kept since the market opened
+    pub _padding: [u8; 3],
+}
+
+#[zero_copy]
+pub struct BookOutcome {
+    pub total_shares: u64,
+    pub liquidity_reserve: u64,
+    pub total_backing: u64,
+}
+
+impl MarketBook {
+    pub const SPACE: usize = 8 + std::mem::size_of::<MarketBook>();
+
+    pub fn outcome(&self, outcome_id: u8) -> &BookOutcome {
+        &self.outcomes[outcome_id as usize]
+    }
+
+    /// Current implied probability of `outcome_id`, scaled by `FIXED_ONE`. LMSR markets quote
+    /// their curve, parimutuel markets the outcome's share of the pool (even while it is empty),
+    /// and constant-product markets weigh each outcome by its backing plus reserve.
+    pub fn implied_probability(&self, curve: &AmmCurve, outcome_id: u8) -> Result<u128> {
+        match curve {
+            AmmCurve::Lmsr { b } => return lmsr_price(&self.share_quantities(), *b, outcome_id as usize),
+            AmmCurve::Parimutuel if self.total_pool == 0 => {
+                return Ok(FIXED_ONE / self.outcome_count as u128);
+            }
+            AmmCurve::Parimutuel => {
+                return Ok(self.outcome(outcome_id).total_backing as u128 * FIXED_ONE / self.total_pool as u128);
+            }
+            AmmCurve::ConstantProduct => {}
+        }
+        let weight = |o: &BookOutcome| o.total_backing as u128 + o.liquidity_reserve as u128;
+        let total: u128 = self.outcomes[..self.outcome_count as usize].iter().map(weight).sum();
+        require!(total > 0, StreamError::InvalidMarketSetup);
+        Ok(weight(self.outcome(outcome_id)) * FIXED_ONE / total)
+    }
+
+    /// Implied probability of every live outcome in basis points, in outcome id order
+    pub fn implied_probabilities_bps(&self, curve: &AmmCurve) -> Result<Vec<u16>> {
+        (0..self.outcome_count)
+            .map(|outcome_id| {
+                let probability = self.implied_probability(curve, outcome_id)?;
+                Ok((probability * BPS_DENOMINATOR as u128 / FIXED_ONE) as u16)
+            })
+            .collect()
+    }
+
+    /// Keeps `eligible_bettors` in step with a position whose stake moved from `was_eligible` to
+    /// `is_eligible` validator standing
+    pub fn track_eligibility(&mut self, was_eligible: bool, is_eligible: bool) -> Result<()> {
+        self.eligible_bettors = match (was_eligible, is_eligible) {
+            (false, true) => self.eligible_bettors.checked_add(1).ok_or(StreamError::MathOverflow)?,
+            (true, false) => self.eligible_bettors.saturating_sub(1),
+            _ => self.eligible_bettors,
+        };
+        Ok(())
+    }
+
+    /// Adds `shares` bought for `amount` to `outcome_id`, half of the stake deepening its reserve
+    pub fn record_purchase(&mut self, outcome_id: u8, shares: u64, amount: u64) -> Result<()> {
+        let outcome = &mut self.outcomes[outcome_id as usize];
+        outcome.total_shares = outcome
+            .total_shares
+            .checked_add(shares)
+            .ok_or(StreamError::MathOverflow)?;
+        outcome.total_backing = outcome
+            .total_backing
+            .checked_add(amount)
+            .ok_or(StreamError::MathOverflow)?;
+        // Half goes to liquidity for AMM stability
+        outcome.liquidity_reserve = outcome
+            .liquidity_reserve
+            .checked_add(amount / 2)
+            .ok_or(StreamError::MathOverflow)?;
+
+        self.total_pool = self
+            .total_pool
+            .checked_add(amount)
+            .ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Adds `amount` carried over from another market to the pool, spreading it evenly over the
+    /// outcome reserves so the extra depth prices every outcome alike. Parimutuel markets have no
+    /// reserves and only grow the pool.
+    pub fn credit_liquidity(&mut self, curve: &AmmCurve, amount: u64) -> Result<()> {
+        if *curve != AmmCurve::Parimutuel {
+            let per_outcome = amount / self.outcome_count as u64;
+            for outcome in self.outcomes.iter_mut().take(self.outcome_count as usize) {
+                outcome.liquidity_reserve = outcome
+                    .liquidity_reserve
+                    .checked_add(per_outcome)
+                    .ok_or(StreamError::MathOverflow)?;
+            }
+        }
+        self.total_pool = self.total_pool.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Outstanding shares of each live outcome, in outcome id order
+    pub fn share_quantities(&self) -> Vec<u64> {
+        self.outcomes[..self.outcome_count as usize]
+            .iter()
+            .map(|o| o.total_shares)
+            .collect()
+    }
+}
+
+pub const MAX_PAYOUT_PLACES: usize = 5;
+pub const MAX_RULES_URI_LEN: usize = 200;
+/// Running betting statistics for indexers, so leaderboards and analytics need not replay the
+/// event history. Nothing is counted before the account exists.
+#[account]
+pub struct MarketStats {
+    pub market: Pubkey,
+    pub bettor_count: u32,      // Wallets that opened a position
+    pub bet_count: u64,         // Bets placed, free bets and vouchers included
+    pub sell_count: u64,        // Sales back into the AMM, cash outs included
+    pub claim_count: u64,       // Winning claims paid
+    pub largest_bet: u64,       // Largest single stake
+    pub total_volume: u64,      // Stakes placed
+    pub sold_volume: u64,       // Paid out for shares sold back before resolution
+    pub claimed_volume: u64,    // Paid out in winning claims, before fees
+    pub outcome_volume: [u64; MAX_MARKET_OUTCOMES], // Stakes placed per outcome id
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Space for MarketStats {
+    const INIT_SPACE: usize = 8 // Discriminator
+        + 32    // market
+        + 4     // bettor_count
+        + 8     // bet_count
+        + 8     // sell_count
+        + 8     // claim_count
+        + 8     // largest_bet
+        + 8     // total_volume
+        + 8     // sold_volume
+        + 8     // claimed_volume
+        + 8 * MAX_MARKET_OUTCOMES // outcome_volume
+        + 8     // created_at
+        + 1;    // bump
+}
+
+impl MarketStats {
+    pub fn new(market: Pubkey, bump: u8) -> Result<Self> {
+        Ok(Self {
+            market,
+            bettor_count: 0,
+            bet_count: 0,
+            sell_count: 0,
+            claim_count: 0,
+            largest_bet: 0,
+            total_volume: 0,
+            sold_volume: 0,
+            claimed_volume: 0,
+            outcome_volume: [0; MAX_MARKET_OUTCOMES],
+            created_at: Clock::get()?.unix_timestamp,
+            bump,
+        })
+    }
+
+    /// Applies `update` to the stats at `stats`, which must be the market's stats PDA (enforced
+    /// by the caller's seeds constraint). Does nothing while the account is uninitialized.
+    fn update(stats: &AccountInfo, update: impl FnOnce(&mut MarketStats) -> Result<()>) -> Result<()> {
+        if stats.data_is_empty() {
+            return Ok(());
+        }
+        require_keys_eq!(*stats.owner, crate::ID, StreamError::Unauthorized);
+        let mut state = MarketStats::try_deserialize(&mut &stats.data.borrow()[..])?;
+        update(&mut state)?;
+        state.try_serialize(&mut &mut stats.data.borrow_mut()[..])?;
+        Ok(())
+    }
+
+    pub fn record_bet(stats: &AccountInfo, outcome_id: u8, amount: u64, new_bettor: bool) -> Result<()> {
+        Self::update(stats, |state| {
+            if new_bettor {
+                state.bettor_count = state.bettor_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+            }
+            state.bet_count = state.bet_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+            state.largest_bet = state.largest_bet.max(amount);
+            state.total_volume = state.total_volume.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+            let volume = &mut state.outcome_volume[outcome_id as usize];
+            *volume = volume.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+            Ok(())
+        })
+    }
+
+    pub fn record_sell(stats: &AccountInfo, amount: u64) -> Result<()> {
+        Self::update(stats, |state| {
+            state.sell_count = state.sell_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+            state.sold_volume = state.sold_volume.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+            Ok(())
+        })
+    }
+
+    pub fn record_claim(stats: &AccountInfo, amount: u64) -> Result<()> {
+        Self::update(stats, |state| {
+            state.claim_count = state.claim_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+            state.claimed_volume = state.claimed_volume.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+            Ok(())
+        })
+    }
+}
+
+pub const MAX_MARKET_OUTCOMES: usize = 10;
+pub const MAX_OUTCOME_DESCRIPTION_LEN: usize = 64;
+pub const DEFAULT_CONSENSUS_BPS: u16 = 6_667; // Two thirds
+pub const DEFAULT_QUORUM_BPS: u16 = 6_667; // Two thirds of the selected validators vote before consensus is checked
+pub const MIN_QUORUM_BPS: u16 = 5_000;
+pub const DEFAULT_SUPERMAJORITY_BPS: u16 = 5_000; // Overturning a consensus takes more dispute stake than upholding it
+pub const MAX_SUPERMAJORITY_BPS: u16 = 9_000; // Leaves upholders unable to block every dispute with a sliver of stake
+pub const MARKET_VERSION: u8 = 1; // Layout version written to new market headers
+pub const POSITION_VERSION: u8 = 2; // Layout version written to new bettor positions; 2 moved shares to per-outcome accounts
+pub const CLAIM_WINDOW: i64 = 90 * 24 * 60 * 60; // Time bettors have to claim once a market resolves or is cancelled
+pub const LP_FEE_SHARE_BPS: u64 = 5_000; // Cut of market fees paid to liquidity providers while any are staked
+pub const MAX_BET_REFERRAL_SHARE_BPS: u16 = 5_000; // Most of the host's fee on a claim a market may pay to referrers
+pub const DEFAULT_RESOLUTION_TIMEOUT: i64 = 7 * 24 * 60 * 60; // Wait for validators past the resolution time before forcing
+pub const RANDOMNESS_TIMEOUT: i64 = 24 * 60 * 60; // Wait for a forced tie-break before the market is cancelled
+pub const RANDOMNESS_REQUEST_TIMEOUT: i64 = 60 * 60; // Wait for a VRF callback before the request may be expired
+
+// Rounding policy: every amount credited to a user (shares, winnings, stake after a fee) rounds
+// down, and fees are whatever the rounded credit leaves. Rounding never takes more out of the
+// pool than it holds; the leftovers collect in `BettingMarket::dust_accumulator`.
+
+/// Part of `amount` a user keeps after a `fee_bps` fee, rounded down
+pub fn net_of_fee(amount: u64, fee_bps: u16) -> u64 {
+    let kept_bps = BPS_DENOMINATOR.saturating_sub(fee_bps as u64);
+    (amount as u128 * kept_bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+impl BettingMarket {
+    /// Whether bets and sells are accepted at `now`: before the resolution time and before any
+    /// early close by the host
+    pub fn betting_open(&self, now: i64) -> bool {
+        let before_close = match self.betting_closed_at {
+            Some(closed_at) => now < closed_at,
+            None => true,
+        };
+        now < self.resolution_time && before_close
+    }
+
+    /// Winning outcomes paired with their share of the pool in basis points
+    pub fn payout_weights(&self, config: &MarketConfig) -> Vec<(u8, u16)> {
+        match &config.payout_plan {
+            ResolutionPayoutPlan::Weighted { weights_bps } if !self.ranked_outcomes.is_empty() => self
+                .ranked_outcomes
+                .iter()
+                .copied()
+                .zip(weights_bps.iter().copied())
+                .collect(),
+            _ => self
+                .winning_outcome
+                .map(|winner| vec![(winner, 10_000)])
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Payout owed for the paying outcome positions in `held` not claimed yet; fails when
+    /// every one of them has been claimed or nothing is owed
+    pub fn claimable(&self, book: &MarketBook, config: &MarketConfig, held: &[OutcomePositionAccount]) -> Result<u64> {
+        require!(self.resolved, StreamError::MarketNotResolved);
+        self.winning_outcome.ok_or(StreamError::MarketNotResolved)?;
+        self.require_claim_window_open()?;
+        require!(
+            held.is_empty() || held.iter().any(|position| !position.claimed),
+            StreamError::AlreadyClaimed
+        );
+
+        let payout = self.winnings_for(book, config, held)?;
+        require!(payout > 0, StreamError::NoWinnings);
+        Ok(payout)
+    }
+
+    /// Claims the unclaimed positions in `held` for `bettor`, marking each claimed and adding
+    /// the payout to the bettor's returns. Claims are tracked per outcome, so a bettor whose
+    /// outcome starts paying after an earlier claim, such as when a dispute overturns the
+    /// result, can claim again. Each paying outcome's winnings are paid net of the free bet
+    /// credit staked on that outcome and not yet withheld, which stays in the vault for the host
+    /// to reclaim; credit lost on other outcomes takes nothing from them.
+    pub fn take_claim(
+        &mut self,
+        book: &MarketBook,
+        config: &MarketConfig,
+        bettor: &mut BettorPosition,
+        held: &mut [OutcomePositionAccount],
+    ) -> Result<u64> {
+        let payout = self.claimable(book, config, held)?;
+        let mut withheld = 0u64;
+        for position in held.iter_mut().filter(|position| !position.claimed && position.credit_staked > 0) {
+            let owed = self.winnings_for(book, config, std::slice::from_ref(position))?;
+            let credit = owed.min(position.credit_staked);
+            position.credit_staked -= credit;
+            withheld = withheld.checked_add(credit).ok_or(StreamError::MathOverflow)?;
+        }
+        self.settle_position(book, config, held)?;
+        for position in held.iter_mut() {
+            position.claimed = true;
+        }
+
+        bettor.credit_staked = bettor.credit_staked.saturating_sub(withheld);
+        self.free_bet_withheld = self
+            .free_bet_withheld
+            .checked_add(withheld)
+            .ok_or(StreamError::MathOverflow)?;
+
+        let paid = payout - withheld;
+        bettor.has_claimed = true;
+        bettor.total_returned = bettor.total_returned.checked_add(paid).ok_or(StreamError::MathOverflow)?;
+        Ok(paid)
+    }
+
+    /// Net payout owed for the outcome positions in `held`, after the market fee
+    pub fn winnings_for(&self, book: &MarketBook, config: &MarketConfig, held: &[OutcomePositionAccount]) -> Result<u64> {
+        let (_, net, _) = self.settlement_for(book, config, held)?;
+        Ok(net)
+    }
+
+    /// Value of the unclaimed paying shares in `held` as (before the market fee, after it, paying
+    /// shares). Each outcome's slice of the pool is split pro rata and rounded down per position.
+    pub fn settlement_for(
+        &self,
+        book: &MarketBook,
+        config: &MarketConfig,
+        held: &[OutcomePositionAccount],
+    ) -> Result<(u64, u64, u64)> {
+        let (mut gross, mut net, mut shares) = (0u64, 0u64, 0u64);
+        let payout_weights = self.payout_weights(config);
+
+        for position in held.iter().filter(|position| !position.claimed) {
+            let Some((outcome_id, weight_bps)) = payout_weights
+                .iter()
+                .find(|(id, _)| *id == position.outcome_id)
+                .copied()
+            else {
+                continue;
+            };
+
+            let winning_outcome_data = book.outcome(outcome_id);
+
+            if winning_outcome_data.total_shares > 0 {
+                // Calculate proportional share of this outcome's slice of the pool
+                let share_value = (book.total_pool as u128)
+                    .checked_mul(weight_bps as u128)
+                    .ok_or(StreamError::MathOverflow)?
+                    .checked_mul(position.shares as u128)
+                    .ok_or(StreamError::MathOverflow)?
+                    .checked_div(10_000u128 * winning_outcome_data.total_shares as u128)
+                    .ok_or(StreamError::MathOverflow)?
+                    as u64;
+
+                gross = gross.checked_add(share_value).ok_or(StreamError::MathOverflow)?;
+                net = net
+                    .checked_add(net_of_fee(share_value, config.fee_percentage))
+                    .ok_or(StreamError::MathOverflow)?;
+                shares = shares.checked_add(position.shares).ok_or(StreamError::MathOverflow)?;
+            }
+        }
+        Ok((gross, net, shares))
+    }
+
+    /// Marks the market resolved, opens the claim window and counts the paying shares still to
+    /// be settled
+    pub fn record_resolution(&mut self, book: &MarketBook, config: &MarketConfig) -> Result<()> {
+        self.resolved = true;
+        self.open_claim_window()?;
+        let mut unsettled = 0u64;
+        for (outcome_id, _) in self.payout_weights(config) {
+            unsettled = unsettled
+                .checked_add(book.outcome(outcome_id).total_shares)
+                .ok_or(StreamError::MathOverflow)?;
+        }
+        self.unsettled_shares = unsettled;
+        // With nobody to pay, the pool goes back to the bettors through claim_refund, not to dust
+        if self.no_winning_shares(book, config) {
+            return Ok(());
+        }
+        self.collect_dust(book.total_pool)
+    }
+
+    /// Whether the market resolved to outcomes nobody holds shares in, leaving no one to pay;
+    /// bettors then take back their stakes as if it had been cancelled
+    pub fn no_winning_shares(&self, book: &MarketBook, config: &MarketConfig) -> bool {
+        let paying = self.payout_weights(config);
+        self.resolved
+            && !self.cancelled
+            && !paying.is_empty()
+            && paying.iter().all(|(outcome_id, _)| book.outcome(*outcome_id).total_shares == 0)
+    }
+
+    /// Retires the paying shares in `held`, whether claimed or closed without winnings, and
+    /// accrues the market fee kept back from them
+    pub fn settle_position(&mut self, book: &MarketBook, config: &MarketConfig, held: &[OutcomePositionAccount]) -> Result<()> {
+        let (gross, net, shares) = self.settlement_for(book, config, held)?;
+        self.accrue_fee(gross - net)?;
+        self.unsettled_shares = self
+            .unsettled_shares
+            .checked_sub(shares)
+            .ok_or(StreamError::MathOverflow)?;
+        self.settled_gross = self
+            .settled_gross
+            .checked_add(gross)
+            .ok_or(StreamError::MathOverflow)?;
+        self.collect_dust(book.total_pool)
+    }
+
+    /// Starts the `CLAIM_WINDOW` after which `sweep_unclaimed` may empty the vault
+    pub fn open_claim_window(&mut self) -> Result<()> {
+        self.claim_deadline = Clock::get()?
+            .unix_timestamp
+            .checked_add(CLAIM_WINDOW)
+            .ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Fails once the claim window has closed
+    pub fn require_claim_window_open(&self) -> Result<()> {
+        require!(
+            Clock::get()?.unix_timestamp <= self.claim_deadline,
+            StreamError::ClaimWindowClosed
+        );
+        Ok(())
+    }
+
+    /// Splits a market fee between the liquidity providers, while any are staked, and the host
+    fn accrue_fee(&mut self, fee: u64) -> Result<()> {
+        let lp_cut = if self.lp_total_shares > 0 {
+            (fee as u128 * LP_FEE_SHARE_BPS as u128 / BPS_DENOMINATOR as u128) as u64
+        } else {
+            0
+        };
+        self.lp_pool = self.lp_pool.checked_add(lp_cut).ok_or(StreamError::MathOverflow)?;
+        self.fees_collected = self
+            .fees_collected
+            .checked_add(fee - lp_cut)
+            .ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Provider shares minted for depositing `amount`, at the current value of the LP pool
+    pub fn lp_shares_for_deposit(&self, amount: u64) -> Result<u64> {
+        if self.lp_total_shares == 0 || self.lp_pool == 0 {
+            return Ok(amount);
+        }
+        let shares = (amount as u128)
+            .checked_mul(self.lp_total_shares as u128)
+            .ok_or(StreamError::MathOverflow)?
+            / self.lp_pool as u128;
+        u64::try_from(shares).map_err(|_| StreamError::MathOverflow.into())
+    }
+
+    /// Tokens `lp_shares` redeem for: their pro rata slice of the LP pool, rounded down
+    pub fn lp_redemption(&self, lp_shares: u64) -> Result<u64> {
+        require!(
+            lp_shares > 0 && lp_shares <= self.lp_total_shares,
+            StreamError::InsufficientLpShares
+        );
+        Ok((self.lp_pool as u128 * lp_shares as u128 / self.lp_total_shares as u128) as u64)
+    }
+
+    /// Once no paying shares remain, whatever the settled positions did not take (rounding
+    /// remainders and slices nobody holds shares in) moves to the dust accumulator
+    fn collect_dust(&mut self, total_pool: u64) -> Result<()> {
+        if self.unsettled_shares > 0 {
+            return Ok(());
+        }
+        let dust = total_pool
+            .checked_sub(self.settled_gross)
+            .ok_or(StreamError::MathOverflow)?;
+        self.dust_accumulator = self
+            .dust_accumulator
+            .checked_add(dust)
+            .ok_or(StreamError::MathOverflow)?;
+        self.settled_gross = total_pool;
+        Ok(())
+    }
+}
+
+/// Layout of `BettingMarket` before its trading state and settings moved to `MarketBook` and
+/// `MarketConfig`; only read by `migrate_betting_market`
+#[derive(AnchorDeserialize)]
+pub struct LegacyBettingMarket {
+    pub stream: Pubkey,
+    pub host: Pubkey,
+    pub mint: Pubkey,
     pub market_type: MarketType,
     pub outcomes: Vec<MarketOutcome>,
     pub total_pool: u64,
@@ -16,67 +652,363 @@ pub struct BettingMarket {
     pub fee_percentage: u16,
     pub created_at: i64,
     pub bump: u8,
+    pub payout_plan: ResolutionPayoutPlan,
+    pub ranked_outcomes: Vec<u8>,
+    pub taper: Option<BetTaper>,
+    pub rules_uri: String,
+    pub rules_hash: [u8; 32],
+    pub consensus_bps: u16,
+    pub quorum_validators: u8,
+    pub settlement_hash: [u8; 32],
+    pub free_bet_withheld: u64,
+    pub dust_accumulator: u64,
+    pub unsettled_shares: u64,
+    pub settled_gross: u64,
 }
 
-#[account]
+/// Validator selection and vote tallies. Zero-copy with fixed-capacity arrays so loading it costs
+/// the same however many validators have voted; only the first `*_count` slots are meaningful.
+#[account(zero_copy)]
 pub struct MarketResolution {
     pub market: Pubkey,
-    pub proposed_outcome: Option<u8>,
-    pub validators: Vec<Pubkey>,
-    pub validator_votes: Vec<ValidatorVote>,
+    pub validators: [Pubkey; MAX_RESOLUTION_VALIDATORS],
+    pub outcome_stakes: [u64; MAX_MARKET_OUTCOMES], // Running stake tally per outcome id
+    pub eligible_validators: [ValidatorStake; MAX_ELIGIBLE_VALIDATORS],
+    pub eligible_exposure: [u64; MAX_ELIGIBLE_VALIDATORS], // Each eligible validator's stake in the market when listed
     pub dispute_end_time: i64,
-    pub resolution_status: ResolutionStatus,
-    pub randomness_seed: [u8; 32],
-    pub randomness_use_case: RandomnessUseCase,
     pub total_stake_validating: u64,
-    pub eligible_validators: Vec<EligibleValidator>,
+    pub validator_reward_pool: u64,  // Set aside from the betting pool when the consensus resolves the market
+    pub validator_rewards_paid: u64, // Paid so far to validators who voted for the winning outcome
+    pub forced_at: i64,              // When a timed-out resolution fell back to randomness, 0 if it has not
+    pub randomness_nonce: u64,       // Number of VRF requests made for the market
+    pub randomness_requested_at: i64, // When the latest VRF request was made
+    pub randomness_seed: [u8; 32],
+    pub votes_cast: u32,
+    pub validator_count: u8,
+    pub eligible_count: u8,
+    pub outcome_count: u8,
+    pub proposed_outcome: u8,      // NO_PROPOSED_OUTCOME until consensus is reached
+    pub resolution_status: u8,     // ResolutionStatus discriminant
+    pub randomness_use_case: u8,   // RandomnessUseCase discriminant
+    pub randomness_status: u8,     // RandomnessRequestStatus discriminant of the latest request
     pub bump: u8,
+    pub tied_outcomes: u16,        // Bitmask of the outcomes a tie-break picks between
+    pub eligible_from_registry: u8, // 1 when the eligible validators were listed from the validator registry
+    pub _padding: [u8; 1],
+}
+
+#[zero_copy]
+pub struct ValidatorStake {
+    pub pubkey: Pubkey,
+    pub stake: u64,
+}
+
+pub const MAX_RESOLUTION_VALIDATORS: usize = 20;
+pub const MAX_ELIGIBLE_VALIDATORS: usize = 100;
+pub const MAX_VALIDATOR_EXPOSURE_BPS: u64 = 500; // Share of the pool a validator may hold in the market and still be drawn
+pub const NO_PROPOSED_OUTCOME: u8 = u8::MAX;
+
+impl MarketResolution {
+    pub const SPACE: usize = 8 + std::mem::size_of::<MarketResolution>();
+
+    pub fn validators(&self) -> &[Pubkey] {
+        &self.validators[..self.validator_count as usize]
+    }
+
+    pub fn outcome_stakes(&self) -> &[u64] {
+        &self.outcome_stakes[..self.outcome_count as usize]
+    }
+
+    pub fn eligible_validators(&self) -> &[ValidatorStake] {
+        &self.eligible_validators[..self.eligible_count as usize]
+    }
+
+    /// Replaces the eligible validator list and each one's stake in the market, rejecting lists
+    /// that do not fit
+    pub fn set_eligible_validators(
+        &mut self,
+        validators: &[ValidatorStake],
+        exposure: &[u64],
+        from_registry: bool,
+    ) -> Result<()> {
+        require!(
+            validators.len() <= MAX_ELIGIBLE_VALIDATORS && exposure.len() == validators.len(),
+            StreamError::TooManyValidators
+        );
+        self.eligible_validators[..validators.len()].copy_from_slice(validators);
+        self.eligible_exposure[..exposure.len()].copy_from_slice(exposure);
+        self.eligible_count = validators.len() as u8;
+        self.eligible_from_registry = from_registry as u8;
+        Ok(())
+    }
+
+    /// Draws up to `count` validators from the eligible list, weighted by stake and without
+    /// replacement, and returns how many were drawn. Validators whose stake in the market
+    /// exceeds `max_exposure` stand to gain from the outcome and are left out of the draw.
+    pub fn select_validators(&mut self, randomness: &[u8; 32], count: usize, max_exposure: u64) -> usize {
+        let mut candidates: Vec<ValidatorStake> = self
+            .eligible_validators()
+            .iter()
+            .zip(&self.eligible_exposure)
+            .filter(|(validator, exposure)| validator.stake > 0 && **exposure <= max_exposure)
+            .map(|(validator, _)| *validator)
+            .collect();
+        let picks = count.min(candidates.len()).min(MAX_RESOLUTION_VALIDATORS);
+
+        for pick in 0..picks {
+            let total_stake: u128 = candidates.iter().map(|candidate| candidate.stake as u128).sum();
+            let hash = hashv(&[randomness, &[pick as u8]]).to_bytes();
+            let mut point = u128::from(u64::from_le_bytes(hash[..8].try_into().unwrap())) % total_stake;
+            let index = candidates
+                .iter()
+                .position(|candidate| {
+                    let hit = point < candidate.stake as u128;
+                    point = point.saturating_sub(candidate.stake as u128);
+                    hit
+                })
+                .unwrap_or(0);
+            self.validators[pick] = candidates.swap_remove(index).pubkey;
+        }
+        self.validator_count = picks as u8;
+        picks
+    }
+
+    /// Stake `validator` was listed with when validators were selected
+    pub fn eligible_stake(&self, validator: &Pubkey) -> Option<u64> {
+        self.eligible_validators()
+            .iter()
+            .find(|eligible| eligible.pubkey == *validator)
+            .map(|eligible| eligible.stake)
+    }
+
+    pub fn proposed_outcome(&self) -> Option<u8> {
+        (self.proposed_outcome != NO_PROPOSED_OUTCOME).then_some(self.proposed_outcome)
+    }
+
+    /// Reward owed to a validator who put `stake` behind the winning outcome, pro rata to the
+    /// stake the selected validators voted for it with
+    pub fn validator_reward(&self, outcome_id: u8, stake: u64) -> Result<u64> {
+        let winning_stake = self.outcome_stakes().get(outcome_id as usize).copied().unwrap_or(0);
+        if winning_stake == 0 {
+            return Ok(0);
+        }
+        Ok((self.validator_reward_pool as u128 * stake as u128 / winning_stake as u128) as u64)
+    }
+
+    pub fn status_is(&self, status: ResolutionStatus) -> bool {
+        self.resolution_status == status as u8
+    }
+
+    pub fn set_status(&mut self, status: ResolutionStatus) {
+        self.resolution_status = status as u8;
+    }
+
+    /// Bitmask of the outcomes sharing the highest vote stake, zero while no outcome has stake
+    pub fn top_stake_outcomes(&self) -> u16 {
+        let max_stake = self.outcome_stakes().iter().copied().max().unwrap_or(0);
+        if max_stake == 0 {
+            return 0;
+        }
+        self.outcome_stakes()
+            .iter()
+            .enumerate()
+            .filter(|(_, stake)| **stake == max_stake)
+            .fold(0, |mask, (outcome, _)| mask | 1 << outcome)
+    }
+
+    /// Outcome at `index` among those set in `tied_outcomes`
+    pub fn tied_outcome(&self, index: u8) -> Option<u8> {
+        (0..self.outcome_count)
+            .filter(|outcome| self.tied_outcomes & (1 << outcome) != 0)
+            .nth(index as usize)
+    }
+
+    /// Validators are drawn once per resolution. A new resolution qualifies, as does one still
+    /// awaiting a first draw because its request expired or the draw found nobody to select.
+    pub fn require_validator_selection_open(&self) -> Result<()> {
+        require!(
+            self.status_is(ResolutionStatus::AwaitingRandomness) && self.validator_count == 0,
+            StreamError::ValidatorsAlreadySelected
+        );
+        Ok(())
+    }
+
+    pub fn randomness_status_is(&self, status: RandomnessRequestStatus) -> bool {
+        self.randomness_status == status as u8
+    }
+
+    /// Records a new VRF request for `use_case` and returns its nonce, refusing while the
+    /// previous request is still in flight
+    pub fn begin_randomness_request(&mut self, use_case: RandomnessUseCase, now: i64) -> Result<u64> {
+        require!(
+            !self.randomness_status_is(RandomnessRequestStatus::Pending),
+            StreamError::RandomnessRequestPending
+        );
+        self.randomness_nonce = self.randomness_nonce.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        self.randomness_requested_at = now;
+        self.randomness_status = RandomnessRequestStatus::Pending as u8;
+        self.randomness_use_case = use_case as u8;
+        Ok(self.randomness_nonce)
+    }
+
+    pub fn use_case(&self) -> Option<RandomnessUseCase> {
+        match self.randomness_use_case {
+            0 => Some(RandomnessUseCase::ValidatorSelection),
+            1 => Some(RandomnessUseCase::TieBreaker),
+            2 => Some(RandomnessUseCase::DisputeResolution),
+            3 => Some(RandomnessUseCase::FairDistribution),
+            _ => None,
+        }
+    }
 }
 
 #[account]
 pub struct BettorPosition {
     pub bettor: Pubkey,
     pub market: Pubkey,
-    pub positions: Vec<OutcomePosition>,
+    pub open_outcomes: u8, // Outcome position accounts holding shares
     pub total_invested: u64,
-    pub total_returned: u64,
-    pub has_claimed: bool,
+    pub total_returned: u64, // Sum of every claim on the position
+    pub has_claimed: bool, // Set by the first claim; outcome positions track what each claim paid
     pub is_eligible_validator: bool,
     pub created_at: i64,
     pub bump: u8,
+    pub credit_staked: u64, // Part of total_invested paid with free bet credit and not yet withheld from a claim
+    pub version: u8, // Layout version, see POSITION_VERSION
+    pub referrer: Option<Pubkey>, // Affiliate who brought the bettor, paid a share of the fee on their claims
 }
 
-// ============= TYPES =============
+impl Space for BettorPosition {
+    const INIT_SPACE: usize = 8 // Discriminator
+        + 32 // bettor
+        + 32 // market
+        + 1  // open_outcomes
+        + 8  // total_invested
+        + 8  // total_returned
+        + 1  // has_claimed
+        + 1  // is_eligible_validator
+        + 8  // created_at
+        + 1  // bump
+        + 8  // credit_staked
+        + 1  // version
+        + 1 + 32; // referrer
+}
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct MarketOutcome {
-    pub id: u8,
-    pub description: String,
-    pub total_shares: u64,
-    pub liquidity_reserve: u64,
-    pub total_backing: u64,
+/// A bettor's shares in one outcome of a market, one account per (market, bettor, outcome) so
+/// a position never outgrows its account however many outcomes it spreads across
+#[account]
+pub struct OutcomePositionAccount {
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub outcome_id: u8,
+    pub shares: u64,
+    pub avg_entry_price: u64,
+    pub invested: u64,
+    pub claimed: bool, // Paid out by a claim; a later claim skips it
+    pub bump: u8,
+    pub credit_staked: u64, // Part of invested paid with free bet credit and not yet withheld from a claim
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct ValidatorVote {
+impl Space for OutcomePositionAccount {
+    const INIT_SPACE: usize = 8 // Discriminator
+        + 32 // market
+        + 32 // bettor
+        + 1  // outcome_id
+        + 8  // shares
+        + 8  // avg_entry_price
+        + 8  // invested
+        + 1  // claimed
+        + 1  // bump
+        + 8; // credit_staked
+}
+
+/// A liquidity provider's stake in one market
+#[account]
+pub struct LpPosition {
+    pub market: Pubkey,
+    pub provider: Pubkey,
+    pub lp_shares: u64,
+    pub deposited: u64, // Cost basis of the shares still held
+    pub bump: u8,
+}
+
+impl Space for LpPosition {
+    const INIT_SPACE: usize = 8 // Discriminator
+        + 32 // market
+        + 32 // provider
+        + 8  // lp_shares
+        + 8  // deposited
+        + 1; // bump
+}
+
+#[account]
+pub struct VoteRecord {
+    pub resolution: Pubkey,
     pub validator: Pubkey,
     pub voted_outcome: u8,
     pub vote_timestamp: i64,
     pub stake_amount: u64,
+    pub bump: u8,
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct EligibleValidator {
-    pub pubkey: Pubkey,
-    pub stake: u64,
+impl Space for VoteRecord {
+    const INIT_SPACE: usize = 8 // Discriminator
+        + 32 // resolution
+        + 32 // validator
+        + 1  // voted_outcome
+        + 8  // vote_timestamp
+        + 8  // stake_amount
+        + 1; // bump
+}
+
+#[account]
+pub struct OptimisticProposal {
+    pub market: Pubkey,
+    pub proposer: Pubkey,
+    pub proposed_outcome: u8,
+    pub bond_amount: u64,
+    pub proposed_at: i64,
+    pub challenge_deadline: i64,
+    pub challenger: Option<Pubkey>,
+    pub status: ProposalStatus,
+    pub bump: u8,
 }
 
+impl Space for OptimisticProposal {
+    const INIT_SPACE: usize = 8 // Discriminator
+        + 32     // market
+        + 32     // proposer
+        + 1      // proposed_outcome
+        + 8      // bond_amount
+        + 8      // proposed_at
+        + 8      // challenge_deadline
+        + 1 + 32 // challenger
+        + 1      // status
+        + 1;     // bump
+}
+
+// ============= TYPES =============
+
+/// Per-outcome state in the legacy market layout
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct OutcomePosition {
+pub struct MarketOutcome {
+    pub id: u8,
+    pub description: String,
+    pub total_shares: u64,
+    pub liquidity_reserve: u64,
+    pub total_backing: u64,
+}
+
+/// What a bet would buy at the current odds, returned by `get_quote`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct BetQuote {
     pub outcome_id: u8,
-    pub shares: u64,
-    pub avg_entry_price: u64,
-    pub invested: u64,
+    pub amount: u64,                // Stake quoted
+    pub shares: u64,                // Shares the stake buys
+    pub average_price: u64,         // Stake per share, scaled by 1_000_000 like avg_entry_price
+    pub extra_fee_bps: u16,         // Late-bet taper fee kept by the pool, 0 outside the taper window
+    pub probability_bps: u16,       // Implied probability of the outcome before the bet
+    pub probability_after_bps: u16, // Implied probability of the outcome once the bet is placed
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
@@ -86,6 +1018,76 @@ pub enum MarketType {
     OverUnder { line: u64 },
 }
 
+/// Linearly shrinks the maximum bet and raises the fee during the final window before resolution
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct BetTaper {
+    pub window_seconds: i64,
+    pub max_bet_at_window_start: u64,
+    pub max_bet_at_close: u64,
+    pub max_extra_fee_bps: u16,
+}
+
+impl BetTaper {
+    pub const SPACE: usize = 8 + 8 + 8 + 2;
+
+    /// Maximum bet size and extra fee in effect `seconds_left` before resolution,
+    /// or `None` outside the taper window
+    pub fn limits_at(&self, seconds_left: i64) -> Option<(u64, u16)> {
+        if seconds_left >= self.window_seconds {
+            return None;
+        }
+        let elapsed = (self.window_seconds - seconds_left.max(0)) as u128;
+        let window = self.window_seconds as u128;
+
+        let shrink = (self.max_bet_at_window_start - self.max_bet_at_close) as u128;
+        let max_bet = self.max_bet_at_window_start - (shrink * elapsed / window) as u64;
+        let extra_fee_bps = (self.max_extra_fee_bps as u128 * elapsed / window) as u16;
+        Some((max_bet, extra_fee_bps))
+    }
+}
+
+/// How the market prices shares. Settlement is parimutuel either way; the curve only decides
+/// how many shares a stake buys and what they sell back for.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Default)]
+pub enum AmmCurve {
+    /// Independent constant-product reserve per outcome
+    #[default]
+    ConstantProduct,
+    /// Logarithmic market scoring rule with liquidity parameter `b`, in base units. Implied
+    /// probabilities always sum to one; a larger `b` moves prices less per bet.
+    Lmsr { b: u64 },
+    /// Pool betting with no pricing: each base unit staked is one share, so the winners split
+    /// the pool by stake. Needs no seeded liquidity, and stakes cannot be sold back.
+    Parimutuel,
+}
+
+impl AmmCurve {
+    pub const SPACE: usize = 1 + 8;
+}
+
+/// Validator consensus settings chosen at market creation
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ConsensusParams {
+    pub consensus_bps: u16,
+    pub quorum_validators: u8,
+    pub quorum_bps: u16,
+    pub supermajority_bps: u16,
+}
+
+/// Settlement rules document and its integrity hash
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct MarketRules {
+    pub uri: String,
+    pub hash: [u8; 32],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub enum ResolutionPayoutPlan {
+    WinnerTakesAll,
+    /// Pool split across finishing places, e.g. [6000, 3000, 1000] for a podium
+    Weighted { weights_bps: Vec<u16> },
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum ResolutionStatus {
     AwaitingRandomness,
@@ -96,6 +1098,14 @@ pub enum ResolutionStatus {
     ForcedByRandomness,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ProposalStatus {
+    Proposed,
+    Challenged,
+    Finalized,
+    Settled,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
 pub enum RandomnessUseCase {
     ValidatorSelection,
@@ -104,21 +1114,98 @@ pub enum RandomnessUseCase {
     FairDistribution,
 }
 
+/// Lifecycle of a market's latest VRF request
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RandomnessRequestStatus {
+    None, // No request made yet
+    Pending,
+    Fulfilled,
+    Expired, // The callback never arrived; a new request may be made
+}
+
 // ============= EVENTS =============
 
 #[event]
 pub struct RandomnessRequested {
     pub market: Pubkey,
+    pub nonce: u64,
     pub use_case: RandomnessUseCase,
     pub requestor: Pubkey,
     pub timestamp: i64,
 }
 
+#[event]
+pub struct RandomnessRequestExpired {
+    pub market: Pubkey,
+    pub nonce: u64,
+    pub requested_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OddsUpdated {
+    pub market: Pubkey,
+    pub probabilities_bps: Vec<u16>, // Implied probability of each outcome, in outcome id order
+    pub total_pool: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct ValidatorsSelected {
     pub market: Pubkey,
     pub validators: Vec<Pubkey>,
     pub total_validators: u8,
+    pub max_exposure: u64,  // Stake in the market above which an eligible validator was left out
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidityAdded {
+    pub market: Pubkey,
+    pub provider: Pubkey,
+    pub amount: u64,
+    pub lp_shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub market: Pubkey,
+    pub provider: Pubkey,
+    pub lp_shares: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketFeesCollected {
+    pub market: Pubkey,
+    pub host_amount: u64,
+    pub platform_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketDustSwept {
+    pub market: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnclaimedSwept {
+    pub market: Pubkey,
+    pub amount: u64,
+    pub platform_fee: u64,
+    pub rolled_into: Option<Pubkey>, // Market whose pool took the funds, if not the host
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolRolledOver {
+    pub market: Pubkey,
+    pub target_market: Pubkey,
+    pub amount: u64,
     pub timestamp: i64,
 }
 
@@ -128,6 +1215,85 @@ pub struct MarketResolved {
     pub winning_outcome: u8,
     pub total_pool: u64,
     pub used_randomness: bool,
+    pub settlement_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketCancelled {
+    pub market: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StakeRefunded {
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub amount: u64,
+    pub credit_withheld: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketResolvedRanked {
+    pub market: Pubkey,
+    pub ranked_outcomes: Vec<u8>,
+    pub weights_bps: Vec<u16>,
+    pub total_pool: u64,
+    pub settlement_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResolutionTimeoutSet {
+    pub market: Pubkey,
+    pub resolution_timeout: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetReferralShareSet {
+    pub market: Pubkey,
+    pub referral_share_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResolutionForced {
+    pub market: Pubkey,
+    pub caller: Pubkey,
+    pub randomness_deadline: i64, // The market is cancelled if randomness has not resolved it by then
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResolutionTimeUpdated {
+    pub market: Pubkey,
+    pub previous_time: i64,
+    pub new_time: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BettingClosedEarly {
+    pub market: Pubkey,
+    pub closed_at: i64,
+    pub resolution_time: i64,
+}
+
+#[event]
+pub struct MarketLimitsSet {
+    pub market: Pubkey,
+    pub max_bet_per_user: Option<u64>,
+    pub max_total_pool: Option<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketTaperSet {
+    pub market: Pubkey,
+    pub taper: Option<BetTaper>,
     pub timestamp: i64,
 }
 
@@ -141,6 +1307,26 @@ pub struct BetPlaced {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct BetGifted {
+    pub market: Pubkey,
+    pub payer: Pubkey,       // Wallet that paid the stake
+    pub beneficiary: Pubkey, // Wallet that owns the position and may claim it
+    pub outcome_id: u8,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SharesSold {
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub outcome_id: u8,
+    pub shares: u64,
+    pub usdc_out: u64,
+    pub timestamp: i64,
+}
+
 #[event]
 pub struct WinningsClaimed {
     pub market: Pubkey,
@@ -155,6 +1341,45 @@ pub struct MarketCreated {
     pub stream: Pubkey,
     pub market_type: MarketType,
     pub outcomes: Vec<String>,
+    pub rules_uri: String,
+    pub rules_hash: [u8; 32],
+    pub consensus_bps: u16,
+    pub quorum_validators: u8,
+    pub quorum_bps: u16,
+    pub supermajority_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketMigrated {
+    pub market: Pubkey,
+    pub market_book: Pubkey,
+    pub market_config: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketOutcomesAdded {
+    pub market: Pubkey,
+    pub outcomes: Vec<String>, // Descriptions of the outcomes added, in their new id order
+    pub outcome_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketOutcomeRemoved {
+    pub market: Pubkey,
+    pub outcome_id: u8,        // Id the outcome had; later outcomes moved down one
+    pub description: String,
+    pub outcome_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketRulesSet {
+    pub market: Pubkey,
+    pub rules_uri: String,
+    pub rules_hash: [u8; 32],
     pub timestamp: i64,
 }
 
@@ -166,3 +1391,47 @@ pub struct ValidationVote {
     pub stake_weight: u64,
     pub timestamp: i64,
 }
+
+#[event]
+pub struct ValidatorRewardPaid {
+    pub market: Pubkey,
+    pub validator: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OutcomeProposed {
+    pub market: Pubkey,
+    pub proposer: Pubkey,
+    pub proposed_outcome: u8,
+    pub bond_amount: u64,
+    pub challenge_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalChallenged {
+    pub market: Pubkey,
+    pub challenger: Pubkey,
+    pub proposed_outcome: u8,
+    pub bond_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ProposalFinalized {
+    pub market: Pubkey,
+    pub proposer: Pubkey,
+    pub winning_outcome: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ChallengeSettled {
+    pub market: Pubkey,
+    pub winner: Pubkey,
+    pub proposal_upheld: bool,
+    pub payout: u64,
+    pub timestamp: i64,
+}
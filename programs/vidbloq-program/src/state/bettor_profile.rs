@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{StreamError, BPS_DENOMINATOR};
+
+/// A bettor's lifetime prediction record across every market
+#[account]
+pub struct BettorProfile {
+    pub bettor: Pubkey,               // Bettor wallet
+    pub markets_participated: u32,    // Resolved markets settled into the profile
+    pub markets_won: u32,             // Of those, markets that paid out
+    pub total_invested: u64,          // Stake across settled markets
+    pub total_returned: u64,          // Payouts across settled markets
+    pub win_rate_bps: u16,            // markets_won / markets_participated
+    pub roi_bps: i64,                 // (returned - invested) / invested
+    pub bump: u8,                     // PDA bump
+}
+
+impl Space for BettorProfile {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // bettor: Pubkey
+        + 4     // markets_participated: u32
+        + 4     // markets_won: u32
+        + 8     // total_invested: u64
+        + 8     // total_returned: u64
+        + 2     // win_rate_bps: u16
+        + 8     // roi_bps: i64
+        + 1;    // bump: u8
+}
+
+impl BettorProfile {
+    /// Settles a claim into the profile. A position's first claim settles the market; later
+    /// claims on it only add their payout.
+    pub fn record_claim(
+        &mut self,
+        bettor: Pubkey,
+        market: Pubkey,
+        invested: u64,
+        returned: u64,
+        first_claim: bool,
+        bump: u8,
+    ) -> Result<()> {
+        if first_claim {
+            return self.record_market(bettor, market, invested, returned, bump);
+        }
+        self.total_returned = self.total_returned.checked_add(returned).ok_or(StreamError::MathOverflow)?;
+        self.refresh_stats(market)
+    }
+
+    /// Settles one resolved market into the profile and emits `BettorStatsUpdated`
+    pub fn record_market(&mut self, bettor: Pubkey, market: Pubkey, invested: u64, returned: u64, bump: u8) -> Result<()> {
+        if self.bettor == Pubkey::default() {
+            self.bettor = bettor;
+            self.bump = bump;
+        }
+        self.markets_participated = self.markets_participated.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        if returned > 0 {
+            self.markets_won = self.markets_won.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        }
+        self.total_invested = self.total_invested.checked_add(invested).ok_or(StreamError::MathOverflow)?;
+        self.total_returned = self.total_returned.checked_add(returned).ok_or(StreamError::MathOverflow)?;
+        self.refresh_stats(market)
+    }
+
+    /// Recomputes the derived rates and emits `BettorStatsUpdated`
+    fn refresh_stats(&mut self, market: Pubkey) -> Result<()> {
+        self.win_rate_bps = (self.markets_won as u64 * BPS_DENOMINATOR / self.markets_participated as u64) as u16;
+        self.roi_bps = if self.total_invested > 0 {
+            let net = self.total_returned as i128 - self.total_invested as i128;
+            i64::try_from(net * BPS_DENOMINATOR as i128 / self.total_invested as i128)
+                .map_err(|_| StreamError::MathOverflow)?
+        } else {
+            0
+        };
+
+        emit!(BettorStatsUpdated {
+            bettor: self.bettor,
+            market,
+            markets_participated: self.markets_participated,
+            markets_won: self.markets_won,
+            total_invested: self.total_invested,
+            total_returned: self.total_returned,
+            win_rate_bps: self.win_rate_bps,
+            roi_bps: self.roi_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+#[event]
+pub struct BettorStatsUpdated {
+    pub bettor: Pubkey,
+    pub market: Pubkey,
+    pub markets_participated: u32,
+    pub markets_won: u32,
+    pub total_invested: u64,
+    pub total_returned: u64,
+    pub win_rate_bps: u16,
+    pub roi_bps: i64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+pub const BLOCKLIST_PAGES: u8 = 16;
+pub const BLOCKLIST_PAGE_CAPACITY: usize = 64;
+
+/// One page of a stream's blocklist; wallets are bucketed into pages by their first key byte
+#[account]
+pub struct Blocklist {
+    pub stream: Pubkey,         // Parent stream
+    pub page: u8,               // Bucket index
+    pub wallets: Vec<Pubkey>,   // Blocked wallets in this bucket
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for Blocklist {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 1     // page: u8
+        + 4 + 32 * BLOCKLIST_PAGE_CAPACITY // wallets: Vec<Pubkey>
+        + 1;    // bump: u8
+}
+
+impl Blocklist {
+    pub fn page_for(wallet: &Pubkey) -> u8 {
+        wallet.to_bytes()[0] % BLOCKLIST_PAGES
+    }
+
+    /// Fails when `wallet` is listed on `page`. The page account must be the PDA for the
+    /// wallet's bucket (enforced by the caller's seeds constraint); an uninitialized page
+    /// means nobody in that bucket is blocked.
+    pub fn require_not_blocked(
+        blocked_count: u32,
+        page: Option<&AccountInfo>,
+        wallet: &Pubkey,
+    ) -> Result<()> {
+        if blocked_count == 0 {
+            return Ok(());
+        }
+        let page = page.ok_or(StreamError::MissingAccounts)?;
+        if page.data_is_empty() {
+            return Ok(());
+        }
+        require_keys_eq!(*page.owner, crate::ID, StreamError::Unauthorized);
+        let blocklist = Blocklist::try_deserialize(&mut &page.data.borrow()[..])?;
+        require!(!blocklist.wallets.contains(wallet), StreamError::WalletBlocked);
+        Ok(())
+    }
+}
+
+#[event]
+pub struct WalletBlocked {
+    pub stream: Pubkey,
+    pub wallet: Pubkey,
+    pub page: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WalletUnblocked {
+    pub stream: Pubkey,
+    pub wallet: Pubkey,
+    pub page: u8,
+    pub timestamp: i64,
+}
@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+pub const MAX_BONUS_WINNERS: u8 = 16;
+pub const BONUS_CLAIM_WINDOW: i64 = 30 * 24 * 60 * 60; // Time winners have to claim before the host may reclaim the rest
+
+/// Where a bonus pool is in its life
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BonusPoolStatus {
+    Open,      // Donors may enter until `entry_deadline`
+    Drawing,   // Waiting on the VRF callback
+    Drawn,     // Winning points known; winners may claim
+    Reclaimed, // What was left went back to the host
+}
+
+impl BonusPoolStatus {
+    pub const SPACE: usize = 1;
+}
+
+/// A pot the host splits into `winner_count` equal picks. Each entered donor owns a range of
+/// the weight line as long as their contribution, and each pick lands on a VRF-derived point on
+/// it, so a donor's odds grow with what they gave and one donor can win several picks.
+#[account]
+pub struct BonusPool {
+    pub stream: Pubkey,         // Stream whose donors may enter
+    pub host: Pubkey,           // Stream host, who funded it
+    pub mint: Pubkey,           // Mint of the pot
+    pub pool_id: u64,           // Host-chosen id so one stream can run several pools
+    pub amount: u64,            // Tokens escrowed for the winners
+    pub winner_count: u8,       // Number of picks the pot is split into
+    pub entry_deadline: i64,    // Entries close and the draw may start at this time
+    pub total_weight: u64,      // Sum of the entered donors' contributions
+    pub entrant_count: u32,     // Donors entered
+    pub status: BonusPoolStatus, // See BonusPoolStatus
+    pub draw_requested_at: i64, // When the latest VRF request was made, 0 before the draw
+    pub randomness: [u8; 32],   // VRF output the winning points derive from
+    pub drawn_at: i64,          // When the draw completed, 0 before
+    pub paid: u64,              // Paid out to winners so far
+    pub created_at: i64,        // Creation time
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for BonusPool {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 32    // host: Pubkey
+        + 32    // mint: Pubkey
+        + 8     // pool_id: u64
+        + 8     // amount: u64
+        + 1     // winner_count: u8
+        + 8     // entry_deadline: i64
+        + 8     // total_weight: u64
+        + 4     // entrant_count: u32
+        + BonusPoolStatus::SPACE // status: BonusPoolStatus
+        + 8     // draw_requested_at: i64
+        + 32    // randomness: [u8; 32]
+        + 8     // drawn_at: i64
+        + 8     // paid: u64
+        + 8     // created_at: i64
+        + 1;    // bump: u8
+}
+
+impl BonusPool {
+    /// Tokens paid per pick; rounding dust is left for the host to reclaim
+    pub fn share_per_pick(&self) -> u64 {
+        self.amount / self.winner_count as u64
+    }
+
+    /// Point on the weight line the `pick`th share lands on
+    pub fn winning_point(&self, pick: u8) -> u64 {
+        let hash = hashv(&[&self.randomness, &[pick]]).to_bytes();
+        u64::from_le_bytes(hash[..8].try_into().unwrap()) % self.total_weight
+    }
+
+    /// Number of picks landing in the range `[weight_start, weight_start + weight)`
+    pub fn picks_won(&self, weight_start: u64, weight: u64) -> u8 {
+        (0..self.winner_count)
+            .filter(|pick| {
+                let point = self.winning_point(*pick);
+                point >= weight_start && point - weight_start < weight
+            })
+            .count() as u8
+    }
+}
+
+/// A donor's range on a bonus pool's weight line
+#[account]
+pub struct BonusPoolEntry {
+    pub pool: Pubkey,           // Pool entered
+    pub donor: Pubkey,          // Donor paid for the picks landing in the range
+    pub weight_start: u64,      // Start of the range
+    pub weight: u64,            // Contribution at entry, the length of the range
+    pub claimed: bool,          // Winnings paid
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for BonusPoolEntry {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // pool: Pubkey
+        + 32    // donor: Pubkey
+        + 8     // weight_start: u64
+        + 8     // weight: u64
+        + 1     // claimed: bool
+        + 1;    // bump: u8
+}
+
+#[event]
+pub struct BonusPoolCreated {
+    pub pool: Pubkey,
+    pub stream: Pubkey,
+    pub amount: u64,
+    pub winner_count: u8,
+    pub entry_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BonusPoolEntered {
+    pub pool: Pubkey,
+    pub donor: Pubkey,
+    pub weight: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BonusPoolDrawn {
+    pub pool: Pubkey,
+    pub total_weight: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BonusClaimed {
+    pub pool: Pubkey,
+    pub donor: Pubkey,
+    pub picks: u8,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BonusPoolReclaimed {
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
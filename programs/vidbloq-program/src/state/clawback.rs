@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+pub const MAX_CLAWBACK_WINDOW: i64 = 7 * 24 * 3600;
+
+/// A payout recipient's standing consent to have recent payouts pulled back. The agreement PDA
+/// is the delegate on the recipient's token account, up to the amount they approved.
+#[account]
+pub struct ClawbackAgreement {
+    pub stream: Pubkey,         // Paying stream
+    pub recipient: Pubkey,      // Consenting recipient
+    pub recipient_ata: Pubkey,  // Token account the delegate approval covers
+    pub window: i64,            // How long after a payout it may be clawed back
+    pub clawbackable: u64,      // Net payouts still inside the window
+    pub window_ends_at: i64,    // End of the window opened by the latest payout
+    pub clawed_back: u64,       // Pulled back over the agreement's lifetime
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for ClawbackAgreement {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 32    // recipient: Pubkey
+        + 32    // recipient_ata: Pubkey
+        + 8     // window: i64
+        + 8     // clawbackable: u64
+        + 8     // window_ends_at: i64
+        + 8     // clawed_back: u64
+        + 1;    // bump: u8
+}
+
+impl ClawbackAgreement {
+    /// Adds a payout to the clawbackable amount. Payouts share one window, which every new
+    /// payout restarts; once it lapses the earlier payouts are final.
+    pub fn record_payout(&mut self, amount: u64, now: i64) -> Result<()> {
+        if now > self.window_ends_at {
+            self.clawbackable = 0;
+        }
+        self.clawbackable = self.clawbackable.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        self.window_ends_at = now.checked_add(self.window).ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Records a payout of `amount` into `recipient_ata` on the agreement at `info`, which stays
+    /// uninitialized unless the recipient has consented. Returns the updated agreement, if any.
+    pub fn record_consented_payout(
+        info: &AccountInfo,
+        recipient_ata: &Pubkey,
+        amount: u64,
+        now: i64,
+    ) -> Result<Option<ClawbackAgreement>> {
+        if info.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*info.owner, crate::ID, StreamError::Unauthorized);
+        let mut agreement = ClawbackAgreement::try_deserialize(&mut &info.data.borrow()[..])?;
+        require_keys_eq!(agreement.recipient_ata, *recipient_ata, StreamError::Unauthorized);
+        agreement.record_payout(amount, now)?;
+        agreement.try_serialize(&mut &mut info.data.borrow_mut()[..])?;
+        Ok(Some(agreement))
+    }
+
+    pub fn claw_back(&mut self, amount: u64, now: i64) -> Result<()> {
+        require!(now <= self.window_ends_at, StreamError::ClawbackWindowClosed);
+        require!(amount <= self.clawbackable, StreamError::ClawbackExceeded);
+        self.clawbackable -= amount;
+        self.clawed_back = self.clawed_back.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[event]
+pub struct ClawbackConsentGiven {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub window: i64,
+    pub max_amount: u64,        // Delegate approval on the recipient's token account
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClawbackWindowOpened {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,            // Net payout just made
+    pub clawbackable: u64,
+    pub window_ends_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClawbackExecuted {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,            // Pulled from the recipient
+    pub received: u64,          // Returned to the stream vault
+    pub clawbackable: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClawbackConsentRevoked {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub clawed_back: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::state::BPS_DENOMINATOR;
+
+/// Where a dispute of a validator consensus stands
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisputeStatus {
+    Open,
+    Upheld,     // The consensus outcome stands and the disputer's bond went to the pool
+    Overturned, // The disputer's outcome replaced it and the bond was returned
+}
+
+/// A bonded claim that validators reached the wrong outcome, put to every eligible validator.
+/// A market can be disputed once; the outcome it settles on is final.
+#[account]
+pub struct MarketDispute {
+    pub market: Pubkey,             // Market whose resolution is disputed
+    pub disputer: Pubkey,           // Posted the bond; receives it back if the dispute succeeds
+    pub disputed_outcome: u8,       // Outcome the validators reached consensus on
+    pub claimed_outcome: u8,        // Outcome the disputer says is right
+    pub bond_amount: u64,           // Tokens held in the dispute bond vault
+    pub uphold_stake: u64,          // Validator stake voting for `disputed_outcome`
+    pub overturn_stake: u64,        // Validator stake voting for `claimed_outcome`
+    pub votes_cast: u32,            // Validators who voted
+    pub voting_deadline: i64,       // Votes close and the dispute can be settled after this
+    pub status: DisputeStatus,      // Open until settled
+    pub bump: u8,                   // PDA bump
+}
+
+impl Space for MarketDispute {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // market: Pubkey
+        + 32    // disputer: Pubkey
+        + 1     // disputed_outcome: u8
+        + 1     // claimed_outcome: u8
+        + 8     // bond_amount: u64
+        + 8     // uphold_stake: u64
+        + 8     // overturn_stake: u64
+        + 4     // votes_cast: u32
+        + 8     // voting_deadline: i64
+        + 1     // status: DisputeStatus
+        + 1;    // bump: u8
+}
+
+impl MarketDispute {
+    /// Overturning the consensus outcome takes strictly more than `supermajority_bps` of the
+    /// stake voted, so at the default of half a tie keeps it
+    pub fn overturned(&self, supermajority_bps: u16) -> bool {
+        let voted = self.overturn_stake as u128 + self.uphold_stake as u128;
+        self.overturn_stake as u128 * BPS_DENOMINATOR as u128 > voted * supermajority_bps as u128
+    }
+}
+
+#[event]
+pub struct DisputeFiled {
+    pub market: Pubkey,
+    pub disputer: Pubkey,
+    pub disputed_outcome: u8,
+    pub claimed_outcome: u8,
+    pub bond_amount: u64,
+    pub voting_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeVoteCast {
+    pub market: Pubkey,
+    pub validator: Pubkey,
+    pub voted_outcome: u8,
+    pub stake_weight: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeSettled {
+    pub market: Pubkey,
+    pub disputer: Pubkey,
+    pub status: DisputeStatus,
+    pub winning_outcome: u8,
+    pub bond_amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,244 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+pub const DISTRIBUTION_WINDOW: i64 = 24 * 60 * 60;
+pub const MAX_DISTRIBUTION_APPROVERS: usize = 5;
+
+/// Host-set spending limits on a stream's payouts. Tighter limits apply at once; looser ones
+/// only after a full window, so a stolen host key cannot lift them and drain the vault.
+/// Payouts above `approval_threshold` additionally need M-of-N approver sign-off.
+#[account]
+pub struct DistributionPolicy {
+    pub stream: Pubkey,                      // Stream the limits apply to
+    pub max_per_distribution: u64,           // Largest single payout, 0 for no cap
+    pub max_per_window: u64,                 // Total paid out per window, 0 for no cap
+    pub window_start: i64,                   // Start of the current window
+    pub window_distributed: u64,             // Paid out since window_start
+    pub pending_max_per_distribution: u64,   // Looser per-payout cap waiting to apply
+    pub pending_max_per_window: u64,         // Looser window cap waiting to apply
+    pub pending_effective_at: i64,           // When the pending caps apply, 0 if none
+    pub approvers: Vec<Pubkey>,              // Wallets that sign off on large payouts
+    pub approvals_required: u8,              // Approvals a large payout needs, 0 when off
+    pub approval_threshold: u64,             // Payouts above this need approvals
+    pub pending_approvers: Vec<Pubkey>,      // Replacement approver set waiting to apply
+    pub pending_approvals_required: u8,
+    pub pending_approval_threshold: u64,
+    pub pending_approvers_at: i64,           // When the pending approver set applies, 0 if none
+    pub next_proposal_id: u64,               // Seed for the next DistributionProposal
+    pub bump: u8,                            // PDA bump
+}
+
+impl Space for DistributionPolicy {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 8     // max_per_distribution: u64
+        + 8     // max_per_window: u64
+        + 8     // window_start: i64
+        + 8     // window_distributed: u64
+        + 8     // pending_max_per_distribution: u64
+        + 8     // pending_max_per_window: u64
+        + 8     // pending_effective_at: i64
+        + 4 + 32 * MAX_DISTRIBUTION_APPROVERS // approvers: Vec<Pubkey>
+        + 1     // approvals_required: u8
+        + 8     // approval_threshold: u64
+        + 4 + 32 * MAX_DISTRIBUTION_APPROVERS // pending_approvers: Vec<Pubkey>
+        + 1     // pending_approvals_required: u8
+        + 8     // pending_approval_threshold: u64
+        + 8     // pending_approvers_at: i64
+        + 8     // next_proposal_id: u64
+        + 1;    // bump: u8
+}
+
+impl DistributionPolicy {
+    /// Applies new caps, deferring them by a window when either is looser than the current one.
+    /// Returns when they take effect.
+    pub fn set_limits(&mut self, max_per_distribution: u64, max_per_window: u64, now: i64) -> Result<i64> {
+        let loosens = |new: u64, old: u64| old != 0 && (new == 0 || new > old);
+        if loosens(max_per_distribution, self.max_per_distribution) || loosens(max_per_window, self.max_per_window) {
+            self.pending_max_per_distribution = max_per_distribution;
+            self.pending_max_per_window = max_per_window;
+            self.pending_effective_at = now.checked_add(DISTRIBUTION_WINDOW).ok_or(StreamError::MathOverflow)?;
+            return Ok(self.pending_effective_at);
+        }
+
+        self.max_per_distribution = max_per_distribution;
+        self.max_per_window = max_per_window;
+        self.pending_effective_at = 0;
+        Ok(now)
+    }
+
+    /// Sets who approves large payouts. The first set applies at once; replacing an existing
+    /// one waits a full window. `approvals_required` of zero turns approvals off.
+    pub fn set_approvers(&mut self, approvers: Vec<Pubkey>, approvals_required: u8, approval_threshold: u64, now: i64) -> Result<i64> {
+        require!(
+            approvers.len() <= MAX_DISTRIBUTION_APPROVERS
+                && approvals_required as usize <= approvers.len()
+                && (approvals_required > 0 || approvers.is_empty()),
+            StreamError::InvalidApprovers
+        );
+        for (i, approver) in approvers.iter().enumerate() {
+            require!(!approvers[..i].contains(approver), StreamError::InvalidApprovers);
+        }
+
+        if self.approvals_required > 0 {
+            self.pending_approvers = approvers;
+            self.pending_approvals_required = approvals_required;
+            self.pending_approval_threshold = approval_threshold;
+            self.pending_approvers_at = now.checked_add(DISTRIBUTION_WINDOW).ok_or(StreamError::MathOverflow)?;
+            return Ok(self.pending_approvers_at);
+        }
+
+        self.approvers = approvers;
+        self.approvals_required = approvals_required;
+        self.approval_threshold = approval_threshold;
+        Ok(now)
+    }
+
+    /// Whether a payout of `amount` must go through a `DistributionProposal`
+    pub fn requires_approval(&self, amount: u64) -> bool {
+        self.approvals_required > 0 && amount > self.approval_threshold
+    }
+
+    /// Checks `amount` against the caps in force at `now` and counts it toward the window
+    pub fn record_distribution(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.apply_pending(now);
+        require!(!self.requires_approval(amount), StreamError::ApprovalRequired);
+        require!(
+            self.max_per_distribution == 0 || amount <= self.max_per_distribution,
+            StreamError::DistributionCapExceeded
+        );
+
+        let distributed = self.window_total(amount, now)?;
+        require!(
+            self.max_per_window == 0 || distributed <= self.max_per_window,
+            StreamError::DistributionWindowCapExceeded
+        );
+        self.window_distributed = distributed;
+        Ok(())
+    }
+
+    /// Counts an approved payout toward the window; the approvers stand in for the caps
+    pub fn record_approved_distribution(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.apply_pending(now);
+        self.window_distributed = self.window_total(amount, now)?;
+        Ok(())
+    }
+
+    pub fn apply_pending(&mut self, now: i64) {
+        if self.pending_effective_at != 0 && now >= self.pending_effective_at {
+            self.max_per_distribution = self.pending_max_per_distribution;
+            self.max_per_window = self.pending_max_per_window;
+            self.pending_effective_at = 0;
+        }
+        if self.pending_approvers_at != 0 && now >= self.pending_approvers_at {
+            self.approvers = std::mem::take(&mut self.pending_approvers);
+            self.approvals_required = self.pending_approvals_required;
+            self.approval_threshold = self.pending_approval_threshold;
+            self.pending_approvers_at = 0;
+        }
+    }
+
+    /// Window total after adding `amount`, starting a new window when the last one has lapsed
+    fn window_total(&mut self, amount: u64, now: i64) -> Result<u64> {
+        if now >= self.window_start.saturating_add(DISTRIBUTION_WINDOW) {
+            self.window_start = now;
+            self.window_distributed = 0;
+        }
+        let total = self.window_distributed.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        Ok(total)
+    }
+}
+
+/// A payout above the approval threshold, waiting for sign-off from the approvers it snapshots
+#[account]
+pub struct DistributionProposal {
+    pub stream: Pubkey,             // Paying stream
+    pub id: u64,                    // Sequence number from the stream's policy
+    pub recipient: Pubkey,          // Wallet to be paid
+    pub amount: u64,                // Gross payout
+    pub approvers: Vec<Pubkey>,     // Approver set when proposed
+    pub approvals_required: u8,     // Approvals needed to execute
+    pub approvals: u8,              // Bitmap over `approvers`
+    pub proposed_at: i64,           // Time of proposal
+    pub bump: u8,                   // PDA bump
+}
+
+impl Space for DistributionProposal {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 8     // id: u64
+        + 32    // recipient: Pubkey
+        + 8     // amount: u64
+        + 4 + 32 * MAX_DISTRIBUTION_APPROVERS // approvers: Vec<Pubkey>
+        + 1     // approvals_required: u8
+        + 1     // approvals: u8
+        + 8     // proposed_at: i64
+        + 1;    // bump: u8
+}
+
+impl DistributionProposal {
+    pub fn approve(&mut self, approver: &Pubkey) -> Result<()> {
+        let index = self
+            .approvers
+            .iter()
+            .position(|a| a == approver)
+            .ok_or(StreamError::NotApprover)?;
+        let bit = 1u8 << index;
+        require!(self.approvals & bit == 0, StreamError::AlreadyApproved);
+        self.approvals |= bit;
+        Ok(())
+    }
+
+    pub fn approval_count(&self) -> u8 {
+        self.approvals.count_ones() as u8
+    }
+}
+
+#[event]
+pub struct DistributionApproversSet {
+    pub stream: Pubkey,
+    pub approvers: Vec<Pubkey>,
+    pub approvals_required: u8,
+    pub approval_threshold: u64,
+    pub effective_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LargeDistributionProposed {
+    pub stream: Pubkey,
+    pub proposal: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub approvals_required: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LargeDistributionApproved {
+    pub stream: Pubkey,
+    pub proposal: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct LargeDistributionExecuted {
+    pub stream: Pubkey,
+    pub proposal: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub platform_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DistributionPolicySet {
+    pub stream: Pubkey,
+    pub max_per_distribution: u64,
+    pub max_per_window: u64,
+    pub effective_at: i64,
+    pub timestamp: i64,
+}
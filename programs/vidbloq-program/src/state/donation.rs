@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::state::{StreamError, MAX_EXTRA_MINTS};
+
 #[account]
 pub struct DonorAccount {
     pub stream: Pubkey,  // Parent stream
@@ -7,6 +9,21 @@ pub struct DonorAccount {
     pub amount: u64,     // Total contributed
     pub refunded: bool,  // Track refund status
     pub bump: u8,        // PDA bump
+    pub deposit_count: u64,   // Number of deposits made
+    pub last_deposit_at: i64, // Timestamp of the latest deposit
+    pub extra_balances: Vec<DonorMintBalance>, // Contributions in the stream's additional mints
+    pub matched_deposits: u64, // Part of amount that drew a sponsor match
+}
+
+/// A donor's outstanding contribution in one of the stream's additional mints
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct DonorMintBalance {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+impl DonorMintBalance {
+    pub const SPACE: usize = 32 + 8;
 }
 
 impl Space for DonorAccount {
@@ -15,5 +32,90 @@ impl Space for DonorAccount {
         + 32    // donor: Pubkey
         + 8     // amount: u64
         + 1     // refunded: bool
+        + 1     // bump: u8
+        + 8     // deposit_count: u64
+        + 8     // last_deposit_at: i64
+        + 4 + DonorMintBalance::SPACE * MAX_EXTRA_MINTS // extra_balances: Vec<DonorMintBalance>
+        + 8;    // matched_deposits: u64
+}
+
+impl DonorAccount {
+    /// Adds a deposit to this donor's running totals, initializing the record on first use
+    pub fn record_deposit(&mut self, stream: Pubkey, donor: Pubkey, amount: u64, now: i64, bump: u8) -> Result<()> {
+        self.count_deposit(stream, donor, now, bump)?;
+        self.amount = self.amount.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        self.refunded = false;
+        Ok(())
+    }
+
+    /// Same as `record_deposit` for one of the stream's additional mints
+    pub fn record_mint_deposit(&mut self, stream: Pubkey, donor: Pubkey, mint: Pubkey, amount: u64, now: i64, bump: u8) -> Result<()> {
+        self.count_deposit(stream, donor, now, bump)?;
+        match self.extra_balances.iter_mut().find(|balance| balance.mint == mint) {
+            Some(balance) => {
+                balance.amount = balance.amount.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+            }
+            None => {
+                require!(self.extra_balances.len() < MAX_EXTRA_MINTS, StreamError::TooManyMints);
+                self.extra_balances.push(DonorMintBalance { mint, amount });
+            }
+        }
+        Ok(())
+    }
+
+    /// Part of `amount` a refund may return. Deposits that drew a sponsor match stay in the
+    /// stream while it is active, so a donor cannot refund and deposit again to drain the pledge.
+    pub fn refundable(&self, stream_active: bool) -> u64 {
+        if stream_active {
+            self.amount.saturating_sub(self.matched_deposits)
+        } else {
+            self.amount
+        }
+    }
+
+    fn count_deposit(&mut self, stream: Pubkey, donor: Pubkey, now: i64, bump: u8) -> Result<()> {
+        if self.deposit_count == 0 {
+            self.stream = stream;
+            self.donor = donor;
+            self.bump = bump;
+        }
+        self.deposit_count = self.deposit_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        self.last_deposit_at = now;
+        Ok(())
+    }
+}
+
+
+pub const MAX_DONATION_MESSAGE_LEN: usize = 200;
+
+#[account]
+pub struct DonationReceipt {
+    pub stream: Pubkey,          // Parent stream
+    pub donor: Pubkey,           // Contributor's wallet, default for anonymous deposits
+    pub commitment: Option<[u8; 32]>, // Donor commitment hash for anonymous deposits
+    pub index: u64,              // Position in the stream's receipt sequence
+    pub amount: u64,             // Amount of this deposit
+    pub message: Option<String>, // Superchat-style message
+    pub timestamp: i64,          // When the deposit landed
+    pub bump: u8,                // PDA bump
+}
+
+impl Space for DonationReceipt {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 32    // donor: Pubkey
+        + 1 + 32 // commitment: Option<[u8; 32]>
+        + 8     // index: u64
+        + 8     // amount: u64
+        + 1 + 4 + MAX_DONATION_MESSAGE_LEN // message: Option<String>
+        + 8     // timestamp: i64
         + 1;    // bump: u8
 }
+
+#[event]
+pub struct DonorAccountMigrated {
+    pub donor_account: Pubkey,
+    pub stream: Pubkey,
+    pub donor: Pubkey,
+    pub timestamp: i64,
+}
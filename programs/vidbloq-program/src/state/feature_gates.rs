@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+/// Program-wide switches controlled by the upgrade authority. A single PDA; while it has not
+/// been initialized every gate is off.
+#[account]
+pub struct FeatureGates {
+    pub admin: Pubkey,                  // Upgrade authority at initialization
+    pub withdrawal_queue_enabled: bool, // Large refunds/claims must go through the queue
+    pub queue_threshold: u64,           // Payouts above this (base units) are queued
+    pub max_releases_per_slot: u16,     // Tickets the crank may release per slot
+    pub current_slot: u64,              // Slot of the latest release
+    pub released_in_slot: u16,          // Tickets released in `current_slot`
+    pub next_ticket_id: u64,            // Seed for the next withdrawal ticket
+    pub pending_tickets: u64,           // Tickets waiting for the crank
+    pub bump: u8,                       // PDA bump
+}
+
+impl Space for FeatureGates {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // admin: Pubkey
+        + 1     // withdrawal_queue_enabled: bool
+        + 8     // queue_threshold: u64
+        + 2     // max_releases_per_slot: u16
+        + 8     // current_slot: u64
+        + 2     // released_in_slot: u16
+        + 8     // next_ticket_id: u64
+        + 8     // pending_tickets: u64
+        + 1;    // bump: u8
+}
+
+impl FeatureGates {
+    pub fn queues(&self, amount: u64) -> bool {
+        self.withdrawal_queue_enabled && amount > self.queue_threshold
+    }
+
+    /// Reads the gates from `gates`, which must be the feature gates PDA (enforced by the
+    /// caller's seeds constraint). An uninitialized account means no gate is active.
+    pub fn load(gates: &AccountInfo) -> Result<Option<FeatureGates>> {
+        if gates.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*gates.owner, crate::ID, StreamError::Unauthorized);
+        Ok(Some(FeatureGates::try_deserialize(&mut &gates.data.borrow()[..])?))
+    }
+
+    /// Fails when a direct payout of `amount` must go through the withdrawal queue instead
+    pub fn require_not_queued(gates: &AccountInfo, amount: u64) -> Result<()> {
+        if let Some(gates) = FeatureGates::load(gates)? {
+            require!(!gates.queues(amount), StreamError::WithdrawalMustBeQueued);
+        }
+        Ok(())
+    }
+
+    /// Counts one ticket release against the current slot's budget
+    pub fn record_release(&mut self, slot: u64) -> Result<()> {
+        if self.current_slot != slot {
+            self.current_slot = slot;
+            self.released_in_slot = 0;
+        }
+        if self.withdrawal_queue_enabled {
+            require!(
+                self.released_in_slot < self.max_releases_per_slot,
+                StreamError::WithdrawalRateLimited
+            );
+        }
+        self.released_in_slot += 1;
+        self.pending_tickets = self.pending_tickets.saturating_sub(1);
+        Ok(())
+    }
+}
+
+#[event]
+pub struct WithdrawalQueueConfigured {
+    pub admin: Pubkey,
+    pub enabled: bool,
+    pub queue_threshold: u64,
+    pub max_releases_per_slot: u16,
+    pub timestamp: i64,
+}
@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+/// Host-funded credit a bettor can stake on one market instead of their own tokens. The credit
+/// itself is never withdrawable; unused credit goes back to the host once it expires.
+#[account]
+pub struct FreeBetCredit {
+    pub market: Pubkey,         // Market the credit can be staked on
+    pub host: Pubkey,           // Funding host, refunded on clawback
+    pub bettor: Pubkey,         // Only this wallet can stake the credit
+    pub granted: u64,           // Amount that arrived in the credit vault
+    pub remaining: u64,         // Credit not yet staked
+    pub expires_at: i64,        // Credit cannot be staked from this time
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for FreeBetCredit {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // market: Pubkey
+        + 32    // host: Pubkey
+        + 32    // bettor: Pubkey
+        + 8     // granted: u64
+        + 8     // remaining: u64
+        + 8     // expires_at: i64
+        + 1;    // bump: u8
+}
+
+#[event]
+pub struct FreeBetCreditGranted {
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub amount: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FreeBetPlaced {
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub outcome_id: u8,
+    pub amount: u64,
+    pub remaining_credit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FreeBetCreditClawedBack {
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FreeBetStakesReclaimed {
+    pub market: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,145 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{StreamError, BPS_DENOMINATOR};
+
+pub const HOST_REFERRAL_SHARE_BPS: u16 = 1_000; // Referrer earns 10% of platform fees
+pub const HOST_REFERRAL_DURATION: i64 = 180 * 24 * 3600; // for six months after the host's first stream
+pub const HOST_BOND_LOCK: i64 = 7 * 24 * 3600; // Bond stays slashable this long after each market resolution
+
+/// Per-host record created with the host's first stream
+#[account]
+pub struct HostProfile {
+    pub host: Pubkey,                 // Host wallet
+    pub referrer: Option<Pubkey>,     // Growth partner that referred the host
+    pub referral_expires_at: i64,     // Referrer stops earning after this time
+    pub streams_created: u64,         // Streams initialized by this host
+    pub created_at: i64,              // Time of the host's first stream
+    pub jurisdiction: [u8; 2],        // ISO 3166-1 alpha-2 country code printed on tax receipts
+    pub streams_completed: u64,       // Streams the host ended normally
+    pub total_raised: u64,            // Sum of deposits across completed streams, in each stream's own mint
+    pub markets_resolved: u64,        // Markets the host resolved by hand
+    pub disputes_lost: u32,           // Resolutions the platform admin ruled malicious
+    pub bond: u64,                    // Lamports held on this account as a slashable bond
+    pub bond_locked_until: i64,       // Bond cannot be withdrawn before this time
+    pub bump: u8,                     // PDA bump
+}
+
+impl Space for HostProfile {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // host: Pubkey
+        + 1 + 32 // referrer: Option<Pubkey>
+        + 8     // referral_expires_at: i64
+        + 8     // streams_created: u64
+        + 8     // created_at: i64
+        + 2     // jurisdiction: [u8; 2]
+        + 8     // streams_completed: u64
+        + 8     // total_raised: u64
+        + 8     // markets_resolved: u64
+        + 4     // disputes_lost: u32
+        + 8     // bond: u64
+        + 8     // bond_locked_until: i64
+        + 1;    // bump: u8
+}
+
+impl HostProfile {
+    /// Counts a new stream, recording the referrer only when the profile is first created
+    pub fn register_stream(&mut self, host: Pubkey, referrer: Option<Pubkey>, bump: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        if self.host == Pubkey::default() {
+            let referrer = referrer.filter(|r| *r != host);
+            self.host = host;
+            self.referrer = referrer;
+            self.referral_expires_at = if referrer.is_some() { now + HOST_REFERRAL_DURATION } else { 0 };
+            self.created_at = now;
+            self.bump = bump;
+
+            if let Some(referrer) = referrer {
+                emit!(HostReferred {
+                    host,
+                    referrer,
+                    expires_at: self.referral_expires_at,
+                    timestamp: now,
+                });
+            }
+        }
+        self.streams_created = self.streams_created.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Adds a completed stream's deposits to the host's track record
+    pub fn record_completion(&mut self, raised: u64) -> Result<()> {
+        self.streams_completed = self.streams_completed.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        self.total_raised = self.total_raised.saturating_add(raised);
+        Ok(())
+    }
+
+    /// Counts a hand resolution and keeps the bond locked long enough for it to be disputed
+    pub fn record_resolution(&mut self, now: i64) -> Result<()> {
+        self.markets_resolved = self.markets_resolved.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        self.bond_locked_until = self.bond_locked_until.max(now.saturating_add(HOST_BOND_LOCK));
+        Ok(())
+    }
+
+    /// Referrer and their cut of a platform fee charged on this host's streams, while the referral runs
+    pub fn referral_cut(&self, platform_fee: u64, now: i64) -> Option<(Pubkey, u64)> {
+        let referrer = self.referrer?;
+        if now >= self.referral_expires_at {
+            return None;
+        }
+        let cut = (platform_fee as u128 * HOST_REFERRAL_SHARE_BPS as u128 / BPS_DENOMINATOR as u128) as u64;
+        (cut > 0).then_some((referrer, cut))
+    }
+}
+
+/// Marks a market the host's bond has been slashed for, so each resolution is ruled on once
+#[account]
+pub struct HostSlash {
+    pub market: Pubkey,               // Market whose resolution was ruled malicious
+    pub host: Pubkey,                 // Host whose bond was slashed
+    pub amount: u64,                  // Lamports moved to the treasury
+    pub slashed_at: i64,              // Ruling time
+    pub bump: u8,                     // PDA bump
+}
+
+impl Space for HostSlash {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // market: Pubkey
+        + 32    // host: Pubkey
+        + 8     // amount: u64
+        + 8     // slashed_at: i64
+        + 1;    // bump: u8
+}
+
+#[event]
+pub struct HostReferred {
+    pub host: Pubkey,
+    pub referrer: Pubkey,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HostBondPosted {
+    pub host: Pubkey,
+    pub amount: u64,
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HostBondWithdrawn {
+    pub host: Pubkey,
+    pub amount: u64,
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HostBondSlashed {
+    pub host: Pubkey,
+    pub market: Pubkey,
+    pub amount: u64,
+    pub treasury: Pubkey,
+    pub disputes_lost: u32,
+    pub timestamp: i64,
+}
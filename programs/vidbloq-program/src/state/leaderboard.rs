@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+pub const MAX_LEADERBOARD_CAPACITY: u16 = 100;
+
+#[account]
+pub struct Leaderboard {
+    pub stream: Pubkey,                   // Parent stream
+    pub capacity: u16,                    // Number of donors tracked (N)
+    pub entries: Vec<LeaderboardEntry>,   // Sorted by amount, largest first
+    pub bump: u8,                         // PDA bump
+}
+
+impl Leaderboard {
+    pub fn space(capacity: u16) -> usize {
+        8       // Discriminator
+        + 32    // stream: Pubkey
+        + 2     // capacity: u16
+        + 4 + capacity as usize * LeaderboardEntry::SPACE // entries: Vec<LeaderboardEntry>
+        + 1     // bump: u8
+    }
+
+    /// Records `donor`'s cumulative `amount` and keeps only the top `capacity` donors. Refunds
+    /// record the lowered amount, and a donor refunded in full leaves the board.
+    pub fn record(&mut self, donor: Pubkey, amount: u64) {
+        match self.entries.iter_mut().find(|e| e.donor == donor) {
+            Some(entry) => entry.amount = amount,
+            None => self.entries.push(LeaderboardEntry { donor, amount }),
+        }
+        self.entries.retain(|e| e.amount > 0);
+        self.entries.sort_by_key(|e| std::cmp::Reverse(e.amount));
+        self.entries.truncate(self.capacity as usize);
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LeaderboardEntry {
+    pub donor: Pubkey,
+    pub amount: u64,
+}
+
+impl LeaderboardEntry {
+    pub const SPACE: usize = 32 + 8;
+}
+
+#[event]
+pub struct LeaderboardResized {
+    pub stream: Pubkey,
+    pub old_capacity: u16,
+    pub new_capacity: u16,
+    pub timestamp: i64,
+}
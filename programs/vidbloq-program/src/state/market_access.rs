@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+use crate::state::StreamError;
+
+/// Who may bet on a market
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MarketAccessMode {
+    /// Anyone not blocked on the stream
+    #[default]
+    Open,
+    /// Only bettors with an allowlist entry
+    Allowlist,
+    /// Anyone without a denylist entry
+    Denylist,
+}
+
+impl MarketAccessMode {
+    pub const SPACE: usize = 1;
+}
+
+/// One bettor's listing on a market. An entry only counts while the market is in the mode it
+/// was listed under, so switching modes never turns an allowlist into a denylist.
+#[account]
+pub struct MarketAccessEntry {
+    pub market: Pubkey,             // Market the listing applies to
+    pub bettor: Pubkey,             // Listed wallet
+    pub mode: MarketAccessMode,     // Mode the bettor was listed under
+    pub bump: u8,                   // PDA bump
+}
+
+impl Space for MarketAccessEntry {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // market: Pubkey
+        + 32    // bettor: Pubkey
+        + MarketAccessMode::SPACE // mode: MarketAccessMode
+        + 1;    // bump: u8
+}
+
+impl MarketAccessEntry {
+    /// Fails when `mode` keeps `bettor` out. The entry account must be the bettor's access PDA
+    /// for the market (enforced by the caller's seeds constraint); an uninitialized account
+    /// means the bettor is not listed.
+    pub fn require_access(mode: MarketAccessMode, entry: Option<&AccountInfo>, bettor: &Pubkey) -> Result<()> {
+        if mode == MarketAccessMode::Open {
+            return Ok(());
+        }
+        let entry = entry.ok_or(StreamError::MissingAccounts)?;
+        let listed = if entry.data_is_empty() {
+            false
+        } else {
+            require_keys_eq!(*entry.owner, crate::ID, StreamError::Unauthorized);
+            let entry = MarketAccessEntry::try_deserialize(&mut &entry.data.borrow()[..])?;
+            entry.bettor == *bettor && entry.mode == mode
+        };
+        require!(listed == (mode == MarketAccessMode::Allowlist), StreamError::MarketAccessDenied);
+        Ok(())
+    }
+}
+
+/// Whether `proof` links `wallet` to `root` in a sha256 merkle tree whose leaves are
+/// sha256(wallet) and whose parents hash each sorted pair of children
+pub fn verify_access_proof(root: [u8; 32], wallet: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let mut node = hashv(&[wallet.as_ref()]).to_bytes();
+    for sibling in proof {
+        node = if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        };
+    }
+    node == root
+}
+
+#[event]
+pub struct MarketAccessSet {
+    pub market: Pubkey,
+    pub mode: MarketAccessMode,
+    pub list_root: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketBettorListed {
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub mode: MarketAccessMode,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MarketBettorUnlisted {
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const MAX_MATCH_RATIO_BPS: u16 = 50_000; // Up to 5:1 matching
+
+#[account]
+pub struct MatchingPledge {
+    pub stream: Pubkey,     // Stream whose deposits are matched
+    pub sponsor: Pubkey,    // Wallet that funded the pledge
+    pub ratio_bps: u16,     // Matched tokens per deposited token, 10_000 = 1:1
+    pub cap: u64,           // Total amount locked for matching
+    pub matched: u64,       // Amount matched so far
+    pub bump: u8,           // PDA bump
+}
+
+impl Space for MatchingPledge {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 32    // sponsor: Pubkey
+        + 2     // ratio_bps: u16
+        + 8     // cap: u64
+        + 8     // matched: u64
+        + 1;    // bump: u8
+}
+
+impl MatchingPledge {
+    /// Amount to match for a deposit of `amount`, limited by what is left under the cap
+    pub fn match_for(&self, amount: u64) -> Result<u64> {
+        let wanted = (amount as u128)
+            .checked_mul(self.ratio_bps as u128)
+            .ok_or(crate::state::StreamError::MathOverflow)?
+            / BPS_DENOMINATOR as u128;
+        let remaining = self.cap.saturating_sub(self.matched);
+        Ok(std::cmp::min(wanted, remaining as u128) as u64)
+    }
+}
+
+#[event]
+pub struct MatchingPledgeCreated {
+    pub stream: Pubkey,
+    pub sponsor: Pubkey,
+    pub ratio_bps: u16,
+    pub cap: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MatchingPledgeClosed {
+    pub stream: Pubkey,
+    pub sponsor: Pubkey,
+    pub matched: u64,
+    pub returned: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+pub const MAX_MIRROR_REVENUE_SHARE_BPS: u16 = 2_000; // Most of a follower's winnings a tipster can be promised
+
+/// Lets viewers copy a tipster's latest bet on a market. The tipster is the host unless the
+/// host designates someone else; `place_bet` keeps the latest bet current.
+#[account]
+pub struct MirrorConfig {
+    pub market: Pubkey,             // Market whose bets are mirrored
+    pub tipster: Pubkey,            // Wallet whose bets followers copy
+    pub revenue_share_bps: u16,     // Share of mirrored winnings owed to the tipster
+    pub latest_outcome: Option<u8>, // Outcome of the tipster's latest bet, none until they bet
+    pub latest_stake: u64,          // Stake of the tipster's latest bet
+    pub latest_at: i64,             // When the tipster's latest bet was placed
+    pub mirrored_volume: u64,       // Stakes followers have placed by mirroring
+    pub bump: u8,                   // PDA bump
+}
+
+impl Space for MirrorConfig {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // market: Pubkey
+        + 32    // tipster: Pubkey
+        + 2     // revenue_share_bps: u16
+        + 1 + 1 // latest_outcome: Option<u8>
+        + 8     // latest_stake: u64
+        + 8     // latest_at: i64
+        + 8     // mirrored_volume: u64
+        + 1;    // bump: u8
+}
+
+impl MirrorConfig {
+    /// Records a bet by `bettor` as the tipster's latest if they are the tipster. `config` must be
+    /// the market's mirror config PDA (enforced by the caller's seeds constraint). Does nothing
+    /// while mirroring is not enabled.
+    pub fn record_bet(config: &AccountInfo, bettor: Pubkey, outcome_id: u8, stake: u64) -> Result<()> {
+        if config.data_is_empty() {
+            return Ok(());
+        }
+        require_keys_eq!(*config.owner, crate::ID, StreamError::Unauthorized);
+        let mut state = MirrorConfig::try_deserialize(&mut &config.data.borrow()[..])?;
+        if state.tipster != bettor {
+            return Ok(());
+        }
+        state.latest_outcome = Some(outcome_id);
+        state.latest_stake = stake;
+        state.latest_at = Clock::get()?.unix_timestamp;
+        state.try_serialize(&mut &mut config.data.borrow_mut()[..])?;
+        Ok(())
+    }
+}
+
+/// A follower's mirrored stake on one outcome, kept so the tipster's cut of the winnings can be
+/// settled later
+#[account]
+pub struct MirrorPosition {
+    pub market: Pubkey,             // Market bet on
+    pub follower: Pubkey,           // Wallet that mirrored
+    pub tipster: Pubkey,            // Wallet mirrored
+    pub source_position: Pubkey,    // Tipster's outcome position the latest mirror copied
+    pub outcome_id: u8,             // Outcome both bet on
+    pub staked: u64,                // Follower's stake placed by mirroring
+    pub shares: u64,                // Shares those stakes bought
+    pub revenue_share_bps: u16,     // Tipster's share of the winnings on these shares
+    pub bump: u8,                   // PDA bump
+}
+
+impl Space for MirrorPosition {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // market: Pubkey
+        + 32    // follower: Pubkey
+        + 32    // tipster: Pubkey
+        + 32    // source_position: Pubkey
+        + 1     // outcome_id: u8
+        + 8     // staked: u64
+        + 8     // shares: u64
+        + 2     // revenue_share_bps: u16
+        + 1;    // bump: u8
+}
+
+#[event]
+pub struct MirroringEnabled {
+    pub market: Pubkey,
+    pub tipster: Pubkey,
+    pub revenue_share_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetMirrored {
+    pub market: Pubkey,
+    pub follower: Pubkey,
+    pub tipster: Pubkey,
+    pub source_position: Pubkey,
+    pub outcome_id: u8,
+    pub amount: u64,
+    pub shares: u64,
+    pub timestamp: i64,
+}
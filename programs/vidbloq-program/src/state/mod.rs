@@ -3,4 +3,72 @@ pub use stream::*;
 pub mod donation;
 pub use donation::*;
 pub mod betting;
-pub use betting::*;
\ No newline at end of file
+pub use betting::*;
+pub mod tiers;
+pub use tiers::*;
+pub mod approval;
+pub use approval::*;
+pub mod matching;
+pub use matching::*;
+pub mod leaderboard;
+pub use leaderboard::*;
+pub mod blocklist;
+pub use blocklist::*;
+pub mod voucher;
+pub use voucher::*;
+pub mod host_profile;
+pub use host_profile::*;
+pub mod referral;
+pub use referral::*;
+pub mod feature_gates;
+pub use feature_gates::*;
+pub mod withdrawal_queue;
+pub use withdrawal_queue::*;
+pub mod tax_receipt;
+pub use tax_receipt::*;
+pub mod platform;
+pub use platform::*;
+pub mod bettor_profile;
+pub use bettor_profile::*;
+pub mod payout;
+pub use payout::*;
+pub mod oracle;
+pub use oracle::*;
+pub mod distribution_policy;
+pub use distribution_policy::*;
+pub mod split;
+pub use split::*;
+pub mod allowance;
+pub use allowance::*;
+pub mod free_bet;
+pub use free_bet::*;
+pub mod attendance;
+pub use attendance::*;
+pub mod clawback;
+pub use clawback::*;
+pub mod access_gate;
+pub use access_gate::*;
+pub mod notification;
+pub use notification::*;
+pub mod registry;
+pub use registry::*;
+pub mod market_access;
+pub use market_access::*;
+pub mod parlay;
+pub use parlay::*;
+pub mod dispute;
+pub use dispute::*;
+pub mod validator_escrow;
+pub use validator_escrow::*;
+pub mod price_oracle;
+pub use price_oracle::*;
+pub mod raffle;
+pub use raffle::*;
+pub mod bonus_pool;
+pub use bonus_pool::*;
+pub mod session;
+pub use session::*;
+pub mod mirror;
+pub use mirror::*;
+pub mod validator_registry;
+pub use validator_registry::*;
\ No newline at end of file
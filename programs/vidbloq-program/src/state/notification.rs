@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+
+pub const BETTING_CLOSING_SOON: i64 = 10 * 60; // Bets this close to resolution time warn that betting is ending
+pub const REFUND_WINDOW_CLOSING_SOON: i64 = 24 * 3600; // Deposits this close to the refund deadline warn donors
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotificationSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// What a `Notification` is about. Each code fixes the meaning of the subject and the two values.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotificationCode {
+    BettingClosingSoon,  // subject: market; values: [resolution time, total pool]
+    DisputeOpened,       // subject: market; values: [challenger bond, proposed outcome], or [oracle round, 0]
+    ConsensusReached,    // subject: market; values: [winning outcome, winning stake]
+    VaultShortfall,      // subject: stream; values: [vault balance, tracked balance]
+    RefundWindowClosing, // subject: stream; values: [refund deadline, refundable balance]
+    ResolutionRescheduled, // subject: market; values: [previous resolution time, new resolution time]
+}
+
+impl NotificationCode {
+    pub fn severity(&self) -> NotificationSeverity {
+        match self {
+            NotificationCode::BettingClosingSoon
+            | NotificationCode::ConsensusReached
+            | NotificationCode::ResolutionRescheduled => NotificationSeverity::Info,
+            NotificationCode::DisputeOpened | NotificationCode::RefundWindowClosing => NotificationSeverity::Warning,
+            NotificationCode::VaultShortfall => NotificationSeverity::Critical,
+        }
+    }
+}
+
+/// Uniform event for off-chain alerting, emitted alongside the specific event of each transition
+#[event]
+pub struct Notification {
+    pub severity: NotificationSeverity,
+    pub code: NotificationCode,
+    pub subject: Pubkey,
+    pub values: [u64; 2],
+    pub timestamp: i64,
+}
+
+pub fn notify(code: NotificationCode, subject: Pubkey, values: [u64; 2], timestamp: i64) {
+    emit!(Notification {
+        severity: code.severity(),
+        code,
+        subject,
+        values,
+        timestamp,
+    });
+}
@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{StreamError, MarketType};
+
+pub const MAX_ORACLE_REPORTERS: usize = 3;
+
+/// Reporter set for a numeric market. Each reporter submits the observed value once per round;
+/// the market resolves from the median once a majority of reporters agree within `tolerance`.
+#[account]
+pub struct MarketOracle {
+    pub market: Pubkey,                        // Market the oracle settles
+    pub reporters: Vec<Pubkey>,                // Configured reporters, at most MAX_ORACLE_REPORTERS
+    pub tolerance: u64,                        // Max distance from the median for a report to agree
+    pub round: u32,                            // Current round; bumped when a dispute is reopened
+    pub submissions: Vec<OracleSubmission>,    // Reports received this round
+    pub status: OracleStatus,                  // Collecting, Agreed or Disputed
+    pub agreed_value: Option<u64>,             // Median once agreement is reached
+    pub bump: u8,                              // PDA bump
+}
+
+impl Space for MarketOracle {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // market: Pubkey
+        + 4 + 32 * MAX_ORACLE_REPORTERS // reporters: Vec<Pubkey>
+        + 8     // tolerance: u64
+        + 4     // round: u32
+        + 4 + OracleSubmission::SPACE * MAX_ORACLE_REPORTERS // submissions: Vec<OracleSubmission>
+        + 1     // status: OracleStatus
+        + 1 + 8 // agreed_value: Option<u64>
+        + 1;    // bump: u8
+}
+
+impl MarketOracle {
+    pub fn load(oracle: &AccountInfo) -> Result<Option<MarketOracle>> {
+        if oracle.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*oracle.owner, crate::ID, StreamError::Unauthorized);
+        Ok(Some(MarketOracle::try_deserialize(&mut &oracle.data.borrow()[..])?))
+    }
+
+    /// Reports needed before the oracle can agree
+    pub fn quorum(&self) -> usize {
+        self.reporters.len() / 2 + 1
+    }
+
+    /// Re-evaluates the round after a new submission. Agreement needs a quorum of reports
+    /// within `tolerance` of their median; once every reporter has reported without one,
+    /// the round is disputed.
+    pub fn evaluate(&mut self) -> OracleStatus {
+        if self.submissions.len() < self.quorum() {
+            return self.status;
+        }
+
+        let mut values: Vec<u64> = self.submissions.iter().map(|s| s.value).collect();
+        values.sort_unstable();
+        let median = values[(values.len() - 1) / 2];
+        let agreeing = values.iter().filter(|v| v.abs_diff(median) <= self.tolerance).count();
+
+        if agreeing >= self.quorum() {
+            self.status = OracleStatus::Agreed;
+            self.agreed_value = Some(median);
+        } else if self.submissions.len() == self.reporters.len() {
+            self.status = OracleStatus::Disputed;
+        }
+        self.status
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct OracleSubmission {
+    pub reporter: Pubkey,
+    pub value: u64,
+    pub submitted_at: i64,
+}
+
+impl OracleSubmission {
+    pub const SPACE: usize = 32 + 8 + 8;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum OracleStatus {
+    Collecting,
+    Agreed,
+    Disputed,
+}
+
+impl MarketType {
+    /// Outcome a reported value settles a numeric market to: 0 when the value lands over
+    /// the line, 1 otherwise. `None` for markets that are not numeric.
+    pub fn outcome_for_value(&self, value: u64) -> Option<u8> {
+        match self {
+            MarketType::OverUnder { line } => Some(if value > *line { 0 } else { 1 }),
+            _ => None,
+        }
+    }
+}
+
+// ============= EVENTS =============
+
+#[event]
+pub struct OracleConfigured {
+    pub market: Pubkey,
+    pub reporters: Vec<Pubkey>,
+    pub tolerance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OracleResultSubmitted {
+    pub market: Pubkey,
+    pub reporter: Pubkey,
+    pub round: u32,
+    pub value: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OracleAgreed {
+    pub market: Pubkey,
+    pub round: u32,
+    pub value: u64,
+    pub timestamp: i64,
+}
+
+/// Reporters disagreed beyond tolerance; the host may open a new round or fall back to
+/// validator resolution
+#[event]
+pub struct OracleDisputed {
+    pub market: Pubkey,
+    pub round: u32,
+    pub values: Vec<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetricSourceSet {
+    pub market: Pubkey,
+    pub source: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MetricReported {
+    pub market: Pubkey,
+    pub source: Pubkey,
+    pub value: u64,
+    pub line: u64,
+    pub winning_outcome: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OracleRoundOpened {
+    pub market: Pubkey,
+    pub round: u32,
+    pub timestamp: i64,
+}
@@ -0,0 +1,100 @@
+use anchor_lang::prelude::*;
+
+pub const MAX_PARLAY_LEGS: usize = 8;
+pub const MIN_PARLAY_LEGS: usize = 2;
+pub const MIN_LEG_PROBABILITY_BPS: u64 = 100; // Longest odds a leg may lock, 1%
+
+/// House bankroll in one mint that parlays are placed against. Open parlays lock their full
+/// payout; only the balance above that can fund new parlays or be withdrawn by the admin.
+#[account]
+pub struct ParlayReserve {
+    pub mint: Pubkey,           // Mint stakes and payouts are paid in
+    pub total_locked: u64,      // Sum of the payouts of open parlays
+    pub open_parlays: u32,      // Parlays placed and not yet settled
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for ParlayReserve {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // mint: Pubkey
+        + 8     // total_locked: u64
+        + 4     // open_parlays: u32
+        + 1;    // bump: u8
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ParlayLeg {
+    pub market: Pubkey,
+    pub outcome_id: u8,
+}
+
+impl ParlayLeg {
+    pub const SPACE: usize = 32 + 1;
+}
+
+/// How a parlay settles once its legs are known
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParlayResult {
+    Won,
+    Lost,
+    Void, // A leg market was cancelled and no leg lost; the stake is returned
+}
+
+/// A stake on every leg resolving to its chosen outcome, at odds fixed when it was placed
+#[account]
+pub struct Parlay {
+    pub bettor: Pubkey,         // Receives the payout and the account rent
+    pub reserve: Pubkey,        // Reserve the parlay is placed against
+    pub parlay_id: u64,         // Bettor-chosen id so one wallet can hold several parlays
+    pub stake: u64,             // Tokens paid in
+    pub payout: u64,            // Tokens paid out if every leg wins
+    pub legs: Vec<ParlayLeg>,   // Markets and chosen outcomes, in the order settle_parlay reads them
+    pub created_at: i64,        // Placement time
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for Parlay {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // bettor: Pubkey
+        + 32    // reserve: Pubkey
+        + 8     // parlay_id: u64
+        + 8     // stake: u64
+        + 8     // payout: u64
+        + 4 + ParlayLeg::SPACE * MAX_PARLAY_LEGS // legs: Vec<ParlayLeg>
+        + 8     // created_at: i64
+        + 1;    // bump: u8
+}
+
+#[event]
+pub struct ParlayReserveFunded {
+    pub reserve: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParlayReserveWithdrawn {
+    pub reserve: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParlayPlaced {
+    pub parlay: Pubkey,
+    pub bettor: Pubkey,
+    pub legs: Vec<ParlayLeg>,
+    pub stake: u64,
+    pub payout: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParlaySettled {
+    pub parlay: Pubkey,
+    pub bettor: Pubkey,
+    pub result: ParlayResult,
+    pub paid: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,98 @@
+use anchor_lang::prelude::*;
+
+pub const MAX_PAYOUT_TIMEOUT: i64 = 30 * 24 * 3600;
+
+/// A distribution escrowed until the recipient accepts it or the host cancels it after `expires_at`
+#[account]
+pub struct PendingPayout {
+    pub stream: Pubkey,         // Paying stream
+    pub host: Pubkey,           // Stream host, refunded the rent
+    pub recipient: Pubkey,      // Only this wallet can accept
+    pub mint: Pubkey,           // Stream mint
+    pub nonce: u64,             // Stream payout sequence number
+    pub amount: u64,            // Amount held in the escrow vault
+    pub proposed_at: i64,       // Time of proposal
+    pub expires_at: i64,        // Host may cancel from this time
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for PendingPayout {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 32    // host: Pubkey
+        + 32    // recipient: Pubkey
+        + 32    // mint: Pubkey
+        + 8     // nonce: u64
+        + 8     // amount: u64
+        + 8     // proposed_at: i64
+        + 8     // expires_at: i64
+        + 1;    // bump: u8
+}
+
+/// Marks `recipient` as an approved payout destination of `stream`; its existence is the approval
+#[account]
+pub struct PayoutRecipient {
+    pub stream: Pubkey,         // Stream the approval applies to
+    pub recipient: Pubkey,      // Approved wallet
+    pub added_at: i64,          // Time of approval
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for PayoutRecipient {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 32    // recipient: Pubkey
+        + 8     // added_at: i64
+        + 1;    // bump: u8
+}
+
+#[event]
+pub struct DistributionProposed {
+    pub stream: Pubkey,
+    pub payout: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DistributionAccepted {
+    pub stream: Pubkey,
+    pub payout: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub platform_fee: u64,
+    pub burned: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DistributionCancelled {
+    pub stream: Pubkey,
+    pub payout: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutRecipientAdded {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutRecipientRemoved {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutPolicySet {
+    pub stream: Pubkey,
+    pub strict: bool,
+    pub timestamp: i64,
+}
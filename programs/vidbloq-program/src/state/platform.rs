@@ -0,0 +1,106 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{StreamError, BPS_DENOMINATOR};
+
+pub const MAX_PLATFORM_FEE_BPS: u16 = 1_000; // 10%
+pub const DEFAULT_MIN_CONSENSUS_BPS: u16 = 5_001; // Strict majority
+pub const DEFAULT_MAX_CONSENSUS_BPS: u16 = 10_000;
+pub const MIN_STREAM_LIFETIME: i64 = 30 * 24 * 60 * 60; // Shortest lifetime the admin may set
+pub const DEFAULT_VALIDATOR_SLASH_BPS: u16 = 1_000; // 10% of a locked stake
+
+/// Global fee settings. A single PDA; while it has not been initialized no platform fee is charged.
+#[account]
+pub struct PlatformConfig {
+    pub admin: Pubkey,          // May update the config
+    pub treasury: Pubkey,       // Wallet whose token accounts receive platform fees
+    pub fee_bps: u16,           // Fee on distributions and winnings claims
+    pub min_consensus_bps: u16, // Loosest validator consensus a market may use
+    pub max_consensus_bps: u16, // Strictest validator consensus a market may use
+    pub max_stream_lifetime: i64, // Seconds after creation until donors may always exit, 0 for no limit
+    pub validator_slash_bps: u16, // Share of a validator's locked stake taken for voting against consensus
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for PlatformConfig {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // admin: Pubkey
+        + 32    // treasury: Pubkey
+        + 2     // fee_bps: u16
+        + 2     // min_consensus_bps: u16
+        + 2     // max_consensus_bps: u16
+        + 8     // max_stream_lifetime: i64
+        + 2     // validator_slash_bps: u16
+        + 1;    // bump: u8
+}
+
+impl PlatformConfig {
+    /// Reads the config from `config`, which must be the platform config PDA (enforced by the
+    /// caller's seeds constraint). An uninitialized account means no fee.
+    pub fn load(config: &AccountInfo) -> Result<Option<PlatformConfig>> {
+        if config.data_is_empty() {
+            return Ok(None);
+        }
+        require_keys_eq!(*config.owner, crate::ID, StreamError::Unauthorized);
+        Ok(Some(PlatformConfig::try_deserialize(&mut &config.data.borrow()[..])?))
+    }
+
+    /// Range a market's `consensus_bps` must fall in, defaulting when there is no config
+    pub fn consensus_bounds(config: Option<&PlatformConfig>) -> (u16, u16) {
+        config.map_or((DEFAULT_MIN_CONSENSUS_BPS, DEFAULT_MAX_CONSENSUS_BPS), |config| {
+            (config.min_consensus_bps, config.max_consensus_bps)
+        })
+    }
+
+    /// Whether a stream created at `created_at` has outlived the platform's maximum lifetime.
+    /// Expired streams can no longer pay out, and anyone may archive them so donors can refund.
+    pub fn stream_expired(config: Option<&PlatformConfig>, created_at: i64, now: i64) -> bool {
+        config.is_some_and(|config| {
+            config.max_stream_lifetime > 0 && now >= created_at.saturating_add(config.max_stream_lifetime)
+        })
+    }
+
+    pub fn validator_slash_on(&self, stake: u64) -> u64 {
+        (stake as u128 * self.validator_slash_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    pub fn fee_on(&self, amount: u64) -> u64 {
+        (amount as u128 * self.fee_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+}
+
+#[event]
+pub struct PlatformConfigUpdated {
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ConsensusBoundsUpdated {
+    pub min_consensus_bps: u16,
+    pub max_consensus_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MaxStreamLifetimeUpdated {
+    pub max_stream_lifetime: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ValidatorSlashUpdated {
+    pub validator_slash_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlatformFeeCollected {
+    pub source: Pubkey,         // Stream or market the fee was taken from
+    pub mint: Pubkey,
+    pub gross_amount: u64,
+    pub fee: u64,
+    pub referral_amount: u64,   // Part of `fee` credited to the host's referrer
+    pub timestamp: i64,
+}
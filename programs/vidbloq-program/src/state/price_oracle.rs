@@ -0,0 +1,193 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{hash::hash, pubkey};
+
+use crate::state::{StreamError, BPS_DENOMINATOR};
+
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LyCJ");
+pub const SWITCHBOARD_ON_DEMAND_PROGRAM_ID: Pubkey = pubkey!("SBondMDrcV3K4kxZR1HNVT7osZxAHVHgYXL5Ze1oMUv");
+pub const MAX_PRICE_AGE: i64 = 60; // Oldest price, in seconds, a market may resolve from
+pub const MAX_PRICE_CONFIDENCE_BPS: u64 = 100; // Widest confidence interval accepted, relative to the price
+
+// Switchboard on-demand `PullFeedAccountData` is zero-copy; these are its field offsets
+// including the discriminator. Results are fixed point with 18 decimals.
+const SWITCHBOARD_LAST_UPDATE_OFFSET: usize = 2216;
+const SWITCHBOARD_RESULT_OFFSET: usize = 2264;
+const SWITCHBOARD_EXPO: i32 = -18;
+
+/// Publisher of the price account a market resolves from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OracleProvider {
+    /// Pyth pull oracle `PriceUpdateV2` account, fully verified
+    Pyth,
+    /// Switchboard on-demand pull feed
+    Switchboard,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriceComparison {
+    Above,
+    Below,
+}
+
+/// How a market learns its outcome
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ResolutionSource {
+    /// Host, validators, reporters or proposals, as configured on the market
+    #[default]
+    Manual,
+    /// Only this neutral key may call `resolve_market`; validators take over if it has not
+    /// resolved by the market's resolution timeout
+    Arbiter(Pubkey),
+    /// A binary market that settles to outcome 0 when the feed's price compares true against
+    /// `target * 10^target_expo` at the resolution time, and to outcome 1 otherwise
+    PriceOracle {
+        provider: OracleProvider,
+        feed: Pubkey,
+        comparison: PriceComparison,
+        target: i64,
+        target_expo: i32,
+    },
+    /// Only validator consensus settles the market; the host, reporters and proposals have no say
+    Consensus,
+}
+
+impl ResolutionSource {
+    pub const SPACE: usize = 1 + 1 + 32 + 1 + 8 + 4;
+
+    /// Fails unless nobody but the host and validators settles the market
+    pub fn require_manual(&self) -> Result<()> {
+        match self {
+            ResolutionSource::Manual => Ok(()),
+            ResolutionSource::Arbiter(_) => err!(StreamError::ArbiterResolutionRequired),
+            ResolutionSource::PriceOracle { .. } => err!(StreamError::PriceOracleResolutionRequired),
+            ResolutionSource::Consensus => err!(StreamError::ConsensusResolutionRequired),
+        }
+    }
+}
+
+/// A price read from an oracle account: `price * 10^expo`, give or take `confidence`
+#[derive(Clone, Copy, Debug)]
+pub struct OraclePrice {
+    pub price: i128,
+    pub confidence: u128,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+impl OraclePrice {
+    /// Reads `account`, checking it is owned by the provider's program and has its layout
+    pub fn read(provider: OracleProvider, account: &AccountInfo) -> Result<OraclePrice> {
+        let data = account.try_borrow_data()?;
+        match provider {
+            OracleProvider::Pyth => {
+                require_keys_eq!(*account.owner, PYTH_RECEIVER_PROGRAM_ID, StreamError::InvalidPriceFeed);
+                Self::read_pyth(&data)
+            }
+            OracleProvider::Switchboard => {
+                require_keys_eq!(*account.owner, SWITCHBOARD_ON_DEMAND_PROGRAM_ID, StreamError::InvalidPriceFeed);
+                Self::read_switchboard(&data)
+            }
+        }
+    }
+
+    /// `PriceUpdateV2`: write authority, verification level, then the price message
+    fn read_pyth(data: &[u8]) -> Result<OraclePrice> {
+        require!(
+            data.len() > 41 && data[..8] == account_discriminator("PriceUpdateV2"),
+            StreamError::InvalidPriceFeed
+        );
+        // Verification level is a Borsh enum: Partial { num_signatures } (0) or Full (1)
+        require!(data[40] == 1, StreamError::InvalidPriceFeed);
+        let message = &data[41..];
+        require!(message.len() >= 32 + 8 + 8 + 4 + 8, StreamError::InvalidPriceFeed);
+        Ok(OraclePrice {
+            price: read_i64(message, 32) as i128,
+            confidence: read_u64(message, 40) as u128,
+            expo: i32::from_le_bytes(message[48..52].try_into().unwrap()),
+            publish_time: read_i64(message, 52),
+        })
+    }
+
+    /// `PullFeedAccountData`: the current result's value and standard deviation
+    fn read_switchboard(data: &[u8]) -> Result<OraclePrice> {
+        require!(
+            data.len() >= SWITCHBOARD_RESULT_OFFSET + 32 && data[..8] == account_discriminator("PullFeedAccountData"),
+            StreamError::InvalidPriceFeed
+        );
+        let value = i128::from_le_bytes(data[SWITCHBOARD_RESULT_OFFSET..SWITCHBOARD_RESULT_OFFSET + 16].try_into().unwrap());
+        let std_dev =
+            i128::from_le_bytes(data[SWITCHBOARD_RESULT_OFFSET + 16..SWITCHBOARD_RESULT_OFFSET + 32].try_into().unwrap());
+        Ok(OraclePrice {
+            price: value,
+            confidence: std_dev.unsigned_abs(),
+            expo: SWITCHBOARD_EXPO,
+            publish_time: read_i64(data, SWITCHBOARD_LAST_UPDATE_OFFSET),
+        })
+    }
+
+    /// Fails unless the price was published after `not_before` and within `MAX_PRICE_AGE` of
+    /// `now`, with a confidence interval no wider than `MAX_PRICE_CONFIDENCE_BPS` of the price
+    pub fn require_usable(&self, not_before: i64, now: i64) -> Result<()> {
+        require!(
+            self.publish_time >= not_before && now.saturating_sub(self.publish_time) <= MAX_PRICE_AGE,
+            StreamError::StalePrice
+        );
+        require!(self.price > 0, StreamError::InvalidPriceFeed);
+        require!(
+            self.confidence * BPS_DENOMINATOR as u128 <= self.price.unsigned_abs() * MAX_PRICE_CONFIDENCE_BPS as u128,
+            StreamError::PriceConfidenceTooWide
+        );
+        Ok(())
+    }
+
+    /// Whether this price compares true against `target * 10^target_expo`
+    pub fn satisfies(&self, comparison: PriceComparison, target: i64, target_expo: i32) -> Result<bool> {
+        let expo = self.expo.min(target_expo);
+        let scale = |value: i128, from: i32| -> Result<i128> {
+            10i128
+                .checked_pow((from - expo) as u32)
+                .and_then(|factor| value.checked_mul(factor))
+                .ok_or(error!(StreamError::MathOverflow))
+        };
+        let price = scale(self.price, self.expo)?;
+        let target = scale(target as i128, target_expo)?;
+        Ok(match comparison {
+            PriceComparison::Above => price > target,
+            PriceComparison::Below => price < target,
+        })
+    }
+}
+
+fn account_discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(format!("account:{}", name).as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+fn read_i64(data: &[u8], offset: usize) -> i64 {
+    i64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[event]
+pub struct MarketArbiterChanged {
+    pub market: Pubkey,
+    pub previous_arbiter: Pubkey,
+    pub new_arbiter: Pubkey,
+    pub changed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PriceOracleResolved {
+    pub market: Pubkey,
+    pub feed: Pubkey,
+    pub price: i128,
+    pub expo: i32,
+    pub publish_time: i64,
+    pub winning_outcome: u8,
+    pub timestamp: i64,
+}
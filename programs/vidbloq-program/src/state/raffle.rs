@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+
+/// Where a raffle is in its life
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RaffleStatus {
+    Open,      // Taking entries until `entry_deadline`
+    Drawing,   // Waiting on the VRF callback
+    Drawn,     // Winner known, prize not yet claimed
+    Claimed,   // Prize paid to the winner
+    Cancelled, // Nobody entered; the prize went back to its funder
+}
+
+impl RaffleStatus {
+    pub const SPACE: usize = 1;
+}
+
+/// A giveaway run by a stream host. The prize is escrowed in the raffle vault at creation and
+/// the winning entry is picked by Ephemeral VRF, so neither the host nor an entrant can steer it.
+#[account]
+pub struct Raffle {
+    pub stream: Pubkey,         // Stream the raffle runs on
+    pub host: Pubkey,           // Stream host, who created it
+    pub mint: Pubkey,           // Mint of the prize and tickets
+    pub raffle_id: u64,         // Host-chosen id so one stream can run several raffles
+    pub funder_token: Pubkey,   // Token account the prize came from and returns to if nobody enters
+    pub prize: u64,             // Tokens escrowed for the winner
+    pub ticket_price: u64,      // Paid into the stream vault per entry, 0 for a free raffle
+    pub entry_deadline: i64,    // Entries close and the draw may start at this time
+    pub max_entries: u32,       // Entry cap, 0 for none
+    pub entry_count: u32,       // Entries so far; entry indices run 0..entry_count
+    pub status: RaffleStatus,   // See RaffleStatus
+    pub draw_requested_at: i64, // When the latest VRF request was made, 0 before the draw
+    pub winning_entry: u32,     // Index of the winning entry once drawn
+    pub winner: Pubkey,         // Wallet that claimed the prize, default until claimed
+    pub created_at: i64,        // Creation time
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for Raffle {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 32    // host: Pubkey
+        + 32    // mint: Pubkey
+        + 8     // raffle_id: u64
+        + 32    // funder_token: Pubkey
+        + 8     // prize: u64
+        + 8     // ticket_price: u64
+        + 8     // entry_deadline: i64
+        + 4     // max_entries: u32
+        + 4     // entry_count: u32
+        + RaffleStatus::SPACE // status: RaffleStatus
+        + 8     // draw_requested_at: i64
+        + 4     // winning_entry: u32
+        + 32    // winner: Pubkey
+        + 8     // created_at: i64
+        + 1;    // bump: u8
+}
+
+/// One wallet's entry in a raffle; its existence stops the wallet entering twice
+#[account]
+pub struct RaffleEntry {
+    pub raffle: Pubkey,         // Raffle entered
+    pub entrant: Pubkey,        // Wallet paid if this entry is drawn
+    pub index: u32,             // Position in entry order, compared against the winning entry
+    pub entered_at: i64,        // Entry time
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for RaffleEntry {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // raffle: Pubkey
+        + 32    // entrant: Pubkey
+        + 4     // index: u32
+        + 8     // entered_at: i64
+        + 1;    // bump: u8
+}
+
+#[event]
+pub struct RaffleCreated {
+    pub raffle: Pubkey,
+    pub stream: Pubkey,
+    pub prize: u64,
+    pub ticket_price: u64,
+    pub entry_deadline: i64,
+    pub sponsored: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RaffleEntered {
+    pub raffle: Pubkey,
+    pub entrant: Pubkey,
+    pub index: u32,
+    pub ticket_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RaffleDrawRequested {
+    pub raffle: Pubkey,
+    pub requestor: Pubkey,
+    pub entry_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RaffleDrawn {
+    pub raffle: Pubkey,
+    pub winning_entry: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RafflePrizeClaimed {
+    pub raffle: Pubkey,
+    pub winner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RaffleCancelled {
+    pub raffle: Pubkey,
+    pub refunded: u64,
+    pub timestamp: i64,
+}
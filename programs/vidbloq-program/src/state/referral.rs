@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+/// Host-referral earnings of one referrer in one mint, rolled up per Solana epoch
+#[account]
+pub struct ReferralRewards {
+    pub referrer: Pubkey,       // Growth partner
+    pub mint: Pubkey,           // Mint the rewards are paid in
+    pub epoch: u64,             // Epoch currently accruing
+    pub epoch_accrued: u64,     // Accrued during `epoch`
+    pub total_accrued: u64,     // Lifetime accrued
+    pub total_claimed: u64,     // Lifetime claimed
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for ReferralRewards {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // referrer: Pubkey
+        + 32    // mint: Pubkey
+        + 8     // epoch: u64
+        + 8     // epoch_accrued: u64
+        + 8     // total_accrued: u64
+        + 8     // total_claimed: u64
+        + 1;    // bump: u8
+}
+
+impl ReferralRewards {
+    /// Adds `amount` to the current epoch, closing out the previous epoch's tally first
+    pub fn accrue(&mut self, host: Pubkey, amount: u64, epoch: u64) -> Result<()> {
+        if epoch != self.epoch {
+            if self.epoch_accrued > 0 {
+                emit!(ReferralEpochClosed {
+                    referrer: self.referrer,
+                    mint: self.mint,
+                    epoch: self.epoch,
+                    accrued: self.epoch_accrued,
+                });
+            }
+            self.epoch = epoch;
+            self.epoch_accrued = 0;
+        }
+        self.epoch_accrued = self.epoch_accrued.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        self.total_accrued = self.total_accrued.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+
+        emit!(HostReferralAccrued {
+            referrer: self.referrer,
+            host,
+            mint: self.mint,
+            epoch,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn claimable(&self) -> u64 {
+        self.total_accrued.saturating_sub(self.total_claimed)
+    }
+}
+
+#[event]
+pub struct HostReferralAccrued {
+    pub referrer: Pubkey,
+    pub host: Pubkey,
+    pub mint: Pubkey,
+    pub epoch: u64,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralEpochClosed {
+    pub referrer: Pubkey,
+    pub mint: Pubkey,
+    pub epoch: u64,
+    pub accrued: u64,
+}
+
+#[event]
+pub struct HostReferralRewardsClaimed {
+    pub referrer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Bet-referral earnings of one affiliate in one mint, escrowed in a vault the PDA owns. Markets
+/// pay in a share of the host's fee whenever a bettor the affiliate referred claims winnings.
+#[account]
+pub struct ReferralEarnings {
+    pub referrer: Pubkey,       // Affiliate
+    pub mint: Pubkey,           // Mint the earnings are paid in
+    pub referred_claims: u64,   // Claims by referred bettors that paid a share
+    pub total_earned: u64,      // Lifetime earned
+    pub total_claimed: u64,     // Lifetime claimed
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for ReferralEarnings {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // referrer: Pubkey
+        + 32    // mint: Pubkey
+        + 8     // referred_claims: u64
+        + 8     // total_earned: u64
+        + 8     // total_claimed: u64
+        + 1;    // bump: u8
+}
+
+impl ReferralEarnings {
+    pub fn accrue(&mut self, amount: u64) -> Result<()> {
+        self.referred_claims = self.referred_claims.checked_add(1).ok_or(StreamError::MathOverflow)?;
+        self.total_earned = self.total_earned.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn claimable(&self) -> u64 {
+        self.total_earned.saturating_sub(self.total_claimed)
+    }
+}
+
+#[event]
+pub struct BetReferralEarned {
+    pub referrer: Pubkey,
+    pub market: Pubkey,
+    pub bettor: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralEarningsClaimed {
+    pub referrer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
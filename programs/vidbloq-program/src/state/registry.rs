@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+/// Program-wide counters so explorers can show totals without scanning every account.
+/// Volumes add raw token amounts across all mints. Nothing is counted before the registry exists.
+#[account]
+pub struct GlobalRegistry {
+    pub stream_count: u64,        // Streams created
+    pub total_deposited: u128,    // Sum of all deposits, in raw token units
+    pub total_distributed: u128,  // Sum of all distributions, in raw token units
+    pub created_at: i64,          // Counting started at this time
+    pub bump: u8,                 // PDA bump
+}
+
+impl Space for GlobalRegistry {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 8     // stream_count: u64
+        + 16    // total_deposited: u128
+        + 16    // total_distributed: u128
+        + 8     // created_at: i64
+        + 1;    // bump: u8
+}
+
+impl GlobalRegistry {
+    /// Applies `update` to the registry at `registry`, which must be the registry PDA (enforced
+    /// by the caller's seeds constraint). Does nothing while the registry is uninitialized.
+    fn update(registry: &AccountInfo, update: impl FnOnce(&mut GlobalRegistry) -> Result<()>) -> Result<()> {
+        if registry.data_is_empty() {
+            return Ok(());
+        }
+        require_keys_eq!(*registry.owner, crate::ID, StreamError::Unauthorized);
+        let mut state = GlobalRegistry::try_deserialize(&mut &registry.data.borrow()[..])?;
+        update(&mut state)?;
+        state.try_serialize(&mut &mut registry.data.borrow_mut()[..])?;
+        Ok(())
+    }
+
+    pub fn record_stream(registry: &AccountInfo) -> Result<()> {
+        Self::update(registry, |state| {
+            state.stream_count = state.stream_count.checked_add(1).ok_or(StreamError::MathOverflow)?;
+            Ok(())
+        })
+    }
+
+    pub fn record_deposit(registry: &AccountInfo, amount: u64) -> Result<()> {
+        Self::update(registry, |state| {
+            state.total_deposited = state.total_deposited.checked_add(amount as u128).ok_or(StreamError::MathOverflow)?;
+            Ok(())
+        })
+    }
+
+    pub fn record_distribution(registry: &AccountInfo, amount: u64) -> Result<()> {
+        Self::update(registry, |state| {
+            state.total_distributed = state.total_distributed.checked_add(amount as u128).ok_or(StreamError::MathOverflow)?;
+            Ok(())
+        })
+    }
+}
+
+/// Every stream a host has created, in creation order. Grows by one entry per stream.
+#[account]
+pub struct HostStreamIndex {
+    pub host: Pubkey,             // Host wallet
+    pub streams: Vec<Pubkey>,     // Stream PDAs
+    pub bump: u8,                 // PDA bump
+}
+
+impl HostStreamIndex {
+    pub fn space(stream_count: usize) -> usize {
+        8       // Discriminator
+        + 32    // host: Pubkey
+        + 4 + stream_count * 32 // streams: Vec<Pubkey>
+        + 1     // bump: u8
+    }
+}
+
+#[event]
+pub struct GlobalRegistryInitialized {
+    pub payer: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+pub const MAX_SESSION_DURATION: i64 = 7 * 24 * 60 * 60; // Longest a session key may act for its owner
+
+/// Lets an ephemeral key spend up to `spend_limit` of the owner's tokens on bets and deposits
+/// until `expires_at`. The session PDA is the SPL delegate of the owner's token account, so a
+/// relayer holding the key can sign and pay fees without ever holding the owner's funds.
+#[account]
+pub struct SessionKey {
+    pub owner: Pubkey,          // Wallet whose tokens the session spends
+    pub session_key: Pubkey,    // Ephemeral key allowed to sign for the owner
+    pub owner_token: Pubkey,    // Owner's token account the session is delegate of
+    pub spend_limit: u64,       // Most the session may spend over its lifetime
+    pub spent: u64,             // Spent so far
+    pub expires_at: i64,        // Session stops working at this time
+    pub created_at: i64,        // Creation time
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for SessionKey {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // owner: Pubkey
+        + 32    // session_key: Pubkey
+        + 32    // owner_token: Pubkey
+        + 8     // spend_limit: u64
+        + 8     // spent: u64
+        + 8     // expires_at: i64
+        + 8     // created_at: i64
+        + 1;    // bump: u8
+}
+
+impl SessionKey {
+    /// Books `amount` against the session, failing once it has expired or would overspend
+    pub fn spend(&mut self, amount: u64, now: i64) -> Result<()> {
+        require!(now < self.expires_at, StreamError::SessionExpired);
+        let spent = self.spent.checked_add(amount).ok_or(StreamError::MathOverflow)?;
+        require!(spent <= self.spend_limit, StreamError::SessionSpendLimitExceeded);
+        self.spent = spent;
+        Ok(())
+    }
+}
+
+#[event]
+pub struct SessionCreated {
+    pub owner: Pubkey,
+    pub session_key: Pubkey,
+    pub spend_limit: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SessionRevoked {
+    pub owner: Pubkey,
+    pub session_key: Pubkey,
+    pub spent: u64,
+    pub timestamp: i64,
+}
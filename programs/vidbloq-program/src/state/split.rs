@@ -0,0 +1,86 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{StreamError, BPS_DENOMINATOR};
+
+pub const MAX_SPLIT_RECIPIENTS: usize = 8;
+
+/// Host-defined revenue split for a stream; `distribute_split` pays every share in one call
+#[account]
+pub struct SplitConfig {
+    pub stream: Pubkey,                 // Stream the split pays out of
+    pub shares: Vec<SplitShare>,        // Recipients and their basis points, summing to 10_000
+    pub updated_at: i64,                // Last time the host changed the split
+    pub bump: u8,                       // PDA bump
+}
+
+impl Space for SplitConfig {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 4 + SplitShare::SPACE * MAX_SPLIT_RECIPIENTS // shares: Vec<SplitShare>
+        + 8     // updated_at: i64
+        + 1;    // bump: u8
+}
+
+impl SplitConfig {
+    /// Checks that `shares` name distinct recipients whose basis points add up to the whole
+    pub fn validate(shares: &[SplitShare]) -> Result<()> {
+        require!(
+            !shares.is_empty() && shares.len() <= MAX_SPLIT_RECIPIENTS,
+            StreamError::InvalidSplit
+        );
+        let mut total_bps: u64 = 0;
+        for (i, share) in shares.iter().enumerate() {
+            require!(share.bps > 0, StreamError::InvalidSplit);
+            require!(
+                !shares[..i].iter().any(|s| s.recipient == share.recipient),
+                StreamError::InvalidSplit
+            );
+            total_bps += share.bps as u64;
+        }
+        require!(total_bps == BPS_DENOMINATOR, StreamError::InvalidSplit);
+        Ok(())
+    }
+
+    /// Divides `amount` by share. Rounding dust goes to the first recipient so nothing is left behind.
+    pub fn amounts(&self, amount: u64) -> Result<Vec<u64>> {
+        let mut amounts: Vec<u64> = self
+            .shares
+            .iter()
+            .map(|share| (amount as u128 * share.bps as u128 / BPS_DENOMINATOR as u128) as u64)
+            .collect();
+        let paid = amounts.iter().try_fold(0u64, |sum, a| sum.checked_add(*a));
+        let dust = paid
+            .and_then(|paid| amount.checked_sub(paid))
+            .ok_or(StreamError::MathOverflow)?;
+        amounts[0] = amounts[0].checked_add(dust).ok_or(StreamError::MathOverflow)?;
+        Ok(amounts)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq)]
+pub struct SplitShare {
+    pub recipient: Pubkey,
+    pub bps: u16,
+}
+
+impl SplitShare {
+    pub const SPACE: usize = 32 + 2;
+}
+
+#[event]
+pub struct SplitConfigSet {
+    pub stream: Pubkey,
+    pub shares: Vec<SplitShare>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SplitDistributed {
+    pub stream: Pubkey,
+    pub amount: u64,
+    pub platform_fee: u64,
+    pub burned: u64,
+    pub recipients: Vec<Pubkey>,
+    pub amounts: Vec<u64>,
+    pub timestamp: i64,
+}
@@ -1,5 +1,7 @@
 use anchor_lang::prelude::*;
 
+use crate::state::{PlatformConfig, BPS_DENOMINATOR};
+
 #[account]
 pub struct StreamState {
     pub host: Pubkey,
@@ -13,8 +15,49 @@ pub struct StreamState {
     pub start_time: Option<i64>,
     pub end_time: Option<i64>,  
     pub stream_type: StreamType, 
+    pub goal_amount: u64,                 // 0 when no fundraising goal is set
+    pub milestone_percentages: Vec<u16>,  // Ascending percentages of goal_amount
+    pub milestones_reached: u8,           // Index of the next milestone to celebrate
+    pub receipt_count: u64,               // Number of donation receipts issued
+    pub burn_rate_per_hour: u64,          // Rolling average of distributed tokens per hour
+    pub last_distribution_at: i64,        // 0 until the first distribution
+    pub metadata_hash: [u8; 32],          // Host-committed hash of off-chain stream metadata
+    pub approval_nonce: u64,              // Next nonce accepted for host-signed approvals
+    pub matching_active: bool,            // Deposits must be matched from the stream's pledge
+    pub leaderboard_active: bool,         // Deposits must update the stream's leaderboard
+    pub refund_deadline: Option<i64>,     // Refunds on active streams close at this time
+    pub blocked_count: u32,               // Wallets currently on the stream's blocklist
+    pub payout_nonce: u64,                // Seed for the next escrowed payout
+    pub payout_allowlist_strict: bool,    // Payouts may only go to allowlisted recipients
+    pub dust_refund_threshold: u64,       // Balances at or below this may be pushed back after cancellation
+    pub has_distribution_policy: bool,    // Payouts are checked against a DistributionPolicy
+    pub extension_rate_per_minute: u64,   // Price of pushing end_time back a minute, 0 when not for sale
+    pub access_gated: bool,               // Depositors and bettors must meet the stream's AccessGate
+    pub stream_id: u64,                   // Host's creation nonce; seeds the PDA so the name may change
+    pub burn_bps: u16,                    // Share of each distribution burned, 0 when deflationary mode is off
+    pub extra_mints: Vec<StreamMintBalance>, // Additional accepted mints and their accounting
+    pub version: u8,                      // Layout version, 0 for streams created before versioning
+    pub seed_kind: StreamSeedKind,        // Which seeds derive the stream's address
+    pub ticket_revenue: u64,              // Raffle ticket sales held in the vault; owed to no donor
+    pub refunds_written_off: u64,         // Payouts cancelled-stream donors absorbed when refunded pro rata
 }
 
+pub const SECONDS_PER_HOUR: u64 = 3600;
+pub const BURN_RATE_SMOOTHING: u64 = 4; // Each distribution weighs 1/4 of the rolling average
+
+pub const MAX_MILESTONES: usize = 8;
+pub const MAX_MILESTONE_PERCENTAGE: u16 = 1000; // Allow stretch goals up to 10x
+
+/// `StreamState::mint` sentinel for streams that hold native SOL in a system-owned vault
+pub const NATIVE_SOL_MINT: Pubkey = Pubkey::new_from_array([0u8; 32]);
+
+pub const MAX_EXTRA_MINTS: usize = 4;
+
+pub const MAX_BURN_BPS: u16 = 5_000; // Recipients always get at least half of a payout
+
+/// Layout written by this program; older streams are brought up to it by `migrate_stream`
+pub const STREAM_VERSION: u8 = 4;
+
 impl Space for StreamState {
     const INIT_SPACE: usize = 8      // Discriminator
         + 32    // host: Pubkey
@@ -27,7 +70,249 @@ impl Space for StreamState {
         + 8     // created_at: i64
         + 1 + 8 // start_time: Option<i64> (1 byte for Some/None + 8 bytes data)
         + 1 + 8 // end_time: Option<i64>
-        + 1 + 16; // stream_type: StreamType (1 byte variant + max variant size)
+        + 1 + 16 // stream_type: StreamType (1 byte variant + max variant size)
+        + 8     // goal_amount: u64
+        + 4 + 2 * MAX_MILESTONES // milestone_percentages: Vec<u16>
+        + 1     // milestones_reached: u8
+        + 8     // receipt_count: u64
+        + 8     // burn_rate_per_hour: u64
+        + 8     // last_distribution_at: i64
+        + 32    // metadata_hash: [u8; 32]
+        + 8     // approval_nonce: u64
+        + 1     // matching_active: bool
+        + 1     // leaderboard_active: bool
+        + 1 + 8 // refund_deadline: Option<i64>
+        + 4     // blocked_count: u32
+        + 8     // payout_nonce: u64
+        + 1     // payout_allowlist_strict: bool
+        + 8     // dust_refund_threshold: u64
+        + 1     // has_distribution_policy: bool
+        + 8     // extension_rate_per_minute: u64
+        + 1     // access_gated: bool
+        + 8     // stream_id: u64
+        + 2     // burn_bps: u16
+        + 4 + StreamMintBalance::SPACE * MAX_EXTRA_MINTS // extra_mints: Vec<StreamMintBalance>
+        + 1     // version: u8
+        + 1 + 4 + 32 // seed_kind: StreamSeedKind (variant + name seed)
+        + 8     // ticket_revenue: u64
+        + 8;    // refunds_written_off: u64
+}
+
+impl StreamState {
+    /// Display names are free-form but must fit the space reserved for them
+    pub fn require_valid_name(name: &str) -> Result<()> {
+        require!(
+            name.len() >= 4 && name.len() <= 32,
+            StreamError::NameLengthInvalid
+        );
+        Ok(())
+    }
+
+    /// Seed after `b"stream"`: the host, or the original name for name-seeded streams
+    pub fn first_seed(&self) -> Vec<u8> {
+        match &self.seed_kind {
+            StreamSeedKind::Nonce => self.host.to_bytes().to_vec(),
+            StreamSeedKind::Name { seed_name } => seed_name.as_bytes().to_vec(),
+        }
+    }
+
+    /// Last seed before the bump: the host's creation nonce, or the host for name-seeded streams
+    pub fn second_seed(&self) -> Vec<u8> {
+        match &self.seed_kind {
+            StreamSeedKind::Nonce => self.stream_id.to_le_bytes().to_vec(),
+            StreamSeedKind::Name { .. } => self.host.to_bytes().to_vec(),
+        }
+    }
+
+    /// Seeds the stream signs its vault transfers with, bump included
+    pub fn signer_seeds(&self) -> Vec<Vec<u8>> {
+        vec![b"stream".to_vec(), self.first_seed(), self.second_seed(), vec![self.bump]]
+    }
+
+    /// Address the stream's seeds and bump derive
+    pub fn address(&self) -> Result<Pubkey> {
+        let seeds = self.signer_seeds();
+        let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+        Pubkey::create_program_address(&seeds, &crate::ID).map_err(|_| error!(StreamError::InvalidStreamAccount))
+    }
+
+    /// Undistributed balance of the stream's mint, donations and raffle ticket revenue alike.
+    /// Payouts absorbed by refunded donors no longer weigh on the donations that remain.
+    pub fn available_balance(&self) -> Result<u64> {
+        let held = self.total_deposited
+            .checked_add(self.ticket_revenue)
+            .and_then(|held| held.checked_add(self.refunds_written_off))
+            .ok_or(StreamError::MathOverflow)?;
+        Ok(held.checked_sub(self.total_distributed).ok_or(StreamError::MathOverflow)?)
+    }
+
+    /// Part of the available balance donors can claim back. Payouts are drawn from ticket
+    /// revenue first, so donors only bear what has been paid out beyond it.
+    pub fn refundable_balance(&self) -> Result<u64> {
+        Ok(self.available_balance()?.min(self.total_deposited))
+    }
+
+    /// What refunding `contribution` of a donor's deposits pays. Active streams repay it in full;
+    /// cancelled streams pay every donor the same share of the refundable balance, whatever
+    /// order they leave in.
+    pub fn refund_payout(&self, contribution: u64) -> Result<u64> {
+        if self.status != StreamStatus::Cancelled {
+            return Ok(contribution);
+        }
+        let refundable = self.refundable_balance()?;
+        if contribution >= self.total_deposited {
+            return Ok(refundable);
+        }
+        let share = (contribution as u128)
+            .checked_mul(refundable as u128)
+            .ok_or(StreamError::MathOverflow)?
+            / self.total_deposited as u128;
+        Ok(share as u64)
+    }
+
+    /// Takes a refunded `contribution` out of the deposits, writing off what `payout` fell short
+    /// of it so the remaining donors keep the same ratio
+    pub fn retire_refund(&mut self, contribution: u64, payout: u64) -> Result<()> {
+        self.total_deposited = self.total_deposited.checked_sub(contribution).ok_or(StreamError::MathOverflow)?;
+        let written_off = contribution.checked_sub(payout).ok_or(StreamError::MathOverflow)?;
+        self.refunds_written_off = self.refunds_written_off.checked_add(written_off).ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn is_native_sol(&self) -> bool {
+        self.mint == NATIVE_SOL_MINT
+    }
+
+    /// Part of a payout burned under the stream's deflationary mode, rounded down
+    pub fn burn_share(&self, amount: u64) -> u64 {
+        (amount as u128 * self.burn_bps as u128 / BPS_DENOMINATOR as u128) as u64
+    }
+
+    /// Accounting for an additional mint registered with `add_stream_mint`
+    pub fn extra_mint_mut(&mut self, mint: &Pubkey) -> Result<&mut StreamMintBalance> {
+        self.extra_mints
+            .iter_mut()
+            .find(|balance| balance.mint == *mint)
+            .ok_or(error!(StreamError::MintNotAccepted))
+    }
+
+    /// Checks the stream type rules for accepting new deposits
+    pub fn require_deposits_open(&self) -> Result<()> {
+        match self.stream_type {
+            StreamType::Prepaid { .. } => {
+                // For prepaid, deposits allowed anytime before start
+                require!(
+                    self.start_time.is_none(),
+                    StreamError::StreamAlreadyStarted
+                );
+            },
+            StreamType::Live => {
+                // For live streams, must be active and started
+                require!(
+                    self.status == StreamStatus::Active && 
+                    self.start_time.is_some(),
+                    StreamError::DepositNotAllowed
+                );
+            },
+            StreamType::Conditional { .. } => {
+                // For conditional, check if stream is active
+                require!(
+                    self.status == StreamStatus::Active,
+                    StreamError::StreamNotActive
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Host payouts require an active stream that has met its type-specific unlock conditions and end time
+    /// In strict payout mode, only recipients with a `PayoutRecipient` entry may be paid
+    pub fn require_recipient_allowed(&self, allowlisted: bool) -> Result<()> {
+        require!(!self.payout_allowlist_strict || allowlisted, StreamError::RecipientNotAllowed);
+        Ok(())
+    }
+
+    pub fn require_distribution_allowed(&self) -> Result<()> {
+        // Check if stream is still active
+        require!(self.status == StreamStatus::Active, StreamError::StreamNotActive);
+
+        // StreamType-specific checks
+        match self.stream_type {
+            StreamType::Prepaid { min_duration } => {
+                // For prepaid, must meet minimum duration
+                let elapsed = Clock::get()?.unix_timestamp
+                    - self.start_time.ok_or(StreamError::StreamNotStarted)?;
+                require!(
+                    elapsed >= min_duration as i64,
+                    StreamError::DurationNotMet
+                );
+            },
+            StreamType::Live => {
+                // No additional restrictions for live streams
+            },
+            StreamType::Conditional { min_amount, unlock_time } => {
+                // Check minimum amount if specified
+                if let Some(min) = min_amount {
+                    require!(
+                        self.total_deposited >= min,
+                        StreamError::AmountNotMet
+                    );
+                }
+                // Check unlock time if specified
+                if let Some(time) = unlock_time {
+                    require!(
+                        Clock::get()?.unix_timestamp >= time,
+                        StreamError::TimeLocked
+                    );
+                }
+            }
+        }
+
+        if let Some(end_time) = self.end_time {
+            let current_time = Clock::get()?.unix_timestamp;
+            require!(
+                current_time >= end_time,
+                StreamError::StreamStillLocked
+            );
+        }
+        Ok(())
+    }
+
+    /// Refunds are always open on cancelled streams, never on ended ones, and on
+    /// active streams only until the refund deadline set at initialization
+    pub fn require_refunds_open(&self) -> Result<()> {
+        match self.status {
+            StreamStatus::Cancelled => Ok(()),
+            StreamStatus::Ended => err!(StreamError::StreamAlreadyEnded),
+            StreamStatus::Active => {
+                let deadline = self.refund_deadline.ok_or(StreamError::RefundWindowClosed)?;
+                require!(
+                    Clock::get()?.unix_timestamp < deadline,
+                    StreamError::RefundWindowClosed
+                );
+                Ok(())
+            }
+        }
+    }
+
+    /// Payouts stop once the stream outlives the platform's maximum lifetime
+    pub fn require_within_lifetime(&self, platform_config: &AccountInfo) -> Result<()> {
+        let config = PlatformConfig::load(platform_config)?;
+        require!(
+            !PlatformConfig::stream_expired(config.as_ref(), self.created_at, Clock::get()?.unix_timestamp),
+            StreamError::StreamLifetimeExceeded
+        );
+        Ok(())
+    }
+
+    /// Whether `percentage` of the goal is covered by the current deposits
+    pub fn milestone_met(&self, percentage: u16) -> Result<bool> {
+        let target = (self.goal_amount as u128)
+            .checked_mul(percentage as u128)
+            .ok_or(StreamError::MathOverflow)?
+            / 100;
+        Ok(self.total_deposited as u128 >= target)
+    }
 }
 
 
@@ -38,6 +323,38 @@ pub enum StreamStatus {
     Cancelled,
 }
 
+impl StreamStatus {
+    /// Active streams may end or be cancelled; ended and cancelled streams are final
+    pub fn can_transition_to(self, next: StreamStatus) -> bool {
+        self == next || self == StreamStatus::Active
+    }
+}
+
+/// How a stream's PDA is derived. Streams created before per-host nonces keep the
+/// `[b"stream", name, host]` address they were created at, so their original name is kept
+/// here and `stream_name` stays free to change.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum StreamSeedKind {
+    Nonce,                      // [b"stream", host, stream_id]
+    Name { seed_name: String }, // [b"stream", seed_name, host]
+}
+
+/// Deposits and payouts of one additional mint accepted by a stream
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct StreamMintBalance {
+    pub mint: Pubkey,
+    pub total_deposited: u64,
+    pub total_distributed: u64,
+}
+
+impl StreamMintBalance {
+    pub const SPACE: usize = 32 + 8 + 8;
+
+    pub fn available(&self) -> Result<u64> {
+        Ok(self.total_deposited.checked_sub(self.total_distributed).ok_or(StreamError::MathOverflow)?)
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub enum StreamType {
     Prepaid {
@@ -63,6 +380,17 @@ pub struct DepositMade {
     pub stream: Pubkey,
     pub donor: Pubkey,
     pub amount: u64,
+    pub matched_amount: u64,
+    pub matching_sponsor: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AnonymousDepositMade {
+    pub stream: Pubkey,
+    pub commitment: [u8; 32],
+    pub receipt: Pubkey,
+    pub amount: u64,
     pub timestamp: i64,
 }
 
@@ -71,6 +399,19 @@ pub struct FundsDistributed {
     pub stream: Pubkey,
     pub recipient: Pubkey,
     pub amount: u64,
+    pub burned: u64,                      // Part of amount burned rather than paid out
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BurnRate {
+    pub stream: Pubkey,
+    pub total_distributed: u64,
+    pub active_seconds: u64,
+    pub lifetime_rate_per_hour: u64,
+    pub rolling_rate_per_hour: u64,
+    pub remaining_balance: u64,
+    pub projected_depletion_at: Option<i64>,
     pub timestamp: i64,
 }
 
@@ -83,6 +424,151 @@ pub struct RefundProcessed {
     pub timestamp: i64,
 }
 
+#[event]
+pub struct BatchRefundProcessed {
+    pub stream: Pubkey,
+    pub caller: Pubkey,
+    pub donors_refunded: u32,
+    pub total_refunded: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamMintAdded {
+    pub stream: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintDepositMade {
+    pub stream: Pubkey,
+    pub donor: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintFundsDistributed {
+    pub stream: Pubkey,
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MintRefundProcessed {
+    pub stream: Pubkey,
+    pub donor: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub remaining_balance: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamUpdated {
+    pub stream: Pubkey,
+    pub old_status: StreamStatus,
+    pub new_status: StreamStatus,
+    pub old_end_time: Option<i64>,
+    pub new_end_time: Option<i64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamRenamed {
+    pub stream: Pubkey,
+    pub old_name: String,
+    pub new_name: String,
+    pub timestamp: i64,
+}
+
+/// An expired stream was cancelled by anyone so its donors can refund
+#[event]
+pub struct StreamArchived {
+    pub stream: Pubkey,
+    pub previous_status: StreamStatus,
+    pub archived_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BurnBpsSet {
+    pub stream: Pubkey,
+    pub burn_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamMigrated {
+    pub stream: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExtensionRateSet {
+    pub stream: Pubkey,
+    pub rate_per_minute: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StreamExtended {
+    pub stream: Pubkey,
+    pub payer: Pubkey,
+    pub minutes: u64,
+    pub amount: u64,              // Tokens that reached the vault
+    pub new_end_time: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DustRefundThresholdSet {
+    pub stream: Pubkey,
+    pub threshold: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FundraisingGoalSet {
+    pub stream: Pubkey,
+    pub goal_amount: u64,
+    pub milestone_percentages: Vec<u16>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MilestoneReached {
+    pub stream: Pubkey,
+    pub goal_amount: u64,
+    pub percentage: u16,
+    pub total_deposited: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ForeignTokensSwept {
+    pub stream: Pubkey,
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExcessLamportsSwept {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
 
 #[error_code]
 pub enum StreamError {
@@ -138,6 +624,180 @@ pub enum StreamError {
     #[msg("Name must be between 4 and 32 characters")]
     NameLengthInvalid,
 
+    #[msg("The stream's mints are tracked by stream accounting and cannot be swept")]
+    CannotSweepStreamMint,
+
+    #[msg("Nothing to sweep")]
+    NothingToSweep,
+
+    #[msg("Milestones must be ascending percentages within bounds")]
+    InvalidMilestones,
+
+    #[msg("Donation message is too long")]
+    MessageTooLong,
+
+    #[msg("Tier thresholds must be ascending, non-zero and within bounds")]
+    InvalidTiers,
+
+    #[msg("Donation amount has not reached the first tier")]
+    TierNotReached,
+
+    #[msg("Host approval does not match this stream or nonce")]
+    InvalidApproval,
+
+    #[msg("Host approval has expired")]
+    ApprovalExpired,
+
+    #[msg("Missing ed25519 signature verification instruction")]
+    MissingSignatureVerification,
+
+    #[msg("Signature verification does not cover the expected signer and message")]
+    InvalidSignatureVerification,
+
+    #[msg("Required accounts were not provided")]
+    MissingAccounts,
+
+    #[msg("Match ratio is out of bounds")]
+    InvalidMatchRatio,
+
+    #[msg("Stream is still active")]
+    StreamStillActive,
+
+    #[msg("Leaderboard capacity is out of bounds")]
+    InvalidLeaderboardCapacity,
+
+    #[msg("Refund window is closed")]
+    RefundWindowClosed,
+
+    #[msg("Wallet is blocked by the host")]
+    WalletBlocked,
+
+    #[msg("Wallet is already blocked")]
+    WalletAlreadyBlocked,
+
+    #[msg("Wallet is not blocked")]
+    WalletNotBlocked,
+
+    #[msg("Blocklist page is full")]
+    BlocklistFull,
+
+    #[msg("Stream is not cancelled")]
+    StreamNotCancelled,
+
+    #[msg("Remaining accounts must be non-empty donor account / token account pairs")]
+    InvalidRemainingAccounts,
+
+    #[msg("Stream does not hold native SOL")]
+    NotNativeSolStream,
+
+    #[msg("Nothing to claim")]
+    NothingToClaim,
+
+    #[msg("Mint is not accepted by this stream")]
+    MintNotAccepted,
+
+    #[msg("Mint is already accepted by this stream")]
+    MintAlreadyAccepted,
+
+    #[msg("Stream accepts the maximum number of mints")]
+    TooManyMints,
+
+    #[msg("Payout exceeds the withdrawal queue threshold and must be queued")]
+    WithdrawalMustBeQueued,
+
+    #[msg("Withdrawal queue is not enabled")]
+    WithdrawalQueueDisabled,
+
+    #[msg("Withdrawal release budget for this slot is exhausted")]
+    WithdrawalRateLimited,
+
+    #[msg("Tax year is out of range")]
+    InvalidTaxYear,
+
+    #[msg("Tax year has not ended yet")]
+    TaxYearNotOver,
+
+    #[msg("Jurisdiction must be a two-letter country code")]
+    InvalidJurisdiction,
+
+    #[msg("Pending payout has not expired yet")]
+    PayoutNotExpired,
+
+    #[msg("Recipient is not on the stream's payout allowlist")]
+    RecipientNotAllowed,
+
+    #[msg("Donor balance is above the stream's dust refund threshold")]
+    AboveDustThreshold,
+
+    #[msg("Distribution exceeds the stream's per-payout cap")]
+    DistributionCapExceeded,
+
+    #[msg("Distribution exceeds the stream's 24h payout cap")]
+    DistributionWindowCapExceeded,
+
+    #[msg("Distribution is above the approval threshold and needs a proposal")]
+    ApprovalRequired,
+
+    #[msg("Approver list is invalid")]
+    InvalidApprovers,
+
+    #[msg("Signer is not an approver of this proposal")]
+    NotApprover,
+
+    #[msg("Approver already approved this proposal")]
+    AlreadyApproved,
+
+    #[msg("Proposal does not have enough approvals")]
+    InsufficientApprovals,
+
+    #[msg("Split recipients must be distinct and add up to 10000 bps")]
+    InvalidSplit,
+
+    #[msg("Amount exceeds the recipient's allowance")]
+    AllowanceExceeded,
+
+    #[msg("Stream cannot move to that status")]
+    InvalidStatusTransition,
+
+    #[msg("Stream has outlived the platform's maximum lifetime")]
+    StreamLifetimeExceeded,
+
+    #[msg("Stream has not reached the platform's maximum lifetime")]
+    StreamLifetimeNotReached,
+
+    #[msg("Stream does not sell extensions")]
+    ExtensionsDisabled,
+
+    #[msg("Viewer checked in too recently")]
+    CheckInTooSoon,
+
+    #[msg("Clawback window is out of bounds")]
+    InvalidClawbackWindow,
+
+    #[msg("Clawback window has closed")]
+    ClawbackWindowClosed,
+
+    #[msg("Clawback window is still open")]
+    ClawbackWindowOpen,
+
+    #[msg("Amount exceeds what can be clawed back")]
+    ClawbackExceeded,
+
+    #[msg("Signer does not hold enough of the stream's gate token")]
+    AccessGateNotMet,
+
+    #[msg("Host bond is locked while recent market resolutions can still be disputed")]
+    HostBondLocked,
+
+    #[msg("Burning requires an SPL mint without a freeze authority and a share of at most half")]
+    BurnNotPermitted,
+
+    #[msg("Account is not a stream owned by this program")]
+    InvalidStreamAccount,
+
+    #[msg("Stream is already on the current layout version")]
+    StreamAlreadyMigrated,
+
     // Betting errors
       #[msg("Invalid market setup")]
     InvalidMarketSetup,
@@ -171,6 +831,182 @@ pub enum StreamError {
     InsufficientStakeForValidation,
     #[msg("Already voted")]
     AlreadyVoted,
+    #[msg("Bond below the required minimum")]
+    BondTooLow,
+    #[msg("Challenge window has closed")]
+    ChallengeWindowClosed,
+    #[msg("Challenge window is still open")]
+    ChallengeWindowOpen,
+    #[msg("Invalid proposal state")]
+    InvalidProposalState,
+    #[msg("Invalid payout plan")]
+    InvalidPayoutPlan,
+    #[msg("Weighted markets must be resolved with a ranking")]
+    RankedResolutionRequired,
+    #[msg("Invalid outcome ranking")]
+    InvalidRanking,
+    #[msg("Invalid taper configuration")]
+    InvalidTaper,
+    #[msg("Bet exceeds the current maximum size")]
+    BetTooLarge,
+    #[msg("Market configuration is locked once betting has started")]
+    MarketConfigLocked,
+    #[msg("Secret does not match the voucher")]
+    InvalidVoucherSecret,
+    #[msg("Market rules URI is empty or too long")]
+    InvalidMarketRules,
+    #[msg("Position still has winnings to claim")]
+    WinningsUnclaimed,
+    #[msg("Oracle needs one to three distinct reporters")]
+    InvalidOracleReporters,
+    #[msg("Oracle round has moved on")]
+    StaleOracleRound,
+    #[msg("Reporter already submitted this round")]
+    AlreadyReported,
+    #[msg("Market must be resolved by its oracle")]
+    OracleResolutionRequired,
+    #[msg("Consensus threshold or quorum out of bounds")]
+    InvalidConsensusParams,
+    #[msg("Resolution needs a settlement statement hash")]
+    MissingSettlementStatement,
+    #[msg("Free bet credit has expired")]
+    FreeBetCreditExpired,
+    #[msg("Free bet credit has not expired yet")]
+    FreeBetCreditActive,
+    #[msg("Too many validators for the resolution account")]
+    TooManyValidators,
+    #[msg("Position holds fewer shares of the outcome than requested")]
+    InsufficientShares,
+    #[msg("Positions staked with free bet credit cannot be sold")]
+    FreeBetSharesNotSellable,
+    #[msg("Over/under markets resolve from the reported metric")]
+    MetricResolutionRequired,
+    #[msg("Market has been cancelled")]
+    MarketCancelled,
+    #[msg("Market has not been cancelled")]
+    MarketNotCancelled,
+    #[msg("Not enough liquidity provider shares")]
+    InsufficientLpShares,
+    #[msg("Bet would take the bettor past the market's per-user limit")]
+    BetLimitExceeded,
+    #[msg("Bet would take the pool past the market's limit")]
+    PoolLimitReached,
+    #[msg("Bettor may not bet on this market")]
+    MarketAccessDenied,
+    #[msg("Proof does not place the bettor on the market allowlist")]
+    InvalidAccessProof,
+    #[msg("Resolution time can only move later once bets exist")]
+    ResolutionTimeShortened,
+    #[msg("Parlay legs are invalid or do not match the accounts passed")]
+    InvalidParlayLegs,
+    #[msg("Bettor position must be settled and closed first")]
+    PositionNotClosed,
+    #[msg("The market's claim window has closed")]
+    ClaimWindowClosed,
+    #[msg("Unclaimed funds can only be swept after the claim deadline")]
+    ClaimWindowOpen,
+    #[msg("Validators are resolving this market; only their consensus can settle it")]
+    ConsensusResolutionRequired,
+    #[msg("The resolution can still be disputed")]
+    DisputeWindowOpen,
+    #[msg("The resolution can no longer be disputed")]
+    DisputeWindowClosed,
+    #[msg("Dispute voting is still open")]
+    DisputeVotingOpen,
+    #[msg("Dispute voting has closed")]
+    DisputeVotingClosed,
+    #[msg("Invalid dispute state")]
+    InvalidDisputeState,
+    #[msg("The resolution has not timed out yet")]
+    ResolutionTimeoutPending,
+    #[msg("Account is not a supported price feed")]
+    InvalidPriceFeed,
+    #[msg("Oracle price is stale or predates the resolution time")]
+    StalePrice,
+    #[msg("Oracle price confidence interval is too wide")]
+    PriceConfidenceTooWide,
+    #[msg("This market resolves from its price oracle")]
+    PriceOracleResolutionRequired,
+    #[msg("Only the market's arbiter may resolve it before the resolution timeout")]
+    ArbiterResolutionRequired,
+    #[msg("A randomness request for this market is still in flight")]
+    RandomnessRequestPending,
+    #[msg("No randomness request for this market is in flight")]
+    NoPendingRandomnessRequest,
+    #[msg("The randomness request has not timed out yet")]
+    RandomnessRequestNotExpired,
+    #[msg("The raffle is not taking entries")]
+    RaffleClosed,
+    #[msg("The raffle has reached its entry cap")]
+    RaffleFull,
+    #[msg("The raffle is still taking entries")]
+    RaffleStillOpen,
+    #[msg("Nobody entered the raffle")]
+    NoRaffleEntries,
+    #[msg("The raffle winner has not been drawn")]
+    RaffleNotDrawn,
+    #[msg("This entry did not win the raffle")]
+    NotRaffleWinner,
+    #[msg("A raffle with entries must be drawn")]
+    RaffleHasEntries,
+    #[msg("A bonus pool is split into between 1 and 16 picks")]
+    InvalidBonusWinnerCount,
+    #[msg("The bonus pool is not taking entries")]
+    BonusPoolClosed,
+    #[msg("The bonus pool is still taking entries or claims")]
+    BonusPoolStillOpen,
+    #[msg("No donor entered the bonus pool")]
+    NoBonusPoolEntries,
+    #[msg("The bonus pool has not been drawn")]
+    BonusPoolNotDrawn,
+    #[msg("No pick landed on this donor")]
+    NotBonusWinner,
+    #[msg("The validators' vote did not end in a tie")]
+    NoTieToBreak,
+    #[msg("Nobody holds the winning outcome; take the stake back with claim_refund")]
+    StakeRefundRequired,
+    #[msg("The bet's deadline has passed")]
+    BetDeadlinePassed,
+    #[msg("Price per share is above the bettor's limit")]
+    PriceLimitExceeded,
+    #[msg("The session key has expired")]
+    SessionExpired,
+    #[msg("The session's spend limit would be exceeded")]
+    SessionSpendLimitExceeded,
+    #[msg("The tipster has not bet on this market yet")]
+    NoBetToMirror,
+    #[msg("Parimutuel stakes cannot be sold back before resolution")]
+    SharesNotSellable,
+    #[msg("The validator's stake is still in its deregistration cooldown")]
+    ValidatorCoolingDown,
+    #[msg("No resolution step is due yet")]
+    NothingToCrank,
+    #[msg("Liquidity stays in the market until it resolves or is cancelled")]
+    LiquidityLocked,
+    #[msg("Deposits that drew a sponsor match cannot be refunded while the stream is active")]
+    MatchedDepositNotRefundable,
+    #[msg("Account is not a donor record owned by this program")]
+    InvalidDonorAccount,
+    #[msg("Donor record is already on the current layout")]
+    DonorAccountAlreadyMigrated,
+    #[msg("Markets only request randomness for validator selection or a tie-break")]
+    UnsupportedRandomnessUseCase,
+    #[msg("Validators have already been selected for this resolution")]
+    ValidatorsAlreadySelected,
+    #[msg("This market predates the eligible bettor count; select validators from the registry")]
+    ValidatorRegistryRequired,
+    #[msg("Instruction is not allowed on mainnet")]
+    NotAllowedOnMainnet,
+    #[msg("Instruction is only allowed on mainnet")]
+    MainnetOnly,
+    #[msg("Voucher has expired")]
+    VoucherExpired,
+    #[msg("Voucher has not expired yet")]
+    VoucherNotExpired,
+    #[msg("The host did not resolve this market by hand")]
+    NotHostResolved,
+    #[msg("The host bond can no longer be slashed for this resolution")]
+    SlashWindowClosed,
 }
 
 // Remember to add the enum that Ayo suggested to handle donations and refunds
\ No newline at end of file
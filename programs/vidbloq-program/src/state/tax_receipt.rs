@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+
+use crate::state::StreamError;
+
+pub const MAX_TAX_RECEIPT_STREAMS: usize = 16;
+
+/// A donor's contributions to one host in one mint over a calendar year (UTC). Issued once,
+/// after the year has ended, by the program from the donor's deposit receipts.
+#[account]
+pub struct TaxReceipt {
+    pub host: Pubkey,                 // Receiving host
+    pub donor: Pubkey,                // Contributor's wallet
+    pub mint: Pubkey,                 // Mint of every contribution on the receipt
+    pub year: u16,                    // Calendar year covered
+    pub jurisdiction: [u8; 2],        // Host's jurisdiction at issuance
+    pub total_amount: u64,            // Sum of all lines
+    pub donation_count: u32,          // Deposits counted
+    pub streams: Vec<TaxReceiptLine>, // Per-stream breakdown
+    pub issued_at: i64,               // Issuance time
+    pub bump: u8,                     // PDA bump
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct TaxReceiptLine {
+    pub stream: Pubkey,
+    pub amount: u64,
+    pub donation_count: u32,
+}
+
+impl TaxReceiptLine {
+    pub const SPACE: usize = 32 + 8 + 4;
+}
+
+impl Space for TaxReceipt {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // host: Pubkey
+        + 32    // donor: Pubkey
+        + 32    // mint: Pubkey
+        + 2     // year: u16
+        + 2     // jurisdiction: [u8; 2]
+        + 8     // total_amount: u64
+        + 4     // donation_count: u32
+        + 4 + TaxReceiptLine::SPACE * MAX_TAX_RECEIPT_STREAMS // streams: Vec<TaxReceiptLine>
+        + 8     // issued_at: i64
+        + 1;    // bump: u8
+}
+
+impl TaxReceipt {
+    /// Unix timestamps of Jan 1 00:00 UTC of `year` and of the following year
+    pub fn year_bounds(year: u16) -> Result<(i64, i64)> {
+        require!((1970..=9999).contains(&year), StreamError::InvalidTaxYear);
+        Ok((days_from_civil(year as i64) * 86_400, days_from_civil(year as i64 + 1) * 86_400))
+    }
+}
+
+/// Days between 1970-01-01 and January 1 of `year` in the proleptic Gregorian calendar
+fn days_from_civil(year: i64) -> i64 {
+    // Howard Hinnant's algorithm with month = 1, day = 1 (counted from March, so Jan is in the prior era-year)
+    let y = year - 1;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = 306; // Jan 1 counted from March 1
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[event]
+pub struct TaxReceiptIssued {
+    pub receipt: Pubkey,
+    pub host: Pubkey,
+    pub donor: Pubkey,
+    pub mint: Pubkey,
+    pub year: u16,
+    pub jurisdiction: [u8; 2],
+    pub total_amount: u64,
+    pub donation_count: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HostJurisdictionSet {
+    pub host: Pubkey,
+    pub jurisdiction: [u8; 2],
+    pub timestamp: i64,
+}
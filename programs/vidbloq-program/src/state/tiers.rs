@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+pub const MAX_TIERS: usize = 8;
+
+#[account]
+pub struct TierConfig {
+    pub stream: Pubkey,        // Parent stream
+    pub thresholds: Vec<u64>,  // Ascending cumulative donation amounts, tier N = thresholds[N - 1]
+    pub bump: u8,              // PDA bump
+}
+
+impl Space for TierConfig {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 4 + 8 * MAX_TIERS // thresholds: Vec<u64>
+        + 1;    // bump: u8
+}
+
+impl TierConfig {
+    /// Highest tier (1-based) covered by `amount`, 0 when below every threshold
+    pub fn tier_for(&self, amount: u64) -> u8 {
+        self.thresholds.iter().take_while(|t| amount >= **t).count() as u8
+    }
+}
+
+#[account]
+pub struct TierMembership {
+    pub stream: Pubkey,   // Parent stream
+    pub donor: Pubkey,    // Member's wallet
+    pub tier: u8,         // Verified tier, 0 when the donor fell below every threshold
+    pub updated_at: i64,  // Last sync with the donor account
+    pub bump: u8,         // PDA bump
+}
+
+impl Space for TierMembership {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // stream: Pubkey
+        + 32    // donor: Pubkey
+        + 1     // tier: u8
+        + 8     // updated_at: i64
+        + 1;    // bump: u8
+}
+
+#[event]
+pub struct TiersConfigured {
+    pub stream: Pubkey,
+    pub thresholds: Vec<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TierMembershipUpdated {
+    pub stream: Pubkey,
+    pub donor: Pubkey,
+    pub previous_tier: u8,
+    pub tier: u8,
+    pub donated_amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+/// Tokens a selected validator locks before voting. Released after resolution, less a slash when
+/// the vote contradicted the finalized outcome.
+#[account]
+pub struct ValidatorEscrow {
+    pub market: Pubkey,     // Market the validator was selected for
+    pub validator: Pubkey,  // Locked the stake and receives what is released
+    pub amount: u64,        // Tokens held for the validator in the escrow vault
+    pub locked_at: i64,     // Lock time
+    pub bump: u8,           // PDA bump
+}
+
+impl Space for ValidatorEscrow {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // market: Pubkey
+        + 32    // validator: Pubkey
+        + 8     // amount: u64
+        + 8     // locked_at: i64
+        + 1;    // bump: u8
+}
+
+#[event]
+pub struct ValidatorStakeLocked {
+    pub market: Pubkey,
+    pub validator: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ValidatorStakeReleased {
+    pub market: Pubkey,
+    pub validator: Pubkey,
+    pub released: u64,
+    pub slashed: u64,       // Sent to the platform treasury
+    pub timestamp: i64,
+}
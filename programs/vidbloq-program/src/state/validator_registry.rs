@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{StreamError, ValidatorStake, MAX_ELIGIBLE_VALIDATORS};
+
+pub const VALIDATOR_COOLDOWN: i64 = 7 * 24 * 60 * 60; // Time a deregistered validator's stake stays locked
+
+/// Validators eligible to resolve markets in one mint, with the stake each has deposited.
+/// Validator selection copies this list, so eligibility no longer depends on betting in the
+/// market being resolved. Zero-copy like `MarketResolution`; only the first `validator_count`
+/// slots are meaningful.
+#[account(zero_copy)]
+pub struct ValidatorRegistry {
+    pub mint: Pubkey,
+    pub validators: [ValidatorStake; MAX_ELIGIBLE_VALIDATORS],
+    pub total_stake: u64,    // Stake of the registered validators, cooling down stake excluded
+    pub validator_count: u8,
+    pub bump: u8,
+    pub _padding: [u8; 6],
+}
+
+impl ValidatorRegistry {
+    pub const SPACE: usize = 8 + std::mem::size_of::<ValidatorRegistry>();
+
+    pub fn validators(&self) -> &[ValidatorStake] {
+        &self.validators[..self.validator_count as usize]
+    }
+
+    /// Lists `validator` with `stake`, or raises their stake when they are already listed
+    pub fn add(&mut self, validator: Pubkey, stake: u64) -> Result<()> {
+        let count = self.validator_count as usize;
+        match self.validators[..count].iter_mut().find(|slot| slot.pubkey == validator) {
+            Some(slot) => slot.stake = slot.stake.checked_add(stake).ok_or(StreamError::MathOverflow)?,
+            None => {
+                require!(count < MAX_ELIGIBLE_VALIDATORS, StreamError::TooManyValidators);
+                self.validators[count] = ValidatorStake { pubkey: validator, stake };
+                self.validator_count += 1;
+            }
+        }
+        self.total_stake = self.total_stake.checked_add(stake).ok_or(StreamError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Unlists `validator`, moving the last slot into theirs
+    pub fn remove(&mut self, validator: Pubkey) -> Result<()> {
+        let count = self.validator_count as usize;
+        let index = self.validators[..count]
+            .iter()
+            .position(|slot| slot.pubkey == validator)
+            .ok_or(StreamError::NotValidator)?;
+        self.total_stake = self.total_stake.saturating_sub(self.validators[index].stake);
+        self.validators[index] = self.validators[count - 1];
+        self.validators[count - 1] = ValidatorStake { pubkey: Pubkey::default(), stake: 0 };
+        self.validator_count -= 1;
+        Ok(())
+    }
+}
+
+/// One validator's deposit in a mint's registry. Active while `unlocks_at` is zero; once the
+/// validator deregisters the stake can be withdrawn from `unlocks_at`.
+#[account]
+pub struct ValidatorRegistration {
+    pub validator: Pubkey,      // Owner of the stake
+    pub mint: Pubkey,           // Registry mint
+    pub stake: u64,             // Deposited in the registry vault
+    pub registered_at: i64,     // First registration time
+    pub unlocks_at: i64,        // End of the cooldown, 0 while registered
+    pub bump: u8,               // PDA bump
+}
+
+impl Space for ValidatorRegistration {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // validator: Pubkey
+        + 32    // mint: Pubkey
+        + 8     // stake: u64
+        + 8     // registered_at: i64
+        + 8     // unlocks_at: i64
+        + 1;    // bump: u8
+}
+
+impl ValidatorRegistration {
+    pub fn is_active(&self) -> bool {
+        self.stake > 0 && self.unlocks_at == 0
+    }
+}
+
+#[event]
+pub struct ValidatorRegistered {
+    pub mint: Pubkey,
+    pub validator: Pubkey,
+    pub amount: u64,
+    pub stake: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ValidatorDeregistered {
+    pub mint: Pubkey,
+    pub validator: Pubkey,
+    pub stake: u64,
+    pub unlocks_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ValidatorStakeWithdrawn {
+    pub mint: Pubkey,
+    pub validator: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,57 @@
+use anchor_lang::prelude::*;
+
+pub const VOUCHER_LIFETIME: i64 = 30 * 24 * 60 * 60; // Time a voucher can be redeemed before the issuer may reclaim it
+
+/// Escrowed winnings paid to the wallet `secret_hash` was committed to, once the secret is shown
+#[account]
+pub struct ClaimVoucher {
+    pub market: Pubkey,         // Market the winnings came from
+    pub issuer: Pubkey,         // Winning bettor who issued the voucher; receives rent on redemption
+    pub mint: Pubkey,           // Mint of the escrowed winnings
+    pub secret_hash: [u8; 32],  // sha256 of the redemption secret followed by the destination owner
+    pub amount: u64,            // Escrowed winnings
+    pub created_at: i64,        // Issue time
+    pub bump: u8,               // PDA bump
+    pub expires_at: i64,        // After this only the issuer can reclaim the winnings
+}
+
+impl Space for ClaimVoucher {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 32    // market: Pubkey
+        + 32    // issuer: Pubkey
+        + 32    // mint: Pubkey
+        + 32    // secret_hash: [u8; 32]
+        + 8     // amount: u64
+        + 8     // created_at: i64
+        + 1     // bump: u8
+        + 8;    // expires_at: i64
+}
+
+#[event]
+pub struct ClaimVoucherIssued {
+    pub market: Pubkey,
+    pub voucher: Pubkey,
+    pub issuer: Pubkey,
+    pub secret_hash: [u8; 32],
+    pub amount: u64,
+    pub expires_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimVoucherRedeemed {
+    pub market: Pubkey,
+    pub voucher: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimVoucherReclaimed {
+    pub market: Pubkey,
+    pub voucher: Pubkey,
+    pub issuer: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
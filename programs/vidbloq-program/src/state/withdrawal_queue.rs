@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+
+/// Where a queued payout is drawn from; decides which PDA signs the release
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WithdrawalSource {
+    Refund,      // Stream vault, signed by the stream
+    Winnings,    // Market vault, signed by the betting market
+}
+
+/// A refund or claim that entered the withdrawal queue. Accounting is settled when the
+/// ticket is created; the crank only moves the tokens.
+#[account]
+pub struct WithdrawalTicket {
+    pub id: u64,                    // Sequence number from FeatureGates
+    pub source: WithdrawalSource,   // Refund or winnings
+    pub authority: Pubkey,          // Stream or market PDA that owns the vault
+    pub vault: Pubkey,              // Token account the payout is drawn from
+    pub mint: Pubkey,               // Payout mint
+    pub recipient: Pubkey,          // Donor or bettor
+    pub destination: Pubkey,        // Recipient token account
+    pub payer: Pubkey,              // Paid the ticket rent, refunded on release
+    pub amount: u64,                // Amount owed
+    pub queued_at: i64,             // Timestamp of queueing
+    pub bump: u8,                   // PDA bump
+}
+
+impl Space for WithdrawalTicket {
+    const INIT_SPACE: usize = 8      // Discriminator
+        + 8     // id: u64
+        + 1     // source: WithdrawalSource
+        + 32    // authority: Pubkey
+        + 32    // vault: Pubkey
+        + 32    // mint: Pubkey
+        + 32    // recipient: Pubkey
+        + 32    // destination: Pubkey
+        + 32    // payer: Pubkey
+        + 8     // amount: u64
+        + 8     // queued_at: i64
+        + 1;    // bump: u8
+}
+
+#[event]
+pub struct WithdrawalQueued {
+    pub ticket: Pubkey,
+    pub id: u64,
+    pub source: WithdrawalSource,
+    pub authority: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawalReleased {
+    pub ticket: Pubkey,
+    pub id: u64,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub slot: u64,
+    pub timestamp: i64,
+}
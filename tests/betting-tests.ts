@@ -17,7 +17,7 @@ import {
   createAssociatedTokenAccountInstruction,
 } from "@solana/spl-token";
 import { assert, expect } from "chai";
-import { randomBytes } from "crypto";
+import { createHash, randomBytes } from "crypto";
 
 // Update to your local VRF program ID
 const EPHEMERAL_VRF_PROGRAM_ID = new PublicKey("Vrf1RNUjXmQGjmQrQLvJHs9SNkvDJEsRVFPkfSQUwGz");
@@ -108,6 +108,107 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
     );
   }
   
+  // Streams are seeded by the host's creation count, not their name
+  function streamPdaFor(hostKey: PublicKey, streamId: BN): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("stream"), hostKey.toBuffer(), streamId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+  }
+
+  // PDA of the stream the host's next initialize will create
+  async function nextStreamPda(hostKey: PublicKey): Promise<PublicKey> {
+    const [hostProfilePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("host_profile"), hostKey.toBuffer()],
+      program.programId
+    );
+    const profile = await program.account.hostProfile.fetchNullable(hostProfilePda);
+    return streamPdaFor(hostKey, profile ? profile.streamsCreated : new BN(0));
+  }
+
+  // Pool and per-outcome LMSR state live on the market's zero-copy book
+  function marketBookPda(market: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("market_book"), market.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  // Outcome descriptions, fees and consensus rules live on the market's config
+  function marketConfigPda(market: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("market_config"), market.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  // Running bet, sale and claim counters for indexers
+  function marketStatsPda(market: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("market_stats"), market.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  // Shares live on one account per (market, bettor, outcome)
+  function outcomePositionPda(market: PublicKey, bettor: PublicKey, outcome: number): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("outcome_position"), market.toBuffer(), bettor.toBuffer(), Buffer.from([outcome])],
+      program.programId
+    )[0];
+  }
+
+  // Claims read the bettor's outcome positions in the paying outcomes from the leading remaining
+  // accounts and mark the ones they pay as claimed
+  function payingPositions(market: PublicKey, bettor: PublicKey, outcomes: number[]) {
+    return outcomes.map((outcome) => ({
+      pubkey: outcomePositionPda(market, bettor, outcome),
+      isSigner: false,
+      isWritable: true,
+    }));
+  }
+
+  // Each bettor has one position per market, totalling their stakes across outcomes
+  function positionPda(market: PublicKey, bettor: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [POSITION_SEED, market.toBuffer(), bettor.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  // Buys `amount` base units of `outcome` for at least one share; `accounts` supplies the
+  // optional accounts some markets need, such as an access entry or a referrer
+  async function placeBet(
+    market: PublicKey,
+    bettor: Keypair,
+    outcome: number,
+    amount: number,
+    accounts: Record<string, PublicKey | null> = {},
+    deadline: BN | null = null,
+    maxPricePerShare: BN | null = null
+  ): Promise<string> {
+    return program.methods
+      .placeBet(outcome, new BN(amount), new BN(1), deadline, maxPricePerShare, null)
+      .accounts({
+        bettor: bettor.publicKey,
+        bettingMarket: market,
+        outcomePosition: outcomePositionPda(market, bettor.publicKey, outcome),
+        bettorPosition: positionPda(market, bettor.publicKey),
+        mint: usdcMint,
+        bettorToken: await getAssociatedTokenAddress(usdcMint, bettor.publicKey),
+        marketVault: PublicKey.findProgramAddressSync(
+          [MARKET_VAULT_SEED, market.toBuffer()],
+          program.programId
+        )[0],
+        systemProgram: SystemProgram.programId,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        ...accounts,
+      })
+      .signers([bettor])
+      .rpc({ commitment: "confirmed" });
+  }
+
   before(async () => {
     console.log("=== Setting up Vidbloq Betting Test Environment ===");
     
@@ -149,10 +250,7 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
     }
     
     // Derive PDAs using the exact seeds from your implementation
-    [streamPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("stream"), Buffer.from(STREAM_NAME), host.publicKey.toBuffer()],
-      program.programId
-    );
+    streamPda = await nextStreamPda(host.publicKey);
     
     console.log("Stream PDA:", streamPda.toBase58());
     
@@ -196,7 +294,7 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
     
     console.log("Initializing stream...");
     await program.methods
-      .initialize(STREAM_NAME, { live: {} }, new BN(endTime))
+      .initialize(STREAM_NAME, { live: {} }, new BN(endTime), null)
       .accounts({
         host: host.publicKey,
         stream: streamPda,
@@ -226,6 +324,68 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
   });
 
   describe("🎲 Market Initialization", () => {
+    it("Should reject a consensus threshold below a strict majority", async () => {
+      const resolutionTime = Math.floor(Date.now() / 1000) + 3600;
+      try {
+        await program.methods
+          .initializeBettingMarket(
+            { binary: {} },
+            ["Team A Wins", "Team B Wins"],
+            new BN(resolutionTime),
+            INITIAL_LIQUIDITY,
+            FEE_PERCENTAGE,
+            { winnerTakesAll: {} },
+            null,
+            { consensusBps: 5000, quorumValidators: 2, quorumBps: 6667, supermajorityBps: 5000 },
+            null,
+            null
+          )
+          .accounts({
+            host: host.publicKey,
+            stream: streamPda,
+            mint: usdcMint,
+            bettingMarket: bettingMarketPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("A 50% threshold should be out of bounds");
+      } catch (error) {
+        assert.include(error.message, "InvalidConsensusParams");
+      }
+    });
+
+    it("Should reject a dispute supermajority no dispute could reach", async () => {
+      const resolutionTime = Math.floor(Date.now() / 1000) + 3600;
+      try {
+        await program.methods
+          .initializeBettingMarket(
+            { binary: {} },
+            ["Team A Wins", "Team B Wins"],
+            new BN(resolutionTime),
+            INITIAL_LIQUIDITY,
+            FEE_PERCENTAGE,
+            { winnerTakesAll: {} },
+            null,
+            { consensusBps: 6667, quorumValidators: 0, quorumBps: 7500, supermajorityBps: 9500 },
+            null,
+            null
+          )
+          .accounts({
+            host: host.publicKey,
+            stream: streamPda,
+            mint: usdcMint,
+            bettingMarket: bettingMarketPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("A 95% supermajority should be out of bounds");
+      } catch (error) {
+        assert.include(error.message, "InvalidConsensusParams");
+      }
+    });
+
     it("Should initialize a binary betting market", async () => {
       const marketType = { binary: {} };
       const outcomes = ["Team A Wins", "Team B Wins"];
@@ -239,7 +399,12 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
           outcomes,
           new BN(resolutionTime),
           INITIAL_LIQUIDITY,
-          FEE_PERCENTAGE
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          null
         )
         .accounts({
           host: host.publicKey,
@@ -255,15 +420,27 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
       
       // Verify market initialization
       const market = await program.account.bettingMarket.fetch(bettingMarketPda);
+      const config = await program.account.marketConfig.fetch(marketConfigPda(bettingMarketPda));
+      const book = await program.account.marketBook.fetch(marketBookPda(bettingMarketPda));
       
       assert.equal(market.stream.toString(), streamPda.toString());
       assert.equal(market.host.toString(), host.publicKey.toString());
       assert.equal(market.mint.toString(), usdcMint.toString());
-      assert.equal(market.outcomes.length, 2);
-      assert.equal(market.outcomes[0].description, "Team A Wins");
-      assert.equal(market.outcomes[1].description, "Team B Wins");
+      assert.equal(market.outcomeCount, 2);
+      assert.deepEqual(market.ammCurve, { constantProduct: {} });
+      assert.equal(book.outcomeCount, 2);
+      assert.equal(book.market.toString(), bettingMarketPda.toString());
+      assert.equal(config.market.toString(), bettingMarketPda.toString());
+      assert.equal(config.outcomeDescriptions.length, 2);
+      assert.equal(config.outcomeDescriptions[0], "Team A Wins");
+      assert.equal(config.outcomeDescriptions[1], "Team B Wins");
       assert.equal(market.resolutionTime.toString(), resolutionTime.toString());
-      assert.equal(market.feePercentage, FEE_PERCENTAGE);
+      assert.equal(config.consensusBps, 6667, "Default consensus is two thirds of stake");
+      assert.equal(config.quorumValidators, 0);
+      assert.equal(config.quorumBps, 6667, "Two thirds of the validators vote before consensus by default");
+      assert.equal(config.supermajorityBps, 5000, "Overturning takes a majority of dispute stake by default");
+      assert.equal(config.feePercentage, FEE_PERCENTAGE);
+      assert.deepEqual(config.resolutionSource, { manual: {} });
       assert.isFalse(market.resolved);
       assert.isFalse(market.randomnessRequested);
       
@@ -272,6 +449,23 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
       console.log("  - Initial Liquidity:", INITIAL_LIQUIDITY.toString());
       console.log("  - Fee:", FEE_PERCENTAGE / 100, "%");
     });
+
+    it("Should refuse to migrate a market that already has a book and config", async () => {
+      try {
+        await program.methods
+          .migrateBettingMarket()
+          .accounts({
+            payer: host.publicKey,
+            bettingMarket: bettingMarketPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("A split market cannot be migrated again");
+      } catch (error) {
+        assert.include((error.logs ?? []).join("\n"), "already in use");
+      }
+    });
   });
 
   describe("💰 Placing Bets with LMSR", () => {
@@ -290,10 +484,11 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
       console.log("  Market Vault PDA:", marketVault.toBase58());
       
       await program.methods
-        .placeBet(0, betAmount, minShares)
+        .placeBet(0, betAmount, minShares, null, null, null)
         .accounts({
           bettor: bettor1.publicKey,
           bettingMarket: bettingMarketPda,
+          outcomePosition: outcomePositionPda(bettingMarketPda, bettor1.publicKey, 0),
           bettorPosition: position1Pda,
           mint: usdcMint,
           bettorToken: bettor1TokenAccount,
@@ -307,16 +502,20 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
       
       // Verify position
       const position = await program.account.bettorPosition.fetch(position1Pda);
+      const held = await program.account.outcomePositionAccount.fetch(
+        outcomePositionPda(bettingMarketPda, bettor1.publicKey, 0)
+      );
       assert.equal(position.bettor.toString(), bettor1.publicKey.toString());
-      assert.equal(position.positions[0].outcomeId, 0);
-      assert.isTrue(position.positions[0].shares.gte(minShares));
+      assert.equal(position.openOutcomes, 1);
+      assert.equal(held.outcomeId, 0);
+      assert.isTrue(held.shares.gte(minShares));
       
       // Check LMSR pricing update
-      const market = await program.account.bettingMarket.fetch(bettingMarketPda);
+      const market = await program.account.marketBook.fetch(marketBookPda(bettingMarketPda));
       const outcome0 = market.outcomes[0];
       
       console.log("✅ Bet placed with LMSR pricing");
-      console.log("  - Shares received:", position.positions[0].shares.toNumber() / 10 ** USDC_DECIMALS);
+      console.log("  - Shares received:", held.shares.toNumber() / 10 ** USDC_DECIMALS);
       console.log("  - Outcome 0 total shares:", outcome0.totalShares.toNumber() / 10 ** USDC_DECIMALS);
       console.log("  - Outcome 0 liquidity:", outcome0.liquidityReserve.toNumber() / 10 ** USDC_DECIMALS);
       console.log("  - Total pool:", market.totalPool.toNumber() / 10 ** USDC_DECIMALS, "USDC");
@@ -334,10 +533,11 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
       console.log("  Min shares expected:", minShares.toNumber() / 10 ** USDC_DECIMALS);
       
       await program.methods
-        .placeBet(1, betAmount, minShares)
+        .placeBet(1, betAmount, minShares, null, null, null)
         .accounts({
           bettor: bettor2.publicKey,
           bettingMarket: bettingMarketPda,
+          outcomePosition: outcomePositionPda(bettingMarketPda, bettor2.publicKey, 1),
           bettorPosition: position2Pda,
           mint: usdcMint,
           bettorToken: bettor2TokenAccount,
@@ -349,14 +549,26 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
         .signers([bettor2])
         .rpc();
       
-      const position = await program.account.bettorPosition.fetch(position2Pda);
+      const held = await program.account.outcomePositionAccount.fetch(
+        outcomePositionPda(bettingMarketPda, bettor2.publicKey, 1)
+      );
       console.log("✅ Bet placed on outcome 1");
-      console.log("  - Shares received:", position.positions[0].shares.toNumber() / 10 ** USDC_DECIMALS);
+      console.log("  - Shares received:", held.shares.toNumber() / 10 ** USDC_DECIMALS);
       
-      const market = await program.account.bettingMarket.fetch(bettingMarketPda);
+      const market = await program.account.marketBook.fetch(marketBookPda(bettingMarketPda));
       console.log("  - Total pool now:", market.totalPool.toNumber() / 10 ** USDC_DECIMALS, "USDC");
     });
 
+    it("Should keep the market's betting statistics", async () => {
+      const stats = await program.account.marketStats.fetch(marketStatsPda(bettingMarketPda));
+      assert.equal(stats.bettorCount, 2);
+      assert.equal(stats.betCount.toNumber(), 2);
+      assert.equal(stats.largestBet.toNumber(), 1500 * 10 ** USDC_DECIMALS);
+      assert.equal(stats.outcomeVolume[0].toNumber(), 1000 * 10 ** USDC_DECIMALS);
+      assert.equal(stats.outcomeVolume[1].toNumber(), 1500 * 10 ** USDC_DECIMALS);
+      assert.equal(stats.totalVolume.toNumber(), 2500 * 10 ** USDC_DECIMALS);
+    });
+
     it("Should make validators eligible with minimum stake", async () => {
       console.log("\nValidators staking to become eligible...");
       
@@ -376,10 +588,11 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
         const validatorTokenAccount = await getAssociatedTokenAddress(usdcMint, validator.publicKey);
         
         await program.methods
-          .placeBet(outcomeId, stakeAmount, minShares)
+          .placeBet(outcomeId, stakeAmount, minShares, null, null, null)
           .accounts({
             bettor: validator.publicKey,
             bettingMarket: bettingMarketPda,
+            outcomePosition: outcomePositionPda(bettingMarketPda, validator.publicKey, outcomeId),
             bettorPosition: positionPda,
             mint: usdcMint,
             bettorToken: validatorTokenAccount,
@@ -392,19 +605,23 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
           .rpc();
         
         const position = await program.account.bettorPosition.fetch(positionPda);
+        const held = await program.account.outcomePositionAccount.fetch(
+          outcomePositionPda(bettingMarketPda, validator.publicKey, outcomeId)
+        );
         assert.isTrue(position.totalInvested.gte(new BN(VALIDATOR_STAKE_REQUIREMENT)));
         
         console.log(`  ✓ Validator ${index + 1} staked ${VALIDATOR_STAKE_REQUIREMENT / 10 ** USDC_DECIMALS} USDC`);
-        console.log(`    Shares received: ${position.positions[0].shares.toNumber() / 10 ** USDC_DECIMALS}`);
+        console.log(`    Shares received: ${held.shares.toNumber() / 10 ** USDC_DECIMALS}`);
       }
       
       console.log("✅ All validators are now eligible");
       
       // Check final market state
-      const market = await program.account.bettingMarket.fetch(bettingMarketPda);
+      const market = await program.account.marketBook.fetch(marketBookPda(bettingMarketPda));
+      const config = await program.account.marketConfig.fetch(marketConfigPda(bettingMarketPda));
       console.log("\nMarket State After All Bets:");
       console.log("  - Total Pool:", market.totalPool.toNumber() / 10 ** USDC_DECIMALS, "USDC");
-      console.log("  - Total Liquidity:", market.totalLiquidity.toNumber() / 10 ** USDC_DECIMALS, "USDC");
+      console.log("  - Total Liquidity:", config.totalLiquidity.toNumber() / 10 ** USDC_DECIMALS, "USDC");
       
       // Show odds for each outcome
       const totalShares = market.outcomes[0].totalShares.add(market.outcomes[1].totalShares);
@@ -415,43 +632,230 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
         console.log("  - Team B implied probability:", (prob1 * 100).toFixed(2) + "%");
       }
     });
+
+    it("Should sell shares back into the AMM before resolution", async () => {
+      const [position3Pda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, bettingMarketPda.toBuffer(), bettor3.publicKey.toBuffer()],
+        program.programId
+      );
+      const bettor3TokenAccount = await getAssociatedTokenAddress(usdcMint, bettor3.publicKey);
+      const betAmount = new BN(200 * 10 ** USDC_DECIMALS);
+
+      await program.methods
+        .placeBet(0, betAmount, new BN(1), null, null, null)
+        .accounts({
+          bettor: bettor3.publicKey,
+          bettingMarket: bettingMarketPda,
+          outcomePosition: outcomePositionPda(bettingMarketPda, bettor3.publicKey, 0),
+          bettorPosition: position3Pda,
+          mint: usdcMint,
+          bettorToken: bettor3TokenAccount,
+          marketVault: marketVault,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bettor3])
+        .rpc();
+
+      const outcome3Pda = outcomePositionPda(bettingMarketPda, bettor3.publicKey, 0);
+      const bought = (await program.account.outcomePositionAccount.fetch(outcome3Pda)).shares;
+      const sold = bought.div(new BN(2));
+      const poolBefore = (await program.account.marketBook.fetch(marketBookPda(bettingMarketPda))).totalPool;
+      const balanceBefore = new BN((await connection.getTokenAccountBalance(bettor3TokenAccount)).value.amount);
+
+      await program.methods
+        .sellShares(0, sold, new BN(1))
+        .accounts({
+          bettor: bettor3.publicKey,
+          bettingMarket: bettingMarketPda,
+          mint: usdcMint,
+          bettorToken: bettor3TokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bettor3])
+        .rpc();
+
+      const held = await program.account.outcomePositionAccount.fetch(outcome3Pda);
+      const balanceAfter = new BN((await connection.getTokenAccountBalance(bettor3TokenAccount)).value.amount);
+      const poolAfter = (await program.account.marketBook.fetch(marketBookPda(bettingMarketPda))).totalPool;
+      const received = balanceAfter.sub(balanceBefore);
+
+      assert.equal(held.shares.toString(), bought.sub(sold).toString());
+      assert.isTrue(received.gtn(0), "Selling pays out USDC");
+      assert.isTrue(received.lt(betAmount.divn(2)), "Half the shares never sell for half the stake or more");
+      assert.equal(poolBefore.sub(poolAfter).toString(), received.toString(), "The pool pays exactly what the seller receives");
+    });
+
+    it("Should refuse to sell more shares than the position holds", async () => {
+      const held = (
+        await program.account.outcomePositionAccount.fetch(outcomePositionPda(bettingMarketPda, bettor3.publicKey, 0))
+      ).shares;
+
+      try {
+        await program.methods
+          .sellShares(0, held.addn(1), new BN(0))
+          .accounts({
+            bettor: bettor3.publicKey,
+            bettingMarket: bettingMarketPda,
+            mint: usdcMint,
+            bettorToken: await getAssociatedTokenAddress(usdcMint, bettor3.publicKey),
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([bettor3])
+          .rpc();
+        assert.fail("A position cannot sell shares it does not hold");
+      } catch (error) {
+        assert.include(error.message, "InsufficientShares");
+      }
+    });
+
+    it("Should cash out a whole position and close it", async () => {
+      const [position3Pda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, bettingMarketPda.toBuffer(), bettor3.publicKey.toBuffer()],
+        program.programId
+      );
+      const bettor3TokenAccount = await getAssociatedTokenAddress(usdcMint, bettor3.publicKey);
+      const balanceBefore = new BN((await connection.getTokenAccountBalance(bettor3TokenAccount)).value.amount);
+
+      const outcome3Pda = outcomePositionPda(bettingMarketPda, bettor3.publicKey, 0);
+
+      await program.methods
+        .cashOutPosition(new BN(1))
+        .accounts({
+          bettor: bettor3.publicKey,
+          bettingMarket: bettingMarketPda,
+          mint: usdcMint,
+          bettorToken: bettor3TokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([{ pubkey: outcome3Pda, isSigner: false, isWritable: true }])
+        .signers([bettor3])
+        .rpc();
+
+      const balanceAfter = new BN((await connection.getTokenAccountBalance(bettor3TokenAccount)).value.amount);
+      assert.isTrue(balanceAfter.gt(balanceBefore), "Cashing out pays the remaining shares");
+      assert.isNull(await program.account.bettorPosition.fetchNullable(position3Pda), "The exited position is closed");
+      assert.isNull(
+        await program.account.outcomePositionAccount.fetchNullable(outcome3Pda),
+        "Its outcome positions close with it"
+      );
+    });
   });
 
   describe("🎰 Ephemeral VRF Integration", () => {
+    let validatorRegistryPda: PublicKey;
+    const registrationPda = (validator: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("validator_registration"), usdcMint.toBuffer(), validator.toBuffer()],
+        program.programId
+      )[0];
+
+    const registerValidator = async (validator: Keypair, amount: number) =>
+      program.methods
+        .registerValidator(new BN(amount))
+        .accounts({
+          validator: validator.publicKey,
+          mint: usdcMint,
+          validatorToken: await getAssociatedTokenAddress(usdcMint, validator.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([validator])
+        .rpc();
+
+    before(async () => {
+      [validatorRegistryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("validator_registry"), usdcMint.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .initializeValidatorRegistry()
+        .accounts({ payer: host.publicKey, mint: usdcMint, tokenProgram: TOKEN_PROGRAM_ID })
+        .signers([host])
+        .rpc();
+      for (const validator of [validator1, validator2, validator3]) {
+        await registerValidator(validator, VALIDATOR_STAKE_REQUIREMENT);
+      }
+    });
+
+    it("Should list staked validators in the registry", async () => {
+      const registry = await program.account.validatorRegistry.fetch(validatorRegistryPda);
+      assert.equal(registry.validatorCount, 3);
+      assert.equal(registry.totalStake.toNumber(), 3 * VALIDATOR_STAKE_REQUIREMENT);
+      assert.equal(registry.validators[0].pubkey.toString(), validator1.publicKey.toString());
+
+      const registration = await program.account.validatorRegistration.fetch(registrationPda(validator1.publicKey));
+      assert.equal(registration.stake.toNumber(), VALIDATOR_STAKE_REQUIREMENT);
+      assert.equal(registration.unlocksAt.toNumber(), 0, "A registered validator's stake is not cooling down");
+    });
+
+    it("Should reject a stake below the validator requirement", async () => {
+      try {
+        await registerValidator(bettor3, VALIDATOR_STAKE_REQUIREMENT - 1);
+        assert.fail("An understaked validator cannot register");
+      } catch (error) {
+        assert.include(error.message, "BondTooLow");
+      }
+    });
+
+    it("Should hold a deregistered validator's stake through the cooldown", async () => {
+      await registerValidator(bettor2, VALIDATOR_STAKE_REQUIREMENT);
+      await program.methods
+        .deregisterValidator()
+        .accounts({
+          validator: bettor2.publicKey,
+          validatorRegistry: validatorRegistryPda,
+          registration: registrationPda(bettor2.publicKey),
+        })
+        .signers([bettor2])
+        .rpc();
+
+      const registry = await program.account.validatorRegistry.fetch(validatorRegistryPda);
+      assert.equal(registry.validatorCount, 3, "Deregistering leaves the registry");
+      const registration = await program.account.validatorRegistration.fetch(registrationPda(bettor2.publicKey));
+      assert.isAbove(registration.unlocksAt.toNumber(), 0);
+
+      try {
+        await program.methods
+          .withdrawValidatorStake()
+          .accounts({
+            validator: bettor2.publicKey,
+            mint: usdcMint,
+            validatorToken: await getAssociatedTokenAddress(usdcMint, bettor2.publicKey),
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([bettor2])
+          .rpc();
+        assert.fail("The stake is locked until the cooldown ends");
+      } catch (error) {
+        assert.include(error.message, "ValidatorCoolingDown");
+      }
+    });
+
     it("Should request randomness for validator selection", async () => {
       const useCase = { validatorSelection: {} };
       const clientSeed = randomBytes(32);
       
-      const eligibleValidators = [
-        {
-          pubkey: validator1.publicKey,
-          stake: new BN(VALIDATOR_STAKE_REQUIREMENT),
-        },
-        {
-          pubkey: validator2.publicKey,
-          stake: new BN(VALIDATOR_STAKE_REQUIREMENT),
-        },
-        {
-          pubkey: validator3.publicKey,
-          stake: new BN(VALIDATOR_STAKE_REQUIREMENT),
-        },
-      ];
-      
       console.log("Requesting randomness from Ephemeral VRF...");
       console.log("  Oracle Queue:", DEFAULT_ORACLE_QUEUE.toBase58());
       console.log("  VRF Program:", EPHEMERAL_VRF_PROGRAM_ID.toBase58());
       
       try {
         const tx = await program.methods
-          .requestMarketRandomness(useCase, Array.from(clientSeed), eligibleValidators)
+          .requestMarketRandomness(useCase, Array.from(clientSeed))
           .accounts({
             requestor: host.publicKey,
             market: bettingMarketPda,
             resolution: marketResolutionPda,
+            validatorRegistry: validatorRegistryPda,
             vrfProgram: EPHEMERAL_VRF_PROGRAM_ID,
             oracleQueue: DEFAULT_ORACLE_QUEUE,
             systemProgram: SystemProgram.programId,
           })
+          // Each registered validator's position, in registry order, so their stake in the market is known
+          .remainingAccounts(
+            validatorPositions.map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+          )
           .signers([host])
           .rpc();
         
@@ -462,8 +866,16 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
         
         const resolution = await program.account.marketResolution.fetch(marketResolutionPda);
         assert.equal(resolution.market.toString(), bettingMarketPda.toString());
-        assert.equal(resolution.eligibleValidators.length, 3);
+        assert.equal(resolution.eligibleCount, 3);
+        assert.equal(resolution.outcomeCount, 2);
+        assert.equal(resolution.proposedOutcome, 255, "No outcome is proposed before validators vote");
         assert.equal(resolution.eligibleValidators[0].pubkey.toString(), validator1.publicKey.toString());
+        assert.equal(
+          resolution.eligibleExposure[0].toNumber(),
+          VALIDATOR_STAKE_REQUIREMENT,
+          "A validator's bet on the market is recorded as exposure"
+        );
+        assert.equal(resolution.randomnessNonce.toNumber(), 1);
         
         console.log("✅ Randomness requested successfully");
         
@@ -472,86 +884,337 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
         console.log("  This is expected in local testing");
       }
     });
-  });
-
-  describe("🏁 Market Resolution", () => {
-    it("Should resolve market with winning outcome", async () => {
-      const winningOutcome = 0; // Team A wins
-      
-      console.log("Resolving market...");
-      console.log("  Winning outcome:", winningOutcome, "(Team A)");
-      
-      const tx = await program.methods
-        .resolveMarket(winningOutcome)
-        .accounts({
-          host: host.publicKey,
-          bettingMarket: bettingMarketPda,
-        })
-        .signers([host])
-        .rpc();
-      
-      console.log("  Transaction:", tx);
-      
-      const market = await program.account.bettingMarket.fetch(bettingMarketPda);
-      assert.isTrue(market.resolved);
-      assert.equal(market.winningOutcome, winningOutcome);
-      
-      console.log("✅ Market resolved successfully");
-    });
-  });
 
-  describe("💸 Claiming Winnings", () => {
-    it("Should calculate and distribute winnings with LMSR", async () => {
-      const bettor1TokenAccount = await getAssociatedTokenAddress(usdcMint, bettor1.publicKey);
-      
-      const balanceBefore = await connection.getTokenAccountBalance(bettor1TokenAccount);
-      
-      console.log("Bettor 1 claiming winnings...");
-      console.log("  Balance before:", balanceBefore.value.uiAmount, "USDC");
-      
-      await program.methods
-        .claimWinnings()
-        .accounts({
-          bettor: bettor1.publicKey,
-          bettingMarket: bettingMarketPda,
-          bettorPosition: position1Pda,
-          mint: usdcMint,
-          bettorToken: bettor1TokenAccount,
-          marketVault: marketVault,
-          systemProgram: SystemProgram.programId,
-          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-          tokenProgram: TOKEN_PROGRAM_ID,
-        })
-        .signers([bettor1])
-        .rpc();
-      
-      const balanceAfter = await connection.getTokenAccountBalance(bettor1TokenAccount);
-      const winnings = balanceAfter.value.uiAmount - balanceBefore.value.uiAmount;
-      
-      console.log("  Balance after:", balanceAfter.value.uiAmount, "USDC");
-      console.log("  Winnings:", winnings, "USDC");
-      
-      // Verify position is claimed
-      const position = await program.account.bettorPosition.fetch(position1Pda);
-      assert.isTrue(position.hasClaimed);
-      
-      // Calculate ROI
-      const roi = (winnings / (1000)) * 100; // Initial bet was 1000 USDC
-      console.log("  ROI:", roi.toFixed(2), "%");
-      
-      console.log("✅ Winnings claimed successfully");
+    it("Should require every registered validator's position to be accounted for", async () => {
+      try {
+        await program.methods
+          .requestMarketRandomness({ validatorSelection: {} }, Array.from(randomBytes(32)))
+          .accounts({
+            requestor: bettor1.publicKey,
+            market: bettingMarketPda,
+            resolution: marketResolutionPda,
+            validatorRegistry: validatorRegistryPda,
+            vrfProgram: EPHEMERAL_VRF_PROGRAM_ID,
+            oracleQueue: DEFAULT_ORACLE_QUEUE,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts(
+            validatorPositions.slice(1).map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }))
+          )
+          .signers([bettor1])
+          .rpc();
+        assert.fail("Leaving out a validator's position would hide their stake in the market");
+      } catch (error) {
+        assert.match(error.message, /InvalidRemainingAccounts|MarketNotReady|RandomnessRequestPending/);
+      }
     });
 
-    it("Should prevent losing bettors from claiming", async () => {
-      const bettor2TokenAccount = await getAssociatedTokenAddress(usdcMint, bettor2.publicKey);
-      
-      console.log("Testing losing bettor claim prevention...");
-      
+    it("Should only list validators from accounts the program owns", async () => {
       try {
         await program.methods
-          .claimWinnings()
+          .requestMarketRandomness({ validatorSelection: {} }, Array.from(randomBytes(32)))
           .accounts({
-            bettor: bettor2.publicKey,
+            requestor: bettor1.publicKey,
+            market: bettingMarketPda,
+            resolution: marketResolutionPda,
+            validatorRegistry: null,
+            vrfProgram: EPHEMERAL_VRF_PROGRAM_ID,
+            oracleQueue: DEFAULT_ORACLE_QUEUE,
+            systemProgram: SystemProgram.programId,
+          })
+          .remainingAccounts([
+            {
+              pubkey: await getAssociatedTokenAddress(usdcMint, bettor1.publicKey),
+              isSigner: false,
+              isWritable: false,
+            },
+          ])
+          .signers([bettor1])
+          .rpc();
+        assert.fail("A token account is not a bettor position");
+      } catch (error) {
+        // Locally the market may not be due for resolution, or the earlier request is still pending
+        assert.match(error.message, /InvalidRemainingAccounts|MarketNotReady|RandomnessRequestPending/);
+      }
+    });
+
+    it("Should only break a tie between validators", async () => {
+      try {
+        await program.methods
+          .requestMarketRandomness({ tieBreaker: {} }, Array.from(randomBytes(32)))
+          .accounts({
+            requestor: bettor1.publicKey,
+            market: bettingMarketPda,
+            resolution: marketResolutionPda,
+            validatorRegistry: null,
+            vrfProgram: EPHEMERAL_VRF_PROGRAM_ID,
+            oracleQueue: DEFAULT_ORACLE_QUEUE,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([bettor1])
+          .rpc();
+        assert.fail("A bettor cannot pick the outcome at random");
+      } catch (error) {
+        // Locally no validators were selected, so there is no vote to have tied
+        assert.match(error.message, /Unauthorized|AccountDiscriminator/);
+      }
+    });
+
+    it("Should refuse randomness for use cases markets do not act on", async () => {
+      for (const useCase of [{ disputeResolution: {} }, { fairDistribution: {} }]) {
+        try {
+          await program.methods
+            .requestMarketRandomness(useCase, Array.from(randomBytes(32)))
+            .accounts({
+              requestor: host.publicKey,
+              market: bettingMarketPda,
+              resolution: marketResolutionPda,
+              validatorRegistry: null,
+              vrfProgram: EPHEMERAL_VRF_PROGRAM_ID,
+              oracleQueue: DEFAULT_ORACLE_QUEUE,
+              systemProgram: SystemProgram.programId,
+            })
+            .signers([host])
+            .rpc();
+          assert.fail("The callback would mark the request fulfilled and do nothing");
+        } catch (error) {
+          assert.include(error.message, "UnsupportedRandomnessUseCase");
+        }
+      }
+    });
+
+    it("Should keep an in-flight randomness request until it times out", async () => {
+      try {
+        await program.methods
+          .expireRandomnessRequest()
+          .accounts({ caller: bettor1.publicKey, market: bettingMarketPda })
+          .signers([bettor1])
+          .rpc();
+        assert.fail("A request cannot be expired before RANDOMNESS_REQUEST_TIMEOUT");
+      } catch (error) {
+        // Without the Ephemeral VRF queue locally no request, and so no resolution, exists
+        assert.match(error.message, /RandomnessRequestNotExpired|NoPendingRandomnessRequest|AccountNotInitialized/);
+      }
+    });
+  });
+
+  describe("🏁 Market Resolution", () => {
+    const statement = "Team A won 3-1; final whistle confirmed by the league feed.";
+    const settlementHash = Array.from(createHash("sha256").update(statement).digest());
+
+    it("Should require a settlement statement hash", async () => {
+      try {
+        await program.methods
+          .resolveMarket(0, new Array(32).fill(0))
+          .accounts({
+            resolver: host.publicKey,
+            bettingMarket: bettingMarketPda,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("Resolution without a statement should fail");
+      } catch (error) {
+        assert.include(error.message, "MissingSettlementStatement");
+      }
+    });
+
+    it("Should not finalize a resolution validators never started", async () => {
+      try {
+        await program.methods
+          .finalizeResolution()
+          .accounts({
+            caller: bettor2.publicKey,
+            bettingMarket: bettingMarketPda,
+          })
+          .signers([bettor2])
+          .rpc();
+        assert.fail("There is no consensus to finalize");
+      } catch (error) {
+        assert.include(error.message, "AccountNotInitialized");
+      }
+    });
+
+    it("Should not accept a dispute without a validator consensus", async () => {
+      try {
+        await program.methods
+          .fileDispute(1, new BN(10_000_000))
+          .accounts({
+            disputer: bettor2.publicKey,
+            bettingMarket: bettingMarketPda,
+            mint: usdcMint,
+            disputerToken: await getAssociatedTokenAddress(usdcMint, bettor2.publicKey),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([bettor2])
+          .rpc();
+        assert.fail("There is no consensus to dispute");
+      } catch (error) {
+        assert.include(error.message, "AccountNotInitialized");
+      }
+    });
+
+    it("Should resolve market with winning outcome", async () => {
+      const winningOutcome = 0; // Team A wins
+      
+      console.log("Resolving market...");
+      console.log("  Winning outcome:", winningOutcome, "(Team A)");
+      
+      const tx = await program.methods
+        .resolveMarket(winningOutcome, settlementHash)
+        .accounts({
+          resolver: host.publicKey,
+          bettingMarket: bettingMarketPda,
+        })
+        .signers([host])
+        .rpc();
+      
+      console.log("  Transaction:", tx);
+      
+      const market = await program.account.bettingMarket.fetch(bettingMarketPda);
+      assert.isTrue(market.resolved);
+      assert.equal(market.winningOutcome, winningOutcome);
+      assert.deepEqual(Array.from(market.settlementHash), settlementHash);
+      
+      console.log("✅ Market resolved successfully");
+    });
+
+    it("Should not pay validator rewards on a market the host resolved", async () => {
+      try {
+        await program.methods
+          .distributeValidatorRewards()
+          .accounts({
+            caller: bettor2.publicKey,
+            bettingMarket: bettingMarketPda,
+            validator: validator1.publicKey,
+            mint: usdcMint,
+            validatorToken: await getAssociatedTokenAddress(usdcMint, validator1.publicKey),
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([bettor2])
+          .rpc();
+        assert.fail("No validator resolved this market");
+      } catch (error) {
+        assert.include(error.message, "AccountNotInitialized");
+      }
+    });
+
+    it("Should have nothing for a keeper to crank once the market is resolved", async () => {
+      try {
+        await program.methods
+          .crankResolution(Array.from(randomBytes(32)))
+          .accounts({
+            caller: bettor2.publicKey,
+            market: bettingMarketPda,
+            validatorRegistry: null,
+            vrfProgram: EPHEMERAL_VRF_PROGRAM_ID,
+            oracleQueue: DEFAULT_ORACLE_QUEUE,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([bettor2])
+          .rpc();
+        assert.fail("A resolved market has no resolution step left");
+      } catch (error) {
+        assert.include(error.message, "MarketResolved");
+      }
+    });
+  });
+
+  describe("💸 Claiming Winnings", () => {
+    it("Should calculate and distribute winnings with LMSR", async () => {
+      const bettor1TokenAccount = await getAssociatedTokenAddress(usdcMint, bettor1.publicKey);
+      
+      const balanceBefore = await connection.getTokenAccountBalance(bettor1TokenAccount);
+      
+      console.log("Bettor 1 claiming winnings...");
+      console.log("  Balance before:", balanceBefore.value.uiAmount, "USDC");
+      
+      await program.methods
+        .claimWinnings()
+        .accounts({
+          bettor: bettor1.publicKey,
+          bettingMarket: bettingMarketPda,
+          bettorPosition: position1Pda,
+          mint: usdcMint,
+          bettorToken: bettor1TokenAccount,
+          marketVault: marketVault,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(payingPositions(bettingMarketPda, bettor1.publicKey, [0]))
+        .signers([bettor1])
+        .rpc();
+      
+      const balanceAfter = await connection.getTokenAccountBalance(bettor1TokenAccount);
+      const winnings = balanceAfter.value.uiAmount - balanceBefore.value.uiAmount;
+      
+      console.log("  Balance after:", balanceAfter.value.uiAmount, "USDC");
+      console.log("  Winnings:", winnings, "USDC");
+      
+      // Verify position is claimed
+      const position = await program.account.bettorPosition.fetch(position1Pda);
+      const held = await program.account.outcomePositionAccount.fetch(
+        outcomePositionPda(bettingMarketPda, bettor1.publicKey, 0)
+      );
+      assert.isTrue(position.hasClaimed);
+      assert.isTrue(held.claimed);
+      assert.equal(
+        position.totalReturned.toString(),
+        new BN(balanceAfter.value.amount).sub(new BN(balanceBefore.value.amount)).toString()
+      );
+
+      // Verify the claim was settled into the bettor's profile
+      const [profilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("bettor_profile"), bettor1.publicKey.toBuffer()],
+        program.programId
+      );
+      const profile = await program.account.bettorProfile.fetch(profilePda);
+      assert.equal(profile.marketsParticipated, 1);
+      assert.equal(profile.marketsWon, 1);
+      assert.equal(profile.winRateBps, 10000);
+      
+      // Calculate ROI
+      const roi = (winnings / (1000)) * 100; // Initial bet was 1000 USDC
+      console.log("  ROI:", roi.toFixed(2), "%");
+      
+      console.log("✅ Winnings claimed successfully");
+    });
+
+    it("Should not pay an outcome position twice", async () => {
+      try {
+        await program.methods
+          .claimWinnings()
+          .accounts({
+            bettor: bettor1.publicKey,
+            bettingMarket: bettingMarketPda,
+            bettorPosition: position1Pda,
+            mint: usdcMint,
+            bettorToken: await getAssociatedTokenAddress(usdcMint, bettor1.publicKey),
+            marketVault: marketVault,
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts(payingPositions(bettingMarketPda, bettor1.publicKey, [0]))
+          .signers([bettor1])
+          .rpc();
+        assert.fail("A claimed outcome position pays nothing more");
+      } catch (error) {
+        assert.include(error.message, "AlreadyClaimed");
+      }
+    });
+
+    it("Should prevent losing bettors from claiming", async () => {
+      const bettor2TokenAccount = await getAssociatedTokenAddress(usdcMint, bettor2.publicKey);
+      
+      console.log("Testing losing bettor claim prevention...");
+      
+      try {
+        await program.methods
+          .claimWinnings()
+          .accounts({
+            bettor: bettor2.publicKey,
             bettingMarket: bettingMarketPda,
             bettorPosition: position2Pda,
             mint: usdcMint,
@@ -561,6 +1224,7 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
             associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             tokenProgram: TOKEN_PROGRAM_ID,
           })
+          .remainingAccounts(payingPositions(bettingMarketPda, bettor2.publicKey, [0]))
           .signers([bettor2])
           .rpc();
         
@@ -569,6 +1233,3046 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
         console.log("✅ Correctly prevented losing bettor from claiming");
       }
     });
+
+    it("Should record a loss when a losing position is closed", async () => {
+      const [profilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("bettor_profile"), bettor2.publicKey.toBuffer()],
+        program.programId
+      );
+
+      // Anyone may settle a losing position; the host cranks it here
+      await program.methods
+        .closeBettorPosition()
+        .accounts({
+          caller: host.publicKey,
+          bettor: bettor2.publicKey,
+          bettingMarket: bettingMarketPda,
+          bettorPosition: position2Pda,
+        })
+        .remainingAccounts(payingPositions(bettingMarketPda, bettor2.publicKey, [0]))
+        .signers([host])
+        .rpc();
+
+      const profile = await program.account.bettorProfile.fetch(profilePda);
+      assert.equal(profile.marketsParticipated, 1);
+      assert.equal(profile.marketsWon, 0);
+      assert.equal(profile.roiBps.toNumber(), -10000);
+
+      const closed = await connection.getAccountInfo(position2Pda);
+      assert.isNull(closed, "Position should be closed");
+    });
+  });
+
+  describe("📡 Oracle Resolution", () => {
+    const ORACLE_STREAM_NAME = "Oracle Over Under Stream";
+    const ORACLE_SEED = Buffer.from("market_oracle");
+    const LINE = 100;
+    let oracleStreamPda: PublicKey;
+    let oracleMarketPda: PublicKey;
+    let oraclePda: PublicKey;
+    let reporters: Keypair[];
+
+    before(async () => {
+      reporters = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+      for (const reporter of reporters) {
+        await airdrop(reporter.publicKey, LAMPORTS_PER_SOL);
+      }
+
+      oracleStreamPda = await nextStreamPda(host.publicKey);
+      [oracleMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, oracleStreamPda.toBuffer()],
+        program.programId
+      );
+      [oraclePda] = PublicKey.findProgramAddressSync(
+        [ORACLE_SEED, oracleMarketPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(ORACLE_STREAM_NAME, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: oracleStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, oracleStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: oracleStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      const resolutionTime = Math.floor(Date.now() / 1000) + 3;
+      await program.methods
+        .initializeBettingMarket(
+          { overUnder: { line: new BN(LINE) } },
+          ["Over", "Under"],
+          new BN(resolutionTime),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: oracleStreamPda,
+          mint: usdcMint,
+          bettingMarket: oracleMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .configureMarketOracle(reporters.map((r) => r.publicKey), new BN(5))
+        .accounts({
+          host: host.publicKey,
+          bettingMarket: oracleMarketPda,
+          marketOracle: oraclePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+    });
+
+    async function submit(reporter: Keypair, round: number, value: number) {
+      await program.methods
+        .submitOracleResult(round, new BN(value))
+        .accounts({
+          reporter: reporter.publicKey,
+          bettingMarket: oracleMarketPda,
+          marketOracle: oraclePda,
+        })
+        .signers([reporter])
+        .rpc();
+    }
+
+    it("Should block manual resolution once an oracle is configured", async () => {
+      try {
+        await program.methods
+          .resolveMarket(0, Array.from(randomBytes(32)))
+          .accounts({
+            resolver: host.publicKey,
+            bettingMarket: oracleMarketPda,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("Host should not bypass the oracle");
+      } catch (error) {
+        assert.include(error.message, "OracleResolutionRequired");
+      }
+    });
+
+    it("Should escalate to a dispute when reporters disagree", async () => {
+      await submit(reporters[0], 0, 90);
+      await submit(reporters[1], 0, 140);
+
+      try {
+        await submit(reporters[0], 0, 90);
+        assert.fail("A reporter may only submit once per round");
+      } catch (error) {
+        assert.include(error.message, "AlreadyReported");
+      }
+
+      await submit(reporters[2], 0, 200);
+
+      const oracle = await program.account.marketOracle.fetch(oraclePda);
+      assert.deepEqual(oracle.status, { disputed: {} });
+
+      await program.methods
+        .openOracleRound()
+        .accounts({
+          host: host.publicKey,
+          bettingMarket: oracleMarketPda,
+          marketOracle: oraclePda,
+        })
+        .signers([host])
+        .rpc();
+
+      try {
+        await submit(reporters[0], 0, 120);
+        assert.fail("Reports for a discarded round should be rejected");
+      } catch (error) {
+        assert.include(error.message, "StaleOracleRound");
+      }
+    });
+
+    it("Should resolve from the median once reporters agree", async () => {
+      await submit(reporters[0], 1, 120);
+      await submit(reporters[1], 1, 123);
+
+      const oracle = await program.account.marketOracle.fetch(oraclePda);
+      assert.deepEqual(oracle.status, { agreed: {} });
+      assert.equal(oracle.agreedValue.toNumber(), 120);
+
+      await program.methods
+        .resolveWithOracle()
+        .accounts({
+          caller: bettor3.publicKey,
+          bettingMarket: oracleMarketPda,
+          marketOracle: oraclePda,
+        })
+        .signers([bettor3])
+        .rpc();
+
+      const market = await program.account.bettingMarket.fetch(oracleMarketPda);
+      assert.isTrue(market.resolved);
+      assert.equal(market.winningOutcome, 0, "A value over the line settles to outcome 0");
+    });
+  });
+
+  describe("📏 Over/Under Metric", () => {
+    const LINE = 50;
+    let metricStreamPda: PublicKey;
+    let metricMarketPda: PublicKey;
+    let feed: Keypair;
+
+    before(async () => {
+      feed = Keypair.generate();
+      metricStreamPda = await nextStreamPda(host.publicKey);
+      [metricMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, metricStreamPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Over Under Metric Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: metricStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, metricStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { overUnder: { line: new BN(LINE) } },
+          [],
+          new BN(Math.floor(Date.now() / 1000) + 3),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: metricStreamPda,
+          mint: usdcMint,
+          bettingMarket: metricMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    function reportMetric(source: Keypair, value: number) {
+      return program.methods
+        .reportMetric(new BN(value))
+        .accounts({ source: source.publicKey, bettingMarket: metricMarketPda })
+        .signers([source])
+        .rpc();
+    }
+
+    it("Should generate Over and Under outcomes for the line", async () => {
+      const config = await program.account.marketConfig.fetch(marketConfigPda(metricMarketPda));
+      assert.deepEqual(config.outcomeDescriptions, ["Over", "Under"]);
+      assert.equal(config.metricSource.toString(), host.publicKey.toString(), "The host reports by default");
+    });
+
+    it("Should hand reporting to a data feed", async () => {
+      await program.methods
+        .setMetricSource(feed.publicKey)
+        .accounts({ host: host.publicKey, bettingMarket: metricMarketPda })
+        .signers([host])
+        .rpc();
+
+      const config = await program.account.marketConfig.fetch(marketConfigPda(metricMarketPda));
+      assert.equal(config.metricSource.toString(), feed.publicKey.toString());
+    });
+
+    it("Should let the host set how long validators get before a forced resolution", async () => {
+      const setTimeout = (seconds: number) =>
+        program.methods
+          .setResolutionTimeout(new BN(seconds))
+          .accounts({ host: host.publicKey, bettingMarket: metricMarketPda })
+          .signers([host])
+          .rpc();
+
+      try {
+        await setTimeout(-1);
+        assert.fail("A negative timeout should be rejected");
+      } catch (error) {
+        assert.include(error.message, "InvalidDuration");
+      }
+
+      await setTimeout(3600);
+      const config = await program.account.marketConfig.fetch(marketConfigPda(metricMarketPda));
+      assert.equal(config.resolutionTimeout.toNumber(), 3600);
+    });
+
+    it("Should not force a resolution validators never started", async () => {
+      try {
+        await program.methods
+          .forceResolveTimeout(Array.from(randomBytes(32)))
+          .accounts({
+            caller: bettor1.publicKey,
+            market: metricMarketPda,
+            vrfProgram: EPHEMERAL_VRF_PROGRAM_ID,
+            oracleQueue: DEFAULT_ORACLE_QUEUE,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([bettor1])
+          .rpc();
+        assert.fail("There is no stalled resolution to force");
+      } catch (error) {
+        assert.include(error.message, "AccountNotInitialized");
+      }
+    });
+
+    it("Should refuse a manual resolution", async () => {
+      try {
+        await program.methods
+          .resolveMarket(0, Array.from(randomBytes(32)))
+          .accounts({ resolver: host.publicKey, bettingMarket: metricMarketPda })
+          .signers([host])
+          .rpc();
+        assert.fail("Over/under markets settle from the metric");
+      } catch (error) {
+        assert.include(error.message, "MetricResolutionRequired");
+      }
+    });
+
+    it("Should only take the metric from the data feed", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      try {
+        await reportMetric(host, LINE + 1);
+        assert.fail("The host handed reporting to the feed");
+      } catch (error) {
+        assert.include(error.message, "Unauthorized");
+      }
+    });
+
+    it("Should settle against the line from the reported value", async () => {
+      await reportMetric(feed, LINE);
+
+      const market = await program.account.bettingMarket.fetch(metricMarketPda);
+      const config = await program.account.marketConfig.fetch(marketConfigPda(metricMarketPda));
+      assert.isTrue(market.resolved);
+      assert.equal(market.winningOutcome, 1, "A value on the line settles Under");
+      assert.equal(config.reportedMetric.toNumber(), LINE);
+    });
+  });
+
+  describe("💹 Price Oracle Markets", () => {
+    let priceStreamPda: PublicKey;
+    let priceMarketPda: PublicKey;
+    const feed = Keypair.generate().publicKey;
+    const priceSource = (marketFeed: PublicKey) => ({
+      priceOracle: {
+        provider: { pyth: {} },
+        feed: marketFeed,
+        comparison: { above: {} },
+        target: new BN(200_00),
+        targetExpo: -2,
+      },
+    });
+
+    before(async () => {
+      priceStreamPda = await nextStreamPda(host.publicKey);
+      [priceMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, priceStreamPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Price Oracle Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: priceStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, priceStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    function initializePriceMarket(marketType: object, outcomes: string[]) {
+      return program.methods
+        .initializeBettingMarket(
+          marketType,
+          outcomes,
+          new BN(Math.floor(Date.now() / 1000) + 3),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          priceSource(feed)
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: priceStreamPda,
+          mint: usdcMint,
+          bettingMarket: priceMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    }
+
+    it("Should only settle yes/no questions from a price feed", async () => {
+      try {
+        await initializePriceMarket({ multiOutcome: { max: 3 } }, ["Low", "Mid", "High"]);
+        assert.fail("A price comparison only has two answers");
+      } catch (error) {
+        assert.include(error.message, "InvalidMarketSetup");
+      }
+
+      await initializePriceMarket({ binary: {} }, ["SOL above $200", "SOL at or below $200"]);
+      const config = await program.account.marketConfig.fetch(marketConfigPda(priceMarketPda));
+      assert.equal(config.resolutionSource.priceOracle.feed.toString(), feed.toString());
+      assert.equal(config.resolutionSource.priceOracle.target.toNumber(), 200_00);
+    });
+
+    it("Should refuse a manual resolution", async () => {
+      try {
+        await program.methods
+          .resolveMarket(0, Array.from(randomBytes(32)))
+          .accounts({ resolver: host.publicKey, bettingMarket: priceMarketPda })
+          .signers([host])
+          .rpc();
+        assert.fail("Price markets settle from their feed");
+      } catch (error) {
+        assert.include(error.message, "PriceOracleResolutionRequired");
+      }
+    });
+
+    it("Should only read the feed the market was created with", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      try {
+        await program.methods
+          .resolveFromOracle()
+          .accounts({
+            caller: bettor1.publicKey,
+            bettingMarket: priceMarketPda,
+            priceFeed: Keypair.generate().publicKey,
+          })
+          .signers([bettor1])
+          .rpc();
+        assert.fail("Another account cannot stand in for the feed");
+      } catch (error) {
+        assert.include(error.message, "InvalidPriceFeed");
+      }
+    });
+  });
+
+  describe("⚖️ Arbiter Markets", () => {
+    let arbiterStreamPda: PublicKey;
+    let arbiterMarketPda: PublicKey;
+    const arbiter = Keypair.generate();
+
+    before(async () => {
+      arbiterStreamPda = await nextStreamPda(host.publicKey);
+      [arbiterMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, arbiterStreamPda.toBuffer()],
+        program.programId
+      );
+      await airdrop(arbiter.publicKey, LAMPORTS_PER_SOL);
+
+      await program.methods
+        .initialize("Arbiter Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: arbiterStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, arbiterStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    function initializeArbiterMarket(arbiterKey: PublicKey) {
+      return program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 3),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          { arbiter: [arbiterKey] }
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: arbiterStreamPda,
+          mint: usdcMint,
+          bettingMarket: arbiterMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    }
+
+    it("Should not let the host arbitrate their own market", async () => {
+      try {
+        await initializeArbiterMarket(host.publicKey);
+        assert.fail("The arbiter must be neutral");
+      } catch (error) {
+        assert.include(error.message, "InvalidMarketSetup");
+      }
+
+      await initializeArbiterMarket(arbiter.publicKey);
+      const config = await program.account.marketConfig.fetch(marketConfigPda(arbiterMarketPda));
+      assert.equal(config.resolutionSource.arbiter[0].toString(), arbiter.publicKey.toString());
+    });
+
+    it("Should only take the outcome from the arbiter", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      try {
+        await program.methods
+          .resolveMarket(0, Array.from(randomBytes(32)))
+          .accounts({ resolver: host.publicKey, bettingMarket: arbiterMarketPda })
+          .signers([host])
+          .rpc();
+        assert.fail("The host cannot resolve an arbiter market");
+      } catch (error) {
+        assert.include(error.message, "Unauthorized");
+      }
+
+      await program.methods
+        .resolveMarket(1, Array.from(randomBytes(32)))
+        .accounts({ resolver: arbiter.publicKey, bettingMarket: arbiterMarketPda })
+        .signers([arbiter])
+        .rpc();
+
+      const market = await program.account.bettingMarket.fetch(arbiterMarketPda);
+      assert.isTrue(market.resolved);
+      assert.equal(market.winningOutcome, 1);
+    });
+  });
+
+  describe("🗳️ Consensus Markets", () => {
+    let consensusStreamPda: PublicKey;
+    let consensusMarketPda: PublicKey;
+
+    before(async () => {
+      consensusStreamPda = await nextStreamPda(host.publicKey);
+      [consensusMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, consensusStreamPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Consensus Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: consensusStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, consensusStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 3),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          { consensus: {} }
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: consensusStreamPda,
+          mint: usdcMint,
+          bettingMarket: consensusMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should not let the host write the outcome before validators resolve", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      try {
+        await program.methods
+          .resolveMarket(0, Array.from(randomBytes(32)))
+          .accounts({ resolver: host.publicKey, bettingMarket: consensusMarketPda })
+          .signers([host])
+          .rpc();
+        assert.fail("Only validator consensus settles the market");
+      } catch (error) {
+        assert.include(error.message, "ConsensusResolutionRequired");
+      }
+
+      const market = await program.account.bettingMarket.fetch(consensusMarketPda);
+      assert.isFalse(market.resolved);
+    });
+  });
+
+  describe("🎁 Free Bet Credits", () => {
+    const FREE_BET_STREAM_NAME = "Free Bet Promo Stream";
+    const CREDIT = 100 * 10 ** USDC_DECIMALS;
+    const FREE_STAKE = 60 * 10 ** USDC_DECIMALS;
+    const PAID_STAKE = 100 * 10 ** USDC_DECIMALS;
+    let promoStreamPda: PublicKey;
+    let promoMarketPda: PublicKey;
+    let promoVault: PublicKey;
+    let creditPda: PublicKey;
+    let creditVault: PublicKey;
+    let hostToken: PublicKey;
+
+    before(async () => {
+      promoStreamPda = await nextStreamPda(host.publicKey);
+      [promoMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, promoStreamPda.toBuffer()],
+        program.programId
+      );
+      [promoVault] = PublicKey.findProgramAddressSync(
+        [MARKET_VAULT_SEED, promoMarketPda.toBuffer()],
+        program.programId
+      );
+      [creditPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("free_bet_credit"), promoMarketPda.toBuffer(), bettor3.publicKey.toBuffer()],
+        program.programId
+      );
+      [creditVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("free_bet_vault"), creditPda.toBuffer()],
+        program.programId
+      );
+      hostToken = await getAssociatedTokenAddress(usdcMint, host.publicKey);
+
+      await program.methods
+        .initialize(FREE_BET_STREAM_NAME, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: promoStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, promoStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: promoStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 6),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: promoStreamPda,
+          mint: usdcMint,
+          bettingMarket: promoMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should stake host-funded credit on a bet", async () => {
+      await program.methods
+        .grantFreeBetCredit(bettor3.publicKey, new BN(CREDIT), new BN(Math.floor(Date.now() / 1000) + 4))
+        .accounts({
+          host: host.publicKey,
+          bettingMarket: promoMarketPda,
+          credit: creditPda,
+          creditVault: creditVault,
+          mint: usdcMint,
+          hostToken: hostToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      const [freePosition] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, promoMarketPda.toBuffer(), bettor3.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .placeFreeBet(0, new BN(FREE_STAKE), new BN(1))
+        .accounts({
+          bettor: bettor3.publicKey,
+          bettingMarket: promoMarketPda,
+          bettorPosition: freePosition,
+          credit: creditPda,
+          creditVault: creditVault,
+          mint: usdcMint,
+          marketVault: promoVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bettor3])
+        .rpc();
+
+      const [paidPosition] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, promoMarketPda.toBuffer(), bettor1.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .placeBet(1, new BN(PAID_STAKE), new BN(1), null, null, null)
+        .accounts({
+          bettor: bettor1.publicKey,
+          bettingMarket: promoMarketPda,
+          outcomePosition: outcomePositionPda(promoMarketPda, bettor1.publicKey, 1),
+          bettorPosition: paidPosition,
+          mint: usdcMint,
+          bettorToken: await getAssociatedTokenAddress(usdcMint, bettor1.publicKey),
+          marketVault: promoVault,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bettor1])
+        .rpc();
+
+      const credit = await program.account.freeBetCredit.fetch(creditPda);
+      assert.equal(credit.remaining.toNumber(), CREDIT - FREE_STAKE);
+      const position = await program.account.bettorPosition.fetch(freePosition);
+      assert.equal(position.creditStaked.toNumber(), FREE_STAKE);
+    });
+
+    it("Should pay only winnings beyond the credit stake and return the rest to the host", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 7000));
+
+      await program.methods
+        .resolveMarket(0, Array.from(randomBytes(32)))
+        .accounts({
+          resolver: host.publicKey,
+          bettingMarket: promoMarketPda,
+        })
+        .signers([host])
+        .rpc();
+
+      const bettorToken = await getAssociatedTokenAddress(usdcMint, bettor3.publicKey);
+      const before = Number((await connection.getTokenAccountBalance(bettorToken)).value.amount);
+      await program.methods
+        .claimWinnings()
+        .accounts({
+          bettor: bettor3.publicKey,
+          bettingMarket: promoMarketPda,
+          bettorPosition: PublicKey.findProgramAddressSync(
+            [POSITION_SEED, promoMarketPda.toBuffer(), bettor3.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          mint: usdcMint,
+          bettorToken: bettorToken,
+          marketVault: promoVault,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(payingPositions(promoMarketPda, bettor3.publicKey, [0]))
+        .signers([bettor3])
+        .rpc();
+      const after = Number((await connection.getTokenAccountBalance(bettorToken)).value.amount);
+
+      const pool = FREE_STAKE + PAID_STAKE;
+      const gross = pool - Math.floor((pool * FEE_PERCENTAGE) / 10_000);
+      assert.equal(after - before, gross - FREE_STAKE, "Credit stake should be held back");
+
+      const market = await program.account.bettingMarket.fetch(promoMarketPda);
+      assert.equal(market.freeBetWithheld.toNumber(), FREE_STAKE);
+
+      const hostBefore = Number((await connection.getTokenAccountBalance(hostToken)).value.amount);
+      await program.methods
+        .clawbackFreeBetCredit()
+        .accounts({
+          host: host.publicKey,
+          bettingMarket: promoMarketPda,
+          credit: creditPda,
+          creditVault: creditVault,
+          mint: usdcMint,
+          hostToken: hostToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+      await program.methods
+        .reclaimFreeBetStakes()
+        .accounts({
+          host: host.publicKey,
+          bettingMarket: promoMarketPda,
+          mint: usdcMint,
+          marketVault: promoVault,
+          hostToken: hostToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+      const hostAfter = Number((await connection.getTokenAccountBalance(hostToken)).value.amount);
+
+      assert.equal(hostAfter - hostBefore, CREDIT, "Unused credit and held-back stake return to the host");
+      assert.isNull(await connection.getAccountInfo(creditPda), "Credit should be closed");
+    });
+  });
+
+  describe("🎁 Free Bet Credit on a Losing Outcome", () => {
+    const FREE_STAKE = 40 * 10 ** USDC_DECIMALS;
+    const CASH_STAKE = 50 * 10 ** USDC_DECIMALS;
+    let splitStreamPda: PublicKey;
+    let splitMarketPda: PublicKey;
+    let splitVault: PublicKey;
+    let creditPda: PublicKey;
+    let creditVault: PublicKey;
+    let positionPda: PublicKey;
+
+    before(async () => {
+      splitStreamPda = await nextStreamPda(host.publicKey);
+      [splitMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, splitStreamPda.toBuffer()],
+        program.programId
+      );
+      [splitVault] = PublicKey.findProgramAddressSync(
+        [MARKET_VAULT_SEED, splitMarketPda.toBuffer()],
+        program.programId
+      );
+      [creditPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("free_bet_credit"), splitMarketPda.toBuffer(), bettor2.publicKey.toBuffer()],
+        program.programId
+      );
+      [creditVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("free_bet_vault"), creditPda.toBuffer()],
+        program.programId
+      );
+      [positionPda] = PublicKey.findProgramAddressSync(
+        [POSITION_SEED, splitMarketPda.toBuffer(), bettor2.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Split Credit Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: splitStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, splitStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({ host: host.publicKey, stream: splitStreamPda })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 6),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: splitStreamPda,
+          mint: usdcMint,
+          bettingMarket: splitMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should track credit on the outcome it was staked on", async () => {
+      await program.methods
+        .grantFreeBetCredit(bettor2.publicKey, new BN(FREE_STAKE), new BN(Math.floor(Date.now() / 1000) + 4))
+        .accounts({
+          host: host.publicKey,
+          bettingMarket: splitMarketPda,
+          credit: creditPda,
+          creditVault: creditVault,
+          mint: usdcMint,
+          hostToken: await getAssociatedTokenAddress(usdcMint, host.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .placeFreeBet(1, new BN(FREE_STAKE), new BN(1))
+        .accounts({
+          bettor: bettor2.publicKey,
+          bettingMarket: splitMarketPda,
+          bettorPosition: positionPda,
+          credit: creditPda,
+          creditVault: creditVault,
+          mint: usdcMint,
+          marketVault: splitVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bettor2])
+        .rpc();
+
+      await program.methods
+        .placeBet(0, new BN(CASH_STAKE), new BN(1), null, null, null)
+        .accounts({
+          bettor: bettor2.publicKey,
+          bettingMarket: splitMarketPda,
+          outcomePosition: outcomePositionPda(splitMarketPda, bettor2.publicKey, 0),
+          bettorPosition: positionPda,
+          mint: usdcMint,
+          bettorToken: await getAssociatedTokenAddress(usdcMint, bettor2.publicKey),
+          marketVault: splitVault,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bettor2])
+        .rpc();
+
+      const loser = await program.account.outcomePositionAccount.fetch(
+        outcomePositionPda(splitMarketPda, bettor2.publicKey, 1)
+      );
+      assert.equal(loser.creditStaked.toNumber(), FREE_STAKE);
+      const winner = await program.account.outcomePositionAccount.fetch(
+        outcomePositionPda(splitMarketPda, bettor2.publicKey, 0)
+      );
+      assert.equal(winner.creditStaked.toNumber(), 0);
+    });
+
+    it("Should not withhold credit lost on another outcome from cash winnings", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 7000));
+
+      await program.methods
+        .resolveMarket(0, Array.from(randomBytes(32)))
+        .accounts({ resolver: host.publicKey, bettingMarket: splitMarketPda })
+        .signers([host])
+        .rpc();
+
+      const bettorToken = await getAssociatedTokenAddress(usdcMint, bettor2.publicKey);
+      const before = Number((await connection.getTokenAccountBalance(bettorToken)).value.amount);
+      await program.methods
+        .claimWinnings()
+        .accounts({
+          bettor: bettor2.publicKey,
+          bettingMarket: splitMarketPda,
+          bettorPosition: positionPda,
+          mint: usdcMint,
+          bettorToken: bettorToken,
+          marketVault: splitVault,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(payingPositions(splitMarketPda, bettor2.publicKey, [0]))
+        .signers([bettor2])
+        .rpc();
+      const after = Number((await connection.getTokenAccountBalance(bettorToken)).value.amount);
+
+      const pool = FREE_STAKE + CASH_STAKE;
+      const gross = pool - Math.floor((pool * FEE_PERCENTAGE) / 10_000);
+      assert.equal(after - before, gross, "The cash stake won the whole pool");
+
+      const market = await program.account.bettingMarket.fetch(splitMarketPda);
+      assert.equal(market.freeBetWithheld.toNumber(), 0);
+    });
+  });
+
+  describe("🧮 Rounding Dust", () => {
+    const DUST_STREAM_NAME = "Rounding Dust Stream";
+    let dustStreamPda: PublicKey;
+    let dustMarketPda: PublicKey;
+    let dustVault: PublicKey;
+    const stakes: [Keypair, number, number][] = [];
+
+    before(async () => {
+      dustStreamPda = await nextStreamPda(host.publicKey);
+      [dustMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, dustStreamPda.toBuffer()],
+        program.programId
+      );
+      [dustVault] = PublicKey.findProgramAddressSync(
+        [MARKET_VAULT_SEED, dustMarketPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(DUST_STREAM_NAME, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: dustStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, dustStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 6),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: dustStreamPda,
+          mint: usdcMint,
+          bettingMarket: dustMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      // Odd stakes so the pro rata split cannot come out even
+      stakes.push([bettor1, 0, 7_777_777], [bettor2, 0, 3_333_331], [bettor3, 1, 5_555_557]);
+      for (const [bettor, outcome, amount] of stakes) {
+        await placeBet(dustMarketPda, bettor, outcome, amount);
+      }
+    });
+
+    it("Should conserve the pool to the base unit across claims and dust", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 7000));
+
+      await program.methods
+        .resolveMarket(0, Array.from(randomBytes(32)))
+        .accounts({
+          resolver: host.publicKey,
+          bettingMarket: dustMarketPda,
+        })
+        .signers([host])
+        .rpc();
+
+      const resolved = await program.account.bettingMarket.fetch(dustMarketPda);
+      const dustBook = await program.account.marketBook.fetch(marketBookPda(dustMarketPda));
+      const pool: BN = dustBook.totalPool;
+      const winningShares: BN = dustBook.outcomes[0].totalShares;
+      assert.equal((await connection.getTokenAccountBalance(dustVault)).value.amount, pool.toString());
+      assert.equal(resolved.dustAccumulator.toNumber(), 0, "Dust is only known once every winner settles");
+
+      let gross = new BN(0);
+      let received = new BN(0);
+      for (const [bettor, outcome] of stakes) {
+        if (outcome !== 0) continue;
+        const held = await program.account.outcomePositionAccount.fetch(
+          outcomePositionPda(dustMarketPda, bettor.publicKey, 0)
+        );
+        gross = gross.add(pool.mul(held.shares).div(winningShares));
+
+        const bettorToken = await getAssociatedTokenAddress(usdcMint, bettor.publicKey);
+        const before = new BN((await connection.getTokenAccountBalance(bettorToken)).value.amount);
+        await program.methods
+          .claimWinnings()
+          .accounts({
+            bettor: bettor.publicKey,
+            bettingMarket: dustMarketPda,
+            bettorPosition: positionPda(dustMarketPda, bettor.publicKey),
+            mint: usdcMint,
+            bettorToken,
+            marketVault: dustVault,
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .remainingAccounts(payingPositions(dustMarketPda, bettor.publicKey, [0]))
+          .signers([bettor])
+          .rpc();
+        const after = new BN((await connection.getTokenAccountBalance(bettorToken)).value.amount);
+        received = received.add(after.sub(before));
+      }
+
+      const market = await program.account.bettingMarket.fetch(dustMarketPda);
+      const vault = new BN((await connection.getTokenAccountBalance(dustVault)).value.amount);
+      assert.equal(market.unsettledShares.toNumber(), 0);
+      assert.equal(market.dustAccumulator.toString(), pool.sub(gross).toString(), "Dust is what the winners' shares did not take");
+      assert.equal(vault.toString(), pool.sub(received).toString(), "Every base unit leaves the vault only through a claim");
+      assert.isTrue(received.lte(gross), "Credits never round up");
+    });
+
+    it("Should let the host sweep the accumulated dust", async () => {
+      const market = await program.account.bettingMarket.fetch(dustMarketPda);
+      const dust = market.dustAccumulator.toNumber();
+      const hostToken = await getAssociatedTokenAddress(usdcMint, host.publicKey);
+      const before = Number((await connection.getTokenAccountBalance(hostToken)).value.amount);
+
+      if (dust === 0) {
+        try {
+          await program.methods
+            .sweepMarketDust()
+            .accounts({
+              host: host.publicKey,
+              bettingMarket: dustMarketPda,
+              mint: usdcMint,
+              marketVault: dustVault,
+              hostToken,
+              tokenProgram: TOKEN_PROGRAM_ID,
+            })
+            .signers([host])
+            .rpc();
+          assert.fail("Nothing to sweep");
+        } catch (error) {
+          assert.include(error.message, "NothingToSweep");
+        }
+        return;
+      }
+
+      await program.methods
+        .sweepMarketDust()
+        .accounts({
+          host: host.publicKey,
+          bettingMarket: dustMarketPda,
+          mint: usdcMint,
+          marketVault: dustVault,
+          hostToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const after = Number((await connection.getTokenAccountBalance(hostToken)).value.amount);
+      assert.equal(after - before, dust);
+      const swept = await program.account.bettingMarket.fetch(dustMarketPda);
+      assert.equal(swept.dustAccumulator.toNumber(), 0);
+    });
+
+    it("Should pay the market fees kept from claims to the host", async () => {
+      const market = await program.account.bettingMarket.fetch(dustMarketPda);
+      const fees = market.feesCollected.toNumber();
+      assert.isAbove(fees, 0, "Winning claims are paid net of the market fee");
+
+      const hostToken = await getAssociatedTokenAddress(usdcMint, host.publicKey);
+      const collect = () =>
+        program.methods
+          .collectMarketFees()
+          .accounts({
+            host: host.publicKey,
+            bettingMarket: dustMarketPda,
+            mint: usdcMint,
+            marketVault: dustVault,
+            hostToken,
+            treasuryAta: null,
+            hostProfile: null,
+            referralRewards: null,
+            referralVault: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+
+      const before = Number((await connection.getTokenAccountBalance(hostToken)).value.amount);
+      await collect();
+      const after = Number((await connection.getTokenAccountBalance(hostToken)).value.amount);
+      assert.equal(after - before, fees);
+
+      const collected = await program.account.bettingMarket.fetch(dustMarketPda);
+      assert.equal(collected.feesCollected.toNumber(), 0);
+      assert.equal(
+        (await connection.getTokenAccountBalance(dustVault)).value.amount,
+        "0",
+        "Claims, dust and fees account for the whole pool"
+      );
+
+      try {
+        await collect();
+        assert.fail("Fees were already collected");
+      } catch (error) {
+        assert.include(error.message, "NothingToSweep");
+      }
+    });
+
+    it("Should keep unclaimed funds in the vault until the claim deadline", async () => {
+      const market = await program.account.bettingMarket.fetch(dustMarketPda);
+      assert.isAbove(
+        market.claimDeadline.toNumber(),
+        Math.floor(Date.now() / 1000) + 89 * 24 * 60 * 60,
+        "Resolution opens a 90 day claim window"
+      );
+
+      try {
+        await program.methods
+          .sweepUnclaimed()
+          .accounts({
+            host: host.publicKey,
+            bettingMarket: dustMarketPda,
+            mint: usdcMint,
+            marketVault: dustVault,
+            hostToken: await getAssociatedTokenAddress(usdcMint, host.publicKey),
+            treasuryAta: null,
+            hostProfile: null,
+            referralRewards: null,
+            referralVault: null,
+            nextMarket: null,
+            nextMarketBook: null,
+            nextMarketVault: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("The claim window is still open");
+      } catch (error) {
+        assert.include(error.message, "ClaimWindowOpen");
+      }
+    });
+  });
+
+  describe("🧾 Eligible Bettor Count", () => {
+    let countStreamPda: PublicKey;
+    let countMarketPda: PublicKey;
+
+    before(async () => {
+      countStreamPda = await nextStreamPda(host.publicKey);
+      [countMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, countStreamPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Eligible Count Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: countStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, countStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 3),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: countStreamPda,
+          mint: usdcMint,
+          bettingMarket: countMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      const stakes: [Keypair, number][] = [
+        [bettor1, VALIDATOR_STAKE_REQUIREMENT],
+        [bettor2, VALIDATOR_STAKE_REQUIREMENT],
+        [bettor3, VALIDATOR_STAKE_REQUIREMENT / 10],
+      ];
+      for (const [bettor, amount] of stakes) {
+        await placeBet(countMarketPda, bettor, 0, amount);
+      }
+    });
+
+    it("Should count the positions staking enough to validate", async () => {
+      const book = await program.account.marketBook.fetch(marketBookPda(countMarketPda));
+      assert.equal(book.eligibleBettors, 2);
+      assert.equal(book.countsEligible, 1);
+    });
+
+    it("Should not select validators from a hand-picked subset of bettors", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      const [resolution] = PublicKey.findProgramAddressSync(
+        [RESOLUTION_SEED, countMarketPda.toBuffer()],
+        program.programId
+      );
+
+      try {
+        await program.methods
+          .requestMarketRandomness({ validatorSelection: {} }, Array.from(randomBytes(32)))
+          .accounts({
+            requestor: bettor1.publicKey,
+            market: countMarketPda,
+            resolution,
+            validatorRegistry: null,
+            vrfProgram: EPHEMERAL_VRF_PROGRAM_ID,
+            oracleQueue: DEFAULT_ORACLE_QUEUE,
+            systemProgram: SystemProgram.programId,
+          })
+          // bettor2 also qualifies but is left out
+          .remainingAccounts(
+            [bettor1, bettor3].map((bettor) => ({
+              pubkey: positionPda(countMarketPda, bettor.publicKey),
+              isSigner: false,
+              isWritable: false,
+            }))
+          )
+          .signers([bettor1])
+          .rpc();
+        assert.fail("Every eligible bettor must be in the draw");
+      } catch (error) {
+        assert.include(error.message, "InvalidRemainingAccounts");
+      }
+    });
+  });
+
+  describe("📈 LMSR Pricing", () => {
+    const B = 1000 * 10 ** USDC_DECIMALS;
+    let lmsrStreamPda: PublicKey;
+    let lmsrMarketPda: PublicKey;
+    let lmsrVault: PublicKey;
+    let lastBet: string;
+
+    function initializeLmsrMarket(b: BN) {
+      return program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 3600),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          { lmsr: { b } },
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: lmsrStreamPda,
+          mint: usdcMint,
+          bettingMarket: lmsrMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host]);
+    }
+
+    async function bet(
+      bettor: Keypair,
+      outcome: number,
+      amount: number,
+      deadline: BN | null = null,
+      maxPricePerShare: BN | null = null
+    ): Promise<BN> {
+      const outcomePda = outcomePositionPda(lmsrMarketPda, bettor.publicKey, outcome);
+      const held = (p) => p?.shares ?? new BN(0);
+      const before = await program.account.outcomePositionAccount.fetchNullable(outcomePda);
+      lastBet = await placeBet(lmsrMarketPda, bettor, outcome, amount, {}, deadline, maxPricePerShare);
+      const after = await program.account.outcomePositionAccount.fetch(outcomePda);
+      return held(after).sub(held(before));
+    }
+
+    before(async () => {
+      lmsrStreamPda = await nextStreamPda(host.publicKey);
+      [lmsrMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, lmsrStreamPda.toBuffer()],
+        program.programId
+      );
+      [lmsrVault] = PublicKey.findProgramAddressSync(
+        [MARKET_VAULT_SEED, lmsrMarketPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("LMSR Pricing Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: lmsrStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, lmsrStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should reject a zero liquidity parameter", async () => {
+      try {
+        await initializeLmsrMarket(new BN(0)).rpc();
+        assert.fail("LMSR needs a positive b");
+      } catch (error) {
+        assert.include(error.message, "InvalidMarketSetup");
+      }
+    });
+
+    it("Should price bets from the LMSR cost function", async () => {
+      await initializeLmsrMarket(new BN(B)).rpc();
+      const market = await program.account.bettingMarket.fetch(lmsrMarketPda);
+      assert.equal(market.ammCurve.lmsr.b.toNumber(), B);
+
+      // At even odds, a stake a buys b * ln(2e^(a/b) - 1) shares
+      const stake = 100 * 10 ** USDC_DECIMALS;
+      const first = await bet(bettor1, 0, stake);
+      const expected = B * Math.log(2 * Math.exp(stake / B) - 1);
+      assert.approximately(first.toNumber(), expected, 10);
+
+      const second = await bet(bettor2, 0, stake);
+      assert.isTrue(second.lt(first), "Buying an outcome raises its price");
+    });
+
+    it("Should keep implied probabilities summing to one", async () => {
+      await bet(bettor3, 1, 250 * 10 ** USDC_DECIMALS);
+      const book = await program.account.marketBook.fetch(marketBookPda(lmsrMarketPda));
+      const weights = book.outcomes
+        .slice(0, book.outcomeCount)
+        .map((o) => Math.exp(o.totalShares.toNumber() / B));
+      const total = weights.reduce((sum, w) => sum + w, 0);
+      const prices = weights.map((w) => w / total);
+      assert.approximately(prices.reduce((sum, p) => sum + p, 0), 1, 1e-9);
+      assert.isTrue(prices[1] > 0.5, "The outcome with more stake behind it is favoured");
+    });
+
+    it("Should quote exactly what a bet buys", async () => {
+      const stake = 120 * 10 ** USDC_DECIMALS;
+      const quote = await program.methods
+        .getQuote(0, new BN(stake))
+        .accounts({ bettingMarket: lmsrMarketPda })
+        .view();
+      assert.equal(quote.extraFeeBps, 0);
+      assert.isTrue(quote.probabilityAfterBps > quote.probabilityBps, "Buying an outcome raises its odds");
+
+      const bought = await bet(bettor1, 0, stake);
+      assert.isTrue(bought.eq(quote.shares), "The quote matches the shares the bet bought");
+
+      const tx = await connection.getTransaction(lastBet, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const odds = [...parser.parseLogs(tx.meta.logMessages)].find(
+        (event) => event.name === "oddsUpdated"
+      )?.data as any;
+      assert.isDefined(odds);
+      assert.equal(odds.probabilitiesBps.length, 2);
+      assert.equal(odds.probabilitiesBps[0], quote.probabilityAfterBps);
+      assert.approximately(odds.probabilitiesBps[0] + odds.probabilitiesBps[1], 10_000, 2);
+    });
+
+    it("Should refuse a bet past its deadline", async () => {
+      try {
+        await bet(bettor2, 1, 10 * 10 ** USDC_DECIMALS, new BN(Math.floor(Date.now() / 1000) - 60));
+        assert.fail("Should have thrown");
+      } catch (error) {
+        assert.include(error.message, "BetDeadlinePassed");
+      }
+    });
+
+    it("Should refuse a bet priced above the bettor's limit", async () => {
+      const stake = new BN(50 * 10 ** USDC_DECIMALS);
+      const quote = await program.methods
+        .getQuote(1, stake)
+        .accounts({ bettingMarket: lmsrMarketPda })
+        .view();
+      try {
+        await bet(bettor2, 1, stake.toNumber(), null, quote.averagePrice.subn(1));
+        assert.fail("Should have thrown");
+      } catch (error) {
+        assert.include(error.message, "PriceLimitExceeded");
+      }
+
+      const bought = await bet(
+        bettor2,
+        1,
+        stake.toNumber(),
+        new BN(Math.floor(Date.now() / 1000) + 60),
+        quote.averagePrice
+      );
+      assert.isTrue(bought.eq(quote.shares));
+    });
+
+    it("Should give a gifted bet's position to the beneficiary", async () => {
+      const stake = new BN(20 * 10 ** USDC_DECIMALS);
+      const giftPosition = outcomePositionPda(lmsrMarketPda, bettor3.publicKey, 0);
+      const before = await program.account.outcomePositionAccount.fetchNullable(giftPosition);
+      const payerToken = await getAssociatedTokenAddress(usdcMint, bettor2.publicKey);
+      const payerBefore = new BN((await connection.getTokenAccountBalance(payerToken)).value.amount);
+
+      await program.methods
+        .placeBet(0, stake, new BN(1), null, null, bettor3.publicKey)
+        .accounts({
+          bettor: bettor2.publicKey,
+          bettingMarket: lmsrMarketPda,
+          outcomePosition: giftPosition,
+          bettorPosition: positionPda(lmsrMarketPda, bettor3.publicKey),
+          mint: usdcMint,
+          bettorToken: payerToken,
+          marketVault: lmsrVault,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bettor2])
+        .rpc();
+
+      const payerAfter = new BN((await connection.getTokenAccountBalance(payerToken)).value.amount);
+      assert.isTrue(payerBefore.sub(payerAfter).eq(stake), "The payer funds the stake");
+      const position = await program.account.bettorPosition.fetch(positionPda(lmsrMarketPda, bettor3.publicKey));
+      const held = await program.account.outcomePositionAccount.fetch(giftPosition);
+      assert.equal(position.bettor.toBase58(), bettor3.publicKey.toBase58());
+      assert.equal(held.bettor.toBase58(), bettor3.publicKey.toBase58());
+      assert.isTrue(held.shares.gt(before?.shares ?? new BN(0)));
+    });
+
+    it("Should let a session key bet with its owner's tokens", async () => {
+      const sessionKey = Keypair.generate();
+      await airdrop(sessionKey.publicKey, LAMPORTS_PER_SOL);
+      const ownerToken = await getAssociatedTokenAddress(usdcMint, bettor1.publicKey);
+      const [session] = PublicKey.findProgramAddressSync(
+        [Buffer.from("session_key"), bettor1.publicKey.toBuffer(), sessionKey.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .createSession(sessionKey.publicKey, new BN(15 * 10 ** USDC_DECIMALS), new BN(600))
+        .accounts({
+          owner: bettor1.publicKey,
+          mint: usdcMint,
+          ownerToken,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bettor1])
+        .rpc();
+
+      const sessionBet = (amount: number) =>
+        program.methods
+          .placeBet(1, new BN(amount), new BN(1), null, null, bettor1.publicKey)
+          .accounts({
+            bettor: sessionKey.publicKey,
+            bettingMarket: lmsrMarketPda,
+            outcomePosition: outcomePositionPda(lmsrMarketPda, bettor1.publicKey, 1),
+            bettorPosition: positionPda(lmsrMarketPda, bettor1.publicKey),
+            session,
+            mint: usdcMint,
+            bettorToken: ownerToken,
+            marketVault: lmsrVault,
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([sessionKey])
+          .rpc();
+
+      const before = await program.account.bettorPosition.fetch(positionPda(lmsrMarketPda, bettor1.publicKey));
+      await sessionBet(10 * 10 ** USDC_DECIMALS);
+      const after = await program.account.bettorPosition.fetch(positionPda(lmsrMarketPda, bettor1.publicKey));
+      assert.equal(after.totalInvested.sub(before.totalInvested).toNumber(), 10 * 10 ** USDC_DECIMALS);
+
+      try {
+        await sessionBet(10 * 10 ** USDC_DECIMALS);
+        assert.fail("Should have thrown");
+      } catch (error) {
+        assert.include(error.message, "SessionSpendLimitExceeded");
+      }
+    });
+
+    it("Should not let the payer take over the gifted position", async () => {
+      try {
+        await program.methods
+          .placeBet(0, new BN(10 * 10 ** USDC_DECIMALS), new BN(1), null, null, bettor3.publicKey)
+          .accounts({
+            bettor: bettor2.publicKey,
+            bettingMarket: lmsrMarketPda,
+            outcomePosition: outcomePositionPda(lmsrMarketPda, bettor2.publicKey, 0),
+            bettorPosition: positionPda(lmsrMarketPda, bettor3.publicKey),
+            mint: usdcMint,
+            bettorToken: await getAssociatedTokenAddress(usdcMint, bettor2.publicKey),
+            marketVault: lmsrVault,
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([bettor2])
+          .rpc();
+        assert.fail("Should have thrown");
+      } catch (error) {
+        assert.match(error.message, /ConstraintSeeds|seeds constraint/);
+      }
+    });
+  });
+
+  describe("🚫 Market Cancellation", () => {
+    let cancelStreamPda: PublicKey;
+    let cancelMarketPda: PublicKey;
+    let cancelVault: PublicKey;
+
+    async function claimRefund(bettor: Keypair) {
+      await program.methods
+        .claimRefund()
+        .accounts({
+          bettor: bettor.publicKey,
+          bettingMarket: cancelMarketPda,
+          bettorPosition: positionPda(cancelMarketPda, bettor.publicKey),
+          mint: usdcMint,
+          bettorToken: await getAssociatedTokenAddress(usdcMint, bettor.publicKey),
+          marketVault: cancelVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bettor])
+        .rpc();
+    }
+
+    before(async () => {
+      cancelStreamPda = await nextStreamPda(host.publicKey);
+      [cancelMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, cancelStreamPda.toBuffer()],
+        program.programId
+      );
+      [cancelVault] = PublicKey.findProgramAddressSync(
+        [MARKET_VAULT_SEED, cancelMarketPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Cancelled Market Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: cancelStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, cancelStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 3600),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          { constantProduct: {} },
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: cancelStreamPda,
+          mint: usdcMint,
+          bettingMarket: cancelMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      await placeBet(cancelMarketPda, bettor1, 0, 40 * 10 ** USDC_DECIMALS);
+      await placeBet(cancelMarketPda, bettor1, 1, 15 * 10 ** USDC_DECIMALS);
+      await placeBet(cancelMarketPda, bettor2, 1, 25 * 10 ** USDC_DECIMALS);
+    });
+
+    it("Should refuse refunds while the market is live", async () => {
+      try {
+        await claimRefund(bettor1);
+        assert.fail("Refunds only open once the market is cancelled");
+      } catch (error) {
+        assert.include(error.message, "MarketNotCancelled");
+      }
+    });
+
+    it("Should only let the host or platform admin cancel", async () => {
+      try {
+        await program.methods
+          .cancelMarket()
+          .accounts({ authority: bettor1.publicKey, bettingMarket: cancelMarketPda })
+          .signers([bettor1])
+          .rpc();
+        assert.fail("A bettor cannot cancel the market");
+      } catch (error) {
+        assert.include(error.message, "Unauthorized");
+      }
+
+      await program.methods
+        .cancelMarket()
+        .accounts({ authority: host.publicKey, bettingMarket: cancelMarketPda })
+        .signers([host])
+        .rpc();
+
+      const market = await program.account.bettingMarket.fetch(cancelMarketPda);
+      assert.isTrue(market.cancelled);
+      assert.isNull(market.winningOutcome);
+    });
+
+    it("Should close the market to further bets", async () => {
+      try {
+        await placeBet(cancelMarketPda, bettor3, 0, 10 * 10 ** USDC_DECIMALS);
+        assert.fail("Cancelled markets take no bets");
+      } catch (error) {
+        assert.include(error.message, "MarketResolved");
+      }
+    });
+
+    it("Should only roll a void round into the next one after the claim window", async () => {
+      const nextRoundStreamPda = await nextStreamPda(host.publicKey);
+      const [nextMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, nextRoundStreamPda.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .initialize("Next Round Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: nextRoundStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, nextRoundStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 3600),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: nextRoundStreamPda,
+          mint: usdcMint,
+          bettingMarket: nextMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+      // The first bet opens the next round's vault
+      await placeBet(nextMarketPda, bettor3, 0, 10 * 10 ** USDC_DECIMALS);
+
+      const rollover = (from: PublicKey, to: PublicKey) =>
+        program.methods
+          .rolloverPool()
+          .accounts({
+            host: host.publicKey,
+            bettingMarket: from,
+            marketVault: PublicKey.findProgramAddressSync([MARKET_VAULT_SEED, from.toBuffer()], program.programId)[0],
+            targetMarket: to,
+            targetVault: PublicKey.findProgramAddressSync([MARKET_VAULT_SEED, to.toBuffer()], program.programId)[0],
+            mint: usdcMint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+
+      try {
+        await rollover(nextMarketPda, cancelMarketPda);
+        assert.fail("A live round has nothing to roll over");
+      } catch (error) {
+        assert.include(error.message, "MarketNotCancelled");
+      }
+
+      try {
+        await rollover(cancelMarketPda, nextMarketPda);
+        assert.fail("Bettors still have their refunds to claim");
+      } catch (error) {
+        assert.include(error.message, "ClaimWindowOpen");
+      }
+    });
+
+    async function closeOutcomePosition(bettor: Keypair, outcome: number) {
+      await program.methods
+        .closeOutcomePosition()
+        .accounts({
+          bettor: bettor.publicKey,
+          bettingMarket: cancelMarketPda,
+          outcomePosition: outcomePositionPda(cancelMarketPda, bettor.publicKey, outcome),
+        })
+        .signers([bettor])
+        .rpc();
+    }
+
+    it("Should keep outcome positions while their bettor position is open", async () => {
+      const position = await program.account.bettorPosition.fetch(positionPda(cancelMarketPda, bettor1.publicKey));
+      assert.equal(position.openOutcomes, 2);
+
+      try {
+        await closeOutcomePosition(bettor1, 0);
+        assert.fail("Outcome positions outlive the position they belong to");
+      } catch (error) {
+        assert.include(error.message, "PositionNotClosed");
+      }
+    });
+
+    it("Should refund every bettor exactly what they invested", async () => {
+      for (const [bettor, invested] of [
+        [bettor1, 55 * 10 ** USDC_DECIMALS],
+        [bettor2, 25 * 10 ** USDC_DECIMALS],
+      ] as [Keypair, number][]) {
+        const token = await getAssociatedTokenAddress(usdcMint, bettor.publicKey);
+        const before = new BN((await connection.getTokenAccountBalance(token)).value.amount);
+        await claimRefund(bettor);
+        const after = new BN((await connection.getTokenAccountBalance(token)).value.amount);
+        assert.equal(after.sub(before).toNumber(), invested);
+        assert.isNull(await program.account.bettorPosition.fetchNullable(positionPda(cancelMarketPda, bettor.publicKey)));
+      }
+
+      const vault = await connection.getTokenAccountBalance(cancelVault);
+      assert.equal(vault.value.amount, "0");
+    });
+
+    it("Should return outcome position rent once the position is refunded", async () => {
+      for (const outcome of [0, 1]) {
+        await closeOutcomePosition(bettor1, outcome);
+        assert.isNull(
+          await program.account.outcomePositionAccount.fetchNullable(
+            outcomePositionPda(cancelMarketPda, bettor1.publicKey, outcome)
+          )
+        );
+      }
+    });
+  });
+
+  describe("🪃 Markets Nobody Won", () => {
+    let unwonStreamPda: PublicKey;
+    let unwonMarketPda: PublicKey;
+    let unwonVault: PublicKey;
+    const STAKE = 30 * 10 ** USDC_DECIMALS;
+
+    before(async () => {
+      unwonStreamPda = await nextStreamPda(host.publicKey);
+      [unwonMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, unwonStreamPda.toBuffer()],
+        program.programId
+      );
+      [unwonVault] = PublicKey.findProgramAddressSync(
+        [MARKET_VAULT_SEED, unwonMarketPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Unwon Market Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: unwonStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, unwonStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 3),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: unwonStreamPda,
+          mint: usdcMint,
+          bettingMarket: unwonMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      await placeBet(unwonMarketPda, bettor1, 0, STAKE);
+
+      await new Promise((resolve) => setTimeout(resolve, 4000));
+      await program.methods
+        .resolveMarket(1, Array.from(randomBytes(32)))
+        .accounts({ resolver: host.publicKey, bettingMarket: unwonMarketPda })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should leave the pool out of the host's dust", async () => {
+      const market = await program.account.bettingMarket.fetch(unwonMarketPda);
+      assert.equal(market.winningOutcome, 1);
+      assert.equal(market.dustAccumulator.toNumber(), 0);
+    });
+
+    it("Should refund the stakes when nobody holds the winning outcome", async () => {
+      const token = await getAssociatedTokenAddress(usdcMint, bettor1.publicKey);
+      const before = new BN((await connection.getTokenAccountBalance(token)).value.amount);
+
+      await program.methods
+        .claimRefund()
+        .accounts({
+          bettor: bettor1.publicKey,
+          bettingMarket: unwonMarketPda,
+          bettorPosition: positionPda(unwonMarketPda, bettor1.publicKey),
+          mint: usdcMint,
+          bettorToken: token,
+          marketVault: unwonVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bettor1])
+        .rpc();
+
+      const after = new BN((await connection.getTokenAccountBalance(token)).value.amount);
+      assert.equal(after.sub(before).toNumber(), STAKE);
+      assert.isNull(await program.account.bettorPosition.fetchNullable(positionPda(unwonMarketPda, bettor1.publicKey)));
+    });
+  });
+
+  describe("➕ Expanding Markets", () => {
+    let expandStreamPda: PublicKey;
+    let expandMarketPda: PublicKey;
+
+    const expand = (outcomes: string[], marketPda = expandMarketPda) =>
+      program.methods
+        .expandMarket(outcomes)
+        .accounts({ host: host.publicKey, bettingMarket: marketPda })
+        .signers([host])
+        .rpc();
+
+    before(async () => {
+      expandStreamPda = await nextStreamPda(host.publicKey);
+      [expandMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, expandStreamPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Expanding Market Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: expandStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, expandStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { multiOutcome: { max: 4 } },
+          ["Red", "Blue"],
+          new BN(Math.floor(Date.now() / 1000) + 3600),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: expandStreamPda,
+          mint: usdcMint,
+          bettingMarket: expandMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should refuse a description longer than the outcome limit", async () => {
+      try {
+        await expand(["G".repeat(65)]);
+        assert.fail("Should have thrown");
+      } catch (error) {
+        assert.include(error.message, "InvalidMarketSetup");
+      }
+    });
+
+    it("Should add outcomes before anyone bets", async () => {
+      await expand(["Green"]);
+
+      const market = await program.account.bettingMarket.fetch(expandMarketPda);
+      const book = await program.account.marketBook.fetch(marketBookPda(expandMarketPda));
+      const config = await program.account.marketConfig.fetch(marketConfigPda(expandMarketPda));
+      assert.equal(market.outcomeCount, 3);
+      assert.equal(book.outcomeCount, 3);
+      assert.deepEqual(config.outcomeDescriptions, ["Red", "Blue", "Green"]);
+      assert.isTrue(book.outcomes[2].liquidityReserve.eq(book.outcomes[0].liquidityReserve));
+    });
+
+    it("Should not grow past the market's outcome cap", async () => {
+      try {
+        await expand(["Yellow", "Purple"]);
+        assert.fail("Should have thrown");
+      } catch (error) {
+        assert.include(error.message, "InvalidMarketSetup");
+      }
+    });
+
+    it("Should swap contestants and keep the liquidity evenly split", async () => {
+      await program.methods
+        .addMarketOutcome("Yellow")
+        .accounts({ host: host.publicKey, bettingMarket: expandMarketPda })
+        .signers([host])
+        .rpc();
+      await program.methods
+        .removeMarketOutcome(1)
+        .accounts({ host: host.publicKey, bettingMarket: expandMarketPda })
+        .signers([host])
+        .rpc();
+
+      const book = await program.account.marketBook.fetch(marketBookPda(expandMarketPda));
+      const config = await program.account.marketConfig.fetch(marketConfigPda(expandMarketPda));
+      assert.deepEqual(config.outcomeDescriptions, ["Red", "Green", "Yellow"]);
+      assert.equal(book.outcomeCount, 3);
+      const share = INITIAL_LIQUIDITY.divn(3);
+      for (const outcome of book.outcomes.slice(0, 3)) {
+        assert.isTrue(outcome.liquidityReserve.eq(share));
+      }
+      assert.equal(book.outcomes[3].liquidityReserve.toNumber(), 0);
+    });
+
+    it("Should lock the outcomes once a bet is in", async () => {
+      await placeBet(expandMarketPda, bettor1, 2, 10 * 10 ** USDC_DECIMALS);
+
+      try {
+        await expand(["Purple"]);
+        assert.fail("Should have thrown");
+      } catch (error) {
+        assert.include(error.message, "MarketConfigLocked");
+      }
+      try {
+        await program.methods
+          .removeMarketOutcome(0)
+          .accounts({ host: host.publicKey, bettingMarket: expandMarketPda })
+          .signers([host])
+          .rpc();
+        assert.fail("Should have thrown");
+      } catch (error) {
+        assert.include(error.message, "MarketConfigLocked");
+      }
+    });
+  });
+
+  describe("💧 Liquidity Providers", () => {
+    const LP_POSITION_SEED = Buffer.from("lp_position");
+    const DEPOSIT = 100 * 10 ** USDC_DECIMALS;
+    let lpStreamPda: PublicKey;
+    let lpMarketPda: PublicKey;
+    let lpVault: PublicKey;
+
+    function lpPositionPda(provider: PublicKey): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [LP_POSITION_SEED, lpMarketPda.toBuffer(), provider.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    async function addLiquidity(provider: Keypair, amount: number) {
+      await program.methods
+        .addLiquidity(new BN(amount))
+        .accounts({
+          provider: provider.publicKey,
+          bettingMarket: lpMarketPda,
+          lpPosition: lpPositionPda(provider.publicKey),
+          mint: usdcMint,
+          providerToken: await getAssociatedTokenAddress(usdcMint, provider.publicKey),
+          marketVault: lpVault,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([provider])
+        .rpc();
+    }
+
+    async function removeLiquidity(provider: Keypair, lpShares: BN) {
+      await program.methods
+        .removeLiquidity(lpShares)
+        .accounts({
+          provider: provider.publicKey,
+          bettingMarket: lpMarketPda,
+          lpPosition: lpPositionPda(provider.publicKey),
+          mint: usdcMint,
+          providerToken: await getAssociatedTokenAddress(usdcMint, provider.publicKey),
+          marketVault: lpVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([provider])
+        .rpc();
+    }
+
+    before(async () => {
+      lpStreamPda = await nextStreamPda(host.publicKey);
+      [lpMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, lpStreamPda.toBuffer()],
+        program.programId
+      );
+      [lpVault] = PublicKey.findProgramAddressSync(
+        [MARKET_VAULT_SEED, lpMarketPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Liquidity Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: lpStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, lpStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 3600),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          { constantProduct: {} },
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: lpStreamPda,
+          mint: usdcMint,
+          bettingMarket: lpMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should back the outcome reserves with deposited tokens", async () => {
+      const bookBefore = await program.account.marketBook.fetch(marketBookPda(lpMarketPda));
+      await addLiquidity(bettor1, DEPOSIT);
+
+      const position = await program.account.lpPosition.fetch(lpPositionPda(bettor1.publicKey));
+      assert.equal(position.lpShares.toNumber(), DEPOSIT, "The first deposit mints shares one to one");
+      assert.equal(position.deposited.toNumber(), DEPOSIT);
+
+      const book = await program.account.marketBook.fetch(marketBookPda(lpMarketPda));
+      for (let i = 0; i < book.outcomeCount; i++) {
+        assert.equal(
+          book.outcomes[i].liquidityReserve.sub(bookBefore.outcomes[i].liquidityReserve).toNumber(),
+          DEPOSIT / 2
+        );
+      }
+      assert.equal(book.totalPool.toNumber(), bookBefore.totalPool.toNumber(), "Provider tokens stay out of the betting pool");
+      assert.equal((await connection.getTokenAccountBalance(lpVault)).value.amount, DEPOSIT.toString());
+    });
+
+    it("Should mint later deposits pro rata to the provider pool", async () => {
+      await addLiquidity(bettor2, DEPOSIT / 2);
+      const market = await program.account.bettingMarket.fetch(lpMarketPda);
+      const position = await program.account.lpPosition.fetch(lpPositionPda(bettor2.publicKey));
+      assert.equal(position.lpShares.toNumber(), DEPOSIT / 2);
+      assert.equal(market.lpTotalShares.toNumber(), DEPOSIT * 1.5);
+      assert.equal(market.lpPool.toNumber(), DEPOSIT * 1.5);
+    });
+
+    it("Should refuse to redeem more shares than the provider holds", async () => {
+      try {
+        await removeLiquidity(bettor2, new BN(DEPOSIT));
+        assert.fail("Only held shares can be redeemed");
+      } catch (error) {
+        assert.include(error.message, "InsufficientLpShares");
+      }
+    });
+
+    it("Should keep provider funds in the market until it settles", async () => {
+      try {
+        await removeLiquidity(bettor1, new BN(DEPOSIT / 4));
+        assert.fail("Depth cannot be pulled out from under open bets");
+      } catch (error) {
+        assert.include(error.message, "LiquidityLocked");
+      }
+
+      await program.methods
+        .cancelMarket()
+        .accounts({ authority: host.publicKey, bettingMarket: lpMarketPda })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should redeem shares and close an emptied position", async () => {
+      const token = await getAssociatedTokenAddress(usdcMint, bettor1.publicKey);
+      const before = Number((await connection.getTokenAccountBalance(token)).value.amount);
+      await removeLiquidity(bettor1, new BN(DEPOSIT / 4));
+      await removeLiquidity(bettor1, new BN((DEPOSIT * 3) / 4));
+      const after = Number((await connection.getTokenAccountBalance(token)).value.amount);
+
+      assert.equal(after - before, DEPOSIT);
+      assert.isNull(await program.account.lpPosition.fetchNullable(lpPositionPda(bettor1.publicKey)));
+      const market = await program.account.bettingMarket.fetch(lpMarketPda);
+      assert.equal(market.lpTotalShares.toNumber(), DEPOSIT / 2);
+    });
+  });
+
+  describe("🧢 Betting Limits", () => {
+    const USDC = 10 ** USDC_DECIMALS;
+    let limitStreamPda: PublicKey;
+    let limitMarketPda: PublicKey;
+
+    function bet(bettor: Keypair, amount: number) {
+      return placeBet(limitMarketPda, bettor, 0, amount);
+    }
+
+    function setLimits(maxBetPerUser: BN | null, maxTotalPool: BN | null) {
+      return program.methods
+        .setMarketLimits(maxBetPerUser, maxTotalPool)
+        .accounts({ host: host.publicKey, bettingMarket: limitMarketPda })
+        .signers([host])
+        .rpc();
+    }
+
+    before(async () => {
+      limitStreamPda = await nextStreamPda(host.publicKey);
+      [limitMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, limitStreamPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Community Market Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: limitStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, limitStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 3600),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          { constantProduct: {} },
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: limitStreamPda,
+          mint: usdcMint,
+          bettingMarket: limitMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should reject a zero limit", async () => {
+      try {
+        await setLimits(new BN(0), null);
+        assert.fail("A zero cap would close the market");
+      } catch (error) {
+        assert.include(error.message, "InvalidAmount");
+      }
+    });
+
+    it("Should cap each bettor's total stake", async () => {
+      await setLimits(new BN(20 * USDC), new BN(30 * USDC));
+      const market = await program.account.bettingMarket.fetch(limitMarketPda);
+      assert.equal(market.maxBetPerUser.toNumber(), 20 * USDC);
+      assert.equal(market.maxTotalPool.toNumber(), 30 * USDC);
+
+      await bet(bettor1, 15 * USDC);
+      try {
+        await bet(bettor1, 10 * USDC);
+        assert.fail("Stakes add up toward the per-user cap");
+      } catch (error) {
+        assert.include(error.message, "BetLimitExceeded");
+      }
+    });
+
+    it("Should stop the pool at its cap", async () => {
+      try {
+        await bet(bettor2, 20 * USDC);
+        assert.fail("The pool would pass its cap");
+      } catch (error) {
+        assert.include(error.message, "PoolLimitReached");
+      }
+
+      await bet(bettor2, 15 * USDC);
+      const book = await program.account.marketBook.fetch(marketBookPda(limitMarketPda));
+      assert.equal(book.totalPool.toNumber(), 30 * USDC);
+    });
+
+    it("Should lock the limits once betting has started", async () => {
+      try {
+        await setLimits(null, null);
+        assert.fail("Limits cannot change under open bets");
+      } catch (error) {
+        assert.include(error.message, "MarketConfigLocked");
+      }
+    });
+
+    it("Should stop bets once the host closes betting early", async () => {
+      const closeBetting = () =>
+        program.methods
+          .closeBetting()
+          .accounts({ host: host.publicKey, bettingMarket: limitMarketPda })
+          .signers([host])
+          .rpc();
+
+      await closeBetting();
+      const market = await program.account.bettingMarket.fetch(limitMarketPda);
+      assert.isNotNull(market.bettingClosedAt);
+      assert.isTrue(market.bettingClosedAt.lt(market.resolutionTime), "Closed ahead of the resolution time");
+
+      try {
+        await bet(bettor3, USDC);
+        assert.fail("Betting is closed");
+      } catch (error) {
+        assert.include(error.message, "BettingClosed");
+      }
+
+      try {
+        await closeBetting();
+        assert.fail("Betting is already closed");
+      } catch (error) {
+        assert.include(error.message, "BettingClosed");
+      }
+    });
+
+    it("Should only push the resolution time later once bets exist", async () => {
+      const reschedule = (newTime: BN) =>
+        program.methods
+          .updateResolutionTime(newTime)
+          .accounts({ host: host.publicKey, bettingMarket: limitMarketPda })
+          .signers([host])
+          .rpc();
+      const { resolutionTime } = await program.account.bettingMarket.fetch(limitMarketPda);
+
+      try {
+        await reschedule(resolutionTime.subn(600));
+        assert.fail("Bettors bet into the original window");
+      } catch (error) {
+        assert.include(error.message, "ResolutionTimeShortened");
+      }
+
+      await reschedule(resolutionTime.addn(1800));
+      const market = await program.account.bettingMarket.fetch(limitMarketPda);
+      assert.equal(market.resolutionTime.toNumber(), resolutionTime.toNumber() + 1800);
+    });
+  });
+
+  describe("🔐 Market Access", () => {
+    const MARKET_ACCESS_SEED = Buffer.from("market_access");
+    const STAKE = 5 * 10 ** USDC_DECIMALS;
+    let accessStreamPda: PublicKey;
+    let accessMarketPda: PublicKey;
+
+    const sha256 = (...parts: Buffer[]) => createHash("sha256").update(Buffer.concat(parts)).digest();
+    const leaf = (wallet: PublicKey) => sha256(wallet.toBuffer());
+    const parent = (a: Buffer, b: Buffer) => (Buffer.compare(a, b) <= 0 ? sha256(a, b) : sha256(b, a));
+
+    function accessPda(bettor: PublicKey): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [MARKET_ACCESS_SEED, accessMarketPda.toBuffer(), bettor.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    function bet(bettor: Keypair) {
+      return placeBet(accessMarketPda, bettor, 0, STAKE, { marketAccess: accessPda(bettor.publicKey) });
+    }
+
+    function setAccess(mode: object, root: Buffer) {
+      return program.methods
+        .setMarketAccess(mode as any, Array.from(root))
+        .accounts({ host: host.publicKey, bettingMarket: accessMarketPda })
+        .signers([host])
+        .rpc();
+    }
+
+    function join(bettor: Keypair, proof: Buffer[]) {
+      return program.methods
+        .joinMarketAllowlist(proof.map((node) => Array.from(node)))
+        .accounts({
+          bettor: bettor.publicKey,
+          bettingMarket: accessMarketPda,
+          accessEntry: accessPda(bettor.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([bettor])
+        .rpc();
+    }
+
+    function list(bettor: PublicKey) {
+      return program.methods
+        .listMarketBettor(bettor)
+        .accounts({
+          host: host.publicKey,
+          bettingMarket: accessMarketPda,
+          accessEntry: accessPda(bettor),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    }
+
+    before(async () => {
+      accessStreamPda = await nextStreamPda(host.publicKey);
+      [accessMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, accessStreamPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Subscriber Market Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: accessStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, accessStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 3600),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          { constantProduct: {} },
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: accessStreamPda,
+          mint: usdcMint,
+          bettingMarket: accessMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      // Subscribers approved off-chain: bettor1 and bettor2
+      await setAccess({ allowlist: {} }, parent(leaf(bettor1.publicKey), leaf(bettor2.publicKey)));
+    });
+
+    it("Should keep unlisted bettors out of an allowlisted market", async () => {
+      try {
+        await bet(bettor3);
+        assert.fail("bettor3 is not on the allowlist");
+      } catch (error) {
+        assert.include(error.message, "MarketAccessDenied");
+      }
+    });
+
+    it("Should reject a proof for someone else's leaf", async () => {
+      try {
+        await join(bettor3, [leaf(bettor2.publicKey)]);
+        assert.fail("The proof does not lead from bettor3 to the root");
+      } catch (error) {
+        assert.include(error.message, "InvalidAccessProof");
+      }
+    });
+
+    it("Should let subscribers join with a merkle proof", async () => {
+      await join(bettor1, [leaf(bettor2.publicKey)]);
+      const entry = await program.account.marketAccessEntry.fetch(accessPda(bettor1.publicKey));
+      assert.ok(entry.mode.allowlist);
+      await bet(bettor1);
+    });
+
+    it("Should let the host list a bettor directly", async () => {
+      await list(bettor3.publicKey);
+      await bet(bettor3);
+    });
+
+    it("Should exclude denylisted bettors and ignore allowlist entries", async () => {
+      await setAccess({ denylist: {} }, Buffer.alloc(32));
+      await list(bettor2.publicKey);
+
+      try {
+        await bet(bettor2);
+        assert.fail("bettor2 is banned");
+      } catch (error) {
+        assert.include(error.message, "MarketAccessDenied");
+      }
+
+      // bettor1's allowlist entry does not ban them once the market is a denylist
+      await bet(bettor1);
+    });
+  });
+
+  describe("🪞 Mirroring", () => {
+    const STAKE = 5 * 10 ** USDC_DECIMALS;
+    let mirrorStreamPda: PublicKey;
+    let mirrorMarketPda: PublicKey;
+    let mirrorVault: PublicKey;
+
+    function mirrorConfigPda(): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("mirror_config"), mirrorMarketPda.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    function mirrorPositionPda(follower: PublicKey, outcome: number): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("mirror_position"), mirrorMarketPda.toBuffer(), follower.toBuffer(), Buffer.from([outcome])],
+        program.programId
+      )[0];
+    }
+
+    function bet(bettor: Keypair, outcome: number) {
+      return placeBet(mirrorMarketPda, bettor, outcome, STAKE);
+    }
+
+    async function mirror(follower: Keypair, tipster: PublicKey, outcome: number) {
+      await program.methods
+        .mirrorBet(new BN(STAKE), new BN(1))
+        .accounts({
+          follower: follower.publicKey,
+          bettingMarket: mirrorMarketPda,
+          sourcePosition: outcomePositionPda(mirrorMarketPda, tipster, outcome),
+          outcomePosition: outcomePositionPda(mirrorMarketPda, follower.publicKey, outcome),
+          mirrorPosition: mirrorPositionPda(follower.publicKey, outcome),
+          mint: usdcMint,
+          followerToken: await getAssociatedTokenAddress(usdcMint, follower.publicKey),
+          marketVault: mirrorVault,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([follower])
+        .rpc();
+    }
+
+    before(async () => {
+      mirrorStreamPda = await nextStreamPda(host.publicKey);
+      [mirrorMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, mirrorStreamPda.toBuffer()],
+        program.programId
+      );
+      [mirrorVault] = PublicKey.findProgramAddressSync(
+        [MARKET_VAULT_SEED, mirrorMarketPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Tipster Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: mirrorStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, mirrorStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 3600),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          { constantProduct: {} },
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: mirrorStreamPda,
+          mint: usdcMint,
+          bettingMarket: mirrorMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      // bettor1 is the tipster, promised 5% of what followers win
+      await program.methods
+        .enableHostPositionMirroring(bettor1.publicKey, 500)
+        .accounts({ host: host.publicKey, bettingMarket: mirrorMarketPda })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should refuse to mirror before the tipster has bet", async () => {
+      try {
+        await mirror(bettor2, bettor1.publicKey, 0);
+        assert.fail("There is no bet to copy yet");
+      } catch (error) {
+        assert.include(error.message, "NoBetToMirror");
+      }
+    });
+
+    it("Should track the tipster's latest bet and copy it", async () => {
+      await bet(bettor1, 0);
+      await bet(bettor1, 1);
+      // Other bettors do not move the bet being mirrored
+      await bet(bettor3, 0);
+
+      const config = await program.account.mirrorConfig.fetch(mirrorConfigPda());
+      assert.equal(config.latestOutcome, 1);
+      assert.equal(config.latestStake.toNumber(), STAKE);
+
+      await mirror(bettor2, bettor1.publicKey, 1);
+
+      const position = await program.account.mirrorPosition.fetch(mirrorPositionPda(bettor2.publicKey, 1));
+      assert.ok(position.tipster.equals(bettor1.publicKey));
+      assert.equal(position.outcomeId, 1);
+      assert.equal(position.revenueShareBps, 500);
+      assert.equal(position.staked.toNumber(), STAKE);
+      assert.ok(position.shares.gtn(0));
+
+      const shares = await program.account.outcomePositionAccount.fetch(
+        outcomePositionPda(mirrorMarketPda, bettor2.publicKey, 1)
+      );
+      assert.ok(shares.shares.eq(position.shares));
+
+      const after = await program.account.mirrorConfig.fetch(mirrorConfigPda());
+      assert.equal(after.mirroredVolume.toNumber(), STAKE);
+    });
+
+    it("Should not let the tipster mirror themselves", async () => {
+      try {
+        await mirror(bettor1, bettor1.publicKey, 1);
+        assert.fail("The tipster cannot copy their own bet");
+      } catch (error) {
+        assert.include(error.message, "Unauthorized");
+      }
+    });
+
+    it("Should cap the tipster's revenue share", async () => {
+      try {
+        await program.methods
+          .enableHostPositionMirroring(null, 2001)
+          .accounts({ host: host.publicKey, bettingMarket: mirrorMarketPda })
+          .signers([host])
+          .rpc();
+        assert.fail("The share is above the 20% cap");
+      } catch (error) {
+        assert.include(error.message, "InvalidFeePercentage");
+      }
+    });
+  });
+
+  describe("🏊 Parimutuel Pools", () => {
+    const UNIT = 10 ** USDC_DECIMALS;
+    let poolStreamPda: PublicKey;
+    let poolMarketPda: PublicKey;
+    let poolVault: PublicKey;
+
+    before(async () => {
+      poolStreamPda = await nextStreamPda(host.publicKey);
+      [poolMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, poolStreamPda.toBuffer()],
+        program.programId
+      );
+      [poolVault] = PublicKey.findProgramAddressSync(
+        [MARKET_VAULT_SEED, poolMarketPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Pool Betting Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: poolStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, poolStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 6),
+          new BN(0),
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          { parimutuel: {} },
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: poolStreamPda,
+          mint: usdcMint,
+          bettingMarket: poolMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should start with no reserves and even odds", async () => {
+      const book = await program.account.marketBook.fetch(marketBookPda(poolMarketPda));
+      assert.isTrue(book.outcomes.slice(0, 2).every((o) => o.liquidityReserve.isZero()));
+
+      const quote = await program.methods
+        .getQuote(0, new BN(10 * UNIT))
+        .accounts({ bettingMarket: poolMarketPda })
+        .view();
+      assert.equal(quote.probabilityBps, 5000);
+      assert.equal(quote.shares.toNumber(), 10 * UNIT);
+    });
+
+    it("Should record stakes one share per unit", async () => {
+      await placeBet(poolMarketPda, bettor1, 0, 10 * UNIT);
+      await placeBet(poolMarketPda, bettor2, 0, 30 * UNIT);
+      await placeBet(poolMarketPda, bettor3, 1, 20 * UNIT);
+
+      const held = await program.account.outcomePositionAccount.fetch(
+        outcomePositionPda(poolMarketPda, bettor2.publicKey, 0)
+      );
+      assert.equal(held.shares.toNumber(), 30 * UNIT);
+
+      const book = await program.account.marketBook.fetch(marketBookPda(poolMarketPda));
+      assert.equal(book.totalPool.toNumber(), 60 * UNIT);
+      assert.equal(book.outcomes[0].totalShares.toNumber(), 40 * UNIT);
+    });
+
+    it("Should not let stakes be sold back", async () => {
+      try {
+        await program.methods
+          .sellShares(0, new BN(UNIT), new BN(0))
+          .accounts({
+            bettor: bettor1.publicKey,
+            bettingMarket: poolMarketPda,
+            mint: usdcMint,
+            bettorToken: await getAssociatedTokenAddress(usdcMint, bettor1.publicKey),
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([bettor1])
+          .rpc();
+        assert.fail("Pool stakes are final");
+      } catch (error) {
+        assert.include(error.message, "SharesNotSellable");
+      }
+    });
+
+    it("Should split the pool between the winners by stake", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 6000));
+      await program.methods
+        .resolveMarket(0, Array.from(randomBytes(32)))
+        .accounts({ resolver: host.publicKey, bettingMarket: poolMarketPda })
+        .signers([host])
+        .rpc();
+
+      const token = await getAssociatedTokenAddress(usdcMint, bettor2.publicKey);
+      const before = new BN((await connection.getTokenAccountBalance(token)).value.amount);
+
+      await program.methods
+        .claimWinnings()
+        .accounts({
+          bettor: bettor2.publicKey,
+          bettingMarket: poolMarketPda,
+          mint: usdcMint,
+          bettorToken: token,
+          marketVault: poolVault,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(payingPositions(poolMarketPda, bettor2.publicKey, [0]))
+        .signers([bettor2])
+        .rpc();
+
+      // 30 of the 40 staked on the winner: three quarters of the 60 pool, less the market fee
+      const gross = 45 * UNIT;
+      const after = new BN((await connection.getTokenAccountBalance(token)).value.amount);
+      assert.equal(after.sub(before).toNumber(), gross - (gross * FEE_PERCENTAGE) / 10_000);
+    });
+  });
+
+  describe("🤝 Bet Referrals", () => {
+    const STAKE = 10 * 10 ** USDC_DECIMALS;
+    let refStreamPda: PublicKey;
+    let refMarketPda: PublicKey;
+    let refVault: PublicKey;
+
+    const earningsPda = (referrer: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("referral_earnings"), referrer.toBuffer(), usdcMint.toBuffer()],
+        program.programId
+      )[0];
+    const earningsVaultPda = (referrer: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("referral_earnings_vault"), earningsPda(referrer).toBuffer()],
+        program.programId
+      )[0];
+    function bet(bettor: Keypair, outcome: number, referrer: PublicKey | null) {
+      return placeBet(refMarketPda, bettor, outcome, STAKE, { referrer });
+    }
+
+    before(async () => {
+      refStreamPda = await nextStreamPda(host.publicKey);
+      [refMarketPda] = PublicKey.findProgramAddressSync(
+        [MARKET_SEED, refStreamPda.toBuffer()],
+        program.programId
+      );
+      [refVault] = PublicKey.findProgramAddressSync(
+        [MARKET_VAULT_SEED, refMarketPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("Affiliate Stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: refStreamPda,
+          mint: usdcMint,
+          streamAta: await getAssociatedTokenAddress(usdcMint, refStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeBettingMarket(
+          { binary: {} },
+          ["Yes", "No"],
+          new BN(Math.floor(Date.now() / 1000) + 6),
+          INITIAL_LIQUIDITY,
+          FEE_PERCENTAGE,
+          { winnerTakesAll: {} },
+          null,
+          null,
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: refStreamPda,
+          mint: usdcMint,
+          bettingMarket: refMarketPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .openReferralEarnings()
+        .accounts({ referrer: bettor3.publicKey, mint: usdcMint, tokenProgram: TOKEN_PROGRAM_ID })
+        .signers([bettor3])
+        .rpc();
+    });
+
+    it("Should cap the referral share of the host's fee", async () => {
+      try {
+        await program.methods
+          .setBetReferralShare(5001)
+          .accounts({ host: host.publicKey, bettingMarket: refMarketPda })
+          .signers([host])
+          .rpc();
+        assert.fail("Referrers get at most half the host's fee");
+      } catch (error) {
+        assert.include(error.message, "InvalidFeePercentage");
+      }
+
+      await program.methods
+        .setBetReferralShare(5000)
+        .accounts({ host: host.publicKey, bettingMarket: refMarketPda })
+        .signers([host])
+        .rpc();
+    });
+
+    it("Should record the first referrer on the position", async () => {
+      await bet(bettor1, 0, bettor3.publicKey);
+      // A later referrer does not replace the first
+      await bet(bettor1, 0, bettor2.publicKey);
+      await bet(bettor2, 1, null);
+
+      const referred = await program.account.bettorPosition.fetch(positionPda(refMarketPda, bettor1.publicKey));
+      assert.ok(referred.referrer.equals(bettor3.publicKey));
+      const unreferred = await program.account.bettorPosition.fetch(positionPda(refMarketPda, bettor2.publicKey));
+      assert.isNull(unreferred.referrer);
+    });
+
+    it("Should pay the referrer a share of the fee on a referred bettor's claim", async () => {
+      await new Promise((resolve) => setTimeout(resolve, 6000));
+      await program.methods
+        .resolveMarket(0, Array.from(randomBytes(32)))
+        .accounts({ resolver: host.publicKey, bettingMarket: refMarketPda })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .claimWinnings()
+        .accounts({
+          bettor: bettor1.publicKey,
+          bettingMarket: refMarketPda,
+          mint: usdcMint,
+          bettorToken: await getAssociatedTokenAddress(usdcMint, bettor1.publicKey),
+          marketVault: refVault,
+          referralEarnings: earningsPda(bettor3.publicKey),
+          referralEarningsVault: earningsVaultPda(bettor3.publicKey),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(payingPositions(refMarketPda, bettor1.publicKey, [0]))
+        .signers([bettor1])
+        .rpc();
+
+      // bettor1 holds every winning share, so the claim is the whole 30 USDC pool
+      const fee = (3 * STAKE * FEE_PERCENTAGE) / 10_000;
+      const earnings = await program.account.referralEarnings.fetch(earningsPda(bettor3.publicKey));
+      assert.equal(earnings.totalEarned.toNumber(), fee / 2);
+      assert.equal(earnings.referredClaims.toNumber(), 1);
+
+      const market = await program.account.bettingMarket.fetch(refMarketPda);
+      assert.equal(market.feesCollected.toNumber(), fee / 2, "The referral share comes out of the host's fee");
+    });
+
+    it("Should let the referrer withdraw their earnings", async () => {
+      const token = await getAssociatedTokenAddress(usdcMint, bettor3.publicKey);
+      const before = new BN((await connection.getTokenAccountBalance(token)).value.amount);
+
+      await program.methods
+        .claimReferralEarnings()
+        .accounts({
+          referrer: bettor3.publicKey,
+          mint: usdcMint,
+          referrerAta: token,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([bettor3])
+        .rpc();
+
+      const after = new BN((await connection.getTokenAccountBalance(token)).value.amount);
+      assert.equal(after.sub(before).toNumber(), (3 * STAKE * FEE_PERCENTAGE) / 20_000);
+
+      try {
+        await program.methods
+          .claimReferralEarnings()
+          .accounts({
+            referrer: bettor3.publicKey,
+            mint: usdcMint,
+            referrerAta: token,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([bettor3])
+          .rpc();
+        assert.fail("Everything earned has been withdrawn");
+      } catch (error) {
+        assert.include(error.message, "NothingToClaim");
+      }
+    });
   });
 
   describe("📊 LMSR Analytics", () => {
@@ -576,28 +4280,31 @@ describe("Vidbloq Betting Markets with Ephemeral VRF", () => {
       console.log("\n=== LMSR Market Analysis ===");
       
       const market = await program.account.bettingMarket.fetch(bettingMarketPda);
+      const book = await program.account.marketBook.fetch(marketBookPda(bettingMarketPda));
+      const config = await program.account.marketConfig.fetch(marketConfigPda(bettingMarketPda));
+      const outcomes = book.outcomes.slice(0, book.outcomeCount);
       
       console.log("Market Statistics:");
-      console.log("  Total Pool:", market.totalPool.toNumber() / 10 ** USDC_DECIMALS, "USDC");
-      console.log("  Total Liquidity:", market.totalLiquidity.toNumber() / 10 ** USDC_DECIMALS, "USDC");
-      console.log("  Fee:", market.feePercentage / 100, "%");
+      console.log("  Total Pool:", book.totalPool.toNumber() / 10 ** USDC_DECIMALS, "USDC");
+      console.log("  Total Liquidity:", config.totalLiquidity.toNumber() / 10 ** USDC_DECIMALS, "USDC");
+      console.log("  Fee:", config.feePercentage / 100, "%");
       console.log("  Resolved:", market.resolved);
       console.log("  Winning Outcome:", market.winningOutcome);
       
       console.log("\nOutcome Analysis (LMSR Pricing):");
       
       let totalLiquidity = new BN(0);
-      for (const outcome of market.outcomes) {
+      for (const outcome of outcomes) {
         totalLiquidity = totalLiquidity.add(outcome.liquidityReserve);
       }
       
-      for (let i = 0; i < market.outcomes.length; i++) {
-        const outcome = market.outcomes[i];
+      for (let i = 0; i < outcomes.length; i++) {
+        const outcome = outcomes[i];
         const probability = totalLiquidity.gt(new BN(0)) 
           ? outcome.liquidityReserve.toNumber() / totalLiquidity.toNumber()
           : 0.5;
         
-        console.log(`\n  ${outcome.description}:`);
+        console.log(`\n  ${config.outcomeDescriptions[i]}:`);
         console.log(`    - Total Shares: ${outcome.totalShares.toNumber() / 10 ** USDC_DECIMALS}`);
         console.log(`    - Liquidity Reserve: ${outcome.liquidityReserve.toNumber() / 10 ** USDC_DECIMALS} USDC`);
         console.log(`    - Total Backing: ${outcome.totalBacking.toNumber() / 10 ** USDC_DECIMALS} USDC`);
@@ -15,6 +15,9 @@ import {
   getAssociatedTokenAddress,
   mintTo,
   createAssociatedTokenAccountInstruction,
+  getAccount,
+  getAssociatedTokenAddressSync,
+  getMint,
 } from "@solana/spl-token";
 import { assert } from "chai";
 
@@ -54,14 +57,7 @@ describe("vidbloq-program", () => {
     mint = await createMint(connection, payer.payer, payer.publicKey, null, 6);
 
     // Derive stream PDA
-    [streamPda] = await PublicKey.findProgramAddress(
-      [
-        Buffer.from("stream"),
-        Buffer.from(streamName),
-        host.publicKey.toBuffer(),
-      ],
-      program.programId
-    );
+    streamPda = await nextStreamPda(host.publicKey);
 
     // Get associated token accounts
     streamAta = await getAssociatedTokenAddress(mint, streamPda, true);
@@ -69,7 +65,7 @@ describe("vidbloq-program", () => {
 
     // Create donor's token account and mint tokens
     await createDonorTokenAccount(mint, donor.publicKey);
-    await mintTokens(mint, donorAta, depositAmount * 10); // Mint enough for multiple deposits
+    await mintTokens(mint, donorAta, depositAmount * 20); // Mint enough for multiple deposits
 
     // Derive donor account PDA
     [donorAccount] = await PublicKey.findProgramAddress(
@@ -101,14 +97,7 @@ describe("vidbloq-program", () => {
 
       // Initialize prepaid stream
       prepaidStreamName = "prepaid_dist_test";
-      [prepaidStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(prepaidStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      prepaidStreamPda = await nextStreamPda(host.publicKey);
       prepaidStreamAta = await getAssociatedTokenAddress(
         mint,
         prepaidStreamPda,
@@ -119,6 +108,7 @@ describe("vidbloq-program", () => {
         .initialize(
           prepaidStreamName,
           { prepaid: { minDuration: new anchor.BN(5) } }, // Short duration for testing
+          null,
           null
         )
         .accounts({
@@ -144,7 +134,7 @@ describe("vidbloq-program", () => {
       );
 
       await program.methods
-        .deposit(new anchor.BN(depositAmount))
+        .deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: donor.publicKey,
           stream: prepaidStreamPda,
@@ -170,14 +160,7 @@ describe("vidbloq-program", () => {
 
       // Initialize conditional stream
       conditionalStreamName = "cond_dist_test";
-      [conditionalStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(conditionalStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      conditionalStreamPda = await nextStreamPda(host.publicKey);
       conditionalStreamAta = await getAssociatedTokenAddress(
         mint,
         conditionalStreamPda,
@@ -194,6 +177,7 @@ describe("vidbloq-program", () => {
               unlockTime: new anchor.BN(currentTime + 2), // Almost immediate unlock
             },
           },
+          null,
           null
         )
         .accounts({
@@ -219,7 +203,7 @@ describe("vidbloq-program", () => {
       );
 
       await program.methods
-        .deposit(new anchor.BN(depositAmount))
+        .deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: donor.publicKey,
           stream: conditionalStreamPda,
@@ -235,14 +219,7 @@ describe("vidbloq-program", () => {
 
       // Initialize live stream
       liveStreamName = "live_dist_test";
-      [liveStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(liveStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      liveStreamPda = await nextStreamPda(host.publicKey);
       liveStreamAta = await getAssociatedTokenAddress(
         mint,
         liveStreamPda,
@@ -250,7 +227,7 @@ describe("vidbloq-program", () => {
       );
 
       await program.methods
-        .initialize(liveStreamName, { live: {} }, null)
+        .initialize(liveStreamName, { live: {} }, null, null)
         .accounts({
           host: host.publicKey,
           stream: liveStreamPda,
@@ -285,7 +262,7 @@ describe("vidbloq-program", () => {
 
       // Then deposit to it
       await program.methods
-        .deposit(new anchor.BN(depositAmount))
+        .deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: donor.publicKey,
           stream: liveStreamPda,
@@ -469,14 +446,7 @@ describe("vidbloq-program", () => {
     it("should fail to distribute from non-active stream", async () => {
       // Create a new stream and mark it as ended
       const endedStreamName = "ended_stream_test";
-      const [endedStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(endedStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      const endedStreamPda = await nextStreamPda(host.publicKey);
       const endedStreamAta = await getAssociatedTokenAddress(
         mint,
         endedStreamPda,
@@ -484,7 +454,7 @@ describe("vidbloq-program", () => {
       );
 
       await program.methods
-        .initialize(endedStreamName, { live: {} }, null)
+        .initialize(endedStreamName, { live: {} }, null, null)
         .accounts({
           host: host.publicKey,
           stream: endedStreamPda,
@@ -534,6 +504,54 @@ describe("vidbloq-program", () => {
       }
     });
 
+    it("should not reactivate an ended stream or move its end time", async () => {
+      const endedStreamPda = await streamPdaByName(host.publicKey, "ended_stream_test");
+
+      try {
+        await program.methods
+          .updateStream(null, { active: {} })
+          .accounts({
+            host: host.publicKey,
+            stream: endedStreamPda,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("Ended streams should stay ended");
+      } catch (err) {
+        assert.include(err.toString(), "InvalidStatusTransition");
+      }
+
+      try {
+        await program.methods
+          .updateStream(new anchor.BN(Math.floor(Date.now() / 1000) + 3600), null)
+          .accounts({
+            host: host.publicKey,
+            stream: endedStreamPda,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("End time of an ended stream should be final");
+      } catch (err) {
+        assert.include(err.toString(), "StreamNotActive");
+      }
+    });
+
+    it("should reject an end time in the past", async () => {
+      try {
+        await program.methods
+          .updateStream(new anchor.BN(Math.floor(Date.now() / 1000) - 3600), null)
+          .accounts({
+            host: host.publicKey,
+            stream: streamPda,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("Past end time should be rejected");
+      } catch (err) {
+        assert.include(err.toString(), "InvalidTime");
+      }
+    });
+
     it("should fail to distribute with non-host signer", async () => {
       try {
         await program.methods
@@ -573,14 +591,7 @@ describe("vidbloq-program", () => {
     before(async () => {
       // Initialize a new stream for refund tests
       refundStreamName = "refund_test_stream";
-      [refundStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(refundStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      refundStreamPda = await nextStreamPda(host.publicKey);
       refundStreamAta = await getAssociatedTokenAddress(
         mint,
         refundStreamPda,
@@ -592,7 +603,8 @@ describe("vidbloq-program", () => {
         .initialize(
           refundStreamName,
           { prepaid: { minDuration: new anchor.BN(3600) } },
-          null
+          null,
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
         )
         .accounts({
           host: host.publicKey,
@@ -618,7 +630,7 @@ describe("vidbloq-program", () => {
 
       // Deposit funds to the stream
       await program.methods
-        .deposit(new anchor.BN(depositAmount))
+        .deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: donor.publicKey,
           stream: refundStreamPda,
@@ -813,14 +825,7 @@ describe("vidbloq-program", () => {
     it("should fail to refund from ended stream", async () => {
       // Create a new stream for this test
       const endedStreamName = "ended_refund_test";
-      const [endedStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(endedStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      const endedStreamPda = await nextStreamPda(host.publicKey);
       const endedStreamAta = await getAssociatedTokenAddress(
         mint,
         endedStreamPda,
@@ -829,7 +834,7 @@ describe("vidbloq-program", () => {
 
       // Initialize the stream
       await program.methods
-        .initialize(endedStreamName, { live: {} }, null)
+        .initialize(endedStreamName, { live: {} }, null, null)
         .accounts({
           host: host.publicKey,
           stream: endedStreamPda,
@@ -862,156 +867,4679 @@ describe("vidbloq-program", () => {
         .signers([host])
         .rpc();
 
-      // Deposit to the stream
-      await program.methods
-        .deposit(new anchor.BN(depositAmount))
+      // Deposit to the stream
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: endedStreamPda,
+          donorAccount: endedDonorAccount,
+          donorAta: donorAta,
+          streamAta: endedStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      // End the stream
+      await program.methods
+        .completeStream()
+        .accounts({
+          host: host.publicKey,
+          stream: endedStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      // Try to refund
+      try {
+        await program.methods
+          .refund(new anchor.BN(1000))
+          .accounts({
+            donor: donor.publicKey,
+            initiator: host.publicKey,
+            stream: endedStreamPda,
+            donorAccount: endedDonorAccount,
+            donorAta: donorAta,
+            streamAta: endedStreamAta,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("Should have rejected refund from ended stream");
+      } catch (err) {
+        assert.include(
+          err.toString(),
+          "StreamAlreadyEnded",
+          "Expected StreamAlreadyEnded error"
+        );
+      }
+    });
+
+    it("should fail to refund from an active stream without a refund window", async () => {
+      const noWindowStreamName = "no_window_refund";
+      const noWindowStreamPda = await nextStreamPda(host.publicKey);
+      const noWindowStreamAta = await getAssociatedTokenAddress(
+        mint,
+        noWindowStreamPda,
+        true
+      );
+
+      // Initialize the stream without a refund deadline
+      await program.methods
+        .initialize(noWindowStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: noWindowStreamPda,
+          mint: mint,
+          streamAta: noWindowStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const [noWindowDonorAccount] = await PublicKey.findProgramAddress(
+        [
+          Buffer.from("donor"),
+          noWindowStreamPda.toBuffer(),
+          donor.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: noWindowStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: noWindowStreamPda,
+          donorAccount: noWindowDonorAccount,
+          donorAta: donorAta,
+          streamAta: noWindowStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      // Live streams without a refund window only refund after cancellation
+      try {
+        await program.methods
+          .refund(new anchor.BN(1000))
+          .accounts({
+            donor: donor.publicKey,
+            initiator: donor.publicKey,
+            stream: noWindowStreamPda,
+            donorAccount: noWindowDonorAccount,
+            donorAta: donorAta,
+            streamAta: noWindowStreamAta,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([donor])
+          .rpc();
+        assert.fail("Should have rejected refund outside the refund window");
+      } catch (err) {
+        assert.include(
+          err.toString(),
+          "RefundWindowClosed",
+          "Expected RefundWindowClosed error"
+        );
+      }
+    });
+
+    it("should fail to refund when non-authorized initiator tries", async () => {
+      // Create a new stream and donor account
+      const newStreamName = "auth_refund_test";
+      const newStreamPda = await nextStreamPda(host.publicKey);
+      const newStreamAta = await getAssociatedTokenAddress(
+        mint,
+        newStreamPda,
+        true
+      );
+
+      // Initialize the stream
+      await program.methods
+        .initialize(newStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: newStreamPda,
+          mint: mint,
+          streamAta: newStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      // Start the stream
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: newStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      // Find donor account PDA
+      const [newDonorAccount] = await PublicKey.findProgramAddress(
+        [
+          Buffer.from("donor"),
+          newStreamPda.toBuffer(),
+          donor.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+
+      // Deposit to the stream
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: newStreamPda,
+          donorAccount: newDonorAccount,
+          donorAta: donorAta,
+          streamAta: newStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      // Create unauthorized user
+      const unauthorizedUser = Keypair.generate();
+      await airdrop(unauthorizedUser.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+
+      // Try to refund with unauthorized user
+      try {
+        await program.methods
+          .refund(new anchor.BN(1000))
+          .accounts({
+            donor: donor.publicKey,
+            initiator: unauthorizedUser.publicKey,
+            stream: newStreamPda,
+            donorAccount: newDonorAccount,
+            donorAta: donorAta,
+            streamAta: newStreamAta,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([unauthorizedUser])
+          .rpc();
+        assert.fail("Should have rejected refund from unauthorized initiator");
+      } catch (err) {
+        // This error comes from the account constraint check
+        assert(
+          err.toString().includes("Error"),
+          "Expected error for unauthorized initiator"
+        );
+      }
+    });
+
+    it("should batch refund donors of a cancelled stream", async () => {
+      const batchStreamName = "batch_refund_stream";
+      const batchStreamPda = await nextStreamPda(host.publicKey);
+      const batchStreamAta = await getAssociatedTokenAddress(
+        mint,
+        batchStreamPda,
+        true
+      );
+
+      await program.methods
+        .initialize(
+          batchStreamName,
+          { prepaid: { minDuration: new anchor.BN(3600) } },
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: batchStreamPda,
+          mint: mint,
+          streamAta: batchStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const [batchDonorAccount] = await PublicKey.findProgramAddress(
+        [
+          Buffer.from("donor"),
+          batchStreamPda.toBuffer(),
+          donor.publicKey.toBuffer(),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: batchStreamPda,
+          donorAccount: batchDonorAccount,
+          donorAta: donorAta,
+          streamAta: batchStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      await program.methods
+        .updateStream(null, { cancelled: {} })
+        .accounts({
+          host: host.publicKey,
+          stream: batchStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      const initialDonorBalance = Number((await connection.getTokenAccountBalance(donorAta)).value.amount);
+
+      // Anyone can trigger the batch; here the host pays for it
+      await program.methods
+        .refundBatch()
+        .accounts({
+          caller: host.publicKey,
+          stream: batchStreamPda,
+          streamAta: batchStreamAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: batchDonorAccount, isWritable: true, isSigner: false },
+          { pubkey: donorAta, isWritable: true, isSigner: false },
+        ])
+        .signers([host])
+        .rpc();
+
+      const donorAccountInfo = await program.account.donorAccount.fetch(batchDonorAccount);
+      assert.equal(donorAccountInfo.refunded, true, "Donor should be marked as refunded");
+      assert.equal(donorAccountInfo.amount.toNumber(), 0, "Donor balance should be cleared");
+
+      const finalDonorBalance = Number((await connection.getTokenAccountBalance(donorAta)).value.amount);
+      assert.equal(finalDonorBalance - initialDonorBalance, depositAmount, "Donor should be refunded in full");
+    });
+
+    it("should refund every donor of a cancelled stream at the same ratio", async () => {
+      const contribution = 1_000_000;
+      const distributed = 500_000;
+      const ratioStreamPda = await nextStreamPda(host.publicKey);
+      const ratioStreamAta = await getAssociatedTokenAddress(mint, ratioStreamPda, true);
+
+      await program.methods
+        .initialize("pro_rata_refund_stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: ratioStreamPda,
+          mint: mint,
+          streamAta: ratioStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+      await program.methods
+        .startStream()
+        .accounts({ host: host.publicKey, stream: ratioStreamPda })
+        .signers([host])
+        .rpc();
+
+      const donors: { wallet: Keypair; ata: PublicKey; record: PublicKey }[] = [];
+      for (let i = 0; i < 2; i++) {
+        const wallet = Keypair.generate();
+        await airdrop(wallet.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+        const ata = await createDonorTokenAccount(mint, wallet.publicKey);
+        await mintTokens(mint, ata, contribution);
+        const [record] = PublicKey.findProgramAddressSync(
+          [Buffer.from("donor"), ratioStreamPda.toBuffer(), wallet.publicKey.toBuffer()],
+          program.programId
+        );
+        await program.methods
+          .deposit(new anchor.BN(contribution), null)
+          .accounts({
+            donor: wallet.publicKey,
+            stream: ratioStreamPda,
+            donorAccount: record,
+            donorAta: ata,
+            streamAta: ratioStreamAta,
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([wallet])
+          .rpc();
+        donors.push({ wallet, ata, record });
+      }
+
+      const recipient = Keypair.generate();
+      await program.methods
+        .distribute(new anchor.BN(distributed))
+        .accounts({
+          host: host.publicKey,
+          recipient: recipient.publicKey,
+          mint: mint,
+          stream: ratioStreamPda,
+          streamAta: ratioStreamAta,
+          recipientAta: await getAssociatedTokenAddress(mint, recipient.publicKey),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+      await program.methods
+        .updateStream(null, { cancelled: {} })
+        .accounts({ host: host.publicKey, stream: ratioStreamPda })
+        .signers([host])
+        .rpc();
+
+      // Half of the donations left the vault, so each donor gets half back however they leave
+      const expected = contribution / 2;
+      const [first, second] = donors;
+      await program.methods
+        .refund(new anchor.BN(contribution))
+        .accounts({
+          donor: first.wallet.publicKey,
+          initiator: first.wallet.publicKey,
+          stream: ratioStreamPda,
+          donorAccount: first.record,
+          donorAta: first.ata,
+          streamAta: ratioStreamAta,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([first.wallet])
+        .rpc();
+      await program.methods
+        .refundBatch()
+        .accounts({
+          caller: host.publicKey,
+          stream: ratioStreamPda,
+          streamAta: ratioStreamAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: second.record, isWritable: true, isSigner: false },
+          { pubkey: second.ata, isWritable: true, isSigner: false },
+        ])
+        .signers([host])
+        .rpc();
+
+      for (const { ata } of donors) {
+        const balance = Number((await connection.getTokenAccountBalance(ata)).value.amount);
+        assert.equal(balance, expected, "Each donor recovers the same share");
+      }
+      const stream = await program.account.streamState.fetch(ratioStreamPda);
+      assert.equal(stream.totalDistributed.toNumber(), distributed, "Refunds leave the payout record alone");
+      assert.equal(stream.refundsWrittenOff.toNumber(), 2 * (contribution - expected));
+      assert.equal(stream.totalDeposited.toNumber(), 0);
+      assert.equal(Number((await getAccount(connection, ratioStreamAta)).amount), 0);
+    });
+  });
+  describe("matching pledges", () => {
+    const sponsor = Keypair.generate();
+    let matchStreamPda: PublicKey;
+    let matchStreamAta: PublicKey;
+    let matchDonorAccount: PublicKey;
+    let pledgePda: PublicKey;
+    let pledgeVault: PublicKey;
+
+    before(async () => {
+      matchStreamPda = await nextStreamPda(host.publicKey);
+      matchStreamAta = await getAssociatedTokenAddress(mint, matchStreamPda, true);
+      [matchDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), matchStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      [pledgePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("matching_pledge"), matchStreamPda.toBuffer()],
+        program.programId
+      );
+      [pledgeVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("pledge_vault"), matchStreamPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(
+          "matching_stream",
+          { live: {} },
+          null,
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: matchStreamPda,
+          mint: mint,
+          streamAta: matchStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await airdrop(sponsor.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      const sponsorAta = await createDonorTokenAccount(mint, sponsor.publicKey);
+      await mintTokens(mint, sponsorAta, depositAmount);
+      await program.methods
+        .createMatchingPledge(10_000, new anchor.BN(depositAmount))
+        .accounts({
+          sponsor: sponsor.publicKey,
+          stream: matchStreamPda,
+          mint: mint,
+          matchingPledge: pledgePda,
+          pledgeVault: pledgeVault,
+          sponsorAta: sponsorAta,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([sponsor])
+        .rpc();
+    });
+
+    async function refund(amount: number) {
+      await program.methods
+        .refund(new anchor.BN(amount))
+        .accounts({
+          donor: donor.publicKey,
+          initiator: donor.publicKey,
+          stream: matchStreamPda,
+          donorAccount: matchDonorAccount,
+          donorAta: donorAta,
+          streamAta: matchStreamAta,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+    }
+
+    it("should keep a matched deposit in the stream while it is active", async () => {
+      const amount = 1000;
+      await program.methods
+        .deposit(new anchor.BN(amount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: matchStreamPda,
+          donorAccount: matchDonorAccount,
+          donorAta: donorAta,
+          streamAta: matchStreamAta,
+          matchingPledge: pledgePda,
+          pledgeVault: pledgeVault,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      const donorAccount = await program.account.donorAccount.fetch(matchDonorAccount);
+      assert.equal(donorAccount.matchedDeposits.toNumber(), amount);
+      const stream = await program.account.streamState.fetch(matchStreamPda);
+      assert.equal(stream.totalDeposited.toNumber(), amount * 2, "The sponsor matched the deposit 1:1");
+
+      try {
+        await refund(amount);
+        assert.fail("Refunding a matched deposit would drain the pledge");
+      } catch (error) {
+        assert.include(error.message, "MatchedDepositNotRefundable");
+      }
+
+      // Once the stream is cancelled donors may exit in full
+      await program.methods
+        .updateStream(null, { cancelled: {} })
+        .accounts({ host: host.publicKey, stream: matchStreamPda })
+        .signers([host])
+        .rpc();
+      await refund(amount);
+      const refunded = await program.account.donorAccount.fetch(matchDonorAccount);
+      assert.equal(refunded.amount.toNumber(), 0);
+      assert.equal(refunded.matchedDeposits.toNumber(), 0);
+    });
+  });
+
+  describe("native SOL streams", () => {
+    const solStreamName = "sol_stream_test";
+    const solDeposit = anchor.web3.LAMPORTS_PER_SOL / 2;
+    let solStreamPda: PublicKey;
+    let solVault: PublicKey;
+    let solDonorAccount: PublicKey;
+
+    before(async () => {
+      solStreamPda = await nextStreamPda(host.publicKey);
+      [solVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("sol_vault"), solStreamPda.toBuffer()],
+        program.programId
+      );
+      [solDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), solStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initializeSolStream(
+          solStreamName,
+          { prepaid: { minDuration: new anchor.BN(3600) } },
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: solStreamPda,
+          solVault: solVault,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("should deposit lamports into the stream vault", async () => {
+      const vaultBefore = await connection.getBalance(solVault);
+
+      await program.methods
+        .depositSol(new anchor.BN(solDeposit), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: solStreamPda,
+          solVault: solVault,
+          donorAccount: solDonorAccount,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([donor])
+        .rpc();
+
+      const vaultAfter = await connection.getBalance(solVault);
+      assert.equal(vaultAfter - vaultBefore, solDeposit, "Vault should hold the deposit");
+
+      const streamAccount = await program.account.streamState.fetch(solStreamPda);
+      assert.equal(streamAccount.totalDeposited.toNumber(), solDeposit);
+      assert.equal(streamAccount.mint.toString(), PublicKey.default.toString());
+    });
+
+    it("should refund lamports from a cancelled SOL stream", async () => {
+      await program.methods
+        .updateStream(null, { cancelled: {} })
+        .accounts({
+          host: host.publicKey,
+          stream: solStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      const vaultBefore = await connection.getBalance(solVault);
+
+      await program.methods
+        .refundSol(new anchor.BN(solDeposit))
+        .accounts({
+          donor: donor.publicKey,
+          initiator: host.publicKey,
+          stream: solStreamPda,
+          donorAccount: solDonorAccount,
+          solVault: solVault,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      const vaultAfter = await connection.getBalance(solVault);
+      assert.equal(vaultBefore - vaultAfter, solDeposit, "Vault should pay out the refund");
+
+      const donorAccountInfo = await program.account.donorAccount.fetch(solDonorAccount);
+      assert.equal(donorAccountInfo.refunded, true);
+    });
+  });
+
+  describe("additional stream mints", () => {
+    const multiStreamName = "multi_mint_stream";
+    let multiStreamPda: PublicKey;
+    let secondMint: PublicKey;
+    let secondStreamAta: PublicKey;
+    let secondDonorAta: PublicKey;
+    let multiDonorAccount: PublicKey;
+
+    before(async () => {
+      multiStreamPda = await nextStreamPda(host.publicKey);
+      [multiDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), multiStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(
+          multiStreamName,
+          { prepaid: { minDuration: new anchor.BN(3600) } },
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: multiStreamPda,
+          mint: mint,
+          streamAta: await getAssociatedTokenAddress(mint, multiStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      secondMint = await createMint(connection, payer.payer, payer.publicKey, null, 9);
+      secondStreamAta = await getAssociatedTokenAddress(secondMint, multiStreamPda, true);
+      secondDonorAta = await createDonorTokenAccount(secondMint, donor.publicKey);
+      await mintTokens(secondMint, secondDonorAta, depositAmount * 2);
+    });
+
+    it("should register an additional mint with its own vault", async () => {
+      await program.methods
+        .addStreamMint()
+        .accounts({
+          host: host.publicKey,
+          stream: multiStreamPda,
+          mint: secondMint,
+          streamAta: secondStreamAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const streamAccount = await program.account.streamState.fetch(multiStreamPda);
+      assert.equal(streamAccount.extraMints.length, 1);
+      assert.equal(streamAccount.extraMints[0].mint.toString(), secondMint.toString());
+    });
+
+    it("should not let the host sweep a tracked extra mint", async () => {
+      try {
+        await program.methods
+          .sweepForeignTokens()
+          .accounts({
+            host: host.publicKey,
+            stream: multiStreamPda,
+            foreignMint: secondMint,
+            streamForeignAta: secondStreamAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("Donations in an extra mint are owed out by the stream");
+      } catch (error) {
+        assert.include(error.message, "CannotSweepStreamMint");
+      }
+    });
+
+    it("should track deposits and refunds per mint", async () => {
+      await program.methods
+        .depositStreamMint(new anchor.BN(depositAmount))
+        .accounts({
+          donor: donor.publicKey,
+          stream: multiStreamPda,
+          mint: secondMint,
+          donorAccount: multiDonorAccount,
+          donorAta: secondDonorAta,
+          streamAta: secondStreamAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      let streamAccount = await program.account.streamState.fetch(multiStreamPda);
+      assert.equal(streamAccount.totalDeposited.toNumber(), 0, "Primary mint total should be untouched");
+      assert.equal(streamAccount.extraMints[0].totalDeposited.toNumber(), depositAmount);
+
+      let donorAccountInfo = await program.account.donorAccount.fetch(multiDonorAccount);
+      assert.equal(donorAccountInfo.extraBalances[0].amount.toNumber(), depositAmount);
+
+      await program.methods
+        .updateStream(null, { cancelled: {} })
+        .accounts({
+          host: host.publicKey,
+          stream: multiStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .refundStreamMint(new anchor.BN(depositAmount))
+        .accounts({
+          donor: donor.publicKey,
+          initiator: donor.publicKey,
+          stream: multiStreamPda,
+          mint: secondMint,
+          donorAccount: multiDonorAccount,
+          donorAta: secondDonorAta,
+          streamAta: secondStreamAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      streamAccount = await program.account.streamState.fetch(multiStreamPda);
+      assert.equal(streamAccount.extraMints[0].totalDeposited.toNumber(), 0);
+      donorAccountInfo = await program.account.donorAccount.fetch(multiDonorAccount);
+      assert.equal(donorAccountInfo.extraBalances[0].amount.toNumber(), 0);
+
+      const donorBalance = Number((await connection.getTokenAccountBalance(secondDonorAta)).value.amount);
+      assert.equal(donorBalance, depositAmount * 2, "Donor should be refunded in the deposited mint");
+    });
+  });
+
+  describe("withdrawal queue", () => {
+    const queueStreamName = "queued_refunds";
+    let queueStreamPda: PublicKey;
+    let queueStreamAta: PublicKey;
+    let queueDonorAccount: PublicKey;
+    let featureGates: PublicKey;
+
+    before(async () => {
+      [featureGates] = PublicKey.findProgramAddressSync(
+        [Buffer.from("feature_gates")],
+        program.programId
+      );
+      queueStreamPda = await nextStreamPda(host.publicKey);
+      [queueDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), queueStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      queueStreamAta = await getAssociatedTokenAddress(mint, queueStreamPda, true);
+
+      const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+      );
+      // The test validator deploys with the provider wallet as upgrade authority
+      await program.methods
+        .initializeFeatureGates()
+        .accounts({
+          admin: payer.publicKey,
+          programData,
+        })
+        .rpc();
+
+      await program.methods
+        .initialize(
+          queueStreamName,
+          { prepaid: { minDuration: new anchor.BN(3600) } },
+          null,
+          null
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: queueStreamPda,
+          mint: mint,
+          streamAta: queueStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: queueStreamPda,
+          donorAccount: queueDonorAccount,
+          donorAta: donorAta,
+          streamAta: queueStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      await program.methods
+        .updateStream(null, { cancelled: {} })
+        .accounts({
+          host: host.publicKey,
+          stream: queueStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .setWithdrawalQueue(true, new anchor.BN(depositAmount / 2), 1)
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .setWithdrawalQueue(false, new anchor.BN(0), 0)
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+    });
+
+    it("should reject a direct refund above the queue threshold", async () => {
+      try {
+        await program.methods
+          .refund(new anchor.BN(depositAmount))
+          .accounts({
+            donor: donor.publicKey,
+            initiator: donor.publicKey,
+            stream: queueStreamPda,
+            donorAccount: queueDonorAccount,
+            donorAta: donorAta,
+            streamAta: queueStreamAta,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([donor])
+          .rpc();
+        assert.fail("Large refund should have to be queued");
+      } catch (error) {
+        assert.include(error.message, "WithdrawalMustBeQueued");
+      }
+    });
+
+    it("should queue a large refund and release it through the crank", async () => {
+      const gatesBefore = await program.account.featureGates.fetch(featureGates);
+      const [ticket] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("withdrawal_ticket"),
+          gatesBefore.nextTicketId.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .queueRefund(new anchor.BN(depositAmount))
+        .accounts({
+          donor: donor.publicKey,
+          initiator: donor.publicKey,
+          stream: queueStreamPda,
+          donorAccount: queueDonorAccount,
+          donorAta: donorAta,
+          streamAta: queueStreamAta,
+          ticket,
+        })
+        .signers([donor])
+        .rpc();
+
+      const donorAccountInfo = await program.account.donorAccount.fetch(queueDonorAccount);
+      assert.equal(donorAccountInfo.refunded, true, "Refund accounting settles when queued");
+
+      const initialDonorBalance = Number((await connection.getTokenAccountBalance(donorAta)).value.amount);
+
+      await program.methods
+        .processWithdrawalTicket()
+        .accounts({
+          cranker: host.publicKey,
+          ticket,
+          payer: donor.publicKey,
+          stream: queueStreamPda,
+          bettingMarket: null,
+          vault: queueStreamAta,
+          destination: donorAta,
+          mint: mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const finalDonorBalance = Number((await connection.getTokenAccountBalance(donorAta)).value.amount);
+      assert.equal(finalDonorBalance - initialDonorBalance, depositAmount, "Crank should release the refund");
+
+      const gatesAfter = await program.account.featureGates.fetch(featureGates);
+      assert.equal(gatesAfter.pendingTickets.toNumber(), 0);
+    });
+  });
+
+  describe("platform fee", () => {
+    const feeStreamName = "platform_fee_test";
+    const feeBps = 250;
+    let feeStreamPda: PublicKey;
+    let feeStreamAta: PublicKey;
+    let treasury: Keypair;
+    let treasuryAta: PublicKey;
+    let hostProfile: PublicKey;
+
+    before(async () => {
+      treasury = Keypair.generate();
+      treasuryAta = await createDonorTokenAccount(mint, treasury.publicKey);
+
+      feeStreamPda = await nextStreamPda(host.publicKey);
+      [hostProfile] = PublicKey.findProgramAddressSync(
+        [Buffer.from("host_profile"), host.publicKey.toBuffer()],
+        program.programId
+      );
+      feeStreamAta = await getAssociatedTokenAddress(mint, feeStreamPda, true);
+      const [feeDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), feeStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+      );
+      await program.methods
+        .initializePlatformConfig(treasury.publicKey, feeBps)
+        .accounts({
+          admin: payer.publicKey,
+          programData,
+        })
+        .rpc();
+
+      await program.methods
+        .initialize(feeStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: feeStreamPda,
+          mint: mint,
+          streamAta: feeStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: feeStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: feeStreamPda,
+          donorAccount: feeDonorAccount,
+          donorAta: donorAta,
+          streamAta: feeStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+    });
+
+    after(async () => {
+      await program.methods
+        .updatePlatformConfig(null, null, 0)
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+    });
+
+    it("should skim the platform fee to the treasury on distribute", async () => {
+      const recipient = Keypair.generate();
+      const recipientAta = await getAssociatedTokenAddress(mint, recipient.publicKey);
+      const amount = 1_000_000;
+      const fee = (amount * feeBps) / 10_000;
+
+      await program.methods
+        .distribute(new anchor.BN(amount))
+        .accounts({
+          host: host.publicKey,
+          recipient: recipient.publicKey,
+          mint: mint,
+          stream: feeStreamPda,
+          streamAta: feeStreamAta,
+          recipientAta: recipientAta,
+          treasuryAta: treasuryAta,
+          hostProfile: hostProfile,
+          referralRewards: null,
+          referralVault: null,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const treasuryBalance = Number((await connection.getTokenAccountBalance(treasuryAta)).value.amount);
+      assert.equal(treasuryBalance, fee, "Treasury should receive the fee");
+
+      const recipientBalance = Number((await connection.getTokenAccountBalance(recipientAta)).value.amount);
+      assert.equal(recipientBalance, amount - fee, "Recipient should receive the net amount");
+
+      const streamAccount = await program.account.streamState.fetch(feeStreamPda);
+      assert.equal(streamAccount.totalDistributed.toNumber(), amount);
+    });
+
+    it("should reject platform config updates from a non-admin", async () => {
+      try {
+        await program.methods
+          .updatePlatformConfig(null, null, 500)
+          .accounts({ admin: host.publicKey })
+          .signers([host])
+          .rpc();
+        assert.fail("Only the admin may update the platform config");
+      } catch (error) {
+        assert.include(error.message, "Unauthorized");
+      }
+    });
+  });
+
+  describe("host referral fee share", () => {
+    const referralFeeBps = 1_000;
+    let referredHost: Keypair;
+    let referrer: Keypair;
+    let outsider: Keypair;
+    let referredStreamPda: PublicKey;
+    let referredStreamAta: PublicKey;
+    let hostProfile: PublicKey;
+    let treasuryAta: PublicKey;
+
+    function referralRewardsPda(owner: PublicKey): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("referral_rewards"), owner.toBuffer(), mint.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    function referralVaultPda(owner: PublicKey): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("referral_vault"), referralRewardsPda(owner).toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    before(async () => {
+      referredHost = Keypair.generate();
+      referrer = Keypair.generate();
+      outsider = Keypair.generate();
+      await airdrop(referredHost.publicKey, 10 * anchor.web3.LAMPORTS_PER_SOL);
+      await airdrop(referrer.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      await airdrop(outsider.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+
+      const [configPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("platform_config")],
+        program.programId
+      );
+      const config = await program.account.platformConfig.fetch(configPda);
+      treasuryAta = await getAssociatedTokenAddress(mint, config.treasury);
+      await program.methods
+        .updatePlatformConfig(null, null, referralFeeBps)
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+
+      referredStreamPda = await nextStreamPda(referredHost.publicKey);
+      referredStreamAta = await getAssociatedTokenAddress(mint, referredStreamPda, true);
+      [hostProfile] = PublicKey.findProgramAddressSync(
+        [Buffer.from("host_profile"), referredHost.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("referred_stream", { live: {} }, null, null)
+        .accounts({
+          host: referredHost.publicKey,
+          stream: referredStreamPda,
+          referrer: referrer.publicKey,
+          mint: mint,
+          streamAta: referredStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([referredHost])
+        .rpc();
+      await program.methods
+        .startStream()
+        .accounts({ host: referredHost.publicKey, stream: referredStreamPda })
+        .signers([referredHost])
+        .rpc();
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: referredStreamPda,
+          donorAta: donorAta,
+          streamAta: referredStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      // Anyone may open a referrer's rewards; the provider wallet pays for both here.
+      for (const owner of [referrer, outsider]) {
+        await program.methods
+          .openReferralRewards()
+          .accounts({
+            payer: payer.publicKey,
+            referrer: owner.publicKey,
+            mint: mint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .rpc();
+      }
+    });
+
+    after(async () => {
+      await program.methods
+        .updatePlatformConfig(null, null, 0)
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+    });
+
+    function distributeTo(recipient: PublicKey, rewardsOwner: PublicKey) {
+      return program.methods
+        .distribute(new anchor.BN(1_000_000))
+        .accounts({
+          host: referredHost.publicKey,
+          recipient: recipient,
+          mint: mint,
+          stream: referredStreamPda,
+          streamAta: referredStreamAta,
+          recipientAta: getAssociatedTokenAddressSync(mint, recipient),
+          treasuryAta: treasuryAta,
+          hostProfile: hostProfile,
+          referralRewards: referralRewardsPda(rewardsOwner),
+          referralVault: referralVaultPda(rewardsOwner),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([referredHost])
+        .rpc();
+    }
+
+    it("should require the referrer's rewards accounts while the referral is active", async () => {
+      const recipient = Keypair.generate();
+      try {
+        await program.methods
+          .distribute(new anchor.BN(1_000_000))
+          .accounts({
+            host: referredHost.publicKey,
+            recipient: recipient.publicKey,
+            mint: mint,
+            stream: referredStreamPda,
+            streamAta: referredStreamAta,
+            recipientAta: getAssociatedTokenAddressSync(mint, recipient.publicKey),
+            treasuryAta: treasuryAta,
+            hostProfile: hostProfile,
+            referralRewards: null,
+            referralVault: null,
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([referredHost])
+          .rpc();
+        assert.fail("The referrer's share must not fall through to the treasury");
+      } catch (error) {
+        assert.include(error.message, "MissingAccounts");
+      }
+
+      const rewards = await program.account.referralRewards.fetch(referralRewardsPda(referrer.publicKey));
+      assert.ok(rewards.referrer.equals(referrer.publicKey));
+      assert.equal(rewards.totalAccrued.toNumber(), 0);
+    });
+
+    it("should reject a rewards account that is not the recorded referrer's", async () => {
+      try {
+        await distributeTo(outsider.publicKey, outsider.publicKey);
+        assert.fail("The referrer's share must not reach another rewards vault");
+      } catch (error) {
+        assert.include(error.message, "Unauthorized");
+      }
+
+      const outsiderVault = await getAccount(connection, referralVaultPda(outsider.publicKey));
+      assert.equal(Number(outsiderVault.amount), 0);
+    });
+
+    it("should credit the recorded referrer's share of the fee", async () => {
+      const recipient = Keypair.generate();
+      const fee = (1_000_000 * referralFeeBps) / 10_000;
+      const cut = fee / 10;
+      const treasuryBefore = Number((await getAccount(connection, treasuryAta)).amount);
+
+      await distributeTo(recipient.publicKey, referrer.publicKey);
+
+      const vault = await getAccount(connection, referralVaultPda(referrer.publicKey));
+      assert.equal(Number(vault.amount), cut);
+      const rewards = await program.account.referralRewards.fetch(referralRewardsPda(referrer.publicKey));
+      assert.equal(rewards.totalAccrued.toNumber(), cut);
+      const treasuryAfter = Number((await getAccount(connection, treasuryAta)).amount);
+      assert.equal(treasuryAfter - treasuryBefore, fee - cut);
+    });
+  });
+
+  describe("escrowed payouts", () => {
+    const escrowStreamName = "escrow_payouts";
+    let escrowStreamPda: PublicKey;
+    let escrowStreamAta: PublicKey;
+    let recipient: Keypair;
+
+    before(async () => {
+      recipient = Keypair.generate();
+      await airdrop(recipient.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+
+      escrowStreamPda = await nextStreamPda(host.publicKey);
+      escrowStreamAta = await getAssociatedTokenAddress(mint, escrowStreamPda, true);
+      const [escrowDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), escrowStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(escrowStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: escrowStreamPda,
+          mint: mint,
+          streamAta: escrowStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: escrowStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: escrowStreamPda,
+          donorAccount: escrowDonorAccount,
+          donorAta: donorAta,
+          streamAta: escrowStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+    });
+
+    it("should escrow a payout until the recipient accepts it", async () => {
+      const amount = 2000;
+      const streamBefore = await program.account.streamState.fetch(escrowStreamPda);
+      const [pendingPayout] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("pending_payout"),
+          escrowStreamPda.toBuffer(),
+          streamBefore.payoutNonce.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .proposeDistribution(new anchor.BN(amount), new anchor.BN(3600))
+        .accounts({
+          host: host.publicKey,
+          recipient: recipient.publicKey,
+          stream: escrowStreamPda,
+          streamAta: escrowStreamAta,
+          pendingPayout,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const streamAfter = await program.account.streamState.fetch(escrowStreamPda);
+      assert.equal(streamAfter.totalDistributed.toNumber(), amount, "Escrowed funds count as distributed");
+
+      // Nobody but the recipient can accept
+      try {
+        await program.methods
+          .acceptDistribution()
+          .accounts({
+            recipient: donor.publicKey,
+            stream: escrowStreamPda,
+            pendingPayout,
+            host: host.publicKey,
+            mint: mint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([donor])
+          .rpc();
+        assert.fail("Only the recipient may accept");
+      } catch (error) {
+        assert.include(error.message, "Unauthorized");
+      }
+
+      await program.methods
+        .acceptDistribution()
+        .accounts({
+          recipient: recipient.publicKey,
+          stream: escrowStreamPda,
+          pendingPayout,
+          host: host.publicKey,
+          mint: mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([recipient])
+        .rpc();
+
+      const recipientAta = await getAssociatedTokenAddress(mint, recipient.publicKey);
+      const recipientBalance = Number((await connection.getTokenAccountBalance(recipientAta)).value.amount);
+      assert.equal(recipientBalance, amount, "Recipient should receive the escrowed payout");
+      assert.isNull(await connection.getAccountInfo(pendingPayout), "Pending payout should be closed");
+    });
+
+    it("should not cancel a payout before it expires", async () => {
+      const streamBefore = await program.account.streamState.fetch(escrowStreamPda);
+      const [pendingPayout] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("pending_payout"),
+          escrowStreamPda.toBuffer(),
+          streamBefore.payoutNonce.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .proposeDistribution(new anchor.BN(1000), new anchor.BN(3600))
+        .accounts({
+          host: host.publicKey,
+          recipient: recipient.publicKey,
+          stream: escrowStreamPda,
+          streamAta: escrowStreamAta,
+          pendingPayout,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      try {
+        await program.methods
+          .cancelDistribution()
+          .accounts({
+            host: host.publicKey,
+            stream: escrowStreamPda,
+            pendingPayout,
+            streamAta: escrowStreamAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("Cancel should wait for the timeout");
+      } catch (error) {
+        assert.include(error.message, "PayoutNotExpired");
+      }
+    });
+  });
+
+  describe("payout allowlist", () => {
+    const allowlistStreamName = "payout_allowlist";
+    let allowlistStreamPda: PublicKey;
+    let allowlistStreamAta: PublicKey;
+    let approved: Keypair;
+    let stranger: Keypair;
+
+    before(async () => {
+      approved = Keypair.generate();
+      stranger = Keypair.generate();
+
+      allowlistStreamPda = await nextStreamPda(host.publicKey);
+      allowlistStreamAta = await getAssociatedTokenAddress(mint, allowlistStreamPda, true);
+      const [allowlistDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), allowlistStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(allowlistStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: allowlistStreamPda,
+          mint: mint,
+          streamAta: allowlistStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: allowlistStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: allowlistStreamPda,
+          donorAccount: allowlistDonorAccount,
+          donorAta: donorAta,
+          streamAta: allowlistStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+    });
+
+    function payoutRecipientPda(recipient: PublicKey): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("payout_recipient"), allowlistStreamPda.toBuffer(), recipient.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    it("should only pay allowlisted recipients in strict mode", async () => {
+      const amount = 1000;
+
+      await program.methods
+        .addPayoutRecipient(approved.publicKey)
+        .accounts({
+          host: host.publicKey,
+          stream: allowlistStreamPda,
+          payoutRecipient: payoutRecipientPda(approved.publicKey),
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .setPayoutPolicy(true)
+        .accounts({
+          host: host.publicKey,
+          stream: allowlistStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      try {
+        await program.methods
+          .distribute(new anchor.BN(amount))
+          .accounts({
+            host: host.publicKey,
+            recipient: stranger.publicKey,
+            mint: mint,
+            stream: allowlistStreamPda,
+            streamAta: allowlistStreamAta,
+            recipientAta: await getAssociatedTokenAddress(mint, stranger.publicKey),
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("Recipients off the allowlist should be rejected");
+      } catch (error) {
+        assert.include(error.message, "RecipientNotAllowed");
+      }
+
+      const approvedAta = await getAssociatedTokenAddress(mint, approved.publicKey);
+      await program.methods
+        .distribute(new anchor.BN(amount))
+        .accounts({
+          host: host.publicKey,
+          recipient: approved.publicKey,
+          mint: mint,
+          stream: allowlistStreamPda,
+          streamAta: allowlistStreamAta,
+          recipientAta: approvedAta,
+          payoutRecipient: payoutRecipientPda(approved.publicKey),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const approvedBalance = Number((await connection.getTokenAccountBalance(approvedAta)).value.amount);
+      assert.equal(approvedBalance, amount, "Allowlisted recipient should be paid");
+    });
+
+    it("should let the host remove a recipient from the allowlist", async () => {
+      const entry = payoutRecipientPda(approved.publicKey);
+
+      await program.methods
+        .removePayoutRecipient()
+        .accounts({
+          host: host.publicKey,
+          stream: allowlistStreamPda,
+          payoutRecipient: entry,
+        })
+        .signers([host])
+        .rpc();
+
+      assert.isNull(await connection.getAccountInfo(entry), "Allowlist entry should be closed");
+
+      await program.methods
+        .setPayoutPolicy(false)
+        .accounts({
+          host: host.publicKey,
+          stream: allowlistStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      const stream = await program.account.streamState.fetch(allowlistStreamPda);
+      assert.isFalse(stream.payoutAllowlistStrict, "Strict mode should be off");
+    });
+  });
+
+  describe("dust refunds", () => {
+    const dustStreamName = "dust_refunds";
+    let dustStreamPda: PublicKey;
+    let dustStreamAta: PublicKey;
+    let dustDonorAccount: PublicKey;
+
+    before(async () => {
+      dustStreamPda = await nextStreamPda(host.publicKey);
+      dustStreamAta = await getAssociatedTokenAddress(mint, dustStreamPda, true);
+      [dustDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), dustStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(dustStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: dustStreamPda,
+          mint: mint,
+          streamAta: dustStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: dustStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: dustStreamPda,
+          donorAccount: dustDonorAccount,
+          donorAta: donorAta,
+          streamAta: dustStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      await program.methods
+        .setDustRefundThreshold(new anchor.BN(depositAmount - 1))
+        .accounts({
+          host: host.publicKey,
+          stream: dustStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .updateStream(null, { cancelled: {} })
+        .accounts({
+          host: host.publicKey,
+          stream: dustStreamPda,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    async function pushDustRefund() {
+      await program.methods
+        .pushDustRefund()
+        .accounts({
+          caller: host.publicKey,
+          donor: donor.publicKey,
+          stream: dustStreamPda,
+          mint: mint,
+          donorAccount: dustDonorAccount,
+          donorAta: donorAta,
+          streamAta: dustStreamAta,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+    }
+
+    it("should leave balances above the threshold to the standard refund", async () => {
+      try {
+        await pushDustRefund();
+        assert.fail("Balances above the dust threshold should not be pushed");
+      } catch (error) {
+        assert.include(error.message, "AboveDustThreshold");
+      }
+    });
+
+    it("should push dust back to the donor without their signature", async () => {
+      await program.methods
+        .setDustRefundThreshold(new anchor.BN(depositAmount))
+        .accounts({
+          host: host.publicKey,
+          stream: dustStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      const initialDonorBalance = Number((await connection.getTokenAccountBalance(donorAta)).value.amount);
+      await pushDustRefund();
+
+      const donorAccountInfo = await program.account.donorAccount.fetch(dustDonorAccount);
+      assert.isTrue(donorAccountInfo.refunded, "Donor should be marked as refunded");
+      const finalDonorBalance = Number((await connection.getTokenAccountBalance(donorAta)).value.amount);
+      assert.equal(finalDonorBalance - initialDonorBalance, depositAmount, "Donor should get their balance back");
+    });
+  });
+
+  describe("distribution limits", () => {
+    const limitStreamName = "distribution_limits";
+    let limitStreamPda: PublicKey;
+    let limitStreamAta: PublicKey;
+    let policyPda: PublicKey;
+    let recipient: Keypair;
+    let recipientAta: PublicKey;
+
+    before(async () => {
+      recipient = Keypair.generate();
+      limitStreamPda = await nextStreamPda(host.publicKey);
+      limitStreamAta = await getAssociatedTokenAddress(mint, limitStreamPda, true);
+      recipientAta = await getAssociatedTokenAddress(mint, recipient.publicKey);
+      [policyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("distribution_policy"), limitStreamPda.toBuffer()],
+        program.programId
+      );
+      const [limitDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), limitStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(limitStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: limitStreamPda,
+          mint: mint,
+          streamAta: limitStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: limitStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: limitStreamPda,
+          donorAccount: limitDonorAccount,
+          donorAta: donorAta,
+          streamAta: limitStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      await program.methods
+        .setDistributionPolicy(new anchor.BN(1000), new anchor.BN(1500))
+        .accounts({
+          host: host.publicKey,
+          stream: limitStreamPda,
+          distributionPolicy: policyPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    async function distribute(amount: number) {
+      await program.methods
+        .distribute(new anchor.BN(amount))
+        .accounts({
+          host: host.publicKey,
+          recipient: recipient.publicKey,
+          mint: mint,
+          stream: limitStreamPda,
+          streamAta: limitStreamAta,
+          recipientAta: recipientAta,
+          distributionPolicy: policyPda,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+    }
+
+    it("should cap single payouts and payouts per window", async () => {
+      try {
+        await distribute(1001);
+        assert.fail("Payout above the per-distribution cap should fail");
+      } catch (error) {
+        assert.include(error.message, "DistributionCapExceeded");
+      }
+
+      await distribute(1000);
+
+      try {
+        await distribute(600);
+        assert.fail("Payout above the window cap should fail");
+      } catch (error) {
+        assert.include(error.message, "DistributionWindowCapExceeded");
+      }
+    });
+
+    it("should delay looser limits by a full window", async () => {
+      await program.methods
+        .setDistributionPolicy(new anchor.BN(0), new anchor.BN(0))
+        .accounts({
+          host: host.publicKey,
+          stream: limitStreamPda,
+          distributionPolicy: policyPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      const policy = await program.account.distributionPolicy.fetch(policyPda);
+      assert.equal(policy.maxPerWindow.toNumber(), 1500, "Current cap stays in force");
+      assert.isAbove(policy.pendingEffectiveAt.toNumber(), 0, "Looser caps should be pending");
+
+      try {
+        await distribute(600);
+        assert.fail("Pending limits should not apply yet");
+      } catch (error) {
+        assert.include(error.message, "DistributionWindowCapExceeded");
+      }
+    });
+
+    it("should count payouts in an extra mint against the same limits", async () => {
+      const extraMint = await createMint(connection, payer.payer, payer.publicKey, null, 9);
+      const extraStreamAta = await getAssociatedTokenAddress(extraMint, limitStreamPda, true);
+      const extraDonorAta = await createDonorTokenAccount(extraMint, donor.publicKey);
+      await mintTokens(extraMint, extraDonorAta, depositAmount);
+
+      await program.methods
+        .addStreamMint()
+        .accounts({
+          host: host.publicKey,
+          stream: limitStreamPda,
+          mint: extraMint,
+          streamAta: extraStreamAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .depositStreamMint(new anchor.BN(depositAmount))
+        .accounts({
+          donor: donor.publicKey,
+          stream: limitStreamPda,
+          mint: extraMint,
+          donorAccount: PublicKey.findProgramAddressSync(
+            [Buffer.from("donor"), limitStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+            program.programId
+          )[0],
+          donorAta: extraDonorAta,
+          streamAta: extraStreamAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      try {
+        await program.methods
+          .distributeStreamMint(new anchor.BN(600))
+          .accounts({
+            host: host.publicKey,
+            recipient: recipient.publicKey,
+            mint: extraMint,
+            stream: limitStreamPda,
+            streamAta: extraStreamAta,
+            recipientAta: await getAssociatedTokenAddress(extraMint, recipient.publicKey),
+            distributionPolicy: policyPda,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("An extra mint should not bypass the window cap");
+      } catch (error) {
+        assert.include(error.message, "DistributionWindowCapExceeded");
+      }
+    });
+  });
+
+  describe("large distribution approvals", () => {
+    const approvalStreamName = "distribution_approvals";
+    let approvalStreamPda: PublicKey;
+    let approvalStreamAta: PublicKey;
+    let policyPda: PublicKey;
+    let approvers: Keypair[];
+    let recipient: Keypair;
+    let recipientAta: PublicKey;
+
+    before(async () => {
+      approvers = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+      recipient = Keypair.generate();
+      approvalStreamPda = await nextStreamPda(host.publicKey);
+      approvalStreamAta = await getAssociatedTokenAddress(mint, approvalStreamPda, true);
+      recipientAta = await getAssociatedTokenAddress(mint, recipient.publicKey);
+      [policyPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("distribution_policy"), approvalStreamPda.toBuffer()],
+        program.programId
+      );
+      const [approvalDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), approvalStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(approvalStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: approvalStreamPda,
+          mint: mint,
+          streamAta: approvalStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: approvalStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: approvalStreamPda,
+          donorAccount: approvalDonorAccount,
+          donorAta: donorAta,
+          streamAta: approvalStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      await program.methods
+        .setDistributionApprovers(approvers.map((a) => a.publicKey), 2, new anchor.BN(1000))
+        .accounts({
+          host: host.publicKey,
+          stream: approvalStreamPda,
+          distributionPolicy: policyPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("should route payouts above the threshold through 2-of-3 approval", async () => {
+      const amount = 1500;
+
+      try {
+        await program.methods
+          .distribute(new anchor.BN(amount))
+          .accounts({
+            host: host.publicKey,
+            recipient: recipient.publicKey,
+            mint: mint,
+            stream: approvalStreamPda,
+            streamAta: approvalStreamAta,
+            recipientAta: recipientAta,
+            distributionPolicy: policyPda,
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("Large payouts should need approval");
+      } catch (error) {
+        assert.include(error.message, "ApprovalRequired");
+      }
+
+      const [proposal] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("distribution_proposal"),
+          approvalStreamPda.toBuffer(),
+          new anchor.BN(0).toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .proposeLargeDistribution(new anchor.BN(amount))
+        .accounts({
+          host: host.publicKey,
+          recipient: recipient.publicKey,
+          stream: approvalStreamPda,
+          distributionPolicy: policyPda,
+          proposal,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      const execute = () =>
+        program.methods
+          .executeDistribution()
+          .accounts({
+            host: host.publicKey,
+            recipient: recipient.publicKey,
+            stream: approvalStreamPda,
+            distributionPolicy: policyPda,
+            proposal,
+            mint: mint,
+            streamAta: approvalStreamAta,
+            recipientAta: recipientAta,
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+
+      await program.methods
+        .approveDistribution()
+        .accounts({ approver: approvers[0].publicKey, proposal })
+        .signers([approvers[0]])
+        .rpc();
+
+      try {
+        await execute();
+        assert.fail("One approval should not be enough");
+      } catch (error) {
+        assert.include(error.message, "InsufficientApprovals");
+      }
+
+      await program.methods
+        .approveDistribution()
+        .accounts({ approver: approvers[2].publicKey, proposal })
+        .signers([approvers[2]])
+        .rpc();
+
+      await execute();
+
+      const recipientBalance = Number((await connection.getTokenAccountBalance(recipientAta)).value.amount);
+      assert.equal(recipientBalance, amount, "Approved payout should reach the recipient");
+      assert.isNull(await connection.getAccountInfo(proposal), "Executed proposal should be closed");
+    });
+  });
+
+  describe("revenue splits", () => {
+    const splitStreamName = "revenue_split";
+    let splitStreamPda: PublicKey;
+    let splitStreamAta: PublicKey;
+    let splitConfigPda: PublicKey;
+    let collaborators: Keypair[];
+    let collaboratorAtas: PublicKey[];
+
+    before(async () => {
+      collaborators = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+      collaboratorAtas = [];
+      for (const collaborator of collaborators) {
+        collaboratorAtas.push(await createDonorTokenAccount(mint, collaborator.publicKey));
+      }
+      splitStreamPda = await nextStreamPda(host.publicKey);
+      splitStreamAta = await getAssociatedTokenAddress(mint, splitStreamPda, true);
+      [splitConfigPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("split_config"), splitStreamPda.toBuffer()],
+        program.programId
+      );
+      const [splitDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), splitStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(splitStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: splitStreamPda,
+          mint: mint,
+          streamAta: splitStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: splitStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: splitStreamPda,
+          donorAccount: splitDonorAccount,
+          donorAta: donorAta,
+          streamAta: splitStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+    });
+
+    const setSplit = (bps: number[]) =>
+      program.methods
+        .setSplitConfig(collaborators.map((c, i) => ({ recipient: c.publicKey, bps: bps[i] })))
+        .accounts({
+          host: host.publicKey,
+          stream: splitStreamPda,
+          splitConfig: splitConfigPda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+    it("should reject shares that do not add up to 100%", async () => {
+      try {
+        await setSplit([7000, 2000, 500]);
+        assert.fail("Split should need exactly 10000 bps");
+      } catch (error) {
+        assert.include(error.message, "InvalidSplit");
+      }
+    });
+
+    it("should pay every recipient their share in one call", async () => {
+      await setSplit([7000, 2000, 1000]);
+
+      const amount = 1001;
+      await program.methods
+        .distributeSplit(new anchor.BN(amount))
+        .accounts({
+          host: host.publicKey,
+          stream: splitStreamPda,
+          splitConfig: splitConfigPda,
+          mint: mint,
+          streamAta: splitStreamAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          [
+            ...collaboratorAtas,
+            ...collaborators.map(
+              (c) =>
+                PublicKey.findProgramAddressSync(
+                  [Buffer.from("clawback_agreement"), splitStreamPda.toBuffer(), c.publicKey.toBuffer()],
+                  program.programId
+                )[0]
+            ),
+          ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+        )
+        .signers([host])
+        .rpc();
+
+      const balances = await Promise.all(
+        collaboratorAtas.map(async (ata) => Number((await connection.getTokenAccountBalance(ata)).value.amount))
+      );
+      // 70% takes the rounding dust
+      assert.deepEqual(balances, [701, 200, 100]);
+
+      const stream = await program.account.streamState.fetch(splitStreamPda);
+      assert.equal(stream.totalDistributed.toNumber(), amount);
+    });
+  });
+
+  describe("recipient allowances", () => {
+    const allowanceStreamName = "pull_payments";
+    let allowanceStreamPda: PublicKey;
+    let allowanceStreamAta: PublicKey;
+    let allowancePda: PublicKey;
+    let payee: Keypair;
+    let payeeAta: PublicKey;
+
+    before(async () => {
+      payee = Keypair.generate();
+      await airdrop(payee.publicKey, 1e9);
+      allowanceStreamPda = await nextStreamPda(host.publicKey);
+      allowanceStreamAta = await getAssociatedTokenAddress(mint, allowanceStreamPda, true);
+      payeeAta = await getAssociatedTokenAddress(mint, payee.publicKey);
+      [allowancePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("recipient_allowance"), allowanceStreamPda.toBuffer(), payee.publicKey.toBuffer()],
+        program.programId
+      );
+      const [allowanceDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), allowanceStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(allowanceStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: allowanceStreamPda,
+          mint: mint,
+          streamAta: allowanceStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: allowanceStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: allowanceStreamPda,
+          donorAccount: allowanceDonorAccount,
+          donorAta: donorAta,
+          streamAta: allowanceStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      await program.methods
+        .setRecipientAllowance(payee.publicKey, new anchor.BN(3000))
+        .accounts({
+          host: host.publicKey,
+          stream: allowanceStreamPda,
+          allowance: allowancePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    const withdraw = (amount: number) =>
+      program.methods
+        .withdrawAllowance(new anchor.BN(amount))
+        .accounts({
+          recipient: payee.publicKey,
+          stream: allowanceStreamPda,
+          allowance: allowancePda,
+          mint: mint,
+          streamAta: allowanceStreamAta,
+          recipientAta: payeeAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([payee])
+        .rpc();
+
+    it("should let the recipient pull up to their allowance without the host", async () => {
+      await withdraw(2000);
+
+      const balance = Number((await connection.getTokenAccountBalance(payeeAta)).value.amount);
+      assert.equal(balance, 2000);
+      const allowance = await program.account.recipientAllowance.fetch(allowancePda);
+      assert.equal(allowance.remaining.toNumber(), 1000);
+      assert.equal(allowance.withdrawn.toNumber(), 2000);
+
+      try {
+        await withdraw(1001);
+        assert.fail("Withdrawal above the allowance should fail");
+      } catch (error) {
+        assert.include(error.message, "AllowanceExceeded");
+      }
+    });
+  });
+
+  describe("initialize and deposit", () => {
+    it("should create a stream and take its first deposit in one transaction", async () => {
+      const comboStreamName = "one_shot_onboard";
+      const comboStreamPda = await nextStreamPda(host.publicKey);
+      const comboStreamAta = await getAssociatedTokenAddress(mint, comboStreamPda, true);
+      const [comboDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), comboStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      const [firstReceipt] = PublicKey.findProgramAddressSync(
+        [Buffer.from("receipt"), comboStreamPda.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+
+      await program.methods
+        .initializeAndDeposit(comboStreamName, { live: {} }, null, null, new anchor.BN(depositAmount))
+        .accounts({
+          host: host.publicKey,
+          donor: donor.publicKey,
+          stream: comboStreamPda,
+          mint: mint,
+          streamAta: comboStreamAta,
+          donorAccount: comboDonorAccount,
+          receipt: firstReceipt,
+          donorAta: donorAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host, donor])
+        .rpc();
+
+      const stream = await program.account.streamState.fetch(comboStreamPda);
+      assert.equal(stream.totalDeposited.toNumber(), depositAmount);
+      assert.equal(stream.receiptCount.toNumber(), 1);
+
+      const donorRecord = await program.account.donorAccount.fetch(comboDonorAccount);
+      assert.equal(donorRecord.amount.toNumber(), depositAmount);
+
+      const vaultBalance = Number((await connection.getTokenAccountBalance(comboStreamAta)).value.amount);
+      assert.equal(vaultBalance, depositAmount);
+    });
+  });
+
+  describe("maximum stream lifetime", () => {
+    // Runs after "platform fee", which initializes the platform config
+    const day = 24 * 60 * 60;
+
+    after(async () => {
+      await program.methods
+        .setMaxStreamLifetime(new anchor.BN(0))
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+    });
+
+    it("should reject a lifetime shorter than the platform minimum", async () => {
+      try {
+        await program.methods
+          .setMaxStreamLifetime(new anchor.BN(day))
+          .accounts({ admin: payer.publicKey })
+          .rpc();
+        assert.fail("Lifetimes under 30 days should be rejected");
+      } catch (error) {
+        assert.include(error.message, "InvalidDuration");
+      }
+    });
+
+    it("should reject lifetime updates from a non-admin", async () => {
+      try {
+        await program.methods
+          .setMaxStreamLifetime(new anchor.BN(180 * day))
+          .accounts({ admin: host.publicKey })
+          .signers([host])
+          .rpc();
+        assert.fail("Only the admin may set the maximum lifetime");
+      } catch (error) {
+        assert.include(error.message, "Unauthorized");
+      }
+    });
+
+    it("should refuse to archive a stream before its lifetime ends", async () => {
+      await program.methods
+        .setMaxStreamLifetime(new anchor.BN(180 * day))
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+
+      const config = await program.account.platformConfig.fetch(
+        PublicKey.findProgramAddressSync([Buffer.from("platform_config")], program.programId)[0]
+      );
+      assert.equal(config.maxStreamLifetime.toNumber(), 180 * day);
+
+      try {
+        await program.methods
+          .archiveStream()
+          .accounts({
+            caller: donor.publicKey,
+            stream: streamPda,
+          })
+          .signers([donor])
+          .rpc();
+        assert.fail("A young stream should not be archivable");
+      } catch (error) {
+        assert.include(error.message, "StreamLifetimeNotReached");
+      }
+
+      const streamAccount = await program.account.streamState.fetch(streamPda);
+      assert.notDeepEqual(streamAccount.status, { cancelled: {} });
+    });
+  });
+
+  describe("validator slashing", () => {
+    // Runs after "platform fee", which initializes the platform config
+    const configPda = () =>
+      PublicKey.findProgramAddressSync([Buffer.from("platform_config")], program.programId)[0];
+
+    it("should default to slashing a tenth of a validator's stake", async () => {
+      const config = await program.account.platformConfig.fetch(configPda());
+      assert.equal(config.validatorSlashBps, 1_000);
+    });
+
+    it("should reject a slash above the whole stake", async () => {
+      try {
+        await program.methods
+          .setValidatorSlashBps(10_001)
+          .accounts({ admin: payer.publicKey })
+          .rpc();
+        assert.fail("Slashing more than the stake should be rejected");
+      } catch (error) {
+        assert.include(error.message, "InvalidFeePercentage");
+      }
+    });
+
+    it("should let only the admin change the slash", async () => {
+      try {
+        await program.methods
+          .setValidatorSlashBps(2_500)
+          .accounts({ admin: host.publicKey })
+          .signers([host])
+          .rpc();
+        assert.fail("Only the admin may set the slash");
+      } catch (error) {
+        assert.include(error.message, "Unauthorized");
+      }
+
+      await program.methods
+        .setValidatorSlashBps(2_500)
+        .accounts({ admin: payer.publicKey })
+        .rpc();
+      const config = await program.account.platformConfig.fetch(configPda());
+      assert.equal(config.validatorSlashBps, 2_500);
+    });
+  });
+
+  describe("stream extensions", () => {
+    const extensionStreamName = "marathon_stream";
+    const ratePerMinute = 10_000;
+    let extensionStreamPda: PublicKey;
+    let extensionStreamAta: PublicKey;
+
+    before(async () => {
+      extensionStreamPda = await nextStreamPda(host.publicKey);
+      extensionStreamAta = await getAssociatedTokenAddress(mint, extensionStreamPda, true);
+      const endTime = Math.floor(Date.now() / 1000) + 3600;
+
+      await program.methods
+        .initialize(extensionStreamName, { live: {} }, new anchor.BN(endTime), null)
+        .accounts({
+          host: host.publicKey,
+          stream: extensionStreamPda,
+          mint: mint,
+          streamAta: extensionStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("should reject extensions until the host sets a rate", async () => {
+      try {
+        await program.methods
+          .extendStream(new anchor.BN(5))
+          .accounts({
+            payer: donor.publicKey,
+            stream: extensionStreamPda,
+            mint: mint,
+            payerAta: donorAta,
+            streamAta: extensionStreamAta,
+            blocklist: null,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([donor])
+          .rpc();
+        assert.fail("Extensions should be disabled by default");
+      } catch (error) {
+        assert.include(error.message, "ExtensionsDisabled");
+      }
+    });
+
+    it("should push the end time back by the minutes paid for", async () => {
+      await program.methods
+        .setExtensionRate(new anchor.BN(ratePerMinute))
+        .accounts({
+          host: host.publicKey,
+          stream: extensionStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      const before = await program.account.streamState.fetch(extensionStreamPda);
+      const minutes = 15;
+
+      await program.methods
+        .extendStream(new anchor.BN(minutes))
+        .accounts({
+          payer: donor.publicKey,
+          stream: extensionStreamPda,
+          mint: mint,
+          payerAta: donorAta,
+          streamAta: extensionStreamAta,
+          blocklist: null,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      const after = await program.account.streamState.fetch(extensionStreamPda);
+      assert.equal(after.endTime.toNumber(), before.endTime.toNumber() + minutes * 60);
+      assert.equal(
+        after.totalDeposited.toNumber(),
+        before.totalDeposited.toNumber() + minutes * ratePerMinute
+      );
+
+      const vaultBalance = Number((await connection.getTokenAccountBalance(extensionStreamAta)).value.amount);
+      assert.equal(vaultBalance, minutes * ratePerMinute);
+    });
+
+    it("should reject rate changes from a non-host", async () => {
+      try {
+        await program.methods
+          .setExtensionRate(new anchor.BN(1))
+          .accounts({
+            host: donor.publicKey,
+            stream: extensionStreamPda,
+          })
+          .signers([donor])
+          .rpc();
+        assert.fail("Only the host may price extensions");
+      } catch (error) {
+        assert.ok(error);
+      }
+    });
+  });
+
+  describe("viewer check-in", () => {
+    const attendanceStreamName = "attendance_stream";
+    let attendanceStreamPda: PublicKey;
+    let attendancePda: PublicKey;
+
+    before(async () => {
+      attendanceStreamPda = await nextStreamPda(host.publicKey);
+      [attendancePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("viewer_attendance"), attendanceStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(attendanceStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: attendanceStreamPda,
+          mint: mint,
+          streamAta: await getAssociatedTokenAddress(mint, attendanceStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("should reject check-ins before the stream starts", async () => {
+      try {
+        await program.methods
+          .checkIn()
+          .accounts({
+            viewer: donor.publicKey,
+            stream: attendanceStreamPda,
+            blocklist: null,
+          })
+          .signers([donor])
+          .rpc();
+        assert.fail("Nobody can attend a stream that has not started");
+      } catch (error) {
+        assert.include(error.message, "StreamNotActive");
+      }
+    });
+
+    it("should record the first check-in on a live stream", async () => {
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: attendanceStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .checkIn()
+        .accounts({
+          viewer: donor.publicKey,
+          stream: attendanceStreamPda,
+          blocklist: null,
+        })
+        .signers([donor])
+        .rpc();
+
+      const attendance = await program.account.viewerAttendance.fetch(attendancePda);
+      assert.ok(attendance.stream.equals(attendanceStreamPda));
+      assert.ok(attendance.viewer.equals(donor.publicKey));
+      assert.equal(attendance.checkInCount, 1);
+      assert.equal(attendance.firstSeen.toNumber(), attendance.lastSeen.toNumber());
+    });
+
+    it("should not count a second check-in within the interval", async () => {
+      try {
+        await program.methods
+          .checkIn()
+          .accounts({
+            viewer: donor.publicKey,
+            stream: attendanceStreamPda,
+            blocklist: null,
+          })
+          .signers([donor])
+          .rpc();
+        assert.fail("Back-to-back check-ins should be rejected");
+      } catch (error) {
+        assert.include(error.message, "CheckInTooSoon");
+      }
+
+      const attendance = await program.account.viewerAttendance.fetch(attendancePda);
+      assert.equal(attendance.checkInCount, 1);
+    });
+  });
+
+  describe("clawback agreements", () => {
+    const clawbackStreamName = "clawback_stream";
+    const window = 24 * 60 * 60;
+    let clawbackStreamPda: PublicKey;
+    let clawbackStreamAta: PublicKey;
+    let agreementPda: PublicKey;
+    let payee: Keypair;
+    let payeeAta: PublicKey;
+
+    before(async () => {
+      payee = Keypair.generate();
+      await airdrop(payee.publicKey, 1e9);
+      clawbackStreamPda = await nextStreamPda(host.publicKey);
+      clawbackStreamAta = await getAssociatedTokenAddress(mint, clawbackStreamPda, true);
+      payeeAta = await getAssociatedTokenAddress(mint, payee.publicKey);
+      [agreementPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("clawback_agreement"), clawbackStreamPda.toBuffer(), payee.publicKey.toBuffer()],
+        program.programId
+      );
+      const [clawbackDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), clawbackStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      const [payoutRecipient] = PublicKey.findProgramAddressSync(
+        [Buffer.from("payout_recipient"), clawbackStreamPda.toBuffer(), payee.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(clawbackStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: clawbackStreamPda,
+          mint: mint,
+          streamAta: clawbackStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: clawbackStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: clawbackStreamPda,
+          donorAccount: clawbackDonorAccount,
+          donorAta: donorAta,
+          streamAta: clawbackStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      await program.methods
+        .addPayoutRecipient(payee.publicKey)
+        .accounts({
+          host: host.publicKey,
+          stream: clawbackStreamPda,
+          payoutRecipient,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("should record consent and delegate to the agreement", async () => {
+      await program.methods
+        .consentToClawback(new anchor.BN(window), new anchor.BN(depositAmount))
+        .accounts({
+          recipient: payee.publicKey,
+          stream: clawbackStreamPda,
+          mint: mint,
+          recipientAta: payeeAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([payee])
+        .rpc();
+
+      const agreement = await program.account.clawbackAgreement.fetch(agreementPda);
+      assert.equal(agreement.window.toNumber(), window);
+      assert.ok(agreement.recipientAta.equals(payeeAta));
+
+      const ata = await getAccount(connection, payeeAta);
+      assert.ok(ata.delegate.equals(agreementPda), "Agreement should be the delegate");
+    });
+
+    it("should pull back a payout inside the window", async () => {
+      const amount = 5000;
+      await program.methods
+        .distribute(new anchor.BN(amount))
+        .accounts({
+          host: host.publicKey,
+          recipient: payee.publicKey,
+          mint: mint,
+          stream: clawbackStreamPda,
+          streamAta: clawbackStreamAta,
+          recipientAta: payeeAta,
+          clawbackAgreement: agreementPda,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      try {
+        await program.methods
+          .initiateClawback(new anchor.BN(amount + 1))
+          .accounts({
+            host: host.publicKey,
+            stream: clawbackStreamPda,
+            clawbackAgreement: agreementPda,
+            mint: mint,
+            recipientAta: payeeAta,
+            streamAta: clawbackStreamAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("Only what was paid in the window can be clawed back");
+      } catch (error) {
+        assert.include(error.message, "ClawbackExceeded");
+      }
+
+      await program.methods
+        .initiateClawback(new anchor.BN(amount))
+        .accounts({
+          host: host.publicKey,
+          stream: clawbackStreamPda,
+          clawbackAgreement: agreementPda,
+          mint: mint,
+          recipientAta: payeeAta,
+          streamAta: clawbackStreamAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const payeeBalance = Number((await connection.getTokenAccountBalance(payeeAta)).value.amount);
+      assert.equal(payeeBalance, 0, "Payout should be back in the vault");
+
+      const stream = await program.account.streamState.fetch(clawbackStreamPda);
+      assert.equal(stream.totalDistributed.toNumber(), 0);
+
+      const agreement = await program.account.clawbackAgreement.fetch(agreementPda);
+      assert.equal(agreement.clawbackable.toNumber(), 0);
+      assert.equal(agreement.clawedBack.toNumber(), amount);
+    });
+
+    it("should keep consent while a payout is inside the window", async () => {
+      await program.methods
+        .distribute(new anchor.BN(1000))
+        .accounts({
+          host: host.publicKey,
+          recipient: payee.publicKey,
+          mint: mint,
+          stream: clawbackStreamPda,
+          streamAta: clawbackStreamAta,
+          recipientAta: payeeAta,
+          clawbackAgreement: agreementPda,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      try {
+        await program.methods
+          .revokeClawbackConsent()
+          .accounts({
+            recipient: payee.publicKey,
+            clawbackAgreement: agreementPda,
+            recipientAta: payeeAta,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([payee])
+          .rpc();
+        assert.fail("Consent cannot be revoked inside the window");
+      } catch (error) {
+        assert.include(error.message, "ClawbackWindowOpen");
+      }
+    });
+
+    it("should make allowance withdrawals clawbackable without the recipient passing consent", async () => {
+      const [allowancePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("recipient_allowance"), clawbackStreamPda.toBuffer(), payee.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .setRecipientAllowance(payee.publicKey, new anchor.BN(500))
+        .accounts({
+          host: host.publicKey,
+          stream: clawbackStreamPda,
+          allowance: allowancePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      const before = await program.account.clawbackAgreement.fetch(agreementPda);
+      await program.methods
+        .withdrawAllowance(new anchor.BN(500))
+        .accounts({
+          recipient: payee.publicKey,
+          stream: clawbackStreamPda,
+          allowance: allowancePda,
+          mint: mint,
+          streamAta: clawbackStreamAta,
+          recipientAta: payeeAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([payee])
+        .rpc();
+
+      const after = await program.account.clawbackAgreement.fetch(agreementPda);
+      assert.equal(after.clawbackable.sub(before.clawbackable).toNumber(), 500);
+    });
+  });
+
+  describe("access gates", () => {
+    const gatedStreamName = "gated_stream";
+    let gatedStreamPda: PublicKey;
+    let gatedStreamAta: PublicKey;
+    let gatedDonorAccount: PublicKey;
+    let gateMint: PublicKey;
+    let donorGateAta: PublicKey;
+
+    before(async () => {
+      gatedStreamPda = await nextStreamPda(host.publicKey);
+      gatedStreamAta = await getAssociatedTokenAddress(mint, gatedStreamPda, true);
+      [gatedDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), gatedStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      gateMint = await createMint(connection, payer.payer, payer.publicKey, null, 0);
+      donorGateAta = await createDonorTokenAccount(gateMint, donor.publicKey);
+
+      await program.methods
+        .initialize(gatedStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: gatedStreamPda,
+          mint: mint,
+          streamAta: gatedStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({
+          host: host.publicKey,
+          stream: gatedStreamPda,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .setAccessGate(gateMint, new anchor.BN(1))
+        .accounts({
+          host: host.publicKey,
+          stream: gatedStreamPda,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    function gatedDeposit(gateTokenAccount: PublicKey | null) {
+      const [accessGate] = PublicKey.findProgramAddressSync(
+        [Buffer.from("access_gate"), gatedStreamPda.toBuffer()],
+        program.programId
+      );
+      return program.methods
+        .deposit(new anchor.BN(1000), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: gatedStreamPda,
+          donorAccount: gatedDonorAccount,
+          donorAta: donorAta,
+          streamAta: gatedStreamAta,
+          accessGate,
+          gateTokenAccount,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+    }
+
+    it("should require the gate accounts on a gated stream", async () => {
+      try {
+        await gatedDeposit(null);
+        assert.fail("Deposits should need the donor's gate token account");
+      } catch (error) {
+        assert.include(error.message, "MissingAccounts");
+      }
+    });
+
+    it("should reject donors below the gate balance", async () => {
+      try {
+        await gatedDeposit(donorGateAta);
+        assert.fail("Donors without the gate token should be rejected");
+      } catch (error) {
+        assert.include(error.message, "AccessGateNotMet");
+      }
+    });
+
+    it("should accept donors holding the gate token", async () => {
+      await mintTokens(gateMint, donorGateAta, 1);
+      await gatedDeposit(donorGateAta);
+
+      const stream = await program.account.streamState.fetch(gatedStreamPda);
+      assert.equal(stream.totalDeposited.toNumber(), 1000);
+    });
+  });
+
+  describe("host reputation and bond", () => {
+    const reputationStreamName = "reputation_stream";
+    let reputationStreamPda: PublicKey;
+    let hostProfilePda: PublicKey;
+
+    before(async () => {
+      reputationStreamPda = await nextStreamPda(host.publicKey);
+      [hostProfilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("host_profile"), host.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(reputationStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: reputationStreamPda,
+          mint: mint,
+          streamAta: await getAssociatedTokenAddress(mint, reputationStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("should count completed streams on the host profile", async () => {
+      const before = await program.account.hostProfile.fetch(hostProfilePda);
+
+      await program.methods
+        .startStream()
+        .accounts({ host: host.publicKey, stream: reputationStreamPda })
+        .signers([host])
+        .rpc();
+      await program.methods
+        .completeStream()
+        .accounts({ host: host.publicKey, stream: reputationStreamPda })
+        .signers([host])
+        .rpc();
+
+      const after = await program.account.hostProfile.fetch(hostProfilePda);
+      assert.equal(
+        after.streamsCompleted.toNumber(),
+        before.streamsCompleted.toNumber() + 1
+      );
+      assert.equal(after.totalRaised.toString(), before.totalRaised.toString());
+    });
+
+    it("should hold posted bond on the profile and release it on withdrawal", async () => {
+      const amount = new anchor.BN(anchor.web3.LAMPORTS_PER_SOL / 10);
+      const lamportsBefore = await provider.connection.getBalance(hostProfilePda);
+
+      await program.methods
+        .postHostBond(amount)
+        .accounts({ host: host.publicKey })
+        .signers([host])
+        .rpc();
+
+      let profile = await program.account.hostProfile.fetch(hostProfilePda);
+      assert.equal(profile.bond.toString(), amount.toString());
+      assert.equal(
+        await provider.connection.getBalance(hostProfilePda),
+        lamportsBefore + amount.toNumber()
+      );
+
+      try {
+        await program.methods
+          .withdrawHostBond(amount.addn(1))
+          .accounts({ host: host.publicKey })
+          .signers([host])
+          .rpc();
+        assert.fail("Cannot withdraw more than the bond");
+      } catch (error) {
+        assert.include(error.message, "InsufficientFunds");
+      }
+
+      await program.methods
+        .withdrawHostBond(amount)
+        .accounts({ host: host.publicKey })
+        .signers([host])
+        .rpc();
+
+      profile = await program.account.hostProfile.fetch(hostProfilePda);
+      assert.equal(profile.bond.toNumber(), 0);
+      assert.equal(await provider.connection.getBalance(hostProfilePda), lamportsBefore);
+    });
+  });
+
+  describe("tax receipts", () => {
+    let taxStreamPda: PublicKey;
+    let hostProfilePda: PublicKey;
+    let taxDonorAccount: PublicKey;
+    let taxReceiptPda: PublicKey;
+    const currentYear = new Date().getUTCFullYear();
+
+    before(async () => {
+      taxStreamPda = await nextStreamPda(host.publicKey);
+      const taxStreamAta = await getAssociatedTokenAddress(mint, taxStreamPda, true);
+      [hostProfilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("host_profile"), host.publicKey.toBuffer()],
+        program.programId
+      );
+      [taxDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), taxStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      [taxReceiptPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("receipt"), taxStreamPda.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+
+      await program.methods
+        .initialize("tax_receipt_stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: taxStreamPda,
+          mint: mint,
+          streamAta: taxStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+      await program.methods
+        .startStream()
+        .accounts({ host: host.publicKey, stream: taxStreamPda })
+        .signers([host])
+        .rpc();
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: taxStreamPda,
+          donorAccount: taxDonorAccount,
+          receipt: taxReceiptPda,
+          donorAta: donorAta,
+          streamAta: taxStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+    });
+
+    function aggregate(year: number) {
+      return program.methods
+        .aggregateDonorReceipt(year, 1)
+        .accounts({
+          donor: donor.publicKey,
+          hostProfile: hostProfilePda,
+          mint: mint,
+        })
+        .remainingAccounts([
+          { pubkey: taxStreamPda, isWritable: false, isSigner: false },
+          { pubkey: taxDonorAccount, isWritable: false, isSigner: false },
+          { pubkey: taxReceiptPda, isWritable: false, isSigner: false },
+        ])
+        .signers([donor])
+        .rpc();
+    }
+
+    it("should only accept an uppercase jurisdiction code", async () => {
+      try {
+        await program.methods
+          .setHostJurisdiction(Array.from(Buffer.from("us")))
+          .accounts({ host: host.publicKey })
+          .signers([host])
+          .rpc();
+        assert.fail("Lowercase jurisdiction codes should be rejected");
+      } catch (error) {
+        assert.include(error.message, "InvalidJurisdiction");
+      }
+
+      await program.methods
+        .setHostJurisdiction(Array.from(Buffer.from("US")))
+        .accounts({ host: host.publicKey })
+        .signers([host])
+        .rpc();
+
+      const profile = await program.account.hostProfile.fetch(hostProfilePda);
+      assert.equal(Buffer.from(profile.jurisdiction).toString(), "US");
+    });
+
+    it("should not issue a receipt before the year has ended", async () => {
+      try {
+        await aggregate(currentYear);
+        assert.fail("Receipts are only issued for finished years");
+      } catch (error) {
+        assert.include(error.message, "TaxYearNotOver");
+      }
+    });
+
+    it("should leave deposits made outside the year off the receipt", async () => {
+      try {
+        await aggregate(currentYear - 1);
+        assert.fail("A year without deposits should not yield a receipt");
+      } catch (error) {
+        assert.include(error.message, "NothingToClaim");
+      }
+    });
+  });
+
+  describe("parlays", () => {
+    const UNIT = 1_000_000;
+    const bettor = Keypair.generate();
+    let bettorAta: PublicKey;
+    let reservePda: PublicKey;
+    let reserveVault: PublicKey;
+    let gateMint: PublicKey;
+    const markets: PublicKey[] = [];
+    const streams: PublicKey[] = [];
+
+    function marketBookPda(market: PublicKey): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("market_book"), market.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    function parlayPda(parlayId: number): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("parlay"),
+          bettor.publicKey.toBuffer(),
+          new anchor.BN(parlayId).toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      )[0];
+    }
+
+    async function placeParlay(
+      parlayId: number,
+      legs: { market: PublicKey; outcomeId: number }[],
+      stake: number,
+      gateTokenAccount: PublicKey | null = null
+    ) {
+      // The program ID stands in for the blocklist page, access gate and gate token account
+      // of a leg whose stream needs none of them
+      const legAccounts = (market: PublicKey) => {
+        const stream = streams[markets.findIndex((m) => m.equals(market))];
+        const gated = gateTokenAccount !== null && stream.equals(streams[3]);
+        const [accessGate] = PublicKey.findProgramAddressSync(
+          [Buffer.from("access_gate"), stream.toBuffer()],
+          program.programId
+        );
+        return [
+          market,
+          marketBookPda(market),
+          stream,
+          program.programId,
+          gated ? accessGate : program.programId,
+          gated ? gateTokenAccount : program.programId,
+        ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: false }));
+      };
+
+      await program.methods
+        .placeParlay(new anchor.BN(parlayId), legs, new anchor.BN(stake), new anchor.BN(1))
+        .accounts({
+          bettor: bettor.publicKey,
+          reserve: reservePda,
+          parlay: parlayPda(parlayId),
+          mint: mint,
+          bettorToken: bettorAta,
+          reserveVault: reserveVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(legs.flatMap((leg) => legAccounts(leg.market)))
+        .signers([bettor])
+        .rpc();
+    }
+
+    async function settleParlay(parlayId: number) {
+      const parlay = await program.account.parlay.fetch(parlayPda(parlayId));
+      await program.methods
+        .settleParlay()
+        .accounts({
+          bettor: bettor.publicKey,
+          reserve: reservePda,
+          parlay: parlayPda(parlayId),
+          mint: mint,
+          bettorToken: bettorAta,
+          reserveVault: reserveVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(
+          parlay.legs.map((leg) => ({ pubkey: leg.market, isSigner: false, isWritable: false }))
+        )
+        .signers([bettor])
+        .rpc();
+    }
+
+    before(async () => {
+      await airdrop(bettor.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      bettorAta = await createDonorTokenAccount(mint, bettor.publicKey);
+      await mintTokens(mint, bettorAta, 100 * UNIT);
+      gateMint = await createMint(connection, payer.payer, payer.publicKey, null, 0);
+
+      // The fourth market's stream is access gated
+      for (let i = 0; i < 4; i++) {
+        const parlayStreamPda = await nextStreamPda(host.publicKey);
+        const [marketPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("betting_market"), parlayStreamPda.toBuffer()],
+          program.programId
+        );
+        await program.methods
+          .initialize(`parlay_stream_${i}`, { live: {} }, null, null)
+          .accounts({
+            host: host.publicKey,
+            stream: parlayStreamPda,
+            mint: mint,
+            streamAta: await getAssociatedTokenAddress(mint, parlayStreamPda, true),
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+        await program.methods
+          .initializeBettingMarket(
+            { binary: {} },
+            ["Yes", "No"],
+            new anchor.BN(Math.floor(Date.now() / 1000) + 3600),
+            new anchor.BN(1000 * UNIT),
+            250,
+            { winnerTakesAll: {} },
+            null,
+            null,
+            { constantProduct: {} },
+            null
+          )
+          .accounts({
+            host: host.publicKey,
+            stream: parlayStreamPda,
+            mint: mint,
+            bettingMarket: marketPda,
+            systemProgram: SystemProgram.programId,
+          })
+          .signers([host])
+          .rpc();
+        markets.push(marketPda);
+        streams.push(parlayStreamPda);
+      }
+
+      await program.methods
+        .setAccessGate(gateMint, new anchor.BN(1))
+        .accounts({
+          host: host.publicKey,
+          stream: streams[3],
+        })
+        .signers([host])
+        .rpc();
+
+      [reservePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("parlay_reserve"), mint.toBuffer()],
+        program.programId
+      );
+      [reserveVault] = PublicKey.findProgramAddressSync(
+        [Buffer.from("parlay_vault"), reservePda.toBuffer()],
+        program.programId
+      );
+    });
+
+    it("should only let the platform admin open a reserve", async () => {
+      try {
+        await program.methods
+          .initializeParlayReserve()
+          .accounts({
+            admin: host.publicKey,
+            mint: mint,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([host])
+          .rpc();
+        assert.fail("Only the admin opens reserves");
+      } catch (error) {
+        assert.include(error.message, "Unauthorized");
+      }
+
+      await program.methods
+        .initializeParlayReserve()
+        .accounts({
+          admin: payer.publicKey,
+          mint: mint,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      const payerAta = await createDonorTokenAccount(mint, payer.publicKey);
+      await mintTokens(mint, payerAta, 500 * UNIT);
+      await program.methods
+        .fundParlayReserve(new anchor.BN(500 * UNIT))
+        .accounts({
+          funder: payer.publicKey,
+          mint: mint,
+          funderToken: payerAta,
+          reserveVault: reserveVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      const vault = await getAccount(connection, reserveVault);
+      assert.equal(vault.amount.toString(), (500 * UNIT).toString());
+    });
+
+    it("should reject single-leg and repeated-market parlays", async () => {
+      for (const legs of [
+        [{ market: markets[0], outcomeId: 0 }],
+        [
+          { market: markets[0], outcomeId: 0 },
+          { market: markets[0], outcomeId: 1 },
+        ],
+      ]) {
+        try {
+          await placeParlay(99, legs, 10 * UNIT);
+          assert.fail("Parlays need distinct markets");
+        } catch (error) {
+          assert.include(error.message, "InvalidParlayLegs");
+        }
+      }
+    });
+
+    it("should hold each leg to its stream's access gate", async () => {
+      const legs = [
+        { market: markets[0], outcomeId: 0 },
+        { market: markets[3], outcomeId: 0 },
+      ];
+      try {
+        await placeParlay(98, legs, 10 * UNIT);
+        assert.fail("A gated leg needs the gate accounts");
+      } catch (error) {
+        assert.include(error.message, "MissingAccounts");
+      }
+
+      const bettorGateAta = await createDonorTokenAccount(gateMint, bettor.publicKey);
+      try {
+        await placeParlay(98, legs, 10 * UNIT, bettorGateAta);
+        assert.fail("The bettor holds none of the gate mint");
+      } catch (error) {
+        assert.include(error.message, "AccessGateNotMet");
+      }
+    });
+
+    it("should lock the product of the leg odds as the payout", async () => {
+      await placeParlay(1, [
+        { market: markets[0], outcomeId: 1 },
+        { market: markets[1], outcomeId: 0 },
+      ], 10 * UNIT);
+      await placeParlay(2, [
+        { market: markets[0], outcomeId: 0 },
+        { market: markets[2], outcomeId: 0 },
+      ], 10 * UNIT);
+      await placeParlay(3, [
+        { market: markets[0], outcomeId: 0 },
+        { market: markets[1], outcomeId: 0 },
+      ], 10 * UNIT);
+
+      // Untraded binary markets price both sides at even odds
+      const parlay = await program.account.parlay.fetch(parlayPda(2));
+      assert.equal(parlay.stake.toNumber(), 10 * UNIT);
+      assert.equal(parlay.payout.toNumber(), 40 * UNIT);
+
+      const reserve = await program.account.parlayReserve.fetch(reservePda);
+      assert.equal(reserve.totalLocked.toNumber(), 120 * UNIT);
+      assert.equal(reserve.openParlays, 3);
+    });
+
+    it("should settle lost, won and voided parlays from the leg markets", async () => {
+      for (const market of [markets[0], markets[2]]) {
+        await program.methods
+          .resolveMarket(0, new Array(32).fill(1))
+          .accounts({ resolver: host.publicKey, bettingMarket: market })
+          .signers([host])
+          .rpc();
+      }
+
+      // A lost leg settles the parlay even while another leg is still open
+      let balance = (await getAccount(connection, bettorAta)).amount;
+      await settleParlay(1);
+      assert.equal((await getAccount(connection, bettorAta)).amount, balance);
+      assert.isNull(await program.account.parlay.fetchNullable(parlayPda(1)));
+
+      await settleParlay(2);
+      assert.equal(
+        (await getAccount(connection, bettorAta)).amount,
+        balance + BigInt(40 * UNIT)
+      );
+
+      try {
+        await settleParlay(3);
+        assert.fail("An open leg keeps the parlay pending");
+      } catch (error) {
+        assert.include(error.message, "MarketNotResolved");
+      }
+
+      await program.methods
+        .cancelMarket()
+        .accounts({ authority: host.publicKey, bettingMarket: markets[1] })
+        .signers([host])
+        .rpc();
+      balance = (await getAccount(connection, bettorAta)).amount;
+      await settleParlay(3);
+      assert.equal(
+        (await getAccount(connection, bettorAta)).amount,
+        balance + BigInt(10 * UNIT)
+      );
+
+      const reserve = await program.account.parlayReserve.fetch(reservePda);
+      assert.equal(reserve.totalLocked.toNumber(), 0);
+      assert.equal(reserve.openParlays, 0);
+    });
+  });
+
+  describe("notifications", () => {
+    const notifyStreamName = "notify_stream";
+    let notifyStreamPda: PublicKey;
+    let notifyStreamAta: PublicKey;
+
+    before(async () => {
+      notifyStreamPda = await nextStreamPda(host.publicKey);
+      notifyStreamAta = await getAssociatedTokenAddress(mint, notifyStreamPda, true);
+
+      // Refunds close within the hour, inside the closing-soon window
+      const refundDeadline = new anchor.BN(Math.floor(Date.now() / 1000) + 3600);
+      await program.methods
+        .initialize(notifyStreamName, { live: {} }, null, refundDeadline)
+        .accounts({
+          host: host.publicKey,
+          stream: notifyStreamPda,
+          mint: mint,
+          streamAta: notifyStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({ host: host.publicKey, stream: notifyStreamPda })
+        .signers([host])
+        .rpc();
+    });
+
+    it("should warn depositors that the refund window is closing", async () => {
+      const [notifyDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), notifyStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+
+      const signature = await program.methods
+        .deposit(new anchor.BN(1000), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: notifyStreamPda,
+          donorAccount: notifyDonorAccount,
+          donorAta: donorAta,
+          streamAta: notifyStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc({ commitment: "confirmed" });
+
+      const tx = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const notifications = [...parser.parseLogs(tx.meta.logMessages)].filter(
+        (event) => event.name === "notification"
+      );
+
+      assert.equal(notifications.length, 1);
+      const notification = notifications[0].data as any;
+      assert.deepEqual(notification.severity, { warning: {} });
+      assert.deepEqual(notification.code, { refundWindowClosing: {} });
+      assert.ok(notification.subject.equals(notifyStreamPda));
+      assert.equal(notification.values[1].toNumber(), 1000);
+    });
+  });
+
+  describe("global registry and host stream index", () => {
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("global_registry")],
+      program.programId
+    );
+
+    before(async () => {
+      const existing = await connection.getAccountInfo(registryPda);
+      if (!existing) {
+        await program.methods.initializeGlobalRegistry().rpc();
+      }
+    });
+
+    it("should count new streams and list them under their host", async () => {
+      const registryStreamName = "registry_stream";
+      const registryStreamPda = await nextStreamPda(host.publicKey);
+      const [hostStreamIndexPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("host_stream_index"), host.publicKey.toBuffer()],
+        program.programId
+      );
+      const before = await program.account.globalRegistry.fetch(registryPda);
+
+      await program.methods
+        .initialize(registryStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: registryStreamPda,
+          mint: mint,
+          streamAta: await getAssociatedTokenAddress(mint, registryStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const after = await program.account.globalRegistry.fetch(registryPda);
+      assert.equal(after.streamCount.toNumber(), before.streamCount.toNumber() + 1);
+
+      const index = await program.account.hostStreamIndex.fetch(hostStreamIndexPda);
+      assert.ok(index.host.equals(host.publicKey));
+      assert.ok(index.streams[index.streams.length - 1].equals(registryStreamPda));
+
+      const profile = await program.account.hostProfile.fetch(
+        PublicKey.findProgramAddressSync(
+          [Buffer.from("host_profile"), host.publicKey.toBuffer()],
+          program.programId
+        )[0]
+      );
+      assert.equal(index.streams.length, profile.streamsCreated.toNumber());
+    });
+
+    it("should add deposits to the registry volume", async () => {
+      const registryStreamPda = await streamPdaByName(host.publicKey, "registry_stream");
+      const [registryDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), registryStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .startStream()
+        .accounts({ host: host.publicKey, stream: registryStreamPda })
+        .signers([host])
+        .rpc();
+      const before = await program.account.globalRegistry.fetch(registryPda);
+
+      await program.methods
+        .deposit(new anchor.BN(1000), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: registryStreamPda,
+          donorAccount: registryDonorAccount,
+          donorAta: donorAta,
+          streamAta: await getAssociatedTokenAddress(mint, registryStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      const after = await program.account.globalRegistry.fetch(registryPda);
+      assert.equal(
+        after.totalDeposited.sub(before.totalDeposited).toNumber(),
+        1000
+      );
+    });
+  });
+
+  describe("leaderboard refunds", () => {
+    let boardStreamPda: PublicKey;
+    let boardStreamAta: PublicKey;
+    let boardDonorAccount: PublicKey;
+    let leaderboardPda: PublicKey;
+
+    before(async () => {
+      boardStreamPda = await nextStreamPda(host.publicKey);
+      boardStreamAta = await getAssociatedTokenAddress(mint, boardStreamPda, true);
+      [boardDonorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), boardStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      [leaderboardPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("leaderboard"), boardStreamPda.toBuffer()],
+        program.programId
+      );
+
+      await program.methods
+        .initialize(
+          "leaderboard_refunds",
+          { prepaid: { minDuration: new anchor.BN(3600) } },
+          null,
+          new anchor.BN(Math.floor(Date.now() / 1000) + 3600)
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: boardStreamPda,
+          mint: mint,
+          streamAta: boardStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .initializeLeaderboard(5)
+        .accounts({ host: host.publicKey, stream: boardStreamPda })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .deposit(new anchor.BN(3000), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: boardStreamPda,
+          donorAccount: boardDonorAccount,
+          donorAta: donorAta,
+          streamAta: boardStreamAta,
+          leaderboard: leaderboardPda,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+    });
+
+    const refund = (amount: number) =>
+      program.methods
+        .refund(new anchor.BN(amount))
+        .accounts({
+          donor: donor.publicKey,
+          initiator: donor.publicKey,
+          stream: boardStreamPda,
+          donorAccount: boardDonorAccount,
+          donorAta: donorAta,
+          streamAta: boardStreamAta,
+          leaderboard: leaderboardPda,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+    it("should lower a donor's total when they take a refund", async () => {
+      await refund(1000);
+
+      const board = await program.account.leaderboard.fetch(leaderboardPda);
+      assert.equal(board.entries.length, 1);
+      assert.ok(board.entries[0].donor.equals(donor.publicKey));
+      assert.equal(board.entries[0].amount.toNumber(), 2000);
+    });
+
+    it("should drop a donor refunded in full from the board", async () => {
+      await refund(2000);
+
+      const board = await program.account.leaderboard.fetch(leaderboardPda);
+      assert.equal(board.entries.length, 0);
+    });
+  });
+
+  describe("deflationary streams", () => {
+    const burnStreamName = "burn_stream";
+    let burnMint: PublicKey;
+    let burnStreamPda: PublicKey;
+    let burnStreamAta: PublicKey;
+    let burnDonorAta: PublicKey;
+    let recipient: Keypair;
+
+    before(async () => {
+      recipient = Keypair.generate();
+      await airdrop(recipient.publicKey, anchor.web3.LAMPORTS_PER_SOL);
+      burnMint = await createMint(connection, payer.payer, payer.publicKey, null, 6);
+      burnStreamPda = await nextStreamPda(host.publicKey);
+      burnStreamAta = await getAssociatedTokenAddress(burnMint, burnStreamPda, true);
+      burnDonorAta = await createDonorTokenAccount(burnMint, donor.publicKey);
+      await mintTokens(burnMint, burnDonorAta, depositAmount);
+
+      await program.methods
+        .initialize(burnStreamName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: burnStreamPda,
+          mint: burnMint,
+          streamAta: burnStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({ host: host.publicKey, stream: burnStreamPda })
+        .signers([host])
+        .rpc();
+
+      const [donorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), burnStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: burnStreamPda,
+          donorAccount,
+          donorAta: burnDonorAta,
+          streamAta: burnStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+    });
+
+    it("should reject burn shares above half of a payout", async () => {
+      try {
+        await program.methods
+          .setBurnBps(5_001)
+          .accounts({ host: host.publicKey, stream: burnStreamPda, mint: burnMint })
+          .signers([host])
+          .rpc();
+        assert.fail("Burn share should be capped");
+      } catch (error) {
+        assert.include(error.message, "BurnNotPermitted");
+      }
+    });
+
+    it("should reject mints with a freeze authority", async () => {
+      const freezableMint = await createMint(connection, payer.payer, payer.publicKey, payer.publicKey, 6);
+      const freezableStreamPda = await nextStreamPda(host.publicKey);
+
+      await program.methods
+        .initialize("freezable_stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: freezableStreamPda,
+          mint: freezableMint,
+          streamAta: await getAssociatedTokenAddress(freezableMint, freezableStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      try {
+        await program.methods
+          .setBurnBps(1_000)
+          .accounts({ host: host.publicKey, stream: freezableStreamPda, mint: freezableMint })
+          .signers([host])
+          .rpc();
+        assert.fail("Freezable mints cannot be burned from the vault");
+      } catch (error) {
+        assert.include(error.message, "BurnNotPermitted");
+      }
+    });
+
+    it("should burn the configured share of each distribution", async () => {
+      await program.methods
+        .setBurnBps(2_500)
+        .accounts({ host: host.publicKey, stream: burnStreamPda, mint: burnMint })
+        .signers([host])
+        .rpc();
+
+      const amount = 4_000;
+      const recipientBurnAta = await getAssociatedTokenAddress(burnMint, recipient.publicKey);
+      const supplyBefore = (await getMint(connection, burnMint)).supply;
+
+      const signature = await program.methods
+        .distribute(new anchor.BN(amount))
+        .accounts({
+          host: host.publicKey,
+          recipient: recipient.publicKey,
+          mint: burnMint,
+          stream: burnStreamPda,
+          streamAta: burnStreamAta,
+          recipientAta: recipientBurnAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc({ commitment: "confirmed" });
+
+      const recipientBalance = await getAccount(connection, recipientBurnAta);
+      assert.equal(Number(recipientBalance.amount), 3_000, "Recipient should receive the unburned share");
+      const supplyAfter = (await getMint(connection, burnMint)).supply;
+      assert.equal(Number(supplyBefore) - Number(supplyAfter), 1_000, "Burned share should leave the supply");
+
+      const stream = await program.account.streamState.fetch(burnStreamPda);
+      assert.equal(stream.totalDistributed.toNumber(), amount, "Burns count toward distributed funds");
+
+      const tx = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const distributed = [...parser.parseLogs(tx.meta.logMessages)].find(
+        (event) => event.name === "fundsDistributed"
+      );
+      assert.equal((distributed.data as any).burned.toNumber(), 1_000);
+    });
+
+    it("should burn the configured share of allowance withdrawals", async () => {
+      const [allowancePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("recipient_allowance"), burnStreamPda.toBuffer(), recipient.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .setRecipientAllowance(recipient.publicKey, new anchor.BN(2_000))
+        .accounts({
+          host: host.publicKey,
+          stream: burnStreamPda,
+          allowance: allowancePda,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+
+      const recipientBurnAta = await getAssociatedTokenAddress(burnMint, recipient.publicKey);
+      const balanceBefore = (await getAccount(connection, recipientBurnAta)).amount;
+      const supplyBefore = (await getMint(connection, burnMint)).supply;
+
+      await program.methods
+        .withdrawAllowance(new anchor.BN(2_000))
+        .accounts({
+          recipient: recipient.publicKey,
+          stream: burnStreamPda,
+          allowance: allowancePda,
+          mint: burnMint,
+          streamAta: burnStreamAta,
+          recipientAta: recipientBurnAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([recipient])
+        .rpc();
+
+      const balanceAfter = (await getAccount(connection, recipientBurnAta)).amount;
+      assert.equal(Number(balanceAfter - balanceBefore), 1_500, "Recipient should receive the unburned share");
+      const supplyAfter = (await getMint(connection, burnMint)).supply;
+      assert.equal(Number(supplyBefore - supplyAfter), 500, "Burned share should leave the supply");
+    });
+  });
+
+  describe("raffles", () => {
+    const prize = 1_000_000;
+    const ticketPrice = 100_000;
+    let raffleMint: PublicKey;
+    let raffleStreamPda: PublicKey;
+    let raffleStreamAta: PublicKey;
+    let raffleDonorAta: PublicKey;
+
+    const rafflePda = (raffleId: number, stream: PublicKey = raffleStreamPda) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("raffle"), stream.toBuffer(), new anchor.BN(raffleId).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    const raffleVaultPda = (raffle: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("raffle_vault"), raffle.toBuffer()], program.programId)[0];
+
+    function createRaffle(
+      raffleId: number,
+      entryWindow: number,
+      stream: PublicKey = raffleStreamPda,
+      streamAta: PublicKey = raffleStreamAta
+    ) {
+      const raffle = rafflePda(raffleId, stream);
+      return program.methods
+        .createRaffle(
+          new anchor.BN(raffleId),
+          new anchor.BN(prize),
+          new anchor.BN(ticketPrice),
+          new anchor.BN(Math.floor(Date.now() / 1000) + entryWindow),
+          0
+        )
+        .accounts({
+          host: host.publicKey,
+          stream,
+          mint: raffleMint,
+          streamAta,
+          sponsor: null,
+          sponsorToken: null,
+          distributionPolicy: null,
+          treasuryAta: null,
+          hostProfile: null,
+          referralRewards: null,
+          referralVault: null,
+          raffle,
+          raffleVault: raffleVaultPda(raffle),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    }
+
+    function enterRaffle(raffleId: number) {
+      return program.methods
+        .enterRaffle()
+        .accounts({
+          entrant: donor.publicKey,
+          stream: raffleStreamPda,
+          raffle: rafflePda(raffleId),
+          mint: raffleMint,
+          entrantToken: raffleDonorAta,
+          streamAta: raffleStreamAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([donor])
+        .rpc();
+    }
+
+    before(async () => {
+      raffleMint = await createMint(connection, payer.payer, payer.publicKey, null, 6);
+      raffleStreamPda = await nextStreamPda(host.publicKey);
+      raffleStreamAta = await getAssociatedTokenAddress(raffleMint, raffleStreamPda, true);
+      raffleDonorAta = await createDonorTokenAccount(raffleMint, donor.publicKey);
+      await mintTokens(raffleMint, raffleDonorAta, depositAmount);
+
+      await program.methods
+        .initialize("raffle_stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: raffleStreamPda,
+          mint: raffleMint,
+          streamAta: raffleStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({ host: host.publicKey, stream: raffleStreamPda })
+        .signers([host])
+        .rpc();
+
+      const [donorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), raffleStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .deposit(new anchor.BN(2 * prize), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: raffleStreamPda,
+          donorAccount,
+          donorAta: raffleDonorAta,
+          streamAta: raffleStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+    });
+
+    it("should escrow a prize from the stream vault and sell tickets into it", async () => {
+      await createRaffle(1, 3600);
+
+      const vault = await getAccount(connection, raffleVaultPda(rafflePda(1)));
+      assert.equal(Number(vault.amount), prize);
+      let stream = await program.account.streamState.fetch(raffleStreamPda);
+      assert.equal(stream.totalDistributed.toNumber(), prize, "The prize leaves the available balance");
+
+      await enterRaffle(1);
+      const raffle = await program.account.raffle.fetch(rafflePda(1));
+      assert.equal(raffle.entryCount, 1);
+      stream = await program.account.streamState.fetch(raffleStreamPda);
+      assert.equal(stream.ticketRevenue.toNumber(), ticketPrice, "Tickets are paid into the stream");
+      assert.equal(stream.totalDeposited.toNumber(), 2 * prize, "Ticket buyers are not donors");
+
+      try {
+        await enterRaffle(1);
+        assert.fail("One entry per wallet");
+      } catch (error) {
+        assert.include(error.message, "already in use");
+      }
+    });
+
+    it("should only pay a drawn winner", async () => {
+      try {
+        await program.methods
+          .claimRafflePrize()
+          .accounts({
+            winner: donor.publicKey,
+            raffle: rafflePda(1),
+            mint: raffleMint,
+            raffleVault: raffleVaultPda(rafflePda(1)),
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([donor])
+          .rpc();
+        assert.fail("No winner has been drawn");
+      } catch (error) {
+        assert.include(error.message, "RaffleNotDrawn");
+      }
+    });
+
+    it("should return the prize of a raffle nobody entered", async () => {
+      await createRaffle(2, 2);
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+
+      await program.methods
+        .cancelRaffle()
+        .accounts({
+          host: host.publicKey,
+          stream: raffleStreamPda,
+          raffle: rafflePda(2),
+          mint: raffleMint,
+          raffleVault: raffleVaultPda(rafflePda(2)),
+          funderToken: raffleStreamAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const raffle = await program.account.raffle.fetch(rafflePda(2));
+      assert.deepEqual(raffle.status, { cancelled: {} });
+      const stream = await program.account.streamState.fetch(raffleStreamPda);
+      assert.equal(stream.totalDistributed.toNumber(), prize, "Only the open raffle's prize stays out");
+    });
+
+    it("should not fund a raffle from a cancelled stream's vault", async () => {
+      const cancelledStreamPda = await nextStreamPda(host.publicKey);
+      const cancelledStreamAta = await getAssociatedTokenAddress(raffleMint, cancelledStreamPda, true);
+      await program.methods
+        .initialize("cancelled_raffle_stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: cancelledStreamPda,
+          mint: raffleMint,
+          streamAta: cancelledStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+      await program.methods
+        .startStream()
+        .accounts({ host: host.publicKey, stream: cancelledStreamPda })
+        .signers([host])
+        .rpc();
+
+      const [donorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), cancelledStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .deposit(new anchor.BN(prize), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: cancelledStreamPda,
+          donorAccount,
+          donorAta: raffleDonorAta,
+          streamAta: cancelledStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+      await program.methods
+        .updateStream(null, { cancelled: {} })
+        .accounts({ host: host.publicKey, stream: cancelledStreamPda })
+        .signers([host])
+        .rpc();
+
+      try {
+        await createRaffle(1, 3600, cancelledStreamPda, cancelledStreamAta);
+        assert.fail("A cancelled stream's balance is owed back to its donors");
+      } catch (error) {
+        assert.include(error.message, "StreamNotActive");
+      }
+      const stream = await program.account.streamState.fetch(cancelledStreamPda);
+      assert.equal(stream.totalDistributed.toNumber(), 0);
+    });
+  });
+
+  describe("donor bonus pools", () => {
+    const pot = 1_000_000;
+    const donation = 400_000;
+    let bonusMint: PublicKey;
+    let bonusStreamPda: PublicKey;
+    let hostBonusAta: PublicKey;
+
+    const bonusPoolPda = (poolId: number) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("bonus_pool"), bonusStreamPda.toBuffer(), new anchor.BN(poolId).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    const poolVaultPda = (pool: PublicKey) =>
+      PublicKey.findProgramAddressSync([Buffer.from("bonus_pool_vault"), pool.toBuffer()], program.programId)[0];
+
+    function createBonusPool(poolId: number, winnerCount: number, entryWindow: number) {
+      const bonusPool = bonusPoolPda(poolId);
+      return program.methods
+        .createBonusPool(
+          new anchor.BN(poolId),
+          new anchor.BN(pot),
+          winnerCount,
+          new anchor.BN(Math.floor(Date.now() / 1000) + entryWindow)
+        )
+        .accounts({
+          host: host.publicKey,
+          stream: bonusStreamPda,
+          mint: bonusMint,
+          hostToken: hostBonusAta,
+          bonusPool,
+          poolVault: poolVaultPda(bonusPool),
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([host])
+        .rpc();
+    }
+
+    before(async () => {
+      bonusMint = await createMint(connection, payer.payer, payer.publicKey, null, 6);
+      bonusStreamPda = await nextStreamPda(host.publicKey);
+      const bonusStreamAta = await getAssociatedTokenAddress(bonusMint, bonusStreamPda, true);
+      const bonusDonorAta = await createDonorTokenAccount(bonusMint, donor.publicKey);
+      hostBonusAta = await createDonorTokenAccount(bonusMint, host.publicKey);
+      await mintTokens(bonusMint, bonusDonorAta, donation);
+      await mintTokens(bonusMint, hostBonusAta, 3 * pot);
+
+      await program.methods
+        .initialize("bonus_stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: bonusStreamPda,
+          mint: bonusMint,
+          streamAta: bonusStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      await program.methods
+        .startStream()
+        .accounts({ host: host.publicKey, stream: bonusStreamPda })
+        .signers([host])
+        .rpc();
+
+      const [donorAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), bonusStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      );
+      await program.methods
+        .deposit(new anchor.BN(donation), null)
+        .accounts({
+          donor: donor.publicKey,
+          stream: bonusStreamPda,
+          donorAccount,
+          donorAta: bonusDonorAta,
+          streamAta: bonusStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+    });
+
+    it("should split the pot into at least one pick", async () => {
+      try {
+        await createBonusPool(1, 0, 3600);
+        assert.fail("A pool needs a winner");
+      } catch (error) {
+        assert.include(error.message, "InvalidBonusWinnerCount");
+      }
+    });
+
+    it("should weight each donor by their contribution", async () => {
+      await createBonusPool(1, 3, 3600);
+      await program.methods
+        .enterBonusPool()
+        .accounts({ donor: donor.publicKey, bonusPool: bonusPoolPda(1) })
+        .signers([donor])
+        .rpc();
+
+      const pool = await program.account.bonusPool.fetch(bonusPoolPda(1));
+      assert.equal(pool.amount.toNumber(), pot);
+      assert.equal(pool.totalWeight.toNumber(), donation);
+      assert.equal(pool.entrantCount, 1);
+
+      try {
+        await program.methods
+          .claimBonus()
+          .accounts({
+            donor: donor.publicKey,
+            bonusPool: bonusPoolPda(1),
+            mint: bonusMint,
+            poolVault: poolVaultPda(bonusPoolPda(1)),
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([donor])
+          .rpc();
+        assert.fail("Nothing is paid before the draw");
+      } catch (error) {
+        assert.include(error.message, "BonusPoolNotDrawn");
+      }
+    });
+
+    it("should hand a pot nobody entered back to the host", async () => {
+      await createBonusPool(2, 1, 2);
+      await new Promise((resolve) => setTimeout(resolve, 3000));
+      const before = await getAccount(connection, hostBonusAta);
+
+      await program.methods
+        .reclaimBonusPool()
+        .accounts({
+          host: host.publicKey,
+          bonusPool: bonusPoolPda(2),
+          mint: bonusMint,
+          poolVault: poolVaultPda(bonusPoolPda(2)),
+          hostToken: hostBonusAta,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const after = await getAccount(connection, hostBonusAta);
+      assert.equal(Number(after.amount) - Number(before.amount), pot);
+    });
+  });
+
+  describe("stream renames", () => {
+    const originalName = "rename_me";
+    let renameStreamPda: PublicKey;
+
+    before(async () => {
+      renameStreamPda = await nextStreamPda(host.publicKey);
+      await program.methods
+        .initialize(originalName, { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: renameStreamPda,
+          mint: mint,
+          streamAta: await getAssociatedTokenAddress(mint, renameStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("should rename a stream without moving it", async () => {
+      await program.methods
+        .renameStream("renamed stream")
+        .accounts({ host: host.publicKey, stream: renameStreamPda })
+        .signers([host])
+        .rpc();
+
+      const stream = await program.account.streamState.fetch(renameStreamPda);
+      assert.equal(stream.streamName, "renamed stream");
+    });
+
+    it("should let a host reuse a name", async () => {
+      const reusedStreamPda = await nextStreamPda(host.publicKey);
+      assert.notEqual(reusedStreamPda.toBase58(), renameStreamPda.toBase58());
+
+      await program.methods
+        .initialize("renamed stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: reusedStreamPda,
+          mint: mint,
+          streamAta: await getAssociatedTokenAddress(mint, reusedStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+
+      const stream = await program.account.streamState.fetch(reusedStreamPda);
+      assert.equal(stream.streamName, "renamed stream");
+    });
+
+    it("should reject names outside the allowed length", async () => {
+      try {
+        await program.methods
+          .renameStream("abc")
+          .accounts({ host: host.publicKey, stream: renameStreamPda })
+          .signers([host])
+          .rpc();
+        assert.fail("Names must be at least 4 characters");
+      } catch (error) {
+        assert.include(error.message, "NameLengthInvalid");
+      }
+    });
+
+    it("should reject renames by anyone but the host", async () => {
+      try {
+        await program.methods
+          .renameStream("hijacked")
+          .accounts({ host: donor.publicKey, stream: renameStreamPda })
+          .signers([donor])
+          .rpc();
+        assert.fail("Only the host can rename the stream");
+      } catch (error) {
+        assert.include(error.message, "Error");
+      }
+    });
+  });
+
+  describe("stream versioning", () => {
+    let versionedStreamPda: PublicKey;
+
+    before(async () => {
+      versionedStreamPda = await nextStreamPda(host.publicKey);
+      await program.methods
+        .initialize("versioned stream", { live: {} }, null, null)
+        .accounts({
+          host: host.publicKey,
+          stream: versionedStreamPda,
+          mint: mint,
+          streamAta: await getAssociatedTokenAddress(mint, versionedStreamPda, true),
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([host])
+        .rpc();
+    });
+
+    it("should create streams on the current layout version", async () => {
+      const stream = await program.account.streamState.fetch(versionedStreamPda);
+      assert.equal(stream.version, 4);
+      assert.deepEqual(stream.seedKind, { nonce: {} });
+    });
+
+    it("should refuse to migrate a stream that is already current", async () => {
+      try {
+        await program.methods
+          .migrateStream()
+          .accounts({ payer: donor.publicKey, stream: versionedStreamPda })
+          .signers([donor])
+          .rpc();
+        assert.fail("A current stream has nothing to migrate");
+      } catch (error) {
+        assert.include(error.message, "StreamAlreadyMigrated");
+      }
+    });
+
+    it("should refuse to migrate an account that is not a stream", async () => {
+      const [hostProfilePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("host_profile"), host.publicKey.toBuffer()],
+        program.programId
+      );
+      try {
+        await program.methods
+          .migrateStream()
+          .accounts({ payer: donor.publicKey, stream: hostProfilePda })
+          .signers([donor])
+          .rpc();
+        assert.fail("Only stream accounts can be migrated");
+      } catch (error) {
+        assert.include(error.message, "InvalidStreamAccount");
+      }
+    });
+  });
+
+  describe("name-seeded streams", () => {
+    // tests/fixtures/legacy-stream.json holds a stream in the layout the program wrote before
+    // per-host nonces, at its `[b"stream", name, host]` address; the host signs with this key.
+    // tests/fixtures/legacy-donor.json is a donor record for it in the original layout.
+    const legacyHost = Keypair.fromSeed(new Uint8Array(32).fill(7));
+    const legacyDonor = Keypair.fromSeed(new Uint8Array(32).fill(8));
+    const [legacyStreamPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("stream"), Buffer.from("legacy_stream"), legacyHost.publicKey.toBuffer()],
+      program.programId
+    );
+    const [legacyDonorAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from("donor"), legacyStreamPda.toBuffer(), legacyDonor.publicKey.toBuffer()],
+      program.programId
+    );
+
+    it("should migrate a name-seeded stream in place", async () => {
+      await program.methods
+        .migrateStream()
+        .accounts({ payer: payer.publicKey, stream: legacyStreamPda })
+        .rpc();
+
+      const stream = await program.account.streamState.fetch(legacyStreamPda);
+      assert.equal(stream.version, 4);
+      assert.deepEqual(stream.seedKind, { name: { seedName: "legacy_stream" } });
+      assert.equal(stream.host.toString(), legacyHost.publicKey.toString());
+      assert.equal(stream.totalDeposited.toNumber(), 5_000_000);
+      assert.equal(stream.totalDistributed.toNumber(), 1_000_000);
+      assert.equal(stream.goalAmount.toNumber(), 0);
+      assert.isEmpty(stream.milestonePercentages);
+      assert.isNull(stream.refundDeadline);
+      assert.isEmpty(stream.extraMints);
+    });
+
+    it("should migrate a donor record written in the original layout", async () => {
+      await program.methods
+        .migrateDonorAccount()
+        .accounts({ payer: payer.publicKey, donorAccount: legacyDonorAccount })
+        .rpc();
+
+      const record = await program.account.donorAccount.fetch(legacyDonorAccount);
+      assert.equal(record.stream.toString(), legacyStreamPda.toString());
+      assert.equal(record.donor.toString(), legacyDonor.publicKey.toString());
+      assert.equal(record.amount.toNumber(), 5_000_000);
+      assert.equal(record.depositCount.toNumber(), 0);
+      assert.isEmpty(record.extraBalances);
+      assert.equal(record.matchedDeposits.toNumber(), 0);
+
+      try {
+        await program.methods
+          .migrateDonorAccount()
+          .accounts({ payer: payer.publicKey, donorAccount: legacyDonorAccount })
+          .rpc();
+        assert.fail("A current donor record has nothing to migrate");
+      } catch (error) {
+        assert.include(error.message, "DonorAccountAlreadyMigrated");
+      }
+    });
+
+    it("should keep addressing the stream by its name seeds after a rename", async () => {
+      await program.methods
+        .renameStream("renamed legacy")
+        .accounts({ host: legacyHost.publicKey, stream: legacyStreamPda })
+        .signers([legacyHost])
+        .rpc();
+      await program.methods
+        .startStream()
+        .accounts({ host: legacyHost.publicKey, stream: legacyStreamPda })
+        .signers([legacyHost])
+        .rpc();
+
+      const stream = await program.account.streamState.fetch(legacyStreamPda);
+      assert.equal(stream.streamName, "renamed legacy");
+      assert.deepEqual(stream.seedKind, { name: { seedName: "legacy_stream" } });
+      assert.isNotNull(stream.startTime);
+    });
+  });
+
+  describe("session keys", () => {
+    const limit = 500_000;
+    const sessionKey = Keypair.generate();
+    let sessionMint: PublicKey;
+    let sessionStreamPda: PublicKey;
+    let sessionStreamAta: PublicKey;
+    let donorSessionAta: PublicKey;
+
+    const sessionPda = (owner: PublicKey, key: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("session_key"), owner.toBuffer(), key.toBuffer()],
+        program.programId
+      )[0];
+    const donorAccountPda = () =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("donor"), sessionStreamPda.toBuffer(), donor.publicKey.toBuffer()],
+        program.programId
+      )[0];
+
+    function depositWithSession(amount: number) {
+      return program.methods
+        .depositWithSession(new anchor.BN(amount), null)
         .accounts({
-          donor: donor.publicKey,
-          stream: endedStreamPda,
-          donorAccount: endedDonorAccount,
-          donorAta: donorAta,
-          streamAta: endedStreamAta,
+          sessionSigner: sessionKey.publicKey,
+          session: sessionPda(donor.publicKey, sessionKey.publicKey),
+          stream: sessionStreamPda,
+          mint: sessionMint,
+          donorAccount: donorAccountPda(),
+          ownerToken: donorSessionAta,
+          streamAta: sessionStreamAta,
           systemProgram: SystemProgram.programId,
-          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
-        .signers([donor])
+        .signers([sessionKey])
         .rpc();
+    }
+
+    before(async () => {
+      sessionMint = await createMint(connection, payer.payer, payer.publicKey, null, 6);
+      sessionStreamPda = await nextStreamPda(host.publicKey);
+      sessionStreamAta = await getAssociatedTokenAddress(sessionMint, sessionStreamPda, true);
+      donorSessionAta = await createDonorTokenAccount(sessionMint, donor.publicKey);
+      await mintTokens(sessionMint, donorSessionAta, 2 * limit);
+      await airdrop(sessionKey.publicKey, anchor.web3.LAMPORTS_PER_SOL);
 
-      // End the stream
       await program.methods
-        .completeStream()
+        .initialize("session_stream", { live: {} }, null, null)
         .accounts({
           host: host.publicKey,
-          stream: endedStreamPda,
+          stream: sessionStreamPda,
+          mint: sessionMint,
+          streamAta: sessionStreamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([host])
         .rpc();
+      await program.methods
+        .startStream()
+        .accounts({ host: host.publicKey, stream: sessionStreamPda })
+        .signers([host])
+        .rpc();
+    });
 
-      // Try to refund
+    it("should bound how long a session lasts", async () => {
       try {
         await program.methods
-          .refund(new anchor.BN(1000))
+          .createSession(sessionKey.publicKey, new anchor.BN(limit), new anchor.BN(8 * 24 * 60 * 60))
           .accounts({
-            donor: donor.publicKey,
-            initiator: host.publicKey,
-            stream: endedStreamPda,
-            donorAccount: endedDonorAccount,
-            donorAta: donorAta,
-            streamAta: endedStreamAta,
-            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            owner: donor.publicKey,
+            mint: sessionMint,
+            ownerToken: donorSessionAta,
             tokenProgram: TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
           })
-          .signers([host])
+          .signers([donor])
           .rpc();
-        assert.fail("Should have rejected refund from ended stream");
-      } catch (err) {
-        assert.include(
-          err.toString(),
-          "StreamAlreadyEnded",
-          "Expected StreamAlreadyEnded error"
-        );
+        assert.fail("Sessions are capped at a week");
+      } catch (error) {
+        assert.include(error.message, "InvalidDuration");
       }
     });
 
-    it("should fail to refund when non-authorized initiator tries", async () => {
-      // Create a new stream and donor account
-      const newStreamName = "auth_refund_test";
-      const [newStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(newStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
-      const newStreamAta = await getAssociatedTokenAddress(
-        mint,
-        newStreamPda,
-        true
-      );
-
-      // Initialize the stream
+    it("should let the session key deposit for its owner", async () => {
       await program.methods
-        .initialize(newStreamName, { live: {} }, null)
+        .createSession(sessionKey.publicKey, new anchor.BN(limit), new anchor.BN(3600))
         .accounts({
-          host: host.publicKey,
-          stream: newStreamPda,
-          mint: mint,
-          streamAta: newStreamAta,
-          systemProgram: SystemProgram.programId,
-          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          owner: donor.publicKey,
+          mint: sessionMint,
+          ownerToken: donorSessionAta,
           tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
-        .signers([host])
+        .signers([donor])
         .rpc();
 
-      // Start the stream
-      await program.methods
-        .startStream()
-        .accounts({
-          host: host.publicKey,
-          stream: newStreamPda,
-        })
-        .signers([host])
-        .rpc();
+      await depositWithSession(300_000);
 
-      // Find donor account PDA
-      const [newDonorAccount] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("donor"),
-          newStreamPda.toBuffer(),
-          donor.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      const donorAccount = await program.account.donorAccount.fetch(donorAccountPda());
+      const session = await program.account.sessionKey.fetch(sessionPda(donor.publicKey, sessionKey.publicKey));
+      const ownerBalance = await connection.getTokenAccountBalance(donorSessionAta);
+      assert.equal(donorAccount.donor.toBase58(), donor.publicKey.toBase58());
+      assert.equal(donorAccount.amount.toNumber(), 300_000);
+      assert.equal(session.spent.toNumber(), 300_000);
+      assert.equal(ownerBalance.value.amount, String(2 * limit - 300_000));
+    });
 
-      // Deposit to the stream
+    it("should stop at the session's spend limit", async () => {
+      try {
+        await depositWithSession(limit - 300_000 + 1);
+        assert.fail("The session would overspend");
+      } catch (error) {
+        assert.include(error.message, "SessionSpendLimitExceeded");
+      }
+    });
+
+    it("should end the session and its delegation on revoke", async () => {
       await program.methods
-        .deposit(new anchor.BN(depositAmount))
+        .revokeSession()
         .accounts({
-          donor: donor.publicKey,
-          stream: newStreamPda,
-          donorAccount: newDonorAccount,
-          donorAta: donorAta,
-          streamAta: newStreamAta,
-          systemProgram: SystemProgram.programId,
-          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          owner: donor.publicKey,
+          session: sessionPda(donor.publicKey, sessionKey.publicKey),
+          ownerToken: donorSessionAta,
           tokenProgram: TOKEN_PROGRAM_ID,
         })
         .signers([donor])
         .rpc();
 
-      // Create unauthorized user
-      const unauthorizedUser = Keypair.generate();
-      await airdrop(unauthorizedUser.publicKey, anchor.web3.LAMPORTS_PER_SOL);
-
-      // Try to refund with unauthorized user
-      try {
-        await program.methods
-          .refund(new anchor.BN(1000))
-          .accounts({
-            donor: donor.publicKey,
-            initiator: unauthorizedUser.publicKey,
-            stream: newStreamPda,
-            donorAccount: newDonorAccount,
-            donorAta: donorAta,
-            streamAta: newStreamAta,
-            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-            tokenProgram: TOKEN_PROGRAM_ID,
-          })
-          .signers([unauthorizedUser])
-          .rpc();
-        assert.fail("Should have rejected refund from unauthorized initiator");
-      } catch (err) {
-        // This error comes from the account constraint check
-        assert(
-          err.toString().includes("Error"),
-          "Expected error for unauthorized initiator"
-        );
-      }
+      const ownerToken = await getAccount(connection, donorSessionAta);
+      assert.isNull(ownerToken.delegate);
+      assert.isNull(
+        await program.account.sessionKey.fetchNullable(sessionPda(donor.publicKey, sessionKey.publicKey))
+      );
     });
   });
+
   async function airdrop(address: PublicKey, amount: number) {
     const sig = await connection.requestAirdrop(address, amount);
     await connection.confirmTransaction(sig);
@@ -1041,6 +5569,40 @@ describe("vidbloq-program", () => {
     );
   }
 
+  // Streams are seeded by the host's creation count, not their name
+  function streamPdaFor(hostKey: PublicKey, streamId: anchor.BN): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("stream"), hostKey.toBuffer(), streamId.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+  }
+
+  // PDA of the stream the host's next initialize will create
+  async function nextStreamPda(hostKey: PublicKey): Promise<PublicKey> {
+    const [hostProfilePda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("host_profile"), hostKey.toBuffer()],
+      program.programId
+    );
+    const profile = await program.account.hostProfile.fetchNullable(hostProfilePda);
+    return streamPdaFor(hostKey, profile ? profile.streamsCreated : new anchor.BN(0));
+  }
+
+  // Finds an existing stream by display name through the host's stream index
+  async function streamPdaByName(hostKey: PublicKey, name: string): Promise<PublicKey> {
+    const [indexPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("host_stream_index"), hostKey.toBuffer()],
+      program.programId
+    );
+    const index = await program.account.hostStreamIndex.fetch(indexPda);
+    for (const stream of index.streams) {
+      const state = await program.account.streamState.fetch(stream);
+      if (state.streamName === name) {
+        return stream;
+      }
+    }
+    throw new Error(`Host has no stream named ${name}`);
+  }
+
   // Helper function to compare BN objects
   function assertBNEqual(
     actual: anchor.BN,
@@ -1061,6 +5623,7 @@ describe("vidbloq-program", () => {
       .initialize(
         streamName,
         { prepaid: { minDuration: new anchor.BN(minDuration) } },
+        null,
         null
       )
       .accounts({
@@ -1087,14 +5650,7 @@ describe("vidbloq-program", () => {
 
   it("should initialize a conditional stream", async () => {
     const conditionalStreamName = "conditional_str";
-    const [conditionalStreamPda] = await PublicKey.findProgramAddress(
-      [
-        Buffer.from("stream"),
-        Buffer.from(conditionalStreamName),
-        host.publicKey.toBuffer(),
-      ],
-      program.programId
-    );
+    const conditionalStreamPda = await nextStreamPda(host.publicKey);
 
     const conditionalStreamAta = await getAssociatedTokenAddress(
       mint,
@@ -1111,6 +5667,7 @@ describe("vidbloq-program", () => {
             unlockTime: new anchor.BN(unlockTime),
           },
         },
+        null,
         null
       )
       .accounts({
@@ -1144,14 +5701,7 @@ describe("vidbloq-program", () => {
 
   it("should initialize a live stream", async () => {
     const liveStreamName = "live_stream_123";
-    const [liveStreamPda] = await PublicKey.findProgramAddress(
-      [
-        Buffer.from("stream"),
-        Buffer.from(liveStreamName),
-        host.publicKey.toBuffer(),
-      ],
-      program.programId
-    );
+    const liveStreamPda = await nextStreamPda(host.publicKey);
 
     const liveStreamAta = await getAssociatedTokenAddress(
       mint,
@@ -1160,7 +5710,7 @@ describe("vidbloq-program", () => {
     );
 
     const tx = await program.methods
-      .initialize(liveStreamName, { live: {} }, null)
+      .initialize(liveStreamName, { live: {} }, null, null)
       .accounts({
         host: host.publicKey,
         stream: liveStreamPda,
@@ -1185,17 +5735,10 @@ describe("vidbloq-program", () => {
     const tooShortName = "abc";
 
     try {
-      const [pda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(tooShortName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      const pda = await nextStreamPda(host.publicKey);
 
       await program.methods
-        .initialize(tooShortName, { live: {} }, null)
+        .initialize(tooShortName, { live: {} }, null, null)
         .accounts({
           host: host.publicKey,
           stream: pda,
@@ -1219,22 +5762,15 @@ describe("vidbloq-program", () => {
 
     // Test 2: Name too long (33 characters)
     const tooLongName = "a".repeat(33);
-    const validPdaName = tooLongName.substring(0, 32); // Truncate for PDA
 
     try {
-      const [pda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(validPdaName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      const pda = await nextStreamPda(host.publicKey);
 
       await program.methods
         .initialize(
           tooLongName, // Pass full name to program
           { live: {} },
+          null,
           null
         )
         .accounts({
@@ -1263,7 +5799,7 @@ describe("vidbloq-program", () => {
   describe("deposit instruction", () => {
     it("should allow deposit to prepaid stream before starting", async () => {
       const tx = await program.methods
-        .deposit(new anchor.BN(depositAmount))
+        .deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: donor.publicKey,
           stream: streamPda,
@@ -1291,6 +5827,11 @@ describe("vidbloq-program", () => {
         false,
         "Donor should not be refunded initially"
       );
+      assert.equal(
+        donorAccountInfo.depositCount.toNumber(),
+        1,
+        "First deposit should be counted"
+      );
 
       // Verify stream total deposited was updated
       const streamAccount = await program.account.streamState.fetch(streamPda);
@@ -1301,10 +5842,95 @@ describe("vidbloq-program", () => {
       );
     });
 
+    it("should accumulate repeat deposits without double counting", async () => {
+      const donorBefore = await program.account.donorAccount.fetch(donorAccount);
+      const streamBefore = await program.account.streamState.fetch(streamPda);
+
+      for (let i = 0; i < 2; i++) {
+        await program.methods
+          .deposit(new anchor.BN(depositAmount), null)
+          .accounts({
+            donor: donor.publicKey,
+            stream: streamPda,
+            donorAccount: donorAccount,
+            donorAta: donorAta,
+            streamAta: streamAta,
+            systemProgram: SystemProgram.programId,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([donor])
+          .rpc();
+      }
+
+      const donorAfter = await program.account.donorAccount.fetch(donorAccount);
+      const streamAfter = await program.account.streamState.fetch(streamPda);
+
+      assert.equal(
+        donorAfter.amount.toNumber() - donorBefore.amount.toNumber(),
+        depositAmount * 2,
+        "Donor total should grow by exactly the deposited amount"
+      );
+      assert.equal(
+        streamAfter.totalDeposited.toNumber() - streamBefore.totalDeposited.toNumber(),
+        depositAmount * 2,
+        "Stream total should grow by exactly the deposited amount"
+      );
+      assert.equal(
+        donorAfter.depositCount.toNumber(),
+        donorBefore.depositCount.toNumber() + 2,
+        "Each deposit should be counted"
+      );
+      assert.isAtLeast(
+        donorAfter.lastDepositAt.toNumber(),
+        donorBefore.lastDepositAt.toNumber(),
+        "Last deposit time should move forward"
+      );
+    });
+
+    it("should record the donation message in a receipt", async () => {
+      const message = "Great stream, keep it up!";
+      const streamBefore = await program.account.streamState.fetch(streamPda);
+      const [receiptPda] = PublicKey.findProgramAddressSync(
+        [
+          Buffer.from("receipt"),
+          streamPda.toBuffer(),
+          streamBefore.receiptCount.toArrayLike(Buffer, "le", 8),
+        ],
+        program.programId
+      );
+
+      await program.methods
+        .deposit(new anchor.BN(depositAmount), message)
+        .accounts({
+          donor: donor.publicKey,
+          stream: streamPda,
+          donorAccount: donorAccount,
+          receipt: receiptPda,
+          donorAta: donorAta,
+          streamAta: streamAta,
+          systemProgram: SystemProgram.programId,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([donor])
+        .rpc();
+
+      const receipt = await program.account.donationReceipt.fetch(receiptPda);
+      assert.equal(receipt.message, message, "Receipt message mismatch");
+      assert.equal(receipt.amount.toNumber(), depositAmount);
+      assert.equal(receipt.donor.toString(), donor.publicKey.toString());
+      assert.equal(
+        receipt.index.toNumber(),
+        streamBefore.receiptCount.toNumber(),
+        "Receipt index should follow the stream counter"
+      );
+    });
+
     it("should fail to deposit 0 amount", async () => {
       try {
         await program.methods
-          .deposit(new anchor.BN(0))
+          .deposit(new anchor.BN(0), null)
           .accounts({
             donor: donor.publicKey,
             stream: streamPda,
@@ -1415,14 +6041,7 @@ describe("vidbloq-program", () => {
     it("should reject when non-host tries to complete stream", async () => {
       // Create a new stream for this test
       const newStreamName = "complete_test_stream";
-      const [newStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(newStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      const newStreamPda = await nextStreamPda(host.publicKey);
       const newStreamAta = await getAssociatedTokenAddress(
         mint,
         newStreamPda,
@@ -1431,7 +6050,7 @@ describe("vidbloq-program", () => {
 
       // Initialize the new stream
       await program.methods
-        .initialize(newStreamName, { live: {} }, null)
+        .initialize(newStreamName, { live: {} }, null, null)
         .accounts({
           host: host.publicKey,
           stream: newStreamPda,
@@ -1476,14 +6095,7 @@ describe("vidbloq-program", () => {
     it("should reject completing a stream that hasn't started", async () => {
       // Create a new stream for this test
       const newStreamName = "not_started_stream";
-      const [newStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(newStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      const newStreamPda = await nextStreamPda(host.publicKey);
       const newStreamAta = await getAssociatedTokenAddress(
         mint,
         newStreamPda,
@@ -1492,7 +6104,7 @@ describe("vidbloq-program", () => {
 
       // Initialize the new stream but don't start it
       await program.methods
-        .initialize(newStreamName, { live: {} }, null)
+        .initialize(newStreamName, { live: {} }, null, null)
         .accounts({
           host: host.publicKey,
           stream: newStreamPda,
@@ -1534,14 +6146,7 @@ describe("vidbloq-program", () => {
     before(async () => {
       updateStreamName = "update_test_stream";
 
-      [updateStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(updateStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      updateStreamPda = await nextStreamPda(host.publicKey);
 
       updateStreamAta = await getAssociatedTokenAddress(
         mint,
@@ -1551,7 +6156,7 @@ describe("vidbloq-program", () => {
 
       // Initialize the stream for update tests
       await program.methods
-        .initialize(updateStreamName, { live: {} }, null)
+        .initialize(updateStreamName, { live: {} }, null, null)
         .accounts({
           host: host.publicKey,
           stream: updateStreamPda,
@@ -1635,14 +6240,7 @@ describe("vidbloq-program", () => {
     // Create and test a live stream
     it("should allow deposit to a live stream after it's started", async () => {
       const liveStreamName = "live_deposit_test";
-      const [liveStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(liveStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      const liveStreamPda = await nextStreamPda(host.publicKey);
       const liveStreamAta = await getAssociatedTokenAddress(
         mint,
         liveStreamPda,
@@ -1659,7 +6257,7 @@ describe("vidbloq-program", () => {
 
       // Initialize live stream
       await program.methods
-        .initialize(liveStreamName, { live: {} }, null)
+        .initialize(liveStreamName, { live: {} }, null, null)
         .accounts({
           host: host.publicKey,
           stream: liveStreamPda,
@@ -1675,7 +6273,7 @@ describe("vidbloq-program", () => {
       // Try deposit before starting - should fail
       try {
         await program.methods
-          .deposit(new anchor.BN(depositAmount))
+          .deposit(new anchor.BN(depositAmount), null)
           .accounts({
             donor: donor.publicKey,
             stream: liveStreamPda,
@@ -1711,7 +6309,7 @@ describe("vidbloq-program", () => {
 
       // Now deposit should succeed
       await program.methods
-        .deposit(new anchor.BN(depositAmount))
+        .deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: donor.publicKey,
           stream: liveStreamPda,
@@ -1739,14 +6337,7 @@ describe("vidbloq-program", () => {
     // Create and test a conditional stream
     it("should allow deposit to an active conditional stream", async () => {
       const condStreamName = "cond_deposit_test";
-      const [condStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(condStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      const condStreamPda = await nextStreamPda(host.publicKey);
       const condStreamAta = await getAssociatedTokenAddress(
         mint,
         condStreamPda,
@@ -1771,6 +6362,7 @@ describe("vidbloq-program", () => {
               unlockTime: new anchor.BN(unlockTime),
             },
           },
+          null,
           null
         )
         .accounts({
@@ -1787,7 +6379,7 @@ describe("vidbloq-program", () => {
 
       // Deposit to conditional stream
       await program.methods
-        .deposit(new anchor.BN(depositAmount))
+        .deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: donor.publicKey,
           stream: condStreamPda,
@@ -1814,14 +6406,7 @@ describe("vidbloq-program", () => {
 
     it("should reject deposit to a non-active conditional stream", async () => {
       const condStreamName = "inactive_cond_test";
-      const [condStreamPda] = await PublicKey.findProgramAddress(
-        [
-          Buffer.from("stream"),
-          Buffer.from(condStreamName),
-          host.publicKey.toBuffer(),
-        ],
-        program.programId
-      );
+      const condStreamPda = await nextStreamPda(host.publicKey);
       const condStreamAta = await getAssociatedTokenAddress(
         mint,
         condStreamPda,
@@ -1846,6 +6431,7 @@ describe("vidbloq-program", () => {
               unlockTime: new anchor.BN(unlockTime),
             },
           },
+          null,
           null
         )
         .accounts({
@@ -1873,7 +6459,7 @@ describe("vidbloq-program", () => {
       // Try deposit to ended stream - should fail
       try {
         await program.methods
-          .deposit(new anchor.BN(depositAmount))
+          .deposit(new anchor.BN(depositAmount), null)
           .accounts({
             donor: donor.publicKey,
             stream: condStreamPda,
@@ -1928,10 +6514,7 @@ describe("vidbloq-program", () => {
   
     it("should handle conditional stream with only minimum amount", async () => {
       const streamName = "cond_min_amount_only";
-      const [streamPda] = await PublicKey.findProgramAddress(
-        [Buffer.from("stream"), Buffer.from(streamName), edgeCaseHost.publicKey.toBuffer()],
-        program.programId
-      );
+      const streamPda = await nextStreamPda(edgeCaseHost.publicKey);
       const streamAta = await getAssociatedTokenAddress(edgeCaseMint, streamPda, true);
       
       // Initialize stream with only minimum amount (no unlock time)
@@ -1943,6 +6526,7 @@ describe("vidbloq-program", () => {
             unlockTime: null // No unlock time
           } 
         },
+        null,
         null
       )
       .accounts({
@@ -1964,7 +6548,7 @@ describe("vidbloq-program", () => {
       );
       
       // Deposit to stream
-      await program.methods.deposit(new anchor.BN(depositAmount))
+      await program.methods.deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: edgeCaseDonor.publicKey,
           stream: streamPda,
@@ -1990,10 +6574,7 @@ describe("vidbloq-program", () => {
   
     it("should handle conditional stream with only unlock time", async () => {
       const streamName = "cond_time_only";
-      const [streamPda] = await PublicKey.findProgramAddress(
-        [Buffer.from("stream"), Buffer.from(streamName), edgeCaseHost.publicKey.toBuffer()],
-        program.programId
-      );
+      const streamPda = await nextStreamPda(edgeCaseHost.publicKey);
       const streamAta = await getAssociatedTokenAddress(edgeCaseMint, streamPda, true);
       
       const unlockTime = new anchor.BN(Math.floor(Date.now() / 1000) + 2); // 2 seconds from now
@@ -2007,6 +6588,7 @@ describe("vidbloq-program", () => {
             unlockTime: unlockTime // Only unlock time
           } 
         },
+        null,
         null
       )
       .accounts({
@@ -2028,7 +6610,7 @@ describe("vidbloq-program", () => {
       );
       
       // Deposit to stream
-      await program.methods.deposit(new anchor.BN(depositAmount))
+      await program.methods.deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: edgeCaseDonor.publicKey,
           stream: streamPda,
@@ -2082,16 +6664,14 @@ describe("vidbloq-program", () => {
   
     it("should handle very large token amounts", async () => {
       const streamName = "large_amount_stream";
-      const [streamPda] = await PublicKey.findProgramAddress(
-        [Buffer.from("stream"), Buffer.from(streamName), edgeCaseHost.publicKey.toBuffer()],
-        program.programId
-      );
+      const streamPda = await nextStreamPda(edgeCaseHost.publicKey);
       const streamAta = await getAssociatedTokenAddress(edgeCaseMint, streamPda, true);
       
       // Initialize live stream
       await program.methods.initialize(
         streamName,
         { live: {} },
+        null,
         null
       )
       .accounts({
@@ -2126,7 +6706,7 @@ describe("vidbloq-program", () => {
       await mintTokens(edgeCaseMint, donorAta, largeAmount);
       
       // Deposit large amount to stream
-      await program.methods.deposit(new anchor.BN(largeAmount))
+      await program.methods.deposit(new anchor.BN(largeAmount), null)
         .accounts({
           donor: edgeCaseDonor.publicKey,
           stream: streamPda,
@@ -2188,10 +6768,7 @@ describe("vidbloq-program", () => {
   
       // Create stream PDA
       const multiStreamName = "multi_donor_stream";
-      [multiStreamPda] = await PublicKey.findProgramAddress(
-        [Buffer.from("stream"), Buffer.from(multiStreamName), multiStreamHost.publicKey.toBuffer()],
-        program.programId
-      );
+      multiStreamPda = await nextStreamPda(multiStreamHost.publicKey);
   
       // Get associated token account for stream
       multiStreamAta = await getAssociatedTokenAddress(multiStreamMint, multiStreamPda, true);
@@ -2229,6 +6806,7 @@ describe("vidbloq-program", () => {
       await program.methods.initialize(
         multiStreamName,
         { live: {} }, // Using live stream for simplicity
+        null,
         null
       )
       .accounts({
@@ -2255,7 +6833,7 @@ describe("vidbloq-program", () => {
   
     it("should allow multiple donors to contribute to the same stream", async () => {
       // Donor 1 deposits
-      await program.methods.deposit(new anchor.BN(donor1Amount))
+      await program.methods.deposit(new anchor.BN(donor1Amount), null)
         .accounts({
           donor: donor1.publicKey,
           stream: multiStreamPda,
@@ -2270,7 +6848,7 @@ describe("vidbloq-program", () => {
         .rpc();
       
       // Donor 2 deposits
-      await program.methods.deposit(new anchor.BN(donor2Amount))
+      await program.methods.deposit(new anchor.BN(donor2Amount), null)
         .accounts({
           donor: donor2.publicKey,
           stream: multiStreamPda,
@@ -2285,7 +6863,7 @@ describe("vidbloq-program", () => {
         .rpc();
       
       // Donor 3 deposits
-      await program.methods.deposit(new anchor.BN(donor3Amount))
+      await program.methods.deposit(new anchor.BN(donor3Amount), null)
         .accounts({
           donor: donor3.publicKey,
           stream: multiStreamPda,
@@ -2471,15 +7049,13 @@ describe("vidbloq-program", () => {
     it("should execute full stream lifecycle: initialize → deposit → start → distribute → refund → complete", async () => {
       // 1. Initialize
       const streamName = "full_lifecycle_stream";
-      const [streamPda] = await PublicKey.findProgramAddress(
-        [Buffer.from("stream"), Buffer.from(streamName), lifecycleHost.publicKey.toBuffer()],
-        program.programId
-      );
+      const streamPda = await nextStreamPda(lifecycleHost.publicKey);
       const streamAta = await getAssociatedTokenAddress(lifecycleMint, streamPda, true);
       
       await program.methods.initialize(
         streamName,
         { prepaid: { minDuration: new anchor.BN(3) } }, // Short duration for testing
+        null,
         null
       )
       .accounts({
@@ -2500,7 +7076,7 @@ describe("vidbloq-program", () => {
         program.programId
       );
       
-      await program.methods.deposit(new anchor.BN(depositAmount))
+      await program.methods.deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: lifecycleDonor.publicKey,
           stream: streamPda,
@@ -2603,15 +7179,13 @@ describe("vidbloq-program", () => {
     it("should cancel a stream before it starts", async () => {
       // Initialize stream
       const streamName = "cancel_before_start";
-      const [streamPda] = await PublicKey.findProgramAddress(
-        [Buffer.from("stream"), Buffer.from(streamName), lifecycleHost.publicKey.toBuffer()],
-        program.programId
-      );
+      const streamPda = await nextStreamPda(lifecycleHost.publicKey);
       const streamAta = await getAssociatedTokenAddress(lifecycleMint, streamPda, true);
       
       await program.methods.initialize(
         streamName,
         { prepaid: { minDuration: new anchor.BN(60) } },
+        null,
         null
       )
       .accounts({
@@ -2632,7 +7206,7 @@ describe("vidbloq-program", () => {
         program.programId
       );
       
-      await program.methods.deposit(new anchor.BN(depositAmount))
+      await program.methods.deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: lifecycleDonor.publicKey,
           stream: streamPda,
@@ -2680,15 +7254,13 @@ describe("vidbloq-program", () => {
     it("should cancel a stream after it starts but before completion", async () => {
       // Initialize stream
       const streamName = "cancel_after_start";
-      const [streamPda] = await PublicKey.findProgramAddress(
-        [Buffer.from("stream"), Buffer.from(streamName), lifecycleHost.publicKey.toBuffer()],
-        program.programId
-      );
+      const streamPda = await nextStreamPda(lifecycleHost.publicKey);
       const streamAta = await getAssociatedTokenAddress(lifecycleMint, streamPda, true);
       
       await program.methods.initialize(
         streamName,
         { live: {} },
+        null,
         null
       )
       .accounts({
@@ -2722,7 +7294,7 @@ describe("vidbloq-program", () => {
         program.programId
       );
       
-      await program.methods.deposit(new anchor.BN(depositAmount))
+      await program.methods.deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: lifecycleDonor.publicKey,
           stream: streamPda,
@@ -2842,16 +7414,14 @@ describe("vidbloq-program", () => {
       
       // Create a stream
       const streamName = "security_test_stream";
-      [streamPda] = await PublicKey.findProgramAddress(
-        [Buffer.from("stream"), Buffer.from(streamName), securityHost.publicKey.toBuffer()],
-        program.programId
-      );
+      streamPda = await nextStreamPda(securityHost.publicKey);
       streamAta = await getAssociatedTokenAddress(securityMint, streamPda, true);
       
       // Initialize stream
       await program.methods.initialize(
         streamName,
         { live: {} },
+        null,
         null
       )
       .accounts({
@@ -2881,7 +7451,7 @@ describe("vidbloq-program", () => {
         program.programId
       );
       
-      await program.methods.deposit(new anchor.BN(depositAmount))
+      await program.methods.deposit(new anchor.BN(depositAmount), null)
         .accounts({
           donor: victim.publicKey,
           stream: streamPda,
@@ -2900,10 +7470,7 @@ describe("vidbloq-program", () => {
       // Attacker tries to create their own stream with same name but fails
       // because PDA includes host key
       const attackerStreamName = "security_test_stream"; // Same name
-      const [attackerStreamPda] = await PublicKey.findProgramAddress(
-        [Buffer.from("stream"), Buffer.from(attackerStreamName), attacker.publicKey.toBuffer()],
-        program.programId
-      );
+      const attackerStreamPda = await nextStreamPda(attacker.publicKey);
       
       // Verify PDAs are different
       assert.notEqual(
@@ -2918,6 +7485,7 @@ describe("vidbloq-program", () => {
       await program.methods.initialize(
         attackerStreamName,
         { live: {} },
+        null,
         null
       )
       .accounts({
@@ -3033,7 +7601,7 @@ describe("vidbloq-program", () => {
           program.programId
         );
         
-        await program.methods.deposit(new anchor.BN(depositAmount))
+        await program.methods.deposit(new anchor.BN(depositAmount), null)
           .accounts({
             donor: attacker.publicKey,
             stream: streamPda,